@@ -0,0 +1,172 @@
+#ifndef PDF2MD_H
+#define PDF2MD_H
+
+/* Generated by cbindgen from crates/pdf2md-ffi/src/lib.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Kind of progress event delivered to a [`Pdf2MdProgressFn`].
+ */
+typedef enum {
+  /**
+   * `total` pages are about to be converted; `page` is 0.
+   */
+  Pdf2MdEvent_CONVERSION_START = 0,
+  /**
+   * Page `page` (1-based) is being sent to the model.
+   */
+  Pdf2MdEvent_PAGE_START = 1,
+  /**
+   * Page `page` converted; `detail` is `NULL`.
+   */
+  Pdf2MdEvent_PAGE_COMPLETE = 2,
+  /**
+   * Page `page` failed; `detail` holds the error message.
+   */
+  Pdf2MdEvent_PAGE_ERROR = 3,
+  /**
+   * All pages were attempted; `page` is the number that succeeded.
+   */
+  Pdf2MdEvent_CONVERSION_COMPLETE = 4,
+} Pdf2MdEvent;
+
+/**
+ * Result code of the `pdf2md_convert_*` calls.
+ */
+typedef enum {
+  /**
+   * Success.
+   */
+  Pdf2MdStatus_OK = 0,
+  /**
+   * A required pointer was `NULL`, a string was not UTF-8, or the options
+   * JSON did not parse.
+   */
+  Pdf2MdStatus_INVALID_ARGUMENT = 1,
+  /**
+   * The input file or URL could not be found, read, or downloaded.
+   */
+  Pdf2MdStatus_NOT_FOUND = 2,
+  /**
+   * The input is not a PDF, is corrupt, exceeds a size limit, or a page is
+   * out of range.
+   */
+  Pdf2MdStatus_INVALID_PDF = 3,
+  /**
+   * The PDF is encrypted and no (or a wrong) password was given.
+   */
+  Pdf2MdStatus_PASSWORD_REQUIRED = 4,
+  /**
+   * No LLM provider could be configured (missing API key or name).
+   */
+  Pdf2MdStatus_PROVIDER_NOT_CONFIGURED = 5,
+  /**
+   * The provider rejected the credentials.
+   */
+  Pdf2MdStatus_AUTH_FAILED = 6,
+  /**
+   * The provider failed: rate limits, timeouts, outages, API errors.
+   */
+  Pdf2MdStatus_PROVIDER_FAILED = 7,
+  /**
+   * Every selected page failed.
+   */
+  Pdf2MdStatus_ALL_PAGES_FAILED = 8,
+  /**
+   * The options were well-formed JSON but an invalid configuration.
+   */
+  Pdf2MdStatus_INVALID_CONFIG = 9,
+  /**
+   * Writing output failed.
+   */
+  Pdf2MdStatus_IO = 10,
+  /**
+   * A panic was caught at the FFI boundary.
+   */
+  Pdf2MdStatus_PANIC = 98,
+  /**
+   * Any other internal error.
+   */
+  Pdf2MdStatus_INTERNAL = 99,
+} Pdf2MdStatus;
+
+/**
+ * Progress callback.
+ *
+ * `detail` is `NULL` except for `Pdf2MdEvent_PAGE_ERROR` and is only valid
+ * for the duration of the call. Pages may be reported concurrently from
+ * several threads, so the callback must be thread-safe.
+ */
+typedef void (*Pdf2MdProgressFn)(void *user_data,
+                                 Pdf2MdEvent event,
+                                 size_t page,
+                                 size_t total,
+                                 const char *detail);
+
+/**
+ * Convert a PDF file path or HTTP(S) URL to Markdown.
+ *
+ * On success writes the assembled Markdown to `*out_markdown` and, if
+ * `out_json` is non-null, the full result (pages, metadata, stats) as JSON
+ * to `*out_json`. Either out-pointer may be `NULL`, but not both. Free
+ * returned strings with [`pdf2md_string_free`].
+ *
+ * # Safety
+ * `input` must be a valid NUL-terminated string; `options_json` must be
+ * `NULL` or a valid NUL-terminated string; `out_markdown` / `out_json` must
+ * be `NULL` or valid for a pointer write; `progress`, if set, must be safe
+ * to call with `user_data` from any thread until this function returns.
+ */
+Pdf2MdStatus pdf2md_convert_file(const char *input,
+                                 const char *options_json,
+                                 Pdf2MdProgressFn progress,
+                                 void *user_data,
+                                 char **out_markdown,
+                                 char **out_json);
+
+/**
+ * Convert an in-memory PDF to Markdown.
+ *
+ * Same outputs as [`pdf2md_convert_file`].
+ *
+ * # Safety
+ * `data` must point to `len` readable bytes (it may be `NULL` only when
+ * `len` is 0, which is then rejected); the other arguments follow
+ * [`pdf2md_convert_file`].
+ */
+Pdf2MdStatus pdf2md_convert_bytes(const uint8_t *data,
+                                  size_t len,
+                                  const char *options_json,
+                                  Pdf2MdProgressFn progress,
+                                  void *user_data,
+                                  char **out_markdown,
+                                  char **out_json);
+
+/**
+ * Message describing the last failure on this thread, or `NULL`.
+ *
+ * The pointer stays valid until the next `pdf2md_*` call on the same
+ * thread; do not free it.
+ */
+const char *pdf2md_last_error(void);
+
+/**
+ * Free a string returned by this library. `NULL` is ignored.
+ *
+ * # Safety
+ * `s` must be `NULL` or a pointer returned through an out-parameter of this
+ * library that has not been freed yet.
+ */
+void pdf2md_string_free(char *s);
+
+/**
+ * Library version as a static NUL-terminated string (e.g. `"0.1.0"`).
+ */
+const char *pdf2md_version(void);
+
+#endif /* PDF2MD_H */
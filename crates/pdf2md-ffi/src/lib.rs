@@ -0,0 +1,600 @@
+//! Stable C ABI for `edgequake-pdf2md`.
+//!
+//! ## Why a C ABI?
+//!
+//! Python, Node, and Go applications that want the conversion pipeline
+//! otherwise shell out to the `pdf2md` binary and scrape its output. A shared
+//! library (`libpdf2md.so` / `.dylib` / `pdf2md.dll`) lets them call the
+//! pipeline in-process through `ctypes`, `ffi-napi`, or `cgo`, with progress
+//! events and structured results.
+//!
+//! ## ABI rules
+//!
+//! - Conversion functions return a [`Pdf2MdStatus`]; `Pdf2MdStatus_OK` is `0`.
+//! - Options are passed as a JSON object (see [`FfiOptions`]) so new knobs
+//!   never change a function signature. `NULL` means "all defaults".
+//! - Strings returned through out-pointers are NUL-terminated UTF-8 owned by
+//!   the library; release them with [`pdf2md_string_free`].
+//! - After a failure, [`pdf2md_last_error`] describes it. The message is
+//!   per-thread and valid until the next call on the same thread.
+//! - Calls block the calling thread. They run on a shared multi-threaded
+//!   Tokio runtime, so they are safe to make from several threads at once.
+//! - Panics never cross the boundary; they surface as `Pdf2MdStatus_PANIC`.
+//!
+//! The C header is `include/pdf2md.h`, regenerated with
+//! `cbindgen --config cbindgen.toml --output include/pdf2md.h`.
+
+use edgequake_pdf2md::{
+    ConversionConfig, ConversionOutput, ConversionProgressCallback, FidelityTier, PageSelection,
+    Pdf2MdError,
+};
+use once_cell::sync::Lazy;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+/// Result code of the `pdf2md_convert_*` calls.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pdf2MdStatus {
+    /// Success.
+    Ok = 0,
+    /// A required pointer was `NULL`, a string was not UTF-8, or the options
+    /// JSON did not parse.
+    InvalidArgument = 1,
+    /// The input file or URL could not be found, read, or downloaded.
+    NotFound = 2,
+    /// The input is not a PDF, is corrupt, exceeds a size limit, or a page is
+    /// out of range.
+    InvalidPdf = 3,
+    /// The PDF is encrypted and no (or a wrong) password was given.
+    PasswordRequired = 4,
+    /// No LLM provider could be configured (missing API key or name).
+    ProviderNotConfigured = 5,
+    /// The provider rejected the credentials.
+    AuthFailed = 6,
+    /// The provider failed: rate limits, timeouts, outages, API errors.
+    ProviderFailed = 7,
+    /// Every selected page failed.
+    AllPagesFailed = 8,
+    /// The options were well-formed JSON but an invalid configuration.
+    InvalidConfig = 9,
+    /// Writing output failed.
+    Io = 10,
+    /// A panic was caught at the FFI boundary.
+    Panic = 98,
+    /// Any other internal error.
+    Internal = 99,
+}
+
+impl From<&Pdf2MdError> for Pdf2MdStatus {
+    fn from(e: &Pdf2MdError) -> Self {
+        match e {
+            Pdf2MdError::FileNotFound { .. }
+            | Pdf2MdError::PermissionDenied { .. }
+            | Pdf2MdError::InvalidInput { .. }
+            | Pdf2MdError::DownloadFailed { .. }
+            | Pdf2MdError::DownloadTimeout { .. } => Pdf2MdStatus::NotFound,
+            Pdf2MdError::NotAPdf { .. }
+            | Pdf2MdError::CorruptPdf { .. }
+            | Pdf2MdError::PageOutOfRange { .. }
+            | Pdf2MdError::DocumentTooLarge { .. } => Pdf2MdStatus::InvalidPdf,
+            Pdf2MdError::PasswordRequired { .. } | Pdf2MdError::WrongPassword { .. } => {
+                Pdf2MdStatus::PasswordRequired
+            }
+            Pdf2MdError::ProviderNotConfigured { .. } => Pdf2MdStatus::ProviderNotConfigured,
+            Pdf2MdError::AuthError { .. } => Pdf2MdStatus::AuthFailed,
+            Pdf2MdError::LlmApiError { .. }
+            | Pdf2MdError::RateLimitExceeded { .. }
+            | Pdf2MdError::ApiTimeout { .. }
+            | Pdf2MdError::ProviderUnavailable { .. }
+            | Pdf2MdError::ProviderLost { .. } => Pdf2MdStatus::ProviderFailed,
+            Pdf2MdError::AllPagesFailed { .. }
+            | Pdf2MdError::PartialFailure { .. }
+            | Pdf2MdError::PageFailed(_) => Pdf2MdStatus::AllPagesFailed,
+            Pdf2MdError::InvalidConfig(_) | Pdf2MdError::LocalOnlyViolation { .. } => {
+                Pdf2MdStatus::InvalidConfig
+            }
+            Pdf2MdError::OutputWriteFailed { .. } => Pdf2MdStatus::Io,
+            _ => Pdf2MdStatus::Internal,
+        }
+    }
+}
+
+/// Kind of progress event delivered to a [`Pdf2MdProgressFn`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pdf2MdEvent {
+    /// `total` pages are about to be converted; `page` is 0.
+    ConversionStart = 0,
+    /// Page `page` (1-based) is being sent to the model.
+    PageStart = 1,
+    /// Page `page` converted; `detail` is `NULL`.
+    PageComplete = 2,
+    /// Page `page` failed; `detail` holds the error message.
+    PageError = 3,
+    /// All pages were attempted; `page` is the number that succeeded.
+    ConversionComplete = 4,
+}
+
+/// Progress callback.
+///
+/// `detail` is `NULL` except for `Pdf2MdEvent_PAGE_ERROR` and is only valid
+/// for the duration of the call. Pages may be reported concurrently from
+/// several threads, so the callback must be thread-safe.
+pub type Pdf2MdProgressFn = Option<
+    unsafe extern "C" fn(
+        user_data: *mut c_void,
+        event: Pdf2MdEvent,
+        page: usize,
+        total: usize,
+        detail: *const c_char,
+    ),
+>;
+
+/// Conversion options accepted as JSON by the `pdf2md_convert_*` functions.
+///
+/// Every field is optional; omitted fields keep the library defaults.
+/// `pages` uses the serde form of [`PageSelection`], e.g. `"All"`,
+/// `{"Single": 3}`, `{"Range": [1, 5]}`, or `{"Set": [1, 4, 9]}`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FfiOptions {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub base_url: Option<String>,
+    /// Explicit API key; otherwise the provider's env var is read.
+    pub api_key: Option<String>,
+    pub dpi: Option<u32>,
+    pub concurrency: Option<usize>,
+    pub pages: Option<PageSelection>,
+    pub password: Option<String>,
+    pub system_prompt: Option<String>,
+    pub fidelity: Option<FidelityTier>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<usize>,
+    pub max_retries: Option<u32>,
+    pub maintain_format: Option<bool>,
+    pub include_metadata: Option<bool>,
+    pub api_timeout_secs: Option<u64>,
+}
+
+impl FfiOptions {
+    /// Parse `json`; an empty string means all defaults.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        serde_json::from_str(json).map_err(|e| format!("invalid options JSON: {e}"))
+    }
+
+    /// Build a [`ConversionConfig`] from these options and an optional callback.
+    pub fn into_config(
+        self,
+        progress: Option<Arc<dyn ConversionProgressCallback>>,
+    ) -> Result<ConversionConfig, Pdf2MdError> {
+        let mut b = ConversionConfig::builder();
+        if let Some(v) = self.provider {
+            b = b.provider_name(v);
+        }
+        if let Some(v) = self.model {
+            b = b.model(v);
+        }
+        if let Some(v) = self.base_url {
+            b = b.base_url(v);
+        }
+        if let Some(v) = self.api_key {
+            b = b.api_key(v);
+        }
+        if let Some(v) = self.dpi {
+            b = b.dpi(v);
+        }
+        if let Some(v) = self.concurrency {
+            b = b.concurrency(v);
+        }
+        if let Some(v) = self.pages {
+            b = b.pages(v);
+        }
+        if let Some(v) = self.password {
+            b = b.password(v);
+        }
+        if let Some(v) = self.system_prompt {
+            b = b.system_prompt(v);
+        }
+        if let Some(v) = self.fidelity {
+            b = b.fidelity(v);
+        }
+        if let Some(v) = self.temperature {
+            b = b.temperature(v);
+        }
+        if let Some(v) = self.max_tokens {
+            b = b.max_tokens(v);
+        }
+        if let Some(v) = self.max_retries {
+            b = b.max_retries(v);
+        }
+        if let Some(v) = self.maintain_format {
+            b = b.maintain_format(v);
+        }
+        if let Some(v) = self.include_metadata {
+            b = b.include_metadata(v);
+        }
+        if let Some(v) = self.api_timeout_secs {
+            b = b.api_timeout_secs(v);
+        }
+        if let Some(cb) = progress {
+            b = b.progress_callback(cb);
+        }
+        b.build()
+    }
+}
+
+// ── Runtime and error state ──────────────────────────────────────────────
+
+static RUNTIME: Lazy<Result<Runtime, String>> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("pdf2md-ffi")
+        .build()
+        .map_err(|e| format!("failed to start tokio runtime: {e}"))
+});
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl Into<String>) {
+    // Interior NULs would truncate the C string; replace them.
+    let message = message.into().replace('\0', " ");
+    let c = CString::new(message).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(c));
+}
+
+fn clear_last_error() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = None);
+}
+
+// ── Progress bridge ──────────────────────────────────────────────────────
+
+/// Forwards pipeline events to a C function pointer.
+struct FfiProgress {
+    callback: unsafe extern "C" fn(*mut c_void, Pdf2MdEvent, usize, usize, *const c_char),
+    user_data: *mut c_void,
+}
+
+// SAFETY: the caller promises (see `Pdf2MdProgressFn`) that the callback and
+// its `user_data` may be used from any thread for the duration of the call.
+unsafe impl Send for FfiProgress {}
+unsafe impl Sync for FfiProgress {}
+
+impl FfiProgress {
+    fn emit(&self, event: Pdf2MdEvent, page: usize, total: usize, detail: Option<&str>) {
+        let detail = detail.map(|d| CString::new(d.replace('\0', " ")).unwrap_or_default());
+        let ptr = detail.as_ref().map_or(std::ptr::null(), |d| d.as_ptr());
+        // SAFETY: function pointer and user_data supplied by the caller;
+        // `ptr` outlives the call.
+        unsafe { (self.callback)(self.user_data, event, page, total, ptr) }
+    }
+}
+
+impl ConversionProgressCallback for FfiProgress {
+    fn on_conversion_start(&self, total_pages: usize) {
+        self.emit(Pdf2MdEvent::ConversionStart, 0, total_pages, None);
+    }
+
+    fn on_page_start(&self, page_num: usize, total_pages: usize) {
+        self.emit(Pdf2MdEvent::PageStart, page_num, total_pages, None);
+    }
+
+    fn on_page_complete(&self, page_num: usize, total_pages: usize, _markdown_len: usize) {
+        self.emit(Pdf2MdEvent::PageComplete, page_num, total_pages, None);
+    }
+
+    fn on_page_error(&self, page_num: usize, total_pages: usize, error: String) {
+        self.emit(Pdf2MdEvent::PageError, page_num, total_pages, Some(&error));
+    }
+
+    fn on_conversion_complete(&self, total_pages: usize, success_count: usize) {
+        self.emit(
+            Pdf2MdEvent::ConversionComplete,
+            success_count,
+            total_pages,
+            None,
+        );
+    }
+}
+
+// ── Shared call path ─────────────────────────────────────────────────────
+
+enum Input<'a> {
+    Path(&'a str),
+    Bytes(&'a [u8]),
+}
+
+/// Borrow a C string as UTF-8; `NULL` becomes `None`.
+///
+/// # Safety
+/// `ptr` must be `NULL` or a valid NUL-terminated string.
+unsafe fn opt_str<'a>(ptr: *const c_char, what: &str) -> Result<Option<&'a str>, Pdf2MdStatus> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr).to_str().map(Some).map_err(|_| {
+        set_last_error(format!("{what} is not valid UTF-8"));
+        Pdf2MdStatus::InvalidArgument
+    })
+}
+
+/// Hand `s` to the caller through `out`, if `out` is non-null.
+///
+/// # Safety
+/// `out` must be `NULL` or valid for a pointer write.
+unsafe fn write_out(out: *mut *mut c_char, s: String) {
+    if !out.is_null() {
+        *out = CString::new(s.replace('\0', " "))
+            .unwrap_or_default()
+            .into_raw();
+    }
+}
+
+/// # Safety
+/// Pointer arguments follow the contract of [`pdf2md_convert_file`].
+unsafe fn run(
+    input: Input<'_>,
+    options_json: *const c_char,
+    progress: Pdf2MdProgressFn,
+    user_data: *mut c_void,
+    out_markdown: *mut *mut c_char,
+    out_json: *mut *mut c_char,
+) -> Pdf2MdStatus {
+    clear_last_error();
+    if out_markdown.is_null() && out_json.is_null() {
+        set_last_error("at least one of out_markdown / out_json must be non-NULL");
+        return Pdf2MdStatus::InvalidArgument;
+    }
+    if !out_markdown.is_null() {
+        *out_markdown = std::ptr::null_mut();
+    }
+    if !out_json.is_null() {
+        *out_json = std::ptr::null_mut();
+    }
+
+    let options = match opt_str(options_json, "options_json") {
+        Ok(json) => json.unwrap_or(""),
+        Err(status) => return status,
+    };
+    let options = match FfiOptions::parse(options) {
+        Ok(o) => o,
+        Err(msg) => {
+            set_last_error(msg);
+            return Pdf2MdStatus::InvalidArgument;
+        }
+    };
+    let callback = progress.map(|callback| {
+        Arc::new(FfiProgress {
+            callback,
+            user_data,
+        }) as Arc<dyn ConversionProgressCallback>
+    });
+
+    let outcome = catch_unwind(AssertUnwindSafe(|| {
+        let runtime = RUNTIME
+            .as_ref()
+            .map_err(|e| Pdf2MdError::Internal(e.clone()))?;
+        let config = options.into_config(callback)?;
+        runtime.block_on(async {
+            match input {
+                Input::Path(path) => edgequake_pdf2md::convert(path, &config).await,
+                Input::Bytes(bytes) => edgequake_pdf2md::convert_from_bytes(bytes, &config).await,
+            }
+        })
+    }));
+
+    let output: ConversionOutput = match outcome {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            return Pdf2MdStatus::from(&e);
+        }
+        Err(_) => {
+            set_last_error("panic during conversion");
+            return Pdf2MdStatus::Panic;
+        }
+    };
+
+    if !out_json.is_null() {
+        match serde_json::to_string(&output) {
+            Ok(json) => write_out(out_json, json),
+            Err(e) => {
+                set_last_error(format!("failed to serialise output: {e}"));
+                return Pdf2MdStatus::Internal;
+            }
+        }
+    }
+    write_out(out_markdown, output.markdown);
+    Pdf2MdStatus::Ok
+}
+
+// ── Exported functions ───────────────────────────────────────────────────
+
+/// Convert a PDF file path or HTTP(S) URL to Markdown.
+///
+/// On success writes the assembled Markdown to `*out_markdown` and, if
+/// `out_json` is non-null, the full result (pages, metadata, stats) as JSON
+/// to `*out_json`. Either out-pointer may be `NULL`, but not both. Free
+/// returned strings with [`pdf2md_string_free`].
+///
+/// # Safety
+/// `input` must be a valid NUL-terminated string; `options_json` must be
+/// `NULL` or a valid NUL-terminated string; `out_markdown` / `out_json` must
+/// be `NULL` or valid for a pointer write; `progress`, if set, must be safe
+/// to call with `user_data` from any thread until this function returns.
+#[no_mangle]
+pub unsafe extern "C" fn pdf2md_convert_file(
+    input: *const c_char,
+    options_json: *const c_char,
+    progress: Pdf2MdProgressFn,
+    user_data: *mut c_void,
+    out_markdown: *mut *mut c_char,
+    out_json: *mut *mut c_char,
+) -> Pdf2MdStatus {
+    let path = match opt_str(input, "input") {
+        Ok(Some(path)) => path,
+        Ok(None) => {
+            set_last_error("input is NULL");
+            return Pdf2MdStatus::InvalidArgument;
+        }
+        Err(status) => return status,
+    };
+    run(
+        Input::Path(path),
+        options_json,
+        progress,
+        user_data,
+        out_markdown,
+        out_json,
+    )
+}
+
+/// Convert an in-memory PDF to Markdown.
+///
+/// Same outputs as [`pdf2md_convert_file`].
+///
+/// # Safety
+/// `data` must point to `len` readable bytes (it may be `NULL` only when
+/// `len` is 0, which is then rejected); the other arguments follow
+/// [`pdf2md_convert_file`].
+#[no_mangle]
+pub unsafe extern "C" fn pdf2md_convert_bytes(
+    data: *const u8,
+    len: usize,
+    options_json: *const c_char,
+    progress: Pdf2MdProgressFn,
+    user_data: *mut c_void,
+    out_markdown: *mut *mut c_char,
+    out_json: *mut *mut c_char,
+) -> Pdf2MdStatus {
+    if data.is_null() || len == 0 {
+        set_last_error("data is NULL or empty");
+        return Pdf2MdStatus::InvalidArgument;
+    }
+    let bytes = std::slice::from_raw_parts(data, len);
+    run(
+        Input::Bytes(bytes),
+        options_json,
+        progress,
+        user_data,
+        out_markdown,
+        out_json,
+    )
+}
+
+/// Message describing the last failure on this thread, or `NULL`.
+///
+/// The pointer stays valid until the next `pdf2md_*` call on the same
+/// thread; do not free it.
+#[no_mangle]
+pub extern "C" fn pdf2md_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(std::ptr::null(), |c| c.as_ptr()))
+}
+
+/// Free a string returned by this library. `NULL` is ignored.
+///
+/// # Safety
+/// `s` must be `NULL` or a pointer returned through an out-parameter of this
+/// library that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn pdf2md_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+/// Library version as a static NUL-terminated string (e.g. `"0.1.0"`).
+#[no_mangle]
+pub extern "C" fn pdf2md_version() -> *const c_char {
+    VERSION.as_ptr().cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options_parse_defaults_and_fields() {
+        assert!(FfiOptions::parse("").unwrap().model.is_none());
+        let o = FfiOptions::parse(
+            r#"{"model": "gpt-4.1-mini", "dpi": 200, "pages": {"Range": [1, 5]}, "fidelity": "Tier3"}"#,
+        )
+        .unwrap();
+        assert_eq!(o.model.as_deref(), Some("gpt-4.1-mini"));
+        assert_eq!(o.dpi, Some(200));
+        assert!(matches!(o.pages, Some(PageSelection::Range(1, 5))));
+        assert_eq!(o.fidelity, Some(FidelityTier::Tier3));
+    }
+
+    #[test]
+    fn options_reject_unknown_fields() {
+        let err = FfiOptions::parse(r#"{"modle": "x"}"#).unwrap_err();
+        assert!(err.contains("modle"), "{err}");
+    }
+
+    #[test]
+    fn error_codes_map_from_library_errors() {
+        let e = Pdf2MdError::FileNotFound {
+            path: "/nope.pdf".into(),
+        };
+        assert_eq!(Pdf2MdStatus::from(&e), Pdf2MdStatus::NotFound);
+        let e = Pdf2MdError::InvalidConfig("dpi".into());
+        assert_eq!(Pdf2MdStatus::from(&e), Pdf2MdStatus::InvalidConfig);
+    }
+
+    #[test]
+    fn null_arguments_are_rejected() {
+        let mut out: *mut c_char = std::ptr::null_mut();
+        let status = unsafe {
+            pdf2md_convert_file(
+                std::ptr::null(),
+                std::ptr::null(),
+                None,
+                std::ptr::null_mut(),
+                &mut out,
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, Pdf2MdStatus::InvalidArgument);
+        assert!(out.is_null());
+        let msg = unsafe { CStr::from_ptr(pdf2md_last_error()) };
+        assert_eq!(msg.to_str().unwrap(), "input is NULL");
+    }
+
+    #[test]
+    fn bad_options_json_is_an_invalid_argument() {
+        let input = CString::new("/nope.pdf").unwrap();
+        let options = CString::new("{not json").unwrap();
+        let mut out: *mut c_char = std::ptr::null_mut();
+        let status = unsafe {
+            pdf2md_convert_file(
+                input.as_ptr(),
+                options.as_ptr(),
+                None,
+                std::ptr::null_mut(),
+                &mut out,
+                std::ptr::null_mut(),
+            )
+        };
+        assert_eq!(status, Pdf2MdStatus::InvalidArgument);
+        assert!(out.is_null());
+    }
+
+    #[test]
+    fn version_is_nul_terminated() {
+        let v = unsafe { CStr::from_ptr(pdf2md_version()) };
+        assert_eq!(v.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    }
+}
@@ -6,7 +6,8 @@
 // Library resolution order (first match wins)
 // ─────────────────────────────────────────────
 //   1. `PDFIUM_BUNDLE_LIB` env var — explicit path you supply (CI / air-gapped)
-//   2. Auto-download from bblanchon/pdfium-binaries using `curl`
+//   2. Auto-download from bblanchon/pdfium-binaries using `curl`, or from
+//      PDFIUM_AUTO_MIRROR_URL when set
 //
 // Auto-download cache
 // ───────────────────
@@ -238,10 +239,17 @@ fn resolve_lib(target_os: &str, target_arch: &str) -> PathBuf {
         )
     });
 
-    let url = format!(
-        "{BASE_URL}/chromium%2F{PDFIUM_VERSION}/{}",
-        bundle.archive_name
-    );
+    let url = match std::env::var("PDFIUM_AUTO_MIRROR_URL") {
+        Ok(mirror) if !mirror.trim().is_empty() => format!(
+            "{}/{}",
+            mirror.trim().trim_end_matches('/'),
+            bundle.archive_name
+        ),
+        _ => format!(
+            "{BASE_URL}/chromium%2F{PDFIUM_VERSION}/{}",
+            bundle.archive_name
+        ),
+    };
     let tgz_path = cache_dir.join(bundle.archive_name);
 
     download_file(&url, &tgz_path);
@@ -265,6 +273,7 @@ fn main() {
     println!("cargo:rerun-if-env-changed=PDFIUM_BUNDLE_LIB");
     println!("cargo:rerun-if-env-changed=CARGO_FEATURE_BUNDLED");
     println!("cargo:rerun-if-env-changed=PDFIUM_BUILD_CACHE_DIR");
+    println!("cargo:rerun-if-env-changed=PDFIUM_AUTO_MIRROR_URL");
 
     if std::env::var("CARGO_FEATURE_BUNDLED").is_err() {
         return; // bundled feature not active — nothing to do
@@ -0,0 +1,91 @@
+//! `pdfium-auto` — populate the PDFium cache from the command line.
+//!
+//! ```text
+//! pdfium-auto install                                  # download (honours PDFIUM_AUTO_MIRROR_URL)
+//! pdfium-auto install --from ARCHIVE.tgz --sha256 HEX  # unpack a local copy of the archive
+//! ```
+//!
+//! Prints the path of the installed library on success.
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: pdfium-auto install [--from <ARCHIVE> [--sha256 <HEX>]]
+
+Installs the PDFium library into the pdfium-auto cache and prints its path.
+
+Options:
+  --from <ARCHIVE>  Unpack this copy of the platform release archive
+                    (e.g. pdfium-linux-x64.tgz) instead of downloading it
+  --sha256 <HEX>    Expected SHA-256 of the archive; required unless the
+                    crate or PDFIUM_AUTO_SHA256 knows its digest
+  -h, --help        Print this help";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let install = match parse_args(&args) {
+        Ok(Some(install)) => install,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(msg) => {
+            eprintln!("error: {msg}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let result = match &install.from {
+        Some(archive) => pdfium_auto::install_from_archive(archive, install.sha256.as_deref()),
+        None => pdfium_auto::ensure_pdfium_library(None),
+    };
+    match result {
+        Ok(path) => {
+            println!("{}", path.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Options of the `install` command.
+struct Install {
+    from: Option<PathBuf>,
+    sha256: Option<String>,
+}
+
+/// The options of an `install` command, or `Ok(None)` for help.
+fn parse_args(args: &[String]) -> Result<Option<Install>, String> {
+    let mut args = args.iter();
+    match args.next().map(String::as_str) {
+        Some("install") => {}
+        Some("-h" | "--help") | None => return Ok(None),
+        Some(other) => return Err(format!("unknown command '{other}'")),
+    }
+    let mut install = Install {
+        from: None,
+        sha256: None,
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => {
+                let path = args.next().ok_or("--from needs an archive path")?;
+                install.from = Some(PathBuf::from(path));
+            }
+            "--sha256" => {
+                let digest = args.next().ok_or("--sha256 needs a hex digest")?;
+                install.sha256 = Some(digest.clone());
+            }
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("unexpected argument '{other}'")),
+        }
+    }
+    if install.sha256.is_some() && install.from.is_none() {
+        return Err("--sha256 applies to --from; pin downloads with PDFIUM_AUTO_SHA256".into());
+    }
+    Ok(Some(install))
+}
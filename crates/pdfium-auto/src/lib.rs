@@ -10,7 +10,8 @@
 //!
 //! 1. Checks `~/.cache/pdf2md/pdfium-{VERSION}/` for the platform library.
 //! 2. If absent, downloads the correct `.tgz` from
-//!    [bblanchon/pdfium-binaries](https://github.com/bblanchon/pdfium-binaries).
+//!    [bblanchon/pdfium-binaries](https://github.com/bblanchon/pdfium-binaries)
+//!    and checks its SHA-256 digest (see [Integrity](#integrity)).
 //! 3. Extracts `lib/libpdfium.dylib` (or `.so` / `.dll`) to the cache dir.
 //! 4. Calls [`Pdfium::bind_to_library`] to load the real library.
 //!
@@ -65,6 +66,18 @@
 //! let pdfium = bind_pdfium_from_path(&path).expect("bind failed");
 //! ```
 //!
+//! [`ensure_pdfium_library`] uses blocking HTTP and must not run on a tokio
+//! worker thread. From async code, await [`ensure_pdfium_library_async`]
+//! instead; afterwards the sync functions find the library without I/O.
+//!
+//! ```rust,no_run
+//! # async fn setup() -> Result<(), pdfium_auto::PdfiumAutoError> {
+//! let path = pdfium_auto::ensure_pdfium_library_async(None).await?;
+//! let pdfium = pdfium_auto::bind_pdfium_from_path(&path)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Platform support
 //!
 //! | OS      | Arch    | Library               |
@@ -77,10 +90,40 @@
 //! | Windows | aarch64 | `pdfium.dll`          |
 //! | Windows | x86     | `pdfium.dll`          |
 //!
+//! ## Air-gapped machines
+//!
+//! Copy the platform archive (e.g. `pdfium-linux-x64.tgz`) onto the machine
+//! and install it into the cache with [`install_from_archive`], or with the
+//! bundled command-line helper:
+//!
+//! ```sh
+//! pdfium-auto install --from /path/to/pdfium-linux-x64.tgz --sha256 <HEX>
+//! ```
+//!
+//! The archive must match the given digest, or else the one known for a
+//! download (see below); a local archive with neither is refused. The same
+//! rule covers downloads from `PDFIUM_AUTO_MIRROR_URL`.
+//!
+//! ## Integrity
+//!
+//! A downloaded archive is hashed before anything is extracted from it. The
+//! digest must match the one pinned by `PDFIUM_AUTO_SHA256`, or else the
+//! one this crate ships for the platform archive of [`PDFIUM_VERSION`];
+//! on a mismatch the download fails with [`PdfiumAutoError::Checksum`] and
+//! nothing is written to the cache. When neither is known the install fails
+//! with [`PdfiumAutoError::UnknownChecksum`] before downloading anything;
+//! only `PDFIUM_AUTO_SKIP_CHECKSUM=1` installs an archive unverified.
+//!
 //! ## Environment variable overrides
 //!
 //! - `PDFIUM_LIB_PATH` — path to an existing pdfium library; skips download.
 //! - `PDFIUM_AUTO_CACHE_DIR` — override the default cache directory.
+//! - `PDFIUM_AUTO_MIRROR_URL` — download from this URL instead of GitHub:
+//!   a directory holding the platform archives of [`PDFIUM_VERSION`] under
+//!   their release names, e.g. `https://artifacts.example.com/pdfium/7690`.
+//! - `PDFIUM_AUTO_SHA256` — expected SHA-256 (hex) of the downloaded archive.
+//! - `PDFIUM_AUTO_SKIP_CHECKSUM` — set to `1` to skip the digest check, e.g.
+//!   for a locally rebuilt archive. Not recommended.
 //! - `PDFIUM_BUNDLE_LIB` — (compile time) path to the dylib to embed when
 //!   the `bundled` feature is active.
 
@@ -101,6 +144,12 @@ pub const PDFIUM_VERSION: &str = "7690";
 /// GitHub release base URL.
 const BASE_URL: &str = "https://github.com/bblanchon/pdfium-binaries/releases/download";
 
+/// SHA-256 digests (lowercase hex) of the [`PDFIUM_VERSION`] release
+/// archives, by asset name. Regenerate with `scripts/pdfium-sha256.sh`
+/// whenever [`PDFIUM_VERSION`] changes. An archive missing here cannot be
+/// installed unless `PDFIUM_AUTO_SHA256` pins its digest.
+const ARCHIVE_SHA256: &[(&str, &str)] = &[];
+
 // ── Error type ───────────────────────────────────────────────────────────────
 
 /// Errors returned by pdfium-auto operations.
@@ -122,6 +171,24 @@ pub enum PdfiumAutoError {
     #[error("Archive extraction failed: {0}")]
     Extract(String),
 
+    /// The downloaded archive does not have the expected SHA-256 digest.
+    #[error(
+        "Checksum mismatch for {archive}: expected SHA-256 {expected}, got {actual} \
+         (set PDFIUM_AUTO_SKIP_CHECKSUM=1 to bypass)"
+    )]
+    Checksum {
+        archive: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// No digest is known for the archive, so it cannot be verified.
+    #[error(
+        "No known SHA-256 for {archive}: set PDFIUM_AUTO_SHA256 to the digest of the \
+         release archive (or PDFIUM_AUTO_SKIP_CHECKSUM=1 to install it unverified)"
+    )]
+    UnknownChecksum { archive: String },
+
     /// `libloading` / `pdfium-render` could not load the library.
     #[error("Failed to bind PDFium from '{path}': {reason}")]
     Bind { path: PathBuf, reason: String },
@@ -274,6 +341,82 @@ pub fn ensure_pdfium_library(
     Ok(path)
 }
 
+/// Async variant of [`ensure_pdfium_library`], safe to call from a tokio
+/// worker thread.
+///
+/// The download uses async HTTP; checking and unpacking the archive run on
+/// tokio's blocking pool. `on_progress` receives the same
+/// `(bytes_downloaded, total_size_option)` updates. Must be called within
+/// a tokio runtime.
+pub async fn ensure_pdfium_library_async(
+    on_progress: Option<&(dyn Fn(u64, Option<u64>) + Send + Sync)>,
+) -> Result<PathBuf, PdfiumAutoError> {
+    if let Some(path) = RESOLVED_PATH.get() {
+        return Ok(path.clone());
+    }
+
+    let path = match lookup()? {
+        Lookup::Found(path) => path,
+        Lookup::Missing {
+            info,
+            cache_dir,
+            lib_path,
+        } => {
+            let expected = expected_sha256(info.archive_name)?;
+            let archive_bytes = download_bytes_async(&archive_url(&info), on_progress).await?;
+            let dest = lib_path.clone();
+            tokio::task::spawn_blocking(move || {
+                install_archive(
+                    &info,
+                    &archive_bytes,
+                    expected.as_deref(),
+                    &cache_dir,
+                    &dest,
+                )
+            })
+            .await
+            .map_err(|e| PdfiumAutoError::Extract(format!("Install task failed: {e}")))??;
+            lib_path
+        }
+    };
+
+    let _ = RESOLVED_PATH.set(path.clone());
+    Ok(path)
+}
+
+/// Installs the library from a local copy of the platform release archive
+/// (see [`PDFIUM_VERSION`]) into [`pdfium_cache_dir`], for machines that
+/// cannot reach the download server. Returns the library's path.
+///
+/// The archive must have the SHA-256 `sha256` (hex) when given, or else the
+/// digest a download would be checked against; with neither nothing is
+/// read and [`PdfiumAutoError::UnknownChecksum`] is returned. An existing
+/// cached library is replaced.
+pub fn install_from_archive(
+    archive: &Path,
+    sha256: Option<&str>,
+) -> Result<PathBuf, PdfiumAutoError> {
+    let info = detect_platform()?;
+    let expected = match sha256.map(str::trim).filter(|d| !d.is_empty()) {
+        Some(digest) => Some(digest.to_string()),
+        None => expected_sha256(info.archive_name)?,
+    };
+    let archive_bytes = std::fs::read(archive)
+        .map_err(|e| PdfiumAutoError::Extract(format!("Cannot read {}: {e}", archive.display())))?;
+    let cache_dir = pdfium_cache_dir();
+    let lib_path = cache_dir.join(info.lib_name);
+    install_archive(
+        &info,
+        &archive_bytes,
+        expected.as_deref(),
+        &cache_dir,
+        &lib_path,
+    )?;
+
+    let _ = RESOLVED_PATH.set(lib_path.clone());
+    Ok(lib_path)
+}
+
 /// Binds to PDFium, downloading it first if necessary.
 ///
 /// `on_progress` receives `(bytes_downloaded, total_bytes_option)` during
@@ -400,14 +543,47 @@ pub fn bind_bundled() -> Result<Pdfium, PdfiumAutoError> {
 
 // ── Internal helpers ─────────────────────────────────────────────────────────
 
+/// Where the library is, or where it has to be downloaded to.
+enum Lookup {
+    Found(PathBuf),
+    Missing {
+        info: PlatformInfo,
+        cache_dir: PathBuf,
+        lib_path: PathBuf,
+    },
+}
+
 fn resolve_or_download(
     on_progress: Option<&dyn Fn(u64, Option<u64>)>,
 ) -> Result<PathBuf, PdfiumAutoError> {
+    match lookup()? {
+        Lookup::Found(path) => Ok(path),
+        Lookup::Missing {
+            info,
+            cache_dir,
+            lib_path,
+        } => {
+            let expected = expected_sha256(info.archive_name)?;
+            let archive_bytes = download_bytes(&archive_url(&info), on_progress)?;
+            install_archive(
+                &info,
+                &archive_bytes,
+                expected.as_deref(),
+                &cache_dir,
+                &lib_path,
+            )?;
+            Ok(lib_path)
+        }
+    }
+}
+
+/// Checks `PDFIUM_LIB_PATH` and the cache, without touching the network.
+fn lookup() -> Result<Lookup, PdfiumAutoError> {
     // 1. Environment variable override.
     if let Ok(env_path) = std::env::var("PDFIUM_LIB_PATH") {
         let p = PathBuf::from(env_path);
         if p.exists() {
-            return Ok(p);
+            return Ok(Lookup::Found(p));
         }
         // Fall through: env var set but file missing → still auto-download.
         eprintln!(
@@ -422,21 +598,50 @@ fn resolve_or_download(
 
     // 2. Already cached on disk.
     if lib_path.exists() {
-        return Ok(lib_path);
+        return Ok(Lookup::Found(lib_path));
     }
 
-    // 3. Download and extract.
-    let url = format!(
-        "{}/chromium%2F{}/{}",
-        BASE_URL, PDFIUM_VERSION, info.archive_name
-    );
+    // 3. Needs a download.
+    Ok(Lookup::Missing {
+        info,
+        cache_dir,
+        lib_path,
+    })
+}
 
-    std::fs::create_dir_all(&cache_dir).map_err(PdfiumAutoError::CacheDir)?;
+/// Download URL of the platform archive, from `PDFIUM_AUTO_MIRROR_URL`
+/// when set.
+fn archive_url(info: &PlatformInfo) -> String {
+    let mirror = std::env::var("PDFIUM_AUTO_MIRROR_URL").ok();
+    archive_url_from(mirror.as_deref(), info.archive_name)
+}
 
-    let archive_bytes = download_bytes(&url, on_progress)?;
-    extract_library(&archive_bytes, info.lib_path_in_archive, &lib_path)?;
+/// `{mirror}/{archive_name}`, or the GitHub release asset URL when there
+/// is no mirror.
+fn archive_url_from(mirror: Option<&str>, archive_name: &str) -> String {
+    match mirror.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(mirror) => format!("{}/{}", mirror.trim_end_matches('/'), archive_name),
+        None => format!(
+            "{}/chromium%2F{}/{}",
+            BASE_URL, PDFIUM_VERSION, archive_name
+        ),
+    }
+}
 
-    Ok(lib_path)
+/// Checks the archive against `expected` (see [`expected_sha256`]) and
+/// extracts the library to `lib_path`.
+fn install_archive(
+    info: &PlatformInfo,
+    archive_bytes: &[u8],
+    expected: Option<&str>,
+    cache_dir: &Path,
+    lib_path: &Path,
+) -> Result<(), PdfiumAutoError> {
+    if let Some(expected) = expected {
+        check_digest(info.archive_name, archive_bytes, Some(expected))?;
+    }
+    std::fs::create_dir_all(cache_dir).map_err(PdfiumAutoError::CacheDir)?;
+    extract_library(archive_bytes, info.lib_path_in_archive, lib_path)
 }
 
 /// Streams a URL into a `Vec<u8>`, calling `on_progress` every 64 KiB.
@@ -490,6 +695,111 @@ fn download_bytes(
     Ok(buf)
 }
 
+/// Async counterpart of [`download_bytes`], reporting progress per chunk.
+async fn download_bytes_async(
+    url: &str,
+    on_progress: Option<&(dyn Fn(u64, Option<u64>) + Send + Sync)>,
+) -> Result<Vec<u8>, PdfiumAutoError> {
+    let client = reqwest::Client::builder()
+        .user_agent(concat!("pdfium-auto/", env!("CARGO_PKG_VERSION")))
+        .redirect(reqwest::redirect::Policy::limited(5))
+        .build()
+        .map_err(|e| PdfiumAutoError::Download(e.to_string()))?;
+
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| PdfiumAutoError::Download(format!("GET {url}: {e}")))?;
+
+    if !response.status().is_success() {
+        return Err(PdfiumAutoError::Download(format!(
+            "HTTP {} for {url}",
+            response.status()
+        )));
+    }
+
+    let total = response.content_length();
+    let capacity = total.unwrap_or(35 * 1024 * 1024) as usize;
+    let mut buf = Vec::with_capacity(capacity);
+    let mut downloaded: u64 = 0;
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| PdfiumAutoError::Download(format!("Read error: {e}")))?
+    {
+        buf.extend_from_slice(&chunk);
+        downloaded += chunk.len() as u64;
+        if let Some(cb) = on_progress {
+            cb(downloaded, total);
+        }
+    }
+
+    Ok(buf)
+}
+
+/// The SHA-256 `archive_name` must have; see the crate docs. `None` only
+/// when `PDFIUM_AUTO_SKIP_CHECKSUM` switches the check off.
+fn expected_sha256(archive_name: &str) -> Result<Option<String>, PdfiumAutoError> {
+    let skip = std::env::var("PDFIUM_AUTO_SKIP_CHECKSUM").is_ok_and(|v| v == "1" || v == "true");
+    let pinned = std::env::var("PDFIUM_AUTO_SHA256").ok();
+    resolve_sha256(archive_name, pinned.as_deref(), skip)
+}
+
+/// [`expected_sha256`] with the environment already read.
+fn resolve_sha256(
+    archive_name: &str,
+    pinned: Option<&str>,
+    skip: bool,
+) -> Result<Option<String>, PdfiumAutoError> {
+    if skip {
+        eprintln!("pdfium-auto: PDFIUM_AUTO_SKIP_CHECKSUM set; not verifying {archive_name}");
+        return Ok(None);
+    }
+    match pinned
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .or_else(|| known_sha256(archive_name))
+    {
+        Some(digest) => Ok(Some(digest.to_string())),
+        None => Err(PdfiumAutoError::UnknownChecksum {
+            archive: archive_name.to_string(),
+        }),
+    }
+}
+
+/// The digest this crate ships for `archive_name`, if any.
+fn known_sha256(archive_name: &str) -> Option<&'static str> {
+    ARCHIVE_SHA256
+        .iter()
+        .find(|(name, _)| *name == archive_name)
+        .map(|(_, digest)| *digest)
+}
+
+/// Returns the SHA-256 of `bytes` as lowercase hex, or
+/// [`PdfiumAutoError::Checksum`] when it differs from `expected`.
+fn check_digest(
+    archive_name: &str,
+    bytes: &[u8],
+    expected: Option<&str>,
+) -> Result<String, PdfiumAutoError> {
+    use sha2::{Digest, Sha256};
+
+    let actual: String = Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    match expected {
+        Some(e) if !e.trim().eq_ignore_ascii_case(&actual) => Err(PdfiumAutoError::Checksum {
+            archive: archive_name.to_string(),
+            expected: e.trim().to_ascii_lowercase(),
+            actual,
+        }),
+        _ => Ok(actual),
+    }
+}
+
 /// Extracts a single file from a gzipped tar archive into `dest_path`.
 fn extract_library(
     archive_bytes: &[u8],
@@ -556,6 +866,88 @@ mod tests {
         assert!(d.to_str().unwrap().contains(PDFIUM_VERSION));
     }
 
+    #[test]
+    fn mirror_replaces_the_release_url() {
+        assert_eq!(
+            archive_url_from(
+                Some("https://mirror.example/pdfium/7690/"),
+                "pdfium-linux-x64.tgz"
+            ),
+            "https://mirror.example/pdfium/7690/pdfium-linux-x64.tgz"
+        );
+        let github = archive_url_from(None, "pdfium-linux-x64.tgz");
+        assert!(github.starts_with(BASE_URL));
+        assert!(github.ends_with(&format!("chromium%2F{PDFIUM_VERSION}/pdfium-linux-x64.tgz")));
+        assert_eq!(
+            archive_url_from(Some(" "), "a.tgz"),
+            archive_url_from(None, "a.tgz")
+        );
+    }
+
+    #[test]
+    fn digest_mismatch_is_an_error() {
+        let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(check_digest("a.tgz", b"abc", None).unwrap(), abc);
+        assert!(check_digest("a.tgz", b"abc", Some(&abc.to_uppercase())).is_ok());
+        match check_digest("a.tgz", b"abd", Some(abc)) {
+            Err(PdfiumAutoError::Checksum { expected, .. }) => assert_eq!(expected, abc),
+            other => panic!("expected Checksum, got {other:?}"),
+        }
+    }
+
+    /// A gzipped tar holding `lib/libpdfium.so` with `contents`.
+    fn archive(contents: &[u8]) -> Vec<u8> {
+        let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::fast(),
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        tar.append_data(&mut header, "lib/libpdfium.so", contents)
+            .unwrap();
+        tar.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn tampered_archive_is_not_installed() {
+        let info = PlatformInfo {
+            archive_name: "pdfium-test.tgz",
+            lib_path_in_archive: "lib/libpdfium.so",
+            lib_name: "libpdfium.so",
+        };
+        let genuine = archive(b"genuine");
+        let digest = check_digest(info.archive_name, &genuine, None).unwrap();
+        let dir = std::env::temp_dir().join(format!("pdfium-auto-test-{}", std::process::id()));
+        let lib_path = dir.join(info.lib_name);
+
+        let tampered = archive(b"tampered");
+        let result = install_archive(&info, &tampered, Some(&digest), &dir, &lib_path);
+        assert!(matches!(result, Err(PdfiumAutoError::Checksum { .. })));
+        assert!(!lib_path.exists());
+
+        install_archive(&info, &genuine, Some(&digest), &dir, &lib_path).unwrap();
+        assert_eq!(std::fs::read(&lib_path).unwrap(), b"genuine");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn archive_without_a_digest_is_refused() {
+        assert!(matches!(
+            resolve_sha256("pdfium-unknown.tgz", None, false),
+            Err(PdfiumAutoError::UnknownChecksum { .. })
+        ));
+        assert_eq!(
+            resolve_sha256("pdfium-unknown.tgz", Some(" abc "), false).unwrap(),
+            Some("abc".to_string())
+        );
+        assert_eq!(
+            resolve_sha256("pdfium-unknown.tgz", None, true).unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn platform_info_fields_nonempty() {
         let info = detect_platform().unwrap();
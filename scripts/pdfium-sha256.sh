@@ -0,0 +1,41 @@
+#!/usr/bin/env bash
+# ─────────────────────────────────────────────────────────────────────────────
+# pdfium-sha256.sh — Print the ARCHIVE_SHA256 table of pdfium-auto
+# ─────────────────────────────────────────────────────────────────────────────
+# Downloads every platform archive of the pinned pdfium-binaries release and
+# prints its SHA-256 as Rust source, ready to paste into
+# crates/pdfium-auto/src/lib.rs. Run it whenever PDFIUM_VERSION changes.
+#
+# Usage:
+#   ./scripts/pdfium-sha256.sh [VERSION]     # default: 7690
+# ─────────────────────────────────────────────────────────────────────────────
+
+set -euo pipefail
+
+VERSION="${1:-7690}"
+BASE_URL="https://github.com/bblanchon/pdfium-binaries/releases/download/chromium%2F${VERSION}"
+ARCHIVES=(
+    pdfium-mac-arm64.tgz
+    pdfium-mac-x64.tgz
+    pdfium-linux-x64.tgz
+    pdfium-linux-arm64.tgz
+    pdfium-win-x64.tgz
+    pdfium-win-arm64.tgz
+    pdfium-win-x86.tgz
+)
+
+if command -v sha256sum >/dev/null 2>&1; then
+    sha256() { sha256sum "$1" | cut -d' ' -f1; }
+else
+    sha256() { shasum -a 256 "$1" | cut -d' ' -f1; }
+fi
+
+TMP="$(mktemp -d)"
+trap 'rm -rf "$TMP"' EXIT
+
+echo "const ARCHIVE_SHA256: &[(&str, &str)] = &["
+for archive in "${ARCHIVES[@]}"; do
+    curl -fsSL -o "$TMP/$archive" "$BASE_URL/$archive"
+    printf '    (\n        "%s",\n        "%s",\n    ),\n' "$archive" "$(sha256 "$TMP/$archive")"
+done
+echo "];"
@@ -0,0 +1,372 @@
+//! Embedded files: listing, saving, and e-invoice parsing.
+//!
+//! ## Why attachments?
+//!
+//! A PDF can carry files the page images never show: the spreadsheet behind
+//! a report's charts, or — in a ZUGFeRD / Factur-X e-invoice — the invoice
+//! itself as machine-readable XML. That XML is exact where a model reading
+//! the page can misread a digit.
+//!
+//! Every conversion lists the attachments, by name and size, in
+//! [`crate::DocumentMetadata::attachments`]. With
+//! [`crate::ConversionConfigBuilder::attachments_dir`] they are also saved
+//! to that directory, and with [`crate::ConversionConfigBuilder::parse_einvoice`]
+//! an embedded e-invoice (`factur-x.xml`, `zugferd-invoice.xml`,
+//! `xrechnung.xml`, …) is parsed into [`Attachment::invoice`].
+//!
+//! Only the UN/CEFACT Cross Industry Invoice syntax used by ZUGFeRD 2 and
+//! Factur-X is read. Names are the XML's, amounts are the document totals
+//! (`GrandTotalAmount`, `TaxTotalAmount`, …), and dates are turned from
+//! `YYYYMMDD` into `YYYY-MM-DD`.
+
+use crate::config::ConversionConfig;
+use crate::error::Pdf2MdError;
+use crate::invoice::{Invoice, LineItem};
+use crate::pipeline::session::PdfSession;
+use pdfium_render::prelude::PdfDocument;
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// A file embedded in the PDF.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// File name as stored in the PDF.
+    pub name: String,
+    pub size_bytes: usize,
+    /// Where the file was saved, with
+    /// [`crate::ConversionConfig::attachments_dir`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// The parsed e-invoice, for a ZUGFeRD / Factur-X XML attachment with
+    /// [`crate::ConversionConfig::parse_einvoice`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invoice: Option<Invoice>,
+}
+
+/// `true` for the attachment names ZUGFeRD, Factur-X, and XRechnung use for
+/// the invoice XML.
+pub fn is_einvoice(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    matches!(
+        name.as_str(),
+        "factur-x.xml" | "zugferd-invoice.xml" | "xrechnung.xml" | "order-x.xml"
+    ) || (name.starts_with("zugferd") && name.ends_with(".xml"))
+}
+
+/// Parse a Cross Industry Invoice (ZUGFeRD 2 / Factur-X) XML document.
+///
+/// # Errors
+/// [`Pdf2MdError::InvalidInput`] when the text is not XML or its root is not
+/// a `CrossIndustryInvoice`.
+pub fn parse_einvoice(xml: &str) -> Result<Invoice, Pdf2MdError> {
+    let invalid = |detail: String| Pdf2MdError::InvalidInput {
+        input: format!("e-invoice XML: {detail}"),
+    };
+    let doc = Document::parse(xml).map_err(|e| invalid(e.to_string()))?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "CrossIndustryInvoice" {
+        return Err(invalid(format!(
+            "root element is {}, not CrossIndustryInvoice",
+            root.tag_name().name()
+        )));
+    }
+
+    let header = child(root, &["ExchangedDocument"]);
+    let transaction = child(root, &["SupplyChainTradeTransaction"]);
+    let agreement = transaction.and_then(|t| child(t, &["ApplicableHeaderTradeAgreement"]));
+    let settlement = transaction.and_then(|t| child(t, &["ApplicableHeaderTradeSettlement"]));
+    let sums =
+        settlement.and_then(|s| child(s, &["SpecifiedTradeSettlementHeaderMonetarySummation"]));
+    let amount = |name: &str| sums.and_then(|s| number(s, &[name]));
+
+    let charges = amount("ChargeTotalAmount");
+    let allowances = amount("AllowanceTotalAmount");
+    let other_charges = match (charges, allowances) {
+        (None, None) => None,
+        (c, a) => Some(c.unwrap_or(0.0) - a.unwrap_or(0.0)),
+    };
+
+    Ok(Invoice {
+        vendor: agreement.and_then(|a| text(a, &["SellerTradeParty", "Name"])),
+        customer: agreement.and_then(|a| text(a, &["BuyerTradeParty", "Name"])),
+        invoice_number: header.and_then(|h| text(h, &["ID"])),
+        date: header.and_then(|h| date(h, &["IssueDateTime", "DateTimeString"])),
+        due_date: settlement.and_then(|s| {
+            date(
+                s,
+                &[
+                    "SpecifiedTradePaymentTerms",
+                    "DueDateDateTime",
+                    "DateTimeString",
+                ],
+            )
+        }),
+        currency: settlement.and_then(|s| text(s, &["InvoiceCurrencyCode"])),
+        line_items: transaction
+            .map(|t| {
+                t.children()
+                    .filter(|n| n.tag_name().name() == "IncludedSupplyChainTradeLineItem")
+                    .map(line_item)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        subtotal: amount("LineTotalAmount"),
+        tax: amount("TaxTotalAmount"),
+        other_charges,
+        total: amount("GrandTotalAmount"),
+    })
+}
+
+/// One `IncludedSupplyChainTradeLineItem`.
+fn line_item(item: Node) -> LineItem {
+    LineItem {
+        description: text(item, &["SpecifiedTradeProduct", "Name"]).unwrap_or_default(),
+        quantity: number(item, &["SpecifiedLineTradeDelivery", "BilledQuantity"]),
+        unit_price: number(
+            item,
+            &[
+                "SpecifiedLineTradeAgreement",
+                "NetPriceProductTradePrice",
+                "ChargeAmount",
+            ],
+        ),
+        amount: number(
+            item,
+            &[
+                "SpecifiedLineTradeSettlement",
+                "SpecifiedTradeSettlementLineMonetarySummation",
+                "LineTotalAmount",
+            ],
+        ),
+    }
+}
+
+/// The element reached from `node` through children with these local
+/// names, ignoring namespaces.
+fn child<'a, 'input>(node: Node<'a, 'input>, path: &[&str]) -> Option<Node<'a, 'input>> {
+    path.iter().try_fold(node, |n, name| {
+        n.children()
+            .find(|c| c.is_element() && c.tag_name().name() == *name)
+    })
+}
+
+/// Trimmed, non-empty text of the element at `path`.
+fn text(node: Node, path: &[&str]) -> Option<String> {
+    let t = child(node, path)?.text()?.trim();
+    (!t.is_empty()).then(|| t.to_string())
+}
+
+fn number(node: Node, path: &[&str]) -> Option<f64> {
+    text(node, path)?.parse().ok()
+}
+
+/// A `YYYYMMDD` date (CII format 102) as `YYYY-MM-DD`; other formats as
+/// written.
+fn date(node: Node, path: &[&str]) -> Option<String> {
+    let d = text(node, path)?;
+    Some(if d.len() == 8 && d.bytes().all(|b| b.is_ascii_digit()) {
+        format!("{}-{}-{}", &d[..4], &d[4..6], &d[6..])
+    } else {
+        d
+    })
+}
+
+/// A file name that stays inside the target directory and is not yet in
+/// `used`.
+fn safe_name(name: &str, index: usize, used: &mut HashSet<String>) -> String {
+    let base = Path::new(name)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .filter(|n| !n.is_empty() && *n != "..")
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("attachment-{}", index + 1));
+    let mut candidate = base.clone();
+    let mut n = 1;
+    while !used.insert(candidate.clone()) {
+        n += 1;
+        candidate = format!("{n}-{base}");
+    }
+    candidate
+}
+
+/// Read the attachments of the document `session` holds open, saving them
+/// to [`ConversionConfig::attachments_dir`] and parsing e-invoices as
+/// configured.
+pub(crate) async fn extract(
+    session: &PdfSession,
+    config: &ConversionConfig,
+) -> Result<Vec<Attachment>, Pdf2MdError> {
+    let files = session.run(read_attachments).await?;
+    let dir = config.attachments_dir.clone();
+    let parse = config.parse_einvoice;
+
+    tokio::task::spawn_blocking(move || save_attachments(files, dir.as_deref(), parse))
+        .await
+        .map_err(|e| Pdf2MdError::Internal(format!("Attachment task panicked: {}", e)))?
+}
+
+/// Name and contents of each attachment of `document`; `None` contents for
+/// one that could not be read.
+fn read_attachments(document: &PdfDocument) -> Vec<(String, Option<Vec<u8>>)> {
+    document
+        .attachments()
+        .iter()
+        .map(|file| {
+            let name = file.name();
+            let bytes = match file.save_to_bytes() {
+                Ok(b) => Some(b),
+                Err(e) => {
+                    warn!("Attachment '{}' could not be read: {:?}", name, e);
+                    None
+                }
+            };
+            (name, bytes)
+        })
+        .collect()
+}
+
+/// Blocking half of [`extract`]: save and parse the attachments read from
+/// the document.
+fn save_attachments(
+    files: Vec<(String, Option<Vec<u8>>)>,
+    dir: Option<&Path>,
+    parse: bool,
+) -> Result<Vec<Attachment>, Pdf2MdError> {
+    let write_err = |path: &Path, source| Pdf2MdError::OutputWriteFailed {
+        path: path.to_path_buf(),
+        source,
+    };
+    if let Some(dir) = dir {
+        std::fs::create_dir_all(dir).map_err(|e| write_err(dir, e))?;
+    }
+
+    let mut used = HashSet::new();
+    let mut attachments = Vec::new();
+    for (index, (name, bytes)) in files.into_iter().enumerate() {
+        let Some(bytes) = bytes else {
+            attachments.push(Attachment {
+                name,
+                ..Default::default()
+            });
+            continue;
+        };
+        let path = match dir {
+            Some(dir) => {
+                let path = dir.join(safe_name(&name, index, &mut used));
+                std::fs::write(&path, &bytes).map_err(|e| write_err(&path, e))?;
+                debug!("Saved attachment '{}' to {}", name, path.display());
+                Some(path)
+            }
+            None => None,
+        };
+        let invoice = if parse && is_einvoice(&name) {
+            match parse_einvoice(&String::from_utf8_lossy(&bytes)) {
+                Ok(invoice) => Some(invoice),
+                Err(e) => {
+                    warn!("Attachment '{}': {}", name, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        attachments.push(Attachment {
+            name,
+            size_bytes: bytes.len(),
+            path,
+            invoice,
+        });
+    }
+    Ok(attachments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FACTUR_X: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rsm:CrossIndustryInvoice xmlns:rsm="urn:un:unece:uncefact:data:standard:CrossIndustryInvoice:100"
+    xmlns:ram="urn:un:unece:uncefact:data:standard:ReusableAggregateBusinessInformationEntity:100"
+    xmlns:udt="urn:un:unece:uncefact:data:standard:UnqualifiedDataType:100">
+  <rsm:ExchangedDocument>
+    <ram:ID>RE-2024-0042</ram:ID>
+    <ram:IssueDateTime><udt:DateTimeString format="102">20240315</udt:DateTimeString></ram:IssueDateTime>
+  </rsm:ExchangedDocument>
+  <rsm:SupplyChainTradeTransaction>
+    <ram:IncludedSupplyChainTradeLineItem>
+      <ram:SpecifiedTradeProduct><ram:Name>Solar panel</ram:Name></ram:SpecifiedTradeProduct>
+      <ram:SpecifiedLineTradeAgreement>
+        <ram:NetPriceProductTradePrice><ram:ChargeAmount>250.00</ram:ChargeAmount></ram:NetPriceProductTradePrice>
+      </ram:SpecifiedLineTradeAgreement>
+      <ram:SpecifiedLineTradeDelivery><ram:BilledQuantity unitCode="C62">4</ram:BilledQuantity></ram:SpecifiedLineTradeDelivery>
+      <ram:SpecifiedLineTradeSettlement>
+        <ram:SpecifiedTradeSettlementLineMonetarySummation><ram:LineTotalAmount>1000.00</ram:LineTotalAmount></ram:SpecifiedTradeSettlementLineMonetarySummation>
+      </ram:SpecifiedLineTradeSettlement>
+    </ram:IncludedSupplyChainTradeLineItem>
+    <ram:ApplicableHeaderTradeAgreement>
+      <ram:SellerTradeParty><ram:Name>Acme GmbH</ram:Name></ram:SellerTradeParty>
+      <ram:BuyerTradeParty><ram:Name>Sunny Roofs SARL</ram:Name></ram:BuyerTradeParty>
+    </ram:ApplicableHeaderTradeAgreement>
+    <ram:ApplicableHeaderTradeSettlement>
+      <ram:InvoiceCurrencyCode>EUR</ram:InvoiceCurrencyCode>
+      <ram:SpecifiedTradePaymentTerms>
+        <ram:DueDateDateTime><udt:DateTimeString format="102">20240414</udt:DateTimeString></ram:DueDateDateTime>
+      </ram:SpecifiedTradePaymentTerms>
+      <ram:SpecifiedTradeSettlementHeaderMonetarySummation>
+        <ram:LineTotalAmount>1000.00</ram:LineTotalAmount>
+        <ram:ChargeTotalAmount>25.00</ram:ChargeTotalAmount>
+        <ram:TaxTotalAmount currencyID="EUR">194.75</ram:TaxTotalAmount>
+        <ram:GrandTotalAmount>1219.75</ram:GrandTotalAmount>
+      </ram:SpecifiedTradeSettlementHeaderMonetarySummation>
+    </ram:ApplicableHeaderTradeSettlement>
+  </rsm:SupplyChainTradeTransaction>
+</rsm:CrossIndustryInvoice>"#;
+
+    #[test]
+    fn factur_x_xml_becomes_an_invoice() {
+        let invoice = parse_einvoice(FACTUR_X).unwrap();
+        assert_eq!(invoice.vendor.as_deref(), Some("Acme GmbH"));
+        assert_eq!(invoice.customer.as_deref(), Some("Sunny Roofs SARL"));
+        assert_eq!(invoice.invoice_number.as_deref(), Some("RE-2024-0042"));
+        assert_eq!(invoice.date.as_deref(), Some("2024-03-15"));
+        assert_eq!(invoice.due_date.as_deref(), Some("2024-04-14"));
+        assert_eq!(invoice.currency.as_deref(), Some("EUR"));
+        assert_eq!(
+            invoice.line_items,
+            [LineItem {
+                description: "Solar panel".into(),
+                quantity: Some(4.0),
+                unit_price: Some(250.0),
+                amount: Some(1000.0),
+            }]
+        );
+        assert_eq!(
+            (
+                invoice.subtotal,
+                invoice.tax,
+                invoice.other_charges,
+                invoice.total
+            ),
+            (Some(1000.0), Some(194.75), Some(25.0), Some(1219.75))
+        );
+        assert!(invoice.validate().is_empty());
+
+        assert!(parse_einvoice("<Invoice/>").is_err());
+        assert!(parse_einvoice("not xml").is_err());
+    }
+
+    #[test]
+    fn attachment_names_are_recognised_and_made_safe() {
+        assert!(is_einvoice("factur-x.xml"));
+        assert!(is_einvoice("ZUGFeRD-invoice.xml"));
+        assert!(!is_einvoice("data.xlsx"));
+
+        let mut used = HashSet::new();
+        assert_eq!(safe_name("../../etc/passwd", 0, &mut used), "passwd");
+        assert_eq!(safe_name("passwd", 1, &mut used), "2-passwd");
+        assert_eq!(safe_name("", 2, &mut used), "attachment-3");
+    }
+}
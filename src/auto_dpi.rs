@@ -0,0 +1,277 @@
+//! Per-document DPI calibration.
+//!
+//! ## Why probe?
+//!
+//! Image tokens grow with pixel count, so on image-priced models DPI is the
+//! biggest lever on cost. But the right DPI depends on the document: a
+//! born-digital report reads as well at 96 DPI as at 220, while a scan with
+//! 8-point footnotes does not.
+//!
+//! With [`crate::ConversionConfigBuilder::auto_dpi`], [`crate::convert`]
+//! first converts one sample page at every candidate DPI, concurrently. The
+//! highest DPI's answer is the reference; each lower DPI is scored by how
+//! well its answer agrees with it, word for word (longest common
+//! subsequence, `2·common / (words_a + words_b)`). The lowest DPI that
+//! succeeds, is not truncated, and reaches [`AutoDpiConfig::min_agreement`]
+//! converts the document. The outcome, with each probe's score and tokens,
+//! is in [`crate::ConversionStats::dpi_calibration`].
+//!
+//! Calibration costs one extra page call per candidate. If the sample page
+//! is blank or the reference probe fails, the configured
+//! [`crate::ConversionConfig::dpi`] is kept.
+
+use crate::backend::VisionBackend;
+use crate::config::ConversionConfig;
+use crate::diff::{diff_lines, Op};
+use crate::error::Pdf2MdError;
+use crate::output::PageResult;
+use crate::pipeline::session::PdfSession;
+use crate::pipeline::{llm, postprocess, render};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Options for DPI calibration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoDpiConfig {
+    /// DPIs to try, 72–400, in any order. Default: 96, 150, 220.
+    pub candidates: Vec<u32>,
+    /// Word-level agreement with the highest-DPI answer that a lower DPI
+    /// must reach, 0.0–1.0. Default: 0.97.
+    pub min_agreement: f64,
+    /// 1-based page to probe. Default: `None`, the middle page of the
+    /// selection (first pages are often sparse covers).
+    pub sample_page: Option<usize>,
+}
+
+impl Default for AutoDpiConfig {
+    fn default() -> Self {
+        Self {
+            candidates: vec![96, 150, 220],
+            min_agreement: 0.97,
+            sample_page: None,
+        }
+    }
+}
+
+impl AutoDpiConfig {
+    pub(crate) fn validate(&self) -> Result<(), Pdf2MdError> {
+        if self.candidates.is_empty() {
+            return Err(Pdf2MdError::InvalidConfig(
+                "auto_dpi needs at least one candidate DPI".into(),
+            ));
+        }
+        if let Some(dpi) = self.candidates.iter().find(|d| !(72..=400).contains(*d)) {
+            return Err(Pdf2MdError::InvalidConfig(format!(
+                "auto_dpi candidates must be 72–400, got {dpi}"
+            )));
+        }
+        if !(0.0..=1.0).contains(&self.min_agreement) {
+            return Err(Pdf2MdError::InvalidConfig(format!(
+                "auto_dpi min_agreement must be 0.0–1.0, got {}",
+                self.min_agreement
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of calibration, in [`crate::ConversionStats::dpi_calibration`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DpiCalibration {
+    /// 1-based page that was probed.
+    pub sample_page: usize,
+    /// DPI the document was converted at.
+    pub chosen_dpi: u32,
+    /// One probe per candidate, lowest DPI first.
+    pub probes: Vec<DpiProbe>,
+}
+
+/// One candidate DPI's conversion of the sample page.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DpiProbe {
+    pub dpi: u32,
+    /// Agreement with the highest-DPI answer, 0.0–1.0; 0 when the probe
+    /// failed or was truncated.
+    pub agreement: f64,
+    pub input_tokens: usize,
+    pub image_tokens: usize,
+    pub output_tokens: usize,
+    /// Why the probe failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Probe the candidates on one page of `page_indices` (0-based) and pick a
+/// DPI. `None` when calibration could not decide; the caller keeps its DPI.
+pub(crate) async fn calibrate(
+    session: &PdfSession,
+    provider: &Arc<dyn VisionBackend>,
+    config: &ConversionConfig,
+    auto: &AutoDpiConfig,
+    page_indices: &[usize],
+) -> Option<DpiCalibration> {
+    let page_index = match auto.sample_page {
+        Some(page) => page.checked_sub(1)?,
+        None => *page_indices.get(page_indices.len() / 2)?,
+    };
+    let mut candidates = auto.candidates.clone();
+    candidates.sort_unstable();
+    candidates.dedup();
+    info!(
+        "Calibrating DPI on page {} at {:?}",
+        page_index + 1,
+        candidates
+    );
+
+    let results: Vec<Result<PageResult, Pdf2MdError>> =
+        futures::stream::iter(candidates.iter().copied())
+            .map(|dpi| probe(session, provider, config, page_index, dpi))
+            .buffered(config.concurrency)
+            .collect()
+            .await;
+    let results: Vec<PageResult> = match results.into_iter().collect() {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("DPI calibration skipped: {}", e);
+            return None;
+        }
+    };
+    if results.iter().any(|r| r.skipped_blank) {
+        warn!("DPI calibration skipped: page {} is blank", page_index + 1);
+        return None;
+    }
+    let reference = results.last()?;
+    if reference.error.is_some() || reference.markdown.trim().is_empty() {
+        warn!("DPI calibration skipped: the reference probe produced no text");
+        return None;
+    }
+
+    let probes: Vec<DpiProbe> = candidates
+        .iter()
+        .zip(&results)
+        .map(|(&dpi, r)| DpiProbe {
+            dpi,
+            agreement: if r.error.is_some() || r.truncated {
+                0.0
+            } else {
+                agreement(&r.markdown, &reference.markdown)
+            },
+            input_tokens: r.input_tokens,
+            image_tokens: r.image_tokens,
+            output_tokens: r.output_tokens,
+            error: r.error.as_ref().map(|e| e.to_string()),
+        })
+        .collect();
+    let chosen_dpi = pick_dpi(&probes, auto.min_agreement);
+    info!("DPI calibration chose {} DPI", chosen_dpi);
+    Some(DpiCalibration {
+        sample_page: page_index + 1,
+        chosen_dpi,
+        probes,
+    })
+}
+
+/// Render and convert one page at `dpi`, post-processed like any page.
+async fn probe(
+    session: &PdfSession,
+    provider: &Arc<dyn VisionBackend>,
+    config: &ConversionConfig,
+    page_index: usize,
+    dpi: u32,
+) -> Result<PageResult, Pdf2MdError> {
+    let config = ConversionConfig {
+        dpi,
+        ..config.clone()
+    };
+    let mut rx = render::spawn_lazy_render_encode_in(session, &config, &[page_index], 1).await?;
+    let page = rx
+        .recv()
+        .await
+        .ok_or_else(|| Pdf2MdError::RasterisationFailed {
+            page: page_index + 1,
+            detail: "the renderer stopped before the page".into(),
+        })?;
+    let mut result = llm::process_encoded_page(provider, &[], page, None, &config).await;
+    postprocess::postprocess_page(&mut result, &config);
+    Ok(result)
+}
+
+/// The lowest DPI whose probe reached `min_agreement`; the highest probed
+/// DPI when none did. `probes` is sorted by DPI and the last one is the
+/// reference.
+pub fn pick_dpi(probes: &[DpiProbe], min_agreement: f64) -> u32 {
+    probes
+        .iter()
+        .find(|p| p.error.is_none() && p.agreement >= min_agreement)
+        .or(probes.last())
+        .map_or(0, |p| p.dpi)
+}
+
+/// Word-level similarity of two answers, 0.0–1.0: twice the longest common
+/// word subsequence over the total word count.
+pub fn agreement(a: &str, b: &str) -> f64 {
+    let a: Vec<&str> = a.split_whitespace().collect();
+    let b: Vec<&str> = b.split_whitespace().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let common = diff_lines(&a, &b)
+        .iter()
+        .filter(|(op, _)| *op == Op::Equal)
+        .count();
+    2.0 * common as f64 / (a.len() + b.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(dpi: u32, agreement: f64) -> DpiProbe {
+        DpiProbe {
+            dpi,
+            agreement,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn agreement_scores_word_overlap() {
+        let reference = "# Results\n\nThe model improves recall by 4%.";
+        assert_eq!(agreement(reference, reference), 1.0);
+        let misread = "# Resu1ts\n\nThe model improves recall by 4%.";
+        assert!((agreement(misread, reference) - 14.0 / 16.0).abs() < 1e-9);
+        assert_eq!(agreement("", reference), 0.0);
+        assert_eq!(agreement("", ""), 1.0);
+    }
+
+    #[test]
+    fn lowest_dpi_meeting_the_bar_wins() {
+        let probes = [probe(96, 0.91), probe(150, 0.98), probe(220, 1.0)];
+        assert_eq!(pick_dpi(&probes, 0.97), 150);
+        assert_eq!(pick_dpi(&probes, 0.90), 96);
+        assert_eq!(pick_dpi(&probes, 0.999), 220);
+        let failed = DpiProbe {
+            error: Some("timeout".into()),
+            ..probe(96, 1.0)
+        };
+        assert_eq!(pick_dpi(&[failed, probe(220, 1.0)], 0.97), 220);
+    }
+
+    #[test]
+    fn config_is_validated() {
+        assert!(AutoDpiConfig::default().validate().is_ok());
+        let empty = AutoDpiConfig {
+            candidates: vec![],
+            ..Default::default()
+        };
+        assert!(empty.validate().is_err());
+        let sharp = AutoDpiConfig {
+            candidates: vec![150, 600],
+            ..Default::default()
+        };
+        assert!(sharp.validate().is_err());
+    }
+}
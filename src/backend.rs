@@ -0,0 +1,331 @@
+//! Pluggable vision backends: the one seam between the pipeline and a model.
+//!
+//! ## Why a crate-local trait?
+//!
+//! The page pipeline needs exactly one capability from a model: "here is a
+//! page image and a prompt, give me Markdown". Tying that to
+//! `edgequake_llm::LLMProvider` forces callers outside the edgequake
+//! ecosystem to implement a full chat/completion/embedding-shaped interface
+//! just to plug in their own HTTP client or an in-process model.
+//! [`VisionBackend`] is that single capability, expressed in crate-local
+//! types ([`PageImage`], [`PagePrompt`], [`DescribeOptions`],
+//! [`PageCompletion`]).
+//!
+//! Every edgequake-llm provider is still supported through
+//! [`LlmProviderBackend`], which is what the crate builds internally when no
+//! backend is injected.
+//!
+//! ## Errors and retries
+//!
+//! Retry, back-off, fallback, and truncation handling stay in
+//! [`crate::pipeline::llm`]; a backend makes a single attempt. Failures are
+//! classified from their message text by
+//! [`crate::pipeline::llm::classify_error`], so a backend should include the
+//! HTTP status (and any `Retry-After` hint) in
+//! [`Pdf2MdError::LlmApiError::message`], e.g. `"429 Too Many Requests,
+//! retry after 20s"`.
+//!
+//! # Example
+//! ```rust,no_run
+//! use async_trait::async_trait;
+//! use edgequake_pdf2md::backend::{
+//!     DescribeOptions, PageCompletion, PageImage, PagePrompt, VisionBackend,
+//! };
+//! use edgequake_pdf2md::{ConversionConfig, Pdf2MdError};
+//! use std::sync::Arc;
+//!
+//! struct LocalModel;
+//!
+//! #[async_trait]
+//! impl VisionBackend for LocalModel {
+//!     fn name(&self) -> &str { "local" }
+//!     fn model(&self) -> &str { "my-ocr-v2" }
+//!
+//!     async fn describe_page(
+//!         &self,
+//!         image: &PageImage,
+//!         prompt: &PagePrompt,
+//!         opts: &DescribeOptions,
+//!     ) -> Result<PageCompletion, Pdf2MdError> {
+//!         // Call your model with `image.data` (base64) and `prompt.system` here.
+//!         Ok(PageCompletion::new("# Page title\n\nBody text."))
+//!     }
+//! }
+//!
+//! let config = ConversionConfig::builder()
+//!     .backend(Arc::new(LocalModel))
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::error::Pdf2MdError;
+use crate::pipeline::encode;
+use async_trait::async_trait;
+use edgequake_llm::{ChatMessage, CompletionOptions, ImageData, LLMProvider};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A base64-encoded page (or tile) image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageImage {
+    /// Base64-encoded image bytes, without a `data:` prefix.
+    pub data: String,
+    /// MIME type, e.g. `"image/png"`.
+    pub mime_type: String,
+    /// Provider resolution hint (`"high"` / `"low"`); `None` lets the
+    /// backend decide.
+    pub detail: Option<String>,
+}
+
+impl PageImage {
+    /// Wrap base64 `data` of the given MIME type.
+    pub fn new(data: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            data: data.into(),
+            mime_type: mime_type.into(),
+            detail: None,
+        }
+    }
+
+    /// Set the resolution hint.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// A worked example for the model: a page image and the Markdown it should
+/// produce. See [`crate::ConversionConfig::few_shot_examples`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FewShotExample {
+    /// The example page.
+    pub image: PageImage,
+    /// The expected Markdown for `image`.
+    pub markdown: String,
+}
+
+impl FewShotExample {
+    /// Pair an encoded image with its Markdown.
+    pub fn new(image: PageImage, markdown: impl Into<String>) -> Self {
+        Self {
+            image,
+            markdown: markdown.into(),
+        }
+    }
+
+    /// Load an example from an image file (PNG or JPEG, sent as PNG) and a
+    /// Markdown file.
+    pub fn from_files(
+        image_path: impl AsRef<Path>,
+        markdown_path: impl AsRef<Path>,
+    ) -> Result<Self, Pdf2MdError> {
+        let (image_path, markdown_path) = (image_path.as_ref(), markdown_path.as_ref());
+        let invalid = |path: &Path, e: &dyn std::fmt::Display| {
+            Pdf2MdError::InvalidConfig(format!("few-shot example '{}': {e}", path.display()))
+        };
+        let img = image::open(image_path).map_err(|e| invalid(image_path, &e))?;
+        let image = encode::encode_page(&img).map_err(|e| invalid(image_path, &e))?;
+        let markdown =
+            std::fs::read_to_string(markdown_path).map_err(|e| invalid(markdown_path, &e))?;
+        Ok(Self::new(image, markdown.trim()))
+    }
+}
+
+/// The text that accompanies a page image.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PagePrompt {
+    /// Conversion instructions: the page prompt template, rendered for this
+    /// page (see [`crate::prompts`]).
+    pub system: String,
+    /// Markdown of the preceding page(s), already wrapped in its instruction,
+    /// when `maintain_format` is on.
+    pub context: Option<String>,
+    /// Example conversions to show before the page, in order; usually empty.
+    pub examples: Vec<FewShotExample>,
+    /// Per-image hint (tile position, column layout); often empty.
+    pub user_text: String,
+}
+
+/// Sampling options for one call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DescribeOptions {
+    /// Sampling temperature.
+    pub temperature: f32,
+    /// Output token budget. Raised on retry when a page is truncated.
+    pub max_tokens: usize,
+    /// Sampling seed, from [`crate::ConversionConfig::seed`]. Backends whose
+    /// API takes one should pass it on; [`LlmProviderBackend`] cannot.
+    pub seed: Option<u64>,
+}
+
+/// A backend's answer for one image.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageCompletion {
+    /// The model's Markdown, before post-processing.
+    pub markdown: String,
+    /// Prompt tokens billed (0 when unknown).
+    pub input_tokens: usize,
+    /// Completion tokens billed (0 when unknown).
+    pub output_tokens: usize,
+    /// Why generation stopped; `"length"` marks a truncated answer.
+    pub finish_reason: Option<String>,
+    /// Prompt tokens spent on images, when the provider reports them
+    /// separately (e.g. Gemini's per-modality usage). `None` lets the
+    /// pipeline estimate it from the prompt length.
+    pub image_tokens: Option<usize>,
+}
+
+impl PageCompletion {
+    /// A completed answer with unknown token usage.
+    pub fn new(markdown: impl Into<String>) -> Self {
+        Self {
+            markdown: markdown.into(),
+            finish_reason: Some("stop".to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// Set the billed token counts.
+    pub fn with_usage(mut self, input_tokens: usize, output_tokens: usize) -> Self {
+        self.input_tokens = input_tokens;
+        self.output_tokens = output_tokens;
+        self
+    }
+
+    /// Set the image share of the input tokens.
+    pub fn with_image_tokens(mut self, image_tokens: usize) -> Self {
+        self.image_tokens = Some(image_tokens);
+        self
+    }
+}
+
+/// Anything that can turn a page image into Markdown.
+///
+/// Implementations must be cheap to share (`Arc<dyn VisionBackend>`) and
+/// safe to call concurrently: up to `concurrency` pages are in flight.
+#[async_trait]
+pub trait VisionBackend: Send + Sync {
+    /// Short backend name used in logs and error messages (e.g. `"openai"`).
+    fn name(&self) -> &str;
+
+    /// Model identifier used in logs, spans, and metrics.
+    fn model(&self) -> &str;
+
+    /// `true` when page images never leave this machine (an in-process
+    /// model, or a server on a loopback address). Backends must opt in to
+    /// run under [`crate::ConversionConfig::local_only`]. Default: `false`.
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// Make one attempt at converting `image` to Markdown.
+    async fn describe_page(
+        &self,
+        image: &PageImage,
+        prompt: &PagePrompt,
+        opts: &DescribeOptions,
+    ) -> Result<PageCompletion, Pdf2MdError>;
+}
+
+/// [`VisionBackend`] adapter for any edgequake-llm provider.
+///
+/// Sends the system prompt, the optional context as a second system
+/// message, each few-shot example as a user turn (image) answered by an
+/// assistant turn (Markdown), and the image in a final user turn — the
+/// layout documented on [`crate::pipeline::llm::process_page`].
+#[derive(Clone)]
+pub struct LlmProviderBackend {
+    provider: Arc<dyn LLMProvider>,
+}
+
+impl LlmProviderBackend {
+    /// Wrap `provider`.
+    pub fn new(provider: Arc<dyn LLMProvider>) -> Self {
+        Self { provider }
+    }
+
+    /// The wrapped provider.
+    pub fn provider(&self) -> &Arc<dyn LLMProvider> {
+        &self.provider
+    }
+}
+
+impl From<Arc<dyn LLMProvider>> for LlmProviderBackend {
+    fn from(provider: Arc<dyn LLMProvider>) -> Self {
+        Self::new(provider)
+    }
+}
+
+#[async_trait]
+impl VisionBackend for LlmProviderBackend {
+    fn name(&self) -> &str {
+        self.provider.name()
+    }
+
+    fn model(&self) -> &str {
+        self.provider.model()
+    }
+
+    async fn describe_page(
+        &self,
+        image: &PageImage,
+        prompt: &PagePrompt,
+        opts: &DescribeOptions,
+    ) -> Result<PageCompletion, Pdf2MdError> {
+        let mut messages = vec![ChatMessage::system(prompt.system.as_str())];
+        if let Some(ref context) = prompt.context {
+            messages.push(ChatMessage::system(context.as_str()));
+        }
+        for example in &prompt.examples {
+            messages.push(ChatMessage::user_with_images(
+                "",
+                vec![image_data(&example.image)],
+            ));
+            messages.push(ChatMessage::assistant(example.markdown.as_str()));
+        }
+        messages.push(ChatMessage::user_with_images(
+            prompt.user_text.as_str(),
+            vec![image_data(image)],
+        ));
+
+        let options = completion_options(opts);
+        let response = self
+            .provider
+            .chat(&messages, Some(&options))
+            .await
+            .map_err(|e| Pdf2MdError::LlmApiError {
+                message: e.to_string(),
+            })?;
+        Ok(PageCompletion {
+            markdown: response.content,
+            input_tokens: response.prompt_tokens,
+            output_tokens: response.completion_tokens,
+            finish_reason: response.finish_reason,
+            image_tokens: None,
+        })
+    }
+}
+
+/// edgequake-llm options for `opts`, shared by page calls and the text
+/// passes. `CompletionOptions` has no seed field, so `opts.seed` stops here;
+/// the temperature of 0 that a seed implies still applies.
+pub(crate) fn completion_options(opts: &DescribeOptions) -> CompletionOptions {
+    CompletionOptions {
+        temperature: Some(opts.temperature),
+        max_tokens: Some(opts.max_tokens),
+        ..Default::default()
+    }
+}
+
+fn image_data(image: &PageImage) -> ImageData {
+    let data = ImageData::new(image.data.clone(), image.mime_type.as_str());
+    match image.detail {
+        Some(ref detail) => data.with_detail(detail.as_str()),
+        None => data,
+    }
+}
+
+/// Wrap an edgequake-llm provider as a shared [`VisionBackend`].
+pub fn from_provider(provider: Arc<dyn LLMProvider>) -> Arc<dyn VisionBackend> {
+    Arc::new(LlmProviderBackend::new(provider))
+}
@@ -4,15 +4,25 @@
 //! to `ConversionConfig` and prints results.
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser};
+use edgequake_pdf2md::formats::render;
+use edgequake_pdf2md::pipeline::render as pdf;
+use edgequake_pdf2md::split::{chunk_ranges, section_ranges};
 use edgequake_pdf2md::{
-    convert, convert_to_file, inspect, ConversionConfig, ConversionProgressCallback, FidelityTier,
-    PageSelection, PageSeparator, ProgressCallback,
+    convert, convert_to_file, convert_to_layout, convert_with_debug_report, diff_outputs, evaluate,
+    extract_invoice, extract_structured, generate_thumbnails, inspect, merge_pdfs, split_pdf,
+    AutoDpiConfig, ConfigFile, ConfigProfile, ContentHint, ConversionConfig, ConversionOutput,
+    ConversionProgressCallback, ConversionStats, CropRect, DocumentPreset, EnrichmentConfig,
+    EvalReport, ExtractionConfig, ExtractionScope, FewShotExample, FidelityTier, HeaderMap,
+    ImageLinkPolicy, MarginMask, MarkdownFlavor, OutputLayout, OutputTarget, PageClassifier,
+    PageFileNaming, PageKind, PageSelection, PageSeparator, PiiConfig, PolishConfig,
+    ProgressCallback, PunctuationStyle, Strictness, SummaryConfig, ThumbnailConfig, TocHandling,
+    WebhookConfig,
 };
 use indicatif::{ProgressBar, ProgressStyle};
 use std::collections::HashMap;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
@@ -36,6 +46,32 @@ fn cyan(s: &str) -> String {
     format!("\x1b[36m{s}\x1b[0m")
 }
 
+/// `" (N image)"` after the input token count, when any were image tokens.
+fn image_suffix(image_tokens: u64) -> String {
+    if image_tokens == 0 {
+        return String::new();
+    }
+    format!(" ({} image)", dim(&image_tokens.to_string()))
+}
+
+/// `"  ≈ $0.0123"` when the run's cost is known, else empty.
+fn cost_suffix(cost_usd: Option<f64>) -> String {
+    cost_usd
+        .map(|c| format!("  ≈ {}", dim(&format!("${c:.4}"))))
+        .unwrap_or_default()
+}
+
+/// The DPI `--auto-dpi` chose, if calibration ran.
+fn print_calibration(stats: &ConversionStats) {
+    if let Some(ref cal) = stats.dpi_calibration {
+        eprintln!(
+            "   calibrated at {} DPI on page {}",
+            bold(&cal.chosen_dpi.to_string()),
+            cal.sample_page
+        );
+    }
+}
+
 // ── CLI progress callback using indicatif ────────────────────────────────────
 
 /// Terminal progress callback: renders a live progress bar and per-page log
@@ -199,6 +235,14 @@ const AFTER_HELP: &str = r#"EXAMPLES:
   # Use a specific model
   pdf2md --model gpt-4.1 --provider openai document.pdf
 
+  # OpenRouter with fallback models when the primary is down
+  export OPENROUTER_API_KEY=your-key
+  pdf2md --provider openrouter --fallback-models google/gemini-2.0-flash-001 document.pdf
+
+  # Self-hosted vLLM or a gateway that needs its own auth header
+  pdf2md --base-url http://localhost:8000/v1 --model Qwen/Qwen2-VL-7B-Instruct doc.pdf
+  pdf2md --base-url https://llm.corp/v1 --header X-Gateway-Key=abc123 doc.pdf
+
   # Use Mistral (pixtral-12b-2409 auto-selected as the vision model)
   export MISTRAL_API_KEY=your-key
   pdf2md document.pdf
@@ -206,15 +250,29 @@ const AFTER_HELP: &str = r#"EXAMPLES:
   # Convert from URL
   pdf2md https://arxiv.org/pdf/1706.03762 -o attention.md
 
+  # Read the PDF from stdin (pipelines); `-o -` writes to stdout
+  curl -sL https://arxiv.org/pdf/1706.03762 | pdf2md - -o attention.md
+  cat report.pdf | pdf2md - -o - | less
+
   # Inspect PDF metadata (no API key needed)
   pdf2md --inspect-only document.pdf
 
+  # Write 256px page previews next to the Markdown
+  pdf2md document.pdf -o doc.md --thumbnails-dir thumbs/
+
   # Sequential mode for consistent formatting
   pdf2md --maintain-format --pages all book.pdf -o book.md
 
   # JSON output with metadata
   pdf2md --json --metadata document.pdf > output.json
 
+  # Named profile from ./pdf2md.toml or ~/.config/pdf2md/config.toml
+  pdf2md --profile archival thesis.pdf -o thesis.md
+
+  # HTTP API (build with --features server)
+  pdf2md --model gpt-4.1-mini serve --port 8080
+  curl -F file=@document.pdf http://localhost:8080/convert
+
 SUPPORTED PROVIDERS & MODELS:
   Provider     Model                  Input $/1M  Output $/1M  Vision
   ─────────    ─────────────────────  ──────────  ───────────  ──────
@@ -227,6 +285,7 @@ SUPPORTED PROVIDERS & MODELS:
   gemini       gemini-2.0-flash       $0.10       $0.40        ✓
   gemini       gemini-2.5-pro         $1.25       $10.00       ✓
   mistral      pixtral-12b-2409       $0.15       $0.15        ✓
+  openrouter   openai/gpt-4.1-nano    $0.10       $0.40        ✓
   ollama       llava, llama3.2-vision free        free         ✓
 
 COST ESTIMATE (50-page document @ 150 DPI):
@@ -243,6 +302,7 @@ ENVIRONMENT VARIABLES:
   ANTHROPIC_API_KEY       Anthropic API key
   GEMINI_API_KEY          Google Gemini API key
   MISTRAL_API_KEY         Mistral AI API key (uses pixtral-12b-2409 for vision)
+  OPENROUTER_API_KEY      OpenRouter API key (uses openai/gpt-4.1-nano for vision)
   EDGEQUAKE_LLM_PROVIDER  Override provider (openai, anthropic, gemini, mistral, ollama)
   EDGEQUAKE_MODEL         Override model ID
   PDFIUM_LIB_PATH         Path to an existing libpdfium — skips auto-download
@@ -267,14 +327,20 @@ SETUP:
 using Vision Language Models. Supports OpenAI, Anthropic, Google Gemini, Azure OpenAI, and \
 any OpenAI-compatible endpoint (Ollama, vLLM, LiteLLM, etc.).",
     arg_required_else_help = true,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true,
     color = clap::ColorChoice::Auto,
     after_long_help = AFTER_HELP
 )]
 struct Cli {
-    /// Local PDF file path or HTTP/HTTPS URL.
-    input: String,
+    #[command(subcommand)]
+    command: Option<Command>,
 
-    /// Write Markdown to this file instead of stdout.
+    /// Local PDF file path, HTTP/HTTPS URL, or `-` to read the PDF from stdin.
+    #[arg(required = true)]
+    input: Option<String>,
+
+    /// Write Markdown to this file instead of stdout (`-` means stdout).
     #[arg(short, long, env = "PDF2MD_OUTPUT")]
     output: Option<PathBuf>,
 
@@ -287,36 +353,195 @@ struct Cli {
     )]
     model: Option<String>,
 
-    /// LLM provider: openai, anthropic, gemini, ollama, azure.
+    /// LLM provider: openai, anthropic, gemini, ollama, azure, openrouter.
     #[arg(
         long,
         env = "EDGEQUAKE_PROVIDER",
         long_help = "LLM provider. Auto-detected from API key env vars if not set.\n\
-          Supported: openai, anthropic, gemini, azure, ollama, or any OpenAI-compatible URL."
+          Supported: openai, anthropic, gemini, azure, ollama, openrouter, or any OpenAI-compatible URL."
     )]
     provider: Option<String>,
 
+    /// Comma-separated fallback models tried when the primary model fails a page.
+    #[arg(
+        long,
+        env = "PDF2MD_FALLBACK_MODELS",
+        value_delimiter = ',',
+        long_help = "Ordered fallback models, tried in turn when a page exhausts its retries\n\
+          on the primary model. Uses the same --provider; designed for OpenRouter, e.g.\n\
+          --provider openrouter --fallback-models google/gemini-2.0-flash-001,anthropic/claude-3.5-haiku\n\
+          OpenRouter receives them in the request's `models` array and falls back itself."
+    )]
+    fallback_models: Vec<String>,
+
+    /// Base URL of an OpenAI-compatible endpoint (vLLM, LiteLLM, gateways).
+    #[arg(long, env = "PDF2MD_BASE_URL")]
+    base_url: Option<String>,
+
+    /// Extra HTTP header for VLM requests, as KEY=VALUE (repeatable).
+    #[arg(long = "header", value_name = "KEY=VALUE")]
+    headers: Vec<String>,
+
+    /// Refuse remote providers and URL inputs: only Ollama, LM Studio, or a localhost --base-url.
+    #[arg(long, env = "PDF2MD_LOCAL_ONLY")]
+    local_only: bool,
+
     /// Rendering DPI (72–400).
     #[arg(long, env = "PDF2MD_DPI", default_value_t = 150,
           value_parser = clap::value_parser!(u32).range(72..=400))]
     dpi: u32,
 
+    /// Probe a sample page at 96/150/220 DPI and convert at the lowest that
+    /// reads as well as 220 (replaces --dpi). Costs three extra page calls.
+    #[arg(long, env = "PDF2MD_AUTO_DPI")]
+    auto_dpi: bool,
+
     /// Number of concurrent VLM API calls.
     #[arg(short, long, env = "PDF2MD_CONCURRENCY", default_value_t = 10)]
     concurrency: usize,
 
+    /// Pages encoded while the next is rasterised (ignored with --maintain-format).
+    #[arg(long, env = "PDF2MD_RENDER_WORKERS", default_value_t = 1)]
+    render_workers: usize,
+
     /// Sequential mode: pass previous page as context for format continuity.
     #[arg(long, env = "PDF2MD_MAINTAIN_FORMAT")]
     maintain_format: bool,
 
-    /// Page selection: all, 5, 3-15, or 1,3,5,7.
+    /// With --maintain-format, number of previous pages passed as context.
+    #[arg(long, env = "PDF2MD_CONTEXT_WINDOW_PAGES", default_value_t = 1)]
+    context_window_pages: usize,
+
+    /// With --maintain-format, convert chapters (from bookmarks or headings) in parallel.
+    #[arg(long, env = "PDF2MD_PARALLEL_SECTIONS")]
+    parallel_sections: bool,
+
+    /// Split oversized or very dense pages into halves/quadrants (one VLM call per tile).
+    #[arg(long, env = "PDF2MD_TILE_DENSE_PAGES")]
+    tile_dense_pages: bool,
+
+    /// Detect multi-column pages from the text layer and add a reading-order hint.
+    #[arg(long, env = "PDF2MD_LAYOUT_HINTS")]
+    layout_hints: bool,
+
+    /// On tagged PDFs, hint the tagged headings and correct heading levels to match.
+    #[arg(long, env = "PDF2MD_STRUCTURE_TAGS")]
+    structure_tags: bool,
+
+    /// Describe each figure with an extra model call and add the alt text to
+    /// --json output.
+    #[arg(long, env = "PDF2MD_FIGURE_CAPTIONS")]
+    figure_captions: bool,
+
+    /// With --figure-captions, save figures to this directory and link them
+    /// from the Markdown with their alt text.
+    #[arg(long, env = "PDF2MD_FIGURES_DIR", value_name = "DIR")]
+    figures_dir: Option<PathBuf>,
+
+    /// Transcribe the data of bar, line, and pie charts into tables beneath
+    /// their captions.
+    #[arg(long, env = "PDF2MD_CHART_TABLES")]
+    chart_tables: bool,
+
+    /// Put a placeholder in place of sheet music, technical drawings, and
+    /// maps instead of a prose description; with --thumbnails-dir it links
+    /// the page thumbnail.
+    #[arg(long, env = "PDF2MD_NICHE_PLACEHOLDERS")]
+    niche_placeholders: bool,
+
+    /// Table-of-contents pages: keep, normalize (dot leaders become list
+    /// items), skip, or replace (with links to the document's headings).
+    #[arg(long, env = "PDF2MD_TOC", value_enum, default_value = "keep")]
+    toc: TocArg,
+
+    /// Send blank pages to the model instead of skipping them.
+    #[arg(long, env = "PDF2MD_KEEP_BLANK_PAGES")]
+    keep_blank_pages: bool,
+
+    /// Turn sideways pages (e.g. landscape tables) upright before sending them.
+    #[arg(long, env = "PDF2MD_AUTO_ROTATE")]
+    auto_rotate: bool,
+
+    /// Split two-page book spreads at the gutter and convert each half separately.
+    #[arg(long, env = "PDF2MD_SPLIT_SPREADS")]
+    split_spreads: bool,
+
+    /// Paint running headers and footers white before sending pages:
+    /// auto (detect them), TOP,BOTTOM in percent of the page height (8,5),
+    /// or both (auto,8,5).
+    #[arg(long, env = "PDF2MD_MASK_MARGINS", value_name = "SPEC", value_parser = parse_margin_mask)]
+    mask_margins: Option<MarginMask>,
+
+    /// Suppress diagonal "DRAFT" / "CONFIDENTIAL" watermarks before sending pages.
+    #[arg(long, env = "PDF2MD_REMOVE_WATERMARKS")]
+    remove_watermarks: bool,
+
+    /// Leave annotations (stamps, highlights, sticky notes) out of the rendered pages.
+    #[arg(long, env = "PDF2MD_NO_ANNOTATIONS")]
+    no_annotations: bool,
+
+    /// Leave filled form fields out of the rendered pages.
+    #[arg(long, env = "PDF2MD_NO_FORM_FIELDS")]
+    no_form_fields: bool,
+
+    /// Render without anti-aliasing of text, images, and shapes.
+    #[arg(long, env = "PDF2MD_NO_ANTI_ALIASING")]
+    no_anti_aliasing: bool,
+
+    /// Render text with sub-pixel (LCD) anti-aliasing.
+    #[arg(long, env = "PDF2MD_LCD_TEXT")]
+    lcd_text: bool,
+
+    /// Draw text with pdfium's own glyph rasteriser instead of the platform's.
+    #[arg(long, env = "PDF2MD_NO_NATIVE_TEXT")]
+    no_native_text: bool,
+
+    /// Send pages as 8-bit grayscale PNG: about half the upload for text pages.
+    #[arg(long, env = "PDF2MD_GRAYSCALE")]
+    grayscale: bool,
+
+    /// Send pages as 256-colour palette PNG; keeps colour, smaller than full RGBA.
+    #[arg(long, env = "PDF2MD_PNG_PALETTE")]
+    png_palette: bool,
+
+    /// Page selection: all, 5, 3-15, 1,3,5,7, odd, even, last10, 1-100:2, or all,!7.
+    /// Page kinds can be excluded too: !toc, !ads, !cover, !references, !blank.
     #[arg(long, env = "PDF2MD_PAGES", default_value = "all")]
     pages: String,
 
+    /// Classify pages before converting them: heuristic (text layer, free)
+    /// or model (one short call per page; needed to find adverts in scans).
+    /// Excluding a kind in --pages turns on the heuristic by default.
+    #[arg(long, env = "PDF2MD_CLASSIFY_PAGES", value_enum)]
+    classify_pages: Option<ClassifierArg>,
+
+    /// Convert only a region of a page, as [PAGE:]X,Y,W,H in fractions of the
+    /// page from its top-left corner (repeatable). Without PAGE, applies to
+    /// every page listed by --pages.
+    #[arg(long = "crop", value_name = "[PAGE:]X,Y,W,H")]
+    crops: Vec<String>,
+
     /// Output quality: tier1, tier2, tier3.
     #[arg(long, env = "PDF2MD_FIDELITY", value_enum, default_value = "tier2")]
     fidelity: FidelityArg,
 
+    /// What the pages are written in: printed, handwritten (literal
+    /// transcription at 300 DPI when --dpi is left at 150, unsure words
+    /// marked [?]), or auto (ask the model about a sample page first).
+    #[arg(
+        long,
+        env = "PDF2MD_CONTENT_HINT",
+        value_enum,
+        default_value = "printed"
+    )]
+    content_hint: ContentArg,
+
+    /// Document class preset: invoice, scientific-paper, legal-contract, or
+    /// slide-deck. Sets a tuned prompt, cleanup, and defaults for other
+    /// flags, which still override it.
+    #[arg(long, env = "PDF2MD_PRESET", value_name = "NAME", value_parser = parse_preset)]
+    preset: Option<DocumentPreset>,
+
     /// Page separator: none, hr, comment, or custom string.
     #[arg(long, env = "PDF2MD_SEPARATOR", default_value = "none")]
     separator: String,
@@ -329,26 +554,176 @@ struct Cli {
     #[arg(long, env = "PDF2MD_SYSTEM_PROMPT")]
     system_prompt: Option<PathBuf>,
 
+    /// Example page image sent before every page with its expected Markdown,
+    /// read from the file of the same name ending in `.md`. Repeatable.
+    #[arg(long = "few-shot", value_name = "IMAGE")]
+    few_shot: Vec<PathBuf>,
+
     /// Max LLM output tokens per page.
     #[arg(long, env = "PDF2MD_MAX_TOKENS", default_value_t = 4096)]
     max_tokens: usize,
 
+    /// Retry pages cut off at --max-tokens with a doubled budget, up to this many tokens.
+    #[arg(long, value_name = "TOKENS", env = "PDF2MD_MAX_TOKENS_RETRY_CAP")]
+    max_tokens_retry_cap: Option<usize>,
+
     /// LLM temperature (0.0–2.0).
     #[arg(long, env = "PDF2MD_TEMPERATURE", default_value_t = 0.1)]
     temperature: f32,
 
+    /// Sampling seed for repeatable runs; implies --temperature 0.
+    #[arg(long, value_name = "N", env = "PDF2MD_SEED")]
+    seed: Option<u64>,
+
     /// Retries per page on LLM failure.
     #[arg(long, env = "PDF2MD_MAX_RETRIES", default_value_t = 3)]
     max_retries: u32,
 
+    /// Stop after N pages in a row fail with auth/connection errors (0 = never).
+    #[arg(long, env = "PDF2MD_FAILURE_ABORT_THRESHOLD", default_value_t = 5)]
+    failure_abort_threshold: usize,
+
+    /// What a failed page does: best-effort (keep going), fail-fast (abort),
+    /// or all-or-nothing (finish, then fail if any page failed).
+    #[arg(
+        long,
+        env = "PDF2MD_STRICTNESS",
+        value_enum,
+        default_value = "best-effort"
+    )]
+    strictness: StrictnessArg,
+
     /// Prepend YAML front-matter with document metadata.
     #[arg(long, env = "PDF2MD_METADATA")]
     metadata: bool,
 
+    /// Markdown dialect: gfm, commonmark, pandoc, or mdx (Docusaurus/Next.js).
+    #[arg(long, env = "PDF2MD_FLAVOR", value_enum, default_value = "gfm")]
+    flavor: FlavorArg,
+
+    /// Image links to keep: strip-all (only absolute http(s) links),
+    /// keep-relative (also `figures/fig1.png`), or allow:PREFIX,... (also
+    /// links starting with a prefix, e.g. allow:assets/).
+    #[arg(
+        long,
+        env = "PDF2MD_IMAGE_LINKS",
+        value_name = "POLICY",
+        value_parser = parse_image_links,
+        default_value = "strip-all"
+    )]
+    image_links: ImageLinkPolicy,
+
+    /// Map quotes: keep, standard (rare variants such as „ → “), or ascii
+    /// (also “ ” ‘ ’ → " ').
+    #[arg(long, env = "PDF2MD_QUOTE_STYLE", value_enum, value_name = "STYLE")]
+    quote_style: Option<PunctuationArg>,
+
+    /// Map dashes: keep, standard (rare variants such as ― → —), or ascii
+    /// (also – → -, — → --).
+    #[arg(long, env = "PDF2MD_DASH_STYLE", value_enum, value_name = "STYLE")]
+    dash_style: Option<PunctuationArg>,
+
+    /// Generate an abstract with an extra LLM pass and prepend it under "## Summary".
+    #[arg(long, env = "PDF2MD_SUMMARY")]
+    summary: bool,
+
+    /// Extract keywords, entities, and document type (added to --metadata front-matter and --json).
+    #[arg(long, env = "PDF2MD_ENRICH")]
+    enrich: bool,
+
+    /// Fix OCR-style typos with an extra text-only call per page; answers
+    /// that restructure or rewrite the page are discarded.
+    #[arg(long, env = "PDF2MD_POLISH")]
+    polish: bool,
+
+    /// Cheaper text model for --polish (default: --model). Needs --provider.
+    #[arg(
+        long,
+        env = "PDF2MD_POLISH_MODEL",
+        value_name = "MODEL",
+        requires = "polish"
+    )]
+    polish_model: Option<String>,
+
+    /// Parse the bibliography into --json `references` and turn citation
+    /// markers such as [3] into footnote links.
+    #[arg(long, env = "PDF2MD_REFERENCES")]
+    references: bool,
+
+    /// Turn printed footnotes (² or <sup>2</sup> plus a note at the page
+    /// bottom) into `[^2]` footnotes collected at the end of the document.
+    #[arg(long, env = "PDF2MD_FOOTNOTES")]
+    footnotes: bool,
+
+    /// Save files embedded in the PDF (source spreadsheets, invoice XML) to
+    /// this directory. They are listed in --json metadata either way.
+    #[arg(long, env = "PDF2MD_ATTACHMENTS_DIR", value_name = "DIR")]
+    attachments_dir: Option<PathBuf>,
+
+    /// Parse an embedded ZUGFeRD / Factur-X invoice XML into the --json
+    /// attachment list.
+    #[arg(long, env = "PDF2MD_EINVOICE")]
+    einvoice: bool,
+
+    /// Mask emails, phone numbers, SSNs, and IBANs in the output.
+    #[arg(long, env = "PDF2MD_PII")]
+    pii: bool,
+
+    /// With --pii, also find and mask person names with an extra LLM pass.
+    #[arg(long, env = "PDF2MD_PII_NAMES", requires = "pii")]
+    pii_names: bool,
+
     /// Output structured JSON (ConversionOutput) instead of Markdown.
     #[arg(long, env = "PDF2MD_JSON")]
     json: bool,
 
+    /// Document format: markdown, asciidoc (Antora), rst (Sphinx), or
+    /// pandoc-json (Pandoc's JSON AST). Other formats are translated from
+    /// GFM, so --flavor is ignored.
+    #[arg(
+        long,
+        value_enum,
+        env = "PDF2MD_TO",
+        default_value = "markdown",
+        conflicts_with_all = ["json", "obsidian_vault", "per_page_output"]
+    )]
+    to: TargetArg,
+
+    /// Write an Obsidian vault into DIR instead: one note per section, an
+    /// index note, wiki-links, and tagged YAML front-matter.
+    #[arg(
+        long,
+        value_name = "DIR",
+        env = "PDF2MD_OBSIDIAN_VAULT",
+        conflicts_with_all = ["output", "json"]
+    )]
+    obsidian_vault: Option<PathBuf>,
+
+    /// Write each page as DIR/page-0001.png plus a page-0001.md sidecar
+    /// instead of one combined document.
+    #[arg(
+        long,
+        value_name = "DIR",
+        env = "PDF2MD_PER_PAGE_OUTPUT",
+        conflicts_with_all = ["output", "json", "obsidian_vault"]
+    )]
+    per_page_output: Option<PathBuf>,
+
+    /// File names for --per-page-output: page (page-0001.*) or stem
+    /// (<input stem>-0001.*).
+    #[arg(
+        long,
+        value_enum,
+        env = "PDF2MD_PER_PAGE_NAMING",
+        default_value = "page",
+        requires = "per_page_output"
+    )]
+    per_page_naming: PageNamingArg,
+
+    /// With --json, include each page's raw model output before cleanup (raw_markdown).
+    #[arg(long, env = "PDF2MD_KEEP_RAW")]
+    keep_raw: bool,
+
     /// Disable progress bar.
     #[arg(long, env = "PDF2MD_NO_PROGRESS")]
     no_progress: bool,
@@ -357,6 +732,39 @@ struct Cli {
     #[arg(long)]
     inspect_only: bool,
 
+    /// Also write an HTML report to PATH: each page's image beside its
+    /// Markdown, with timings, tokens, retries, and post-processing diffs.
+    #[arg(
+        long,
+        value_name = "PATH",
+        env = "PDF2MD_DEBUG_REPORT",
+        conflicts_with_all = ["obsidian_vault", "per_page_output"]
+    )]
+    debug_report: Option<PathBuf>,
+
+    /// Also write a small JPEG preview of each converted page to DIR
+    /// (page-0001.jpg, page-0002.jpg, …).
+    #[arg(long, value_name = "DIR", env = "PDF2MD_THUMBNAILS_DIR")]
+    thumbnails_dir: Option<PathBuf>,
+
+    /// Longest edge of each thumbnail in pixels.
+    #[arg(long, env = "PDF2MD_THUMBNAIL_SIZE", default_value_t = 256)]
+    thumbnail_size: u32,
+
+    /// Append a JSONL record of run events (page start/complete/error, tokens) to FILE.
+    #[arg(long, value_name = "FILE", env = "PDF2MD_EVENT_LOG")]
+    event_log: Option<PathBuf>,
+
+    /// Write each page's prompt, image hash, and model response (API keys
+    /// redacted) to DIR/page-NNNN.jsonl for audit.
+    #[arg(long, value_name = "DIR", env = "PDF2MD_TRANSCRIPT_DIR")]
+    transcript_dir: Option<PathBuf>,
+
+    /// POST a JSON notification (stats, metadata) to URL when the conversion
+    /// finishes or fails.
+    #[arg(long, value_name = "URL", env = "PDF2MD_WEBHOOK")]
+    webhook: Option<String>,
+
     /// Enable DEBUG-level tracing logs.
     #[arg(short, long, env = "PDF2MD_VERBOSE")]
     verbose: bool,
@@ -365,6 +773,14 @@ struct Cli {
     #[arg(short, long, env = "PDF2MD_QUIET")]
     quiet: bool,
 
+    /// Refuse documents selecting more than this many pages.
+    #[arg(long, value_name = "N", env = "PDF2MD_MAX_PAGES")]
+    max_pages: Option<usize>,
+
+    /// Refuse input files larger than this many bytes.
+    #[arg(long, value_name = "BYTES", env = "PDF2MD_MAX_FILE_SIZE")]
+    max_file_size: Option<u64>,
+
     /// HTTP download timeout in seconds.
     #[arg(long, env = "PDF2MD_DOWNLOAD_TIMEOUT", default_value_t = 120)]
     download_timeout: u64,
@@ -372,6 +788,172 @@ struct Cli {
     /// Per-page LLM call timeout in seconds.
     #[arg(long, env = "PDF2MD_API_TIMEOUT", default_value_t = 60)]
     api_timeout: u64,
+
+    /// Config file with defaults and [profile.NAME] tables
+    /// (default: ./pdf2md.toml, then ~/.config/pdf2md/config.toml).
+    #[arg(long, value_name = "FILE", env = "PDF2MD_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Profile from the config file, laid over its top-level settings.
+    #[arg(long, value_name = "NAME", env = "PDF2MD_PROFILE")]
+    profile: Option<String>,
+
+    /// Ignore pdf2md.toml / ~/.config/pdf2md/config.toml.
+    #[arg(long)]
+    no_config: bool,
+
+    /// Settings resolved from the config file (filled in after parsing).
+    #[arg(skip)]
+    file_settings: Option<ConfigProfile>,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Compare two versions of a document: per-page unified diffs and the
+    /// sections added, removed, or changed.
+    ///
+    /// Each side is a PDF (path or URL), converted with the global flags
+    /// given before `diff`, or a `.json` file saved earlier with --json.
+    /// With --json, prints the diff as JSON.
+    Diff {
+        /// The earlier version.
+        old: String,
+        /// The later version.
+        new: String,
+    },
+    /// Score conversions against hand-checked Markdown: character and word
+    /// error rates, table cell accuracy, and heading-structure similarity.
+    ///
+    /// Each PDF is converted with the global flags given before `eval` and
+    /// compared with `<TRUTH>/<stem>.md`. With --json, prints the reports
+    /// as JSON.
+    Eval {
+        /// Directory of ground-truth Markdown files named after the PDFs.
+        #[arg(long, value_name = "DIR")]
+        truth: PathBuf,
+        /// PDFs to convert and score.
+        #[arg(required = true)]
+        inputs: Vec<String>,
+    },
+    /// Convert the same sample pages with several models and compare cost,
+    /// latency, and output length side by side.
+    ///
+    /// Global flags given before `bench` apply to every run. A model may be
+    /// written `provider/model`; otherwise the provider is inferred from the
+    /// name (gpt-* → openai, claude-* → anthropic, gemini-* → gemini,
+    /// pixtral-*/mistral-* → mistral) or taken from --provider.
+    Bench {
+        /// PDF file path or URL.
+        input: String,
+        /// Comma-separated models, e.g. gpt-4.1-nano,gemini-2.0-flash,claude-haiku-4-5.
+        #[arg(long, value_delimiter = ',', required = true)]
+        models: Vec<String>,
+        /// Pages to sample when --pages is not given: the first N.
+        #[arg(long, value_name = "N", default_value_t = 3)]
+        sample_pages: usize,
+        /// Ground-truth Markdown for the sampled pages; adds eval scores.
+        #[arg(long, value_name = "FILE")]
+        truth: Option<PathBuf>,
+    },
+    /// Fill a JSON Schema with data read from the document and print the
+    /// result as JSON.
+    ///
+    /// By default the whole document is converted and the schema is filled
+    /// once from its Markdown; with --per-page each page image is read into
+    /// its own instance. Answers that break the schema are sent back to the
+    /// model for repair. --invoice uses the built-in invoice schema and also
+    /// checks that the amounts add up. Global flags given before `extract`
+    /// apply.
+    Extract {
+        /// PDF file path or URL.
+        input: String,
+        /// JSON Schema file describing the data to extract.
+        #[arg(long, value_name = "FILE", required_unless_present = "invoice")]
+        schema: Option<PathBuf>,
+        /// Extract vendor, dates, line items, and totals with the built-in
+        /// invoice schema.
+        #[arg(long, conflicts_with_all = ["schema", "per_page"])]
+        invoice: bool,
+        /// Fill the schema once per page instead of once per document.
+        #[arg(long)]
+        per_page: bool,
+        /// Repair requests allowed after an answer breaks the schema.
+        #[arg(long, value_name = "N", default_value_t = 2)]
+        repair_attempts: u32,
+    },
+    /// Split a PDF into smaller PDFs before converting, by page ranges,
+    /// fixed-size parts, or chapters. Prints the written paths.
+    ///
+    /// Uses the global --password given before `split`.
+    Split {
+        /// PDF file to split.
+        input: PathBuf,
+        /// Directory for the parts, named `<stem>-<start>-<end>.pdf`.
+        #[arg(long, value_name = "DIR", default_value = ".")]
+        out_dir: PathBuf,
+        /// Page ranges, one part each: e.g. 1-40,41-80,81-120.
+        #[arg(long, value_name = "RANGES", required_unless_present_any = ["every", "chapters"],
+              conflicts_with_all = ["every", "chapters"])]
+        ranges: Option<String>,
+        /// Parts of N pages each.
+        #[arg(long, value_name = "N", conflicts_with = "chapters")]
+        every: Option<usize>,
+        /// One part per chapter, from the outline or "Chapter N" headings.
+        #[arg(long)]
+        chapters: bool,
+    },
+    /// Concatenate PDFs, in the order given, into one file.
+    Merge {
+        /// PDF files to merge.
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        /// Merged PDF to write.
+        #[arg(short, long, value_name = "FILE")]
+        output: PathBuf,
+    },
+    /// Install the PDFium engine into the local cache and print its path.
+    ///
+    /// Downloads it (from PDFIUM_AUTO_MIRROR_URL when set), or with --from
+    /// unpacks a copy of the release archive, for machines that cannot
+    /// reach GitHub.
+    SetupPdfium {
+        /// Platform archive from bblanchon/pdfium-binaries, e.g. pdfium-linux-x64.tgz.
+        #[arg(long, value_name = "ARCHIVE")]
+        from: Option<PathBuf>,
+        /// Expected SHA-256 of the --from archive; required unless its
+        /// digest is already known (PDFIUM_AUTO_SHA256).
+        #[arg(long, value_name = "HEX", requires = "from")]
+        sha256: Option<String>,
+    },
+    /// Serve the HTTP API: POST /convert (multipart `file` or `url`), GET /health,
+    /// and GET /metrics when built with the `prometheus` feature.
+    ///
+    /// Global conversion flags (--model, --provider, --concurrency, …) given
+    /// before `serve` become the defaults for every request.
+    #[cfg(feature = "server")]
+    Serve {
+        /// Port to listen on.
+        #[arg(long, env = "PDF2MD_PORT", default_value_t = 8080)]
+        port: u16,
+
+        /// Address to bind. Use 0.0.0.0 to accept remote connections.
+        #[arg(long, env = "PDF2MD_HOST", default_value = "127.0.0.1")]
+        host: std::net::IpAddr,
+
+        /// Maximum upload size in MiB; also caps PDFs fetched for `url` input.
+        #[arg(long, default_value_t = 100)]
+        max_upload_mb: usize,
+
+        /// Conversions that may run at once; further requests get 503.
+        #[arg(long, default_value_t = edgequake_pdf2md::server::DEFAULT_MAX_CONCURRENT)]
+        max_concurrent: usize,
+
+        /// Accept the `url` form field and fetch the PDF server-side. Hosts
+        /// that resolve to loopback, private or link-local addresses are
+        /// refused even then.
+        #[arg(long)]
+        allow_url_input: bool,
+    },
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -379,6 +961,8 @@ enum FidelityArg {
     Tier1,
     Tier2,
     Tier3,
+    /// Experimental: tier3 plus flowcharts and org charts as Mermaid.
+    Tier4,
 }
 
 impl From<FidelityArg> for FidelityTier {
@@ -387,13 +971,160 @@ impl From<FidelityArg> for FidelityTier {
             FidelityArg::Tier1 => FidelityTier::Tier1,
             FidelityArg::Tier2 => FidelityTier::Tier2,
             FidelityArg::Tier3 => FidelityTier::Tier3,
+            FidelityArg::Tier4 => FidelityTier::Tier4,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum FlavorArg {
+    Gfm,
+    Commonmark,
+    Pandoc,
+    Mdx,
+}
+
+impl From<FlavorArg> for MarkdownFlavor {
+    fn from(v: FlavorArg) -> Self {
+        match v {
+            FlavorArg::Gfm => MarkdownFlavor::Gfm,
+            FlavorArg::Commonmark => MarkdownFlavor::CommonMark,
+            FlavorArg::Pandoc => MarkdownFlavor::Pandoc,
+            FlavorArg::Mdx => MarkdownFlavor::Mdx,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum PunctuationArg {
+    Keep,
+    Standard,
+    Ascii,
+}
+
+impl From<PunctuationArg> for PunctuationStyle {
+    fn from(v: PunctuationArg) -> Self {
+        match v {
+            PunctuationArg::Keep => PunctuationStyle::Keep,
+            PunctuationArg::Standard => PunctuationStyle::Standard,
+            PunctuationArg::Ascii => PunctuationStyle::Ascii,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum TargetArg {
+    Markdown,
+    Asciidoc,
+    Rst,
+    PandocJson,
+}
+
+impl From<TargetArg> for OutputTarget {
+    fn from(v: TargetArg) -> Self {
+        match v {
+            TargetArg::Markdown => OutputTarget::Markdown,
+            TargetArg::Asciidoc => OutputTarget::AsciiDoc,
+            TargetArg::Rst => OutputTarget::ReStructuredText,
+            TargetArg::PandocJson => OutputTarget::PandocJson,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum PageNamingArg {
+    Page,
+    Stem,
+}
+
+impl From<PageNamingArg> for PageFileNaming {
+    fn from(v: PageNamingArg) -> Self {
+        match v {
+            PageNamingArg::Page => PageFileNaming::PageNumber,
+            PageNamingArg::Stem => PageFileNaming::InputStem,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum TocArg {
+    Keep,
+    Normalize,
+    Skip,
+    Replace,
+}
+
+impl From<TocArg> for TocHandling {
+    fn from(v: TocArg) -> Self {
+        match v {
+            TocArg::Keep => TocHandling::Keep,
+            TocArg::Normalize => TocHandling::Normalize,
+            TocArg::Skip => TocHandling::Skip,
+            TocArg::Replace => TocHandling::Replace,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum ClassifierArg {
+    Heuristic,
+    Model,
+}
+
+impl From<ClassifierArg> for PageClassifier {
+    fn from(v: ClassifierArg) -> Self {
+        match v {
+            ClassifierArg::Heuristic => PageClassifier::Heuristic,
+            ClassifierArg::Model => PageClassifier::Model,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum ContentArg {
+    Printed,
+    Handwritten,
+    Auto,
+}
+
+impl From<ContentArg> for ContentHint {
+    fn from(v: ContentArg) -> Self {
+        match v {
+            ContentArg::Printed => ContentHint::Printed,
+            ContentArg::Handwritten => ContentHint::Handwritten,
+            ContentArg::Auto => ContentHint::Auto,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum StrictnessArg {
+    BestEffort,
+    FailFast,
+    AllOrNothing,
+}
+
+impl From<StrictnessArg> for Strictness {
+    fn from(v: StrictnessArg) -> Self {
+        match v {
+            StrictnessArg::BestEffort => Strictness::BestEffort,
+            StrictnessArg::FailFast => Strictness::FailFast,
+            StrictnessArg::AllOrNothing => Strictness::AllOrNothing,
         }
     }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    // Config-file values become clap defaults, so flags and env vars still
+    // take precedence over the file.
+    let file_settings = load_file_settings()?;
+    let mut command = Cli::command();
+    if let Some(ref settings) = layer_preset(file_settings.clone())? {
+        command = apply_file_defaults(command, settings);
+    }
+    let mut cli = Cli::from_arg_matches(&command.get_matches()).unwrap_or_else(|e| e.exit());
+    cli.file_settings = file_settings;
 
     // ── Logging setup ────────────────────────────────────────────────────
     // Suppress INFO-level library logs when the progress bar is active;
@@ -416,6 +1147,21 @@ async fn main() -> Result<()> {
         .with_writer(io::stderr)
         .init();
 
+    // ── Setup mode ───────────────────────────────────────────────────────
+    // Before the engine check below, which would otherwise try to download.
+    if let Some(Command::SetupPdfium { from, sha256 }) = &cli.command {
+        let path = match from {
+            Some(archive) => pdfium_auto::install_from_archive(archive, sha256.as_deref()),
+            #[cfg(feature = "bundled")]
+            None => tokio::task::block_in_place(pdfium_auto::ensure_pdfium_bundled),
+            #[cfg(not(feature = "bundled"))]
+            None => pdfium_auto::ensure_pdfium_library_async(None).await,
+        }
+        .context("PDFium setup failed")?;
+        println!("{}", path.display());
+        return Ok(());
+    }
+
     // ── Ensure PDFium engine is available ───────────────────────────────────
     // When compiled with `--features bundled`, the pdfium shared library was
     // embedded at compile time.  We just extract it (if needed) and continue.
@@ -447,35 +1193,200 @@ async fn main() -> Result<()> {
             dl_bar.enable_steady_tick(Duration::from_millis(80));
 
             let bar = dl_bar.clone();
-            // block_in_place keeps the reference lifetime valid (no 'static
-            // requirement) while still offloading the blocking download from
-            // the async executor's hot path.
-            tokio::task::block_in_place(|| {
-                pdfium_auto::ensure_pdfium_library(Some(&|downloaded, total| {
-                    if let Some(t) = total {
-                        if bar.length().unwrap_or(0) != t {
-                            bar.set_length(t);
-                            bar.set_prefix("PDF engine");
-                        }
-                        bar.set_position(downloaded);
-                    } else {
-                        bar.set_position(downloaded);
+            pdfium_auto::ensure_pdfium_library_async(Some(&move |downloaded, total| {
+                if let Some(t) = total {
+                    if bar.length().unwrap_or(0) != t {
+                        bar.set_length(t);
+                        bar.set_prefix("PDF engine");
                     }
-                }))
-            })
+                    bar.set_position(downloaded);
+                } else {
+                    bar.set_position(downloaded);
+                }
+            }))
+            .await
             .context("Failed to download PDFium engine")?;
 
             dl_bar.finish_with_message("ready ✓");
         } else {
             // Quiet mode — download silently; errors still propagate.
-            tokio::task::block_in_place(|| pdfium_auto::ensure_pdfium_library(None))
+            pdfium_auto::ensure_pdfium_library_async(None)
+                .await
                 .context("Failed to download PDFium engine")?;
         }
     }
 
+    // ── Serve mode ───────────────────────────────────────────────────────
+    #[cfg(feature = "server")]
+    if let Some(Command::Serve {
+        port,
+        host,
+        max_upload_mb,
+        max_concurrent,
+        allow_url_input,
+    }) = &cli.command
+    {
+        let config = build_config(&cli, None).await?;
+        let addr = std::net::SocketAddr::new(*host, *port);
+        eprintln!(
+            "{} listening on {}",
+            green("✔"),
+            bold(&format!("http://{addr}"))
+        );
+        let options = edgequake_pdf2md::server::ServerOptions {
+            max_upload_bytes: max_upload_mb * 1024 * 1024,
+            max_concurrent: *max_concurrent,
+            allow_url_input: *allow_url_input,
+        };
+        edgequake_pdf2md::server::serve(addr, config, options)
+            .await
+            .context("Server failed")?;
+        return Ok(());
+    }
+
+    // ── Diff mode ────────────────────────────────────────────────────────
+    if let Some(Command::Diff { old, new }) = &cli.command {
+        let config = build_config(&cli, None).await?;
+        let old_output = load_or_convert(old, &config).await?;
+        let new_output = load_or_convert(new, &config).await?;
+        let diff = diff_outputs(&old_output, &new_output);
+        if cli.json {
+            let json = serde_json::to_string_pretty(&diff).context("Failed to serialise diff")?;
+            println!("{json}");
+        } else {
+            print!("{}", diff.render());
+        }
+        return Ok(());
+    }
+
+    // ── Bench mode ───────────────────────────────────────────────────────
+    if let Some(Command::Bench {
+        input,
+        models,
+        sample_pages,
+        truth,
+    }) = &cli.command
+    {
+        let mut config = build_config(&cli, None).await?;
+        if cli.pages == "all" {
+            config.pages = PageSelection::Range(1, (*sample_pages).max(1));
+        }
+        let truth = truth
+            .as_ref()
+            .map(|path| {
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))
+            })
+            .transpose()?;
+        return run_bench(input, models, &config, truth.as_deref(), cli.json).await;
+    }
+
+    // ── Eval mode ────────────────────────────────────────────────────────
+    if let Some(Command::Eval { truth, inputs }) = &cli.command {
+        let config = build_config(&cli, None).await?;
+        return run_eval(truth, inputs, &config, cli.json).await;
+    }
+
+    // ── Split / merge mode ───────────────────────────────────────────────
+    if let Some(Command::Split {
+        input,
+        out_dir,
+        ranges,
+        every,
+        chapters,
+    }) = &cli.command
+    {
+        let password = cli.password.as_deref();
+        let ranges = match (ranges, every) {
+            (Some(spec), _) => parse_ranges(spec)?,
+            (None, Some(n)) => {
+                let metadata = pdf::extract_metadata(input, password).await?;
+                chunk_ranges(metadata.page_count, *n)
+            }
+            (None, None) => {
+                debug_assert!(*chapters, "clap requires --ranges, --every, or --chapters");
+                let metadata = pdf::extract_metadata(input, password).await?;
+                let starts = pdf::detect_section_starts(input, password).await?;
+                if starts.is_empty() {
+                    anyhow::bail!("No chapters found in {}", input.display());
+                }
+                section_ranges(&starts, metadata.page_count)
+            }
+        };
+        let parts = split_pdf(input, &ranges, out_dir, password)
+            .await
+            .context("Split failed")?;
+        for part in parts {
+            println!("{}", part.display());
+        }
+        return Ok(());
+    }
+    if let Some(Command::Merge { inputs, output }) = &cli.command {
+        let merged = merge_pdfs(inputs, output).await.context("Merge failed")?;
+        println!("{}", merged.display());
+        return Ok(());
+    }
+
+    // ── Extract mode ─────────────────────────────────────────────────────
+    if let Some(Command::Extract {
+        input,
+        schema,
+        invoice,
+        per_page,
+        repair_attempts,
+    }) = &cli.command
+    {
+        let mut config = build_config(&cli, None).await?;
+        config.extraction = ExtractionConfig {
+            scope: if *per_page {
+                ExtractionScope::Page
+            } else {
+                ExtractionScope::Document
+            },
+            repair_attempts: *repair_attempts,
+            ..ExtractionConfig::default()
+        };
+        let data = match schema {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let schema: serde_json::Value = serde_json::from_str(&text)
+                    .with_context(|| format!("{} is not valid JSON", path.display()))?;
+                extract_structured(input, &schema, &config)
+                    .await
+                    .context("Extraction failed")?
+            }
+            None => {
+                debug_assert!(*invoice, "clap requires --schema or --invoice");
+                let invoice = extract_invoice(input, &config)
+                    .await
+                    .context("Invoice extraction failed")?;
+                serde_json::to_value(invoice).context("Failed to serialise invoice")?
+            }
+        };
+        let json = serde_json::to_string_pretty(&data).context("Failed to serialise data")?;
+        println!("{json}");
+        return Ok(());
+    }
+
+    // clap enforces `input` whenever no subcommand is given.
+    let input = cli.input.as_deref().context("missing INPUT")?;
+
+    // `-` spools stdin into a tempfile; it is deleted when `stdin_pdf` drops.
+    let stdin_pdf = if input == "-" {
+        Some(read_stdin_pdf()?)
+    } else {
+        None
+    };
+    let stdin_path = stdin_pdf
+        .as_ref()
+        .map(|tmp| tmp.path().to_string_lossy().into_owned());
+    let input = stdin_path.as_deref().unwrap_or(input);
+    let output_file = cli.output.as_ref().filter(|p| p.as_os_str() != "-");
+
     // ── Inspect-only mode ────────────────────────────────────────────────
     if cli.inspect_only {
-        let meta = inspect(&cli.input).await.context("Failed to inspect PDF")?;
+        let meta = inspect(input).await.context("Failed to inspect PDF")?;
 
         if cli.json {
             println!(
@@ -483,7 +1394,14 @@ async fn main() -> Result<()> {
                 serde_json::to_string_pretty(&meta).context("Failed to serialize metadata")?
             );
         } else {
-            println!("File:         {}", cli.input);
+            println!(
+                "File:         {}",
+                if stdin_pdf.is_some() {
+                    "<stdin>"
+                } else {
+                    input
+                }
+            );
             if let Some(ref t) = meta.title {
                 println!("Title:        {}", t);
             }
@@ -496,12 +1414,58 @@ async fn main() -> Result<()> {
             println!("Pages:        {}", meta.page_count);
             println!("PDF Version:  {}", meta.pdf_version);
             println!("Encrypted:    {}", meta.is_encrypted);
+            println!("Tagged:       {}", meta.conformance.tagged);
+            if let Some(ref level) = meta.conformance.pdfa {
+                println!("PDF/A:        {}", level);
+            }
+            if let Some(part) = meta.conformance.pdfua {
+                println!("PDF/UA:       {}", part);
+            }
             if let Some(ref p) = meta.producer {
                 println!("Producer:     {}", p);
             }
             if let Some(ref c) = meta.creator {
                 println!("Creator:      {}", c);
             }
+            for sig in &meta.signatures {
+                let mut notes = vec![if sig.covers_whole_document {
+                    "covers whole document".to_string()
+                } else {
+                    "file changed after signing".to_string()
+                }];
+                if let Some(level) = sig.certification_level {
+                    notes.insert(0, format!("certification level {level}"));
+                }
+                if let Some(ref t) = sig.signing_time {
+                    notes.insert(0, t.clone());
+                }
+                println!(
+                    "Signed by:    {} ({})",
+                    sig.signer.as_deref().unwrap_or("unknown signer"),
+                    notes.join(", ")
+                );
+            }
+            if !meta.pages.is_empty() {
+                let scanned = meta.pages.iter().filter(|p| !p.has_text_layer).count();
+                println!(
+                    "Text layer:   {}/{} pages",
+                    meta.pages.len() - scanned,
+                    meta.pages.len()
+                );
+                println!();
+                println!("  Page   Width×Height (pt)  Rot  Text  Images");
+                for p in &meta.pages {
+                    println!(
+                        "  {:>4}   {:>7.1}×{:<8.1}   {:>3}  {:<4}  {:>6}",
+                        p.page_num,
+                        p.width_pt,
+                        p.height_pt,
+                        p.rotation,
+                        if p.has_text_layer { "yes" } else { "no" },
+                        p.image_count,
+                    );
+                }
+            }
         }
         return Ok(());
     }
@@ -521,11 +1485,65 @@ async fn main() -> Result<()> {
     let config = build_config(&cli, progress_cb).await?;
 
     // ── Run conversion ───────────────────────────────────────────────────
-    if let Some(ref output_path) = cli.output {
-        let stats = convert_to_file(&cli.input, output_path, &config)
+    let layout = if let Some(ref dir) = cli.obsidian_vault {
+        Some((OutputLayout::ObsidianVault { dir: dir.clone() }, dir))
+    } else {
+        cli.per_page_output.as_ref().map(|dir| {
+            let naming = cli.per_page_naming.clone().into();
+            let layout = OutputLayout::PerPageFiles {
+                dir: dir.clone(),
+                naming,
+            };
+            (layout, dir)
+        })
+    };
+    if let Some((layout, dir)) = layout {
+        let stats = convert_to_layout(input, &layout, &config)
             .await
             .context("Conversion failed")?;
 
+        if !cli.quiet {
+            let selected = stats.processed_pages + stats.failed_pages + stats.skipped_pages;
+            eprintln!(
+                "{}  {}/{} pages  {}ms  →  {}",
+                if stats.failed_pages == 0 {
+                    green("✔")
+                } else {
+                    cyan("⚠")
+                },
+                stats.processed_pages,
+                selected,
+                stats.total_duration_ms,
+                bold(&dir.display().to_string()),
+            );
+            eprintln!(
+                "   {} tokens in{}  /  {} tokens out{}",
+                dim(&stats.total_input_tokens.to_string()),
+                image_suffix(stats.total_image_tokens),
+                dim(&stats.total_output_tokens.to_string()),
+                cost_suffix(stats.total_cost_usd),
+            );
+            print_calibration(&stats);
+        }
+    } else if let Some(output_path) = output_file {
+        let target = OutputTarget::from(cli.to.clone());
+        let stats = if target == OutputTarget::Markdown && cli.debug_report.is_none() {
+            convert_to_file(input, output_path, &config)
+                .await
+                .context("Conversion failed")?
+        } else {
+            let output = convert_for_cli(&cli, input, &config).await?;
+            if let Some(parent) = output_path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            tokio::fs::write(output_path, render(&output.markdown, target))
+                .await
+                .with_context(|| format!("Failed to write {}", output_path.display()))?;
+            output.stats
+        };
+
         // Summary line (callback already printed the per-page log).
         if !cli.quiet {
             let selected = stats.processed_pages + stats.failed_pages + stats.skipped_pages;
@@ -542,28 +1560,39 @@ async fn main() -> Result<()> {
                 bold(&output_path.display().to_string()),
             );
             eprintln!(
-                "   {} tokens in  /  {} tokens out",
+                "   {} tokens in{}  /  {} tokens out{}",
                 dim(&stats.total_input_tokens.to_string()),
+                image_suffix(stats.total_image_tokens),
                 dim(&stats.total_output_tokens.to_string()),
+                cost_suffix(stats.total_cost_usd),
             );
+            print_calibration(&stats);
         }
     } else {
-        let output = convert(&cli.input, &config)
-            .await
-            .context("Conversion failed")?;
+        let output = convert_for_cli(&cli, input, &config).await?;
+        if let Some(ref reason) = output.fatal_error {
+            if !cli.quiet {
+                eprintln!(
+                    "{}  Stopped early, output is partial: {}",
+                    cyan("⚠"),
+                    reason
+                );
+            }
+        }
 
         if cli.json {
             let json =
                 serde_json::to_string_pretty(&output).context("Failed to serialise output")?;
             println!("{json}");
         } else {
+            let text = render(&output.markdown, cli.to.clone().into());
             let stdout = io::stdout();
             let mut handle = stdout.lock();
             handle
-                .write_all(output.markdown.as_bytes())
+                .write_all(text.as_bytes())
                 .context("Failed to write to stdout")?;
             // Ensure a trailing newline on stdout.
-            if !output.markdown.ends_with('\n') {
+            if !text.ends_with('\n') {
                 handle.write_all(b"\n").ok();
             }
         }
@@ -583,17 +1612,532 @@ async fn main() -> Result<()> {
             }
         } else if !cli.quiet && !cli.json {
             eprintln!(
-                "   {} tokens in  /  {} tokens out  —  {}ms total",
+                "   {} tokens in{}  /  {} tokens out{}  —  {}ms total",
                 dim(&output.stats.total_input_tokens.to_string()),
+                image_suffix(output.stats.total_image_tokens),
                 dim(&output.stats.total_output_tokens.to_string()),
+                cost_suffix(output.stats.total_cost_usd),
                 output.stats.total_duration_ms,
             );
+            print_calibration(&output.stats);
+        }
+    }
+
+    if let Some(ref path) = cli.debug_report {
+        if !cli.quiet {
+            eprintln!("   debug report  →  {}", bold(&path.display().to_string()));
+        }
+    }
+
+    // ── Thumbnails ───────────────────────────────────────────────────────
+    if let Some(ref dir) = cli.thumbnails_dir {
+        let written = write_thumbnails(input, dir, cli.thumbnail_size, &config).await?;
+        if !cli.quiet {
+            eprintln!(
+                "   {} thumbnails  →  {}",
+                written,
+                bold(&dir.display().to_string())
+            );
         }
     }
 
     Ok(())
 }
 
+/// `pdf2md eval`: convert each input and score it against its truth file.
+async fn run_eval(
+    truth_dir: &Path,
+    inputs: &[String],
+    config: &ConversionConfig,
+    json: bool,
+) -> Result<()> {
+    let mut reports = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let stem = Path::new(input.split('?').next().unwrap_or(input))
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .with_context(|| format!("Cannot name a truth file for {input}"))?;
+        let truth_path = truth_dir.join(format!("{stem}.md"));
+        let truth = std::fs::read_to_string(&truth_path)
+            .with_context(|| format!("Failed to read {}", truth_path.display()))?;
+        let output = convert(input, config)
+            .await
+            .with_context(|| format!("Conversion of {input} failed"))?;
+        reports.push((input.clone(), evaluate(&output.markdown, &truth)));
+    }
+
+    if json {
+        let rows: Vec<_> = reports
+            .iter()
+            .map(|(input, report)| serde_json::json!({ "input": input, "report": report }))
+            .collect();
+        let json = serde_json::to_string_pretty(&rows).context("Failed to serialise reports")?;
+        println!("{json}");
+        return Ok(());
+    }
+
+    let pct = |v: f64| format!("{:.2}%", v * 100.0);
+    let width = reports
+        .iter()
+        .map(|(i, _)| i.len())
+        .max()
+        .unwrap_or(0)
+        .max(8);
+    println!(
+        "{:<width$}  {:>8}  {:>8}  {:>8}  {:>8}",
+        "Input", "CER", "WER", "Tables", "Headings"
+    );
+    for (input, r) in &reports {
+        println!(
+            "{:<width$}  {:>8}  {:>8}  {:>8}  {:>8}",
+            input,
+            pct(r.cer),
+            pct(r.wer),
+            r.table_cell_accuracy.map(pct).unwrap_or_else(|| "—".into()),
+            pct(r.heading_similarity),
+        );
+    }
+    if reports.len() > 1 {
+        let n = reports.len() as f64;
+        let mean = |f: fn(&EvalReport) -> f64| reports.iter().map(|(_, r)| f(r)).sum::<f64>() / n;
+        println!(
+            "{:<width$}  {:>8}  {:>8}  {:>8}  {:>8}",
+            "mean",
+            pct(mean(|r| r.cer)),
+            pct(mean(|r| r.wer)),
+            "",
+            pct(mean(|r| r.heading_similarity)),
+        );
+    }
+    Ok(())
+}
+
+/// Provider inferred from a model name for `pdf2md bench`, or `None` to
+/// keep the configured one.
+fn provider_for_model(model: &str) -> Option<&'static str> {
+    let model = model.to_ascii_lowercase();
+    let prefixed = |prefixes: &[&str]| prefixes.iter().any(|p| model.starts_with(p));
+    if prefixed(&["gpt-", "o1", "o3", "o4"]) {
+        Some("openai")
+    } else if prefixed(&["claude"]) {
+        Some("anthropic")
+    } else if prefixed(&["gemini"]) {
+        Some("gemini")
+    } else if prefixed(&["pixtral", "mistral"]) {
+        Some("mistral")
+    } else {
+        None
+    }
+}
+
+/// One row of the `pdf2md bench` table.
+#[derive(serde::Serialize)]
+struct BenchRow {
+    model: String,
+    provider: Option<String>,
+    pages_ok: usize,
+    pages_failed: usize,
+    cost_usd: Option<f64>,
+    duration_ms: u64,
+    median_page_ms: u64,
+    output_chars: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eval: Option<EvalReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// `pdf2md bench`: convert the sample with each model in turn.
+async fn run_bench(
+    input: &str,
+    models: &[String],
+    base: &ConversionConfig,
+    truth: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    const KNOWN_PROVIDERS: &[&str] = &[
+        "openai",
+        "anthropic",
+        "gemini",
+        "mistral",
+        "azure",
+        "ollama",
+        "lmstudio",
+        "openrouter",
+    ];
+
+    let mut rows = Vec::with_capacity(models.len());
+    for spec in models {
+        let spec = spec.trim();
+        let (provider, model) = match spec.split_once('/') {
+            Some((p, m))
+                if KNOWN_PROVIDERS.contains(&p)
+                    && base.provider_name.as_deref() != Some("openrouter") =>
+            {
+                (Some(p.to_string()), m.to_string())
+            }
+            _ => (
+                provider_for_model(spec)
+                    .map(str::to_string)
+                    .or_else(|| base.provider_name.clone()),
+                spec.to_string(),
+            ),
+        };
+        let mut config = base.clone();
+        config.model = Some(model.clone());
+        config.provider_name = provider.clone();
+        config.progress_callback = None;
+
+        eprintln!("{}  {}", dim("…"), bold(spec));
+        let mut row = BenchRow {
+            model: spec.to_string(),
+            provider,
+            pages_ok: 0,
+            pages_failed: 0,
+            cost_usd: None,
+            duration_ms: 0,
+            median_page_ms: 0,
+            output_chars: 0,
+            eval: None,
+            error: None,
+        };
+        match convert(input, &config).await {
+            Ok(output) => {
+                row.pages_ok = output.stats.processed_pages;
+                row.pages_failed = output.stats.failed_pages;
+                row.cost_usd = output.stats.total_cost_usd;
+                row.duration_ms = output.stats.total_duration_ms;
+                row.median_page_ms = output.stats.llm_latency.median_ms;
+                row.output_chars = output.markdown.chars().count();
+                row.eval = truth.map(|t| evaluate(&output.markdown, t));
+            }
+            Err(e) => row.error = Some(e.to_string()),
+        }
+        rows.push(row);
+    }
+
+    if json {
+        let json = serde_json::to_string_pretty(&rows).context("Failed to serialise results")?;
+        println!("{json}");
+        return Ok(());
+    }
+
+    let width = rows.iter().map(|r| r.model.len()).max().unwrap_or(0).max(5);
+    let pct = |v: f64| format!("{:.2}%", v * 100.0);
+    let mut header = format!(
+        "{:<width$}  {:>7}  {:>10}  {:>9}  {:>9}  {:>8}",
+        "Model", "Pages", "Cost", "Time", "p50/page", "Chars"
+    );
+    if truth.is_some() {
+        header.push_str(&format!("  {:>8}  {:>8}  {:>8}", "CER", "WER", "Tables"));
+    }
+    println!("{header}");
+    for r in &rows {
+        if let Some(ref e) = r.error {
+            println!("{:<width$}  {}", r.model, red(&format!("failed: {e}")));
+            continue;
+        }
+        let mut line = format!(
+            "{:<width$}  {:>7}  {:>10}  {:>8.1}s  {:>7}ms  {:>8}",
+            r.model,
+            format!("{}/{}", r.pages_ok, r.pages_ok + r.pages_failed),
+            r.cost_usd
+                .map(|c| format!("${c:.4}"))
+                .unwrap_or_else(|| "—".into()),
+            r.duration_ms as f64 / 1000.0,
+            r.median_page_ms,
+            r.output_chars,
+        );
+        if let Some(ref e) = r.eval {
+            line.push_str(&format!(
+                "  {:>8}  {:>8}  {:>8}",
+                pct(e.cer),
+                pct(e.wer),
+                e.table_cell_accuracy.map(pct).unwrap_or_else(|| "—".into()),
+            ));
+        }
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// `convert`, also writing the `--debug-report` when one was asked for.
+async fn convert_for_cli(
+    cli: &Cli,
+    input: &str,
+    config: &ConversionConfig,
+) -> Result<ConversionOutput> {
+    match cli.debug_report {
+        Some(ref path) => convert_with_debug_report(input, path, config).await,
+        None => convert(input, config).await,
+    }
+    .context("Conversion failed")
+}
+
+/// A side of `pdf2md diff`: a saved `--json` output, or a PDF to convert.
+async fn load_or_convert(input: &str, config: &ConversionConfig) -> Result<ConversionOutput> {
+    if input.ends_with(".json") {
+        let text =
+            std::fs::read_to_string(input).with_context(|| format!("Failed to read {input}"))?;
+        return serde_json::from_str(&text)
+            .with_context(|| format!("{input} is not a pdf2md --json output"));
+    }
+    convert(input, config)
+        .await
+        .with_context(|| format!("Conversion of {input} failed"))
+}
+
+/// Copy a PDF piped on stdin into a tempfile so pdfium can open it by path.
+fn read_stdin_pdf() -> Result<tempfile::NamedTempFile> {
+    let mut stdin = io::stdin().lock();
+    if stdin.is_terminal() {
+        anyhow::bail!(
+            "INPUT is '-' but stdin is a terminal; pipe a PDF in, e.g. `curl … | pdf2md -`"
+        );
+    }
+    let mut tmp = tempfile::Builder::new()
+        .prefix("pdf2md-stdin-")
+        .suffix(".pdf")
+        .tempfile()
+        .context("Failed to create a tempfile for stdin")?;
+    let n = io::copy(&mut stdin, &mut tmp).context("Failed to read PDF from stdin")?;
+    if n == 0 {
+        anyhow::bail!("No data on stdin");
+    }
+    tmp.flush().context("Failed to write stdin tempfile")?;
+    Ok(tmp)
+}
+
+/// Render per-page JPEG previews for the pages selected in `config` and
+/// write them into `dir`. Returns the number of files written.
+async fn write_thumbnails(
+    input: &str,
+    dir: &std::path::Path,
+    max_edge: u32,
+    config: &ConversionConfig,
+) -> Result<usize> {
+    let thumb_config = ThumbnailConfig {
+        max_edge,
+        pages: config.pages.clone(),
+        password: config.password.clone(),
+        ..Default::default()
+    };
+    let thumbs = generate_thumbnails(input, &thumb_config)
+        .await
+        .context("Failed to generate thumbnails")?;
+
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    for t in &thumbs {
+        let path = dir.join(format!("page-{:04}.{}", t.page_num, t.format.extension()));
+        std::fs::write(&path, &t.bytes)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(thumbs.len())
+}
+
+/// Resolve `--config` / `--profile` before the real parse.
+///
+/// The file has to be read first because its values become the clap defaults,
+/// so these three flags are picked out of the raw arguments here. The full
+/// parse still validates them.
+fn load_file_settings() -> Result<Option<ConfigProfile>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--no-config") {
+        return Ok(None);
+    }
+    let profile = raw_flag(&args, "--profile", "PDF2MD_PROFILE");
+    let path = match raw_flag(&args, "--config", "PDF2MD_CONFIG") {
+        Some(p) => Some(PathBuf::from(p)),
+        None => ConfigFile::discover(),
+    };
+    let Some(path) = path else {
+        if let Some(name) = profile {
+            anyhow::bail!("--profile {name} given but no pdf2md.toml was found");
+        }
+        return Ok(None);
+    };
+    let file = ConfigFile::load(&path)?;
+    Ok(Some(file.resolve(profile.as_deref())?))
+}
+
+/// The value of `name` in the raw arguments, else of the `env` variable.
+fn raw_flag(args: &[String], name: &str, env: &str) -> Option<String> {
+    let prefix = format!("{name}=");
+    args.iter()
+        .enumerate()
+        .find_map(|(i, a)| {
+            if a == name {
+                args.get(i + 1).cloned()
+            } else {
+                a.strip_prefix(&prefix).map(str::to_string)
+            }
+        })
+        .or_else(|| std::env::var(env).ok().filter(|v| !v.is_empty()))
+}
+
+/// Put the settings of `--preset` (or the file's `preset`) under the file's
+/// own, so both become clap defaults and every explicit setting still wins.
+fn layer_preset(file: Option<ConfigProfile>) -> Result<Option<ConfigProfile>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let preset = match raw_flag(&args, "--preset", "PDF2MD_PRESET") {
+        Some(name) => Some(name.parse::<DocumentPreset>().map_err(anyhow::Error::msg)?),
+        None => file.as_ref().and_then(|f| f.preset),
+    };
+    Ok(match (preset, file) {
+        (Some(preset), file) => Some(preset.profile().overlay(file.unwrap_or_default())),
+        (None, file) => file,
+    })
+}
+
+/// Install config-file settings as the defaults of the matching CLI args.
+fn apply_file_defaults(mut cmd: clap::Command, s: &ConfigProfile) -> clap::Command {
+    let mut defaults: Vec<(&str, String)> = Vec::new();
+    let mut push = |id, v: Option<String>| {
+        if let Some(v) = v {
+            defaults.push((id, v));
+        }
+    };
+    push("preset", s.preset.map(|p| p.name().to_string()));
+    push("provider", s.provider.clone());
+    push("model", s.model.clone());
+    push("base_url", s.base_url.clone());
+    push("dpi", s.dpi.map(|v| v.to_string()));
+    push("concurrency", s.concurrency.map(|v| v.to_string()));
+    push("render_workers", s.render_workers.map(|v| v.to_string()));
+    push("maintain_format", s.maintain_format.map(|v| v.to_string()));
+    push(
+        "context_window_pages",
+        s.context_window_pages.map(|v| v.to_string()),
+    );
+    push(
+        "parallel_sections",
+        s.parallel_sections.map(|v| v.to_string()),
+    );
+    push(
+        "tile_dense_pages",
+        s.tile_dense_pages.map(|v| v.to_string()),
+    );
+    push("layout_hints", s.layout_hints.map(|v| v.to_string()));
+    push("structure_tags", s.structure_tags.map(|v| v.to_string()));
+    push("figure_captions", s.figure_captions.map(|v| v.to_string()));
+    push("chart_tables", s.chart_tables.map(|v| v.to_string()));
+    push(
+        "niche_placeholders",
+        s.niche_placeholders.map(|v| v.to_string()),
+    );
+    push(
+        "toc",
+        s.toc_handling.map(|v| {
+            match v {
+                TocHandling::Keep => "keep",
+                TocHandling::Normalize => "normalize",
+                TocHandling::Skip => "skip",
+                TocHandling::Replace => "replace",
+            }
+            .to_string()
+        }),
+    );
+    push(
+        "keep_blank_pages",
+        s.skip_blank_pages.map(|v| (!v).to_string()),
+    );
+    push("auto_rotate", s.auto_rotate.map(|v| v.to_string()));
+    push("split_spreads", s.split_spreads.map(|v| v.to_string()));
+    push("mask_margins", s.margin_mask.map(|v| v.to_string()));
+    push(
+        "remove_watermarks",
+        s.remove_watermarks.map(|v| v.to_string()),
+    );
+    push("grayscale", s.grayscale.map(|v| v.to_string()));
+    push("png_palette", s.png_palette.map(|v| v.to_string()));
+    push(
+        "fidelity",
+        s.fidelity.map(|t| {
+            match t {
+                FidelityTier::Tier1 => "tier1",
+                FidelityTier::Tier2 => "tier2",
+                FidelityTier::Tier3 => "tier3",
+                FidelityTier::Tier4 => "tier4",
+            }
+            .to_string()
+        }),
+    );
+    push(
+        "content_hint",
+        s.content_hint.map(|v| {
+            match v {
+                ContentHint::Printed => "printed",
+                ContentHint::Handwritten => "handwritten",
+                ContentHint::Auto => "auto",
+            }
+            .to_string()
+        }),
+    );
+    push(
+        "classify_pages",
+        s.page_classifier.map(|v| {
+            match v {
+                PageClassifier::Heuristic => "heuristic",
+                PageClassifier::Model => "model",
+            }
+            .to_string()
+        }),
+    );
+    push("temperature", s.temperature.map(|v| v.to_string()));
+    push("seed", s.seed.map(|v| v.to_string()));
+    push("max_tokens", s.max_tokens.map(|v| v.to_string()));
+    push(
+        "max_tokens_retry_cap",
+        s.max_tokens_retry_cap.map(|v| v.to_string()),
+    );
+    push("max_retries", s.max_retries.map(|v| v.to_string()));
+    push(
+        "failure_abort_threshold",
+        s.failure_abort_threshold.map(|v| v.to_string()),
+    );
+    push(
+        "strictness",
+        s.strictness.map(|v| {
+            match v {
+                Strictness::BestEffort => "best-effort",
+                Strictness::FailFast => "fail-fast",
+                Strictness::AllOrNothing => "all-or-nothing",
+            }
+            .to_string()
+        }),
+    );
+    push("metadata", s.include_metadata.map(|v| v.to_string()));
+    push("flavor", s.flavor.map(|v| v.name().to_string()));
+    push(
+        "image_links",
+        s.image_link_policy.as_ref().map(|v| v.to_string()),
+    );
+    push("local_only", s.local_only.map(|v| v.to_string()));
+    push("max_pages", s.max_pages.map(|v| v.to_string()));
+    push(
+        "max_file_size",
+        s.max_file_size_bytes.map(|v| v.to_string()),
+    );
+    push(
+        "download_timeout",
+        s.download_timeout_secs.map(|v| v.to_string()),
+    );
+    push("api_timeout", s.api_timeout_secs.map(|v| v.to_string()));
+    for (id, value) in defaults {
+        cmd = cmd.mut_arg(id, |a| a.default_value(value));
+    }
+
+    if let Some(ref models) = s.fallback_models {
+        let models = models.clone();
+        cmd = cmd.mut_arg("fallback_models", |a| a.default_values(models));
+    }
+    if let Some(ref headers) = s.extra_headers {
+        let pairs: Vec<String> = headers.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        cmd = cmd.mut_arg("headers", |a| a.default_values(pairs));
+    }
+    cmd
+}
+
 /// Map CLI args to `ConversionConfig`.
 async fn build_config(cli: &Cli, progress: Option<ProgressCallback>) -> Result<ConversionConfig> {
     let system_prompt = if let Some(ref path) = cli.system_prompt {
@@ -603,29 +2147,200 @@ async fn build_config(cli: &Cli, progress: Option<ProgressCallback>) -> Result<C
                 .with_context(|| format!("Failed to read system prompt from {:?}", path))?,
         )
     } else {
-        None
+        cli.file_settings
+            .as_ref()
+            .and_then(|s| s.system_prompt.clone())
     };
 
     let pages = parse_pages(&cli.pages)?;
+    let crops = parse_crops(&cli.crops, &pages)?;
     let separator = parse_separator(&cli.separator);
 
     let mut builder = ConversionConfig::builder()
         .dpi(cli.dpi)
         .concurrency(cli.concurrency)
+        .render_workers(cli.render_workers)
         .maintain_format(cli.maintain_format)
+        .context_window_pages(cli.context_window_pages)
+        .parallel_sections(cli.parallel_sections)
+        .tile_dense_pages(cli.tile_dense_pages)
+        .layout_hints(cli.layout_hints)
+        .structure_tags(cli.structure_tags)
+        .figure_captions(cli.figure_captions)
+        .chart_tables(cli.chart_tables)
+        .niche_placeholders(cli.niche_placeholders)
+        .toc_handling(cli.toc.clone().into())
+        .skip_blank_pages(!cli.keep_blank_pages)
+        .auto_rotate(cli.auto_rotate)
+        .split_spreads(cli.split_spreads)
+        .remove_watermarks(cli.remove_watermarks)
+        .grayscale(cli.grayscale)
+        .png_palette(cli.png_palette)
         .pages(pages)
+        .page_crops(crops)
         .fidelity(cli.fidelity.clone().into())
+        .content_hint(cli.content_hint.clone().into())
         .page_separator(separator)
         .max_tokens(cli.max_tokens)
         .temperature(cli.temperature)
         .max_retries(cli.max_retries)
+        .failure_abort_threshold(cli.failure_abort_threshold)
+        .strictness(cli.strictness.clone().into())
+        .fallback_models(cli.fallback_models.iter().cloned())
         .include_metadata(cli.metadata)
+        .flavor(match OutputTarget::from(cli.to.clone()) {
+            OutputTarget::Markdown => cli.flavor.clone().into(),
+            _ => MarkdownFlavor::Gfm,
+        })
+        .image_link_policy(cli.image_links.clone())
+        .keep_raw_output(cli.keep_raw)
+        .local_only(cli.local_only)
         .download_timeout_secs(cli.download_timeout)
         .api_timeout_secs(cli.api_timeout);
 
+    if let Some(seed) = cli.seed {
+        builder = builder.seed(seed);
+    }
+    if let Some(mask) = cli.mask_margins {
+        builder = builder.margin_mask(mask);
+    }
+    if let Some(ref classifier) = cli.classify_pages {
+        builder = builder.page_classifier(classifier.clone().into());
+    }
     if let Some(cb) = progress {
         builder = builder.progress_callback(cb);
     }
+    if let Some(ref url) = cli.base_url {
+        builder = builder.base_url(url);
+    }
+    if !cli.headers.is_empty() {
+        builder = builder.extra_headers(parse_headers(&cli.headers)?);
+    }
+    if let Some(ref path) = cli.event_log {
+        builder = builder.event_log(path);
+    }
+    if let Some(ref dir) = cli.transcript_dir {
+        builder = builder.transcript_dir(dir);
+    }
+    if let Some(cap) = cli.max_tokens_retry_cap {
+        builder = builder.max_tokens_retry_cap(cap);
+    }
+    if let Some(n) = cli.max_pages {
+        builder = builder.max_pages(n);
+    }
+    if let Some(bytes) = cli.max_file_size {
+        builder = builder.max_file_size_bytes(bytes);
+    }
+    if cli.summary {
+        builder = builder.generate_summary(SummaryConfig {
+            prepend: true,
+            ..Default::default()
+        });
+    }
+    if cli.enrich {
+        builder = builder.enrich(EnrichmentConfig::default());
+    }
+    if cli.polish {
+        builder = builder.text_polish(PolishConfig {
+            model: cli.polish_model.clone(),
+            ..Default::default()
+        });
+    }
+    if cli.references {
+        builder = builder.parse_references(true);
+    }
+    if cli.footnotes {
+        builder = builder.link_footnotes(true);
+    }
+    if let Some(ref dir) = cli.figures_dir {
+        builder = builder.figures_dir(dir);
+    }
+    if let Some(ref dir) = cli.thumbnails_dir {
+        builder = builder.placeholder_thumbnails(dir);
+    }
+    if let Some(ref dir) = cli.attachments_dir {
+        builder = builder.attachments_dir(dir);
+    }
+    if cli.einvoice {
+        builder = builder.parse_einvoice(true);
+    }
+    if cli.pii {
+        builder = builder.pii(PiiConfig {
+            llm_names: cli.pii_names,
+            ..Default::default()
+        });
+    }
+    // `--auto-dpi` enables calibration with the file's `[auto_dpi]` table, if any.
+    match cli.file_settings.as_ref().and_then(|s| s.auto_dpi.clone()) {
+        Some(auto) => builder = builder.auto_dpi(auto),
+        None if cli.auto_dpi => builder = builder.auto_dpi(AutoDpiConfig::default()),
+        None => {}
+    }
+    if let Some(ms) = cli.file_settings.as_ref().and_then(|s| s.retry_backoff_ms) {
+        builder = builder.retry_backoff_ms(ms);
+    }
+    if let Some(prices) = cli
+        .file_settings
+        .as_ref()
+        .and_then(|s| s.model_prices.clone())
+    {
+        builder = builder.model_prices(prices);
+    }
+    // `--webhook` replaces the file's URL but keeps its headers and options.
+    let mut webhook = cli.file_settings.as_ref().and_then(|s| s.webhook.clone());
+    if let Some(ref url) = cli.webhook {
+        webhook
+            .get_or_insert_with(|| WebhookConfig::new(url.as_str()))
+            .url = url.clone();
+    }
+    if let Some(hook) = webhook {
+        builder = builder.webhook(hook);
+    }
+    // `--quote-style` and `--dash-style` override the file's `[unicode]` table.
+    let mut unicode = cli
+        .file_settings
+        .as_ref()
+        .and_then(|s| s.unicode)
+        .unwrap_or_default();
+    if let Some(ref v) = cli.quote_style {
+        unicode.quotes = v.clone().into();
+    }
+    if let Some(ref v) = cli.dash_style {
+        unicode.dashes = v.clone().into();
+    }
+    builder = builder.unicode(unicode);
+    // `--no-annotations` and `--no-form-fields` override the file's
+    // `[render_layers]` table.
+    let mut layers = cli
+        .file_settings
+        .as_ref()
+        .and_then(|s| s.render_layers)
+        .unwrap_or_default();
+    if cli.no_annotations {
+        layers.annotations = false;
+    }
+    if cli.no_form_fields {
+        layers.form_fields = false;
+    }
+    builder = builder.render_layers(layers);
+    // Likewise for the `[render_options]` table.
+    let mut render_options = cli
+        .file_settings
+        .as_ref()
+        .and_then(|s| s.render_options)
+        .unwrap_or_default();
+    if cli.no_anti_aliasing {
+        render_options.text_smoothing = false;
+        render_options.image_smoothing = false;
+        render_options.path_smoothing = false;
+    }
+    if cli.lcd_text {
+        render_options.lcd_text = true;
+    }
+    if cli.no_native_text {
+        render_options.native_text = false;
+    }
+    builder = builder.render_options(render_options);
 
     let mut config = builder.build().context("Invalid configuration")?;
 
@@ -634,20 +2349,127 @@ async fn build_config(cli: &Cli, progress: Option<ProgressCallback>) -> Result<C
     config.provider_name = cli.provider.clone();
     config.password = cli.password.clone();
     config.system_prompt = system_prompt;
+    config.preset = cli.preset;
+    config.few_shot_examples = cli
+        .few_shot
+        .iter()
+        .map(|image| FewShotExample::from_files(image, image.with_extension("md")))
+        .collect::<Result<_, _>>()?;
 
     Ok(config)
 }
 
+fn parse_preset(s: &str) -> Result<DocumentPreset, String> {
+    s.parse()
+}
+
+fn parse_image_links(s: &str) -> Result<ImageLinkPolicy, String> {
+    s.parse()
+}
+
+fn parse_margin_mask(s: &str) -> Result<MarginMask, String> {
+    s.parse()
+}
+
 /// Parse `--pages` string into `PageSelection`.
+///
+/// Comma-separated terms: `all`, `odd`, `even`, `lastN`, `N`, `M-N`, a
+/// stepped range `M-N:S` or `all:S`, and exclusions `!N` / `!M-N` or of a
+/// page kind, `!toc`. Terms are unioned, then exclusions removed (from all
+/// pages if nothing else is given).
 fn parse_pages(s: &str) -> Result<PageSelection> {
     let s = s.trim().to_lowercase();
+    let mut include = Vec::new();
+    let mut exclude = Vec::new();
+    let mut kinds = Vec::new();
+    for term in s.split(',').map(str::trim) {
+        match term.strip_prefix('!') {
+            Some(rest) if rest.trim().starts_with(|c: char| c.is_ascii_alphabetic()) => {
+                kinds.push(rest.parse::<PageKind>().map_err(anyhow::Error::msg)?);
+            }
+            Some(rest) => match parse_page_term(rest.trim())? {
+                PageSelection::Single(p) => exclude.push(p),
+                PageSelection::Range(start, end) => exclude.extend(start..=end),
+                _ => anyhow::bail!("Only pages and ranges can be excluded (got '!{}')", rest),
+            },
+            None => include.push(parse_page_term(term)?),
+        }
+    }
 
-    if s == "all" {
-        return Ok(PageSelection::All);
+    if !kinds.is_empty() {
+        let kinds = PageSelection::ExcludeKinds(kinds);
+        if include.is_empty() && exclude.is_empty() {
+            return Ok(kinds);
+        }
+        include.push(kinds);
+    }
+    if exclude.is_empty() {
+        // "1,3,5,7" stays a plain set
+        if include.len() > 1
+            && include
+                .iter()
+                .all(|p| matches!(p, PageSelection::Single(_)))
+        {
+            let pages = include
+                .into_iter()
+                .filter_map(|p| match p {
+                    PageSelection::Single(n) => Some(n),
+                    _ => None,
+                })
+                .collect();
+            return Ok(PageSelection::Set(pages));
+        }
+        if include.len() == 1 {
+            return Ok(include.remove(0));
+        }
+        return Ok(PageSelection::Union(include));
+    }
+    if include.is_empty() || matches!(include.as_slice(), [PageSelection::All]) {
+        return Ok(PageSelection::Exclude(exclude));
+    }
+    include.push(PageSelection::Exclude(exclude));
+    Ok(PageSelection::Union(include))
+}
+
+/// Parse one `--pages` term (without a leading `!`).
+fn parse_page_term(term: &str) -> Result<PageSelection> {
+    match term {
+        "all" => return Ok(PageSelection::All),
+        "odd" => return Ok(PageSelection::Odd),
+        "even" => return Ok(PageSelection::Even),
+        "" => anyhow::bail!("Empty page selection term"),
+        _ => {}
+    }
+
+    // Last pages: "last10"
+    if let Some(n) = term.strip_prefix("last") {
+        let n: usize = n
+            .trim()
+            .parse()
+            .context("Invalid page count after 'last'")?;
+        if n < 1 {
+            anyhow::bail!("'last' needs at least 1 page");
+        }
+        return Ok(PageSelection::Last(n));
+    }
+
+    // Step: "1-100:2" or "all:3"
+    let (term, step) = match term.split_once(':') {
+        Some((base, step)) => {
+            let step: usize = step.trim().parse().context("Invalid page step")?;
+            if step < 1 {
+                anyhow::bail!("Page step must be at least 1");
+            }
+            (base.trim(), Some(step))
+        }
+        None => (term, None),
+    };
+    if term == "all" {
+        return Ok(PageSelection::EveryNth(step.unwrap_or(1)));
     }
 
     // Range: "3-15"
-    if let Some((start, end)) = s.split_once('-') {
+    if let Some((start, end)) = term.split_once('-') {
         let start: usize = start
             .trim()
             .parse()
@@ -665,31 +2487,20 @@ fn parse_pages(s: &str) -> Result<PageSelection> {
             );
         }
 
-        return Ok(PageSelection::Range(start, end));
+        return Ok(match step {
+            Some(step) if step > 1 => PageSelection::StepRange(start, end, step),
+            _ => PageSelection::Range(start, end),
+        });
     }
 
-    // Set: "1,3,5,7"
-    if s.contains(',') {
-        let pages: Vec<usize> = s
-            .split(',')
-            .map(|p| {
-                p.trim()
-                    .parse::<usize>()
-                    .context(format!("Invalid page number: '{}'", p.trim()))
-            })
-            .collect::<Result<Vec<_>>>()?;
-
-        for &p in &pages {
-            if p < 1 {
-                anyhow::bail!("Pages are 1-indexed, minimum is 1 (got {})", p);
-            }
-        }
-
-        return Ok(PageSelection::Set(pages));
+    if step.is_some() {
+        anyhow::bail!("A page step needs a range, e.g. 1-100:2 (got '{}')", term);
     }
 
     // Single page: "5"
-    let page: usize = s.parse().context("Invalid page number")?;
+    let page: usize = term
+        .parse()
+        .with_context(|| format!("Invalid page number: '{}'", term))?;
     if page < 1 {
         anyhow::bail!("Pages are 1-indexed, minimum is 1 (got {})", page);
     }
@@ -697,6 +2508,76 @@ fn parse_pages(s: &str) -> Result<PageSelection> {
     Ok(PageSelection::Single(page))
 }
 
+/// Parse repeated `--header KEY=VALUE` flags into a `HeaderMap`.
+fn parse_headers(raw: &[String]) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    for h in raw {
+        let (key, value) = h
+            .split_once('=')
+            .with_context(|| format!("Invalid header '{h}': expected KEY=VALUE"))?;
+        let name: reqwest::header::HeaderName = key
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid header name '{}'", key.trim()))?;
+        let value = value
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid value for header '{}'", key.trim()))?;
+        headers.append(name, value);
+    }
+    Ok(headers)
+}
+
+/// Parse `--crop` values into regions by page. A region without a `PAGE:`
+/// prefix applies to each page of an explicit `--pages` list or range.
+fn parse_crops(raw: &[String], pages: &PageSelection) -> Result<HashMap<usize, Vec<CropRect>>> {
+    let mut crops: HashMap<usize, Vec<CropRect>> = HashMap::new();
+    for c in raw {
+        let (targets, rect) = match c.split_once(':') {
+            Some((page, rect)) => {
+                let page: usize = page
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Invalid page in --crop '{c}'"))?;
+                (vec![page], rect)
+            }
+            None => {
+                let targets = match pages {
+                    PageSelection::Single(p) => vec![*p],
+                    PageSelection::Range(start, end) => (*start..=*end).collect(),
+                    PageSelection::Set(pages) => pages.clone(),
+                    _ => anyhow::bail!(
+                        "--crop '{c}' has no PAGE: prefix; give one, or list the pages with --pages"
+                    ),
+                };
+                (targets, c.as_str())
+            }
+        };
+        let rect: CropRect = rect.parse().map_err(anyhow::Error::msg)?;
+        for page in targets {
+            crops.entry(page).or_default().push(rect);
+        }
+    }
+    Ok(crops)
+}
+
+/// Parse `split --ranges`: comma-separated pages `N` or ranges `M-N`.
+fn parse_ranges(s: &str) -> Result<Vec<(usize, usize)>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|term| {
+            let (start, end) = term.split_once('-').unwrap_or((term, term));
+            let page = |p: &str| {
+                p.trim()
+                    .parse::<usize>()
+                    .with_context(|| format!("Invalid page range '{term}'"))
+            };
+            Ok((page(start)?, page(end)?))
+        })
+        .collect()
+}
+
 /// Parse `--separator` string into `PageSeparator`.
 fn parse_separator(s: &str) -> PageSeparator {
     match s.to_lowercase().as_str() {
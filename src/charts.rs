@@ -0,0 +1,208 @@
+//! Chart data: the numbers behind bar, line, and pie charts as tables.
+//!
+//! ## Why tables?
+//!
+//! A chart converted at any tier comes back as an italic caption, which
+//! keeps the reading flow but drops the data the chart exists to show.
+//! With [`crate::ConversionConfigBuilder::chart_tables`] the page prompt
+//! gains [`crate::prompts::CHART_DATA_RULES`], which ask the model to name
+//! the chart type in the caption and transcribe the data into a pipe table
+//! right beneath it:
+//!
+//! ```text
+//! *Bar chart: Revenue by region, 2023*
+//!
+//! | Region | Revenue ($M) |
+//! |---|---:|
+//! | North | 12.4 |
+//! | South | ~9 |
+//! ```
+//!
+//! The table stays in the Markdown for readers, and [`extract`] parses
+//! each caption-and-table pair into a [`ChartData`] in
+//! [`crate::PageResult::charts`] for programs. Values the model read off
+//! the axis rather than a data label carry a `~`; [`ChartData::values`]
+//! parses both kinds and [`ChartData::estimated`] tells them apart.
+//!
+//! A caption with no table beneath it (the model could not read the data)
+//! yields no entry.
+
+use crate::pipeline::flavor::{alignments, split_row};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// `*Bar chart: Revenue by region*`, with an optional `Figure 3:` in front.
+static CAPTION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^\*(?:(?:figure|fig\.|chart|exhibit)\s+[\w.-]+\s*[:.]\s*)?([a-z][a-z -]*?)\s+(?:chart|graph|plot)\s*:\s*(\S.*?)\s*\*$",
+    )
+    .unwrap()
+});
+
+/// Kind of chart, from the word before "chart" in its caption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartKind {
+    /// Bar, column, and histogram charts.
+    Bar,
+    /// Line and area charts.
+    Line,
+    /// Pie and donut charts.
+    Pie,
+    /// Scatter plots.
+    Scatter,
+    /// Any other type the model named.
+    Other,
+}
+
+impl ChartKind {
+    fn from_caption(word: &str) -> Self {
+        let word = word.to_ascii_lowercase();
+        let last = word.split_whitespace().last().unwrap_or("");
+        match last {
+            "bar" | "column" | "histogram" => ChartKind::Bar,
+            "line" | "area" => ChartKind::Line,
+            "pie" | "donut" | "doughnut" => ChartKind::Pie,
+            "scatter" => ChartKind::Scatter,
+            _ => ChartKind::Other,
+        }
+    }
+}
+
+/// The data of one chart on a page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartData {
+    /// 1-based position among the page's charts.
+    pub index: usize,
+    /// Chart type named in the caption.
+    pub kind: ChartKind,
+    /// Caption text after the chart type, e.g. `Revenue by region, 2023`.
+    pub title: String,
+    /// Table header: the category axis, then one name per series.
+    pub columns: Vec<String>,
+    /// Table rows as written, padded or cut to the width of `columns`.
+    pub rows: Vec<Vec<String>>,
+}
+
+impl ChartData {
+    /// The numbers of column `column` (1 for the first series), one per
+    /// row; `None` for cells that are not a number. Thousands separators,
+    /// currency signs, `%`, and the `~` estimate marker are ignored.
+    pub fn values(&self, column: usize) -> Vec<Option<f64>> {
+        self.rows
+            .iter()
+            .map(|row| row.get(column).and_then(|c| parse_number(c)))
+            .collect()
+    }
+
+    /// Whether the cell at `row`, `column` is an estimate read off the axis.
+    pub fn estimated(&self, row: usize, column: usize) -> bool {
+        self.rows
+            .get(row)
+            .and_then(|r| r.get(column))
+            .is_some_and(|c| c.trim_start().starts_with('~'))
+    }
+}
+
+/// Charts of `markdown`: each italic chart caption directly followed (blank
+/// lines aside) by a pipe table. See the module docs.
+pub fn extract(markdown: &str) -> Vec<ChartData> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut charts = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let Some(caps) = CAPTION_RE.captures(lines[i].trim()) else {
+            i += 1;
+            continue;
+        };
+        let mut start = i + 1;
+        while start < lines.len() && lines[start].trim().is_empty() {
+            start += 1;
+        }
+        let Some((columns, rows, end)) = table_at(&lines, start) else {
+            i += 1;
+            continue;
+        };
+        charts.push(ChartData {
+            index: charts.len() + 1,
+            kind: ChartKind::from_caption(&caps[1]),
+            title: caps[2].to_string(),
+            columns,
+            rows,
+        });
+        i = end;
+    }
+    charts
+}
+
+/// A pipe table's header, its rows, and the index of the line after it.
+type Table = (Vec<String>, Vec<Vec<String>>, usize);
+
+/// The pipe table starting at line `start`, if one does.
+fn table_at(lines: &[&str], start: usize) -> Option<Table> {
+    fn is_row(line: &str) -> bool {
+        line.trim_start().starts_with('|')
+    }
+    let header = *lines.get(start)?;
+    if !is_row(header) {
+        return None;
+    }
+    alignments(lines.get(start + 1)?)?;
+    let columns = split_row(header);
+    let mut rows = Vec::new();
+    let mut end = start + 2;
+    while end < lines.len() && is_row(lines[end]) {
+        let mut row = split_row(lines[end]);
+        row.resize(columns.len(), String::new());
+        rows.push(row);
+        end += 1;
+    }
+    (!rows.is_empty()).then_some((columns, rows, end))
+}
+
+/// `~1,234.5`, `$3.2`, `45%`, `-0.7` as numbers.
+fn parse_number(cell: &str) -> Option<f64> {
+    let cleaned: String = cell
+        .trim()
+        .trim_start_matches('~')
+        .chars()
+        .filter(|c| !matches!(c, ',' | ' ' | '%' | '$' | '€' | '£' | '¥'))
+        .collect();
+    cleaned.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn captions_with_tables_become_charts() {
+        let md = "# Results\n\n*Figure 2: Bar chart: Revenue by region, 2023*\n\n\
+                  | Region | 2022 | 2023 |\n|---|---:|---:|\n| North | 1,200 | ~1,450 |\n| South | $980 | 45% |\n\n\
+                  Text between.\n\n*Pie chart: Market share*\nno table here\n\n\
+                  *Line graph: Users*\n| Month | Users |\n|---|---|\n| Jan | 10 |\n";
+        let charts = extract(md);
+        assert_eq!(charts.len(), 2);
+
+        let bar = &charts[0];
+        assert_eq!((bar.index, bar.kind), (1, ChartKind::Bar));
+        assert_eq!(bar.title, "Revenue by region, 2023");
+        assert_eq!(bar.columns, ["Region", "2022", "2023"]);
+        assert_eq!(bar.values(1), [Some(1200.0), Some(980.0)]);
+        assert_eq!(bar.values(2), [Some(1450.0), Some(45.0)]);
+        assert!(bar.estimated(0, 2));
+        assert!(!bar.estimated(0, 1));
+
+        assert_eq!((charts[1].index, charts[1].kind), (2, ChartKind::Line));
+        assert_eq!(charts[1].rows, [["Jan", "10"]]);
+    }
+
+    #[test]
+    fn ordinary_captions_and_tables_are_ignored() {
+        let md = "*Figure 1: Site photo*\n\n| A | B |\n|---|---|\n| 1 | 2 |\n\n| Chart | x |\n|---|---|\n| 1 | 2 |";
+        assert!(extract(md).is_empty());
+        assert_eq!(ChartKind::from_caption("Stacked column"), ChartKind::Bar);
+        assert_eq!(ChartKind::from_caption("Radar"), ChartKind::Other);
+    }
+}
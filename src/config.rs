@@ -10,11 +10,30 @@
 //! The builder pattern lets callers set only what they care about and rely on
 //! well-documented defaults for the rest.
 
+use crate::auto_dpi::AutoDpiConfig;
+use crate::backend::{FewShotExample, VisionBackend};
+use crate::enrich::EnrichmentConfig;
 use crate::error::Pdf2MdError;
+use crate::extract::ExtractionConfig;
+use crate::metrics::MetricsSink;
+use crate::page_kind::{PageClassifier, PageKind};
+use crate::pii::PiiConfig;
+use crate::pipeline::margins::MarginMask;
+use crate::polish::PolishConfig;
+use crate::presets::DocumentPreset;
+use crate::pricing::ModelPrice;
 use crate::progress::ConversionProgressCallback;
-use edgequake_llm::LLMProvider;
-use serde::{Deserialize, Serialize};
+use crate::prompts::PromptTemplate;
+use crate::resolver::ProviderResolver;
+use crate::summary::SummaryConfig;
+use crate::webhook::WebhookConfig;
+use edgequake_llm::{EmbeddingProvider, LLMProvider};
+use image::DynamicImage;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 /// Configuration for a PDF-to-Markdown conversion.
@@ -22,6 +41,13 @@ use std::sync::Arc;
 /// Built via [`ConversionConfig::builder()`] or using
 /// [`ConversionConfig::default()`].
 ///
+/// The config is `Serialize`/`Deserialize` so it can travel through job
+/// queues and be logged next to a run's output. Runtime-only fields (provider
+/// and backend instances, callbacks, metrics sinks, trace context) are
+/// skipped, and missing keys take their defaults. Serialise
+/// [`redact_secrets()`](Self::redact_secrets) when the JSON leaves your
+/// process.
+///
 /// # Example
 /// ```rust
 /// use edgequake_pdf2md::ConversionConfig;
@@ -33,7 +59,8 @@ use std::sync::Arc;
 ///     .build()
 ///     .unwrap();
 /// ```
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, remote = "Self")]
 pub struct ConversionConfig {
     /// Rendering DPI used when rasterising each PDF page. Range: 72–400. Default: 150.
     ///
@@ -43,6 +70,13 @@ pub struct ConversionConfig {
     /// pages where file size matters more than pixel density.
     pub dpi: u32,
 
+    /// Pick the DPI per document by probing a sample page. Default: None.
+    ///
+    /// Replaces `dpi` with the lowest candidate whose output matches the
+    /// highest candidate's; costs one extra page call per candidate.
+    /// Applies to [`crate::convert`] only. See [`crate::auto_dpi`].
+    pub auto_dpi: Option<AutoDpiConfig>,
+
     /// Maximum rendered image dimension (width or height) in pixels. Default: 2000.
     ///
     /// A safety cap independent of DPI. A 200-DPI render of an A0 poster could
@@ -59,6 +93,17 @@ pub struct ConversionConfig {
     /// your network is wide, you can raise it safely.
     pub concurrency: usize,
 
+    /// Number of pages in flight between rasterising and encoding. Default: 1.
+    ///
+    /// Workers share the conversion's open document and take turns
+    /// rasterising: pdfium is not thread-safe, and its bindings lock it for
+    /// the whole process. The gain comes from overlapping PNG encoding and
+    /// image processing with rasterising; it helps most when
+    /// [`Self::concurrency`] is high and the model answers faster than
+    /// pages are produced. Ignored under [`Self::maintain_format`], which
+    /// needs pages in order.
+    pub render_workers: usize,
+
     /// LLM model identifier, e.g. "gpt-4o", "claude-sonnet-4-20250514".
     /// If None, uses provider default.
     pub model: Option<String>,
@@ -68,8 +113,99 @@ pub struct ConversionConfig {
     pub provider_name: Option<String>,
 
     /// Pre-constructed LLM provider. Takes precedence over `provider_name`.
+    #[serde(skip)]
     pub provider: Option<Arc<dyn LLMProvider>>,
 
+    /// Custom vision backend for page conversion. Takes precedence over every
+    /// provider setting.
+    ///
+    /// Lets callers outside the edgequake-llm ecosystem plug in their own HTTP
+    /// client or in-process model (see [`crate::backend`]). `fallback_models`
+    /// is ignored with a backend; the summary and enrichment passes still
+    /// need a text-capable `provider` and are skipped without one.
+    #[serde(skip)]
+    pub backend: Option<Arc<dyn VisionBackend>>,
+
+    /// Picks the provider per conversion, e.g. by tenant. Default: None.
+    ///
+    /// Ranks below `provider` and above every other provider setting. Lets a
+    /// multi-tenant server share one config and pass the tenant with each
+    /// call to [`crate::convert_with_context`]. `fallback_models` is ignored
+    /// with a resolver. See [`crate::resolver`].
+    #[serde(skip)]
+    pub provider_resolver: Option<Arc<dyn ProviderResolver>>,
+
+    /// Ordered fallback models tried when the primary model fails a page. Default: empty.
+    ///
+    /// Designed for OpenRouter (`provider_name = "openrouter"`), where one API
+    /// key reaches many vision models: list e.g. `["google/gemini-2.0-flash-001",
+    /// "anthropic/claude-3.5-haiku"]` and they are sent in the request's
+    /// `models` array, so OpenRouter itself moves on to the next model when
+    /// one is down. With any other `provider_name` (or a gateway
+    /// [`Self::base_url`] in front of OpenRouter) the fallback happens here:
+    /// a page that exhausts its retries on the primary model is re-sent to
+    /// each fallback in turn, each created with the same `provider_name`.
+    /// Ignored when a pre-built `provider` is injected.
+    pub fallback_models: Vec<String>,
+
+    /// Base URL of an OpenAI-compatible endpoint. Default: None.
+    ///
+    /// When set, the provider is created as a generic OpenAI-compatible client
+    /// pointed at this URL (e.g. `http://localhost:8000/v1` for vLLM, or a
+    /// LiteLLM proxy / corporate gateway) instead of the vendor's own API.
+    /// The key comes from [`Self::api_key`] or an `Authorization` entry in
+    /// [`Self::extra_headers`]; the environment only supplies one when the
+    /// URL is a known vendor's endpoint (`OPENAI_API_KEY` for
+    /// `api.openai.com`), so no key is ever sent to another host.
+    pub base_url: Option<String>,
+
+    /// Extra HTTP headers sent with every VLM request. Default: empty.
+    ///
+    /// Gateways frequently require their own auth or routing headers
+    /// (`X-Api-Gateway-Key`, `X-Tenant`). Setting any header routes provider
+    /// creation through the OpenAI-compatible client (see [`Self::base_url`]).
+    /// Without `base_url` the headers go to the named provider's own
+    /// OpenAI-compatible endpoint; providers without one (Ollama, LM Studio)
+    /// need `base_url`.
+    /// Header values are never printed by the `Debug` impl. Serialised as a
+    /// `{"name": "value"}` map.
+    #[serde(with = "header_map_serde")]
+    pub extra_headers: HeaderMap,
+
+    /// API key for the resolved provider. Default: None (read from the environment).
+    ///
+    /// Hosts that keep secrets in a vault can pass the key directly instead of
+    /// setting `OPENAI_API_KEY` & co. Mutating process env vars per request
+    /// is a race in a multi-tenant server; an explicit key never touches the
+    /// environment. With a key set, the provider is created as an
+    /// OpenAI-compatible client for the provider's endpoint, and no
+    /// environment auto-detection happens. Without `provider_name` the key is
+    /// used for OpenAI. Never printed by `Debug`; cleared by
+    /// [`redact_secrets`](Self::redact_secrets).
+    pub api_key: Option<String>,
+
+    /// Per-provider API keys. Default: empty.
+    ///
+    /// Consulted when `api_key` is unset, by provider name. Useful with
+    /// `fallback_models` or when one config template serves several
+    /// providers.
+    pub credentials: ProviderCredentials,
+
+    /// Refuse to send anything off this machine. Default: false.
+    ///
+    /// For compliance-sensitive deployments that need a hard guarantee that
+    /// page images never leave the host. The provider must then be
+    /// `"ollama"` or `"lmstudio"` (with `OLLAMA_HOST` / `LMSTUDIO_HOST`, if
+    /// set, on a loopback address) or a `base_url` on `localhost`,
+    /// `127.0.0.0/8`, or `::1` (vLLM, llama.cpp, LM Studio). Environment
+    /// auto-detection is disabled and URL inputs are rejected. Violations
+    /// fail with [`Pdf2MdError::LocalOnlyViolation`] before any page is
+    /// rendered. A pre-built `provider` or `provider_resolver` cannot be
+    /// proven local and is refused; a custom `backend` must report
+    /// [`VisionBackend::is_local`](crate::backend::VisionBackend::is_local).
+    /// A [`Self::webhook`] must be on a loopback address too.
+    pub local_only: bool,
+
     /// Sampling temperature for the LLM completion. Default: 0.1.
     ///
     /// Low temperature (close to 0) makes the model deterministic and faithful
@@ -77,6 +213,19 @@ pub struct ConversionConfig {
     /// Higher values introduce creativity that worsens OCR accuracy.
     pub temperature: f32,
 
+    /// Sampling seed for repeatable conversions. Default: None.
+    ///
+    /// Set with [`ConversionConfigBuilder::seed`], which also sets
+    /// `temperature` to 0; `build()` rejects a seed with any other
+    /// temperature. Backends receive it in
+    /// [`crate::backend::DescribeOptions::seed`], and the text passes
+    /// (polish, summaries, PII names) build their options from the same
+    /// struct. The OpenRouter fallback backend sends it; edgequake-llm
+    /// providers cannot (their `CompletionOptions` has no seed), so there
+    /// temperature 0 is what pins the output. Recorded in
+    /// [`crate::ReproducibilityReport`].
+    pub seed: Option<u64>,
+
     /// Maximum tokens the LLM may generate per page. Default: 4096.
     ///
     /// Dense pages (tables, code listings) can exceed 2 000 output tokens.
@@ -85,6 +234,15 @@ pub struct ConversionConfig {
     /// per-page cost predictable.
     pub max_tokens: usize,
 
+    /// Largest token budget a truncated page may be retried with. Default: None (no retry).
+    ///
+    /// When a completion stops at `max_tokens` the page is flagged
+    /// [`crate::PageResult::truncated`]. With a cap set, the page is re-sent
+    /// with a doubled budget (4 096 → 8 192 → …) until it completes or the
+    /// next doubling would exceed the cap, so only the rare dense page pays
+    /// for the larger budget.
+    pub max_tokens_retry_cap: Option<usize>,
+
     /// Maximum retry attempts on a transient VLM API failure. Default: 3.
     ///
     /// Most 5xx and timeout errors are transient (overloaded backend, network
@@ -100,12 +258,52 @@ pub struct ConversionConfig {
     /// simultaneously and immediately overwhelm a recovering API endpoint.
     pub retry_backoff_ms: u64,
 
+    /// Abort after this many consecutive provider failures. Default: 5.
+    ///
+    /// When the API key is wrong or the endpoint is unreachable, every page
+    /// fails the same way; burning `max_retries` on each of 800 pages only
+    /// delays the inevitable. If the first `N` pages to finish all fail with
+    /// auth or connection errors, [`crate::convert`] returns
+    /// [`Pdf2MdError::ProviderUnavailable`] instead. If the streak starts
+    /// after some pages converted, the run stops there and returns those pages
+    /// with [`crate::ConversionOutput::fatal_error`] set. `0` disables it.
+    pub failure_abort_threshold: usize,
+
+    /// How page failures affect the result of an eager conversion. Default: [`Strictness::BestEffort`].
+    ///
+    /// Streaming conversions always yield each page's result and ignore it.
+    pub strictness: Strictness,
+
     /// PDF user password for encrypted documents.
     pub password: Option<String>,
 
     /// Custom system prompt. If None, uses built-in default.
+    ///
+    /// Treated as a [`PromptTemplate`], so it may use the variables listed
+    /// in [`crate::prompts`]. Ignored when [`Self::prompt_template`] is set.
     pub system_prompt: Option<String>,
 
+    /// Page prompt with variables such as `{page_num}` and
+    /// `{document_title}`. Default: None (the built-in template for
+    /// [`Self::fidelity`]). See [`crate::prompts`].
+    pub prompt_template: Option<PromptTemplate>,
+
+    /// Example page→Markdown pairs sent before every page. Default: empty.
+    ///
+    /// Showing the model two or three converted pages of the same kind
+    /// (invoices, lab reports) fixes its choice of headings, table layout,
+    /// and field order far more reliably than prompt wording. The price is
+    /// known up front: every page request also carries each example's image
+    /// and Markdown tokens. Not serialisable; set it with the builder.
+    #[serde(skip)]
+    pub few_shot_examples: Vec<FewShotExample>,
+
+    /// Document class whose prompt and page cleanup to use. Default: None.
+    ///
+    /// Set it with [`ConversionConfigBuilder::preset`], which also applies
+    /// the preset's settings. See [`crate::presets`].
+    pub preset: Option<DocumentPreset>,
+
     /// Sequential mode: pass the previous page's Markdown as context to the VLM. Default: false.
     ///
     /// **Why it helps:** VLMs do not inherently know that page 3 continues the
@@ -120,6 +318,153 @@ pub struct ConversionConfig {
     /// independent pages (slide decks, scanned invoices).
     pub maintain_format: bool,
 
+    /// Number of prior pages passed as context in `maintain_format` mode. Default: 1.
+    ///
+    /// One page is enough for numbered lists and running text; books whose
+    /// sections span several pages keep heading levels and terminology more
+    /// consistent with 2–3. Input tokens grow roughly linearly with the
+    /// window, so it is bounded rather than "all pages so far". Must be ≥ 1;
+    /// ignored unless `maintain_format` is set.
+    pub context_window_pages: usize,
+
+    /// Run `maintain_format` chapters in parallel. Default: false.
+    ///
+    /// The document is split at chapter boundaries — PDF bookmarks, or
+    /// "Chapter N" headings in the text layer when there is no outline — and
+    /// each section is converted sequentially with prior-page context while up
+    /// to `concurrency` sections run at once. The first page of each section
+    /// gets no context. Documents with fewer than two sections fall back to
+    /// plain sequential mode. Applies to [`crate::convert`]; streaming stays
+    /// page-sequential. Ignored unless `maintain_format` is set.
+    pub parallel_sections: bool,
+
+    /// Make [`crate::convert_stream`] yield pages in ascending page order. Default: false.
+    ///
+    /// Out-of-order completions are held back until every earlier page has
+    /// been emitted, so consumers that append to a file need no re-sorting.
+    /// Up to `concurrency` calls stay in flight, but one slow page delays the
+    /// pages behind it. Has no effect on `convert` (always ordered) or with
+    /// `maintain_format` (already sequential).
+    pub ordered_stream: bool,
+
+    /// Split very large or very dense pages into tiles converted separately. Default: false.
+    ///
+    /// A broadsheet newspaper page or a 6-point-font data sheet squeezed into
+    /// `max_rendered_pixels` leaves too few pixels per glyph for reliable
+    /// reading. With tiling on, such pages are rendered at a higher resolution,
+    /// cut into halves or quadrants that each fit the pixel cap, sent to the
+    /// VLM one tile at a time, and the tile Markdown is joined back into one
+    /// page. Costs one VLM call per tile, so only pages over the thresholds in
+    /// [`crate::pipeline::render::tile_grid`] are split.
+    pub tile_dense_pages: bool,
+
+    /// Detect multi-column layouts and tell the VLM about them. Default: false.
+    ///
+    /// Uses the PDF text layer (no extra VLM call) to spot 2- and 3-column
+    /// pages and adds a reading-order instruction ("read the left column fully
+    /// before the right column") to that page's request. Pages without a text
+    /// layer, such as scans, are sent unchanged.
+    pub layout_hints: bool,
+
+    /// Use a tagged PDF's structure tree for headings. Default: false.
+    ///
+    /// Pages with tagged headings get them listed, with their levels, in the
+    /// request, and the returned Markdown's matching headings are set to
+    /// those levels. No extra VLM call; untagged pages are sent unchanged.
+    /// See [`crate::tagged`].
+    pub structure_tags: bool,
+
+    /// Write alt text for each figure with an extra VLM call per figure.
+    /// Default: false.
+    ///
+    /// Figures are the raster images placed on a page; results go to
+    /// [`crate::PageResult::figures`]. See [`crate::figures`].
+    pub figure_captions: bool,
+
+    /// Directory to save figure images to, linked from the Markdown with
+    /// their alt text. Default: None (figures are described, not linked).
+    ///
+    /// Links name the directory by its last component, so write the
+    /// Markdown next to it. Only used with [`Self::figure_captions`].
+    pub figures_dir: Option<PathBuf>,
+
+    /// Ask the model to transcribe the data of bar, line, and pie charts
+    /// into a table beneath the chart's caption. Default: false.
+    ///
+    /// Only affects the built-in and preset prompts. The tables are also
+    /// parsed into [`crate::PageResult::charts`]. See [`crate::charts`].
+    pub chart_tables: bool,
+
+    /// Ask the model to mark sheet music, technical drawings, and maps
+    /// instead of describing them, and put a placeholder block in their
+    /// place. Default: false.
+    ///
+    /// Only affects the built-in and preset prompts. The class goes to
+    /// [`crate::PageResult::content_class`]. See [`crate::niche`].
+    pub niche_placeholders: bool,
+
+    /// Directory holding page thumbnails named `page-NNNN.jpg`, as written
+    /// by `pdf2md --thumbnails-dir`. Default: None.
+    ///
+    /// Placeholders of [`Self::niche_placeholders`] link to the page's
+    /// thumbnail, naming the directory by its last component. Nothing is
+    /// written there by the conversion itself.
+    pub placeholder_thumbnails: Option<PathBuf>,
+
+    /// What to do with table-of-contents pages. Default:
+    /// [`TocHandling::Keep`].
+    ///
+    /// A page is a contents page when [`Self::page_classifier`] says so or
+    /// its Markdown has a "Contents" heading or mostly dot-leader lines;
+    /// either way its [`crate::PageResult::page_kind`] is set. See
+    /// [`crate::toc`].
+    pub toc_handling: TocHandling,
+
+    /// Skip blank pages without a VLM call. Default: true.
+    ///
+    /// Pages with no text layer whose rendered pixels barely vary (see
+    /// [`crate::pipeline::blank`]) are returned as empty results flagged
+    /// `skipped_blank` and counted in `ConversionStats::skipped_pages`.
+    /// Scans are full of separator sheets that would otherwise each cost a
+    /// full API call.
+    pub skip_blank_pages: bool,
+
+    /// Turn sideways pages upright before sending them. Default: false.
+    ///
+    /// Landscape tables and figures printed on portrait pages come back
+    /// garbled when the model sees them sideways. With this on, the angle of
+    /// the page's text-layer characters decides whether to rotate the render
+    /// by 90°, 180°, or 270° (see [`crate::pipeline::layout::detect_rotation`]);
+    /// the turn is recorded in `PageResult::rotation_applied`. The page's
+    /// `/Rotate` flag is always honoured by rendering, so scans without a
+    /// text layer are left as they are.
+    pub auto_rotate: bool,
+
+    /// Split two-page book spreads and convert each half separately. Default: false.
+    ///
+    /// Scans that hold two facing pages per PDF page are read straight across
+    /// the gutter otherwise. A landscape render with a quiet vertical band near
+    /// the centre (see [`crate::pipeline::spread::detect_spread`]) is cut
+    /// there; the left and right pages are converted in order and joined into
+    /// one `PageResult`. Ignored for pages already split by
+    /// [`Self::tile_dense_pages`].
+    pub split_spreads: bool,
+
+    /// Send pages as 8-bit grayscale PNG. Default: false.
+    ///
+    /// One byte per pixel instead of four: text-only pages shrink to about
+    /// half with no loss a model can read. Colour is lost, so leave it off
+    /// for charts or maps that rely on it.
+    pub grayscale: bool,
+
+    /// Send pages as palettised PNG, at most 256 colours. Default: false.
+    ///
+    /// Like [`Self::grayscale`] but keeps colour: pages with few colours are
+    /// stored exactly, photos are reduced by median cut (see
+    /// [`crate::pipeline::encode::encode_indexed_png`]). Ignored when
+    /// `grayscale` is on.
+    pub png_palette: bool,
+
     /// Fidelity tier controlling prompt complexity and output richness. Default: [`FidelityTier::Tier2`].
     ///
     /// Higher tiers instruct the VLM to handle more complex constructs (LaTeX,
@@ -127,21 +472,210 @@ pub struct ConversionConfig {
     /// slightly slow responses. Tier2 is the right default for most documents.
     pub fidelity: FidelityTier,
 
+    /// What the pages are written in. Default: [`ContentHint::Printed`].
+    ///
+    /// [`ContentHint::Handwritten`] switches to a transcription prompt that
+    /// marks uncertain words with `[?]`, and renders at
+    /// [`crate::handwriting::HANDWRITING_DPI`] when `dpi` is left at 150
+    /// (with a matching pixel cap when `max_rendered_pixels` is left at 2000).
+    /// [`ContentHint::Auto`] asks the model about a sample page first
+    /// ([`crate::convert`] only). See [`crate::handwriting`].
+    pub content_hint: ContentHint,
+
     /// Page selection. Default: All pages.
     pub pages: PageSelection,
 
+    /// Classify the selected pages before converting them, into
+    /// [`crate::PageResult::page_kind`]. Default: `None` (off), unless
+    /// `pages` uses [`PageSelection::ExcludeKinds`], which needs the kinds
+    /// and falls back to the heuristic. See [`crate::page_kind`].
+    pub page_classifier: Option<PageClassifier>,
+
+    /// Regions to convert instead of the whole page, by 1-based page number.
+    /// Default: empty.
+    ///
+    /// A listed page is rendered at up to
+    /// [`crate::pipeline::render::MAX_CROP_ZOOM`] times the usual size, and
+    /// each region is cut out and converted on its own, giving one
+    /// [`crate::PageResult`] per region. The page must still be selected by
+    /// [`Self::pages`]; dense-page tiling and spread splitting do not apply.
+    pub page_crops: HashMap<usize, Vec<CropRect>>,
+
+    /// Paint bands at the top and bottom of each rendered page white before
+    /// it is encoded, so running headers, footers, and page numbers never
+    /// reach the model. Default: None.
+    ///
+    /// Applied after [`Self::auto_rotate`] and before [`Self::image_filter`].
+    /// See [`crate::pipeline::margins`].
+    pub margin_mask: Option<MarginMask>,
+
+    /// Suppress diagonal watermarks such as "DRAFT" or "CONFIDENTIAL":
+    /// remove angled, translucent or very large text objects before
+    /// rendering and paint pale pixels white after. Default: false.
+    ///
+    /// Pale table shading and highlighting are whitened too. See
+    /// [`crate::pipeline::watermark`].
+    pub remove_watermarks: bool,
+
+    /// Which layers of the page pdfium draws into the image: page content,
+    /// annotations (stamps, highlights, sticky notes), and filled form
+    /// fields. Default: all three.
+    pub render_layers: RenderLayers,
+
+    /// How pdfium rasterises the page: anti-aliasing, LCD text, native text
+    /// drawing, byte order. Default: [`RenderOptions::default`], which
+    /// matches pdfium-render's own defaults.
+    pub render_options: RenderOptions,
+
     /// Page separator in assembled output. Default: None.
     pub page_separator: PageSeparator,
 
+    /// Markdown dialect of the output. Default: [`MarkdownFlavor::Gfm`].
+    ///
+    /// Adds dialect rules to the built-in and preset prompts (not to a
+    /// custom `system_prompt` or `prompt_template`) and rewrites each page
+    /// to the dialect during post-processing; see [`crate::pipeline::flavor`].
+    pub flavor: MarkdownFlavor,
+
+    /// Which `![alt](url)` links post-processing keeps. Default:
+    /// [`ImageLinkPolicy::StripAll`] — only absolute `http(s)` links survive;
+    /// the rest become `*alt*` captions.
+    pub image_link_policy: ImageLinkPolicy,
+
+    /// Unicode clean-up of each page: NFC, ligatures (`ﬁ` → `fi`), words
+    /// split by soft hyphens, and optionally quote and dash styles. Default:
+    /// everything but the quote and dash mapping. See
+    /// [`crate::pipeline::unicode`].
+    pub unicode: UnicodePolicy,
+
     /// Include YAML front-matter with document metadata. Default: false.
     pub include_metadata: bool,
 
+    /// Keep each page's Markdown as returned by the model. Default: false.
+    ///
+    /// Stored in [`crate::PageResult::raw_markdown`] before the post-processing
+    /// cleanup runs, for debugging cleanup regressions or comparing raw and
+    /// cleaned output. Roughly doubles the memory held per page. Not kept
+    /// when [`Self::pii`] is set: the raw text is never masked.
+    pub keep_raw_output: bool,
+
+    /// Keep the image each page was sent to the model as. Default: false.
+    ///
+    /// Stored in [`crate::PageResult::page_image`], for callers that show or
+    /// save the page next to its Markdown without rendering it again. Split
+    /// pages keep their first part; blank and unrenderable pages keep none.
+    /// Holds one encoded image per page in memory.
+    pub keep_page_images: bool,
+
+    /// Largest number of pages one conversion may process. Default: None (no limit).
+    ///
+    /// Counts the pages left after [`Self::pages`] is applied. A larger
+    /// selection fails with [`Pdf2MdError::DocumentTooLarge`] before any page
+    /// is rendered, so a server never pays for an accidental 5,000-page upload.
+    pub max_pages: Option<usize>,
+
+    /// Largest input file, in bytes, a conversion will open. Default: None (no limit).
+    ///
+    /// Checked right after the input is resolved (for URLs, once downloaded),
+    /// before the PDF is parsed. Fails with [`Pdf2MdError::DocumentTooLarge`].
+    pub max_file_size_bytes: Option<u64>,
+
+    /// Token prices that override or extend the built-in table. Default: empty.
+    ///
+    /// Keys are model names or name prefixes; see [`crate::pricing`] for how
+    /// a model is matched. Used to fill [`crate::PageResult::cost_usd`] and
+    /// [`crate::ConversionStats::total_cost_usd`].
+    pub model_prices: BTreeMap<String, ModelPrice>,
+
     /// Download timeout for URL inputs in seconds. Default: 120.
     pub download_timeout_secs: u64,
 
     /// Per-VLM-call timeout in seconds. Default: 60.
     pub api_timeout_secs: u64,
 
+    /// Summarise the converted document with an extra LLM pass. Default: None.
+    ///
+    /// The abstract is stored in [`crate::ConversionOutput::summary`] and,
+    /// with [`SummaryConfig::prepend`], placed at the top of the Markdown.
+    /// Costs one text-only call per `chunk_chars` of Markdown plus one to
+    /// combine them. Applies to [`crate::convert`] only.
+    pub summary: Option<SummaryConfig>,
+
+    /// Extract keywords, named entities, and the document type. Default: None.
+    ///
+    /// Stored in [`crate::ConversionOutput::enrichment`] and, with
+    /// `include_metadata`, added to the YAML front-matter. One text-only call
+    /// per chunk of Markdown. Applies to [`crate::convert`] only.
+    pub enrichment: Option<EnrichmentConfig>,
+
+    /// Fix OCR-style typos with a cheap text model. Default: None.
+    ///
+    /// One text-only call per page; answers that change structure or edit
+    /// too much are discarded. Applies to [`crate::convert`] only. See
+    /// [`crate::polish`].
+    pub text_polish: Option<PolishConfig>,
+
+    /// Parse the bibliography and link citation markers to it. Default: false.
+    ///
+    /// Entries land in [`crate::ConversionOutput::references`]; markers such
+    /// as `[3]` become footnote references. Local and free: no model calls.
+    /// Applies to [`crate::convert`] only. See [`crate::references`].
+    pub parse_references: bool,
+
+    /// Turn printed footnotes into Markdown footnotes and gather them at the
+    /// end of the document. Default: false.
+    ///
+    /// Markers such as `²` or `<sup>2</sup>` become `[^2]`, with labels
+    /// made unique across pages. Local and free: no model calls. Page
+    /// normalisation applies to every API; relocation to the document end to
+    /// [`crate::convert`] only. See [`crate::footnotes`].
+    pub link_footnotes: bool,
+
+    /// Directory to save the PDF's embedded files to. Default: None.
+    ///
+    /// Attachments are always listed in
+    /// [`crate::DocumentMetadata::attachments`]; with a directory each is
+    /// also written there and its path recorded. Created if missing. See
+    /// [`crate::attachments`].
+    pub attachments_dir: Option<PathBuf>,
+
+    /// Parse an embedded ZUGFeRD / Factur-X invoice XML into
+    /// [`crate::Attachment::invoice`]. Default: false.
+    ///
+    /// Local and exact: the figures come from the XML, not the page image.
+    pub parse_einvoice: bool,
+
+    /// Options for [`crate::extract_structured`]: scope and repair attempts.
+    /// Ignored by [`crate::convert`].
+    pub extraction: ExtractionConfig,
+
+    /// Detect and mask (or tag) personal data in the output. Default: None.
+    ///
+    /// Emails, phone numbers, SSNs, and IBANs are found by regex on every
+    /// page, in the streaming APIs too; person names need
+    /// [`PiiConfig::llm_names`] and [`crate::convert`]. Findings land in
+    /// [`crate::ConversionOutput::pii_findings`]. See [`crate::pii`].
+    pub pii: Option<PiiConfig>,
+
+    /// Append a JSONL audit record of the run to this file. Default: None.
+    ///
+    /// One line per event: conversion start, page start/complete/error (with
+    /// retries and tokens), and conversion end. See [`crate::event_log`].
+    pub event_log: Option<PathBuf>,
+
+    /// Write each page's model calls to `page-NNNN.jsonl` in this directory:
+    /// prompt, image hash, and response, with API keys redacted.
+    /// Default: None. See [`crate::transcript`].
+    pub transcript_dir: Option<PathBuf>,
+
+    /// POST a JSON notification when the conversion finishes or fails.
+    /// Default: None.
+    ///
+    /// Sent by [`crate::convert`] and the functions built on it, never by the
+    /// streaming APIs. Delivery failures are logged, not returned. See
+    /// [`crate::webhook`].
+    pub webhook: Option<WebhookConfig>,
+
     /// Optional progress callback invoked per-page during conversion.
     ///
     /// When set, the library calls [`ConversionProgressCallback`] methods at
@@ -165,60 +699,297 @@ pub struct ConversionConfig {
     ///     .unwrap();
     /// ```
     /// This field is intentionally not serialisable; use the builder to set it at runtime.
+    #[serde(skip)]
     pub progress_callback: Option<Arc<dyn ConversionProgressCallback>>,
+
+    /// Parent trace context forwarded as `traceparent`/`tracestate` headers.
+    ///
+    /// Only OpenAI-compatible requests carry it: those made with
+    /// `base_url`/`extra_headers` set, or with `provider_name = "openai"`.
+    #[cfg(feature = "otel")]
+    #[serde(skip)]
+    pub trace_context: Option<crate::otel::TraceContext>,
+
+    /// Optional sink for per-stage durations, token counts, and retries.
+    ///
+    /// See [`crate::metrics`] for the stages reported and an example.
+    #[serde(skip)]
+    pub metrics_sink: Option<Arc<dyn MetricsSink>>,
+
+    /// Embeds Markdown chunks for [`crate::sqlite::convert_to_sqlite`].
+    /// Default: None (chunks are stored without embeddings).
+    #[serde(skip)]
+    pub embedding_provider: Option<Arc<dyn EmbeddingProvider>>,
+
+    /// Transform each rendered page image before it is encoded. Default: None.
+    ///
+    /// Called with the 1-based page number and the full-page render, on the
+    /// render thread, before tiling and encoding. Blank out signatures, faces,
+    /// or account numbers here, and the original pixels never leave the
+    /// machine. The filter must not panic; a panic stops rendering of the
+    /// remaining pages.
+    #[serde(skip)]
+    pub image_filter: Option<ImageFilter>,
+
+    /// Inspect each page's Markdown before it is stored or assembled.
+    /// Default: None.
+    ///
+    /// Called with the 1-based page number and the page's text after
+    /// post-processing, and before PII masking and text polish, so a data
+    /// loss prevention policy sees the text as extracted. Blank and failed
+    /// pages are not passed. See [`FilterDecision`] for what the answer does.
+    #[serde(skip)]
+    pub output_filter: Option<OutputFilter>,
+}
+
+/// Per-page image hook; see [`ConversionConfig::image_filter`].
+pub type ImageFilter = Arc<dyn Fn(usize, DynamicImage) -> DynamicImage + Send + Sync>;
+
+/// Per-page Markdown hook; see [`ConversionConfig::output_filter`].
+pub type OutputFilter = Arc<dyn Fn(usize, String) -> FilterDecision + Send + Sync>;
+
+/// What [`ConversionConfig::output_filter`] does with a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterDecision {
+    /// Keep the page's Markdown as it is.
+    Keep,
+    /// Replace the page's Markdown with this text.
+    Redact(String),
+    /// Drop the page's text. The page keeps its place in the output, with
+    /// empty Markdown, so page numbers and statistics stay aligned.
+    Drop,
+}
+
+// The derives above are `remote = "Self"`, which makes them the inherent
+// `ConversionConfig::{serialize, deserialize}`; these impls wrap them so
+// every deserialised config is validated like a built one.
+impl Serialize for ConversionConfig {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ConversionConfig::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConversionConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let config = ConversionConfig::deserialize(deserializer)?;
+        config.validate().map_err(serde::de::Error::custom)?;
+        Ok(config)
+    }
 }
 
 impl Default for ConversionConfig {
     fn default() -> Self {
         Self {
             dpi: 150,
+            auto_dpi: None,
             max_rendered_pixels: 2000,
             concurrency: 10,
+            render_workers: 1,
             model: None,
             provider_name: None,
             provider: None,
+            backend: None,
+            provider_resolver: None,
+            fallback_models: Vec::new(),
+            base_url: None,
+            extra_headers: HeaderMap::new(),
+            api_key: None,
+            credentials: ProviderCredentials::default(),
+            local_only: false,
             temperature: 0.1,
+            seed: None,
             max_tokens: 4096,
+            max_tokens_retry_cap: None,
             max_retries: 3,
             retry_backoff_ms: 500,
+            failure_abort_threshold: 5,
+            strictness: Strictness::default(),
             password: None,
             system_prompt: None,
+            prompt_template: None,
+            few_shot_examples: Vec::new(),
+            preset: None,
             maintain_format: false,
+            context_window_pages: 1,
+            parallel_sections: false,
+            ordered_stream: false,
+            tile_dense_pages: false,
+            layout_hints: false,
+            structure_tags: false,
+            figure_captions: false,
+            figures_dir: None,
+            chart_tables: false,
+            niche_placeholders: false,
+            placeholder_thumbnails: None,
+            toc_handling: TocHandling::default(),
+            skip_blank_pages: true,
+            auto_rotate: false,
+            split_spreads: false,
+            grayscale: false,
+            png_palette: false,
             fidelity: FidelityTier::default(),
+            content_hint: ContentHint::default(),
             pages: PageSelection::default(),
+            page_classifier: None,
+            page_crops: HashMap::new(),
+            margin_mask: None,
+            remove_watermarks: false,
+            render_layers: RenderLayers::default(),
+            render_options: RenderOptions::default(),
             page_separator: PageSeparator::default(),
+            flavor: MarkdownFlavor::default(),
+            image_link_policy: ImageLinkPolicy::default(),
+            unicode: UnicodePolicy::default(),
             include_metadata: false,
+            keep_raw_output: false,
+            keep_page_images: false,
+            max_pages: None,
+            max_file_size_bytes: None,
+            model_prices: BTreeMap::new(),
             download_timeout_secs: 120,
             api_timeout_secs: 60,
+            summary: None,
+            enrichment: None,
+            text_polish: None,
+            parse_references: false,
+            link_footnotes: false,
+            attachments_dir: None,
+            parse_einvoice: false,
+            extraction: ExtractionConfig::default(),
+            pii: None,
+            event_log: None,
+            transcript_dir: None,
+            webhook: None,
             progress_callback: None,
+            #[cfg(feature = "otel")]
+            trace_context: None,
+            metrics_sink: None,
+            embedding_provider: None,
+            image_filter: None,
+            output_filter: None,
         }
     }
 }
 
 impl fmt::Debug for ConversionConfig {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("ConversionConfig")
-            .field("dpi", &self.dpi)
+        let mut d = f.debug_struct("ConversionConfig");
+        d.field("dpi", &self.dpi)
+            .field("auto_dpi", &self.auto_dpi)
             .field("max_rendered_pixels", &self.max_rendered_pixels)
             .field("concurrency", &self.concurrency)
+            .field("render_workers", &self.render_workers)
             .field("model", &self.model)
             .field("provider_name", &self.provider_name)
             .field(
                 "provider",
                 &self.provider.as_ref().map(|_| "<dyn LLMProvider>"),
             )
+            .field("backend", &self.backend.as_ref().map(|b| b.name()))
+            .field(
+                "provider_resolver",
+                &self.provider_resolver.as_ref().map(|_| "<resolver>"),
+            )
+            .field("fallback_models", &self.fallback_models)
+            .field("base_url", &self.base_url)
+            .field(
+                "extra_headers",
+                &self.extra_headers.keys().collect::<Vec<_>>(),
+            )
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("credentials", &self.credentials)
+            .field("local_only", &self.local_only)
             .field("temperature", &self.temperature)
+            .field("seed", &self.seed)
             .field("max_tokens", &self.max_tokens)
+            .field("max_tokens_retry_cap", &self.max_tokens_retry_cap)
             .field("max_retries", &self.max_retries)
+            .field("failure_abort_threshold", &self.failure_abort_threshold)
+            .field("strictness", &self.strictness)
+            .field("prompt_template", &self.prompt_template)
+            .field("few_shot_examples", &self.few_shot_examples.len())
+            .field("preset", &self.preset)
             .field("maintain_format", &self.maintain_format)
+            .field("context_window_pages", &self.context_window_pages)
+            .field("parallel_sections", &self.parallel_sections)
+            .field("ordered_stream", &self.ordered_stream)
+            .field("tile_dense_pages", &self.tile_dense_pages)
+            .field("layout_hints", &self.layout_hints)
+            .field("structure_tags", &self.structure_tags)
+            .field("figure_captions", &self.figure_captions)
+            .field("figures_dir", &self.figures_dir)
+            .field("chart_tables", &self.chart_tables)
+            .field("niche_placeholders", &self.niche_placeholders)
+            .field("placeholder_thumbnails", &self.placeholder_thumbnails)
+            .field("toc_handling", &self.toc_handling)
+            .field("skip_blank_pages", &self.skip_blank_pages)
+            .field("auto_rotate", &self.auto_rotate)
+            .field("split_spreads", &self.split_spreads)
+            .field("grayscale", &self.grayscale)
+            .field("png_palette", &self.png_palette)
             .field("fidelity", &self.fidelity)
+            .field("content_hint", &self.content_hint)
             .field("pages", &self.pages)
+            .field("page_classifier", &self.page_classifier)
+            .field("page_crops", &self.page_crops)
+            .field("margin_mask", &self.margin_mask)
+            .field("remove_watermarks", &self.remove_watermarks)
+            .field("render_layers", &self.render_layers)
+            .field("render_options", &self.render_options)
+            .field("max_pages", &self.max_pages)
+            .field("max_file_size_bytes", &self.max_file_size_bytes)
+            .field("model_prices", &self.model_prices)
             .field("page_separator", &self.page_separator)
+            .field("flavor", &self.flavor)
+            .field("image_link_policy", &self.image_link_policy)
+            .field("unicode", &self.unicode)
+            .field("summary", &self.summary)
+            .field("enrichment", &self.enrichment)
+            .field("text_polish", &self.text_polish)
+            .field("parse_references", &self.parse_references)
+            .field("link_footnotes", &self.link_footnotes)
+            .field("attachments_dir", &self.attachments_dir)
+            .field("parse_einvoice", &self.parse_einvoice)
+            .field("extraction", &self.extraction)
+            .field("pii", &self.pii)
+            .field("event_log", &self.event_log)
+            .field("transcript_dir", &self.transcript_dir)
+            .field(
+                "webhook",
+                &self.webhook.as_ref().map(|w| {
+                    (
+                        &w.url,
+                        w.headers.keys().collect::<Vec<_>>(),
+                        w.include_markdown,
+                    )
+                }),
+            )
             .field(
                 "progress_callback",
                 &self.progress_callback.as_ref().map(|_| "<callback>"),
             )
-            .finish()
+            .field(
+                "metrics_sink",
+                &self.metrics_sink.as_ref().map(|_| "<metrics sink>"),
+            )
+            .field(
+                "embedding_provider",
+                &self
+                    .embedding_provider
+                    .as_ref()
+                    .map(|_| "<dyn EmbeddingProvider>"),
+            )
+            .field(
+                "image_filter",
+                &self.image_filter.as_ref().map(|_| "<image filter>"),
+            )
+            .field(
+                "output_filter",
+                &self.output_filter.as_ref().map(|_| "<output filter>"),
+            );
+        #[cfg(feature = "otel")]
+        d.field("trace_context", &self.trace_context);
+        d.finish()
     }
 }
 
@@ -227,7 +998,166 @@ impl ConversionConfig {
     pub fn builder() -> ConversionConfigBuilder {
         ConversionConfigBuilder {
             config: Self::default(),
+            error: None,
+        }
+    }
+
+    /// Where `page` sorts among results for the same page number: its
+    /// region's position in [`Self::page_crops`], 0 for a whole page.
+    pub(crate) fn region_rank(&self, page: &crate::PageResult) -> usize {
+        page.region
+            .and_then(|r| {
+                self.page_crops
+                    .get(&page.page_num)?
+                    .iter()
+                    .position(|c| *c == r)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Check the settings [`ConversionConfigBuilder::build`] checks.
+    ///
+    /// Deserialising a config runs this too, so a payload that skipped the
+    /// builder cannot carry, say, a zero `concurrency` that would hang the
+    /// run. Call it yourself after editing fields directly.
+    pub fn validate(&self) -> Result<(), Pdf2MdError> {
+        if self.dpi < 72 || self.dpi > 400 {
+            return Err(Pdf2MdError::InvalidConfig(format!(
+                "DPI must be 72–400, got {}",
+                self.dpi
+            )));
+        }
+        if self.concurrency == 0 {
+            return Err(Pdf2MdError::InvalidConfig("Concurrency must be ≥ 1".into()));
+        }
+        if self.context_window_pages == 0 {
+            return Err(Pdf2MdError::InvalidConfig(
+                "Context window must be ≥ 1 page".into(),
+            ));
+        }
+        if self.max_pages == Some(0) {
+            return Err(Pdf2MdError::InvalidConfig("max_pages must be ≥ 1".into()));
+        }
+        if self.seed.is_some() && self.temperature != 0.0 {
+            return Err(Pdf2MdError::InvalidConfig(format!(
+                "A seed requires temperature 0, got {}",
+                self.temperature
+            )));
+        }
+        if let Some(ref hook) = self.webhook {
+            hook.validate()?;
+            if self.local_only && !crate::convert::is_loopback_url(&hook.url) {
+                return Err(Pdf2MdError::InvalidConfig(format!(
+                    "local_only allows only a loopback webhook, got '{}'",
+                    hook.url
+                )));
+            }
+        }
+        if let Some(ref auto) = self.auto_dpi {
+            auto.validate()?;
+        }
+        if let Some(ref mask) = self.margin_mask {
+            mask.validate()?;
+        }
+        self.render_layers.validate()?;
+        for (&page, regions) in &self.page_crops {
+            if page == 0 {
+                return Err(Pdf2MdError::InvalidConfig(
+                    "page_crops page numbers start at 1".into(),
+                ));
+            }
+            for region in regions {
+                region.validate(page)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A copy without the PDF password, API keys, endpoint details
+    /// (`base_url`, `extra_headers`), or webhook headers, which may carry
+    /// credentials. The webhook URL is cut down to its origin, since
+    /// receivers often take a token in the path, query, or userinfo.
+    ///
+    /// Serialise this instead of `self` when logging a config or storing it
+    /// outside a trusted boundary.
+    pub fn redact_secrets(&self) -> Self {
+        Self {
+            password: None,
+            base_url: None,
+            extra_headers: HeaderMap::new(),
+            api_key: None,
+            credentials: ProviderCredentials::default(),
+            webhook: self.webhook.clone().map(|w| WebhookConfig {
+                url: url_origin(&w.url),
+                headers: BTreeMap::new(),
+                ..w
+            }),
+            ..self.clone()
+        }
+    }
+
+    /// Token price of `model`, from [`Self::model_prices`] or the built-in
+    /// table in [`crate::pricing`].
+    pub fn price_for(&self, model: &str) -> Option<ModelPrice> {
+        crate::pricing::price_for(model, &self.model_prices)
+    }
+
+    /// The page prompt in effect: [`Self::prompt_template`], else
+    /// [`Self::system_prompt`], else the handwriting prompt for
+    /// [`ContentHint::Handwritten`], else the prompt of [`Self::preset`],
+    /// else the built-in template for [`Self::fidelity`]. The last three get
+    /// the [`Self::flavor`] rules appended, and the chart and placeholder
+    /// rules with [`Self::chart_tables`] and [`Self::niche_placeholders`].
+    pub fn page_prompt_template(&self) -> PromptTemplate {
+        let handwritten = self.content_hint == ContentHint::Handwritten;
+        let mut builtin = match (&self.prompt_template, &self.system_prompt, self.preset) {
+            (Some(t), _, _) => return t.clone(),
+            (None, Some(s), _) => return PromptTemplate::new(s.clone()),
+            _ if handwritten => crate::prompts::HANDWRITING_SYSTEM_PROMPT.to_string(),
+            (None, None, Some(p)) => p.prompt().to_string(),
+            (None, None, None) => PromptTemplate::builtin(self.fidelity).text().to_string(),
+        };
+        if let Some(rules) = self.flavor.prompt_rules() {
+            builtin.push_str(rules);
+        }
+        if self.chart_tables {
+            builtin.push_str(crate::prompts::CHART_DATA_RULES);
+        }
+        if self.niche_placeholders {
+            builtin.push_str(crate::prompts::NICHE_CONTENT_RULES);
+        }
+        PromptTemplate::new(builtin)
+    }
+}
+
+/// `HeaderMap` as a `{"name": "value"}` map; non-UTF-8 values are an error.
+mod header_map_serde {
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(headers: &HeaderMap, s: S) -> Result<S::Ok, S::Error> {
+        let mut map = BTreeMap::new();
+        for (name, value) in headers {
+            let value = value
+                .to_str()
+                .map_err(|e| S::Error::custom(format!("header '{name}': {e}")))?;
+            map.insert(name.as_str(), value);
+        }
+        map.serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<HeaderMap, D::Error> {
+        let map = BTreeMap::<String, String>::deserialize(d)?;
+        let mut headers = HeaderMap::new();
+        for (name, value) in map {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(D::Error::custom)?;
+            let value = HeaderValue::from_str(&value).map_err(D::Error::custom)?;
+            headers.insert(name, value);
         }
+        Ok(headers)
     }
 }
 
@@ -235,6 +1165,8 @@ impl ConversionConfig {
 #[derive(Debug)]
 pub struct ConversionConfigBuilder {
     config: ConversionConfig,
+    /// First failure of a chained call, returned by [`Self::build`].
+    error: Option<Pdf2MdError>,
 }
 
 impl ConversionConfigBuilder {
@@ -243,6 +1175,12 @@ impl ConversionConfigBuilder {
         self
     }
 
+    /// Choose the DPI per document; see [`crate::auto_dpi`].
+    pub fn auto_dpi(mut self, auto: AutoDpiConfig) -> Self {
+        self.config.auto_dpi = Some(auto);
+        self
+    }
+
     pub fn max_rendered_pixels(mut self, px: u32) -> Self {
         self.config.max_rendered_pixels = px.max(100);
         self
@@ -253,6 +1191,12 @@ impl ConversionConfigBuilder {
         self
     }
 
+    /// Render producers; see [`ConversionConfig::render_workers`].
+    pub fn render_workers(mut self, n: usize) -> Self {
+        self.config.render_workers = n.max(1);
+        self
+    }
+
     pub fn model(mut self, model: impl Into<String>) -> Self {
         self.config.model = Some(model.into());
         self
@@ -271,14 +1215,79 @@ impl ConversionConfigBuilder {
     ///
     /// # Resolution order
     /// 1. `config.provider` ← this method (highest priority)
-    /// 2. `config.provider_name` + `config.model` (named factory)
-    /// 3. `EDGEQUAKE_LLM_PROVIDER` + `EDGEQUAKE_MODEL` env vars
-    /// 4. `ProviderFactory::from_env()` (auto-detect — lowest priority)
+    /// 2. `config.base_url` / `config.extra_headers` (OpenAI-compatible endpoint)
+    /// 3. `config.provider_name` + `config.model` (named factory)
+    /// 4. `EDGEQUAKE_LLM_PROVIDER` + `EDGEQUAKE_MODEL` env vars
+    /// 5. `ProviderFactory::from_env()` (auto-detect — lowest priority)
     pub fn provider(mut self, provider: Arc<dyn LLMProvider>) -> Self {
         self.config.provider = Some(provider);
         self
     }
 
+    /// Inject a custom [`VisionBackend`] for page conversion.
+    ///
+    /// Overrides every provider setting for page calls; see
+    /// [`ConversionConfig::backend`].
+    pub fn backend(mut self, backend: Arc<dyn VisionBackend>) -> Self {
+        self.config.backend = Some(backend);
+        self
+    }
+
+    /// Resolve the provider per conversion (see [`crate::resolver`]).
+    pub fn provider_resolver(mut self, resolver: Arc<dyn ProviderResolver>) -> Self {
+        self.config.provider_resolver = Some(resolver);
+        self
+    }
+
+    /// Set the ordered list of fallback models (see [`ConversionConfig::fallback_models`]).
+    ///
+    /// # Example
+    /// ```rust
+    /// use edgequake_pdf2md::ConversionConfig;
+    ///
+    /// let config = ConversionConfig::builder()
+    ///     .provider_name("openrouter")
+    ///     .model("openai/gpt-4.1-nano")
+    ///     .fallback_models(["google/gemini-2.0-flash-001", "anthropic/claude-3.5-haiku"])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(config.fallback_models.len(), 2);
+    /// ```
+    pub fn fallback_models<I, S>(mut self, models: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.fallback_models = models.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Point the provider at an OpenAI-compatible endpoint (vLLM, LiteLLM, gateways).
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.config.base_url = Some(url.into());
+        self
+    }
+
+    /// Send additional HTTP headers with every VLM request.
+    ///
+    /// # Example
+    /// ```rust
+    /// use edgequake_pdf2md::{ConversionConfig, HeaderMap};
+    ///
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert("x-tenant", "acme".parse().unwrap());
+    /// let config = ConversionConfig::builder()
+    ///     .base_url("https://llm-gateway.internal/v1")
+    ///     .extra_headers(headers)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.extra_headers.contains_key("x-tenant"));
+    /// ```
+    pub fn extra_headers(mut self, headers: HeaderMap) -> Self {
+        self.config.extra_headers = headers;
+        self
+    }
+
     /// Set a progress callback for per-page conversion events.
     ///
     /// The callback is invoked at the start of conversion, before and after
@@ -289,87 +1298,604 @@ impl ConversionConfigBuilder {
         self
     }
 
-    pub fn temperature(mut self, t: f32) -> Self {
-        self.config.temperature = t.clamp(0.0, 2.0);
+    /// Summarise the converted document; see [`crate::summary`].
+    pub fn generate_summary(mut self, summary: SummaryConfig) -> Self {
+        self.config.summary = Some(summary);
         self
     }
 
-    pub fn max_tokens(mut self, n: usize) -> Self {
-        self.config.max_tokens = n;
+    /// Extract keywords, entities, and document type; see [`crate::enrich`].
+    pub fn enrich(mut self, enrichment: EnrichmentConfig) -> Self {
+        self.config.enrichment = Some(enrichment);
         self
     }
 
-    pub fn max_retries(mut self, n: u32) -> Self {
-        self.config.max_retries = n;
+    /// Fix OCR-style typos with a text model; see [`crate::polish`].
+    pub fn text_polish(mut self, polish: PolishConfig) -> Self {
+        self.config.text_polish = Some(polish);
         self
     }
 
-    pub fn retry_backoff_ms(mut self, ms: u64) -> Self {
-        self.config.retry_backoff_ms = ms;
+    /// Parse the bibliography and link citations; see [`crate::references`].
+    pub fn parse_references(mut self, v: bool) -> Self {
+        self.config.parse_references = v;
         self
     }
 
-    pub fn password(mut self, pwd: impl Into<String>) -> Self {
-        self.config.password = Some(pwd.into());
+    /// Link printed footnotes and move them to the end; see [`crate::footnotes`].
+    pub fn link_footnotes(mut self, v: bool) -> Self {
+        self.config.link_footnotes = v;
         self
     }
 
-    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
-        self.config.system_prompt = Some(prompt.into());
+    /// Save embedded files to `dir`; see [`ConversionConfig::attachments_dir`].
+    pub fn attachments_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.attachments_dir = Some(dir.into());
         self
     }
 
-    pub fn maintain_format(mut self, v: bool) -> Self {
-        self.config.maintain_format = v;
+    /// Parse embedded e-invoice XML; see [`crate::attachments`].
+    pub fn parse_einvoice(mut self, v: bool) -> Self {
+        self.config.parse_einvoice = v;
         self
     }
 
-    pub fn fidelity(mut self, tier: FidelityTier) -> Self {
-        self.config.fidelity = tier;
+    /// Options for [`crate::extract_structured`]; see [`crate::extract`].
+    pub fn extraction(mut self, extraction: ExtractionConfig) -> Self {
+        self.config.extraction = extraction;
         self
     }
 
-    pub fn pages(mut self, selection: PageSelection) -> Self {
-        self.config.pages = selection;
+    /// Mask or tag personal data in the output; see [`crate::pii`].
+    pub fn pii(mut self, pii: PiiConfig) -> Self {
+        self.config.pii = Some(pii);
         self
     }
 
-    pub fn page_separator(mut self, sep: PageSeparator) -> Self {
-        self.config.page_separator = sep;
+    pub fn event_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.event_log = Some(path.into());
         self
     }
 
-    pub fn include_metadata(mut self, v: bool) -> Self {
-        self.config.include_metadata = v;
+    /// Record every page's prompt, image hash, and response under `dir`.
+    pub fn transcript_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.transcript_dir = Some(dir.into());
         self
     }
 
-    pub fn download_timeout_secs(mut self, secs: u64) -> Self {
-        self.config.download_timeout_secs = secs;
+    /// Notify `webhook.url` when the conversion finishes; see [`crate::webhook`].
+    pub fn webhook(mut self, webhook: WebhookConfig) -> Self {
+        self.config.webhook = Some(webhook);
         self
     }
 
-    pub fn api_timeout_secs(mut self, secs: u64) -> Self {
+    pub fn metrics_sink(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.config.metrics_sink = Some(sink);
+        self
+    }
+
+    /// Embed chunks written by [`crate::sqlite::convert_to_sqlite`].
+    pub fn embedding_provider(mut self, provider: Arc<dyn EmbeddingProvider>) -> Self {
+        self.config.embedding_provider = Some(provider);
+        self
+    }
+
+    /// Redact or otherwise transform each page image before encoding.
+    ///
+    /// # Example
+    /// ```rust
+    /// use edgequake_pdf2md::{ConversionConfig, DynamicImage};
+    /// use std::sync::Arc;
+    ///
+    /// // Black out the bottom 15 % of every page (signature block).
+    /// let config = ConversionConfig::builder()
+    ///     .image_filter(Arc::new(|_page: usize, img: DynamicImage| {
+    ///         let mut rgb = img.to_rgb8();
+    ///         let (w, h) = rgb.dimensions();
+    ///         for y in h * 85 / 100..h {
+    ///             for x in 0..w {
+    ///                 rgb.put_pixel(x, y, image::Rgb([0, 0, 0]));
+    ///             }
+    ///         }
+    ///         DynamicImage::ImageRgb8(rgb)
+    ///     }))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.image_filter.is_some());
+    /// ```
+    pub fn image_filter(mut self, filter: ImageFilter) -> Self {
+        self.config.image_filter = Some(filter);
+        self
+    }
+
+    /// Keep, redact, or drop each page's Markdown before it is stored.
+    ///
+    /// # Example
+    /// ```rust
+    /// use edgequake_pdf2md::{ConversionConfig, FilterDecision};
+    /// use std::sync::Arc;
+    ///
+    /// // Withhold any page marked confidential.
+    /// let config = ConversionConfig::builder()
+    ///     .output_filter(Arc::new(|_page: usize, markdown: String| {
+    ///         if markdown.contains("CONFIDENTIAL") {
+    ///             FilterDecision::Redact("*[page withheld]*\n".into())
+    ///         } else {
+    ///             FilterDecision::Keep
+    ///         }
+    ///     }))
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.output_filter.is_some());
+    /// ```
+    pub fn output_filter(mut self, filter: OutputFilter) -> Self {
+        self.config.output_filter = Some(filter);
+        self
+    }
+
+    /// Propagate a parent trace into VLM requests (feature `otel`).
+    ///
+    /// # Example
+    /// ```rust,ignore
+    /// use edgequake_pdf2md::{ConversionConfig, TraceContext};
+    ///
+    /// let config = ConversionConfig::builder()
+    ///     .provider_name("openai")
+    ///     .trace_context(TraceContext::current().expect("inside a traced span"))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "otel")]
+    pub fn trace_context(mut self, cx: crate::otel::TraceContext) -> Self {
+        self.config.trace_context = Some(cx);
+        self
+    }
+
+    pub fn temperature(mut self, t: f32) -> Self {
+        self.config.temperature = t.clamp(0.0, 2.0);
+        self
+    }
+
+    /// Sample with `seed` at temperature 0, for repeatable conversions.
+    ///
+    /// A later [`Self::temperature`] above 0 makes `build()` fail.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self.config.temperature = 0.0;
+        self
+    }
+
+    pub fn max_tokens(mut self, n: usize) -> Self {
+        self.config.max_tokens = n;
+        self
+    }
+
+    /// Retry truncated pages with a doubled `max_tokens`, up to `cap` tokens.
+    pub fn max_tokens_retry_cap(mut self, cap: usize) -> Self {
+        self.config.max_tokens_retry_cap = Some(cap);
+        self
+    }
+
+    pub fn max_retries(mut self, n: u32) -> Self {
+        self.config.max_retries = n;
+        self
+    }
+
+    pub fn retry_backoff_ms(mut self, ms: u64) -> Self {
+        self.config.retry_backoff_ms = ms;
+        self
+    }
+
+    /// Abort the run when the first `n` pages all fail with auth or
+    /// connection errors. `0` disables the check.
+    pub fn failure_abort_threshold(mut self, n: usize) -> Self {
+        self.config.failure_abort_threshold = n;
+        self
+    }
+
+    /// Choose whether page failures abort the run or fail the result; see [`Strictness`].
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.config.strictness = strictness;
+        self
+    }
+
+    /// Use this API key instead of reading one from the environment.
+    ///
+    /// # Example
+    /// ```rust
+    /// use edgequake_pdf2md::ConversionConfig;
+    ///
+    /// let key = "sk-from-vault".to_string(); // e.g. fetched per tenant
+    /// let config = ConversionConfig::builder()
+    ///     .provider_name("anthropic")
+    ///     .api_key(key)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.api_key.is_some());
+    /// ```
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.config.api_key = Some(key.into());
+        self
+    }
+
+    /// Per-provider API keys, looked up by provider name.
+    pub fn credentials(mut self, credentials: ProviderCredentials) -> Self {
+        self.config.credentials = credentials;
+        self
+    }
+
+    /// Only talk to providers on this machine; see [`ConversionConfig::local_only`].
+    pub fn local_only(mut self, v: bool) -> Self {
+        self.config.local_only = v;
+        self
+    }
+
+    pub fn password(mut self, pwd: impl Into<String>) -> Self {
+        self.config.password = Some(pwd.into());
+        self
+    }
+
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.config.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Page prompt with variables; see [`ConversionConfig::prompt_template`].
+    pub fn prompt_template(mut self, template: PromptTemplate) -> Self {
+        self.config.prompt_template = Some(template);
+        self
+    }
+
+    /// Start from the settings, prompt, and cleanup of a document class.
+    ///
+    /// Call it first: builder calls after it override the preset's
+    /// settings. See [`crate::presets`].
+    ///
+    /// ```rust
+    /// use edgequake_pdf2md::{ConversionConfig, DocumentPreset, FidelityTier};
+    ///
+    /// let config = ConversionConfig::builder()
+    ///     .preset(DocumentPreset::ScientificPaper)
+    ///     .fidelity(FidelityTier::Tier2)
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(config.layout_hints);
+    /// assert_eq!(config.fidelity, FidelityTier::Tier2);
+    /// ```
+    pub fn preset(mut self, preset: DocumentPreset) -> Self {
+        self.config.preset = Some(preset);
+        let profile = preset.profile();
+        match profile.headers() {
+            Ok(headers) => profile.apply_parsed(self, headers),
+            Err(e) => {
+                self.error.get_or_insert(e);
+                self
+            }
+        }
+    }
+
+    /// Example conversions shown before every page; see
+    /// [`ConversionConfig::few_shot_examples`].
+    ///
+    /// ```rust,no_run
+    /// use edgequake_pdf2md::{ConversionConfig, FewShotExample};
+    ///
+    /// let config = ConversionConfig::builder()
+    ///     .few_shot_examples(vec![
+    ///         FewShotExample::from_files("examples/invoice-1.png", "examples/invoice-1.md")?,
+    ///         FewShotExample::from_files("examples/invoice-2.png", "examples/invoice-2.md")?,
+    ///     ])
+    ///     .build()?;
+    /// # Ok::<(), edgequake_pdf2md::Pdf2MdError>(())
+    /// ```
+    pub fn few_shot_examples(mut self, examples: Vec<FewShotExample>) -> Self {
+        self.config.few_shot_examples = examples;
+        self
+    }
+
+    pub fn maintain_format(mut self, v: bool) -> Self {
+        self.config.maintain_format = v;
+        self
+    }
+
+    /// Pass the last `n` converted pages as context in `maintain_format` mode.
+    pub fn context_window_pages(mut self, n: usize) -> Self {
+        self.config.context_window_pages = n;
+        self
+    }
+
+    /// Convert `maintain_format` chapters in parallel; see [`ConversionConfig::parallel_sections`].
+    pub fn parallel_sections(mut self, v: bool) -> Self {
+        self.config.parallel_sections = v;
+        self
+    }
+
+    pub fn ordered_stream(mut self, v: bool) -> Self {
+        self.config.ordered_stream = v;
+        self
+    }
+
+    pub fn tile_dense_pages(mut self, v: bool) -> Self {
+        self.config.tile_dense_pages = v;
+        self
+    }
+
+    pub fn layout_hints(mut self, v: bool) -> Self {
+        self.config.layout_hints = v;
+        self
+    }
+
+    /// Guide and correct heading levels with a tagged PDF's structure tree;
+    /// see [`ConversionConfig::structure_tags`].
+    pub fn structure_tags(mut self, v: bool) -> Self {
+        self.config.structure_tags = v;
+        self
+    }
+
+    /// Describe each figure for alt text; see [`ConversionConfig::figure_captions`].
+    pub fn figure_captions(mut self, v: bool) -> Self {
+        self.config.figure_captions = v;
+        self
+    }
+
+    /// Save figures to `dir` and link them; see [`ConversionConfig::figures_dir`].
+    pub fn figures_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.figures_dir = Some(dir.into());
+        self
+    }
+
+    /// Transcribe chart data into tables; see [`ConversionConfig::chart_tables`].
+    pub fn chart_tables(mut self, v: bool) -> Self {
+        self.config.chart_tables = v;
+        self
+    }
+
+    /// Replace untranscribable content with placeholders; see
+    /// [`ConversionConfig::niche_placeholders`].
+    pub fn niche_placeholders(mut self, v: bool) -> Self {
+        self.config.niche_placeholders = v;
+        self
+    }
+
+    /// Link placeholders to the thumbnails in `dir`; see
+    /// [`ConversionConfig::placeholder_thumbnails`].
+    pub fn placeholder_thumbnails(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.placeholder_thumbnails = Some(dir.into());
+        self
+    }
+
+    /// Keep, tidy, skip, or regenerate contents pages; see [`TocHandling`].
+    pub fn toc_handling(mut self, handling: TocHandling) -> Self {
+        self.config.toc_handling = handling;
+        self
+    }
+
+    pub fn skip_blank_pages(mut self, v: bool) -> Self {
+        self.config.skip_blank_pages = v;
+        self
+    }
+
+    pub fn auto_rotate(mut self, v: bool) -> Self {
+        self.config.auto_rotate = v;
+        self
+    }
+
+    pub fn split_spreads(mut self, v: bool) -> Self {
+        self.config.split_spreads = v;
+        self
+    }
+
+    /// Send pages as grayscale PNG; see [`ConversionConfig::grayscale`].
+    pub fn grayscale(mut self, v: bool) -> Self {
+        self.config.grayscale = v;
+        self
+    }
+
+    /// Send pages as palettised PNG; see [`ConversionConfig::png_palette`].
+    pub fn png_palette(mut self, v: bool) -> Self {
+        self.config.png_palette = v;
+        self
+    }
+
+    pub fn fidelity(mut self, tier: FidelityTier) -> Self {
+        self.config.fidelity = tier;
+        self
+    }
+
+    /// Say whether pages are printed or handwritten; see [`ContentHint`].
+    pub fn content_hint(mut self, hint: ContentHint) -> Self {
+        self.config.content_hint = hint;
+        self
+    }
+
+    pub fn pages(mut self, selection: PageSelection) -> Self {
+        self.config.pages = selection;
+        self
+    }
+
+    /// Classify pages before converting them; see [`crate::page_kind`].
+    pub fn page_classifier(mut self, classifier: PageClassifier) -> Self {
+        self.config.page_classifier = Some(classifier);
+        self
+    }
+
+    /// Convert only these regions of the listed pages; see
+    /// [`ConversionConfig::page_crops`].
+    pub fn page_crops(mut self, crops: HashMap<usize, Vec<CropRect>>) -> Self {
+        self.config.page_crops = crops;
+        self
+    }
+
+    /// Blank running headers and footers out of the rendered pages; see
+    /// [`ConversionConfig::margin_mask`].
+    pub fn margin_mask(mut self, mask: MarginMask) -> Self {
+        self.config.margin_mask = Some(mask);
+        self
+    }
+
+    /// Suppress diagonal watermarks; see
+    /// [`ConversionConfig::remove_watermarks`].
+    pub fn remove_watermarks(mut self, v: bool) -> Self {
+        self.config.remove_watermarks = v;
+        self
+    }
+
+    /// Layers drawn into the rendered pages; see [`RenderLayers`].
+    pub fn render_layers(mut self, layers: RenderLayers) -> Self {
+        self.config.render_layers = layers;
+        self
+    }
+
+    /// Rasterisation settings; see [`RenderOptions`].
+    pub fn render_options(mut self, options: RenderOptions) -> Self {
+        self.config.render_options = options;
+        self
+    }
+
+    pub fn page_separator(mut self, sep: PageSeparator) -> Self {
+        self.config.page_separator = sep;
+        self
+    }
+
+    /// Markdown dialect of the output; see [`MarkdownFlavor`].
+    pub fn flavor(mut self, flavor: MarkdownFlavor) -> Self {
+        self.config.flavor = flavor;
+        self
+    }
+
+    /// Which image links post-processing keeps; see [`ImageLinkPolicy`].
+    pub fn image_link_policy(mut self, policy: ImageLinkPolicy) -> Self {
+        self.config.image_link_policy = policy;
+        self
+    }
+
+    /// Unicode clean-up of each page; see [`UnicodePolicy`].
+    pub fn unicode(mut self, policy: UnicodePolicy) -> Self {
+        self.config.unicode = policy;
+        self
+    }
+
+    pub fn include_metadata(mut self, v: bool) -> Self {
+        self.config.include_metadata = v;
+        self
+    }
+
+    /// Keep the un-postprocessed model output in [`crate::PageResult::raw_markdown`].
+    pub fn keep_raw_output(mut self, v: bool) -> Self {
+        self.config.keep_raw_output = v;
+        self
+    }
+
+    /// Keep each page's encoded image in [`crate::PageResult::page_image`].
+    pub fn keep_page_images(mut self, v: bool) -> Self {
+        self.config.keep_page_images = v;
+        self
+    }
+
+    /// Refuse conversions selecting more than `n` pages; see [`ConversionConfig::max_pages`].
+    pub fn max_pages(mut self, n: usize) -> Self {
+        self.config.max_pages = Some(n);
+        self
+    }
+
+    /// Refuse input files larger than `bytes`; see [`ConversionConfig::max_file_size_bytes`].
+    pub fn max_file_size_bytes(mut self, bytes: u64) -> Self {
+        self.config.max_file_size_bytes = Some(bytes);
+        self
+    }
+
+    /// Price `model`, and model names starting with it, at `price`.
+    pub fn model_price(mut self, model: impl Into<String>, price: ModelPrice) -> Self {
+        self.config.model_prices.insert(model.into(), price);
+        self
+    }
+
+    /// Replace all price overrides; see [`ConversionConfig::model_prices`].
+    pub fn model_prices(mut self, prices: BTreeMap<String, ModelPrice>) -> Self {
+        self.config.model_prices = prices;
+        self
+    }
+
+    pub fn download_timeout_secs(mut self, secs: u64) -> Self {
+        self.config.download_timeout_secs = secs;
+        self
+    }
+
+    pub fn api_timeout_secs(mut self, secs: u64) -> Self {
         self.config.api_timeout_secs = secs;
         self
     }
 
     /// Build the configuration, validating constraints.
     pub fn build(self) -> Result<ConversionConfig, Pdf2MdError> {
-        let c = &self.config;
-        if c.dpi < 72 || c.dpi > 400 {
-            return Err(Pdf2MdError::InvalidConfig(format!(
-                "DPI must be 72–400, got {}",
-                c.dpi
-            )));
-        }
-        if c.concurrency == 0 {
-            return Err(Pdf2MdError::InvalidConfig("Concurrency must be ≥ 1".into()));
+        if let Some(e) = self.error {
+            return Err(e);
         }
+        self.config.validate()?;
         Ok(self.config)
     }
 }
 
+/// `scheme://host[:port]/` of `url`, or an empty string when it does not
+/// parse.
+fn url_origin(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => format!("{}/", parsed.origin().ascii_serialization()),
+        Err(_) => String::new(),
+    }
+}
+
+// ── Credentials ──────────────────────────────────────────────────────────
+
+/// API keys by provider name (`"openai"`, `"anthropic"`, `"openrouter"`, …).
+///
+/// Names are matched case-insensitively. `Debug` lists provider names only.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ProviderCredentials {
+    keys: BTreeMap<String, String>,
+}
+
+impl ProviderCredentials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or replace) the key for `provider`.
+    pub fn with(mut self, provider: impl Into<String>, api_key: impl Into<String>) -> Self {
+        self.keys
+            .insert(provider.into().to_ascii_lowercase(), api_key.into());
+        self
+    }
+
+    /// The key for `provider`, if one was supplied.
+    pub fn get(&self, provider: &str) -> Option<&str> {
+        self.keys
+            .get(&provider.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Provider names with a key, in sorted order.
+    pub fn providers(&self) -> impl Iterator<Item = &str> {
+        self.keys.keys().map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The keys themselves, for redaction.
+    pub(crate) fn values(&self) -> impl Iterator<Item = &str> {
+        self.keys.values().map(String::as_str)
+    }
+}
+
+impl fmt::Debug for ProviderCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.keys.keys()).finish()
+    }
+}
+
 // ── Enums ────────────────────────────────────────────────────────────────
 
 /// Quality tier controlling which Markdown features the VLM is asked to produce.
@@ -384,15 +1910,287 @@ impl ConversionConfigBuilder {
 /// | 1 | Plain-text extraction, embedding pipelines, sentiment analysis |
 /// | 2 | Documentation, wikis, readable reports (default) |
 /// | 3 | Scientific papers, technical books with math and complex tables |
+/// | 4 | Tier 3 plus flowcharts and org charts as Mermaid (experimental) |
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum FidelityTier {
     /// Basic: text, headings, lists only. Lowest prompt overhead.
+    #[serde(alias = "tier1")]
     Tier1,
     /// Structural: text, headings, lists, GFM tables, footnotes. (default)
     #[default]
+    #[serde(alias = "tier2")]
     Tier2,
     /// High-fidelity: Tier2 + LaTeX math (`$…$`, `$$…$$`), HTML table fallback, image captions.
+    #[serde(alias = "tier3")]
     Tier3,
+    /// Experimental: Tier3 + flowcharts and org charts redrawn as ```` ```mermaid ````
+    /// blocks. Blocks that fail [`crate::mermaid::is_valid`] fall back to an
+    /// italic caption.
+    #[serde(alias = "tier4")]
+    Tier4,
+}
+
+/// What a document's pages are written in.
+///
+/// Models read print far better than handwriting, and on a handwritten
+/// form the default prompt invites them to fill illegible words with
+/// plausible guesses. See [`crate::handwriting`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentHint {
+    /// Typeset or typed text. (default)
+    #[default]
+    Printed,
+    /// Handwritten notes, letters, or filled-in forms: handwriting prompt,
+    /// higher default DPI, uncertain words marked `[?]`.
+    Handwritten,
+    /// Ask the model whether a sample page is handwritten before converting.
+    Auto,
+}
+
+/// What to do with table-of-contents pages; see [`crate::toc`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TocHandling {
+    /// Leave them as converted. (default)
+    #[default]
+    Keep,
+    /// Rewrite dot-leader lines as list items, `- Results — 41`.
+    Normalize,
+    /// Drop their text.
+    Skip,
+    /// Drop their text and put a list of links to the document's headings
+    /// on the first one. Streaming conversions skip instead.
+    Replace,
+}
+
+/// Markdown dialect of the output.
+///
+/// The built-in prompts and post-processing target GitHub Flavored
+/// Markdown; the other flavors adjust both so the output drops into their
+/// toolchains unchanged:
+///
+/// | Flavor | Tables | Callouts | Front-matter | Other rewrites |
+/// |--------|--------|----------|--------------|----------------|
+/// | `gfm` | pipe | `> [!NOTE]` | YAML | — (default) |
+/// | `commonmark` | HTML | `> **Note**` | YAML inside `<!-- -->` | `~~x~~` → `<del>` |
+/// | `pandoc` | pipe | `::: note` fenced divs | YAML | — |
+/// | `mdx` | pipe | `:::note` admonitions | YAML, `description` for the subject | `{`, `}`, stray `<` escaped; comments as `{/* */}`; void tags self-closed |
+///
+/// `mdx` targets Docusaurus and other MDX 2+ sites (Next.js, Astro) with
+/// `remark-gfm`; math needs `remark-math`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkdownFlavor {
+    /// GitHub Flavored Markdown. (default)
+    #[default]
+    Gfm,
+    /// Strict CommonMark: no pipe tables or strikethrough.
+    #[serde(alias = "common_mark", alias = "common-mark")]
+    CommonMark,
+    /// Pandoc Markdown.
+    Pandoc,
+    /// MDX, as used by Docusaurus and Next.js docs sites.
+    Mdx,
+}
+
+impl MarkdownFlavor {
+    /// Every flavor, in documentation order.
+    pub const ALL: [MarkdownFlavor; 4] = [
+        MarkdownFlavor::Gfm,
+        MarkdownFlavor::CommonMark,
+        MarkdownFlavor::Pandoc,
+        MarkdownFlavor::Mdx,
+    ];
+
+    /// Lowercase name, as used by `--flavor` and `pdf2md.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            MarkdownFlavor::Gfm => "gfm",
+            MarkdownFlavor::CommonMark => "commonmark",
+            MarkdownFlavor::Pandoc => "pandoc",
+            MarkdownFlavor::Mdx => "mdx",
+        }
+    }
+
+    /// Rules appended to the built-in and preset page prompts; `None` for
+    /// GFM, which they already target.
+    pub fn prompt_rules(self) -> Option<&'static str> {
+        match self {
+            MarkdownFlavor::Gfm => None,
+            MarkdownFlavor::CommonMark => Some(crate::prompts::COMMONMARK_RULES),
+            MarkdownFlavor::Pandoc => Some(crate::prompts::PANDOC_RULES),
+            MarkdownFlavor::Mdx => Some(crate::prompts::MDX_RULES),
+        }
+    }
+}
+
+impl fmt::Display for MarkdownFlavor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Which `![alt](url)` image links post-processing keeps.
+///
+/// Models often invent image URLs (`image-url`, `chart.png`) for figures
+/// they cannot transcribe, so by default every link that is not absolute
+/// `http(s)` is replaced by its alt text. Workflows that drop extracted
+/// figures into an asset directory next to the Markdown need the relative
+/// links kept. Links to placeholder hosts such as `example.com` are
+/// stripped unless allow-listed.
+///
+/// | Policy | `https://…` | `figures/fig1.png` | `image-url` |
+/// |--------|-------------|--------------------|-------------|
+/// | `StripAll` | kept | stripped | stripped |
+/// | `KeepRelative` | kept | kept | stripped |
+/// | `AllowList(["figures/"])` | kept | kept | stripped |
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageLinkPolicy {
+    /// Keep only absolute `http(s)` links. (default)
+    #[default]
+    StripAll,
+    /// Also keep relative paths to a file with an extension, such as
+    /// `figures/fig1.png` or `./img/p3.jpg`.
+    KeepRelative,
+    /// Also keep links starting with one of these prefixes, such as
+    /// `assets/` or `../media/`.
+    AllowList(Vec<String>),
+}
+
+impl fmt::Display for ImageLinkPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageLinkPolicy::StripAll => f.write_str("strip-all"),
+            ImageLinkPolicy::KeepRelative => f.write_str("keep-relative"),
+            ImageLinkPolicy::AllowList(prefixes) => write!(f, "allow:{}", prefixes.join(",")),
+        }
+    }
+}
+
+/// Unicode clean-up applied to each page before the other post-processing
+/// rules; see [`crate::pipeline::unicode`].
+///
+/// Text copied out of PDFs — and VLM transcriptions of it — carries
+/// presentation forms that look right but break search and diffing:
+/// decomposed accents, `ﬁ` ligatures, words split by a soft hyphen at a
+/// line break. Those fixes are on by default. Quote and dash mapping
+/// changes visible punctuation, so it is opt-in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnicodePolicy {
+    /// Unicode NFC normalisation (`e` + `◌́` → `é`). Default: true.
+    pub nfc: bool,
+    /// Expand Latin ligatures U+FB00–FB06 (`ﬁ` → `fi`, `ﬄ` → `ffl`).
+    /// Default: true.
+    pub expand_ligatures: bool,
+    /// Join words split by a soft hyphen (U+00AD) at a line break, which
+    /// the invisible-character rule would otherwise leave split. Default: true.
+    pub join_soft_hyphens: bool,
+    /// Quote characters. Default: [`PunctuationStyle::Keep`].
+    pub quotes: PunctuationStyle,
+    /// Dash and hyphen characters. Default: [`PunctuationStyle::Keep`].
+    pub dashes: PunctuationStyle,
+}
+
+impl Default for UnicodePolicy {
+    fn default() -> Self {
+        Self {
+            nfc: true,
+            expand_ligatures: true,
+            join_soft_hyphens: true,
+            quotes: PunctuationStyle::Keep,
+            dashes: PunctuationStyle::Keep,
+        }
+    }
+}
+
+impl UnicodePolicy {
+    /// Leave the text as the model wrote it.
+    pub fn off() -> Self {
+        Self {
+            nfc: false,
+            expand_ligatures: false,
+            join_soft_hyphens: false,
+            ..Self::default()
+        }
+    }
+}
+
+/// Canonical form for quotes or dashes in [`UnicodePolicy`].
+///
+/// | Style | Quotes | Dashes |
+/// |-------|--------|--------|
+/// | `keep` | unchanged | unchanged |
+/// | `standard` | `„` `‟` → `“`, `‚` `‛` → `‘`, fullwidth → ASCII | `‐` `‑` → `-`, `‒` → `–`, `―` → `—`, small and fullwidth forms |
+/// | `ascii` | as `standard`, then `“` `”` → `"`, `‘` `’` → `'` | as `standard`, then `–` → `-`, `—` → `--` |
+///
+/// Guillemets (`«»`) and primes (`′″`) are never changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PunctuationStyle {
+    /// Leave as written. (default)
+    #[default]
+    Keep,
+    /// Map rare variants to the common typographic character.
+    Standard,
+    /// Map everything to ASCII.
+    Ascii,
+}
+
+/// Parses the `--image-links` syntax: `strip-all`, `keep-relative`, or
+/// `allow:assets/,figures/`.
+impl std::str::FromStr for ImageLinkPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(list) = s.strip_prefix("allow:") {
+            let prefixes: Vec<String> = list
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(String::from)
+                .collect();
+            if prefixes.is_empty() {
+                return Err("image link allow-list is empty".into());
+            }
+            return Ok(ImageLinkPolicy::AllowList(prefixes));
+        }
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "strip-all" => Ok(ImageLinkPolicy::StripAll),
+            "keep-relative" => Ok(ImageLinkPolicy::KeepRelative),
+            _ => Err(format!(
+                "unknown image link policy '{s}', expected strip-all, keep-relative, or allow:PREFIX,..."
+            )),
+        }
+    }
+}
+
+/// How page failures affect the result of [`crate::convert`] and friends.
+///
+/// | Policy | First page failure | Result with failed pages |
+/// |--------|--------------------|--------------------------|
+/// | `BestEffort` | conversion continues | `Ok`, failures in `pages` (default) |
+/// | `FailFast` | run aborts with [`Pdf2MdError::PageFailed`] | — |
+/// | `AllOrNothing` | conversion continues | `Err(`[`Pdf2MdError::PartialFailure`]`)` |
+///
+/// `AllOrNothing` still converts every page, so its error reports how many
+/// failed; `FailFast` stops spending on a run that cannot succeed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Strictness {
+    /// Return partial output; failed pages carry a [`crate::PageError`]. (default)
+    #[default]
+    #[serde(alias = "best-effort")]
+    BestEffort,
+    /// Abort on the first failed page.
+    #[serde(alias = "fail-fast")]
+    FailFast,
+    /// Convert everything, but return an error unless every page succeeded.
+    #[serde(alias = "all-or-nothing")]
+    AllOrNothing,
 }
 
 /// Specifies which pages of the PDF to convert.
@@ -407,6 +2205,29 @@ pub enum PageSelection {
     Range(usize, usize),
     /// Convert specific pages (1-indexed, deduplicated).
     Set(Vec<usize>),
+    /// Odd pages: 1, 3, 5, … (the fronts of a duplex scan).
+    Odd,
+    /// Even pages: 2, 4, 6, … (the backs of a duplex scan).
+    Even,
+    /// Every nth page starting at page 1: 1, 1 + n, 1 + 2n, …
+    EveryNth(usize),
+    /// Every `step`th page of a range (1-indexed, inclusive): `(1, 100, 2)`
+    /// is 1, 3, …, 99.
+    StepRange(usize, usize, usize),
+    /// The last n pages.
+    Last(usize),
+    /// Every page except these (1-indexed).
+    Exclude(Vec<usize>),
+    /// Every page except those of these kinds, as found by
+    /// [`ConversionConfig::page_classifier`]. Pages the classifier cannot
+    /// place are kept. Applied by the conversion entry points after
+    /// classification; [`Self::to_indices`] alone treats it as every page.
+    ExcludeKinds(Vec<PageKind>),
+    /// Pages selected by any member, minus the pages of `Exclude` and
+    /// `ExcludeKinds` members.
+    ///
+    /// With only exclusion members, the base is every page.
+    Union(Vec<PageSelection>),
 }
 
 impl PageSelection {
@@ -414,6 +2235,46 @@ impl PageSelection {
     pub fn to_indices(&self, total_pages: usize) -> Vec<usize> {
         let mut indices: Vec<usize> = match self {
             PageSelection::All => (0..total_pages).collect(),
+            PageSelection::Odd => (0..total_pages).step_by(2).collect(),
+            PageSelection::Even => (1..total_pages).step_by(2).collect(),
+            PageSelection::EveryNth(n) => (0..total_pages).step_by((*n).max(1)).collect(),
+            PageSelection::StepRange(start, end, step) => {
+                let s = (*start).max(1) - 1;
+                let e = (*end).min(total_pages);
+                (s..e).step_by((*step).max(1)).collect()
+            }
+            PageSelection::Last(n) => (total_pages.saturating_sub(*n)..total_pages).collect(),
+            PageSelection::Exclude(pages) => (0..total_pages)
+                .filter(|i| !pages.contains(&(i + 1)))
+                .collect(),
+            PageSelection::ExcludeKinds(_) => (0..total_pages).collect(),
+            PageSelection::Union(parts) => {
+                let (excludes, includes): (Vec<_>, Vec<_>) = parts.iter().partition(|p| {
+                    matches!(
+                        p,
+                        PageSelection::Exclude(_) | PageSelection::ExcludeKinds(_)
+                    )
+                });
+                let excluded: Vec<usize> = excludes
+                    .iter()
+                    .flat_map(|p| match p {
+                        PageSelection::Exclude(pages) => pages.clone(),
+                        _ => Vec::new(),
+                    })
+                    .collect();
+                let included: Vec<usize> = if includes.is_empty() {
+                    (0..total_pages).collect()
+                } else {
+                    includes
+                        .iter()
+                        .flat_map(|p| p.to_indices(total_pages))
+                        .collect()
+                };
+                included
+                    .into_iter()
+                    .filter(|i| !excluded.contains(&(i + 1)))
+                    .collect()
+            }
             PageSelection::Single(p) => {
                 if *p >= 1 && *p <= total_pages {
                     vec![p - 1]
@@ -436,6 +2297,181 @@ impl PageSelection {
         indices.dedup();
         indices
     }
+
+    /// The kinds of [`Self::ExcludeKinds`], here or in a union.
+    pub fn excluded_kinds(&self) -> Vec<PageKind> {
+        match self {
+            PageSelection::ExcludeKinds(kinds) => kinds.clone(),
+            PageSelection::Union(parts) => parts.iter().flat_map(|p| p.excluded_kinds()).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// A region of a page, in fractions of the page from its top-left corner,
+/// after any [`ConversionConfig::auto_rotate`] turn: `x: 0.5, width: 0.5`
+/// is the right half. See [`ConversionConfig::page_crops`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl CropRect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// `(x, y, width, height)` in pixels of a `width × height` image, at
+    /// least one pixel each way and inside the image.
+    pub fn to_pixels(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let span = |start: f32, len: f32, total: u32| {
+            let first = ((start * total as f32).round() as u32).min(total.saturating_sub(1));
+            let len =
+                ((len * total as f32).round() as u32).clamp(1, total.saturating_sub(first).max(1));
+            (first, len)
+        };
+        let (x, w) = span(self.x, self.width, width);
+        let (y, h) = span(self.y, self.height, height);
+        (x, y, w, h)
+    }
+
+    pub(crate) fn validate(&self, page: usize) -> Result<(), Pdf2MdError> {
+        let inside = |start: f32, len: f32| start >= 0.0 && len > 0.0 && start + len <= 1.0 + 1e-6;
+        if inside(self.x, self.width) && inside(self.y, self.height) {
+            Ok(())
+        } else {
+            Err(Pdf2MdError::InvalidConfig(format!(
+                "crop region {self} on page {page} must lie within the page (fractions 0–1)"
+            )))
+        }
+    }
+}
+
+impl fmt::Display for CropRect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{},{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+/// Parses the `--crop` syntax: `x,y,width,height`, e.g. `0,0.5,1,0.5` for
+/// the bottom half.
+impl std::str::FromStr for CropRect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<f32> = s
+            .split(',')
+            .map(|p| p.trim().parse::<f32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("invalid crop region '{s}', expected x,y,width,height"))?;
+        match parts[..] {
+            [x, y, width, height] => Ok(Self::new(x, y, width, height)),
+            _ => Err(format!(
+                "invalid crop region '{s}', expected four numbers x,y,width,height"
+            )),
+        }
+    }
+}
+
+/// Layers of a page drawn when it is rasterised; see
+/// [`ConversionConfig::render_layers`].
+///
+/// Reviewer stamps and highlights are annotations, not page content, so
+/// turning `annotations` off converts the document as it was before review.
+/// Turning `content` off leaves only what was added on top of the page,
+/// e.g. to transcribe the values typed into a form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderLayers {
+    /// Text, images, and vector graphics of the page itself. Default: true.
+    pub content: bool,
+    /// Annotations: stamps, highlights, ink, sticky notes. Default: true.
+    pub annotations: bool,
+    /// Values and widgets of interactive form fields. Default: true.
+    pub form_fields: bool,
+}
+
+impl Default for RenderLayers {
+    fn default() -> Self {
+        Self {
+            content: true,
+            annotations: true,
+            form_fields: true,
+        }
+    }
+}
+
+impl RenderLayers {
+    pub(crate) fn validate(&self) -> Result<(), Pdf2MdError> {
+        if self.content || self.annotations || self.form_fields {
+            Ok(())
+        } else {
+            Err(Pdf2MdError::InvalidConfig(
+                "render_layers must keep at least one layer".into(),
+            ))
+        }
+    }
+}
+
+/// pdfium rasterisation settings; see [`ConversionConfig::render_options`].
+///
+/// The defaults suit almost every document. Fonts that come out blurred,
+/// broken, or too thin can often be fixed by turning off anti-aliasing or
+/// pdfium's native text drawing. The rasteriser itself (AGG or Skia) is
+/// chosen when the pdfium library is built, not at run time; the
+/// prebuilt libraries that pdfium-auto downloads use AGG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    /// Anti-alias text. Default: true.
+    pub text_smoothing: bool,
+    /// Smooth images when they are scaled. Default: true.
+    pub image_smoothing: bool,
+    /// Anti-alias lines and vector shapes. Default: true.
+    pub path_smoothing: bool,
+    /// Sub-pixel (LCD) text anti-aliasing. Sharper on screen, but adds
+    /// colour fringes to glyph edges. Default: false.
+    pub lcd_text: bool,
+    /// Let pdfium draw text with the platform's native text renderer where
+    /// it can; `false` always uses pdfium's own glyph rasteriser.
+    /// Default: true.
+    pub native_text: bool,
+    /// Have pdfium write pixels in RGBA rather than BGRA order. The image
+    /// conversion handles either. Default: true.
+    pub reverse_byte_order: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            text_smoothing: true,
+            image_smoothing: true,
+            path_smoothing: true,
+            lcd_text: false,
+            native_text: true,
+            reverse_byte_order: true,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// No anti-aliasing of text, images, or shapes.
+    pub fn aliased() -> Self {
+        Self {
+            text_smoothing: false,
+            image_smoothing: false,
+            path_smoothing: false,
+            ..Self::default()
+        }
+    }
 }
 
 /// How to separate pages in the assembled Markdown output.
@@ -463,3 +2499,225 @@ impl PageSeparator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn sample() -> ConversionConfig {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-gateway-key", HeaderValue::from_static("abc123"));
+        ConversionConfig::builder()
+            .dpi(200)
+            .model("gpt-4.1")
+            .fidelity(FidelityTier::Tier3)
+            .pages(PageSelection::Range(2, 4))
+            .base_url("http://localhost:8000/v1")
+            .extra_headers(headers)
+            .password("hunter2")
+            .api_key("sk-secret")
+            .generate_summary(SummaryConfig::default())
+            .webhook(WebhookConfig {
+                url: "https://jobs.example/done".into(),
+                headers: [("Authorization".to_string(), "Bearer tok-9".to_string())].into(),
+                include_markdown: true,
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn json_round_trip_keeps_settings() {
+        let json = serde_json::to_string(&sample()).unwrap();
+        let back: ConversionConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.dpi, 200);
+        assert_eq!(back.model.as_deref(), Some("gpt-4.1"));
+        assert_eq!(back.fidelity, FidelityTier::Tier3);
+        assert!(matches!(back.pages, PageSelection::Range(2, 4)));
+        assert_eq!(back.extra_headers["x-gateway-key"], "abc123");
+        assert_eq!(back.password.as_deref(), Some("hunter2"));
+        assert!(back.summary.is_some());
+        assert!(back.provider.is_none());
+    }
+
+    #[test]
+    fn missing_keys_take_defaults() {
+        let config: ConversionConfig = serde_json::from_str(r#"{"concurrency": 3}"#).unwrap();
+        assert_eq!(config.concurrency, 3);
+        assert_eq!(config.dpi, 150);
+        assert_eq!(config.max_tokens, 4096);
+    }
+
+    #[test]
+    fn credentials_are_case_insensitive_and_hidden_from_debug() {
+        let creds = ProviderCredentials::new().with("OpenAI", "sk-1");
+        assert_eq!(creds.get("openai"), Some("sk-1"));
+        let config = ConversionConfig::builder()
+            .api_key("sk-2")
+            .credentials(creds)
+            .build()
+            .unwrap();
+        let debug = format!("{config:?}");
+        assert!(
+            !debug.contains("sk-1") && !debug.contains("sk-2"),
+            "{debug}"
+        );
+        assert!(debug.contains("openai"));
+    }
+
+    #[test]
+    fn redacted_config_omits_secrets() {
+        let json = serde_json::to_string(&sample().redact_secrets()).unwrap();
+        assert!(!json.contains("hunter2"));
+        assert!(!json.contains("abc123"));
+        assert!(!json.contains("localhost:8000"));
+        assert!(!json.contains("sk-secret"));
+        assert!(!json.contains("tok-9"));
+        assert!(json.contains("gpt-4.1"));
+        assert!(json.contains("\"https://jobs.example/\""));
+
+        let hook = WebhookConfig::new("https://user:pw@hooks.example:8443/T01/secret?token=x#f");
+        let config = ConversionConfig {
+            webhook: Some(hook),
+            ..Default::default()
+        };
+        let url = config.redact_secrets().webhook.unwrap().url;
+        assert_eq!(url, "https://hooks.example:8443/");
+    }
+
+    #[test]
+    fn builder_defers_chained_failures_to_build() {
+        for preset in DocumentPreset::ALL {
+            let profile = preset.profile();
+            assert!(matches!(profile.headers(), Ok(None)));
+            assert!(ConversionConfig::builder().preset(preset).build().is_ok());
+        }
+        let bad = crate::config_file::ConfigProfile {
+            extra_headers: Some([("bad header".to_string(), "x".to_string())].into()),
+            ..Default::default()
+        };
+        assert!(bad.apply(ConversionConfig::builder()).is_err());
+
+        let mut builder = ConversionConfig::builder().dpi(200);
+        builder.error = Some(Pdf2MdError::InvalidConfig("bad header".into()));
+        assert!(matches!(
+            builder.build(),
+            Err(Pdf2MdError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn deserialising_validates() {
+        for payload in [
+            r#"{"concurrency":0}"#,
+            r#"{"dpi":1000}"#,
+            r#"{"context_window_pages":0}"#,
+            r#"{"max_pages":0}"#,
+            r#"{"seed":7,"temperature":0.5}"#,
+            r#"{"local_only":true,"webhook":{"url":"https://jobs.example/done"}}"#,
+        ] {
+            let err = serde_json::from_str::<ConversionConfig>(payload).err();
+            assert!(err.is_some(), "{payload} was accepted");
+        }
+        assert!(ConversionConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn flavor_rules_extend_builtin_prompts_only() {
+        let gfm = ConversionConfig::default().page_prompt_template();
+        let mdx = ConversionConfig {
+            flavor: MarkdownFlavor::Mdx,
+            ..Default::default()
+        };
+        let text = mdx.page_prompt_template().text().to_string();
+        assert_eq!(text, format!("{}{}", gfm.text(), crate::prompts::MDX_RULES));
+        let charted = ConversionConfig {
+            chart_tables: true,
+            ..mdx.clone()
+        };
+        let text = charted.page_prompt_template().text().to_string();
+        assert!(text.ends_with(&format!(
+            "{}{}",
+            crate::prompts::MDX_RULES,
+            crate::prompts::CHART_DATA_RULES
+        )));
+        let custom = ConversionConfig {
+            system_prompt: Some("Transcribe.".into()),
+            ..mdx
+        };
+        assert_eq!(custom.page_prompt_template().text(), "Transcribe.");
+        let back: MarkdownFlavor = serde_json::from_str("\"commonmark\"").unwrap();
+        assert_eq!(back, MarkdownFlavor::CommonMark);
+    }
+
+    #[test]
+    fn handwriting_prompt_wins_over_preset_but_not_overrides() {
+        let notes = ConversionConfig {
+            content_hint: ContentHint::Handwritten,
+            preset: Some(DocumentPreset::Invoice),
+            ..Default::default()
+        };
+        let text = notes.page_prompt_template().text().to_string();
+        assert_eq!(text, crate::prompts::HANDWRITING_SYSTEM_PROMPT);
+        let custom = ConversionConfig {
+            system_prompt: Some("Transcribe.".into()),
+            ..notes
+        };
+        assert_eq!(custom.page_prompt_template().text(), "Transcribe.");
+        let back: ContentHint = serde_json::from_str("\"handwritten\"").unwrap();
+        assert_eq!(back, ContentHint::Handwritten);
+    }
+
+    #[test]
+    fn image_link_policy_parses_cli_and_serde_forms() {
+        let allow = ImageLinkPolicy::AllowList(vec!["assets/".into(), "figures/".into()]);
+        assert_eq!("allow:assets/, figures/".parse(), Ok(allow.clone()));
+        assert_eq!(allow.to_string().parse(), Ok(allow.clone()));
+        assert_eq!("keep_relative".parse(), Ok(ImageLinkPolicy::KeepRelative));
+        assert!("allow:".parse::<ImageLinkPolicy>().is_err());
+        assert!("keep".parse::<ImageLinkPolicy>().is_err());
+
+        let json = r#"{"allow_list": ["assets/", "figures/"]}"#;
+        assert_eq!(
+            serde_json::from_str::<ImageLinkPolicy>(json).unwrap(),
+            allow
+        );
+    }
+
+    #[test]
+    fn seed_requires_zero_temperature() {
+        let config = ConversionConfig::builder().seed(7).build().unwrap();
+        assert_eq!((config.seed, config.temperature), (Some(7), 0.0));
+        let warm = ConversionConfig::builder().seed(7).temperature(0.3).build();
+        assert!(matches!(warm, Err(Pdf2MdError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn crop_regions_parse_and_map_to_pixels() {
+        let bottom: CropRect = "0, 0.5, 1, 0.5".parse().unwrap();
+        assert_eq!(bottom, CropRect::new(0.0, 0.5, 1.0, 0.5));
+        assert_eq!(bottom.to_pixels(1000, 1400), (0, 700, 1000, 700));
+        assert_eq!(bottom.to_string().parse(), Ok(bottom));
+        assert!("0,0.5,1".parse::<CropRect>().is_err());
+        assert!("a,b,c,d".parse::<CropRect>().is_err());
+        // Never empty, never past the edge.
+        let sliver = CropRect::new(0.9999, 0.0, 0.0001, 1.0);
+        assert_eq!(sliver.to_pixels(100, 100), (99, 0, 1, 100));
+
+        let crops = |page, rect| HashMap::from([(page, vec![rect])]);
+        let ok = ConversionConfig::builder()
+            .page_crops(crops(7, bottom))
+            .build();
+        assert!(ok.is_ok());
+        let outside = CropRect::new(0.5, 0.0, 0.6, 1.0);
+        let err = ConversionConfig::builder()
+            .page_crops(crops(7, outside))
+            .build();
+        assert!(matches!(err, Err(Pdf2MdError::InvalidConfig(_))));
+        let page_zero = ConversionConfig::builder()
+            .page_crops(crops(0, bottom))
+            .build();
+        assert!(matches!(page_zero, Err(Pdf2MdError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,619 @@
+//! `pdf2md.toml` configuration files with named profiles.
+//!
+//! Teams tend to run the same handful of setups ("cheap bulk pass",
+//! "archival quality") and copy the flags around in shell scripts. A config
+//! file keeps those defaults in one place:
+//!
+//! ```toml
+//! # pdf2md.toml
+//! provider = "openai"
+//! model = "gpt-4.1-nano"
+//! concurrency = 8
+//!
+//! [profile.cheap]
+//! dpi = 100
+//! fidelity = "tier1"
+//!
+//! [profile.archival]
+//! model = "gpt-4.1"
+//! dpi = 300
+//! fidelity = "tier3"
+//! maintain_format = true
+//! ```
+//!
+//! Top-level keys are the defaults; a selected `[profile.NAME]` overrides
+//! them key by key. Keys use the [`ConversionConfig`] field names. Unknown
+//! keys are rejected so typos do not silently fall back to defaults.
+//!
+//! The CLI looks for `./pdf2md.toml`, then `~/.config/pdf2md/config.toml`
+//! (see [`ConfigFile::discover`]), and selects a profile with `--profile`.
+//! Flags and environment variables still win over the file. Library users
+//! call [`ConversionConfig::from_toml_file`].
+
+use crate::auto_dpi::AutoDpiConfig;
+use crate::config::{
+    ContentHint, ConversionConfig, ConversionConfigBuilder, FidelityTier, ImageLinkPolicy,
+    MarkdownFlavor, RenderLayers, RenderOptions, Strictness, TocHandling, UnicodePolicy,
+};
+use crate::error::Pdf2MdError;
+use crate::page_kind::PageClassifier;
+use crate::pipeline::margins::MarginMask;
+use crate::presets::DocumentPreset;
+use crate::pricing::ModelPrice;
+use crate::webhook::WebhookConfig;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File name looked up in the current directory.
+pub const CONFIG_FILE_NAME: &str = "pdf2md.toml";
+
+/// One set of settings: the file's top level, or a `[profile.NAME]` table.
+///
+/// Every field is optional; unset fields leave the [`ConversionConfig`]
+/// default (or the value from the layer below) untouched.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigProfile {
+    /// `"invoice"`, `"scientific-paper"`, `"legal-contract"`, or
+    /// `"slide-deck"`; the other keys override its settings.
+    pub preset: Option<DocumentPreset>,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub fallback_models: Option<Vec<String>>,
+    pub base_url: Option<String>,
+    /// Extra HTTP headers for VLM requests, e.g. `{ "X-Gateway-Key" = "…" }`.
+    pub extra_headers: Option<BTreeMap<String, String>>,
+    pub dpi: Option<u32>,
+    /// `{ candidates = [96, 150, 220], min_agreement = 0.97 }`; omitted keys
+    /// keep their defaults.
+    pub auto_dpi: Option<AutoDpiConfig>,
+    pub concurrency: Option<usize>,
+    pub render_workers: Option<usize>,
+    pub maintain_format: Option<bool>,
+    pub context_window_pages: Option<usize>,
+    pub parallel_sections: Option<bool>,
+    pub tile_dense_pages: Option<bool>,
+    pub layout_hints: Option<bool>,
+    pub structure_tags: Option<bool>,
+    pub figure_captions: Option<bool>,
+    pub chart_tables: Option<bool>,
+    pub niche_placeholders: Option<bool>,
+    /// `"keep"`, `"normalize"`, `"skip"`, or `"replace"`.
+    pub toc_handling: Option<TocHandling>,
+    pub skip_blank_pages: Option<bool>,
+    pub auto_rotate: Option<bool>,
+    pub split_spreads: Option<bool>,
+    /// `{ top = 0.08, bottom = 0.05 }` (fractions of the page height),
+    /// `{ auto = true }`, or both.
+    pub margin_mask: Option<MarginMask>,
+    pub remove_watermarks: Option<bool>,
+    /// `{ content = true, annotations = false, form_fields = true }`;
+    /// omitted keys keep their defaults.
+    pub render_layers: Option<RenderLayers>,
+    /// `{ text_smoothing = true, lcd_text = false, native_text = true, … }`;
+    /// omitted keys keep their defaults.
+    pub render_options: Option<RenderOptions>,
+    pub grayscale: Option<bool>,
+    pub png_palette: Option<bool>,
+    /// `"tier1"`, `"tier2"`, or `"tier3"`.
+    pub fidelity: Option<FidelityTier>,
+    pub content_hint: Option<ContentHint>,
+    /// `"heuristic"` or `"model"`.
+    pub page_classifier: Option<PageClassifier>,
+    /// Inline system prompt text.
+    pub system_prompt: Option<String>,
+    pub temperature: Option<f32>,
+    /// Sampling seed; implies `temperature = 0`.
+    pub seed: Option<u64>,
+    pub max_tokens: Option<usize>,
+    pub max_tokens_retry_cap: Option<usize>,
+    pub max_retries: Option<u32>,
+    pub retry_backoff_ms: Option<u64>,
+    pub failure_abort_threshold: Option<usize>,
+    /// `"best_effort"`, `"fail_fast"`, or `"all_or_nothing"`.
+    pub strictness: Option<Strictness>,
+    pub include_metadata: Option<bool>,
+    /// `"gfm"`, `"commonmark"`, `"pandoc"`, or `"mdx"`.
+    pub flavor: Option<MarkdownFlavor>,
+    /// `"strip_all"`, `"keep_relative"`, or `{ allow_list = ["assets/"] }`.
+    pub image_link_policy: Option<ImageLinkPolicy>,
+    /// `{ nfc = true, expand_ligatures = true, join_soft_hyphens = true,
+    /// quotes = "keep", dashes = "keep" }`; omitted keys keep their defaults.
+    pub unicode: Option<UnicodePolicy>,
+    pub local_only: Option<bool>,
+    pub max_pages: Option<usize>,
+    pub max_file_size_bytes: Option<u64>,
+    /// Price overrides, e.g. `"gpt-4.1" = { input_per_mtok = 1.6, output_per_mtok = 6.4 }`.
+    pub model_prices: Option<BTreeMap<String, ModelPrice>>,
+    /// `{ url = "…", headers = { … }, include_markdown = false }`.
+    pub webhook: Option<WebhookConfig>,
+    pub download_timeout_secs: Option<u64>,
+    pub api_timeout_secs: Option<u64>,
+
+    /// `[profile.*]` tables; only meaningful at the top level.
+    profile: BTreeMap<String, ConfigProfile>,
+}
+
+impl ConfigProfile {
+    /// Return `self` with every field that `over` sets replaced by its value.
+    pub fn overlay(self, over: ConfigProfile) -> ConfigProfile {
+        ConfigProfile {
+            preset: over.preset.or(self.preset),
+            provider: over.provider.or(self.provider),
+            model: over.model.or(self.model),
+            fallback_models: over.fallback_models.or(self.fallback_models),
+            base_url: over.base_url.or(self.base_url),
+            extra_headers: over.extra_headers.or(self.extra_headers),
+            dpi: over.dpi.or(self.dpi),
+            auto_dpi: over.auto_dpi.or(self.auto_dpi),
+            concurrency: over.concurrency.or(self.concurrency),
+            render_workers: over.render_workers.or(self.render_workers),
+            maintain_format: over.maintain_format.or(self.maintain_format),
+            context_window_pages: over.context_window_pages.or(self.context_window_pages),
+            parallel_sections: over.parallel_sections.or(self.parallel_sections),
+            tile_dense_pages: over.tile_dense_pages.or(self.tile_dense_pages),
+            layout_hints: over.layout_hints.or(self.layout_hints),
+            structure_tags: over.structure_tags.or(self.structure_tags),
+            figure_captions: over.figure_captions.or(self.figure_captions),
+            chart_tables: over.chart_tables.or(self.chart_tables),
+            niche_placeholders: over.niche_placeholders.or(self.niche_placeholders),
+            toc_handling: over.toc_handling.or(self.toc_handling),
+            skip_blank_pages: over.skip_blank_pages.or(self.skip_blank_pages),
+            auto_rotate: over.auto_rotate.or(self.auto_rotate),
+            split_spreads: over.split_spreads.or(self.split_spreads),
+            margin_mask: over.margin_mask.or(self.margin_mask),
+            remove_watermarks: over.remove_watermarks.or(self.remove_watermarks),
+            render_layers: over.render_layers.or(self.render_layers),
+            render_options: over.render_options.or(self.render_options),
+            grayscale: over.grayscale.or(self.grayscale),
+            png_palette: over.png_palette.or(self.png_palette),
+            fidelity: over.fidelity.or(self.fidelity),
+            content_hint: over.content_hint.or(self.content_hint),
+            page_classifier: over.page_classifier.or(self.page_classifier),
+            system_prompt: over.system_prompt.or(self.system_prompt),
+            temperature: over.temperature.or(self.temperature),
+            seed: over.seed.or(self.seed),
+            max_tokens: over.max_tokens.or(self.max_tokens),
+            max_tokens_retry_cap: over.max_tokens_retry_cap.or(self.max_tokens_retry_cap),
+            max_retries: over.max_retries.or(self.max_retries),
+            retry_backoff_ms: over.retry_backoff_ms.or(self.retry_backoff_ms),
+            failure_abort_threshold: over
+                .failure_abort_threshold
+                .or(self.failure_abort_threshold),
+            strictness: over.strictness.or(self.strictness),
+            include_metadata: over.include_metadata.or(self.include_metadata),
+            flavor: over.flavor.or(self.flavor),
+            image_link_policy: over.image_link_policy.or(self.image_link_policy),
+            unicode: over.unicode.or(self.unicode),
+            local_only: over.local_only.or(self.local_only),
+            max_pages: over.max_pages.or(self.max_pages),
+            max_file_size_bytes: over.max_file_size_bytes.or(self.max_file_size_bytes),
+            model_prices: over.model_prices.or(self.model_prices),
+            webhook: over.webhook.or(self.webhook),
+            download_timeout_secs: over.download_timeout_secs.or(self.download_timeout_secs),
+            api_timeout_secs: over.api_timeout_secs.or(self.api_timeout_secs),
+            profile: BTreeMap::new(),
+        }
+    }
+
+    /// Apply every set field to `builder`.
+    pub fn apply(
+        &self,
+        b: ConversionConfigBuilder,
+    ) -> Result<ConversionConfigBuilder, Pdf2MdError> {
+        let headers = self.headers()?;
+        Ok(self.apply_parsed(b, headers))
+    }
+
+    /// `extra_headers` as a [`HeaderMap`], the only setting that can fail
+    /// to apply.
+    pub(crate) fn headers(&self) -> Result<Option<HeaderMap>, Pdf2MdError> {
+        self.extra_headers.as_ref().map(header_map).transpose()
+    }
+
+    /// [`Self::apply`] with `extra_headers` already parsed into `headers`.
+    pub(crate) fn apply_parsed(
+        &self,
+        mut b: ConversionConfigBuilder,
+        headers: Option<HeaderMap>,
+    ) -> ConversionConfigBuilder {
+        // First, so the keys below override the preset's settings.
+        if let Some(v) = self.preset {
+            b = b.preset(v);
+        }
+        if let Some(ref v) = self.provider {
+            b = b.provider_name(v);
+        }
+        if let Some(ref v) = self.model {
+            b = b.model(v);
+        }
+        if let Some(ref v) = self.fallback_models {
+            b = b.fallback_models(v.iter().cloned());
+        }
+        if let Some(ref v) = self.base_url {
+            b = b.base_url(v);
+        }
+        if let Some(v) = headers {
+            b = b.extra_headers(v);
+        }
+        if let Some(v) = self.dpi {
+            b = b.dpi(v);
+        }
+        if let Some(ref v) = self.auto_dpi {
+            b = b.auto_dpi(v.clone());
+        }
+        if let Some(v) = self.concurrency {
+            b = b.concurrency(v);
+        }
+        if let Some(v) = self.render_workers {
+            b = b.render_workers(v);
+        }
+        if let Some(v) = self.maintain_format {
+            b = b.maintain_format(v);
+        }
+        if let Some(v) = self.context_window_pages {
+            b = b.context_window_pages(v);
+        }
+        if let Some(v) = self.parallel_sections {
+            b = b.parallel_sections(v);
+        }
+        if let Some(v) = self.tile_dense_pages {
+            b = b.tile_dense_pages(v);
+        }
+        if let Some(v) = self.layout_hints {
+            b = b.layout_hints(v);
+        }
+        if let Some(v) = self.structure_tags {
+            b = b.structure_tags(v);
+        }
+        if let Some(v) = self.figure_captions {
+            b = b.figure_captions(v);
+        }
+        if let Some(v) = self.chart_tables {
+            b = b.chart_tables(v);
+        }
+        if let Some(v) = self.niche_placeholders {
+            b = b.niche_placeholders(v);
+        }
+        if let Some(v) = self.toc_handling {
+            b = b.toc_handling(v);
+        }
+        if let Some(v) = self.skip_blank_pages {
+            b = b.skip_blank_pages(v);
+        }
+        if let Some(v) = self.auto_rotate {
+            b = b.auto_rotate(v);
+        }
+        if let Some(v) = self.split_spreads {
+            b = b.split_spreads(v);
+        }
+        if let Some(v) = self.margin_mask {
+            b = b.margin_mask(v);
+        }
+        if let Some(v) = self.remove_watermarks {
+            b = b.remove_watermarks(v);
+        }
+        if let Some(v) = self.render_layers {
+            b = b.render_layers(v);
+        }
+        if let Some(v) = self.render_options {
+            b = b.render_options(v);
+        }
+        if let Some(v) = self.grayscale {
+            b = b.grayscale(v);
+        }
+        if let Some(v) = self.png_palette {
+            b = b.png_palette(v);
+        }
+        if let Some(v) = self.fidelity {
+            b = b.fidelity(v);
+        }
+        if let Some(v) = self.content_hint {
+            b = b.content_hint(v);
+        }
+        if let Some(v) = self.page_classifier {
+            b = b.page_classifier(v);
+        }
+        if let Some(ref v) = self.system_prompt {
+            b = b.system_prompt(v);
+        }
+        if let Some(v) = self.temperature {
+            b = b.temperature(v);
+        }
+        if let Some(v) = self.seed {
+            b = b.seed(v);
+        }
+        if let Some(v) = self.max_tokens {
+            b = b.max_tokens(v);
+        }
+        if let Some(v) = self.max_tokens_retry_cap {
+            b = b.max_tokens_retry_cap(v);
+        }
+        if let Some(v) = self.max_retries {
+            b = b.max_retries(v);
+        }
+        if let Some(v) = self.retry_backoff_ms {
+            b = b.retry_backoff_ms(v);
+        }
+        if let Some(v) = self.failure_abort_threshold {
+            b = b.failure_abort_threshold(v);
+        }
+        if let Some(v) = self.strictness {
+            b = b.strictness(v);
+        }
+        if let Some(v) = self.include_metadata {
+            b = b.include_metadata(v);
+        }
+        if let Some(v) = self.flavor {
+            b = b.flavor(v);
+        }
+        if let Some(ref v) = self.image_link_policy {
+            b = b.image_link_policy(v.clone());
+        }
+        if let Some(v) = self.unicode {
+            b = b.unicode(v);
+        }
+        if let Some(v) = self.local_only {
+            b = b.local_only(v);
+        }
+        if let Some(v) = self.max_pages {
+            b = b.max_pages(v);
+        }
+        if let Some(v) = self.max_file_size_bytes {
+            b = b.max_file_size_bytes(v);
+        }
+        if let Some(ref v) = self.model_prices {
+            b = b.model_prices(v.clone());
+        }
+        if let Some(ref v) = self.webhook {
+            b = b.webhook(v.clone());
+        }
+        if let Some(v) = self.download_timeout_secs {
+            b = b.download_timeout_secs(v);
+        }
+        if let Some(v) = self.api_timeout_secs {
+            b = b.api_timeout_secs(v);
+        }
+        b
+    }
+}
+
+/// A parsed `pdf2md.toml`: top-level defaults plus named profiles.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    /// Top-level keys.
+    pub defaults: ConfigProfile,
+    /// `[profile.NAME]` tables, by name.
+    pub profiles: BTreeMap<String, ConfigProfile>,
+}
+
+impl ConfigFile {
+    /// Parse TOML text.
+    pub fn parse(text: &str) -> Result<Self, Pdf2MdError> {
+        let mut defaults: ConfigProfile = toml::from_str(text)
+            .map_err(|e| Pdf2MdError::InvalidConfig(format!("config file: {e}")))?;
+        let profiles = std::mem::take(&mut defaults.profile);
+        if let Some(name) = profiles
+            .iter()
+            .find_map(|(name, p)| (!p.profile.is_empty()).then_some(name))
+        {
+            return Err(Pdf2MdError::InvalidConfig(format!(
+                "config file: profile '{name}' cannot contain nested profiles"
+            )));
+        }
+        Ok(Self { defaults, profiles })
+    }
+
+    /// Read and parse the file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Pdf2MdError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            Pdf2MdError::InvalidConfig(format!("cannot read {}: {e}", path.display()))
+        })?;
+        Self::parse(&text).map_err(|e| match e {
+            Pdf2MdError::InvalidConfig(msg) => Pdf2MdError::InvalidConfig(format!(
+                "{}: {}",
+                path.display(),
+                msg.trim_start_matches("config file: ")
+            )),
+            other => other,
+        })
+    }
+
+    /// First existing config file: `./pdf2md.toml`, then
+    /// `$XDG_CONFIG_HOME/pdf2md/config.toml` (default `~/.config`).
+    pub fn discover() -> Option<PathBuf> {
+        let local = PathBuf::from(CONFIG_FILE_NAME);
+        if local.is_file() {
+            return Some(local);
+        }
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        let user = config_home.join("pdf2md").join("config.toml");
+        user.is_file().then_some(user)
+    }
+
+    /// The defaults with `profile` (if any) laid over them.
+    pub fn resolve(&self, profile: Option<&str>) -> Result<ConfigProfile, Pdf2MdError> {
+        let Some(name) = profile else {
+            return Ok(self.defaults.clone());
+        };
+        let over = self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            Pdf2MdError::InvalidConfig(format!(
+                "unknown profile '{name}' (available: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            ))
+        })?;
+        Ok(self.defaults.clone().overlay(over.clone()))
+    }
+}
+
+impl ConversionConfig {
+    /// Load a configuration from a `pdf2md.toml`, using its top-level keys.
+    ///
+    /// Runtime-only settings (provider instances, callbacks, page selection)
+    /// are left at their defaults; set them on the result as usual.
+    pub fn from_toml_file(path: impl AsRef<Path>) -> Result<Self, Pdf2MdError> {
+        Self::from_toml_file_with_profile(path, None)
+    }
+
+    /// Like [`from_toml_file`](Self::from_toml_file), with `[profile.NAME]`
+    /// laid over the top-level keys.
+    pub fn from_toml_file_with_profile(
+        path: impl AsRef<Path>,
+        profile: Option<&str>,
+    ) -> Result<Self, Pdf2MdError> {
+        let settings = ConfigFile::load(path)?.resolve(profile)?;
+        settings.apply(ConversionConfig::builder())?.build()
+    }
+}
+
+fn header_map(headers: &BTreeMap<String, String>) -> Result<HeaderMap, Pdf2MdError> {
+    let mut map = HeaderMap::new();
+    for (k, v) in headers {
+        let name = HeaderName::from_bytes(k.as_bytes())
+            .map_err(|e| Pdf2MdError::InvalidConfig(format!("invalid header name '{k}': {e}")))?;
+        let value = HeaderValue::from_str(v).map_err(|e| {
+            Pdf2MdError::InvalidConfig(format!("invalid value for header '{k}': {e}"))
+        })?;
+        map.insert(name, value);
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+        provider = "openai"
+        model = "gpt-4.1-nano"
+        concurrency = 8
+
+        [profile.cheap]
+        dpi = 100
+        fidelity = "tier1"
+
+        [profile.archival]
+        model = "gpt-4.1"
+        dpi = 300
+        fidelity = "Tier3"
+        maintain_format = true
+    "#;
+
+    #[test]
+    fn profile_overrides_defaults_key_by_key() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        assert_eq!(file.profiles.len(), 2);
+
+        let archival = file.resolve(Some("archival")).unwrap();
+        assert_eq!(archival.model.as_deref(), Some("gpt-4.1"));
+        assert_eq!(archival.provider.as_deref(), Some("openai"));
+        assert_eq!(archival.concurrency, Some(8));
+        assert_eq!(archival.fidelity, Some(FidelityTier::Tier3));
+
+        let config = archival
+            .apply(ConversionConfig::builder())
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(config.dpi, 300);
+        assert!(config.maintain_format);
+        assert_eq!(config.concurrency, 8);
+    }
+
+    #[test]
+    fn no_profile_uses_top_level_only() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        let settings = file.resolve(None).unwrap();
+        assert_eq!(settings.dpi, None);
+        assert_eq!(settings.model.as_deref(), Some("gpt-4.1-nano"));
+    }
+
+    #[test]
+    fn lowercase_fidelity_is_accepted() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        let cheap = file.resolve(Some("cheap")).unwrap();
+        assert_eq!(cheap.fidelity, Some(FidelityTier::Tier1));
+    }
+
+    #[test]
+    fn unknown_profile_lists_available_ones() {
+        let file = ConfigFile::parse(SAMPLE).unwrap();
+        let err = file.resolve(Some("fast")).unwrap_err().to_string();
+        assert!(err.contains("archival, cheap"), "{err}");
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(ConfigFile::parse("modle = \"gpt-4.1\"").is_err());
+        assert!(ConfigFile::parse("[profile.x]\ndpii = 100").is_err());
+    }
+
+    #[test]
+    fn nested_profiles_are_rejected() {
+        let err = ConfigFile::parse("[profile.a.profile.b]\ndpi = 100").unwrap_err();
+        assert!(err.to_string().contains("nested"));
+    }
+
+    #[test]
+    fn from_toml_file_reads_headers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pdf2md.toml");
+        std::fs::write(
+            &path,
+            "base_url = \"http://localhost:8000/v1\"\n[extra_headers]\nX-Gateway-Key = \"abc\"\n",
+        )
+        .unwrap();
+        let config = ConversionConfig::from_toml_file(&path).unwrap();
+        assert_eq!(config.base_url.as_deref(), Some("http://localhost:8000/v1"));
+        assert_eq!(config.extra_headers["x-gateway-key"], "abc");
+    }
+
+    #[test]
+    fn render_layers_table_keeps_omitted_layers() {
+        let file = ConfigFile::parse("[render_layers]\nannotations = false\n").unwrap();
+        let config = file
+            .resolve(None)
+            .unwrap()
+            .apply(ConversionConfig::builder())
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(config.render_layers.content && config.render_layers.form_fields);
+        assert!(!config.render_layers.annotations);
+
+        let none = "[render_layers]\ncontent = false\nannotations = false\nform_fields = false\n";
+        let profile = ConfigFile::parse(none).unwrap().resolve(None).unwrap();
+        assert!(profile
+            .apply(ConversionConfig::builder())
+            .unwrap()
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn model_prices_table_overrides_builtin_prices() {
+        let file = ConfigFile::parse(
+            "[model_prices.\"gpt-4.1\"]\ninput_per_mtok = 1.6\noutput_per_mtok = 6.4\n",
+        )
+        .unwrap();
+        let config = file
+            .resolve(None)
+            .unwrap()
+            .apply(ConversionConfig::builder())
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(config.price_for("gpt-4.1"), Some(ModelPrice::new(1.6, 6.4)));
+    }
+}
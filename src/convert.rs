@@ -8,21 +8,50 @@
 //! instead when you want pages progressively or need to limit peak memory
 //! use on documents with hundreds of pages.
 
-use crate::config::ConversionConfig;
-use crate::error::Pdf2MdError;
-use crate::output::{ConversionOutput, ConversionStats, DocumentMetadata, PageResult};
+use crate::attachments;
+use crate::auto_dpi;
+use crate::backend::{self, VisionBackend};
+use crate::config::{ContentHint, ConversionConfig, MarkdownFlavor, Strictness, TocHandling};
+use crate::enrich::{self, Enrichment};
+use crate::error::{PageError, Pdf2MdError};
+use crate::event_log::{EventLog, RunEvent};
+use crate::footnotes;
+use crate::handwriting;
+use crate::headings;
+use crate::openrouter::OpenRouterBackend;
+use crate::output::{
+    ConversionOutput, ConversionStats, DocumentMetadata, OutputLayout, PageFileNaming, PageResult,
+};
+use crate::page_kind;
+use crate::pii;
 use crate::pipeline::render::EncodedPage;
-use crate::pipeline::{input, llm, postprocess, render};
-use edgequake_llm::{LLMProvider, ProviderFactory};
+use crate::pipeline::session::PdfSession;
+use crate::pipeline::timing::StageSamples;
+use crate::pipeline::{flavor, input, llm, postprocess, render, sections};
+use crate::polish;
+use crate::pricing;
+use crate::prompts;
+use crate::references;
+use crate::report;
+use crate::reproducibility::ReproducibilityReport;
+use crate::resolver::ConversionContext;
+use crate::summary;
+use crate::toc;
+use crate::vault;
+use crate::webhook::{self, WebhookPayload};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use edgequake_llm::{LLMProvider, OpenAICompatibleProvider, ProviderConfig, ProviderFactory};
 use futures::StreamExt;
+use reqwest::header::AUTHORIZATION;
+use std::collections::BTreeMap;
 use std::io::Write;
-use std::path::Path;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
 /// Convert a PDF file or URL to Markdown.
 ///
@@ -34,7 +63,9 @@ use tracing::{debug, info};
 ///
 /// # Returns
 /// `Ok(ConversionOutput)` on success, even if some pages failed
-/// (check `output.stats.failed_pages`).
+/// (check `output.stats.failed_pages`). If the provider goes away mid-run
+/// the remaining pages are skipped and the pages already converted are
+/// returned with `output.fatal_error` set.
 ///
 /// # Errors
 /// Returns `Err(Pdf2MdError)` only for fatal errors:
@@ -45,37 +76,126 @@ pub async fn convert(
     input_str: impl AsRef<str>,
     config: &ConversionConfig,
 ) -> Result<ConversionOutput, Pdf2MdError> {
-    let total_start = Instant::now();
+    convert_with_context(input_str, config, ConversionContext::default()).await
+}
+
+/// [`convert`] with a caller-supplied [`ConversionContext`].
+///
+/// The context (tenant, labels) is handed to `config.provider_resolver`, so
+/// one shared config can route each call to a different provider. Its
+/// `input` field is filled in from `input_str`.
+pub async fn convert_with_context(
+    input_str: impl AsRef<str>,
+    config: &ConversionConfig,
+    ctx: ConversionContext,
+) -> Result<ConversionOutput, Pdf2MdError> {
     let input_str = input_str.as_ref();
+    let result = run_conversion(input_str, config, ctx).await;
+    if let Some(ref hook) = config.webhook {
+        // A `local_only` run never reports to a remote receiver, nor about
+        // its own local-only violation.
+        let refused = matches!(result, Err(Pdf2MdError::LocalOnlyViolation { .. }));
+        if refused || (config.local_only && !is_loopback_url(&hook.url)) {
+            warn!("Webhook not sent: refused under local_only");
+        } else {
+            let payload = WebhookPayload::for_result(input_str, &result, hook.include_markdown);
+            webhook::notify(hook, &payload).await;
+        }
+    }
+    result
+}
+
+/// The body of [`convert_with_context`], without the completion webhook.
+async fn run_conversion(
+    input_str: &str,
+    config: &ConversionConfig,
+    mut ctx: ConversionContext,
+) -> Result<ConversionOutput, Pdf2MdError> {
+    let total_start = Instant::now();
     info!("Starting conversion: {}", input_str);
+    ctx.input = input_str.to_string();
 
     // ── Step 1: Resolve input ────────────────────────────────────────────
+    check_local_input(config, input_str)?;
     let resolved = input::resolve_input(input_str, config.download_timeout_secs).await?;
     let pdf_path = resolved.path().to_path_buf();
+    check_file_size(config, &pdf_path)?;
 
-    // ── Step 2: Get/create provider ──────────────────────────────────────
-    let provider = resolve_provider(config).await?;
+    // ── Step 2: Get/create backend ───────────────────────────────────────
+    let (provider, text_provider) = resolve_backend(config, &ctx).await?;
+    let fallbacks = resolve_fallback_backends(config)?;
 
     // ── Step 3: Extract metadata ─────────────────────────────────────────
-    let metadata = render::extract_metadata(&pdf_path, config.password.as_deref()).await?;
+    // One document handle serves every pass of this conversion.
+    let session = PdfSession::open(&pdf_path, config.password.as_deref()).await?;
+    let mut metadata = session.metadata().await?;
+    if config.attachments_dir.is_some() || config.parse_einvoice {
+        metadata.attachments = attachments::extract(&session, config).await?;
+    }
     let total_pages = metadata.page_count;
     info!("PDF has {} pages", total_pages);
 
+    // ── Step 3b: Optional handwriting check ──────────────────────────────
+    let detected_content = match config.content_hint {
+        ContentHint::Auto => handwriting::detect(&session, &provider, config, total_pages).await,
+        _ => None,
+    };
+    let settled = handwriting::settle(config, detected_content);
+    let config = settled.as_ref().unwrap_or(config);
+    let config = &bind_page_prompt(config, &session, input_str, &metadata).await;
+
     // ── Step 4: Compute page indices ─────────────────────────────────────
-    let page_indices = config.pages.to_indices(total_pages);
-    if page_indices.is_empty() {
+    let selected = config.pages.to_indices(total_pages);
+    if selected.is_empty() {
         return Err(Pdf2MdError::PageOutOfRange {
             page: 0,
             total: total_pages,
         });
     }
+
+    // ── Step 4a: Optional page classification ────────────────────────────
+    let selected_before_kinds = selected.len();
+    let (page_indices, page_kinds) =
+        page_kind::select(&session, &provider, config, selected).await?;
+    let excluded_by_kind = selected_before_kinds - page_indices.len();
+    check_page_limit(config, page_indices.len())?;
     debug!("Selected {} pages for conversion", page_indices.len());
 
+    // ── Step 4b: Optional DPI calibration ────────────────────────────────
+    let dpi_calibration = match config.auto_dpi {
+        Some(ref auto) => {
+            auto_dpi::calibrate(&session, &provider, config, auto, &page_indices).await
+        }
+        None => None,
+    };
+    let calibrated;
+    let config = match dpi_calibration {
+        Some(ref c) => {
+            calibrated = ConversionConfig {
+                dpi: c.chosen_dpi,
+                ..config.clone()
+            };
+            &calibrated
+        }
+        None => config,
+    };
+    let reproducibility = ReproducibilityReport::new(config, provider.name(), provider.model());
+
     // Fire on_conversion_start now that we know how many pages will actually
     // be converted (page_indices.len()), not the full document page count.
     if let Some(ref cb) = config.progress_callback {
         cb.on_conversion_start(page_indices.len());
     }
+    let events = match config.event_log {
+        Some(ref path) => Some(Arc::new(EventLog::open(path)?)),
+        None => None,
+    };
+    if let Some(ref log) = events {
+        log.record(&RunEvent::ConversionStart {
+            input: input_str.to_string(),
+            total_pages: page_indices.len(),
+        });
+    }
 
     // ── Step 5–7: Lazy render → encode → VLM pipeline ─────────────────
     //
@@ -85,77 +205,283 @@ pub async fn convert(
     // pages instead of all pages. See issue #16.
     let pipeline_start = Instant::now();
     let selected_count = page_indices.len();
-    let rx = render::spawn_lazy_render_encode(&pdf_path, config, &page_indices, config.concurrency)
-        .await?;
-
-    info!(
-        "Lazy pipeline started for {} pages (concurrency={})",
-        selected_count, config.concurrency
+    let breaker = Arc::new(
+        CircuitBreaker::new(config.failure_abort_threshold)
+            .fail_fast(config.strictness == Strictness::FailFast),
     );
+    let samples = Arc::new(StageSamples::default());
+
+    let sections = if config.maintain_format && config.parallel_sections {
+        let starts = session.section_starts().await?;
+        sections::split_sections(&page_indices, &starts)
+    } else {
+        Vec::new()
+    };
 
-    let (page_results, cumulative_render_ms) = if config.maintain_format {
-        process_sequential_lazy(rx, &provider, config, selected_count).await
+    let page_results = if sections.len() > 1 {
+        info!(
+            "Sectioned pipeline started for {} pages in {} sections (concurrency={})",
+            selected_count,
+            sections.len(),
+            config.concurrency
+        );
+        process_sections_lazy(
+            &session,
+            sections,
+            &provider,
+            &fallbacks,
+            config,
+            selected_count,
+            &breaker,
+            &samples,
+            events.as_deref(),
+        )
+        .await?
     } else {
-        process_concurrent_lazy(rx, &provider, config, selected_count).await
+        let rx = render::spawn_lazy_render_encode_in(
+            &session,
+            config,
+            &page_indices,
+            config.concurrency,
+        )
+        .await?;
+
+        info!(
+            "Lazy pipeline started for {} pages (concurrency={})",
+            selected_count, config.concurrency
+        );
+
+        if config.maintain_format {
+            process_sequential_lazy(
+                rx,
+                &provider,
+                &fallbacks,
+                config,
+                selected_count,
+                &breaker,
+                &samples,
+                events.as_deref(),
+            )
+            .await
+        } else {
+            process_concurrent_lazy(
+                rx,
+                &provider,
+                &fallbacks,
+                config,
+                selected_count,
+                &breaker,
+                &samples,
+                events.clone(),
+            )
+            .await
+        }
+    };
+    // A provider that dies mid-run stops the run but keeps the pages already
+    // converted; one that never worked is fatal.
+    let fatal_error = match breaker.check() {
+        Ok(()) => None,
+        Err(e @ Pdf2MdError::ProviderLost { .. }) => {
+            warn!("Returning partial output: {}", e);
+            Some(e.to_string())
+        }
+        Err(e) => {
+            if let Some(ref log) = events {
+                log.record(&RunEvent::ConversionFailed {
+                    error: e.to_string(),
+                });
+            }
+            return Err(e);
+        }
     };
     let pipeline_duration_ms = pipeline_start.elapsed().as_millis() as u64;
-    let render_duration_ms = cumulative_render_ms;
-    let llm_duration_ms = pipeline_duration_ms;
+    for result in &page_results {
+        samples.record_result(result);
+    }
 
     info!(
-        "Pipeline complete: {} results in {}ms (render={}ms)",
+        "Pipeline complete: {} results in {}ms",
         page_results.len(),
-        pipeline_duration_ms,
-        render_duration_ms
+        pipeline_duration_ms
     );
 
     // ── Step 8: Post-process markdown ────────────────────────────────────
+    let mut postprocess_report = postprocess::CleanReport::default();
     let mut pages: Vec<PageResult> = page_results
         .into_iter()
         .map(|mut pr| {
-            if pr.error.is_none() {
-                pr.markdown = postprocess::clean_markdown(&pr.markdown);
-            }
+            pr.page_kind = page_kinds.get(&(pr.page_num - 1)).copied();
+            postprocess_report.merge(&postprocess::postprocess_page(&mut pr, config));
             pr
         })
         .collect();
 
     // Sort by page number for consistent output
-    pages.sort_by_key(|p| p.page_num);
+    pages.sort_by_key(|p| (p.page_num, config.region_rank(p)));
+    if let Some(ref m) = config.metrics_sink {
+        pages.iter().for_each(|p| m.record_page(p));
+    }
 
-    // ── Step 9: Assemble final document ──────────────────────────────────
-    let markdown = assemble_document(&pages, config, &metadata);
+    if config.output_filter.is_some() {
+        pages
+            .iter_mut()
+            .for_each(|p| postprocess::filter_page(p, config));
+    }
 
-    // ── Step 10: Compute stats ───────────────────────────────────────────
-    let processed = pages.iter().filter(|p| p.error.is_none()).count();
+    // ── Step 8a: Optional PII masking ────────────────────────────────────
+    // Before every text pass, polish included, so no extra model call sees
+    // the data.
+    let mut pii_findings = Vec::new();
+    if let Some(ref pc) = config.pii {
+        for page in pages.iter_mut().filter(|p| p.error.is_none()) {
+            pii_findings.extend(pii::mask_page(page, pc));
+        }
+        match (&text_provider, pc.llm_names) {
+            (Some(text_provider), true) => {
+                pii_findings.extend(pii::mask_names(text_provider, &mut pages, config, pc).await);
+            }
+            (None, true) => {
+                warn!(
+                    "PII name detection skipped: a custom backend needs `provider` for text passes"
+                );
+            }
+            _ => {}
+        }
+        pii_findings.sort_by_key(|f| (f.page, f.kind));
+    }
+
+    // ── Step 8b: Optional text polish ────────────────────────────────────
+    let mut polished = polish::PolishOutcome::default();
+    if let Some(ref pc) = config.text_polish {
+        match polish_provider(config, pc, &text_provider) {
+            Some(provider) => {
+                polished = polish::polish_pages(&provider, &mut pages, config, pc).await;
+                info!(
+                    "Text polish: {} pages changed, {} rejected ({} input / {} output tokens)",
+                    polished.polished,
+                    polished.rejected,
+                    polished.input_tokens,
+                    polished.output_tokens
+                );
+            }
+            None => {
+                warn!("Text polish skipped: a custom backend needs `provider` for text passes")
+            }
+        }
+    }
+
+    // ── Step 8c: Optional footnote and reference linking ─────────────────
+    if config.link_footnotes {
+        footnotes::relabel(&mut pages);
+    }
+    let references = if config.parse_references {
+        references::link_references(&mut pages)
+    } else {
+        Vec::new()
+    };
+
+    // ── Step 9: Optional summary pass ────────────────────────────────────
+    let any_success = pages.iter().any(|p| p.error.is_none() && !p.skipped_blank);
+    let summary = match (&config.summary, &text_provider) {
+        (Some(_), None) => {
+            warn!("Summary skipped: a custom backend needs `provider` for text passes");
+            None
+        }
+        (Some(sc), Some(text_provider)) if any_success => {
+            match summary::summarize(text_provider, &pages, config, sc).await {
+                Ok((text, input_tokens, output_tokens)) => {
+                    info!(
+                        "Summary generated ({} input / {} output tokens)",
+                        input_tokens, output_tokens
+                    );
+                    Some(text)
+                }
+                Err(e) => {
+                    warn!("Summary generation failed: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    // ── Step 10: Optional keyword/entity extraction ──────────────────────
+    let enrichment = match (&config.enrichment, &text_provider) {
+        (Some(_), None) => {
+            warn!("Keyword/entity extraction skipped: a custom backend needs `provider` for text passes");
+            None
+        }
+        (Some(ec), Some(text_provider)) if any_success => {
+            match enrich::enrich(text_provider, &pages, config, ec).await {
+                Ok(e) => Some(e),
+                Err(e) => {
+                    warn!("Keyword/entity extraction failed: {}", e);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    // ── Step 11: Assemble final document ─────────────────────────────────
+    let prepended = summary
+        .as_deref()
+        .filter(|_| config.summary.as_ref().is_some_and(|sc| sc.prepend));
+    if config.toc_handling == TocHandling::Replace {
+        toc::insert_generated(&mut pages, prepended.is_some());
+    }
+    let markdown = assemble_document(&pages, config, &metadata, prepended, enrichment.as_ref());
+    let headings = headings::index(&pages, prepended.is_some());
+
+    // ── Step 12: Compute stats ───────────────────────────────────────────
+    let blank = pages.iter().filter(|p| p.skipped_blank).count();
+    let processed = pages
+        .iter()
+        .filter(|p| p.error.is_none() && !p.skipped_blank)
+        .count();
     let failed = pages.iter().filter(|p| p.error.is_some()).count();
-    let skipped = page_indices.len().saturating_sub(pages.len());
+    let skipped = page_indices.len().saturating_sub(pages.len()) + blank + excluded_by_kind;
 
-    if processed == 0 {
+    // A document of nothing but blank or excluded pages is empty, not failed.
+    let all_blank = (blank > 0 || excluded_by_kind > 0) && failed == 0;
+    if processed == 0 && !all_blank {
         let first_error = pages
             .iter()
             .find_map(|p| p.error.as_ref())
             .map(|e| format!("{}", e))
             .unwrap_or_else(|| "Unknown error".to_string());
 
-        return Err(Pdf2MdError::AllPagesFailed {
+        let err = Pdf2MdError::AllPagesFailed {
             total: pages.len(),
             retries: config.max_retries,
             first_error,
-        });
+        };
+        if let Some(ref log) = events {
+            log.record(&RunEvent::ConversionFailed {
+                error: err.to_string(),
+            });
+        }
+        return Err(err);
     }
 
-    let stats = ConversionStats {
+    let mut stats = ConversionStats {
         total_pages,
         processed_pages: processed,
         failed_pages: failed,
         skipped_pages: skipped,
         total_input_tokens: pages.iter().map(|p| p.input_tokens as u64).sum(),
+        total_image_tokens: pages.iter().map(|p| p.image_tokens as u64).sum(),
         total_output_tokens: pages.iter().map(|p| p.output_tokens as u64).sum(),
+        total_cost_usd: pricing::total_cost(pages.iter().map(|p| p.cost_usd)),
         total_duration_ms: total_start.elapsed().as_millis() as u64,
-        render_duration_ms,
-        llm_duration_ms,
+        postprocess_report,
+        polished_pages: polished.polished,
+        polish_rejected_pages: polished.rejected,
+        dpi_calibration,
+        detected_content,
+        ..Default::default()
     };
+    samples.fill(&mut stats);
 
     info!(
         "Conversion complete: {}/{} pages, {}ms total",
@@ -167,13 +493,34 @@ pub async fn convert(
     if let Some(ref cb) = config.progress_callback {
         cb.on_conversion_complete(page_indices.len(), processed);
     }
+    if let Some(ref log) = events {
+        log.record(&RunEvent::ConversionComplete {
+            processed,
+            failed,
+            total_input_tokens: stats.total_input_tokens,
+            total_output_tokens: stats.total_output_tokens,
+            total_cost_usd: stats.total_cost_usd,
+            duration_ms: stats.total_duration_ms,
+        });
+    }
 
-    Ok(ConversionOutput {
+    let output = ConversionOutput {
         markdown,
         pages,
         metadata,
         stats,
-    })
+        summary,
+        enrichment,
+        pii_findings,
+        references,
+        headings,
+        fatal_error,
+        reproducibility: Some(reproducibility),
+    };
+    if config.strictness == Strictness::AllOrNothing {
+        return output.into_result();
+    }
+    Ok(output)
 }
 
 /// Convert a PDF and write output directly to a file.
@@ -215,6 +562,138 @@ pub async fn convert_to_file(
     Ok(output.stats)
 }
 
+/// Convert a PDF and write it out in the given [`OutputLayout`].
+///
+/// [`OutputLayout::SingleFile`] behaves like [`convert_to_file`];
+/// [`OutputLayout::ObsidianVault`] writes one note per section with
+/// [`vault::write_vault`]; [`OutputLayout::PerPageFiles`] writes each page's
+/// PNG and Markdown sidecar.
+pub async fn convert_to_layout(
+    input_str: impl AsRef<str>,
+    layout: &OutputLayout,
+    config: &ConversionConfig,
+) -> Result<ConversionStats, Pdf2MdError> {
+    match layout {
+        OutputLayout::SingleFile { path } => convert_to_file(input_str, path, config).await,
+        OutputLayout::ObsidianVault { dir } => {
+            let source = input_str.as_ref();
+            let output = convert(source, config).await?;
+            let notes = vault::write_vault(&output, source, dir).await?;
+            debug!("Wrote {} notes to {}", notes, dir.display());
+            Ok(output.stats)
+        }
+        OutputLayout::PerPageFiles { dir, naming } => {
+            convert_to_page_files(input_str.as_ref(), dir, *naming, config).await
+        }
+    }
+}
+
+/// [`OutputLayout::PerPageFiles`]: convert, keeping the PNG each page was
+/// sent to the model as, and write both. A URL is downloaded once.
+async fn convert_to_page_files(
+    source: &str,
+    dir: &Path,
+    naming: PageFileNaming,
+    config: &ConversionConfig,
+) -> Result<ConversionStats, Pdf2MdError> {
+    check_local_input(config, source)?;
+    let resolved = input::resolve_input(source, config.download_timeout_secs).await?;
+    let local = resolved.path().to_string_lossy().into_owned();
+
+    let mut run_config = config.clone();
+    run_config.keep_page_images = true;
+    let output = convert(&local, &run_config).await?;
+    let pngs = page_pngs(&output.pages);
+
+    let write = |path: PathBuf, bytes: Vec<u8>| async move {
+        tokio::fs::write(&path, bytes)
+            .await
+            .map_err(|e| Pdf2MdError::OutputWriteFailed { path, source: e })
+    };
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| Pdf2MdError::OutputWriteFailed {
+            path: dir.to_path_buf(),
+            source: e,
+        })?;
+    for (page_num, png) in pngs {
+        write(
+            dir.join(format!("{}.png", naming.file_stem(source, page_num))),
+            png,
+        )
+        .await?;
+    }
+    for page in output.pages.iter().filter(|p| p.error.is_none()) {
+        let path = dir.join(format!("{}.md", naming.file_stem(source, page.page_num)));
+        write(path, page.markdown.clone().into_bytes()).await?;
+    }
+    debug!(
+        "Wrote {} page files to {}",
+        output.pages.len(),
+        dir.display()
+    );
+    Ok(output.stats)
+}
+
+/// `(page_num, PNG bytes)` of the first kept image of each page, in page
+/// order.
+fn page_pngs(pages: &[PageResult]) -> Vec<(usize, Vec<u8>)> {
+    let mut pngs = BTreeMap::new();
+    for page in pages {
+        let Some(ref image) = page.page_image else {
+            continue;
+        };
+        if pngs.contains_key(&page.page_num) {
+            continue;
+        }
+        match STANDARD.decode(&image.data) {
+            Ok(bytes) => {
+                pngs.insert(page.page_num, bytes);
+            }
+            Err(e) => warn!("Page {}: kept image is not base64: {}", page.page_num, e),
+        }
+    }
+    pngs.into_iter().collect()
+}
+
+/// Convert a PDF and write an HTML debug report to `report_path`: each
+/// page's image beside its Markdown, with timings, token counts, retries,
+/// and what post-processing changed (see [`report`]).
+///
+/// The model's raw answers and the page images it was sent are kept for
+/// the report, and dropped from the returned output again unless
+/// [`ConversionConfig::keep_raw_output`] or
+/// [`ConversionConfig::keep_page_images`] is set. The pages are rendered
+/// once, for the model; the report embeds those images.
+pub async fn convert_with_debug_report(
+    input_str: impl AsRef<str>,
+    report_path: impl AsRef<Path>,
+    config: &ConversionConfig,
+) -> Result<ConversionOutput, Pdf2MdError> {
+    let source = input_str.as_ref();
+    let mut run_config = config.clone();
+    run_config.keep_raw_output = true;
+    run_config.keep_page_images = true;
+    let mut output = convert(source, &run_config).await?;
+
+    let images: Vec<_> = page_pngs(&output.pages)
+        .into_iter()
+        .filter_map(|(page_num, png)| report::png_image(page_num, png))
+        .collect();
+    report::write_report(&output, source, &images, report_path.as_ref()).await?;
+    debug!("Wrote debug report to {}", report_path.as_ref().display());
+
+    for page in &mut output.pages {
+        if !config.keep_raw_output {
+            page.raw_markdown = None;
+        }
+        if !config.keep_page_images {
+            page.page_image = None;
+        }
+    }
+    Ok(output)
+}
+
 /// Synchronous wrapper around [`convert`].
 ///
 /// Creates a temporary tokio runtime internally.
@@ -290,10 +769,14 @@ pub async fn convert_from_bytes(
 /// - **LMStudio / lm-studio / lm_studio**: `llava` is a common vision model
 ///   that ships with LM Studio's model catalogue. Users can override via
 ///   `LMSTUDIO_MODEL` or `config.model`.
+/// - **OpenRouter**: model IDs are namespaced by vendor, so the bare
+///   `gpt-4.1-nano` would be rejected; `openai/gpt-4.1-nano` is the same cheap
+///   vision model routed through OpenRouter.
 /// - All others fall back to `gpt-4.1-nano` (fast, cheap, vision-capable).
 fn default_vision_model_for_provider(provider_name: &str) -> &'static str {
     match provider_name {
         "mistral" | "mistral-ai" | "mistralai" => "pixtral-12b-2409",
+        "openrouter" | "open-router" | "open_router" => "openai/gpt-4.1-nano",
         "ollama" => "llava",
         "lmstudio" | "lm-studio" | "lm_studio" => "llava",
         _ => "gpt-4.1-nano",
@@ -318,45 +801,493 @@ fn create_vision_provider(
     })
 }
 
+/// Instantiate an OpenAI-compatible provider for a custom endpoint.
+///
+/// Used whenever `config.base_url` or `config.extra_headers` is set: both only
+/// make sense for the generic OpenAI wire format, which is what vLLM, LiteLLM
+/// proxies, and most corporate gateways speak. Without an explicit base URL
+/// the request goes to the named provider's own OpenAI-compatible endpoint
+/// (OpenAI when unnamed); providers without one cannot take extra headers.
+fn create_openai_compatible_provider(
+    config: &ConversionConfig,
+    provider_name: &str,
+    model: &str,
+) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
+    let base_url = match config.base_url {
+        Some(ref url) => url.clone(),
+        None => openai_compatible_endpoint(provider_name)
+            .ok_or_else(|| Pdf2MdError::ProviderNotConfigured {
+                provider: provider_name.to_string(),
+                hint: "Extra headers are not supported for this provider; \
+                       set base_url to its OpenAI-compatible endpoint"
+                    .to_string(),
+            })?
+            .to_string(),
+    };
+    let api_key = explicit_api_key(config, provider_name);
+    build_openai_compatible(config, provider_name, model, base_url, api_key)
+}
+
+/// Instantiate a named provider from an explicitly supplied API key.
+///
+/// The key goes into an `Authorization: Bearer` header on an OpenAI-compatible
+/// client aimed at the vendor's OpenAI-compatible endpoint, so nothing is read
+/// from (or written to) the process environment.
+fn create_keyed_provider(
+    config: &ConversionConfig,
+    provider_name: &str,
+    model: &str,
+    api_key: &str,
+) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
+    let base_url = match config.base_url {
+        Some(ref url) => url.clone(),
+        None => openai_compatible_endpoint(provider_name)
+            .ok_or_else(|| Pdf2MdError::ProviderNotConfigured {
+                provider: provider_name.to_string(),
+                hint: "An explicit api_key is not supported for this provider; \
+                       set base_url to its OpenAI-compatible endpoint"
+                    .to_string(),
+            })?
+            .to_string(),
+    };
+    build_openai_compatible(config, provider_name, model, base_url, Some(api_key))
+}
+
+fn build_openai_compatible(
+    config: &ConversionConfig,
+    provider_name: &str,
+    model: &str,
+    base_url: String,
+    api_key: Option<&str>,
+) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
+    #[allow(unused_mut)]
+    let mut headers: std::collections::HashMap<String, String> = config
+        .extra_headers
+        .iter()
+        .map(|(name, value)| {
+            value
+                .to_str()
+                .map(|v| (name.as_str().to_string(), v.to_string()))
+                .map_err(|_| {
+                    Pdf2MdError::InvalidConfig(format!(
+                        "Header '{name}' has a non-ASCII value and cannot be forwarded"
+                    ))
+                })
+        })
+        .collect::<Result<_, _>>()?;
+
+    #[cfg(feature = "otel")]
+    if let Some(ref cx) = config.trace_context {
+        headers.extend(cx.headers());
+    }
+
+    if let Some(key) = api_key {
+        headers.insert("authorization".to_string(), format!("Bearer {key}"));
+    }
+
+    let provider_config = ProviderConfig {
+        name: provider_name.to_string(),
+        base_url: Some(base_url.clone()),
+        // An explicit key must not be shadowed by (or fall back to) the env,
+        // and a vendor's key only ever goes to that vendor's endpoint.
+        api_key_env: api_key
+            .is_none()
+            .then(|| endpoint_key_env(&base_url))
+            .flatten()
+            .map(str::to_string),
+        default_llm_model: Some(model.to_string()),
+        headers,
+        ..Default::default()
+    };
+
+    OpenAICompatibleProvider::from_config(provider_config)
+        .map(|p| Arc::new(p) as Arc<dyn LLMProvider>)
+        .map_err(|e| Pdf2MdError::ProviderNotConfigured {
+            provider: provider_name.to_string(),
+            hint: format!("Could not create OpenAI-compatible provider for {base_url}: {e}"),
+        })
+}
+
+/// OpenAI-compatible endpoints of the providers that accept a bearer API
+/// key: provider names, endpoint, and the variable holding the key.
+const VENDOR_ENDPOINTS: &[(&[&str], &str, &str)] = &[
+    (&["openai"], "https://api.openai.com/v1", "OPENAI_API_KEY"),
+    (
+        &["anthropic", "claude"],
+        "https://api.anthropic.com/v1",
+        "ANTHROPIC_API_KEY",
+    ),
+    (
+        &["gemini", "google"],
+        "https://generativelanguage.googleapis.com/v1beta/openai",
+        "GEMINI_API_KEY",
+    ),
+    (
+        &["mistral", "mistral-ai", "mistralai"],
+        "https://api.mistral.ai/v1",
+        "MISTRAL_API_KEY",
+    ),
+    (
+        &["openrouter", "open-router", "open_router"],
+        "https://openrouter.ai/api/v1",
+        "OPENROUTER_API_KEY",
+    ),
+    (&["groq"], "https://api.groq.com/openai/v1", "GROQ_API_KEY"),
+    (&["xai"], "https://api.x.ai/v1", "XAI_API_KEY"),
+    (
+        &["deepseek"],
+        "https://api.deepseek.com/v1",
+        "DEEPSEEK_API_KEY",
+    ),
+    (
+        &["together"],
+        "https://api.together.xyz/v1",
+        "TOGETHER_API_KEY",
+    ),
+];
+
+/// OpenAI-compatible endpoint for providers that accept a bearer API key.
+fn openai_compatible_endpoint(provider_name: &str) -> Option<&'static str> {
+    let name = provider_name.to_ascii_lowercase();
+    VENDOR_ENDPOINTS
+        .iter()
+        .find(|&&(names, _, _)| names.contains(&name.as_str()))
+        .map(|(_, endpoint, _)| *endpoint)
+}
+
+/// The key variable of the vendor serving `base_url`, matched on the host.
+/// `None` for any other host (vLLM, gateways), which gets no key from the
+/// environment.
+fn endpoint_key_env(base_url: &str) -> Option<&'static str> {
+    let host = |url: &str| {
+        reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_ascii_lowercase))
+    };
+    let target = host(base_url)?;
+    VENDOR_ENDPOINTS
+        .iter()
+        .find(|&&(_, endpoint, _)| host(endpoint).as_deref() == Some(target.as_str()))
+        .map(|(_, _, var)| *var)
+}
+
+/// The caller-supplied key for `provider_name`: `config.api_key`, else the
+/// matching entry in `config.credentials`.
+fn explicit_api_key<'a>(config: &'a ConversionConfig, provider_name: &str) -> Option<&'a str> {
+    config
+        .api_key
+        .as_deref()
+        .or_else(|| config.credentials.get(provider_name))
+}
+
+/// Instantiate a named provider, preferring an explicit key over the env.
+fn create_named_provider(
+    config: &ConversionConfig,
+    provider_name: &str,
+    model: &str,
+) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
+    match explicit_api_key(config, provider_name) {
+        Some(key) => create_keyed_provider(config, provider_name, model, key),
+        None => create_vision_provider(provider_name, model),
+    }
+}
+
+/// Whether the config asks for a custom OpenAI-compatible endpoint.
+///
+/// A trace context also needs per-request headers, which only the
+/// OpenAI-compatible client supports, so it switches an explicit `openai`
+/// provider onto that path; other providers log a warning and go without.
+fn uses_custom_endpoint(config: &ConversionConfig) -> bool {
+    config.base_url.is_some() || !config.extra_headers.is_empty() || wants_trace_headers(config)
+}
+
+#[cfg(feature = "otel")]
+fn wants_trace_headers(config: &ConversionConfig) -> bool {
+    if config.trace_context.is_none() {
+        return false;
+    }
+    match config.provider_name.as_deref() {
+        Some(name) if name.eq_ignore_ascii_case("openai") => true,
+        other => {
+            warn!(
+                "Trace context not propagated: provider '{}' is not OpenAI-compatible \
+                 (set base_url or provider_name = \"openai\")",
+                other.unwrap_or("auto")
+            );
+            false
+        }
+    }
+}
+
+#[cfg(not(feature = "otel"))]
+fn wants_trace_headers(_config: &ConversionConfig) -> bool {
+    false
+}
+
+/// Providers that only ever run on the user's own machine.
+fn is_local_provider(name: &str) -> bool {
+    matches!(
+        name.to_ascii_lowercase().as_str(),
+        "ollama" | "lmstudio" | "lm-studio" | "lm_studio"
+    )
+}
+
+/// `true` for `localhost`, `127.0.0.0/8`, and `::1` URLs (scheme optional).
+pub(crate) fn is_loopback_url(url: &str) -> bool {
+    let url = if url.contains("://") {
+        url.to_string()
+    } else {
+        format!("http://{url}")
+    };
+    let Ok(parsed) = reqwest::Url::parse(&url) else {
+        return false;
+    };
+    let Some(host) = parsed.host_str() else {
+        return false;
+    };
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Enforce `config.local_only` on the provider settings.
+///
+/// Called before any provider is built or used, pre-built ones included, so
+/// a violation never reaches the network. Fallback providers share
+/// `provider_name` and `base_url`, so this covers them too.
+fn check_local_provider(config: &ConversionConfig) -> Result<(), Pdf2MdError> {
+    if !config.local_only {
+        return Ok(());
+    }
+    let violation = |detail: String| Err(Pdf2MdError::LocalOnlyViolation { detail });
+    if let Some(ref provider) = config.provider {
+        return violation(format!(
+            "pre-built provider '{}' cannot be proven local; set provider_name or a \
+             loopback base_url instead",
+            provider.name()
+        ));
+    }
+    if config.provider_resolver.is_some() {
+        return violation("providers from a provider_resolver cannot be proven local".into());
+    }
+    if let Some(ref backend) = config.backend {
+        if !backend.is_local() {
+            return violation(format!(
+                "backend '{}' does not report is_local()",
+                backend.name()
+            ));
+        }
+        return Ok(());
+    }
+    if let Some(ref url) = config.base_url {
+        if !is_loopback_url(url) {
+            return violation(format!("base_url '{url}' is not a loopback address"));
+        }
+        return Ok(());
+    }
+    if uses_custom_endpoint(config) {
+        return violation("extra headers without base_url would go to a public endpoint".into());
+    }
+    let Some(ref name) = config.provider_name else {
+        return violation(
+            "no local provider configured; set provider_name to \"ollama\" or \"lmstudio\", \
+             or base_url to a localhost endpoint"
+                .into(),
+        );
+    };
+    if !is_local_provider(name) {
+        return violation(format!("provider '{name}' is a remote API"));
+    }
+    for var in ["OLLAMA_HOST", "LMSTUDIO_HOST"] {
+        if let Ok(host) = std::env::var(var) {
+            if !host.is_empty() && !is_loopback_url(&host) {
+                return violation(format!("{var}={host} is not a loopback address"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reject URL inputs and non-loopback webhooks under `config.local_only`.
+pub(crate) fn check_local_input(config: &ConversionConfig, input: &str) -> Result<(), Pdf2MdError> {
+    if !config.local_only {
+        return Ok(());
+    }
+    if input::is_url(input) {
+        return Err(Pdf2MdError::LocalOnlyViolation {
+            detail: format!("URL input '{input}' would be downloaded; pass a local file"),
+        });
+    }
+    if let Some(ref hook) = config.webhook {
+        if !is_loopback_url(&hook.url) {
+            return Err(Pdf2MdError::LocalOnlyViolation {
+                detail: format!("webhook '{}' is not a loopback address", hook.url),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Enforce `config.max_file_size_bytes` on the resolved input file.
+pub(crate) fn check_file_size(config: &ConversionConfig, path: &Path) -> Result<(), Pdf2MdError> {
+    let Some(limit) = config.max_file_size_bytes else {
+        return Ok(());
+    };
+    let size = std::fs::metadata(path)
+        .map_err(|e| Pdf2MdError::Internal(format!("cannot stat '{}': {e}", path.display())))?
+        .len();
+    if size > limit {
+        return Err(Pdf2MdError::DocumentTooLarge {
+            detail: format!("file is {size} bytes, max_file_size_bytes is {limit}"),
+        });
+    }
+    Ok(())
+}
+
+/// Enforce `config.max_pages` on the number of selected pages.
+pub(crate) fn check_page_limit(
+    config: &ConversionConfig,
+    selected: usize,
+) -> Result<(), Pdf2MdError> {
+    match config.max_pages {
+        Some(limit) if selected > limit => Err(Pdf2MdError::DocumentTooLarge {
+            detail: format!("{selected} pages selected, max_pages is {limit}"),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// A copy of `config` whose page prompt has this document's variables
+/// (`{total_pages}`, `{document_title}`, `{detected_language}`) filled in,
+/// leaving only `{page_num}` for each page. See [`crate::prompts`].
+///
+/// The text layer is only sampled when the template asks for the language.
+pub(crate) async fn bind_page_prompt(
+    config: &ConversionConfig,
+    session: &PdfSession,
+    input_str: &str,
+    metadata: &DocumentMetadata,
+) -> ConversionConfig {
+    let template = config.page_prompt_template();
+    let language = if template.uses(prompts::VAR_DETECTED_LANGUAGE) {
+        match session.sample_text(LANGUAGE_SAMPLE_CHARS).await {
+            Ok(text) => prompts::detect_language(&text),
+            Err(e) => {
+                debug!("Language detection skipped: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let title = metadata.title.clone().unwrap_or_else(|| {
+        let name = input_str.rsplit(['/', '\\']).next().unwrap_or(input_str);
+        Path::new(name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| name.to_string())
+    });
+    let template = template
+        .var_or_keep(prompts::VAR_TOTAL_PAGES, || metadata.page_count.to_string())
+        .var_or_keep(prompts::VAR_DOCUMENT_TITLE, || title)
+        .var_or_keep(prompts::VAR_DETECTED_LANGUAGE, || {
+            language.unwrap_or("unknown").to_string()
+        });
+    ConversionConfig {
+        prompt_template: Some(template),
+        ..config.clone()
+    }
+}
+
+/// Characters of text layer read for `{detected_language}`.
+const LANGUAGE_SAMPLE_CHARS: usize = 4000;
+
 /// Resolve the LLM provider, from most-specific to least-specific.
 ///
-/// The four-level fallback chain lets library users and CLI users each set
+/// The five-level fallback chain lets library users and CLI users each set
 /// exactly as much or as little as they need:
 ///
 /// 1. **Pre-built provider** (`config.provider`) — the caller constructed and
 ///    configured the provider entirely; we use it as-is. Useful in tests or
 ///    when the caller needs custom middleware (caching, rate-limiting).
+///    Next comes `config.provider_resolver`, asked with `ctx` (per tenant).
+///    With `config.local_only` both are refused, and everything below is
+///    refused unless local.
+///
+/// 2. **Custom endpoint** (`config.base_url` / `config.extra_headers`) — an
+///    OpenAI-compatible client aimed at vLLM, a LiteLLM proxy, or a gateway.
+///    `provider_name` (if any) is only used as a display name here.
 ///
-/// 2. **Named provider + model** (`config.provider_name`) — the caller named
+/// 3. **Named provider + model** (`config.provider_name`) — the caller named
 ///    a provider (e.g. `"openai"`) and optional model. We call
 ///    [`ProviderFactory::create_llm_provider`] which reads the corresponding
-///    API key (`OPENAI_API_KEY`, etc.) from the environment.
+///    API key (`OPENAI_API_KEY`, etc.) from the environment — unless the
+///    config carries an explicit key (`api_key` / `credentials`).
 ///
-/// 3. **Environment pair** (`EDGEQUAKE_LLM_PROVIDER` + `EDGEQUAKE_MODEL`) —
+///    Without a name, explicit credentials pick the provider themselves
+///    (`api_key` means OpenAI) and the environment is never consulted.
+///
+/// 4. **Environment pair** (`EDGEQUAKE_LLM_PROVIDER` + `EDGEQUAKE_MODEL`) —
 ///    Both env vars set means the caller chose a provider and model at the
 ///    execution environment level (Makefile, shell script, CI). Checked before
 ///    full auto-detection so the model choice is honoured even when multiple
 ///    API keys are present.
 ///
-/// 4. **Full auto-detection** (`ProviderFactory::from_env`) — the factory
+/// 5. **Full auto-detection** (`ProviderFactory::from_env`) — the factory
 ///    scans all known API key variables and picks the first available provider.
 ///    Convenient for `pdf2md document.pdf` with no other configuration.
-async fn resolve_provider(config: &ConversionConfig) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
-    // 1) User-provided provider takes priority
+pub(crate) async fn resolve_provider(
+    config: &ConversionConfig,
+    ctx: &ConversionContext,
+) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
+    check_local_provider(config)?;
+
+    // 1) User-provided provider takes priority, then a per-conversion resolver
     if let Some(ref provider) = config.provider {
         return Ok(Arc::clone(provider));
     }
+    if let Some(ref resolver) = config.provider_resolver {
+        return resolver.resolve(ctx).await;
+    }
+
+    // 2) Custom OpenAI-compatible endpoint (vLLM, LiteLLM, gateways)
+    if uses_custom_endpoint(config) {
+        let unnamed = if config.base_url.is_some() {
+            "openai-compatible"
+        } else {
+            "openai"
+        };
+        let name = config.provider_name.as_deref().unwrap_or(unnamed);
+        let model = config.model.as_deref().unwrap_or("gpt-4.1-nano");
+        return create_openai_compatible_provider(config, name, model);
+    }
 
-    // 2) Provider name + model (use provider-aware vision model as default)
+    // 3) Provider name + model (use provider-aware vision model as default)
     if let Some(ref name) = config.provider_name {
         let model = config
             .model
             .as_deref()
             .unwrap_or_else(|| default_vision_model_for_provider(name));
-        return create_vision_provider(name, model);
+        return create_named_provider(config, name, model);
+    }
+
+    // 3b) Explicit credentials without a name: never fall through to the env
+    let keyed = if config.api_key.is_some() {
+        Some("openai")
+    } else {
+        config.credentials.providers().next()
+    };
+    if let Some(name) = keyed {
+        let model = config
+            .model
+            .as_deref()
+            .unwrap_or_else(|| default_vision_model_for_provider(name));
+        return create_named_provider(config, name, model);
     }
 
-    // 3) Auto-detect from environment; honour EDGEQUAKE_LLM_PROVIDER + EDGEQUAKE_MODEL when both set
+    // 4) Auto-detect from environment; honour EDGEQUAKE_LLM_PROVIDER + EDGEQUAKE_MODEL when both set
     if let (Ok(prov), Ok(model)) = (
         std::env::var("EDGEQUAKE_LLM_PROVIDER"),
         std::env::var("EDGEQUAKE_MODEL"),
@@ -400,83 +1331,284 @@ async fn resolve_provider(config: &ConversionConfig) -> Result<Arc<dyn LLMProvid
     Ok(llm_provider)
 }
 
+/// Instantiate one provider per entry in `config.fallback_models`.
+///
+/// Fallbacks share the primary's `provider_name` (typically `"openrouter"`,
+/// where a single key reaches every vendor's models). They are only built
+/// when the provider is named explicitly: a pre-built `config.provider` or an
+/// auto-detected one gives us no name to build siblings from.
+pub(crate) fn resolve_fallback_providers(
+    config: &ConversionConfig,
+) -> Result<Vec<Arc<dyn LLMProvider>>, Pdf2MdError> {
+    if config.fallback_models.is_empty() {
+        return Ok(Vec::new());
+    }
+    let name = match (&config.provider, &config.provider_name) {
+        (None, Some(name)) if config.provider_resolver.is_none() => name,
+        _ => {
+            warn!(
+                "fallback_models ignored: requires provider_name and no pre-built provider \
+                 or resolver"
+            );
+            return Ok(Vec::new());
+        }
+    };
+    config
+        .fallback_models
+        .iter()
+        .map(|model| sibling_provider(config, name, model))
+        .collect()
+}
+
+/// A provider for `model` built the way the primary one is.
+fn sibling_provider(
+    config: &ConversionConfig,
+    name: &str,
+    model: &str,
+) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
+    if uses_custom_endpoint(config) {
+        create_openai_compatible_provider(config, name, model)
+    } else {
+        create_named_provider(config, name, model)
+    }
+}
+
+/// Provider for the text-polish pass: [`PolishConfig::model`] on the
+/// primary's `provider_name` when it can be built (the same rule as
+/// fallbacks), else the text-pass provider.
+///
+/// [`PolishConfig::model`]: crate::polish::PolishConfig::model
+fn polish_provider(
+    config: &ConversionConfig,
+    polish: &polish::PolishConfig,
+    text_provider: &Option<Arc<dyn LLMProvider>>,
+) -> Option<Arc<dyn LLMProvider>> {
+    if let Some(ref model) = polish.model {
+        match (&config.provider, &config.provider_name) {
+            (None, Some(name)) if config.provider_resolver.is_none() => {
+                match sibling_provider(config, name, model) {
+                    Ok(provider) => return Some(provider),
+                    Err(e) => warn!(
+                        "Text polish: model {} unavailable ({}); using the conversion's model",
+                        model, e
+                    ),
+                }
+            }
+            _ => warn!(
+                "Text polish: model ignored: requires provider_name and no pre-built provider \
+                 or resolver"
+            ),
+        }
+    }
+    text_provider.clone()
+}
+
+/// Resolve the backend used for page calls.
+///
+/// A custom `config.backend` wins; otherwise the provider from
+/// [`resolve_provider`] is wrapped in [`backend::LlmProviderBackend`]. The second
+/// value is the provider for text-only passes (summary, enrichment): the
+/// resolved provider, or with a custom backend only an explicit
+/// `config.provider`.
+pub(crate) async fn resolve_backend(
+    config: &ConversionConfig,
+    ctx: &ConversionContext,
+) -> Result<(Arc<dyn VisionBackend>, Option<Arc<dyn LLMProvider>>), Pdf2MdError> {
+    check_local_provider(config)?;
+    if let Some(ref backend) = config.backend {
+        return Ok((Arc::clone(backend), config.provider.clone()));
+    }
+    let provider = resolve_provider(config, ctx).await?;
+    if let Some((name, base_url)) = openrouter_routing(config) {
+        return Ok((openrouter_backend(config, name, base_url)?, Some(provider)));
+    }
+    Ok((
+        backend::from_provider(Arc::clone(&provider)),
+        Some(provider),
+    ))
+}
+
+/// Fallback backends: [`resolve_fallback_providers`] wrapped for page calls.
+///
+/// Empty with a custom `config.backend`, whose fallbacks are its own concern,
+/// and on OpenRouter, where the primary backend sends them in the request.
+pub(crate) fn resolve_fallback_backends(
+    config: &ConversionConfig,
+) -> Result<Vec<Arc<dyn VisionBackend>>, Pdf2MdError> {
+    if config.backend.is_some() {
+        if !config.fallback_models.is_empty() {
+            warn!("fallback_models ignored: a custom backend is configured");
+        }
+        return Ok(Vec::new());
+    }
+    if openrouter_routing(config).is_some() {
+        return Ok(Vec::new());
+    }
+    Ok(resolve_fallback_providers(config)?
+        .into_iter()
+        .map(backend::from_provider)
+        .collect())
+}
+
+/// Provider name and endpoint when page calls go to OpenRouter with
+/// fallback models, which [`OpenRouterBackend`] then routes server-side.
+/// `None` for every other provider, and for a `base_url` that is not
+/// OpenRouter's own (a gateway in front of it): those fall back client-side.
+fn openrouter_routing(config: &ConversionConfig) -> Option<(&str, &str)> {
+    if config.fallback_models.is_empty()
+        || config.backend.is_some()
+        || config.provider.is_some()
+        || config.provider_resolver.is_some()
+    {
+        return None;
+    }
+    let name = config.provider_name.as_deref()?;
+    let endpoint = openai_compatible_endpoint(name)?;
+    let is_openrouter = |url: &str| endpoint_key_env(url) == Some(OPENROUTER_KEY_ENV);
+    if !is_openrouter(endpoint) {
+        return None;
+    }
+    match config.base_url.as_deref() {
+        Some(url) if is_openrouter(url) => Some((name, url)),
+        Some(_) => None,
+        None => Some((name, endpoint)),
+    }
+}
+
+/// Key variable of OpenRouter in [`VENDOR_ENDPOINTS`].
+const OPENROUTER_KEY_ENV: &str = "OPENROUTER_API_KEY";
+
+/// [`OpenRouterBackend`] for the primary model followed by
+/// `config.fallback_models`.
+fn openrouter_backend(
+    config: &ConversionConfig,
+    name: &str,
+    base_url: &str,
+) -> Result<Arc<dyn VisionBackend>, Pdf2MdError> {
+    let api_key = explicit_api_key(config, name)
+        .map(str::to_string)
+        .or_else(|| {
+            std::env::var(OPENROUTER_KEY_ENV)
+                .ok()
+                .filter(|k| !k.is_empty())
+        });
+    if api_key.is_none() && !config.extra_headers.contains_key(AUTHORIZATION) {
+        return Err(Pdf2MdError::ProviderNotConfigured {
+            provider: name.to_string(),
+            hint: format!("Set {OPENROUTER_KEY_ENV} or pass an API key."),
+        });
+    }
+    let primary = config
+        .model
+        .as_deref()
+        .unwrap_or_else(|| default_vision_model_for_provider(name));
+    let models = std::iter::once(primary.to_string())
+        .chain(config.fallback_models.iter().cloned())
+        .collect();
+    let backend = OpenRouterBackend::new(
+        base_url,
+        api_key,
+        models,
+        config.extra_headers.clone(),
+        Duration::from_secs(config.api_timeout_secs),
+    )?;
+    Ok(Arc::new(backend))
+}
+
 /// Process pages concurrently through the lazy pipeline (maintain_format = false).
 ///
 /// Receives encoded pages from the bounded channel and submits them to the VLM
 /// via `buffer_unordered(concurrency)`. Returns the page results and cumulative
 /// render+encode time.
+#[allow(clippy::too_many_arguments)]
 async fn process_concurrent_lazy(
     rx: mpsc::Receiver<EncodedPage>,
-    provider: &Arc<dyn LLMProvider>,
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
     config: &ConversionConfig,
     total_selected_pages: usize,
-) -> (Vec<PageResult>, u64) {
-    let render_ms = Arc::new(AtomicU64::new(0));
+    breaker: &Arc<CircuitBreaker>,
+    samples: &Arc<StageSamples>,
+    events: Option<Arc<EventLog>>,
+) -> Vec<PageResult> {
     let provider_ref = Arc::clone(provider);
+    let fallbacks_ref: Arc<[Arc<dyn VisionBackend>]> = fallbacks.into();
     let cfg_ref = config.clone();
     let concurrency = config.concurrency;
-    let render_ms_clone = Arc::clone(&render_ms);
+    let samples_ref = Arc::clone(samples);
+    let breaker_ref = Arc::clone(breaker);
 
-    let results: Vec<PageResult> = ReceiverStream::new(rx)
+    ReceiverStream::new(rx)
         .map(move |page| {
-            render_ms_clone.fetch_add(page.render_encode_ms, Ordering::Relaxed);
             let prov = Arc::clone(&provider_ref);
+            let fallbacks = Arc::clone(&fallbacks_ref);
             let cfg = cfg_ref.clone();
+            let events = events.clone();
+            let samples = Arc::clone(&samples_ref);
             let total = total_selected_pages;
             async move {
+                samples.record_encoded(&page);
                 let page_num = page.page_index + 1;
                 if let Some(ref cb) = cfg.progress_callback {
                     cb.on_page_start(page_num, total);
                 }
-                let result = llm::process_page(&prov, page_num, page.image_data, None, &cfg).await;
+                if let Some(ref log) = events {
+                    log.record(&RunEvent::PageStart { page: page_num });
+                }
+                let result = llm::process_encoded_page(&prov, &fallbacks, page, None, &cfg).await;
                 if let Some(ref cb) = cfg.progress_callback {
                     match &result.error {
                         None => cb.on_page_complete(page_num, total, result.markdown.len()),
                         Some(e) => cb.on_page_error(page_num, total, e.to_string()),
                     }
                 }
+                if let Some(ref log) = events {
+                    log.record(&RunEvent::for_page(&result));
+                }
                 result
             }
         })
         .buffer_unordered(concurrency)
+        // Once the breaker trips, stop pulling results: dropping the stream
+        // cancels in-flight VLM calls and closes the render channel.
+        .take_while(move |result| futures::future::ready(!breaker_ref.record(result)))
         .collect()
-        .await;
-
-    (results, render_ms.load(Ordering::Relaxed))
+        .await
 }
 
 /// Process pages sequentially through the lazy pipeline (maintain_format = true).
 ///
 /// Receives encoded pages one at a time from the bounded channel, passing the
-/// previous page's markdown as context to each VLM call. Returns the page
-/// results and cumulative render+encode time.
+/// last `context_window_pages` pages' markdown as context to each VLM call.
+#[allow(clippy::too_many_arguments)]
 async fn process_sequential_lazy(
     rx: mpsc::Receiver<EncodedPage>,
-    provider: &Arc<dyn LLMProvider>,
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
     config: &ConversionConfig,
     total_selected_pages: usize,
-) -> (Vec<PageResult>, u64) {
+    breaker: &CircuitBreaker,
+    samples: &StageSamples,
+    events: Option<&EventLog>,
+) -> Vec<PageResult> {
     let mut results = Vec::new();
-    let mut prior_markdown: Option<String> = None;
-    let mut total_render_ms: u64 = 0;
+    let mut prior = llm::PriorPages::for_config(config);
     let mut rx = rx;
 
     while let Some(page) = rx.recv().await {
-        total_render_ms += page.render_encode_ms;
+        samples.record_encoded(&page);
         let page_num = page.page_index + 1;
 
         if let Some(ref cb) = config.progress_callback {
             cb.on_page_start(page_num, total_selected_pages);
         }
+        if let Some(log) = events {
+            log.record(&RunEvent::PageStart { page: page_num });
+        }
 
-        let result = llm::process_page(
-            provider,
-            page_num,
-            page.image_data,
-            prior_markdown.as_deref(),
-            config,
-        )
-        .await;
+        let context = prior.context();
+        let result =
+            llm::process_encoded_page(provider, fallbacks, page, context.as_deref(), config).await;
 
         if let Some(ref cb) = config.progress_callback {
             match &result.error {
@@ -484,46 +1616,267 @@ async fn process_sequential_lazy(
                 Some(e) => cb.on_page_error(page_num, total_selected_pages, e.to_string()),
             }
         }
+        if let Some(log) = events {
+            log.record(&RunEvent::for_page(&result));
+        }
+
+        if breaker.record(&result) {
+            break;
+        }
 
-        if result.error.is_none() {
-            prior_markdown = Some(result.markdown.clone());
+        if result.error.is_none() && !result.skipped_blank {
+            prior.push(&result.markdown);
         }
 
         results.push(result);
     }
 
-    (results, total_render_ms)
+    results
+}
+
+/// Process chapter sections in parallel, each one sequentially
+/// (maintain_format = true, parallel_sections = true).
+///
+/// Every section gets its own lazy render producer on `session` and runs
+/// through [`process_sequential_lazy`], so context never crosses a chapter
+/// boundary. Up to `concurrency` sections are in flight; their producers use
+/// a one-page channel, keeping memory bounded by the number of live sections.
+#[allow(clippy::too_many_arguments)]
+async fn process_sections_lazy(
+    session: &PdfSession,
+    sections: Vec<Vec<usize>>,
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
+    config: &ConversionConfig,
+    total_selected_pages: usize,
+    breaker: &CircuitBreaker,
+    samples: &StageSamples,
+    events: Option<&EventLog>,
+) -> Result<Vec<PageResult>, Pdf2MdError> {
+    let runs: Vec<Result<Vec<PageResult>, Pdf2MdError>> = futures::stream::iter(sections)
+        .map(move |section| async move {
+            if breaker.is_tripped() {
+                return Ok::<_, Pdf2MdError>(Vec::new());
+            }
+            let rx = render::spawn_lazy_render_encode_in(session, config, &section, 1).await?;
+            Ok(process_sequential_lazy(
+                rx,
+                provider,
+                fallbacks,
+                config,
+                total_selected_pages,
+                breaker,
+                samples,
+                events,
+            )
+            .await)
+        })
+        .buffer_unordered(config.concurrency)
+        .collect()
+        .await;
+
+    let mut results = Vec::with_capacity(total_selected_pages);
+    for run in runs {
+        results.extend(run?);
+    }
+    Ok(results)
+}
+
+/// Aborts a run once `threshold` pages in a row fail because the provider is
+/// unusable.
+///
+/// Only failures classified by [`llm::is_provider_outage`] count toward the
+/// threshold; content failures neither count nor reset the streak, while a
+/// successful page resets it. Tripping before any page converted means the
+/// provider never worked ([`Pdf2MdError::ProviderUnavailable`]); tripping
+/// later means it went away mid-run ([`Pdf2MdError::ProviderLost`]) and the
+/// pages already converted are kept. Under [`Strictness::FailFast`] any
+/// failed page trips it ([`Pdf2MdError::PageFailed`]).
+struct CircuitBreaker {
+    threshold: usize,
+    consecutive: AtomicUsize,
+    converted: AtomicBool,
+    tripped: AtomicBool,
+    last_error: Mutex<Option<String>>,
+    fail_fast: bool,
+    first_failure: Mutex<Option<PageError>>,
+}
+
+impl CircuitBreaker {
+    fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            consecutive: AtomicUsize::new(0),
+            converted: AtomicBool::new(false),
+            tripped: AtomicBool::new(false),
+            last_error: Mutex::new(None),
+            fail_fast: false,
+            first_failure: Mutex::new(None),
+        }
+    }
+
+    /// Also trip on the first failed page of any kind.
+    fn fail_fast(mut self, on: bool) -> Self {
+        self.fail_fast = on;
+        self
+    }
+
+    /// Record a finished page. Returns `true` when the breaker is (now) open.
+    fn record(&self, result: &PageResult) -> bool {
+        if self.tripped.load(Ordering::Acquire) {
+            return true;
+        }
+        if let (true, Some(e)) = (self.fail_fast, &result.error) {
+            if let Ok(mut first) = self.first_failure.lock() {
+                first.get_or_insert_with(|| e.clone());
+            }
+            warn!(
+                "Page {} failed under fail-fast strictness — aborting",
+                result.page_num
+            );
+            self.tripped.store(true, Ordering::Release);
+            return true;
+        }
+        if self.threshold == 0 {
+            return false;
+        }
+        match &result.error {
+            // A skipped blank page says nothing about the provider.
+            None if result.skipped_blank => false,
+            None => {
+                self.converted.store(true, Ordering::Release);
+                self.consecutive.store(0, Ordering::Release);
+                false
+            }
+            Some(e) if llm::is_provider_outage(e) => {
+                if let Ok(mut last) = self.last_error.lock() {
+                    *last = Some(e.to_string());
+                }
+                let n = self.consecutive.fetch_add(1, Ordering::AcqRel) + 1;
+                if n >= self.threshold {
+                    warn!("{} consecutive provider failures — aborting", n);
+                    self.tripped.store(true, Ordering::Release);
+                    true
+                } else {
+                    false
+                }
+            }
+            Some(_) => false,
+        }
+    }
+
+    /// `true` once the breaker has opened.
+    fn is_tripped(&self) -> bool {
+        self.tripped.load(Ordering::Acquire)
+    }
+
+    /// `Err(PageFailed)` if a fail-fast failure tripped the breaker, else
+    /// `Err(ProviderUnavailable)` if it tripped before any page converted,
+    /// `Err(ProviderLost)` if it tripped after.
+    fn check(&self) -> Result<(), Pdf2MdError> {
+        if !self.tripped.load(Ordering::Acquire) {
+            return Ok(());
+        }
+        if let Some(e) = self.first_failure.lock().ok().and_then(|f| f.clone()) {
+            return Err(Pdf2MdError::PageFailed(e));
+        }
+        let last_error = self
+            .last_error
+            .lock()
+            .ok()
+            .and_then(|last| last.clone())
+            .unwrap_or_else(|| "Unknown error".to_string());
+        let consecutive_failures = self.consecutive.load(Ordering::Acquire);
+        if self.converted.load(Ordering::Acquire) {
+            Err(Pdf2MdError::ProviderLost {
+                consecutive_failures,
+                last_error,
+            })
+        } else {
+            Err(Pdf2MdError::ProviderUnavailable {
+                consecutive_failures,
+                last_error,
+            })
+        }
+    }
 }
 
 /// Assemble the final markdown document from page results.
-fn assemble_document(
+///
+/// `summary`, when given, is placed under a `## Summary` heading after the
+/// front-matter and before the first page; `enrichment` adds fields to the
+/// front-matter.
+pub(crate) fn assemble_document(
     pages: &[PageResult],
     config: &ConversionConfig,
     metadata: &DocumentMetadata,
+    summary: Option<&str>,
+    enrichment: Option<&Enrichment>,
 ) -> String {
     let mut parts: Vec<String> = Vec::new();
 
     // Optional YAML front-matter
     if config.include_metadata {
-        parts.push(format_yaml_front_matter(metadata));
+        parts.push(format_yaml_front_matter(
+            metadata,
+            enrichment,
+            config.flavor,
+        ));
+    }
+
+    if let Some(text) = summary {
+        let text = flavor::apply(text.trim(), config.flavor);
+        parts.push(format!("## Summary\n\n{}\n\n", text));
     }
 
     // Collect successful page markdowns
-    let successful_pages: Vec<&PageResult> = pages.iter().filter(|p| p.error.is_none()).collect();
+    let successful_pages: Vec<&PageResult> = pages
+        .iter()
+        .filter(|p| p.error.is_none() && !p.skipped_blank)
+        .collect();
 
+    // Page footnotes are gathered after the last page, so they do not split
+    // text that continues across a page break.
+    let mut notes: Vec<String> = Vec::new();
     for (i, page) in successful_pages.iter().enumerate() {
         if i > 0 {
-            parts.push(config.page_separator.render(page.page_num));
+            let separator = config.page_separator.render(page.page_num);
+            parts.push(flavor::apply(&separator, config.flavor));
         }
-        parts.push(page.markdown.clone());
+        if config.link_footnotes {
+            let (body, page_notes) = footnotes::page_notes(&page.markdown);
+            parts.push(body);
+            notes.extend(page_notes);
+        } else {
+            parts.push(page.markdown.clone());
+        }
+    }
+    if !notes.is_empty() {
+        parts.push(format!("\n{}\n", notes.join("\n")));
     }
 
     parts.join("")
 }
 
 /// Format document metadata as YAML front matter.
-fn format_yaml_front_matter(meta: &DocumentMetadata) -> String {
-    let mut yaml = String::from("---\n");
+///
+/// MDX output names the subject `description`, as Docusaurus expects.
+/// CommonMark has no front-matter syntax, so the block goes in an HTML
+/// comment there.
+fn format_yaml_front_matter(
+    meta: &DocumentMetadata,
+    enrichment: Option<&Enrichment>,
+    flavor: MarkdownFlavor,
+) -> String {
+    let (open, close) = match flavor {
+        MarkdownFlavor::CommonMark => ("<!--\n", "-->\n\n"),
+        _ => ("---\n", "---\n\n"),
+    };
+    let subject_key = match flavor {
+        MarkdownFlavor::Mdx => "description",
+        _ => "subject",
+    };
+    let mut yaml = String::from(open);
 
     if let Some(ref t) = meta.title {
         yaml.push_str(&format!("title: \"{}\"\n", t));
@@ -532,7 +1885,7 @@ fn format_yaml_front_matter(meta: &DocumentMetadata) -> String {
         yaml.push_str(&format!("author: \"{}\"\n", a));
     }
     if let Some(ref s) = meta.subject {
-        yaml.push_str(&format!("subject: \"{}\"\n", s));
+        yaml.push_str(&format!("{}: \"{}\"\n", subject_key, s));
     }
     if let Some(ref c) = meta.creator {
         yaml.push_str(&format!("creator: \"{}\"\n", c));
@@ -545,14 +1898,110 @@ fn format_yaml_front_matter(meta: &DocumentMetadata) -> String {
         yaml.push_str(&format!("pdf_version: \"{}\"\n", meta.pdf_version));
     }
 
-    yaml.push_str("---\n\n");
+    if let Some(e) = enrichment {
+        if let Some(ref t) = e.document_type {
+            yaml.push_str(&format!("document_type: {}\n", yaml_quote(t)));
+        }
+        if !e.keywords.is_empty() {
+            let keywords: Vec<String> = e.keywords.iter().map(|k| yaml_quote(k)).collect();
+            yaml.push_str(&format!("keywords: [{}]\n", keywords.join(", ")));
+        }
+        if !e.entities.is_empty() {
+            yaml.push_str("entities:\n");
+            for entity in &e.entities {
+                yaml.push_str(&format!(
+                    "  - name: {}\n    type: {}\n",
+                    yaml_quote(&entity.name),
+                    yaml_quote(&entity.kind)
+                ));
+            }
+        }
+    }
+
+    yaml.push_str(close);
     yaml
 }
 
+/// Double-quote a model-generated string for YAML, escaping `\` and `"`.
+pub(crate) fn yaml_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn summary_goes_after_front_matter() {
+        let config = ConversionConfig {
+            include_metadata: true,
+            ..Default::default()
+        };
+        let pages = vec![PageResult::new(1, "# Body")];
+        let md = assemble_document(
+            &pages,
+            &config,
+            &DocumentMetadata::default(),
+            Some("Short abstract. "),
+            None,
+        );
+        assert!(md.starts_with("---\n"));
+        assert!(md.ends_with("---\n\n## Summary\n\nShort abstract.\n\n# Body"));
+    }
+
+    #[test]
+    fn enrichment_in_front_matter() {
+        let enrichment = Enrichment {
+            document_type: Some("report".into()),
+            keywords: vec!["solar".into(), "say \"hi\"".into()],
+            entities: vec![crate::enrich::Entity {
+                name: "ACME".into(),
+                kind: "organization".into(),
+            }],
+        };
+        let yaml = format_yaml_front_matter(
+            &DocumentMetadata::default(),
+            Some(&enrichment),
+            MarkdownFlavor::Gfm,
+        );
+        assert!(yaml.contains("document_type: \"report\"\n"));
+        assert!(yaml.contains("keywords: [\"solar\", \"say \\\"hi\\\"\"]\n"));
+        assert!(yaml.contains("entities:\n  - name: \"ACME\"\n    type: \"organization\"\n"));
+        assert!(yaml.ends_with("---\n\n"));
+    }
+
+    #[test]
+    fn front_matter_follows_the_flavor() {
+        let meta = DocumentMetadata {
+            subject: Some("Solar output".into()),
+            ..Default::default()
+        };
+        let mdx = format_yaml_front_matter(&meta, None, MarkdownFlavor::Mdx);
+        assert!(mdx.starts_with("---\n") && mdx.contains("description: \"Solar output\"\n"));
+        let cm = format_yaml_front_matter(&meta, None, MarkdownFlavor::CommonMark);
+        assert!(cm.starts_with("<!--\n") && cm.ends_with("-->\n\n"));
+        assert!(cm.contains("subject: \"Solar output\"\n"));
+    }
+
+    #[test]
+    fn page_pngs_take_the_first_image_of_each_page() {
+        let page = |page_num: usize, data: &str| PageResult {
+            page_num,
+            page_image: Some(backend::PageImage::new(data, "image/png")),
+            ..Default::default()
+        };
+        let pages = [
+            page(2, "Ymk="),
+            page(1, "b25l"),
+            page(2, "YmI="),
+            PageResult::new(3, ""),
+        ];
+        assert_eq!(
+            page_pngs(&pages),
+            vec![(1, b"one".to_vec()), (2, b"bi".to_vec())]
+        );
+    }
+
     #[test]
     fn test_default_vision_model_mistral_variants() {
         // All recognized Mistral name variants must return the vision model.
@@ -579,6 +2028,412 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_default_vision_model_openrouter() {
+        // OpenRouter needs vendor-namespaced model IDs.
+        for name in &["openrouter", "open-router", "open_router"] {
+            assert_eq!(
+                default_vision_model_for_provider(name),
+                "openai/gpt-4.1-nano",
+                "provider '{}' should default to openai/gpt-4.1-nano",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_fallback_providers_ignored_without_provider_name() {
+        let config = ConversionConfig::builder()
+            .fallback_models(["google/gemini-2.0-flash-001"])
+            .build()
+            .unwrap();
+        let fallbacks = resolve_fallback_providers(&config).unwrap();
+        assert!(fallbacks.is_empty());
+    }
+
+    #[test]
+    fn test_openrouter_fallbacks_are_routed_server_side() {
+        let config = ConversionConfig::builder()
+            .provider_name("openrouter")
+            .api_key("sk-or")
+            .fallback_models(["google/gemini-2.0-flash-001"])
+            .build()
+            .unwrap();
+        let (name, base_url) = openrouter_routing(&config).unwrap();
+        assert_eq!(base_url, "https://openrouter.ai/api/v1");
+        assert!(resolve_fallback_backends(&config).unwrap().is_empty());
+        let backend = openrouter_backend(&config, name, base_url).unwrap();
+        assert_eq!(
+            (backend.name(), backend.model()),
+            ("openrouter", "openai/gpt-4.1-nano")
+        );
+
+        // Other providers, and gateways in front of OpenRouter, keep the
+        // client-side loop.
+        let config = ConversionConfig::builder()
+            .provider_name("openai")
+            .fallback_models(["gpt-4.1-mini"])
+            .build()
+            .unwrap();
+        assert!(openrouter_routing(&config).is_none());
+        let config = ConversionConfig::builder()
+            .provider_name("openrouter")
+            .base_url("http://gateway.internal/v1")
+            .fallback_models(["google/gemini-2.0-flash-001"])
+            .build()
+            .unwrap();
+        assert!(openrouter_routing(&config).is_none());
+    }
+
+    #[test]
+    fn test_explicit_api_key_prefers_config_over_credentials() {
+        let config = ConversionConfig::builder()
+            .credentials(crate::ProviderCredentials::new().with("anthropic", "sk-ant"))
+            .build()
+            .unwrap();
+        assert_eq!(explicit_api_key(&config, "Anthropic"), Some("sk-ant"));
+        assert_eq!(explicit_api_key(&config, "openai"), None);
+
+        let config = ConversionConfig::builder()
+            .api_key("sk-main")
+            .credentials(crate::ProviderCredentials::new().with("anthropic", "sk-ant"))
+            .build()
+            .unwrap();
+        assert_eq!(explicit_api_key(&config, "anthropic"), Some("sk-main"));
+    }
+
+    #[test]
+    fn test_keyed_provider_needs_known_endpoint() {
+        assert!(openai_compatible_endpoint("OpenRouter").is_some());
+        assert!(openai_compatible_endpoint("ollama").is_none());
+        let err = create_keyed_provider(&ConversionConfig::default(), "ollama", "llava", "k")
+            .err()
+            .unwrap();
+        assert!(matches!(err, Pdf2MdError::ProviderNotConfigured { .. }));
+    }
+
+    struct TenantEcho;
+
+    #[async_trait::async_trait]
+    impl crate::ProviderResolver for TenantEcho {
+        async fn resolve(
+            &self,
+            ctx: &ConversionContext,
+        ) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
+            Err(Pdf2MdError::InvalidConfig(format!(
+                "tenant={}",
+                ctx.tenant.as_deref().unwrap_or("-")
+            )))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolver_receives_context_and_beats_provider_name() {
+        let config = ConversionConfig::builder()
+            .provider_name("openai")
+            .fallback_models(["gpt-4.1-mini"])
+            .provider_resolver(Arc::new(TenantEcho))
+            .build()
+            .unwrap();
+        let ctx = ConversionContext::default().with_tenant("acme");
+        let err = resolve_provider(&config, &ctx).await.err().unwrap();
+        assert!(matches!(err, Pdf2MdError::InvalidConfig(ref m) if m == "tenant=acme"));
+        assert!(resolve_fallback_providers(&config).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_uses_custom_endpoint() {
+        assert!(!uses_custom_endpoint(&ConversionConfig::default()));
+
+        let config = ConversionConfig::builder()
+            .base_url("http://localhost:8000/v1")
+            .build()
+            .unwrap();
+        assert!(uses_custom_endpoint(&config));
+
+        let mut headers = crate::HeaderMap::new();
+        headers.insert("x-gateway-key", "secret".parse().unwrap());
+        let config = ConversionConfig::builder()
+            .extra_headers(headers)
+            .build()
+            .unwrap();
+        assert!(uses_custom_endpoint(&config));
+    }
+
+    #[test]
+    fn test_extra_headers_stay_with_the_named_provider() {
+        let mut headers = crate::HeaderMap::new();
+        headers.insert("x-gateway-key", "secret".parse().unwrap());
+        let config = ConversionConfig::builder()
+            .provider_name("ollama")
+            .extra_headers(headers)
+            .build()
+            .unwrap();
+        let err = create_openai_compatible_provider(&config, "ollama", "llava")
+            .err()
+            .unwrap();
+        assert!(matches!(err, Pdf2MdError::ProviderNotConfigured { .. }));
+
+        assert_eq!(
+            endpoint_key_env("https://api.openai.com/v1"),
+            Some("OPENAI_API_KEY")
+        );
+        assert_eq!(
+            endpoint_key_env(openai_compatible_endpoint("claude").unwrap()),
+            Some("ANTHROPIC_API_KEY")
+        );
+        assert_eq!(endpoint_key_env("http://localhost:8000/v1"), None);
+        assert_eq!(endpoint_key_env("https://api.openai.com.evil.com/v1"), None);
+    }
+
+    #[test]
+    fn test_local_only_refuses_remote_providers() {
+        let local = |b: crate::config::ConversionConfigBuilder| b.local_only(true).build().unwrap();
+
+        let config = local(ConversionConfig::builder().base_url("http://127.0.0.1:8000/v1"));
+        assert!(check_local_provider(&config).is_ok());
+        let config = local(ConversionConfig::builder().base_url("http://[::1]:1234/v1"));
+        assert!(check_local_provider(&config).is_ok());
+
+        for config in [
+            local(ConversionConfig::builder()),
+            local(ConversionConfig::builder().provider_name("openai")),
+            local(ConversionConfig::builder().base_url("https://gateway.example.com/v1")),
+        ] {
+            let err = check_local_provider(&config).unwrap_err();
+            assert!(
+                matches!(err, Pdf2MdError::LocalOnlyViolation { .. }),
+                "{err}"
+            );
+        }
+
+        let config = local(ConversionConfig::builder().base_url("http://localhost:8000/v1"));
+        assert!(check_local_input(&config, "scan.pdf").is_ok());
+        assert!(check_local_input(&config, "https://example.com/a.pdf").is_err());
+        assert!(
+            check_local_input(&ConversionConfig::default(), "https://example.com/a.pdf").is_ok()
+        );
+        let remote_hook = ConversionConfig::builder()
+            .base_url("http://localhost:8000/v1")
+            .webhook(crate::WebhookConfig::new("https://jobs.example/done"))
+            .local_only(true)
+            .build();
+        assert!(matches!(remote_hook, Err(Pdf2MdError::InvalidConfig(_))));
+        let config = ConversionConfig {
+            webhook: Some(crate::WebhookConfig::new("https://jobs.example/done")),
+            ..config
+        };
+        assert!(check_local_input(&config, "scan.pdf").is_err());
+    }
+
+    /// A config that skipped the builder still sends nothing to a remote
+    /// webhook under `local_only`.
+    #[tokio::test]
+    async fn test_local_only_never_notifies_remote_webhook() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("0.0.0.0:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let posts = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&posts);
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                let _ = stream
+                    .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                    .await;
+            }
+        });
+
+        // 0.0.0.0 reaches this host but is not a loopback address.
+        let hook = crate::WebhookConfig::new(format!("http://0.0.0.0:{port}/done"));
+        let probe = Err(Pdf2MdError::Internal("probe".into()));
+        webhook::notify(&hook, &WebhookPayload::for_result("probe", &probe, false)).await;
+        if posts.load(Ordering::SeqCst) == 0 {
+            println!("SKIP — 0.0.0.0 does not reach local listeners here");
+            return;
+        }
+
+        let config = ConversionConfig {
+            local_only: true,
+            base_url: Some("http://localhost:8000/v1".into()),
+            webhook: Some(hook),
+            ..Default::default()
+        };
+        for input in ["scan.pdf", "https://example.com/a.pdf"] {
+            let result = convert_with_context(input, &config, ConversionContext::default()).await;
+            assert!(result.is_err());
+        }
+        assert_eq!(posts.load(Ordering::SeqCst), 1, "only the probe was POSTed");
+    }
+
+    struct Backend {
+        local: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl VisionBackend for Backend {
+        fn name(&self) -> &str {
+            "test"
+        }
+        fn model(&self) -> &str {
+            "test"
+        }
+        fn is_local(&self) -> bool {
+            self.local
+        }
+        async fn describe_page(
+            &self,
+            _image: &backend::PageImage,
+            _prompt: &backend::PagePrompt,
+            _opts: &backend::DescribeOptions,
+        ) -> Result<backend::PageCompletion, Pdf2MdError> {
+            Ok(backend::PageCompletion::new("page"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_only_refuses_prebuilt_providers() {
+        let remote = build_openai_compatible(
+            &ConversionConfig::default(),
+            "remote",
+            "gpt-4.1-nano",
+            "https://llm.example.com/v1".to_string(),
+            Some("key"),
+        )
+        .unwrap();
+        let ctx = ConversionContext::default();
+        let local_only =
+            |b: crate::config::ConversionConfigBuilder| b.local_only(true).build().unwrap();
+
+        for config in [
+            local_only(ConversionConfig::builder().provider(Arc::clone(&remote))),
+            local_only(ConversionConfig::builder().provider_resolver(Arc::new(TenantEcho))),
+            local_only(ConversionConfig::builder().backend(Arc::new(Backend { local: false }))),
+            local_only(
+                ConversionConfig::builder()
+                    .backend(Arc::new(Backend { local: true }))
+                    .provider(Arc::clone(&remote)),
+            ),
+        ] {
+            let err = resolve_backend(&config, &ctx).await.err().unwrap();
+            assert!(
+                matches!(err, Pdf2MdError::LocalOnlyViolation { .. }),
+                "{err}"
+            );
+        }
+
+        let config =
+            local_only(ConversionConfig::builder().backend(Arc::new(Backend { local: true })));
+        assert!(resolve_backend(&config, &ctx).await.is_ok());
+    }
+
+    #[test]
+    fn test_is_loopback_url() {
+        assert!(is_loopback_url("http://localhost:11434"));
+        assert!(is_loopback_url("127.0.0.1:11434"));
+        assert!(is_loopback_url("http://127.1.2.3/v1"));
+        assert!(!is_loopback_url("http://192.168.1.10:11434"));
+        assert!(!is_loopback_url("http://localhost.evil.com"));
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&[0u8; 2048]).unwrap();
+
+        let config = ConversionConfig::builder()
+            .max_pages(100)
+            .max_file_size_bytes(1024)
+            .build()
+            .unwrap();
+        let err = check_file_size(&config, file.path()).unwrap_err();
+        assert!(matches!(err, Pdf2MdError::DocumentTooLarge { .. }), "{err}");
+        assert!(check_page_limit(&config, 100).is_ok());
+        let err = check_page_limit(&config, 5000).unwrap_err();
+        assert!(err.to_string().contains("5000 pages"), "{err}");
+
+        let unlimited = ConversionConfig::default();
+        assert!(check_file_size(&unlimited, file.path()).is_ok());
+        assert!(check_page_limit(&unlimited, 5000).is_ok());
+        assert!(ConversionConfig::builder().max_pages(0).build().is_err());
+    }
+
+    fn failed_page(detail: &str) -> PageResult {
+        PageResult {
+            page_num: 1,
+            error: Some(crate::error::PageError::LlmFailed {
+                page: 1,
+                retries: 0,
+                detail: detail.into(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_circuit_breaker_trips_on_initial_outage() {
+        let breaker = CircuitBreaker::new(2);
+        assert!(!breaker.record(&failed_page("Connection refused")));
+        assert!(breaker.record(&failed_page("Connection refused")));
+        assert!(matches!(
+            breaker.check(),
+            Err(Pdf2MdError::ProviderUnavailable {
+                consecutive_failures: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_circuit_breaker_mid_run_outage_is_provider_lost() {
+        let breaker = CircuitBreaker::new(2);
+        let ok = PageResult {
+            error: None,
+            ..failed_page("")
+        };
+        assert!(!breaker.record(&ok));
+        assert!(!breaker.record(&failed_page("Connection refused")));
+        // A success resets the streak.
+        assert!(!breaker.record(&ok));
+        assert!(!breaker.record(&failed_page("Connection refused")));
+        assert!(breaker.check().is_ok());
+        assert!(breaker.record(&failed_page("Connection refused")));
+        assert!(matches!(
+            breaker.check(),
+            Err(Pdf2MdError::ProviderLost {
+                consecutive_failures: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_circuit_breaker_fail_fast_trips_on_any_failure() {
+        let breaker = CircuitBreaker::new(0).fail_fast(true);
+        let blank = PageResult {
+            error: None,
+            skipped_blank: true,
+            ..failed_page("")
+        };
+        assert!(!breaker.record(&blank));
+        assert!(breaker.record(&failed_page("400 Bad Request")));
+        assert!(matches!(
+            breaker.check(),
+            Err(Pdf2MdError::PageFailed(PageError::LlmFailed { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_circuit_breaker_ignores_content_errors_and_zero_threshold() {
+        let breaker = CircuitBreaker::new(1);
+        assert!(!breaker.record(&failed_page("400 Bad Request")));
+        let disabled = CircuitBreaker::new(0);
+        assert!(!disabled.record(&failed_page("Connection refused")));
+    }
+
     #[test]
     fn test_default_vision_model_local_providers() {
         // Local providers use llava as the vision-capable default.
@@ -0,0 +1,439 @@
+//! Differences between two conversions of a document.
+//!
+//! ## Why diff conversions?
+//!
+//! Reviewing a revised contract or manual means finding what moved between
+//! versions. Diffing the PDFs is hopeless and diffing two long Markdown
+//! files loses the page structure. [`diff_outputs`] compares two
+//! [`ConversionOutput`]s page by page, producing a unified diff for every
+//! page that differs, and section by section (by heading text), listing
+//! sections that were added, removed, or changed:
+//!
+//! ```text
+//! Sections: +1 added, -0 removed, ~2 changed
+//!   + Termination for Convenience
+//!   ~ Payment Terms
+//!   ~ Liability
+//! --- a/page 4
+//! +++ b/page 4
+//! @@ -3,4 +3,4 @@
+//! ```
+//!
+//! Pages are paired by page number; failed pages count as empty. Pair with
+//! [`crate::convert_incremental`] to convert the new version cheaply first.
+
+use crate::output::{ConversionOutput, PageResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Unchanged lines shown around each change in [`PageDiff::unified`].
+const CONTEXT_LINES: usize = 3;
+
+/// Line pairs beyond which a page is diffed as "all removed, all added"
+/// instead of line by line; keeps the quadratic diff bounded.
+const MAX_DIFF_CELLS: usize = 16_000_000;
+
+/// How a page or section differs between the two outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// Only in the second output.
+    Added,
+    /// Only in the first output.
+    Removed,
+    /// In both, with different content.
+    Changed,
+}
+
+/// The difference on one page. Unchanged pages have no entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageDiff {
+    pub page_num: usize,
+    pub kind: ChangeKind,
+    /// Unified diff of the page's Markdown, `--- a/page N` / `+++ b/page N`.
+    pub unified: String,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+}
+
+/// Headings whose section differs, in document order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionChanges {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+/// Result of [`diff_outputs`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentDiff {
+    /// Pages that differ, by page number.
+    pub pages: Vec<PageDiff>,
+    pub sections: SectionChanges,
+}
+
+impl DocumentDiff {
+    /// `true` when the two outputs have the same page content.
+    pub fn is_empty(&self) -> bool {
+        self.pages.is_empty()
+    }
+
+    /// Plain-text report: the section summary, then every page's unified diff.
+    pub fn render(&self) -> String {
+        let s = &self.sections;
+        let mut out = format!(
+            "Sections: +{} added, -{} removed, ~{} changed\n",
+            s.added.len(),
+            s.removed.len(),
+            s.changed.len()
+        );
+        for (mark, headings) in [("+", &s.added), ("-", &s.removed), ("~", &s.changed)] {
+            for h in headings {
+                out.push_str(&format!("  {mark} {h}\n"));
+            }
+        }
+        for page in &self.pages {
+            out.push_str(&page.unified);
+        }
+        out
+    }
+}
+
+/// Compare two conversions of (versions of) the same document.
+pub fn diff_outputs(a: &ConversionOutput, b: &ConversionOutput) -> DocumentDiff {
+    let old_pages = page_texts(&a.pages);
+    let new_pages = page_texts(&b.pages);
+    let page_nums: BTreeSet<usize> = old_pages.keys().chain(new_pages.keys()).copied().collect();
+
+    let mut pages = Vec::new();
+    for page_num in page_nums {
+        let old = old_pages.get(&page_num).copied();
+        let new = new_pages.get(&page_num).copied();
+        if old == new {
+            continue;
+        }
+        let kind = match (old, new) {
+            (None, _) => ChangeKind::Added,
+            (_, None) => ChangeKind::Removed,
+            _ => ChangeKind::Changed,
+        };
+        let old_lines: Vec<&str> = old.unwrap_or("").lines().collect();
+        let new_lines: Vec<&str> = new.unwrap_or("").lines().collect();
+        let ops = diff_lines(&old_lines, &new_lines);
+        let lines_added = ops.iter().filter(|(op, _)| *op == Op::Insert).count();
+        let lines_removed = ops.iter().filter(|(op, _)| *op == Op::Delete).count();
+        if lines_added == 0 && lines_removed == 0 {
+            // Only trailing whitespace or line endings differ.
+            continue;
+        }
+        pages.push(PageDiff {
+            page_num,
+            kind,
+            unified: unified_diff(&format!("page {page_num}"), &ops),
+            lines_added,
+            lines_removed,
+        });
+    }
+
+    DocumentDiff {
+        pages,
+        sections: diff_sections(&document_text(&a.pages), &document_text(&b.pages)),
+    }
+}
+
+/// Unified diff from `old` to `new` under `--- a/{name}` / `+++ b/{name}`;
+/// empty when the lines are the same.
+pub(crate) fn unified_text(name: &str, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    unified_diff(name, &diff_lines(&old_lines, &new_lines))
+}
+
+/// Markdown of each converted page; failed pages are left out.
+fn page_texts(pages: &[PageResult]) -> BTreeMap<usize, &str> {
+    pages
+        .iter()
+        .filter(|p| p.error.is_none())
+        .map(|p| (p.page_num, p.markdown.as_str()))
+        .collect()
+}
+
+fn document_text(pages: &[PageResult]) -> String {
+    pages
+        .iter()
+        .filter(|p| p.error.is_none())
+        .map(|p| p.markdown.as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Line diff by longest common subsequence, after trimming the common
+/// prefix and suffix.
+pub(crate) fn diff_lines<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(Op, &'a str)> {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (mid_a, mid_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+
+    let mut ops: Vec<(Op, &str)> = a[..prefix].iter().map(|l| (Op::Equal, *l)).collect();
+    if (mid_a.len() + 1) * (mid_b.len() + 1) > MAX_DIFF_CELLS {
+        ops.extend(mid_a.iter().map(|l| (Op::Delete, *l)));
+        ops.extend(mid_b.iter().map(|l| (Op::Insert, *l)));
+    } else {
+        // lcs[i][j]: LCS length of mid_a[i..] and mid_b[j..].
+        let (n, m) = (mid_a.len(), mid_b.len());
+        let mut lcs = vec![0u32; (n + 1) * (m + 1)];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i * (m + 1) + j] = if mid_a[i] == mid_b[j] {
+                    lcs[(i + 1) * (m + 1) + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * (m + 1) + j].max(lcs[i * (m + 1) + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && mid_a[i] == mid_b[j] {
+                ops.push((Op::Equal, mid_a[i]));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[(i + 1) * (m + 1) + j] >= lcs[i * (m + 1) + j + 1]) {
+                ops.push((Op::Delete, mid_a[i]));
+                i += 1;
+            } else {
+                ops.push((Op::Insert, mid_b[j]));
+                j += 1;
+            }
+        }
+    }
+    ops.extend(a[a.len() - suffix..].iter().map(|l| (Op::Equal, *l)));
+    ops
+}
+
+/// Render `ops` as a unified diff with [`CONTEXT_LINES`] of context.
+fn unified_diff(name: &str, ops: &[(Op, &str)]) -> String {
+    // Old and new line counts before each op.
+    let mut old_pos = Vec::with_capacity(ops.len() + 1);
+    let mut new_pos = Vec::with_capacity(ops.len() + 1);
+    let (mut o, mut n) = (0, 0);
+    for (op, _) in ops {
+        old_pos.push(o);
+        new_pos.push(n);
+        match op {
+            Op::Equal => {
+                o += 1;
+                n += 1;
+            }
+            Op::Delete => o += 1,
+            Op::Insert => n += 1,
+        }
+    }
+    old_pos.push(o);
+    new_pos.push(n);
+
+    let changes: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != Op::Equal)
+        .map(|(i, _)| i)
+        .collect();
+    if changes.is_empty() {
+        return String::new();
+    }
+
+    let mut out = format!("--- a/{name}\n+++ b/{name}\n");
+    let mut i = 0;
+    while i < changes.len() {
+        let start = changes[i].saturating_sub(CONTEXT_LINES);
+        let mut last = changes[i];
+        while i + 1 < changes.len() && changes[i + 1] - last <= 2 * CONTEXT_LINES + 1 {
+            i += 1;
+            last = changes[i];
+        }
+        let end = (last + CONTEXT_LINES + 1).min(ops.len());
+        i += 1;
+
+        let old_len = old_pos[end] - old_pos[start];
+        let new_len = new_pos[end] - new_pos[start];
+        // An empty range is numbered by the line before it, as in GNU diff.
+        let old_start = old_pos[start] + usize::from(old_len > 0);
+        let new_start = new_pos[start] + usize::from(new_len > 0);
+        out.push_str(&format!(
+            "@@ -{old_start},{old_len} +{new_start},{new_len} @@\n"
+        ));
+        for (op, line) in &ops[start..end] {
+            let mark = match op {
+                Op::Equal => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            out.push(mark);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Sections keyed by heading text (repeats numbered `Heading (2)`), in
+/// document order, with their body text. Headings in code fences are
+/// ignored; text before the first heading is not a section.
+fn sections(markdown: &str) -> Vec<(String, String)> {
+    let mut sections: Vec<(String, String)> = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut in_fence = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let heading = if in_fence { None } else { heading_text(line) };
+        match (heading, sections.last_mut()) {
+            (Some(text), _) => {
+                let n = seen.entry(text.clone()).or_insert(0);
+                *n += 1;
+                let key = if *n == 1 {
+                    text
+                } else {
+                    format!("{text} ({n})")
+                };
+                sections.push((key, String::new()));
+            }
+            (None, Some((_, body))) => {
+                body.push_str(line.trim_end());
+                body.push('\n');
+            }
+            (None, None) => {}
+        }
+    }
+    for (_, body) in &mut sections {
+        *body = body.trim().to_string();
+    }
+    sections
+}
+
+/// Text of an ATX heading line, without the `#`s.
+fn heading_text(line: &str) -> Option<String> {
+    let hashes = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[hashes..];
+    if !(1..=6).contains(&hashes) || !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    let text = rest.trim().trim_end_matches('#').trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn diff_sections(a: &str, b: &str) -> SectionChanges {
+    let old = sections(a);
+    let new = sections(b);
+    let old_map: HashMap<&str, &str> = old.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let new_map: HashMap<&str, &str> = new.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+
+    let mut changes = SectionChanges::default();
+    for (heading, body) in &new {
+        match old_map.get(heading.as_str()) {
+            None => changes.added.push(heading.clone()),
+            Some(old_body) if old_body != body => changes.changed.push(heading.clone()),
+            Some(_) => {}
+        }
+    }
+    changes.removed = old
+        .iter()
+        .filter(|(heading, _)| !new_map.contains_key(heading.as_str()))
+        .map(|(heading, _)| heading.clone())
+        .collect();
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output(pages: &[&str]) -> ConversionOutput {
+        ConversionOutput {
+            markdown: String::new(),
+            pages: pages
+                .iter()
+                .enumerate()
+                .map(|(i, md)| PageResult::new(i + 1, *md))
+                .collect(),
+            metadata: Default::default(),
+            stats: Default::default(),
+            summary: None,
+            enrichment: None,
+            pii_findings: Vec::new(),
+            references: Vec::new(),
+            headings: Vec::new(),
+            fatal_error: None,
+            reproducibility: None,
+        }
+    }
+
+    #[test]
+    fn unified_diff_has_context_and_ranges() {
+        let a = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "10"];
+        let b = ["1", "2", "3", "4", "5", "six", "7", "8", "9", "10"];
+        let diff = unified_diff("page 1", &diff_lines(&a, &b));
+        assert_eq!(
+            diff,
+            "--- a/page 1\n+++ b/page 1\n@@ -3,7 +3,7 @@\n 3\n 4\n 5\n-6\n+six\n 7\n 8\n 9\n"
+        );
+    }
+
+    #[test]
+    fn pages_and_sections_are_compared() {
+        let a = output(&[
+            "# Terms\n\nPay in 30 days.\n\n## Liability\n\nCapped.",
+            "## Notices\n\nBy post.",
+        ]);
+        let b = output(&[
+            "# Terms\n\nPay in 45 days.\n\n## Liability\n\nCapped.",
+            "## Termination\n\nAny time.",
+            "Appendix",
+        ]);
+        let diff = diff_outputs(&a, &b);
+
+        let kinds: Vec<(usize, ChangeKind)> =
+            diff.pages.iter().map(|p| (p.page_num, p.kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (1, ChangeKind::Changed),
+                (2, ChangeKind::Changed),
+                (3, ChangeKind::Added)
+            ]
+        );
+        assert!(diff.pages[0]
+            .unified
+            .contains("-Pay in 30 days.\n+Pay in 45 days.\n"));
+        assert_eq!(
+            (diff.pages[0].lines_added, diff.pages[0].lines_removed),
+            (1, 1)
+        );
+
+        assert_eq!(diff.sections.added, ["Termination"]);
+        assert_eq!(diff.sections.removed, ["Notices"]);
+        assert_eq!(diff.sections.changed, ["Terms"]);
+        assert!(diff
+            .render()
+            .starts_with("Sections: +1 added, -1 removed, ~1 changed\n"));
+    }
+
+    #[test]
+    fn identical_outputs_have_no_diff() {
+        let a = output(&["# A\n\ntext"]);
+        assert!(diff_outputs(&a, &a.clone()).is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+//! Optional keyword, entity, and document-type extraction pass.
+//!
+//! ## Why a separate pass?
+//!
+//! Search indexes want structured fields — "what kind of document is this,
+//! what is it about, who and what does it mention" — that the page-by-page
+//! VLM conversion never sees as a whole. After conversion, the Markdown is
+//! sent back to the model as text with a request for a small JSON object.
+//!
+//! Long documents are split into the same page chunks as the summary pass
+//! ([`crate::summary::chunk_pages`]); each chunk is analysed on its own and
+//! the answers are merged locally, so no extra "reduce" call is needed:
+//! keywords are ranked by how many chunks mention them, entities are
+//! de-duplicated by name, and the most common document type wins.
+//!
+//! Enabled with [`crate::ConversionConfigBuilder::enrich`]; the result lands
+//! in [`crate::ConversionOutput::enrichment`] and, with `include_metadata`,
+//! in the YAML front-matter. A failed pass never fails the conversion.
+
+use crate::config::ConversionConfig;
+use crate::error::Pdf2MdError;
+use crate::output::PageResult;
+use crate::pipeline::llm;
+use crate::prompts::{enrichment_request, ENRICHMENT_SYSTEM_PROMPT};
+use crate::summary::chunk_pages;
+use edgequake_llm::LLMProvider;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Options for the enrichment pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrichmentConfig {
+    /// Maximum number of keywords kept. Default: 10.
+    pub max_keywords: usize,
+    /// Largest chunk of Markdown sent in one request, in characters. Default: 40 000.
+    pub chunk_chars: usize,
+}
+
+impl Default for EnrichmentConfig {
+    fn default() -> Self {
+        Self {
+            max_keywords: 10,
+            chunk_chars: 40_000,
+        }
+    }
+}
+
+/// Search metadata extracted from the converted document.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Enrichment {
+    /// Short lowercase label such as `"research paper"` or `"invoice"`.
+    pub document_type: Option<String>,
+    /// Topical keywords, most important first.
+    pub keywords: Vec<String>,
+    /// Named entities mentioned in the text, in order of first appearance.
+    pub entities: Vec<Entity>,
+}
+
+/// A named entity found in the document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entity {
+    /// The entity as written in the text, e.g. `"European Central Bank"`.
+    pub name: String,
+    /// `person`, `organization`, `location`, `product`, `date`, or `other`.
+    #[serde(rename = "type", default = "Entity::default_kind")]
+    pub kind: String,
+}
+
+impl Entity {
+    fn default_kind() -> String {
+        "other".to_string()
+    }
+}
+
+/// Extract keywords, entities, and the document type from the successful pages.
+///
+/// Chunks that fail or answer with unparseable JSON are skipped with a
+/// warning; an error is returned only when no chunk produced anything.
+pub async fn enrich(
+    provider: &Arc<dyn LLMProvider>,
+    pages: &[PageResult],
+    config: &ConversionConfig,
+    enrichment: &EnrichmentConfig,
+) -> Result<Enrichment, Pdf2MdError> {
+    let chunks = chunk_pages(pages, enrichment.chunk_chars);
+    if chunks.is_empty() {
+        return Err(Pdf2MdError::Internal("No page content to enrich".into()));
+    }
+    info!(
+        "Extracting keywords and entities from {} chunks",
+        chunks.len()
+    );
+
+    let requests: Vec<String> = chunks
+        .iter()
+        .map(|chunk| enrichment_request(chunk, enrichment.max_keywords))
+        .collect();
+    let answers: Vec<Result<(String, usize, usize), Pdf2MdError>> = futures::stream::iter(requests)
+        .map(|request| async move {
+            llm::complete_text(provider, ENRICHMENT_SYSTEM_PROMPT, &request, config).await
+        })
+        .buffered(config.concurrency)
+        .collect()
+        .await;
+
+    let mut parsed = Vec::with_capacity(answers.len());
+    let mut last_err = None;
+    for answer in answers {
+        match answer {
+            Ok((text, _, _)) => match parse_enrichment(&text) {
+                Some(e) => parsed.push(e),
+                None => warn!("Enrichment: unparseable model answer, chunk skipped"),
+            },
+            Err(e) => {
+                warn!("Enrichment: chunk failed — {}", e);
+                last_err = Some(e);
+            }
+        }
+    }
+    if parsed.is_empty() {
+        return Err(last_err.unwrap_or_else(|| Pdf2MdError::LlmApiError {
+            message: "model returned no parseable enrichment JSON".into(),
+        }));
+    }
+    Ok(merge(parsed, enrichment.max_keywords))
+}
+
+/// Parse one model answer, tolerating Markdown fences and surrounding prose.
+pub fn parse_enrichment(text: &str) -> Option<Enrichment> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    if end < start {
+        return None;
+    }
+    let mut e: Enrichment = serde_json::from_str(&text[start..=end]).ok()?;
+    e.document_type = e
+        .document_type
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty());
+    e.keywords.retain(|k| !k.trim().is_empty());
+    e.entities.retain(|en| !en.name.trim().is_empty());
+    Some(e)
+}
+
+/// Merge per-chunk answers into one [`Enrichment`].
+///
+/// Keywords are ranked by the number of chunks listing them (ties keep first
+/// appearance) and truncated to `max_keywords`; entities are de-duplicated
+/// case-insensitively by name; the most frequent document type wins, ties
+/// going to the earliest chunk.
+pub fn merge(parts: Vec<Enrichment>, max_keywords: usize) -> Enrichment {
+    let mut keyword_order: Vec<String> = Vec::new();
+    let mut keyword_counts: HashMap<String, usize> = HashMap::new();
+    let mut entities: Vec<Entity> = Vec::new();
+    let mut seen_entities: HashSet<String> = HashSet::new();
+    let mut type_order: Vec<String> = Vec::new();
+    let mut type_counts: HashMap<String, usize> = HashMap::new();
+
+    for part in parts {
+        for keyword in part.keywords {
+            let keyword = keyword.trim().to_string();
+            let key = keyword.to_lowercase();
+            let count = keyword_counts.entry(key).or_insert(0);
+            if *count == 0 {
+                keyword_order.push(keyword);
+            }
+            *count += 1;
+        }
+        for entity in part.entities {
+            let key = entity.name.trim().to_lowercase();
+            if seen_entities.insert(key) {
+                entities.push(Entity {
+                    name: entity.name.trim().to_string(),
+                    kind: entity.kind.trim().to_lowercase(),
+                });
+            }
+        }
+        if let Some(t) = part.document_type {
+            let count = type_counts.entry(t.clone()).or_insert(0);
+            if *count == 0 {
+                type_order.push(t);
+            }
+            *count += 1;
+        }
+    }
+
+    // Stable sort keeps first-appearance order among equal counts.
+    keyword_order.sort_by_key(|k| std::cmp::Reverse(keyword_counts[&k.to_lowercase()]));
+    keyword_order.truncate(max_keywords);
+
+    let document_type = type_order
+        .iter()
+        .enumerate()
+        .max_by_key(|(i, t)| (type_counts[*t], std::cmp::Reverse(*i)))
+        .map(|(_, t)| t.clone());
+
+    Enrichment {
+        document_type,
+        keywords: keyword_order,
+        entities,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(name: &str, kind: &str) -> Entity {
+        Entity {
+            name: name.into(),
+            kind: kind.into(),
+        }
+    }
+
+    #[test]
+    fn parse_fenced_answer() {
+        let text = "```json\n{\"document_type\": \" Research Paper \", \"keywords\": [\"transformers\", \"\"], \
+                    \"entities\": [{\"name\": \"Google Brain\", \"type\": \"organization\"}, {\"name\": \"NIPS\"}]}\n```";
+        let e = parse_enrichment(text).unwrap();
+        assert_eq!(e.document_type.as_deref(), Some("research paper"));
+        assert_eq!(e.keywords, vec!["transformers"]);
+        assert_eq!(
+            e.entities,
+            vec![
+                entity("Google Brain", "organization"),
+                entity("NIPS", "other")
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_non_json() {
+        assert!(parse_enrichment("I cannot help with that.").is_none());
+        assert!(parse_enrichment("} nope {").is_none());
+    }
+
+    #[test]
+    fn merge_ranks_keywords_and_dedups_entities() {
+        let a = Enrichment {
+            document_type: Some("book".into()),
+            keywords: vec!["rust".into(), "async".into()],
+            entities: vec![entity("Mozilla", "organization")],
+        };
+        let b = Enrichment {
+            document_type: Some("manual".into()),
+            keywords: vec!["tokio".into(), "Async".into()],
+            entities: vec![
+                entity("mozilla", "organization"),
+                entity("Berlin", "location"),
+            ],
+        };
+        let c = Enrichment {
+            document_type: Some("manual".into()),
+            keywords: vec!["async".into()],
+            entities: vec![],
+        };
+        let merged = merge(vec![a, b, c], 2);
+        assert_eq!(merged.document_type.as_deref(), Some("manual"));
+        assert_eq!(merged.keywords, vec!["async", "rust"]);
+        assert_eq!(
+            merged.entities,
+            vec![
+                entity("Mozilla", "organization"),
+                entity("Berlin", "location")
+            ]
+        );
+    }
+}
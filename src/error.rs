@@ -61,6 +61,12 @@ pub enum Pdf2MdError {
     #[error("Wrong password for PDF '{path}'")]
     WrongPassword { path: PathBuf },
 
+    /// The input exceeds [`crate::ConversionConfig::max_pages`] or
+    /// [`crate::ConversionConfig::max_file_size_bytes`]. Raised before any
+    /// page is rendered.
+    #[error("Document too large: {detail}\nSelect fewer pages with --pages or raise the limit.")]
+    DocumentTooLarge { detail: String },
+
     /// Selected page numbers exceed the actual page count.
     #[error("Page {page} is out of range (document has {total} pages)")]
     PageOutOfRange { page: usize, total: usize },
@@ -78,6 +84,12 @@ pub enum Pdf2MdError {
     #[error("LLM API error: {message}")]
     LlmApiError { message: String },
 
+    /// The model's structured-extraction answer still broke the JSON Schema
+    /// (or the cross-field checks of [`crate::extract_invoice`]) after every
+    /// repair attempt. See [`crate::extract`].
+    #[error("Extracted data does not match the schema after {attempts} attempts: {detail}")]
+    SchemaViolation { attempts: u32, detail: String },
+
     /// Every page failed after all retries; output would be empty.
     #[error("All {total} pages failed after {retries} retries each.\nFirst error: {first_error}")]
     AllPagesFailed {
@@ -97,6 +109,11 @@ pub enum Pdf2MdError {
         total: usize,
     },
 
+    /// A page failed under [`crate::config::Strictness::FailFast`], aborting
+    /// the run.
+    #[error("Aborted on the first failed page: {0}")]
+    PageFailed(PageError),
+
     /// VLM API returned HTTP 429 — caller should back off.
     ///
     /// Check `retry_after_secs` for a server-specified delay, or use
@@ -111,6 +128,35 @@ pub enum Pdf2MdError {
     #[error("API call timed out after {elapsed_ms}ms on page {page}")]
     ApiTimeout { page: usize, elapsed_ms: u64 },
 
+    /// The first pages of a conversion all failed with auth or connection
+    /// errors, so the run was aborted instead of retrying every remaining page.
+    ///
+    /// See [`crate::ConversionConfig::failure_abort_threshold`].
+    #[error(
+        "Aborted after the first {consecutive_failures} pages all failed with provider errors.\n\
+Last error: {last_error}\n\
+Check the API key, base URL, and network connectivity."
+    )]
+    ProviderUnavailable {
+        consecutive_failures: usize,
+        last_error: String,
+    },
+
+    /// The provider failed on several pages in a row after earlier pages had
+    /// converted, so the run stopped early.
+    ///
+    /// Not returned as `Err` by [`crate::convert`]: its message is stored in
+    /// [`crate::output::ConversionOutput::fatal_error`] alongside the pages
+    /// that did convert.
+    #[error(
+        "Stopped after {consecutive_failures} consecutive pages failed with provider errors.\n\
+Last error: {last_error}"
+    )]
+    ProviderLost {
+        consecutive_failures: usize,
+        last_error: String,
+    },
+
     /// VLM API returned an authentication error (401/403) — retry unlikely to help.
     #[error("Authentication error from provider '{provider}': {detail}")]
     AuthError { provider: String, detail: String },
@@ -129,6 +175,10 @@ pub enum Pdf2MdError {
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
 
+    /// `local_only` is set but the configuration would send data off the machine.
+    #[error("local_only is set: {detail}\nNo page image or download may leave this machine.")]
+    LocalOnlyViolation { detail: String },
+
     // ── Pdfium binding errors ─────────────────────────────────────────────
     /// Could not bind to a pdfium library.
     #[error(
@@ -168,6 +218,104 @@ pub enum PageError {
     /// LLM call timed out.
     #[error("Page {page}: LLM call timed out after {secs}s")]
     Timeout { page: usize, secs: u64 },
+
+    /// Provider kept answering HTTP 429 until retries ran out.
+    ///
+    /// Distinguishes quota/throughput problems (lower `concurrency`, wait, or
+    /// raise the account limit) from content problems with the page itself.
+    #[error("Page {page}: rate limit exceeded for provider '{provider}'")]
+    RateLimited {
+        page: usize,
+        provider: String,
+        retry_after_secs: Option<u64>,
+    },
+
+    /// Provider rejected the credentials (HTTP 401/403). Never retried.
+    #[error("Page {page}: authentication failed for provider '{provider}': {detail}")]
+    AuthFailed {
+        page: usize,
+        provider: String,
+        detail: String,
+    },
+
+    /// A safety or content-policy filter refused the page or blanked the
+    /// output. Not retried on the same model; fallback models are tried.
+    #[error("Page {page}: blocked by the content filter of provider '{provider}': {detail}")]
+    ContentFiltered {
+        page: usize,
+        provider: String,
+        detail: String,
+    },
+
+    /// The model declined the page ("I'm sorry, I can't help with that") or
+    /// described the image instead of transcribing it ("This image appears
+    /// to contain…"), even after a retry with a firmer prompt. Fallback
+    /// models are tried; the text never reaches the document.
+    #[error("Page {page}: model '{provider}' refused or described the page: {detail}")]
+    ModelRefusal {
+        page: usize,
+        provider: String,
+        detail: String,
+    },
+
+    /// The provider answered with a body that could not be parsed.
+    #[error("Page {page}: invalid response from provider: {detail}")]
+    InvalidResponse { page: usize, detail: String },
+
+    /// The model spent its whole `max_tokens` budget without producing any
+    /// Markdown (typically a reasoning model). Raise `max_tokens` or set
+    /// `max_tokens_retry_cap`. A truncated page that did produce text is a
+    /// success flagged [`crate::PageResult::truncated`] instead.
+    #[error("Page {page}: no output within max_tokens={max_tokens}")]
+    Truncated { page: usize, max_tokens: usize },
+}
+
+impl PageError {
+    /// 1-indexed page number the error belongs to.
+    pub fn page(&self) -> usize {
+        match self {
+            PageError::RenderFailed { page, .. }
+            | PageError::LlmFailed { page, .. }
+            | PageError::Timeout { page, .. }
+            | PageError::RateLimited { page, .. }
+            | PageError::AuthFailed { page, .. }
+            | PageError::ContentFiltered { page, .. }
+            | PageError::ModelRefusal { page, .. }
+            | PageError::InvalidResponse { page, .. }
+            | PageError::Truncated { page, .. } => *page,
+        }
+    }
+
+    /// Whether converting the page again later may succeed without changing
+    /// the page, credentials, or configuration.
+    ///
+    /// `true` for rate limits, timeouts, unparseable responses, and generic
+    /// LLM failures; `false` for render failures, auth errors, content
+    /// filters, refusals, and truncation.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            PageError::LlmFailed { .. }
+                | PageError::Timeout { .. }
+                | PageError::RateLimited { .. }
+                | PageError::InvalidResponse { .. }
+        )
+    }
+
+    /// Stable snake_case name of the variant, suitable as a metric label.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PageError::RenderFailed { .. } => "render_failed",
+            PageError::LlmFailed { .. } => "llm_failed",
+            PageError::Timeout { .. } => "timeout",
+            PageError::RateLimited { .. } => "rate_limited",
+            PageError::AuthFailed { .. } => "auth_failed",
+            PageError::ContentFiltered { .. } => "content_filtered",
+            PageError::ModelRefusal { .. } => "model_refusal",
+            PageError::InvalidResponse { .. } => "invalid_response",
+            PageError::Truncated { .. } => "truncated",
+        }
+    }
 }
 
 #[cfg(test)]
@@ -213,6 +361,75 @@ mod tests {
         assert!(e.to_string().contains("page 3"));
     }
 
+    #[test]
+    fn provider_unavailable_display() {
+        let e = Pdf2MdError::ProviderUnavailable {
+            consecutive_failures: 5,
+            last_error: "connection refused".into(),
+        };
+        let msg = e.to_string();
+        assert!(msg.contains("first 5 pages"), "got: {msg}");
+        assert!(msg.contains("connection refused"), "got: {msg}");
+    }
+
+    #[test]
+    fn page_rate_limited_display() {
+        let e = PageError::RateLimited {
+            page: 4,
+            provider: "openai".into(),
+            retry_after_secs: Some(20),
+        };
+        let msg = e.to_string();
+        assert!(msg.contains("Page 4"), "got: {msg}");
+        assert!(msg.contains("openai"), "got: {msg}");
+    }
+
+    #[test]
+    fn page_failed_display() {
+        let e = Pdf2MdError::PageFailed(PageError::Timeout { page: 7, secs: 60 });
+        let msg = e.to_string();
+        assert!(msg.contains("first failed page"), "got: {msg}");
+        assert!(msg.contains("Page 7"), "got: {msg}");
+    }
+
+    #[test]
+    fn page_error_page_and_transience() {
+        let filtered = PageError::ContentFiltered {
+            page: 2,
+            provider: "azure".into(),
+            detail: "content_filter".into(),
+        };
+        assert_eq!(filtered.page(), 2);
+        assert!(!filtered.is_transient());
+        let truncated = PageError::Truncated {
+            page: 5,
+            max_tokens: 4096,
+        };
+        assert!(truncated.to_string().contains("max_tokens=4096"));
+        assert!(!truncated.is_transient());
+        assert!(PageError::InvalidResponse {
+            page: 1,
+            detail: "eof".into(),
+        }
+        .is_transient());
+    }
+
+    #[test]
+    fn local_only_violation_display() {
+        let e = Pdf2MdError::LocalOnlyViolation {
+            detail: "URL inputs are not allowed".into(),
+        };
+        assert!(e.to_string().starts_with("local_only is set: URL inputs"));
+    }
+
+    #[test]
+    fn document_too_large_display() {
+        let e = Pdf2MdError::DocumentTooLarge {
+            detail: "5000 pages selected, max_pages is 200".into(),
+        };
+        assert!(e.to_string().contains("5000 pages selected"));
+    }
+
     #[test]
     fn auth_error_display() {
         let e = Pdf2MdError::AuthError {
@@ -0,0 +1,254 @@
+//! Conversion quality against hand-checked ground-truth Markdown.
+//!
+//! ## Why an evaluation harness?
+//!
+//! Choosing a model, DPI, or prompt by eyeballing a few pages does not scale
+//! and does not catch regressions. [`evaluate`] scores converted Markdown
+//! against a reference with four numbers:
+//!
+//! | Metric | Measures | Best |
+//! |--------|----------|------|
+//! | `cer` | character edit distance / reference characters | 0.0 |
+//! | `wer` | word edit distance / reference words | 0.0 |
+//! | `table_cell_accuracy` | reference table cells reproduced at the same row and column | 1.0 |
+//! | `heading_similarity` | 1 − edit distance between the heading outlines (level + text) | 1.0 |
+//!
+//! CER and WER are computed on the text with Markdown markup removed (heading
+//! hashes, emphasis, list bullets, table pipes) and whitespace collapsed, so
+//! they score reading accuracy rather than formatting choices; the table and
+//! heading metrics score the structure. Edit distances are quadratic in
+//! length, which is fine for page- or chapter-sized references.
+//!
+//! `pdf2md eval --truth DIR doc.pdf …` converts each PDF with the given
+//! flags and scores it against `DIR/<stem>.md`.
+
+use serde::{Deserialize, Serialize};
+
+/// Scores of one output against its reference. See the module docs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalReport {
+    /// Character error rate. Can exceed 1.0 when the output is much longer.
+    pub cer: f64,
+    /// Word error rate. Can exceed 1.0 when the output is much longer.
+    pub wer: f64,
+    /// Share of reference table cells matched; `None` when the reference
+    /// has no tables.
+    pub table_cell_accuracy: Option<f64>,
+    /// Similarity of the heading outlines, 0.0–1.0.
+    pub heading_similarity: f64,
+    /// Characters in the normalised reference text.
+    pub reference_chars: usize,
+    /// Words in the normalised reference text.
+    pub reference_words: usize,
+}
+
+/// Score `output` Markdown against `ground_truth` Markdown.
+pub fn evaluate(output: &str, ground_truth: &str) -> EvalReport {
+    let out_text = plain_text(output);
+    let ref_text = plain_text(ground_truth);
+
+    let out_chars: Vec<char> = out_text.chars().collect();
+    let ref_chars: Vec<char> = ref_text.chars().collect();
+    let out_words: Vec<&str> = out_text.split(' ').filter(|w| !w.is_empty()).collect();
+    let ref_words: Vec<&str> = ref_text.split(' ').filter(|w| !w.is_empty()).collect();
+
+    EvalReport {
+        cer: error_rate(&out_chars, &ref_chars),
+        wer: error_rate(&out_words, &ref_words),
+        table_cell_accuracy: table_cell_accuracy(output, ground_truth),
+        heading_similarity: heading_similarity(output, ground_truth),
+        reference_chars: ref_chars.len(),
+        reference_words: ref_words.len(),
+    }
+}
+
+/// Edit distance over reference length; 0.0 or 1.0 for an empty reference.
+fn error_rate<T: PartialEq>(output: &[T], reference: &[T]) -> f64 {
+    if reference.is_empty() {
+        return if output.is_empty() { 0.0 } else { 1.0 };
+    }
+    levenshtein(output, reference) as f64 / reference.len() as f64
+}
+
+/// Levenshtein distance with unit costs, in two rows of memory.
+fn levenshtein<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        cur[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(x != y);
+            cur[j + 1] = substitute.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut cur);
+    }
+    prev[b.len()]
+}
+
+/// Text with Markdown markup stripped and whitespace collapsed to single
+/// spaces.
+fn plain_text(markdown: &str) -> String {
+    let mut buf = String::new();
+    for line in markdown.lines() {
+        let line = line.trim();
+        if line.starts_with("```") || line.starts_with("~~~") || is_table_rule(line) {
+            continue;
+        }
+        let line = line.trim_start_matches('#');
+        let line = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .or_else(|| line.strip_prefix("> "))
+            .unwrap_or(line);
+        for c in line.chars() {
+            buf.push(if matches!(c, '|' | '*' | '_' | '`') {
+                ' '
+            } else {
+                c
+            });
+        }
+        buf.push(' ');
+    }
+    buf.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `|---|:--:|` style separator row of a pipe table.
+fn is_table_rule(line: &str) -> bool {
+    line.contains('-') && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+/// Pipe tables of `markdown`, each as rows of normalised cells, separator
+/// rows dropped.
+fn tables(markdown: &str) -> Vec<Vec<Vec<String>>> {
+    let mut tables = Vec::new();
+    let mut current: Vec<Vec<String>> = Vec::new();
+    for line in markdown.lines().map(str::trim) {
+        if line.starts_with('|') {
+            if !is_table_rule(line) {
+                let inner = line.trim_start_matches('|').trim_end_matches('|');
+                current.push(
+                    inner
+                        .split('|')
+                        .map(|cell| cell.split_whitespace().collect::<Vec<_>>().join(" "))
+                        .collect(),
+                );
+            }
+        } else if !current.is_empty() {
+            tables.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.is_empty() {
+        tables.push(current);
+    }
+    tables
+}
+
+/// Reference cells found at the same table, row, and column of the output.
+fn table_cell_accuracy(output: &str, ground_truth: &str) -> Option<f64> {
+    let reference = tables(ground_truth);
+    let total: usize = reference.iter().flatten().map(Vec::len).sum();
+    if total == 0 {
+        return None;
+    }
+    let produced = tables(output);
+    let mut correct = 0;
+    for (t, table) in reference.iter().enumerate() {
+        for (r, row) in table.iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                let got = produced
+                    .get(t)
+                    .and_then(|tb| tb.get(r))
+                    .and_then(|rw| rw.get(c));
+                if got == Some(cell) {
+                    correct += 1;
+                }
+            }
+        }
+    }
+    Some(correct as f64 / total as f64)
+}
+
+/// `(level, text)` of every ATX heading outside code fences.
+fn headings(markdown: &str) -> Vec<(usize, String)> {
+    let mut in_fence = false;
+    let mut out = Vec::new();
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        let level = line.chars().take_while(|&c| c == '#').count();
+        let rest = &line[level..];
+        if (1..=6).contains(&level) && rest.starts_with([' ', '\t']) {
+            let text = rest.trim().trim_end_matches('#').trim();
+            out.push((level, text.split_whitespace().collect::<Vec<_>>().join(" ")));
+        }
+    }
+    out
+}
+
+fn heading_similarity(output: &str, ground_truth: &str) -> f64 {
+    let a = headings(output);
+    let b = headings(ground_truth);
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_text_scores_perfectly() {
+        let md = "# Title\n\nSome *text*.\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+        let report = evaluate(md, md);
+        assert_eq!(report.cer, 0.0);
+        assert_eq!(report.wer, 0.0);
+        assert_eq!(report.table_cell_accuracy, Some(1.0));
+        assert_eq!(report.heading_similarity, 1.0);
+    }
+
+    #[test]
+    fn markup_is_ignored_by_cer_and_wer() {
+        let report = evaluate("## Results\n\n- **Up** 5%", "# Results\n\n* Up 5%");
+        assert_eq!(report.cer, 0.0);
+        assert_eq!(report.wer, 0.0);
+        // The heading level differs, so the outlines do not match.
+        assert_eq!(report.heading_similarity, 0.0);
+    }
+
+    #[test]
+    fn error_rates_count_edits() {
+        let report = evaluate("the cat sat", "the bat sat");
+        assert_eq!(report.reference_words, 3);
+        assert!((report.wer - 1.0 / 3.0).abs() < 1e-9);
+        assert!((report.cer - 1.0 / 11.0).abs() < 1e-9);
+        assert_eq!(levenshtein(b"kitten", b"sitting"), 3);
+    }
+
+    #[test]
+    fn table_cells_are_compared_by_position() {
+        let truth = "| a | b |\n|---|---|\n| 1 | 2 |\n\ntext\n\n| x |\n| y |";
+        let output = "| a | b |\n|---|---|\n| 1 | 3 |\n";
+        // 3 of 4 cells in the first table, none of the missing second one.
+        assert_eq!(table_cell_accuracy(output, truth), Some(3.0 / 6.0));
+        assert_eq!(table_cell_accuracy(output, "no tables"), None);
+    }
+}
@@ -0,0 +1,205 @@
+//! JSONL audit log of a conversion run.
+//!
+//! ## Why a file, not just logs?
+//!
+//! `tracing` output is meant for humans and varies with the subscriber and
+//! log level. An event log is a stable, append-only record with one JSON
+//! object per line — easy to `jq`, load into a dataframe, or ship to a data
+//! warehouse for post-hoc analysis of retries, token spend, and failures.
+//!
+//! Enable it with [`crate::ConversionConfigBuilder::event_log`]. Each line
+//! carries an `event` tag and a `ts_ms` Unix timestamp:
+//!
+//! ```text
+//! {"ts_ms":1760600000000,"event":"conversion_start","input":"paper.pdf","total_pages":12}
+//! {"ts_ms":1760600000120,"event":"page_start","page":1}
+//! {"ts_ms":1760600002310,"event":"page_complete","page":1,"retries":0,"input_tokens":1180,"output_tokens":412,"duration_ms":2190,"truncated":false}
+//! ```
+
+use crate::error::Pdf2MdError;
+use crate::output::PageResult;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// One entry in the event log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent {
+    /// Input resolved and pages selected.
+    ConversionStart { input: String, total_pages: usize },
+    /// A page is about to be sent to the VLM.
+    PageStart { page: usize },
+    /// A page converted successfully.
+    PageComplete {
+        page: usize,
+        retries: u8,
+        input_tokens: usize,
+        output_tokens: usize,
+        duration_ms: u64,
+        truncated: bool,
+        /// Estimated spend in USD, when the model's price is known.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cost_usd: Option<f64>,
+    },
+    /// A page failed after all retries.
+    PageError {
+        page: usize,
+        retries: u8,
+        error: String,
+    },
+    /// All pages were attempted.
+    ConversionComplete {
+        processed: usize,
+        failed: usize,
+        total_input_tokens: u64,
+        total_output_tokens: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        total_cost_usd: Option<f64>,
+        duration_ms: u64,
+    },
+    /// The run ended with a fatal error after it had started.
+    ConversionFailed { error: String },
+}
+
+impl RunEvent {
+    /// The `page_complete` or `page_error` event for a finished page.
+    pub fn for_page(result: &PageResult) -> Self {
+        match &result.error {
+            None => RunEvent::PageComplete {
+                page: result.page_num,
+                retries: result.retries,
+                input_tokens: result.input_tokens,
+                output_tokens: result.output_tokens,
+                duration_ms: result.duration_ms,
+                truncated: result.truncated,
+                cost_usd: result.cost_usd,
+            },
+            Some(e) => RunEvent::PageError {
+                page: result.page_num,
+                retries: result.retries,
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    ts_ms: u64,
+    #[serde(flatten)]
+    event: &'a RunEvent,
+}
+
+/// Append-only JSONL writer shared by all pages of a run.
+///
+/// Each event is written and flushed immediately, so the log is useful even
+/// when the process is killed mid-run. Write failures are logged and
+/// otherwise ignored: an audit log must never fail the conversion.
+pub struct EventLog {
+    file: Mutex<File>,
+}
+
+impl EventLog {
+    /// Open `path` for appending, creating it (and its parent directory).
+    pub fn open(path: &Path) -> Result<Self, Pdf2MdError> {
+        let to_err = |source| Pdf2MdError::OutputWriteFailed {
+            path: path.to_path_buf(),
+            source,
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(to_err)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(to_err)?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Append one event as a JSON line.
+    pub fn record(&self, event: &RunEvent) {
+        let ts_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut line = match serde_json::to_string(&Line { ts_ms, event }) {
+            Ok(l) => l,
+            Err(e) => {
+                warn!("Event log: failed to serialise event: {}", e);
+                return;
+            }
+        };
+        line.push('\n');
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        if let Err(e) = file.write_all(line.as_bytes()).and_then(|_| file.flush()) {
+            warn!("Event log: write failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_append_as_tagged_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("logs").join("run.jsonl");
+        let log = EventLog::open(&path).unwrap();
+        log.record(&RunEvent::ConversionStart {
+            input: "a.pdf".into(),
+            total_pages: 2,
+        });
+        log.record(&RunEvent::PageStart { page: 1 });
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["event"], "conversion_start");
+        assert_eq!(lines[0]["total_pages"], 2);
+        assert_eq!(lines[1]["event"], "page_start");
+        assert!(lines[1]["ts_ms"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn page_event_reflects_outcome() {
+        let ok = PageResult {
+            page_num: 3,
+            markdown: "x".into(),
+            input_tokens: 10,
+            output_tokens: 5,
+            duration_ms: 7,
+            retries: 1,
+            ..Default::default()
+        };
+        assert!(matches!(
+            RunEvent::for_page(&ok),
+            RunEvent::PageComplete {
+                page: 3,
+                retries: 1,
+                ..
+            }
+        ));
+        let failed = PageResult {
+            error: Some(crate::error::PageError::Timeout { page: 3, secs: 60 }),
+            ..ok
+        };
+        assert!(matches!(
+            RunEvent::for_page(&failed),
+            RunEvent::PageError { page: 3, .. }
+        ));
+    }
+}
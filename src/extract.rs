@@ -0,0 +1,598 @@
+//! Schema-guided structured data extraction.
+//!
+//! ## Why a separate entry point?
+//!
+//! Many callers do not want Markdown at all: they want the invoice number,
+//! the total, and the line items as typed fields. [`extract_structured`]
+//! takes a JSON Schema describing that record and asks the model to fill it,
+//! in one of two scopes ([`ExtractionConfig::scope`]):
+//!
+//! | Scope | Model sees | Result | Suits |
+//! |-------|-----------|--------|-------|
+//! | `document` | the converted Markdown of the whole document, as text | one value | one record per document: an invoice, a contract's parties |
+//! | `page` | each page image, with the schema as the page prompt | `[{"page": n, "data": …}]` | one record per page: scanned forms, receipts |
+//!
+//! ## Validation and repair
+//!
+//! Answers are parsed leniently (Markdown fences and surrounding prose are
+//! dropped) and checked against the schema with [`validate`]. When the
+//! answer breaks the schema, the model is shown the violations and its
+//! previous answer and asked again, up to [`ExtractionConfig::repair_attempts`]
+//! times. [`validate`] covers the keywords models need to be held to —
+//! `type`, `enum`, `const`, `properties`, `required`,
+//! `additionalProperties`, `items`, `minItems`/`maxItems`,
+//! `minimum`/`maximum`, `minLength`/`maxLength` — and ignores the rest, so
+//! any JSON Schema is accepted.
+
+use crate::backend::{PageImage, VisionBackend};
+use crate::config::ConversionConfig;
+use crate::convert::{self, check_local_input, convert};
+use crate::error::Pdf2MdError;
+use crate::pipeline::{input, llm, render};
+use crate::prompts::{self, PromptTemplate};
+use crate::resolver::ConversionContext;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, warn};
+
+/// Options for [`extract_structured`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtractionConfig {
+    /// Fill the schema once per document or once per page. Default: document.
+    pub scope: ExtractionScope,
+    /// Extra requests allowed after an answer fails validation. Default: 2.
+    pub repair_attempts: u32,
+    /// Largest amount of Markdown sent in a document-scope request, in
+    /// characters. Longer documents are cut with a warning. Default: 100 000.
+    pub max_chars: usize,
+}
+
+impl Default for ExtractionConfig {
+    fn default() -> Self {
+        Self {
+            scope: ExtractionScope::Document,
+            repair_attempts: 2,
+            max_chars: 100_000,
+        }
+    }
+}
+
+/// What one schema instance describes. See the module docs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractionScope {
+    /// The whole document, read from its converted Markdown. (default)
+    #[default]
+    Document,
+    /// Each selected page, read from its image.
+    Page,
+}
+
+/// Fill the JSON Schema `schema` from the PDF at `input`.
+///
+/// Options come from [`ConversionConfig::extraction`]; the rest of `config`
+/// drives the conversion or page rendering as for [`crate::convert`].
+/// With [`ExtractionScope::Document`] the result is one value; a custom
+/// backend then needs [`ConversionConfig::provider`] for the text request.
+/// With [`ExtractionScope::Page`] the result is an array with one
+/// `{"page": n, "data": value}` entry per selected page, or
+/// `{"page": n, "error": "..."}` for a page that failed; blank pages are
+/// left out.
+///
+/// Returns [`Pdf2MdError::SchemaViolation`] when a document-scope answer
+/// still breaks the schema after every repair attempt.
+pub async fn extract_structured(
+    input: impl AsRef<str>,
+    schema: &Value,
+    config: &ConversionConfig,
+) -> Result<Value, Pdf2MdError> {
+    if !schema.is_object() {
+        return Err(Pdf2MdError::InvalidConfig(
+            "extraction schema must be a JSON object".into(),
+        ));
+    }
+    match config.extraction.scope {
+        ExtractionScope::Document => {
+            extract_document(input.as_ref(), schema, config, &|_| Vec::new()).await
+        }
+        ExtractionScope::Page => extract_pages(input.as_ref(), schema, config).await,
+    }
+}
+
+/// Fill `schema` once from the whole document's Markdown.
+///
+/// `rules` adds checks the schema cannot express (see [`crate::invoice`]);
+/// it only sees answers that already validate, and its messages go through
+/// the same repair loop as schema violations.
+pub(crate) async fn extract_document(
+    source: &str,
+    schema: &Value,
+    config: &ConversionConfig,
+    rules: &(dyn Fn(&Value) -> Vec<String> + Sync),
+) -> Result<Value, Pdf2MdError> {
+    let (_, text_provider) =
+        convert::resolve_backend(config, &ConversionContext::default()).await?;
+    let provider = text_provider.ok_or_else(|| {
+        Pdf2MdError::InvalidConfig(
+            "document-scope extraction with a custom backend needs `provider` for the text \
+             request; set it or use the page scope"
+                .into(),
+        )
+    })?;
+
+    let mut conversion = config.clone();
+    conversion.summary = None;
+    conversion.enrichment = None;
+    conversion.webhook = None;
+    conversion.include_metadata = false;
+    let output = convert(source, &conversion).await?;
+    if output.stats.failed_pages > 0 {
+        warn!(
+            "Extraction: {} pages failed to convert and are missing from the text",
+            output.stats.failed_pages
+        );
+    }
+
+    let limit = config.extraction.max_chars;
+    let markdown = match output.markdown.char_indices().nth(limit) {
+        Some((cut, _)) => {
+            warn!(
+                "Extraction: document cut to its first {} characters (max_chars)",
+                limit
+            );
+            &output.markdown[..cut]
+        }
+        None => output.markdown.as_str(),
+    };
+
+    let schema_text = serde_json::to_string_pretty(schema).expect("a Value always serializes");
+    let request = prompts::extraction_request(markdown, &schema_text);
+    let mut user_text = request.clone();
+    let attempts = config.extraction.repair_attempts + 1;
+    let mut errors = Vec::new();
+    for attempt in 1..=attempts {
+        let (answer, _, _) = llm::complete_text(
+            &provider,
+            prompts::EXTRACTION_SYSTEM_PROMPT,
+            &user_text,
+            config,
+        )
+        .await?;
+        match check_answer(&answer, schema, rules) {
+            Ok(value) => {
+                info!("Extraction: valid answer on attempt {}", attempt);
+                return Ok(value);
+            }
+            Err(found) => {
+                warn!(
+                    "Extraction: attempt {}/{} broke the schema — {}",
+                    attempt,
+                    attempts,
+                    found.join("; ")
+                );
+                user_text = format!(
+                    "{}\n\n{}",
+                    request,
+                    prompts::extraction_feedback(&answer, &found)
+                );
+                errors = found;
+            }
+        }
+    }
+    Err(Pdf2MdError::SchemaViolation {
+        attempts,
+        detail: errors.join("; "),
+    })
+}
+
+async fn extract_pages(
+    source: &str,
+    schema: &Value,
+    config: &ConversionConfig,
+) -> Result<Value, Pdf2MdError> {
+    check_local_input(config, source)?;
+    let resolved = input::resolve_input(source, config.download_timeout_secs).await?;
+    let pdf_path = resolved.path();
+    let metadata = render::extract_metadata(pdf_path, config.password.as_deref()).await?;
+    let page_indices = config.pages.to_indices(metadata.page_count);
+    if page_indices.is_empty() {
+        return Err(Pdf2MdError::PageOutOfRange {
+            page: 0,
+            total: metadata.page_count,
+        });
+    }
+
+    // Each page is read whole, and the answer must reach the validator
+    // exactly as the model wrote it.
+    let mut page_config = config.clone();
+    page_config.tile_dense_pages = false;
+    page_config.split_spreads = false;
+    page_config.keep_raw_output = true;
+    page_config.few_shot_examples.clear();
+    page_config.preset = None;
+    page_config.pii = None;
+
+    let (backend, _) =
+        convert::resolve_backend(&page_config, &ConversionContext::default()).await?;
+    let fallbacks = convert::resolve_fallback_backends(&page_config)?;
+    let schema_text = serde_json::to_string_pretty(schema).expect("a Value always serializes");
+    info!(
+        "Extracting structured data from {} pages",
+        page_indices.len()
+    );
+
+    let rx = render::spawn_lazy_render_encode(
+        pdf_path,
+        &page_config,
+        &page_indices,
+        page_config.concurrency,
+    )
+    .await?;
+    let page_config = &page_config;
+    let (backend, fallbacks, schema_text) = (&backend, &fallbacks, &schema_text);
+    let mut entries: Vec<(usize, Value)> = ReceiverStream::new(rx)
+        .filter(|page| futures::future::ready(!page.blank))
+        .map(|page| async move {
+            let page_num = page.page_index + 1;
+            let entry = match page.render_error {
+                Some(detail) => json!({ "page": page_num, "error": detail }),
+                None => {
+                    let outcome = extract_page(
+                        backend,
+                        fallbacks,
+                        page_num,
+                        page.image_data,
+                        schema,
+                        schema_text,
+                        page_config,
+                    )
+                    .await;
+                    match outcome {
+                        Ok(data) => json!({ "page": page_num, "data": data }),
+                        Err(detail) => json!({ "page": page_num, "error": detail }),
+                    }
+                }
+            };
+            (page_num, entry)
+        })
+        .buffer_unordered(page_config.concurrency)
+        .collect()
+        .await;
+    entries.sort_by_key(|(page_num, _)| *page_num);
+    Ok(Value::Array(entries.into_iter().map(|(_, e)| e).collect()))
+}
+
+/// Fill `schema` from one page image, repairing invalid answers.
+async fn extract_page(
+    backend: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
+    page_num: usize,
+    image: PageImage,
+    schema: &Value,
+    schema_text: &str,
+    config: &ConversionConfig,
+) -> Result<Value, String> {
+    let attempts = config.extraction.repair_attempts + 1;
+    let mut feedback: Option<String> = None;
+    let mut errors = Vec::new();
+    for attempt in 1..=attempts {
+        let mut cfg = config.clone();
+        cfg.prompt_template = Some(PromptTemplate::new(prompts::page_extraction_prompt(
+            schema_text,
+            feedback.as_deref(),
+        )));
+        let result =
+            llm::process_page(backend, fallbacks, page_num, image.clone(), None, &cfg).await;
+        if let Some(e) = result.error {
+            return Err(e.to_string());
+        }
+        let answer = result.raw_markdown.unwrap_or(result.markdown);
+        match check_answer(&answer, schema, &|_| Vec::new()) {
+            Ok(value) => return Ok(value),
+            Err(found) => {
+                warn!(
+                    "Extraction: page {} attempt {}/{} broke the schema — {}",
+                    page_num,
+                    attempt,
+                    attempts,
+                    found.join("; ")
+                );
+                feedback = Some(prompts::extraction_feedback(&answer, &found));
+                errors = found;
+            }
+        }
+    }
+    Err(format!(
+        "answer broke the schema after {} attempts: {}",
+        attempts,
+        errors.join("; ")
+    ))
+}
+
+/// Parse a model answer and validate it against `schema`, then `rules`,
+/// returning the violations on failure.
+fn check_answer(
+    text: &str,
+    schema: &Value,
+    rules: &(dyn Fn(&Value) -> Vec<String> + Sync),
+) -> Result<Value, Vec<String>> {
+    let value = parse_json(text).ok_or_else(|| vec!["answer is not valid JSON".to_string()])?;
+    let mut errors = validate(&value, schema);
+    if errors.is_empty() {
+        errors = rules(&value);
+    }
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parse one model answer, tolerating Markdown fences and surrounding prose.
+pub fn parse_json(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if let Ok(value) = serde_json::from_str(text) {
+        return Some(value);
+    }
+    [('{', '}'), ('[', ']')]
+        .into_iter()
+        .find_map(|(open, close)| {
+            let start = text.find(open)?;
+            let end = text.rfind(close)?;
+            if end < start {
+                return None;
+            }
+            serde_json::from_str(&text[start..=end]).ok()
+        })
+}
+
+/// Check `value` against `schema`, returning one message per violation, each
+/// prefixed with the JSON pointer of the offending value. See the module docs
+/// for the keywords understood.
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(value, schema, "", &mut errors);
+    errors
+}
+
+fn check(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "/" } else { path };
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        Value::Bool(false) => {
+            errors.push(format!("{at}: no value is allowed here"));
+            return;
+        }
+        _ => return,
+    };
+
+    if let Some(t) = schema.get("type") {
+        let allowed: Vec<&str> = match t {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| has_type(value, t)) {
+            errors.push(format!(
+                "{at}: expected {}, got {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{at}: must be one of {}",
+                Value::Array(options.clone())
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{at}: must be {expected}"));
+        }
+    }
+
+    match value {
+        Value::Object(map) => check_object(map, schema, path, errors),
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if len < min {
+                    errors.push(format!("{at}: expected at least {min} items, got {len}"));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if len > max {
+                    errors.push(format!("{at}: expected at most {max} items, got {len}"));
+                }
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item, item_schema, &format!("{path}/{i}"), errors);
+                }
+            }
+        }
+        Value::Number(n) => {
+            let x = n.as_f64().unwrap_or(f64::NAN);
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if x < min {
+                    errors.push(format!("{at}: {n} is below the minimum {min}"));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if x > max {
+                    errors.push(format!("{at}: {n} is above the maximum {max}"));
+                }
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if len < min {
+                    errors.push(format!(
+                        "{at}: expected at least {min} characters, got {len}"
+                    ));
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if len > max {
+                    errors.push(format!(
+                        "{at}: expected at most {max} characters, got {len}"
+                    ));
+                }
+            }
+        }
+        Value::Bool(_) | Value::Null => {}
+    }
+}
+
+fn check_object(
+    map: &Map<String, Value>,
+    schema: &Map<String, Value>,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    let at = if path.is_empty() { "/" } else { path };
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !map.contains_key(name) {
+                errors.push(format!("{at}: missing required property '{name}'"));
+            }
+        }
+    }
+    let properties = schema.get("properties").and_then(Value::as_object);
+    for (key, child) in map {
+        let child_path = format!("{path}/{}", key.replace('~', "~0").replace('/', "~1"));
+        match (
+            properties.and_then(|p| p.get(key)),
+            schema.get("additionalProperties"),
+        ) {
+            (Some(child_schema), _) => check(child, child_schema, &child_path, errors),
+            (None, Some(Value::Bool(false))) => {
+                errors.push(format!("{child_path}: property is not allowed"))
+            }
+            (None, Some(extra @ Value::Object(_))) => check(child, extra, &child_path, errors),
+            (None, _) => {}
+        }
+    }
+}
+
+/// Whether `value` is of JSON Schema type `name`. Unknown names match
+/// anything.
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|x| x.fract() == 0.0)
+        }
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invoice_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["number", "total"],
+            "additionalProperties": false,
+            "properties": {
+                "number": { "type": "string", "minLength": 1 },
+                "total": { "type": "number", "minimum": 0 },
+                "currency": { "enum": ["EUR", "USD"] },
+                "line_items": {
+                    "type": "array",
+                    "maxItems": 2,
+                    "items": {
+                        "type": "object",
+                        "properties": { "qty": { "type": "integer" } }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn valid_record_has_no_errors() {
+        let value = json!({
+            "number": "INV-7",
+            "total": 12.5,
+            "currency": "EUR",
+            "line_items": [{ "qty": 2 }, { "qty": 1.0 }]
+        });
+        assert!(validate(&value, &invoice_schema()).is_empty());
+    }
+
+    #[test]
+    fn violations_are_reported_with_pointers() {
+        let value = json!({
+            "number": "",
+            "total": -1,
+            "currency": "GBP",
+            "line_items": [{ "qty": "two" }, { "qty": 1 }, { "qty": 3 }],
+            "notes": "x"
+        });
+        let errors = validate(&value, &invoice_schema());
+        assert_eq!(
+            errors,
+            vec![
+                "/currency: must be one of [\"EUR\",\"USD\"]",
+                "/line_items: expected at most 2 items, got 3",
+                "/line_items/0/qty: expected integer, got string",
+                "/notes: property is not allowed",
+                "/number: expected at least 1 characters, got 0",
+                "/total: -1 is below the minimum 0",
+            ]
+        );
+        let errors = validate(&json!({ "total": 1 }), &invoice_schema());
+        assert_eq!(errors, vec!["/: missing required property 'number'"]);
+    }
+
+    #[test]
+    fn type_lists_allow_null() {
+        let schema = json!({ "type": ["string", "null"] });
+        assert!(validate(&Value::Null, &schema).is_empty());
+        assert_eq!(
+            validate(&json!(3), &schema),
+            vec!["/: expected string or null, got number"]
+        );
+    }
+
+    #[test]
+    fn answers_are_parsed_through_fences_and_prose() {
+        let value = json!({ "a": 1 });
+        assert_eq!(parse_json("{\"a\": 1}"), Some(value.clone()));
+        assert_eq!(parse_json("```json\n{\"a\": 1}\n```"), Some(value.clone()));
+        assert_eq!(
+            parse_json("Here you go: {\"a\": 1} Hope it helps."),
+            Some(value)
+        );
+        assert_eq!(parse_json("```\n[1, 2]\n```"), Some(json!([1, 2])));
+        assert_eq!(parse_json("no json here"), None);
+    }
+}
@@ -0,0 +1,347 @@
+//! Figure alt text: describe each embedded image and link it accessibly.
+//!
+//! ## Why a second call per figure?
+//!
+//! The page prompt asks the model to caption figures in italics, which
+//! keeps the reading flow but leaves the picture itself out of the
+//! document, and a screen reader with nothing to read. With
+//! [`crate::ConversionConfigBuilder::figure_captions`] each raster image
+//! placed on a page is cut out of the rendered page and sent on its own
+//! with [`crate::prompts::FIGURE_ALT_PROMPT`], which asks for one or two
+//! sentences of alt text. The answers land in [`crate::PageResult::figures`].
+//!
+//! With [`crate::ConversionConfigBuilder::figures_dir`] the cut-outs are
+//! also saved as PNG files and linked from the Markdown as
+//! `![alt](dir/page-0003-figure-1.png "caption")`. Each figure takes the
+//! place of the next whole-line italic caption the model wrote on that
+//! page, whose text becomes the link title; figures left over are appended
+//! to the page. Links name the directory by its last component, so the
+//! Markdown file belongs next to it.
+//!
+//! Figures are found from the PDF's image objects, so vector charts drawn
+//! with paths are not among them, and images covering nearly the whole page
+//! (scans) or only a sliver of it (logos, rules) are skipped.
+
+use crate::backend::{DescribeOptions, PageImage, PagePrompt, VisionBackend};
+use crate::config::{ConversionConfig, CropRect};
+use crate::output::PageResult;
+use crate::pipeline::encode;
+use crate::prompts::FIGURE_ALT_PROMPT;
+use crate::transcript;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::DynamicImage;
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+/// One figure of a page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Figure {
+    /// 1-based position among the page's figures, in content order.
+    pub index: usize,
+    /// Where the figure sits on the rendered page, as fractions.
+    pub region: CropRect,
+    /// Alt text from the model; empty when the call failed.
+    pub alt_text: String,
+    /// The saved PNG, with [`crate::ConversionConfig::figures_dir`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+/// Images covering more of the page than this are page scans, not figures.
+const MAX_FIGURE_AREA: f32 = 0.85;
+
+/// Images covering less of the page than this are logos, bullets, or rules.
+const MIN_FIGURE_AREA: f32 = 0.01;
+
+/// Output token budget for one alt text.
+const ALT_TEXT_MAX_TOKENS: usize = 200;
+
+/// The page's figures as regions of its image after rendering and
+/// `rotation` degrees of [`crate::ConversionConfig::auto_rotate`].
+pub(crate) fn figure_regions(page: &PdfPage, rotation: u16) -> Vec<CropRect> {
+    let boundaries = page.boundaries();
+    let Ok(frame) = boundaries.crop().or_else(|_| boundaries.media()) else {
+        return Vec::new();
+    };
+    let frame = frame.bounds;
+    let (width, height) = (frame.width().value, frame.height().value);
+    if width <= 0.0 || height <= 0.0 {
+        return Vec::new();
+    }
+    let page_rotation = match page.rotation() {
+        Ok(PdfPageRenderRotation::Degrees90) => 90,
+        Ok(PdfPageRenderRotation::Degrees180) => 180,
+        Ok(PdfPageRenderRotation::Degrees270) => 270,
+        _ => 0,
+    };
+    page.objects()
+        .iter()
+        .filter(|o| o.object_type() == PdfPageObjectType::Image)
+        .filter_map(|o| o.bounds().ok())
+        .filter_map(|b| {
+            let x = ((b.left().value - frame.left().value) / width).clamp(0.0, 1.0);
+            let right = ((b.right().value - frame.left().value) / width).clamp(0.0, 1.0);
+            let y = (1.0 - (b.top().value - frame.bottom().value) / height).clamp(0.0, 1.0);
+            let bottom = (1.0 - (b.bottom().value - frame.bottom().value) / height).clamp(0.0, 1.0);
+            let rect = CropRect::new(x, y, right - x, bottom - y);
+            let area = rect.width * rect.height;
+            (MIN_FIGURE_AREA..=MAX_FIGURE_AREA)
+                .contains(&area)
+                .then(|| rotate(rotate(rect, page_rotation), rotation))
+        })
+        .collect()
+}
+
+/// `rect` on an image turned `degrees` clockwise.
+fn rotate(rect: CropRect, degrees: u16) -> CropRect {
+    let CropRect {
+        x,
+        y,
+        width,
+        height,
+    } = rect;
+    match degrees {
+        90 => CropRect::new(1.0 - (y + height), x, height, width),
+        180 => CropRect::new(1.0 - (x + width), 1.0 - (y + height), width, height),
+        270 => CropRect::new(y, 1.0 - (x + width), height, width),
+        _ => rect,
+    }
+}
+
+/// Cut `regions` out of the rendered page and encode each as PNG.
+pub(crate) fn crop_figures(
+    image: &DynamicImage,
+    regions: Vec<CropRect>,
+) -> Vec<(CropRect, PageImage)> {
+    regions
+        .into_iter()
+        .filter_map(|region| {
+            let (x, y, w, h) = region.to_pixels(image.width(), image.height());
+            match encode::encode_page(&image.crop_imm(x, y, w, h)) {
+                Ok(png) => Some((region, png)),
+                Err(e) => {
+                    warn!("Figure encoding failed: {}", e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+/// Ask `provider` for the alt text of each figure, save the figures to
+/// [`ConversionConfig::figures_dir`], and record them on `result` with the
+/// tokens and cost they took. A failed call leaves that figure's alt text
+/// empty rather than failing the page.
+pub(crate) async fn describe_figures(
+    provider: &Arc<dyn VisionBackend>,
+    result: &mut PageResult,
+    figures: Vec<(CropRect, PageImage)>,
+    config: &ConversionConfig,
+) {
+    let prompt = PagePrompt {
+        system: FIGURE_ALT_PROMPT.to_string(),
+        ..Default::default()
+    };
+    let options = DescribeOptions {
+        temperature: config.temperature,
+        max_tokens: ALT_TEXT_MAX_TOKENS,
+        seed: config.seed,
+    };
+    let (mut input_tokens, mut output_tokens) = (0, 0);
+    for (i, (region, image)) in figures.into_iter().enumerate() {
+        let index = i + 1;
+        let answer = provider.describe_page(&image, &prompt, &options).await;
+        transcript::record(
+            config,
+            result.page_num,
+            provider.as_ref(),
+            &image,
+            &prompt,
+            &options,
+            &answer,
+        );
+        let alt_text = match answer {
+            Ok(completion) => {
+                input_tokens += completion.input_tokens;
+                output_tokens += completion.output_tokens;
+                clean_alt_text(&completion.markdown)
+            }
+            Err(e) => {
+                warn!(
+                    "Page {} figure {}: alt text failed — {}",
+                    result.page_num, index, e
+                );
+                String::new()
+            }
+        };
+        let path = config
+            .figures_dir
+            .as_deref()
+            .and_then(|dir| save(dir, result.page_num, index, &image));
+        result.figures.push(Figure {
+            index,
+            region,
+            alt_text,
+            path,
+        });
+    }
+    debug!(
+        "Page {}: {} figures described",
+        result.page_num,
+        result.figures.len()
+    );
+    result.input_tokens += input_tokens;
+    result.output_tokens += output_tokens;
+    if let Some(price) = config.price_for(provider.model()) {
+        *result.cost_usd.get_or_insert(0.0) += price.cost(input_tokens, output_tokens);
+    }
+}
+
+/// Write one figure as `page-NNNN-figure-N.png` in `dir`.
+fn save(dir: &Path, page_num: usize, index: usize, image: &PageImage) -> Option<PathBuf> {
+    let path = dir.join(format!("page-{page_num:04}-figure-{index}.png"));
+    let written = STANDARD
+        .decode(&image.data)
+        .map_err(|e| e.to_string())
+        .and_then(|png| {
+            std::fs::create_dir_all(dir)
+                .and_then(|()| std::fs::write(&path, png))
+                .map_err(|e| e.to_string())
+        });
+    match written {
+        Ok(()) => Some(path),
+        Err(e) => {
+            warn!("Could not save figure {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// One line of plain text: no quotes, labels, or Markdown the model added.
+fn clean_alt_text(answer: &str) -> String {
+    let text = answer.split_whitespace().collect::<Vec<_>>().join(" ");
+    let text = text
+        .trim_start_matches("Alt text:")
+        .trim_start_matches("Alt:")
+        .trim()
+        .trim_matches(['"', '*', '`', '_'])
+        .trim();
+    text.replace(['[', ']'], "")
+}
+
+/// Insert the saved `figures` into a page's Markdown.
+///
+/// Each figure replaces the next whole-line italic caption (`*Figure 2: …*`),
+/// whose text becomes the link title; the rest are appended. Figures that
+/// were not saved are left out.
+pub fn place(markdown: &str, figures: &[Figure]) -> String {
+    let mut saved = figures.iter().filter_map(|f| {
+        let path = f.path.as_deref()?;
+        let file = path.file_name()?.to_string_lossy();
+        let link = match path.parent().and_then(Path::file_name) {
+            Some(dir) => format!("{}/{}", dir.to_string_lossy(), file),
+            None => file.into_owned(),
+        };
+        Some((f, link.replace(' ', "%20")))
+    });
+    let image = |f: &Figure, link: &str, caption: Option<&str>| {
+        let alt = if f.alt_text.is_empty() {
+            caption.unwrap_or("Figure")
+        } else {
+            &f.alt_text
+        };
+        match caption {
+            Some(c) => format!("![{alt}]({link} \"{}\")", c.replace('"', "'")),
+            None => format!("![{alt}]({link})"),
+        }
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_fence = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let caption = italic_line(trimmed).filter(|_| !in_fence);
+        match caption.and_then(|c| saved.next().map(|(f, link)| image(f, &link, Some(c)))) {
+            Some(figure) => lines.push(figure),
+            None => lines.push(line.to_string()),
+        }
+    }
+    let rest: Vec<String> = saved.map(|(f, link)| image(f, &link, None)).collect();
+    let mut out = lines.join("\n");
+    if !rest.is_empty() {
+        let out_trimmed = out.trim_end().len();
+        out.truncate(out_trimmed);
+        if !out.is_empty() {
+            out.push_str("\n\n");
+        }
+        out.push_str(&rest.join("\n\n"));
+    }
+    if markdown.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// The text of a line that is wholly in single-asterisk or underscore
+/// italics.
+fn italic_line(line: &str) -> Option<&str> {
+    let inner = line
+        .strip_prefix('*')
+        .and_then(|l| l.strip_suffix('*'))
+        .filter(|l| !l.starts_with('*') && !l.ends_with('*'))
+        .or_else(|| line.strip_prefix('_').and_then(|l| l.strip_suffix('_')))?;
+    let inner = inner.trim();
+    (!inner.is_empty() && inner.len() <= 300).then_some(inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn figure(index: usize, alt: &str, saved: bool) -> Figure {
+        Figure {
+            index,
+            region: CropRect::new(0.1, 0.1, 0.5, 0.3),
+            alt_text: alt.into(),
+            path: saved
+                .then(|| PathBuf::from(format!("out/doc_figures/page-0003-figure-{index}.png"))),
+        }
+    }
+
+    #[test]
+    fn figures_replace_italic_captions() {
+        let md = "Intro text.\n\n*Figure 1: Revenue by year*\n\n**Bold** text.\n";
+        let figures = [
+            figure(1, "Bar chart rising from 2019 to 2023", true),
+            figure(2, "", true),
+            figure(3, "Not saved", false),
+        ];
+        assert_eq!(
+            place(md, &figures),
+            "Intro text.\n\n![Bar chart rising from 2019 to 2023](doc_figures/page-0003-figure-1.png \
+\"Figure 1: Revenue by year\")\n\n**Bold** text.\n\n![Figure](doc_figures/page-0003-figure-2.png)\n"
+        );
+        assert_eq!(place(md, &[]), md);
+        assert_eq!(italic_line("**Bold**"), None);
+        assert_eq!(clean_alt_text("Alt text: \"A [red] barn\"\n"), "A red barn");
+    }
+
+    #[test]
+    fn regions_follow_rotation() {
+        let r = CropRect::new(0.1, 0.2, 0.3, 0.4);
+        let close = |a: CropRect, b: CropRect| {
+            [a.x - b.x, a.y - b.y, a.width - b.width, a.height - b.height]
+                .iter()
+                .all(|d| d.abs() < 1e-6)
+        };
+        assert!(close(rotate(r, 90), CropRect::new(0.4, 0.1, 0.4, 0.3)));
+        assert!(close(rotate(r, 180), CropRect::new(0.6, 0.4, 0.3, 0.4)));
+        assert!(close(rotate(rotate(r, 90), 270), r));
+    }
+}
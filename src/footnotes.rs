@@ -0,0 +1,425 @@
+//! Optional footnote normalisation and relocation.
+//!
+//! ## Why?
+//!
+//! Footnotes come out of a page-by-page conversion in two broken ways. Some
+//! pages keep the printed form — a superscript `²` in the text and a line
+//! `2 See appendix B.` at the bottom — which Markdown does not know as a
+//! footnote. And the ones the model does write as `[^1]` restart at 1 on
+//! every page, so the assembled document has many `[^1]`s, and their
+//! definitions sit between pages, splitting any paragraph that runs on.
+//!
+//! With [`crate::ConversionConfig::link_footnotes`]:
+//!
+//! 1. Each page (in every API, as part of post-processing) has its
+//!    bottom-of-page notes rewritten as `[^n]: …` when the text carries a
+//!    matching `<sup>n</sup>`, `$^{n}$`, or Unicode superscript marker, which
+//!    becomes `[^n]`. All definitions move to the end of the page.
+//! 2. [`crate::convert`] renames labels that an earlier page already used to
+//!    `p{page}-{label}`, so every footnote is unique in the document.
+//! 3. The assembled Markdown collects the page-end notes after the last
+//!    page.
+//!
+//! A page-end definition counts as a footnote only when its own page
+//! references it; definitions referenced from elsewhere (a bibliography
+//! linked by [`crate::references`]) stay where they are.
+
+use crate::output::PageResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+
+static DEF_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[\^([^\]\s]+)\]:[ \t]*(.*)$").unwrap());
+static RAW_DEF_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?:<sup>\s*(\d{1,3})\s*</sup>|\$\^\{?(\d{1,3})\}?\$|([⁰¹²³⁴⁵⁶⁷⁸⁹]+)|(\d{1,3})[ \t])[ \t]*(\S.*)$",
+    )
+    .unwrap()
+});
+static MARKER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<sup>\s*(\d{1,3})\s*</sup>|\$\^\{?(\d{1,3})\}?\$|([⁰¹²³⁴⁵⁶⁷⁸⁹]+)").unwrap()
+});
+
+/// A footnote definition: label, first line of text, continuation lines.
+struct Note {
+    label: String,
+    text: String,
+    more: Vec<String>,
+}
+
+impl Note {
+    fn render(&self) -> String {
+        let mut out = format!("[^{}]: {}", self.label, self.text);
+        for line in &self.more {
+            out.push_str("\n    ");
+            out.push_str(line.trim_start());
+        }
+        out
+    }
+}
+
+/// Rewrite printed footnotes on one page as Markdown footnotes and move all
+/// definitions to the end of the page. Pages without footnotes are returned
+/// unchanged.
+pub fn normalize_page(markdown: &str) -> String {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let fenced = fence_mask(&lines);
+
+    let (raw_top, raw_notes) = raw_block(&lines, &fenced);
+    let numbers: HashSet<&str> = raw_notes.iter().map(|n| n.label.as_str()).collect();
+    let markers = body_markers(&lines[..raw_top], &fenced);
+    // Every printed note must be referenced, or the "block" is likely an
+    // ordinary numbered paragraph.
+    let (raw_top, raw_notes) = if numbers.iter().all(|n| markers.contains(*n)) {
+        (raw_top, raw_notes)
+    } else {
+        (lines.len(), Vec::new())
+    };
+
+    let mut body: Vec<String> = Vec::new();
+    let mut notes: Vec<Note> = Vec::new();
+    let mut i = 0;
+    while i < raw_top {
+        let line = lines[i];
+        match DEF_RE.captures(line).filter(|_| !fenced[i]) {
+            Some(caps) => {
+                let mut note = Note {
+                    label: caps[1].to_string(),
+                    text: caps[2].to_string(),
+                    more: Vec::new(),
+                };
+                i += 1;
+                while i < raw_top && continues_note(lines[i], lines[i - 1]) {
+                    note.more.push(lines[i].to_string());
+                    i += 1;
+                }
+                notes.push(note);
+            }
+            None => {
+                body.push(if raw_notes.is_empty() || fenced[i] {
+                    line.to_string()
+                } else {
+                    link_markers(line)
+                });
+                i += 1;
+            }
+        }
+    }
+    if notes.is_empty() && raw_notes.is_empty() {
+        return markdown.to_string();
+    }
+    notes.extend(raw_notes);
+
+    while body.last().is_some_and(|l| l.trim().is_empty()) {
+        body.pop();
+    }
+    let defs: Vec<String> = notes.iter().map(Note::render).collect();
+    let mut out = body.join("\n");
+    if !out.is_empty() {
+        out.push_str("\n\n");
+    }
+    out.push_str(&defs.join("\n"));
+    out.push('\n');
+    out
+}
+
+/// Rename footnote labels already used by an earlier page to
+/// `p{page}-{label}`, so labels are unique across the document.
+pub fn relabel(pages: &mut [PageResult]) {
+    let mut seen: HashSet<String> = HashSet::new();
+    for page in pages.iter_mut().filter(|p| p.error.is_none()) {
+        let (_, notes) = split_notes(&page.markdown);
+        for label in notes.iter().map(|n| n.label.clone()) {
+            let label = if seen.contains(&label) {
+                let renamed = format!("p{}-{}", page.page_num, label);
+                page.markdown = page
+                    .markdown
+                    .replace(&format!("[^{label}]"), &format!("[^{renamed}]"));
+                renamed
+            } else {
+                label
+            };
+            seen.insert(label);
+        }
+    }
+}
+
+/// Split one page into its body and the footnote definitions at its end
+/// that the body references, rendered one per entry.
+pub(crate) fn page_notes(markdown: &str) -> (String, Vec<String>) {
+    let (body, notes) = split_notes(markdown);
+    (body, notes.iter().map(Note::render).collect())
+}
+
+fn split_notes(markdown: &str) -> (String, Vec<Note>) {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let fenced = fence_mask(&lines);
+
+    // The trailing run of definitions, continuation lines, and blanks.
+    let mut top = lines.len();
+    while top > 0 {
+        let line = lines[top - 1];
+        let part_of_note = !fenced[top - 1]
+            && (line.trim().is_empty()
+                || DEF_RE.is_match(line)
+                || line.starts_with("    ")
+                || line.starts_with('\t'));
+        if !part_of_note {
+            break;
+        }
+        top -= 1;
+    }
+    // Continuation lines at the top of the run belong to the body.
+    while top < lines.len() && !DEF_RE.is_match(lines[top]) && !lines[top].trim().is_empty() {
+        top += 1;
+    }
+
+    let body_text = lines[..top].join("\n");
+    let mut kept: Vec<&str> = Vec::new();
+    let mut moved: Vec<Note> = Vec::new();
+    let mut i = top;
+    while i < lines.len() {
+        let Some(caps) = DEF_RE.captures(lines[i]) else {
+            kept.push(lines[i]);
+            i += 1;
+            continue;
+        };
+        let start = i;
+        let mut note = Note {
+            label: caps[1].to_string(),
+            text: caps[2].to_string(),
+            more: Vec::new(),
+        };
+        i += 1;
+        while i < lines.len() && (lines[i].starts_with("    ") || lines[i].starts_with('\t')) {
+            note.more.push(lines[i].to_string());
+            i += 1;
+        }
+        if body_text.contains(&format!("[^{}]", note.label)) {
+            moved.push(note);
+        } else {
+            kept.extend(&lines[start..i]);
+        }
+    }
+    if moved.is_empty() {
+        return (markdown.to_string(), moved);
+    }
+
+    let mut body: Vec<&str> = lines[..top].to_vec();
+    while body.last().is_some_and(|l| l.trim().is_empty()) {
+        body.pop();
+    }
+    kept.retain(|l| !l.trim().is_empty());
+    if !kept.is_empty() {
+        body.push("");
+        body.extend(kept);
+    }
+    let mut out = body.join("\n");
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    (out, moved)
+}
+
+/// Per line, whether it is inside (or delimits) a fenced code block.
+fn fence_mask(lines: &[&str]) -> Vec<bool> {
+    let mut in_fence = false;
+    lines
+        .iter()
+        .map(|line| {
+            let t = line.trim_start();
+            if t.starts_with("```") || t.starts_with("~~~") {
+                in_fence = !in_fence;
+                return true;
+            }
+            in_fence
+        })
+        .collect()
+}
+
+/// Whether `line` continues the footnote definition ending at `prev`:
+/// indented, or a lazy continuation directly below it.
+fn continues_note(line: &str, prev: &str) -> bool {
+    if line.starts_with("    ") || line.starts_with('\t') {
+        return true;
+    }
+    let t = line.trim_start();
+    !t.is_empty()
+        && !prev.trim().is_empty()
+        && !DEF_RE.is_match(line)
+        && !t.starts_with(['#', '|', '>', '-', '*', '+'])
+}
+
+/// The printed notes at the bottom of the page, as `(first line of the
+/// block, notes)`; `(lines.len(), [])` when there are none.
+///
+/// Walking up from the end, each line that starts with a note number opens
+/// a note; unnumbered lines between two notes continue the one above. A
+/// horizontal rule directly above the topmost note is removed with it.
+fn raw_block(lines: &[&str], fenced: &[bool]) -> (usize, Vec<Note>) {
+    let mut notes: Vec<Note> = Vec::new();
+    let mut pending: Vec<&str> = Vec::new();
+    let mut top = lines.len();
+    let mut j = lines.len();
+    while j > 0 {
+        j -= 1;
+        let t = lines[j].trim();
+        if fenced[j] {
+            break;
+        }
+        if t.is_empty() {
+            if !pending.is_empty() {
+                break;
+            }
+            continue;
+        }
+        if is_rule(t) {
+            if !notes.is_empty() && pending.is_empty() {
+                top = j;
+            }
+            break;
+        }
+        match RAW_DEF_RE.captures(t) {
+            Some(caps) => {
+                let label = (1..=4)
+                    .find_map(|g| caps.get(g))
+                    .map(|m| from_superscript(m.as_str()))
+                    .unwrap_or_default();
+                pending.reverse();
+                notes.push(Note {
+                    label,
+                    text: caps[5].to_string(),
+                    more: pending.drain(..).map(str::to_string).collect(),
+                });
+                top = j;
+            }
+            None => pending.push(lines[j]),
+        }
+    }
+    notes.reverse();
+    (top, notes)
+}
+
+fn is_rule(t: &str) -> bool {
+    t.len() >= 3
+        && (t.chars().all(|c| c == '-')
+            || t.chars().all(|c| c == '_')
+            || t.chars().all(|c| c == '*'))
+}
+
+/// Note numbers referenced by markers in `lines`.
+fn body_markers(lines: &[&str], fenced: &[bool]) -> HashSet<String> {
+    lines
+        .iter()
+        .zip(fenced)
+        .filter(|(_, f)| !**f)
+        .flat_map(|(line, _)| marker_numbers(line))
+        .collect()
+}
+
+fn marker_numbers(line: &str) -> Vec<String> {
+    MARKER_RE
+        .captures_iter(line)
+        .filter(|caps| is_inline(line, caps.get(0).unwrap().start()))
+        .filter_map(|caps| {
+            (1..=3)
+                .find_map(|g| caps.get(g))
+                .map(|m| from_superscript(m.as_str()))
+        })
+        .collect()
+}
+
+/// Markers at the very start of a line are note numbers, not references.
+fn is_inline(line: &str, at: usize) -> bool {
+    at > 0 && !line[..at].trim().is_empty()
+}
+
+fn link_markers(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for caps in MARKER_RE.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        if !is_inline(line, m.start()) {
+            continue;
+        }
+        let n = (1..=3)
+            .find_map(|g| caps.get(g))
+            .map(|g| from_superscript(g.as_str()))
+            .unwrap_or_default();
+        out.push_str(&line[last..m.start()]);
+        out.push_str(&format!("[^{n}]"));
+        last = m.end();
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+/// `"¹²"` → `"12"`; ASCII digits pass through.
+fn from_superscript(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '⁰' => '0',
+            '¹' => '1',
+            '²' => '2',
+            '³' => '3',
+            '⁴' => '4',
+            '⁵' => '5',
+            '⁶' => '6',
+            '⁷' => '7',
+            '⁸' => '8',
+            '⁹' => '9',
+            other => other,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn printed_notes_become_footnotes() {
+        let md = "Growth was strong² in Q3<sup>3</sup>.\n\n---\n2 Adjusted for inflation.\n3 Unaudited;\nsee appendix B.\n";
+        assert_eq!(
+            normalize_page(md),
+            "Growth was strong[^2] in Q3[^3].\n\n[^2]: Adjusted for inflation.\n[^3]: Unaudited;\n    see appendix B.\n"
+        );
+    }
+
+    #[test]
+    fn numbered_lines_without_markers_are_left_alone() {
+        let md = "Steps:\n\n1 Open the lid\n2 Pour\n";
+        assert_eq!(normalize_page(md), md);
+    }
+
+    #[test]
+    fn definitions_move_to_the_page_end() {
+        let md = "Text[^1] here.\n[^1]: A note\ncontinued.\n\nMore text.\n";
+        let out = normalize_page(md);
+        assert_eq!(
+            out,
+            "Text[^1] here.\n\nMore text.\n\n[^1]: A note\n    continued.\n"
+        );
+        assert_eq!(normalize_page(&out), out);
+    }
+
+    #[test]
+    fn page_notes_keep_unreferenced_definitions() {
+        let md = "See [^a].\n\n[^a]: Mine.\n[^12]: A cited work.\n";
+        let (body, notes) = page_notes(md);
+        assert_eq!(body, "See [^a].\n\n[^12]: A cited work.\n");
+        assert_eq!(notes, vec!["[^a]: Mine."]);
+    }
+
+    #[test]
+    fn repeated_labels_are_renamed_per_page() {
+        let mut pages = vec![
+            PageResult::new(1, "One[^1].\n\n[^1]: First.\n"),
+            PageResult::new(2, "Two[^1].\n\n[^1]: Second.\n"),
+        ];
+        relabel(&mut pages);
+        assert_eq!(pages[1].markdown, "Two[^p2-1].\n\n[^p2-1]: Second.\n");
+        relabel(&mut pages);
+        assert_eq!(pages[1].markdown, "Two[^p2-1].\n\n[^p2-1]: Second.\n");
+    }
+}
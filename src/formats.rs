@@ -0,0 +1,1089 @@
+//! AsciiDoc, reStructuredText, and Pandoc JSON output.
+//!
+//! ## Why convert after assembly?
+//!
+//! Teams whose documentation runs on Antora or Sphinx cannot drop Markdown
+//! into their sources. Asking the model for AsciiDoc or reST directly would
+//! need new prompts and would bypass every Markdown post-processing rule, so
+//! the pipeline stays Markdown-first and [`convert_to_format`] translates the
+//! assembled document at the end, deterministically.
+//!
+//! The translation covers what the conversion produces:
+//!
+//! | Markdown | AsciiDoc | reST |
+//! |----------|----------|------|
+//! | YAML front-matter | `= Title` and `:attr:` entries | title and docinfo fields |
+//! | `#` … `######` | `==` … (properly nested) | underlined titles (properly nested) |
+//! | `**b**`, `*i*`, `` `c` `` | `**b**`, `__i__`, `` `+c+` `` | `**b**`, `*i*`, ` ``c`` ` |
+//! | links, images | `url[text]`, `image::src[alt]` | `` `text <url>`__ ``, `.. image::` |
+//! | lists | `*` / `.` nesting | `-` / `1.` nesting |
+//! | pipe tables | `\|===` tables | `.. list-table::` |
+//! | fenced code, `$$` math, `$x$` | `[source]`, `[latexmath]`, `latexmath:[x]` | `.. code-block::`, `.. math::`, `:math:` |
+//! | `[^n]` footnotes | `footnote:n[…]` | `[#n]_` with `.. [#n]` |
+//! | `> [!NOTE]` alerts, quotes | `[NOTE]` blocks, `____` | `.. note::`, block quotes |
+//! | HTML blocks, comments | `++++` passthrough, `//` | `.. raw:: html`, `..` |
+//!
+//! Heading levels are re-nested so a skipped level (`#` then `###`) does not
+//! produce a section out of sequence, which both toolchains reject.
+//!
+//! [`pandoc`] builds Pandoc's JSON AST from the same parse, for toolchains
+//! that transform documents structurally.
+
+use crate::config::{ConversionConfig, MarkdownFlavor};
+use crate::convert::convert;
+use crate::error::Pdf2MdError;
+use crate::pipeline::flavor::{alignments, split_row};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+pub mod pandoc;
+
+static HEADING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(#{1,6})[ \t]+(.*?)(?:[ \t]+#+)?[ \t]*$").unwrap());
+static RULE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^ {0,3}(?:(?:-[ \t]*){3,}|(?:\*[ \t]*){3,}|(?:_[ \t]*){3,})$").unwrap()
+});
+static ITEM_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([ \t]*)([-*+]|\d{1,9}[.)])[ \t]+(.*)$").unwrap());
+static NOTE_DEF_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[\^([^\]\s]+)\]:[ \t]*(.*)$").unwrap());
+static ALERT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^>\s*\[!(note|tip|important|warning|caution)\]\s*$").unwrap());
+static COMMENT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^<!--\s*(.*?)\s*-->$").unwrap());
+static HTML_BLOCK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^</?[A-Za-z][A-Za-z0-9]*[\s/>]").unwrap());
+static IMAGE_LINE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^!\[([^\]]*)\]\(([^)\s]+)(?:\s+"[^"]*")?\)$"#).unwrap());
+static META_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^([A-Za-z_][\w-]*):[ \t]*(.*)$").unwrap());
+
+static MATH_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\$([^$\s](?:[^$]*?[^$\s])?)\$").unwrap());
+static IMAGE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^!\[([^\]]*)\]\(([^)\s]+)(?:\s+"[^"]*")?\)"#).unwrap());
+static LINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^\[([^\]]*)\]\(([^)\s]+)(?:\s+"[^"]*")?\)"#).unwrap());
+static NOTE_REF_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[\^([^\]\s]+)\]").unwrap());
+static STRONG_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:\*\*(\S(?:.*?\S)?)\*\*|__(\S(?:.*?\S)?)__)").unwrap());
+static EMPH_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:\*(\S(?:.*?\S)?)\*|_(\S(?:.*?\S)?)_)").unwrap());
+static STRIKE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^~~(\S(?:.*?\S)?)~~").unwrap());
+static BR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^<br\s*/?>").unwrap());
+static SUP_SUB_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^<(sup|sub)>(.*?)</(?:sup|sub)>").unwrap());
+static AUTOLINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^<((?:https?|mailto):[^<>\s]+)>").unwrap());
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^</?[A-Za-z][A-Za-z0-9]*[^<>]*>").unwrap());
+
+/// Underline characters for reST section levels 1, 2, … after the title.
+const RST_UNDERLINES: [char; 6] = ['=', '-', '~', '^', '"', '\''];
+
+/// Text format written by [`convert_to_format`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputTarget {
+    /// The assembled Markdown, unchanged. (default)
+    #[default]
+    Markdown,
+    /// AsciiDoc, for Asciidoctor and Antora.
+    #[serde(alias = "adoc")]
+    AsciiDoc,
+    /// reStructuredText, for Sphinx and docutils.
+    #[serde(alias = "rst")]
+    ReStructuredText,
+    /// Pandoc's JSON AST (`pandoc -f json`), see [`pandoc`].
+    #[serde(rename = "pandoc-json", alias = "pandocjson")]
+    PandocJson,
+}
+
+impl OutputTarget {
+    /// Conventional file extension, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputTarget::Markdown => "md",
+            OutputTarget::AsciiDoc => "adoc",
+            OutputTarget::ReStructuredText => "rst",
+            OutputTarget::PandocJson => "json",
+        }
+    }
+}
+
+/// Convert a PDF and return the document in `target`'s format.
+///
+/// Runs [`crate::convert`] with [`ConversionConfig::flavor`] forced to GFM,
+/// the dialect the translators read, and translates the assembled Markdown
+/// with [`render`]. Use `convert` and `render` separately to keep the
+/// [`crate::ConversionOutput`] as well.
+pub async fn convert_to_format(
+    input: impl AsRef<str>,
+    target: OutputTarget,
+    config: &ConversionConfig,
+) -> Result<String, Pdf2MdError> {
+    let mut config = config.clone();
+    config.flavor = MarkdownFlavor::Gfm;
+    let output = convert(input, &config).await?;
+    Ok(render(&output.markdown, target))
+}
+
+/// Translate GitHub Flavored Markdown to `target`. See the module docs.
+pub fn render(markdown: &str, target: OutputTarget) -> String {
+    match target {
+        OutputTarget::Markdown => markdown.to_string(),
+        OutputTarget::AsciiDoc => to_asciidoc(markdown),
+        OutputTarget::ReStructuredText => to_rst(markdown),
+        OutputTarget::PandocJson => pandoc::document(markdown).to_string(),
+    }
+}
+
+/// Translate Markdown to AsciiDoc.
+pub fn to_asciidoc(markdown: &str) -> String {
+    let doc = parse(markdown);
+    let mut w = Writer::new(OutputTarget::AsciiDoc, &doc.notes);
+    let mut parts: Vec<String> = Vec::new();
+    for (block, level) in doc.blocks.iter().zip(&doc.levels) {
+        parts.push(w.asciidoc_block(block, *level));
+    }
+
+    let mut header: Vec<String> = Vec::new();
+    if let Some(title) = doc.meta_value("title") {
+        header.push(format!("= {title}"));
+    }
+    for (key, value) in doc.meta.iter().filter(|(k, _)| k != "title") {
+        header.push(format!(":{}: {}", key.replace('_', "-"), value));
+    }
+    if w.math {
+        header.push(":stem: latexmath".to_string());
+    }
+    if !header.is_empty() {
+        parts.insert(0, header.join("\n"));
+    }
+    finish(parts)
+}
+
+/// Translate Markdown to reStructuredText.
+pub fn to_rst(markdown: &str) -> String {
+    let doc = parse(markdown);
+    let mut w = Writer::new(OutputTarget::ReStructuredText, &doc.notes);
+    let mut parts: Vec<String> = Vec::new();
+    if let Some(title) = doc.meta_value("title") {
+        let rule = "=".repeat(display_width(title));
+        parts.push(format!("{rule}\n{title}\n{rule}"));
+    }
+    let fields: Vec<String> = doc
+        .meta
+        .iter()
+        .filter(|(k, _)| k != "title")
+        .map(|(k, v)| format!(":{k}: {v}"))
+        .collect();
+    if !fields.is_empty() {
+        parts.push(fields.join("\n"));
+    }
+    for (k, (block, level)) in doc.blocks.iter().zip(&doc.levels).enumerate() {
+        // An empty comment keeps a block quote from continuing the list or
+        // directive before it.
+        let after_body = k > 0
+            && matches!(
+                doc.blocks[k - 1],
+                Block::Heading { .. } | Block::Paragraph(_)
+            );
+        if matches!(block, Block::Quote(_)) && k > 0 && !after_body {
+            parts.push("..".to_string());
+        }
+        parts.push(w.rst_block(block, *level));
+    }
+
+    // Definitions of the footnotes the text references, in order of use.
+    let defs: Vec<String> = w
+        .used
+        .clone()
+        .iter()
+        .map(|label| {
+            let text = doc.notes.get(label).map(String::as_str).unwrap_or("");
+            format!(".. [{}] {}", rst_note_label(label), w.inline(text))
+        })
+        .collect();
+    if !defs.is_empty() {
+        parts.push(defs.join("\n"));
+    }
+    finish(parts)
+}
+
+fn finish(parts: Vec<String>) -> String {
+    let mut out = parts
+        .into_iter()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    out.push('\n');
+    out
+}
+
+/// A block of the parsed Markdown.
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Heading {
+        /// Markdown level, 1 for `#`.
+        level: usize,
+        text: String,
+    },
+    Paragraph(Vec<String>),
+    List(Vec<Item>),
+    Code {
+        lang: String,
+        lines: Vec<String>,
+    },
+    Math(Vec<String>),
+    Table {
+        aligns: Vec<Option<&'static str>>,
+        header: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+    Quote(Vec<String>),
+    Admonition {
+        kind: String,
+        lines: Vec<String>,
+    },
+    Html(Vec<String>),
+    Comment(String),
+    Rule,
+    Image {
+        alt: String,
+        src: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Item {
+    depth: usize,
+    ordered: Option<String>,
+    text: String,
+}
+
+/// The parsed document: front-matter scalars, blocks, the section level of
+/// each heading (0 for other blocks), and footnote definitions by label.
+struct Doc {
+    meta: Vec<(String, String)>,
+    blocks: Vec<Block>,
+    levels: Vec<usize>,
+    notes: HashMap<String, String>,
+}
+
+impl Doc {
+    fn meta_value(&self, key: &str) -> Option<&str> {
+        self.meta
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+fn parse(markdown: &str) -> Doc {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let (meta, mut i) = front_matter(&lines);
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut notes: HashMap<String, String> = HashMap::new();
+
+    while i < lines.len() {
+        let line = lines[i];
+        let t = line.trim();
+        if t.is_empty() {
+            i += 1;
+            continue;
+        }
+        let block = if let Some(fence) = fence_of(line) {
+            let lang = t[fence.len()..].trim().to_string();
+            let end = (i + 1..lines.len())
+                .find(|&j| lines[j].trim_start().starts_with(fence))
+                .unwrap_or(lines.len());
+            let body = lines[i + 1..end].iter().map(|l| l.to_string()).collect();
+            i = end + 1;
+            Block::Code { lang, lines: body }
+        } else if t.starts_with("$$") {
+            let single = t.len() > 4 && t.ends_with("$$");
+            let end = if single {
+                i
+            } else {
+                (i + 1..lines.len())
+                    .find(|&j| lines[j].trim_end().ends_with("$$"))
+                    .unwrap_or(lines.len() - 1)
+            };
+            let body = lines[i..=end].join("\n");
+            let body = body.trim().trim_start_matches("$$").trim_end_matches("$$");
+            i = end + 1;
+            Block::Math(body.trim().lines().map(str::to_string).collect())
+        } else if let Some(caps) = HEADING_RE.captures(line) {
+            i += 1;
+            Block::Heading {
+                level: caps[1].len(),
+                text: caps[2].to_string(),
+            }
+        } else if RULE_RE.is_match(line) {
+            i += 1;
+            Block::Rule
+        } else if let Some(caps) = COMMENT_RE.captures(t) {
+            i += 1;
+            Block::Comment(caps[1].to_string())
+        } else if let Some(caps) = NOTE_DEF_RE.captures(line) {
+            let mut text = caps[2].trim().to_string();
+            i += 1;
+            while i < lines.len() && (lines[i].starts_with("    ") || lines[i].starts_with('\t')) {
+                text.push(' ');
+                text.push_str(lines[i].trim());
+                i += 1;
+            }
+            notes.insert(caps[1].to_string(), text);
+            continue;
+        } else if let Some(caps) = ALERT_RE.captures(t) {
+            let (body, next) = quoted(&lines, i + 1);
+            i = next;
+            Block::Admonition {
+                kind: caps[1].to_ascii_lowercase(),
+                lines: body,
+            }
+        } else if t.starts_with('>') {
+            let (body, next) = quoted(&lines, i);
+            i = next;
+            Block::Quote(body)
+        } else if let Some(table) = table_at(&lines, i) {
+            let (block, next) = table;
+            i = next;
+            block
+        } else if ITEM_RE.is_match(line) {
+            let (items, next) = list(&lines, i);
+            i = next;
+            Block::List(items)
+        } else if let Some(caps) = IMAGE_LINE_RE.captures(t) {
+            i += 1;
+            Block::Image {
+                alt: caps[1].to_string(),
+                src: caps[2].to_string(),
+            }
+        } else if HTML_BLOCK_RE.is_match(t) {
+            let end = (i..lines.len())
+                .find(|&j| lines[j].trim().is_empty())
+                .unwrap_or(lines.len());
+            let body = lines[i..end].iter().map(|l| l.to_string()).collect();
+            i = end;
+            Block::Html(body)
+        } else {
+            let mut body = vec![t.to_string()];
+            i += 1;
+            while i < lines.len() && !starts_block(&lines, i) {
+                body.push(lines[i].trim().to_string());
+                i += 1;
+            }
+            Block::Paragraph(body)
+        };
+        blocks.push(block);
+    }
+
+    Doc {
+        meta,
+        levels: nest_levels(&blocks),
+        blocks,
+        notes,
+    }
+}
+
+/// Scalars of a leading `---` YAML block, quotes removed, and the index of
+/// the first line after it. Nested values (lists, maps) are skipped.
+fn front_matter(lines: &[&str]) -> (Vec<(String, String)>, usize) {
+    if lines.first().map(|l| l.trim()) != Some("---") {
+        return (Vec::new(), 0);
+    }
+    let Some(end) = (1..lines.len()).find(|&j| lines[j].trim() == "---") else {
+        return (Vec::new(), 0);
+    };
+    let body = &lines[1..end];
+    let well_formed = body
+        .iter()
+        .all(|l| l.starts_with([' ', '-']) || l.trim().is_empty() || META_RE.is_match(l));
+    if !well_formed {
+        return (Vec::new(), 0);
+    }
+    let meta = body
+        .iter()
+        .filter_map(|l| META_RE.captures(l))
+        .filter_map(|caps| {
+            let value = caps[2].trim();
+            if value.is_empty() || value.starts_with('[') {
+                return None;
+            }
+            let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                Some(inner) => inner.replace("\\\"", "\"").replace("\\\\", "\\"),
+                None => value.to_string(),
+            };
+            Some((caps[1].to_string(), value))
+        })
+        .collect();
+    (meta, end + 1)
+}
+
+/// Section level of each heading after re-nesting: a heading is one level
+/// below the nearest preceding heading with a smaller Markdown level, so
+/// levels never skip. `0` for non-headings.
+fn nest_levels(blocks: &[Block]) -> Vec<usize> {
+    let mut stack: Vec<usize> = Vec::new();
+    blocks
+        .iter()
+        .map(|block| match block {
+            Block::Heading { level, .. } => {
+                while stack.last().is_some_and(|&top| top >= *level) {
+                    stack.pop();
+                }
+                stack.push(*level);
+                stack.len()
+            }
+            _ => 0,
+        })
+        .collect()
+}
+
+fn fence_of(line: &str) -> Option<&'static str> {
+    let t = line.trim_start();
+    if t.starts_with("```") {
+        Some("```")
+    } else if t.starts_with("~~~") {
+        Some("~~~")
+    } else {
+        None
+    }
+}
+
+/// The `>`-prefixed lines from `start`, prefix removed.
+fn quoted(lines: &[&str], start: usize) -> (Vec<String>, usize) {
+    let mut body = Vec::new();
+    let mut i = start;
+    while i < lines.len() && lines[i].trim_start().starts_with('>') {
+        let t = &lines[i].trim_start()[1..];
+        body.push(t.strip_prefix(' ').unwrap_or(t).to_string());
+        i += 1;
+    }
+    (body, i)
+}
+
+fn table_at(lines: &[&str], i: usize) -> Option<(Block, usize)> {
+    if !lines[i].trim_start().starts_with('|') {
+        return None;
+    }
+    let aligns = alignments(lines.get(i + 1)?)?;
+    let mut end = i + 2;
+    while end < lines.len() && lines[end].trim_start().starts_with('|') {
+        end += 1;
+    }
+    let width = aligns.len();
+    let fit = |mut cells: Vec<String>| {
+        cells.resize(width, String::new());
+        cells
+    };
+    let block = Block::Table {
+        header: fit(split_row(lines[i])),
+        rows: lines[i + 2..end]
+            .iter()
+            .map(|l| fit(split_row(l)))
+            .collect(),
+        aligns,
+    };
+    Some((block, end))
+}
+
+/// A list from `start`: items with their nesting depth, continuation lines
+/// joined into the item text.
+fn list(lines: &[&str], start: usize) -> (Vec<Item>, usize) {
+    let mut items: Vec<Item> = Vec::new();
+    let mut indents: Vec<usize> = Vec::new();
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i];
+        if let Some(caps) = ITEM_RE.captures(line) {
+            let indent = caps[1].replace('\t', "    ").len();
+            while indents.last().is_some_and(|&top| top > indent) {
+                indents.pop();
+            }
+            if indents.last().is_none_or(|&top| top < indent) {
+                indents.push(indent);
+            }
+            let marker = &caps[2];
+            let ordered = marker
+                .chars()
+                .next()
+                .filter(char::is_ascii_digit)
+                .map(|_| marker[..marker.len() - 1].to_string());
+            items.push(Item {
+                depth: indents.len() - 1,
+                ordered,
+                text: caps[3].trim().to_string(),
+            });
+            i += 1;
+        } else if line.trim().is_empty() {
+            // A blank line ends the list unless it continues after it.
+            let next = lines.get(i + 1).copied().unwrap_or("");
+            if ITEM_RE.is_match(next) || (next.starts_with("  ") && !next.trim().is_empty()) {
+                i += 1;
+            } else {
+                break;
+            }
+        } else if starts_block(lines, i) && !line.starts_with("  ") {
+            break;
+        } else {
+            let last = items.last_mut().expect("a list starts with an item");
+            last.text.push(' ');
+            last.text.push_str(line.trim());
+            i += 1;
+        }
+    }
+    (items, i)
+}
+
+/// Whether line `i` interrupts a paragraph.
+fn starts_block(lines: &[&str], i: usize) -> bool {
+    let line = lines[i];
+    let t = line.trim();
+    t.is_empty()
+        || fence_of(line).is_some()
+        || t.starts_with("$$")
+        || t.starts_with('>')
+        || HEADING_RE.is_match(line)
+        || RULE_RE.is_match(line)
+        || ITEM_RE.is_match(line)
+        || NOTE_DEF_RE.is_match(line)
+        || COMMENT_RE.is_match(t)
+        || table_at(lines, i).is_some()
+}
+
+/// An inline element of Markdown text. Containers hold their raw inner
+/// text, which is split again when rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Span<'t> {
+    /// A character that reads literally (escapes resolved).
+    Text(char),
+    Code(&'t str),
+    Math(&'t str),
+    Image {
+        alt: &'t str,
+        src: &'t str,
+    },
+    Link {
+        label: &'t str,
+        url: &'t str,
+    },
+    NoteRef(&'t str),
+    Strong(&'t str),
+    Emph(&'t str),
+    Strike(&'t str),
+    Sup(&'t str),
+    Sub(&'t str),
+    /// `<br>`.
+    Break,
+    /// `<https://…>`.
+    Autolink(&'t str),
+    /// Any other HTML tag, kept whole.
+    Tag(&'t str),
+}
+
+/// Split Markdown inline text into [`Span`]s.
+fn spans(text: &str) -> Vec<Span<'_>> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let rest = &text[i..];
+        let c = rest.chars().next().expect("i is on a char boundary");
+        let prev = text[..i].chars().next_back();
+        let (span, len) = match c {
+            '\\' => match rest[1..].chars().next() {
+                Some(next) if next.is_ascii_punctuation() => {
+                    (Span::Text(next), 1 + next.len_utf8())
+                }
+                _ => (Span::Text('\\'), 1),
+            },
+            '`' => {
+                let run = rest.len() - rest.trim_start_matches('`').len();
+                match rest[run..].find(&rest[..run]) {
+                    Some(close) => (Span::Code(rest[run..run + close].trim()), 2 * run + close),
+                    None => {
+                        out.extend(std::iter::repeat_n(Span::Text('`'), run - 1));
+                        (Span::Text('`'), run)
+                    }
+                }
+            }
+            '$' => match MATH_RE
+                .captures(rest)
+                .filter(|caps| !rest[caps[0].len()..].starts_with(|ch: char| ch.is_ascii_digit()))
+            {
+                Some(caps) => (Span::Math(caps.get(1).unwrap().as_str()), caps[0].len()),
+                None => (Span::Text('$'), 1),
+            },
+            '!' if IMAGE_RE.is_match(rest) => {
+                let caps = IMAGE_RE.captures(rest).unwrap();
+                let span = Span::Image {
+                    alt: caps.get(1).unwrap().as_str(),
+                    src: caps.get(2).unwrap().as_str(),
+                };
+                (span, caps[0].len())
+            }
+            '[' if NOTE_REF_RE.is_match(rest) => {
+                let caps = NOTE_REF_RE.captures(rest).unwrap();
+                (Span::NoteRef(caps.get(1).unwrap().as_str()), caps[0].len())
+            }
+            '[' if LINK_RE.is_match(rest) => {
+                let caps = LINK_RE.captures(rest).unwrap();
+                let span = Span::Link {
+                    label: caps.get(1).unwrap().as_str(),
+                    url: caps.get(2).unwrap().as_str(),
+                };
+                (span, caps[0].len())
+            }
+            '*' | '_' => {
+                // `_` only opens emphasis at a word start (not in snake_case).
+                let opens = c == '*' || !prev.is_some_and(char::is_alphanumeric);
+                if let Some(caps) = STRONG_RE.captures(rest).filter(|_| opens) {
+                    (Span::Strong(delimited(&caps)), caps[0].len())
+                } else if let Some(caps) = EMPH_RE.captures(rest).filter(|_| opens) {
+                    (Span::Emph(delimited(&caps)), caps[0].len())
+                } else {
+                    (Span::Text(c), 1)
+                }
+            }
+            '~' if STRIKE_RE.is_match(rest) => {
+                let caps = STRIKE_RE.captures(rest).unwrap();
+                (Span::Strike(caps.get(1).unwrap().as_str()), caps[0].len())
+            }
+            '<' => {
+                if let Some(m) = BR_RE.find(rest) {
+                    (Span::Break, m.end())
+                } else if let Some(caps) = SUP_SUB_RE.captures(rest) {
+                    let inner = caps.get(2).unwrap().as_str();
+                    let span = if caps[1].eq_ignore_ascii_case("sup") {
+                        Span::Sup(inner)
+                    } else {
+                        Span::Sub(inner)
+                    };
+                    (span, caps[0].len())
+                } else if let Some(caps) = AUTOLINK_RE.captures(rest) {
+                    (Span::Autolink(caps.get(1).unwrap().as_str()), caps[0].len())
+                } else if let Some(m) = TAG_RE.find(rest) {
+                    (Span::Tag(m.as_str()), m.end())
+                } else {
+                    (Span::Text('<'), 1)
+                }
+            }
+            _ => (Span::Text(c), c.len_utf8()),
+        };
+        out.push(span);
+        i += len;
+    }
+    out
+}
+
+/// Inner text of a `*`-delimited (group 1) or `_`-delimited (group 2) match.
+fn delimited<'t>(caps: &regex::Captures<'t>) -> &'t str {
+    caps.get(1).or(caps.get(2)).map_or("", |m| m.as_str())
+}
+
+/// Renders blocks and inline text, tracking footnotes and math use.
+struct Writer<'a> {
+    target: OutputTarget,
+    notes: &'a HashMap<String, String>,
+    /// Footnote labels referenced so far, in order of first use.
+    used: Vec<String>,
+    seen: HashSet<String>,
+    /// Whether math was written (AsciiDoc needs `:stem:`).
+    math: bool,
+}
+
+impl<'a> Writer<'a> {
+    fn new(target: OutputTarget, notes: &'a HashMap<String, String>) -> Self {
+        Writer {
+            target,
+            notes,
+            used: Vec::new(),
+            seen: HashSet::new(),
+            math: false,
+        }
+    }
+
+    fn asciidoc_block(&mut self, block: &Block, level: usize) -> String {
+        match block {
+            Block::Heading { text, .. } => {
+                format!("{} {}", "=".repeat((level + 1).min(6)), self.inline(text))
+            }
+            Block::Paragraph(lines) => self.inline_lines(lines),
+            Block::List(items) => items
+                .iter()
+                .map(|item| {
+                    let marker = if item.ordered.is_some() { "." } else { "*" };
+                    format!(
+                        "{} {}",
+                        marker.repeat(item.depth + 1),
+                        self.inline(&item.text)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Block::Code { lang, lines } => {
+                let attrs = if lang.is_empty() {
+                    String::new()
+                } else {
+                    format!("[source,{lang}]\n")
+                };
+                format!("{attrs}----\n{}\n----", lines.join("\n"))
+            }
+            Block::Math(lines) => {
+                self.math = true;
+                format!("[latexmath]\n++++\n{}\n++++", lines.join("\n"))
+            }
+            Block::Table {
+                aligns,
+                header,
+                rows,
+            } => {
+                let cols: Vec<&str> = aligns
+                    .iter()
+                    .map(|a| match a {
+                        Some("center") => "^",
+                        Some("right") => ">",
+                        _ => "<",
+                    })
+                    .collect();
+                let mut out = format!("[%header,cols=\"{}\"]\n|===\n", cols.join(","));
+                out.push_str(&self.asciidoc_row(header));
+                for row in rows {
+                    out.push_str("\n\n");
+                    out.push_str(&self.asciidoc_row(row));
+                }
+                out.push_str("\n|===");
+                out
+            }
+            Block::Quote(lines) => format!("____\n{}\n____", self.inline_lines(lines)),
+            Block::Admonition { kind, lines } => format!(
+                "[{}]\n====\n{}\n====",
+                kind.to_ascii_uppercase(),
+                self.inline_lines(lines)
+            ),
+            Block::Html(lines) => format!("++++\n{}\n++++", lines.join("\n")),
+            Block::Comment(text) => format!("// {text}"),
+            Block::Rule => "'''".to_string(),
+            Block::Image { alt, src } => format!("image::{src}[{}]", alt.replace(']', "\\]")),
+        }
+    }
+
+    fn asciidoc_row(&mut self, cells: &[String]) -> String {
+        cells
+            .iter()
+            .map(|c| format!("|{}", self.inline(c).replace('|', "\\|")))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn rst_block(&mut self, block: &Block, level: usize) -> String {
+        match block {
+            Block::Heading { text, .. } => {
+                let text = self.inline(text);
+                let ch = RST_UNDERLINES[(level.max(1) - 1).min(RST_UNDERLINES.len() - 1)];
+                let rule = ch.to_string().repeat(display_width(&text).max(1));
+                format!("{text}\n{rule}")
+            }
+            Block::Paragraph(lines) => self.inline_lines(lines),
+            Block::List(items) => self.rst_list(items),
+            Block::Code { lang, lines } => {
+                let lang = if lang.is_empty() { "text" } else { lang };
+                format!(".. code-block:: {lang}\n\n{}", indent(lines, 3))
+            }
+            Block::Math(lines) => {
+                self.math = true;
+                format!(".. math::\n\n{}", indent(lines, 3))
+            }
+            Block::Table { header, rows, .. } => {
+                let mut out = String::from(".. list-table::\n   :header-rows: 1\n");
+                for row in std::iter::once(header).chain(rows) {
+                    out.push('\n');
+                    for (k, cell) in row.iter().enumerate() {
+                        let bullet = if k == 0 { "   * -" } else { "     -" };
+                        let text = self.inline(cell);
+                        if text.is_empty() {
+                            out.push_str(bullet);
+                        } else {
+                            out.push_str(&format!("{bullet} {text}"));
+                        }
+                        if k + 1 < row.len() {
+                            out.push('\n');
+                        }
+                    }
+                }
+                out
+            }
+            Block::Quote(lines) => {
+                let text = self.inline_lines(lines);
+                text.lines()
+                    .map(|l| format!("    {l}").trim_end().to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            Block::Admonition { kind, lines } => {
+                let text: Vec<String> = self
+                    .inline_lines(lines)
+                    .lines()
+                    .map(str::to_string)
+                    .collect();
+                format!(".. {kind}::\n\n{}", indent(&text, 3))
+            }
+            Block::Html(lines) => format!(".. raw:: html\n\n{}", indent(lines, 3)),
+            Block::Comment(text) => format!(".. {text}"),
+            Block::Rule => "----".to_string(),
+            Block::Image { alt, src } => format!(".. image:: {src}\n   :alt: {alt}"),
+        }
+    }
+
+    /// reST lists: nested items are indented under their parent's text and
+    /// separated by blank lines, which docutils requires around a sublist.
+    fn rst_list(&mut self, items: &[Item]) -> String {
+        let mut out: Vec<String> = Vec::new();
+        // Marker widths of the open parent items.
+        let mut widths: Vec<usize> = Vec::new();
+        let mut prev_depth = 0;
+        for (k, item) in items.iter().enumerate() {
+            widths.truncate(item.depth);
+            while widths.len() < item.depth {
+                widths.push(2);
+            }
+            let marker = match &item.ordered {
+                Some(n) => format!("{n}."),
+                None => "-".to_string(),
+            };
+            if k > 0 && item.depth != prev_depth {
+                out.push(String::new());
+            }
+            let pad = " ".repeat(widths.iter().sum());
+            out.push(format!("{pad}{marker} {}", self.inline(&item.text)));
+            widths.push(marker.len() + 1);
+            widths.truncate(item.depth + 1);
+            prev_depth = item.depth;
+        }
+        out.join("\n")
+    }
+
+    fn inline_lines(&mut self, lines: &[String]) -> String {
+        lines
+            .iter()
+            .map(|l| self.inline(l))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Translate Markdown inline markup in `text`.
+    fn inline(&mut self, text: &str) -> String {
+        let adoc = self.target == OutputTarget::AsciiDoc;
+        let spans = spans(text);
+        let mut out = String::with_capacity(text.len() + 8);
+        for (k, span) in spans.iter().enumerate() {
+            let piece = match *span {
+                Span::Text(c) => self.literal(c),
+                Span::Code(code) if adoc => format!("`+{code}+`"),
+                Span::Code(code) => format!("``{code}``"),
+                Span::Math(tex) => {
+                    self.math = true;
+                    if adoc {
+                        format!("latexmath:[{}]", tex.replace(']', "\\]"))
+                    } else {
+                        format!(":math:`{tex}`")
+                    }
+                }
+                Span::Image { alt, src } if adoc => {
+                    format!("image:{src}[{}]", alt.replace(']', "\\]"))
+                }
+                Span::Image { alt, .. } => self.plain(alt),
+                Span::NoteRef(label) => self.note_ref(label),
+                Span::Link { label, url } => {
+                    let label = self.inline(label);
+                    if adoc {
+                        let prefix = if url.contains("://") || url.starts_with("mailto:") {
+                            ""
+                        } else {
+                            "link:"
+                        };
+                        format!("{prefix}{url}[{}]", label.replace(']', "\\]"))
+                    } else if label.is_empty() || label == url {
+                        format!("`<{url}>`__")
+                    } else {
+                        format!("`{} <{url}>`__", label.replace('`', "\\`"))
+                    }
+                }
+                Span::Strong(inner) => format!("**{}**", self.inline(inner)),
+                Span::Emph(inner) if adoc => format!("__{}__", self.inline(inner)),
+                Span::Emph(inner) => format!("*{}*", self.inline(inner)),
+                Span::Strike(inner) if adoc => format!("[.line-through]#{}#", self.inline(inner)),
+                Span::Strike(inner) => self.inline(inner),
+                Span::Sup(inner) if adoc => format!("^{}^", self.inline(inner)),
+                Span::Sup(inner) => format!(":sup:`{}`", self.inline(inner)),
+                Span::Sub(inner) if adoc => format!("~{}~", self.inline(inner)),
+                Span::Sub(inner) => format!(":sub:`{}`", self.inline(inner)),
+                Span::Break => " ".to_string(),
+                Span::Autolink(url) => url.to_string(),
+                Span::Tag(_) => String::new(),
+            };
+            // reST inline markup must not touch a letter: `H\ :sub:`2`\ O`.
+            let markup = !adoc
+                && !matches!(
+                    span,
+                    Span::Text(_)
+                        | Span::Image { .. }
+                        | Span::Strike(_)
+                        | Span::Break
+                        | Span::Autolink(_)
+                        | Span::Tag(_)
+                );
+            if markup && out.chars().next_back().is_some_and(char::is_alphanumeric) {
+                out.push_str("\\ ");
+            }
+            out.push_str(&piece);
+            let next_is_word =
+                matches!(spans.get(k + 1), Some(Span::Text(c)) if c.is_alphanumeric());
+            if markup && next_is_word {
+                out.push_str("\\ ");
+            }
+        }
+        out
+    }
+
+    /// Text with no markup of its own.
+    fn plain(&self, text: &str) -> String {
+        text.chars().map(|c| self.literal(c)).collect()
+    }
+
+    /// One character that must read literally.
+    fn literal(&self, c: char) -> String {
+        match (self.target, c) {
+            (OutputTarget::AsciiDoc, '*' | '_' | '`' | '#' | '^' | '~' | '[' | ']') => {
+                format!("+{c}+")
+            }
+            (OutputTarget::ReStructuredText, '\\' | '*' | '`' | '|' | '_') => format!("\\{c}"),
+            _ => c.to_string(),
+        }
+    }
+
+    fn note_ref(&mut self, label: &str) -> String {
+        let first = self.seen.insert(label.to_string());
+        if first {
+            self.used.push(label.to_string());
+        }
+        match self.target {
+            OutputTarget::AsciiDoc => {
+                let id: String = label
+                    .chars()
+                    .map(|c| {
+                        if c.is_alphanumeric() || c == '-' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect();
+                let text = match (first, self.notes.get(label)) {
+                    (true, Some(text)) => self.inline(&text.clone()).replace(']', "\\]"),
+                    _ => String::new(),
+                };
+                format!("footnote:fn-{id}[{text}]")
+            }
+            _ => format!("[{}]_", rst_note_label(label)),
+        }
+    }
+}
+
+/// reST footnote label: numbers as they are, other labels auto-numbered.
+fn rst_note_label(label: &str) -> String {
+    if label.chars().all(|c| c.is_ascii_digit()) {
+        label.to_string()
+    } else {
+        format!("#{label}")
+    }
+}
+
+fn indent(lines: &[String], n: usize) -> String {
+    let pad = " ".repeat(n);
+    lines
+        .iter()
+        .map(|l| {
+            if l.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{pad}{l}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Columns `text` takes in a monospace font; reST title underlines must be
+/// at least this long.
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "---\ntitle: \"Pump Manual\"\nauthor: \"ACME\"\npages: 3\n---\n\n# Overview\n\nThe **P-100** pump[^1] moves *water*; see [the site](https://acme.example).\n\n### Specs\n\n| Item | Value |\n|:---|---:|\n| Flow | 5 m³/h |\n\n- Quiet\n  - under $40$ dB\n- Cheap\n\n> [!WARNING]\n> Disconnect power first.\n\n```sh\npump --start\n```\n\n[^1]: Sold separately.\n";
+
+    #[test]
+    fn asciidoc_document() {
+        assert_eq!(
+            to_asciidoc(SAMPLE),
+            "= Pump Manual\n:author: ACME\n:pages: 3\n:stem: latexmath\n\n\
+== Overview\n\n\
+The **P-100** pumpfootnote:fn-1[Sold separately.] moves __water__; see https://acme.example[the site].\n\n\
+=== Specs\n\n\
+[%header,cols=\"<,>\"]\n|===\n|Item |Value\n\n|Flow |5 m³/h\n|===\n\n\
+* Quiet\n** under latexmath:[40] dB\n* Cheap\n\n\
+[WARNING]\n====\nDisconnect power first.\n====\n\n\
+[source,sh]\n----\npump --start\n----\n"
+        );
+    }
+
+    #[test]
+    fn rst_document() {
+        assert_eq!(
+            to_rst(SAMPLE),
+            "===========\nPump Manual\n===========\n\n:author: ACME\n:pages: 3\n\n\
+Overview\n========\n\n\
+The **P-100** pump\\ [1]_ moves *water*; see `the site <https://acme.example>`__.\n\n\
+Specs\n-----\n\n\
+.. list-table::\n   :header-rows: 1\n\n   * - Item\n     - Value\n   * - Flow\n     - 5 m³/h\n\n\
+- Quiet\n\n  - under :math:`40` dB\n\n- Cheap\n\n\
+.. warning::\n\n   Disconnect power first.\n\n\
+.. code-block:: sh\n\n   pump --start\n\n\
+.. [1] Sold separately.\n"
+        );
+    }
+
+    #[test]
+    fn literals_and_snake_case_survive() {
+        assert_eq!(
+            to_rst("Call my_func_name with 2 * 3 and \\*stars\\*.\n"),
+            "Call my\\_func\\_name with 2 \\* 3 and \\*stars\\*.\n"
+        );
+        assert_eq!(
+            to_asciidoc("Costs $5 and $10, a_b.\n"),
+            "Costs $5 and $10, a+_+b.\n"
+        );
+    }
+
+    #[test]
+    fn headings_are_renested() {
+        let doc = parse("### A\n\ntext\n\n# B\n\n#### C\n\n## D\n\n## E\n");
+        assert_eq!(doc.levels, vec![1, 0, 1, 2, 2, 2]);
+    }
+}
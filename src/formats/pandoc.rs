@@ -0,0 +1,355 @@
+//! Pandoc JSON AST output.
+//!
+//! Pandoc's native JSON (`pandoc -t json`) is the interchange format for its
+//! filters and for any tool that transforms documents structurally. Emitting
+//! it directly gives headings, tables, math, footnotes, and callouts as
+//! typed nodes, so `pandoc -f json` and filters read the document without
+//! guessing at Markdown syntax again.
+//!
+//! Nodes follow pandoc-types [`API_VERSION`]: tables use the `Table` node
+//! with column specs, head, and one body; `> [!NOTE]` alerts become a `Div`
+//! with the alert's class and a `title` div, as pandoc's own GFM reader
+//! produces them; headings carry GitHub-style identifiers; inline and
+//! display math are `Math` nodes. HTML blocks, comments, and unknown tags
+//! are kept as `RawBlock`/`RawInline` HTML.
+
+use super::{parse, spans, Block, Item, Span};
+use crate::headings::{slugify, unique};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// The pandoc-types version of the emitted AST (pandoc 3.1.x and later).
+pub const API_VERSION: [u32; 3] = [1, 23, 1];
+
+/// Convert Markdown to a Pandoc JSON document.
+pub fn document(markdown: &str) -> Value {
+    let doc = parse(markdown);
+    let mut w = AstWriter {
+        notes: &doc.notes,
+        slugs: HashMap::new(),
+    };
+
+    let mut meta = Map::new();
+    for (key, value) in &doc.meta {
+        let inlines = w.inlines(&escape_markdown(value));
+        meta.insert(key.clone(), json!({ "t": "MetaInlines", "c": inlines }));
+    }
+    let blocks = w.blocks(&doc.blocks);
+    json!({
+        "pandoc-api-version": API_VERSION,
+        "meta": meta,
+        "blocks": blocks,
+    })
+}
+
+/// Front-matter values are plain text; keep `*` or `_` in a title literal.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_punctuation() {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+struct AstWriter<'a> {
+    notes: &'a HashMap<String, String>,
+    /// Heading identifiers taken so far.
+    slugs: HashMap<String, usize>,
+}
+
+impl AstWriter<'_> {
+    fn blocks(&mut self, blocks: &[Block]) -> Vec<Value> {
+        blocks.iter().map(|b| self.block(b)).collect()
+    }
+
+    fn block(&mut self, block: &Block) -> Value {
+        match block {
+            Block::Heading { level, text } => {
+                let id = unique(slugify(text), &mut self.slugs);
+                json!({ "t": "Header", "c": [level, attr(&id, &[]), self.inlines(text)] })
+            }
+            Block::Paragraph(lines) => json!({ "t": "Para", "c": self.lines(lines) }),
+            Block::List(items) => self.list(items),
+            Block::Code { lang, lines } => {
+                let classes: Vec<&str> = lang.split_whitespace().take(1).collect();
+                json!({ "t": "CodeBlock", "c": [attr("", &classes), lines.join("\n")] })
+            }
+            Block::Math(lines) => json!({
+                "t": "Para",
+                "c": [{ "t": "Math", "c": [{ "t": "DisplayMath" }, lines.join("\n")] }],
+            }),
+            Block::Table {
+                aligns,
+                header,
+                rows,
+            } => self.table(aligns, header, rows),
+            Block::Quote(lines) => {
+                json!({ "t": "BlockQuote", "c": self.blocks(&parse(&lines.join("\n")).blocks) })
+            }
+            Block::Admonition { kind, lines } => {
+                let mut title: Vec<char> = kind.chars().collect();
+                title[0] = title[0].to_ascii_uppercase();
+                let title: String = title.into_iter().collect();
+                let mut content = vec![json!({
+                    "t": "Div",
+                    "c": [attr("", &["title"]), [{ "t": "Para", "c": [str_node(&title)] }]],
+                })];
+                content.extend(self.blocks(&parse(&lines.join("\n")).blocks));
+                json!({ "t": "Div", "c": [attr("", &[kind.as_str()]), content] })
+            }
+            Block::Html(lines) => json!({ "t": "RawBlock", "c": ["html", lines.join("\n")] }),
+            Block::Comment(text) => {
+                json!({ "t": "RawBlock", "c": ["html", format!("<!-- {text} -->")] })
+            }
+            Block::Rule => json!({ "t": "HorizontalRule" }),
+            Block::Image { alt, src } => json!({
+                "t": "Para",
+                "c": [{ "t": "Image", "c": [attr("", &[]), self.inlines(alt), [src, ""]] }],
+            }),
+        }
+    }
+
+    /// A (tight) list from flat items: items deeper than the first belong
+    /// to the preceding item as a nested list.
+    fn list(&mut self, items: &[Item]) -> Value {
+        let depth = items[0].depth;
+        let mut entries: Vec<Vec<Value>> = Vec::new();
+        let mut k = 0;
+        while k < items.len() {
+            let mut end = k + 1;
+            while end < items.len() && items[end].depth > depth {
+                end += 1;
+            }
+            let mut content = vec![json!({ "t": "Plain", "c": self.inlines(&items[k].text) })];
+            if end > k + 1 {
+                content.push(self.list(&items[k + 1..end]));
+            }
+            entries.push(content);
+            k = end;
+        }
+        match &items[0].ordered {
+            Some(start) => {
+                let start: u64 = start.parse().unwrap_or(1);
+                json!({
+                    "t": "OrderedList",
+                    "c": [[start, { "t": "Decimal" }, { "t": "Period" }], entries],
+                })
+            }
+            None => json!({ "t": "BulletList", "c": entries }),
+        }
+    }
+
+    fn table(
+        &mut self,
+        aligns: &[Option<&'static str>],
+        header: &[String],
+        rows: &[Vec<String>],
+    ) -> Value {
+        let aligns: Vec<Value> = aligns
+            .iter()
+            .map(|a| {
+                let t = match a {
+                    Some("left") => "AlignLeft",
+                    Some("center") => "AlignCenter",
+                    Some("right") => "AlignRight",
+                    _ => "AlignDefault",
+                };
+                json!({ "t": t })
+            })
+            .collect();
+        let colspecs: Vec<Value> = aligns
+            .iter()
+            .map(|a| json!([a, { "t": "ColWidthDefault" }]))
+            .collect();
+        let mut row = |cells: &[String]| -> Value {
+            let cells: Vec<Value> = cells
+                .iter()
+                .zip(&aligns)
+                .map(|(cell, align)| {
+                    let content = if cell.trim().is_empty() {
+                        json!([])
+                    } else {
+                        json!([{ "t": "Plain", "c": self.inlines(cell) }])
+                    };
+                    json!([attr("", &[]), align, 1, 1, content])
+                })
+                .collect();
+            json!([attr("", &[]), cells])
+        };
+        let head = vec![row(header)];
+        let body: Vec<Value> = rows.iter().map(|r| row(r)).collect();
+        json!({
+            "t": "Table",
+            "c": [
+                attr("", &[]),
+                [null, []],
+                colspecs,
+                [attr("", &[]), head],
+                [[attr("", &[]), 0, [], body]],
+                [attr("", &[]), []],
+            ],
+        })
+    }
+
+    /// Paragraph lines joined by soft breaks.
+    fn lines(&mut self, lines: &[String]) -> Vec<Value> {
+        let mut out = Vec::new();
+        for (k, line) in lines.iter().enumerate() {
+            if k > 0 {
+                out.push(json!({ "t": "SoftBreak" }));
+            }
+            out.extend(self.inlines(line));
+        }
+        out
+    }
+
+    fn inlines(&mut self, text: &str) -> Vec<Value> {
+        let mut out: Vec<Value> = Vec::new();
+        let mut word = String::new();
+        let flush = |word: &mut String, out: &mut Vec<Value>| {
+            if !word.is_empty() {
+                out.push(str_node(word));
+                word.clear();
+            }
+        };
+        for span in spans(text) {
+            let node = match span {
+                Span::Text(c) if c.is_whitespace() => {
+                    flush(&mut word, &mut out);
+                    if out.last().is_some_and(|n| n["t"] != "Space") {
+                        out.push(json!({ "t": "Space" }));
+                    }
+                    continue;
+                }
+                Span::Text(c) => {
+                    word.push(c);
+                    continue;
+                }
+                Span::Code(code) => json!({ "t": "Code", "c": [attr("", &[]), code] }),
+                Span::Math(tex) => json!({ "t": "Math", "c": [{ "t": "InlineMath" }, tex] }),
+                Span::Image { alt, src } => json!({
+                    "t": "Image",
+                    "c": [attr("", &[]), self.inlines(alt), [src, ""]],
+                }),
+                Span::Link { label, url } => json!({
+                    "t": "Link",
+                    "c": [attr("", &[]), self.inlines(label), [url, ""]],
+                }),
+                Span::NoteRef(label) => match self.notes.get(label) {
+                    Some(text) => {
+                        let text = text.clone();
+                        json!({ "t": "Note", "c": [{ "t": "Para", "c": self.inlines(&text) }] })
+                    }
+                    None => str_node(&format!("[^{label}]")),
+                },
+                Span::Strong(inner) => json!({ "t": "Strong", "c": self.inlines(inner) }),
+                Span::Emph(inner) => json!({ "t": "Emph", "c": self.inlines(inner) }),
+                Span::Strike(inner) => json!({ "t": "Strikeout", "c": self.inlines(inner) }),
+                Span::Sup(inner) => json!({ "t": "Superscript", "c": self.inlines(inner) }),
+                Span::Sub(inner) => json!({ "t": "Subscript", "c": self.inlines(inner) }),
+                Span::Break => json!({ "t": "LineBreak" }),
+                Span::Autolink(url) => json!({
+                    "t": "Link",
+                    "c": [attr("", &["uri"]), [str_node(url)], [url, ""]],
+                }),
+                Span::Tag(tag) => json!({ "t": "RawInline", "c": ["html", tag] }),
+            };
+            flush(&mut word, &mut out);
+            out.push(node);
+        }
+        flush(&mut word, &mut out);
+        if out.last().is_some_and(|n| n["t"] == "Space") {
+            out.pop();
+        }
+        out
+    }
+}
+
+/// `[identifier, classes, key-value pairs]`.
+fn attr(id: &str, classes: &[&str]) -> Value {
+    json!([id, classes, []])
+}
+
+fn str_node(text: &str) -> Value {
+    json!({ "t": "Str", "c": text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headings_math_and_notes_are_native_nodes() {
+        let ast = document(
+            "---\ntitle: \"Q3 Report\"\n---\n\n# Results\n\nGrowth was $x^2$ **high**[^1].\n\n## Results\n\n[^1]: Unaudited.\n",
+        );
+        assert_eq!(ast["pandoc-api-version"], json!([1, 23, 1]));
+        assert_eq!(
+            ast["meta"]["title"],
+            json!({ "t": "MetaInlines", "c": [
+                { "t": "Str", "c": "Q3" }, { "t": "Space" }, { "t": "Str", "c": "Report" },
+            ] })
+        );
+        let blocks = ast["blocks"].as_array().unwrap();
+        assert_eq!(
+            blocks[0],
+            json!({ "t": "Header", "c": [1, ["results", [], []], [{ "t": "Str", "c": "Results" }]] })
+        );
+        assert_eq!(blocks[2]["c"][1], json!(["results-1", [], []]));
+        assert_eq!(
+            blocks[1]["c"],
+            json!([
+                { "t": "Str", "c": "Growth" }, { "t": "Space" },
+                { "t": "Str", "c": "was" }, { "t": "Space" },
+                { "t": "Math", "c": [{ "t": "InlineMath" }, "x^2"] }, { "t": "Space" },
+                { "t": "Strong", "c": [{ "t": "Str", "c": "high" }] },
+                { "t": "Note", "c": [{ "t": "Para", "c": [{ "t": "Str", "c": "Unaudited." }] }] },
+                { "t": "Str", "c": "." },
+            ])
+        );
+    }
+
+    #[test]
+    fn tables_and_nested_lists() {
+        let ast = document("| A | B |\n|:--|--:|\n| 1 | |\n\n- a\n  1. b\n- c\n");
+        let table = &ast["blocks"][0];
+        assert_eq!(table["t"], "Table");
+        assert_eq!(
+            table["c"][2],
+            json!([
+                [{ "t": "AlignLeft" }, { "t": "ColWidthDefault" }],
+                [{ "t": "AlignRight" }, { "t": "ColWidthDefault" }],
+            ])
+        );
+        let body_row = &table["c"][4][0][3][0][1];
+        assert_eq!(
+            body_row[0][4],
+            json!([{ "t": "Plain", "c": [{ "t": "Str", "c": "1" }] }])
+        );
+        assert_eq!(body_row[1][4], json!([]));
+
+        let list = &ast["blocks"][1];
+        assert_eq!(list["t"], "BulletList");
+        assert_eq!(list["c"].as_array().unwrap().len(), 2);
+        assert_eq!(list["c"][0][1]["t"], "OrderedList");
+        assert_eq!(list["c"][0][1]["c"][0][0], 1);
+    }
+
+    #[test]
+    fn alerts_become_titled_divs() {
+        let ast = document("> [!TIP]\n> Use `--dpi 200`.\n");
+        assert_eq!(
+            ast["blocks"][0],
+            json!({ "t": "Div", "c": [["", ["tip"], []], [
+                { "t": "Div", "c": [["", ["title"], []], [{ "t": "Para", "c": [{ "t": "Str", "c": "Tip" }] }]] },
+                { "t": "Para", "c": [
+                    { "t": "Str", "c": "Use" }, { "t": "Space" },
+                    { "t": "Code", "c": [["", [], []], "--dpi 200"] }, { "t": "Str", "c": "." },
+                ] },
+            ]] })
+        );
+    }
+}
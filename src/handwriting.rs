@@ -0,0 +1,223 @@
+//! Handwritten documents: a transcription prompt, a higher DPI, and `[?]`.
+//!
+//! ## Why a separate mode?
+//!
+//! The page prompts are written for print. On a handwritten form they ask
+//! for clean, complete Markdown, and a model that can only make out half
+//! the words obliges by writing plausible ones: the answer reads well and
+//! is mostly invented. With [`crate::ContentHint::Handwritten`]:
+//!
+//! 1. [`crate::prompts::HANDWRITING_SYSTEM_PROMPT`] replaces the tier and
+//!    preset prompts. It asks for a literal transcription, with each
+//!    uncertain word followed by `[?]` and each illegible one replaced by it.
+//! 2. Pages render at [`HANDWRITING_DPI`] when `dpi` is left at its default
+//!    of 150, with the pixel cap raised to [`HANDWRITING_MAX_PIXELS`] when
+//!    that is left at its default too; pen strokes lose far more to
+//!    downsampling than type does.
+//! 3. [`mark_uncertain`] rewrites the other markers models use anyway
+//!    (`[illegible]`, `[unclear]`, `(?)`) to `[?]`, so one search finds them.
+//!
+//! With [`crate::ContentHint::Auto`], [`crate::convert`] first sends one
+//! sample page with [`crate::prompts::HANDWRITING_CLASSIFIER_PROMPT`] and
+//! switches to the handwriting mode when the answer is `handwritten` or
+//! `mixed`. The answer is in [`crate::ConversionStats::detected_content`];
+//! the check's tokens are not in the totals. A blank sample page or a failed
+//! call leaves the document treated as printed.
+
+use crate::backend::{DescribeOptions, PagePrompt, VisionBackend};
+use crate::config::{ContentHint, ConversionConfig};
+use crate::pipeline::render;
+use crate::pipeline::session::PdfSession;
+use crate::prompts::HANDWRITING_CLASSIFIER_PROMPT;
+use crate::transcript;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Rendering DPI of handwritten pages when `dpi` is left at its default.
+pub const HANDWRITING_DPI: u32 = 300;
+
+/// `max_rendered_pixels` of handwritten pages when it is left at its
+/// default: a US Letter page at [`HANDWRITING_DPI`] is 3300 px tall.
+pub const HANDWRITING_MAX_PIXELS: u32 = 3300;
+
+/// Output token budget for the classifier's one-word answer.
+const CLASSIFIER_MAX_TOKENS: usize = 10;
+
+/// Uncertainty markers other than `[?]`: `[illegible]`, `[unclear word]`,
+/// `[??]`, `(?)`.
+static UNCERTAIN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)\[(?:illegible|unclear|unreadable|indecipherable)(?:\s+\w+)?\]|\[\?{2,}\]|\(\?\)",
+    )
+    .unwrap()
+});
+
+/// `config` with its content hint settled, or `None` when nothing changes.
+///
+/// [`ContentHint::Auto`] becomes `detected`, or printed without one.
+/// Handwritten documents get [`HANDWRITING_DPI`] unless `dpi` was changed
+/// from its default, and [`HANDWRITING_MAX_PIXELS`] unless
+/// `max_rendered_pixels` was.
+pub(crate) fn settle(
+    config: &ConversionConfig,
+    detected: Option<ContentHint>,
+) -> Option<ConversionConfig> {
+    let hint = match config.content_hint {
+        ContentHint::Auto => detected.unwrap_or(ContentHint::Printed),
+        hint => hint,
+    };
+    if hint == config.content_hint && hint != ContentHint::Handwritten {
+        return None;
+    }
+    let defaults = ConversionConfig::default();
+    let handwritten = hint == ContentHint::Handwritten;
+    let dpi = match config.dpi {
+        dpi if handwritten && dpi == defaults.dpi => HANDWRITING_DPI,
+        dpi => dpi,
+    };
+    let max_rendered_pixels = match config.max_rendered_pixels {
+        px if handwritten && px == defaults.max_rendered_pixels => HANDWRITING_MAX_PIXELS,
+        px => px,
+    };
+    Some(ConversionConfig {
+        content_hint: hint,
+        dpi,
+        max_rendered_pixels,
+        ..config.clone()
+    })
+}
+
+/// Ask `provider` whether the middle selected page is handwritten.
+/// `None` when the page is blank or the check failed.
+pub(crate) async fn detect(
+    session: &PdfSession,
+    provider: &Arc<dyn VisionBackend>,
+    config: &ConversionConfig,
+    total_pages: usize,
+) -> Option<ContentHint> {
+    let indices = config.pages.to_indices(total_pages);
+    let page_index = *indices.get(indices.len() / 2)?;
+    let mut rx = match render::spawn_lazy_render_encode_in(session, config, &[page_index], 1).await
+    {
+        Ok(rx) => rx,
+        Err(e) => {
+            warn!("Handwriting check skipped: {}", e);
+            return None;
+        }
+    };
+    let page = rx.recv().await?;
+    if page.blank || page.render_error.is_some() {
+        warn!(
+            "Handwriting check skipped: page {} is blank or failed to render",
+            page_index + 1
+        );
+        return None;
+    }
+    let prompt = PagePrompt {
+        system: HANDWRITING_CLASSIFIER_PROMPT.to_string(),
+        ..Default::default()
+    };
+    let options = DescribeOptions {
+        temperature: 0.0,
+        max_tokens: CLASSIFIER_MAX_TOKENS,
+        seed: config.seed,
+    };
+    let answer = provider
+        .describe_page(&page.image_data, &prompt, &options)
+        .await;
+    transcript::record(
+        config,
+        page_index + 1,
+        provider.as_ref(),
+        &page.image_data,
+        &prompt,
+        &options,
+        &answer,
+    );
+    let hint = match answer {
+        Ok(completion) => parse_class(&completion.markdown),
+        Err(e) => {
+            warn!("Handwriting check failed: {}", e);
+            return None;
+        }
+    };
+    info!("Handwriting check on page {}: {:?}", page_index + 1, hint);
+    hint
+}
+
+/// The classifier's answer: `handwritten` and `mixed` are handwritten.
+fn parse_class(answer: &str) -> Option<ContentHint> {
+    let word = answer
+        .trim()
+        .trim_matches(|c: char| !c.is_alphabetic())
+        .to_ascii_lowercase();
+    match word.as_str() {
+        "printed" => Some(ContentHint::Printed),
+        "handwritten" | "mixed" => Some(ContentHint::Handwritten),
+        _ => None,
+    }
+}
+
+/// Rewrite `[illegible]`, `[unclear]`, `[??]`, and `(?)` as `[?]`.
+pub fn mark_uncertain(markdown: &str) -> String {
+    UNCERTAIN_RE.replace_all(markdown, "[?]").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uncertainty_markers_are_unified() {
+        assert_eq!(
+            mark_uncertain("Met Tom on [illegible] at 3pm (?) re: [Unclear word] and [??]."),
+            "Met Tom on [?] at 3pm [?] re: [?] and [?]."
+        );
+        assert_eq!(mark_uncertain("Tuesday[?] [x] done"), "Tuesday[?] [x] done");
+    }
+
+    #[test]
+    fn classifier_answers_and_settled_configs() {
+        assert_eq!(parse_class(" Handwritten."), Some(ContentHint::Handwritten));
+        assert_eq!(parse_class("mixed"), Some(ContentHint::Handwritten));
+        assert_eq!(parse_class("printed"), Some(ContentHint::Printed));
+        assert_eq!(parse_class("I cannot tell"), None);
+
+        let printed = ConversionConfig::default();
+        assert!(settle(&printed, None).is_none());
+
+        let auto = ConversionConfig {
+            content_hint: ContentHint::Auto,
+            ..Default::default()
+        };
+        let settled = settle(&auto, Some(ContentHint::Handwritten)).unwrap();
+        assert_eq!(settled.content_hint, ContentHint::Handwritten);
+        assert_eq!(settled.dpi, HANDWRITING_DPI);
+        assert_eq!(settled.max_rendered_pixels, HANDWRITING_MAX_PIXELS);
+        // A US Letter page renders at the full 300 DPI, not the default cap.
+        assert_eq!(
+            render::render_size(
+                612.0,
+                792.0,
+                settled.dpi as f32,
+                settled.max_rendered_pixels,
+                settled.max_rendered_pixels
+            ),
+            (2550, 3300)
+        );
+        let settled = settle(&auto, None).unwrap();
+        assert_eq!(settled.max_rendered_pixels, 2000);
+        assert_eq!(settled.content_hint, ContentHint::Printed);
+
+        let explicit = ConversionConfig {
+            content_hint: ContentHint::Handwritten,
+            dpi: 200,
+            max_rendered_pixels: 1600,
+            ..Default::default()
+        };
+        let settled = settle(&explicit, None).unwrap();
+        assert_eq!((settled.dpi, settled.max_rendered_pixels), (200, 1600));
+    }
+}
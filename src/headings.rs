@@ -0,0 +1,175 @@
+//! Heading index with GitHub-compatible anchors.
+//!
+//! ## Why?
+//!
+//! Navigation UIs (a table of contents, a "jump to section" menu, deep links
+//! into a rendered document) need each heading's anchor. Renderers derive it
+//! from the heading text — `## Results & Discussion` becomes
+//! `#results--discussion` on GitHub — and number repeats in document order
+//! (`#results`, `#results-1`). Pages are converted independently, so only the
+//! assembled document knows which repeat a heading is; the index is built
+//! alongside assembly and stored in [`crate::ConversionOutput::headings`].
+//!
+//! Slugs follow GitHub's rules: inline markup is dropped, the text is
+//! lowercased, punctuation other than `-` and `_` is removed, and each space
+//! becomes `-`. A `## Summary` prepended by [`crate::summary`] takes the
+//! first `summary` slug, as it does in the rendered document.
+
+use crate::output::PageResult;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// `![alt](src)` and `[text](href)`, keeping the text.
+static LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+/// Footnote references and HTML tags, which render without text of their own.
+static DROPPED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[\^[^\]]+\]|<[^>]+>").unwrap());
+
+/// One heading of the assembled document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeadingEntry {
+    /// 1 for `#` through 6 for `######`.
+    pub level: u8,
+    /// Heading text without inline markup, e.g. `"Results & Discussion"`.
+    pub text: String,
+    /// Anchor without the `#`, unique in the document: `"results--discussion"`.
+    pub slug: String,
+    /// 1-based page the heading is on.
+    pub page: usize,
+}
+
+/// GitHub's anchor for a heading's text, before de-duplication.
+pub fn slugify(text: &str) -> String {
+    plain_text(text)
+        .to_lowercase()
+        .chars()
+        .filter(|&c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '))
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+/// Index the ATX headings of the pages that [`crate::convert`] assembles,
+/// in document order. `summary` reserves the slug of a prepended
+/// `## Summary`.
+pub(crate) fn index(pages: &[PageResult], summary: bool) -> Vec<HeadingEntry> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    if summary {
+        unique(slugify("Summary"), &mut seen);
+    }
+    let mut entries = Vec::new();
+    for page in pages
+        .iter()
+        .filter(|p| p.error.is_none() && !p.skipped_blank)
+    {
+        let mut in_fence = false;
+        for line in page.markdown.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                continue;
+            }
+            if in_fence {
+                continue;
+            }
+            let Some((level, raw)) = atx_heading(line) else {
+                continue;
+            };
+            entries.push(HeadingEntry {
+                level,
+                text: plain_text(raw),
+                slug: unique(slugify(raw), &mut seen),
+                page: page.page_num,
+            });
+        }
+    }
+    entries
+}
+
+/// `(level, text)` of an ATX heading line, closing `#`s removed.
+fn atx_heading(line: &str) -> Option<(u8, &str)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let line = &line[indent..];
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    if indent > 3
+        || !(1..=6).contains(&level)
+        || !(rest.is_empty() || rest.starts_with([' ', '\t']))
+    {
+        return None;
+    }
+    let text = rest.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with([' ', '\t']) => stripped.trim_end(),
+        _ => text,
+    };
+    Some((level as u8, text))
+}
+
+/// Heading text as rendered: links and images reduced to their text,
+/// footnote references, HTML tags, and emphasis markers removed.
+fn plain_text(raw: &str) -> String {
+    let text = LINK_RE.replace_all(raw, "$1");
+    let text = DROPPED_RE.replace_all(&text, "");
+    let text: String = text
+        .chars()
+        .filter(|c| !matches!(c, '*' | '`' | '~'))
+        .collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `slug`, or the first free `slug-N` if an earlier heading took it.
+pub(crate) fn unique(slug: String, seen: &mut HashMap<String, usize>) -> String {
+    let mut candidate = slug.clone();
+    while seen.contains_key(&candidate) {
+        let n = seen.get_mut(&slug).expect("the base slug was seen first");
+        *n += 1;
+        candidate = format!("{slug}-{n}");
+    }
+    seen.insert(candidate.clone(), 0);
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_match_github() {
+        assert_eq!(slugify("Results & Discussion"), "results--discussion");
+        assert_eq!(slugify("2.1 The `parse()` API"), "21-the-parse-api");
+        assert_eq!(slugify("See [the docs](https://x.y)"), "see-the-docs");
+        assert_eq!(slugify("Über_Größe"), "über_größe");
+    }
+
+    #[test]
+    fn repeats_are_numbered_across_pages() {
+        let pages = vec![
+            PageResult::new(1, "# Intro\n\n## Results\n\n```\n# not a heading\n```\n"),
+            PageResult::new(2, "## Results ##\n\n### Results 1\n\n#hashtag\n"),
+            PageResult::new(3, "## Results\n"),
+        ];
+        let index = index(&pages, true);
+        let slugs: Vec<&str> = index.iter().map(|h| h.slug.as_str()).collect();
+        assert_eq!(
+            slugs,
+            vec!["intro", "results", "results-1", "results-1-1", "results-2"]
+        );
+        assert_eq!(
+            index[2],
+            HeadingEntry {
+                level: 2,
+                text: "Results".into(),
+                slug: "results-1".into(),
+                page: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn summary_reserves_its_slug() {
+        let pages = vec![PageResult::new(1, "## Summary\n")];
+        assert_eq!(index(&pages, false)[0].slug, "summary");
+        assert_eq!(index(&pages, true)[0].slug, "summary-1");
+    }
+}
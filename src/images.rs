@@ -0,0 +1,264 @@
+//! Page image export: rasterise pages without calling a VLM.
+//!
+//! The conversion pipeline already knows how to open (possibly encrypted)
+//! PDFs, bind pdfium, and cap rendered dimensions to
+//! [`ConversionConfig::max_rendered_pixels`]. Exposing that stage on its own
+//! lets callers build previews, or feed page images to their own models,
+//! without re-implementing the pdfium setup. No API key is required.
+
+use crate::config::{ConversionConfig, PageSelection};
+use crate::error::Pdf2MdError;
+use crate::pipeline::{encode, input, render};
+use image::codecs::jpeg::JpegEncoder;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Render selected pages of a PDF file or URL to images.
+///
+/// Honours `config.dpi`, `config.password`, `config.max_rendered_pixels`,
+/// and `config.download_timeout_secs`; every other field is ignored.
+///
+/// # Returns
+/// `(page_num, image)` pairs in ascending page order, where `page_num` is
+/// 1-indexed like [`crate::output::PageResult::page_num`].
+///
+/// # Errors
+/// Fatal input errors as for [`crate::convert::convert`], plus
+/// [`Pdf2MdError::PageOutOfRange`] when the selection matches no pages.
+///
+/// # Example
+/// ```rust,no_run
+/// use edgequake_pdf2md::{render_pages_to_images, ConversionConfig, PageSelection};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = ConversionConfig::default();
+/// let pages = render_pages_to_images("document.pdf", &config, &PageSelection::Range(1, 3)).await?;
+/// for (page_num, img) in &pages {
+///     println!("page {page_num}: {}x{}", img.width(), img.height());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn render_pages_to_images(
+    input_str: impl AsRef<str>,
+    config: &ConversionConfig,
+    pages: &PageSelection,
+) -> Result<Vec<(usize, DynamicImage)>, Pdf2MdError> {
+    crate::convert::check_local_input(config, input_str.as_ref())?;
+    let resolved = input::resolve_input(input_str.as_ref(), config.download_timeout_secs).await?;
+    let pdf_path = resolved.path().to_path_buf();
+
+    let metadata = render::extract_metadata(&pdf_path, config.password.as_deref()).await?;
+    let page_indices = pages.to_indices(metadata.page_count);
+    if page_indices.is_empty() {
+        return Err(Pdf2MdError::PageOutOfRange {
+            page: 0,
+            total: metadata.page_count,
+        });
+    }
+
+    let rendered = render::render_pages(&pdf_path, config, &page_indices).await?;
+    debug!("Rendered {} page images", rendered.len());
+    Ok(rendered
+        .into_iter()
+        .map(|(idx, img)| (idx + 1, img))
+        .collect())
+}
+
+/// Render selected pages to PNG-encoded bytes.
+///
+/// Same as [`render_pages_to_images`] but returns ready-to-write PNG files,
+/// which is what most preview and storage code actually wants.
+pub async fn render_pages_to_png(
+    input_str: impl AsRef<str>,
+    config: &ConversionConfig,
+    pages: &PageSelection,
+) -> Result<Vec<(usize, Vec<u8>)>, Pdf2MdError> {
+    let images = render_pages_to_images(input_str, config, pages).await?;
+    // PNG compression is CPU-bound; keep it off the async worker threads.
+    tokio::task::spawn_blocking(move || {
+        images
+            .into_iter()
+            .map(|(page_num, img)| {
+                encode::encode_png_bytes(&img)
+                    .map(|bytes| (page_num, bytes))
+                    .map_err(|e| Pdf2MdError::RasterisationFailed {
+                        page: page_num,
+                        detail: format!("PNG encoding failed: {e}"),
+                    })
+            })
+            .collect::<Result<Vec<_>, Pdf2MdError>>()
+    })
+    .await
+    .map_err(|e| Pdf2MdError::Internal(format!("PNG encode task panicked: {}", e)))?
+}
+
+// ── Thumbnails ───────────────────────────────────────────────────────────
+
+/// Encoding used for generated thumbnails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ThumbnailFormat {
+    /// Lossy JPEG with the given quality (1–100). Smallest files; the default.
+    Jpeg { quality: u8 },
+    /// Lossless PNG. Larger, but crisp for line art.
+    Png,
+}
+
+impl Default for ThumbnailFormat {
+    fn default() -> Self {
+        ThumbnailFormat::Jpeg { quality: 80 }
+    }
+}
+
+impl ThumbnailFormat {
+    /// File extension (without the dot) matching this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpeg { .. } => "jpg",
+            ThumbnailFormat::Png => "png",
+        }
+    }
+}
+
+/// Options for [`generate_thumbnails`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThumbnailConfig {
+    /// Longest edge of each thumbnail in pixels. Default: 256.
+    ///
+    /// Pages are rasterised directly at this size rather than rendered large
+    /// and scaled down, so thumbnails of a 500-page document stay cheap.
+    pub max_edge: u32,
+    /// Pages to thumbnail. Default: all.
+    pub pages: PageSelection,
+    /// Output encoding. Default: JPEG, quality 80.
+    pub format: ThumbnailFormat,
+    /// PDF user password for encrypted documents.
+    pub password: Option<String>,
+}
+
+impl Default for ThumbnailConfig {
+    fn default() -> Self {
+        Self {
+            max_edge: 256,
+            pages: PageSelection::All,
+            format: ThumbnailFormat::default(),
+            password: None,
+        }
+    }
+}
+
+/// A small encoded preview of one page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thumbnail {
+    /// 1-indexed page number.
+    pub page_num: usize,
+    /// Pixel width of the encoded image.
+    pub width: u32,
+    /// Pixel height of the encoded image.
+    pub height: u32,
+    /// Encoding of `bytes`.
+    pub format: ThumbnailFormat,
+    /// Encoded image file contents.
+    #[serde(skip)]
+    pub bytes: Vec<u8>,
+}
+
+/// Generate small per-page previews, e.g. for document-management UIs that
+/// show the source page next to its Markdown.
+///
+/// # Example
+/// ```rust,no_run
+/// use edgequake_pdf2md::{generate_thumbnails, ThumbnailConfig};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let thumbs = generate_thumbnails("document.pdf", &ThumbnailConfig::default()).await?;
+/// for t in &thumbs {
+///     std::fs::write(format!("page-{:04}.{}", t.page_num, t.format.extension()), &t.bytes)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn generate_thumbnails(
+    input_str: impl AsRef<str>,
+    thumb_config: &ThumbnailConfig,
+) -> Result<Vec<Thumbnail>, Pdf2MdError> {
+    // Render at the highest DPI so `max_edge` alone sets the thumbnail size.
+    let render_config = ConversionConfig {
+        dpi: 400,
+        max_rendered_pixels: thumb_config.max_edge.max(16),
+        password: thumb_config.password.clone(),
+        ..Default::default()
+    };
+    let images = render_pages_to_images(input_str, &render_config, &thumb_config.pages).await?;
+
+    let format = thumb_config.format;
+    tokio::task::spawn_blocking(move || {
+        images
+            .into_iter()
+            .map(|(page_num, img)| encode_thumbnail(page_num, &img, format))
+            .collect::<Result<Vec<_>, Pdf2MdError>>()
+    })
+    .await
+    .map_err(|e| Pdf2MdError::Internal(format!("Thumbnail task panicked: {}", e)))?
+}
+
+/// Encode one rendered page in the requested thumbnail format.
+fn encode_thumbnail(
+    page_num: usize,
+    img: &DynamicImage,
+    format: ThumbnailFormat,
+) -> Result<Thumbnail, Pdf2MdError> {
+    let bytes = match format {
+        ThumbnailFormat::Png => encode::encode_png_bytes(img),
+        ThumbnailFormat::Jpeg { quality } => {
+            // JPEG has no alpha channel; pdfium renders RGBA.
+            let mut buf = Vec::new();
+            DynamicImage::ImageRgb8(img.to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(
+                    &mut buf,
+                    quality.clamp(1, 100),
+                ))
+                .map(|_| buf)
+        }
+    }
+    .map_err(|e| Pdf2MdError::RasterisationFailed {
+        page: page_num,
+        detail: format!("thumbnail encoding failed: {e}"),
+    })?;
+
+    Ok(Thumbnail {
+        page_num,
+        width: img.width(),
+        height: img.height(),
+        format,
+        bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    fn sample() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(20, 10, Rgba([200, 10, 10, 255])))
+    }
+
+    #[test]
+    fn jpeg_thumbnail_has_jpeg_magic() {
+        let t = encode_thumbnail(3, &sample(), ThumbnailFormat::default()).unwrap();
+        assert_eq!(t.page_num, 3);
+        assert_eq!((t.width, t.height), (20, 10));
+        assert_eq!(&t.bytes[..2], &[0xFF, 0xD8]);
+    }
+
+    #[test]
+    fn png_thumbnail_has_png_magic() {
+        let t = encode_thumbnail(1, &sample(), ThumbnailFormat::Png).unwrap();
+        assert_eq!(&t.bytes[..4], b"\x89PNG");
+        assert_eq!(t.format.extension(), "png");
+    }
+}
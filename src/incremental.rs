@@ -0,0 +1,266 @@
+//! Incremental re-conversion of a revised PDF.
+//!
+//! ## Why incremental?
+//!
+//! Contracts, manuals, and specifications are revised a few pages at a time,
+//! yet a plain [`crate::convert`] pays the model for every page again.
+//! Each [`PageResult`] records an [`image_hash`] of its rendered page, so
+//! [`convert_incremental`] can render the new version, fingerprint its pages
+//! (no model calls), and send only pages whose image matches no converted
+//! page of the previous output:
+//!
+//! ```text
+//! v1.pdf ──convert──▶ old_output (pages + hashes)
+//! v2.pdf ──render+hash──▶ page 1 ✓ reused   page 2 ✗ converted   page 3 ✓ reused
+//! ```
+//!
+//! Pages are matched by hash rather than by number, so a page inserted near
+//! the front does not invalidate every page after it. The hash covers the
+//! rendered pixels, so both runs must use the same render settings (DPI,
+//! pixel cap, image filter) for anything to be reused.
+//!
+//! Reused pages keep their old Markdown and report zero tokens and cost, so
+//! [`crate::ConversionStats`] shows what the revision actually spent. The
+//! summary and enrichment of `old_output` are carried over rather than
+//! regenerated; run [`crate::convert`] for fresh ones. References, being
+//! local, are parsed again over the merged pages.
+//!
+//! [`image_hash`]: crate::pipeline::encode::image_hash
+
+use crate::config::{ConversionConfig, PageSelection};
+use crate::convert::{assemble_document, check_local_input, convert};
+use crate::error::Pdf2MdError;
+use crate::footnotes;
+use crate::headings;
+use crate::output::{ConversionOutput, ConversionStats, PageResult};
+use crate::pii::PiiFinding;
+use crate::pipeline::{input, render};
+use crate::pricing;
+use crate::references;
+use crate::webhook::{self, WebhookPayload};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::Instant;
+use tracing::{debug, info};
+
+/// Convert `new_pdf`, reusing pages of `old_output` whose rendered image is
+/// unchanged.
+///
+/// `old_output` must come from a conversion by this version of the crate
+/// (older outputs carry no hashes, and then every page is converted).
+/// Failed pages of `old_output` are never reused. Sends
+/// [`ConversionConfig::webhook`] once, for the merged result.
+pub async fn convert_incremental(
+    old_output: &ConversionOutput,
+    new_pdf: impl AsRef<str>,
+    config: &ConversionConfig,
+) -> Result<ConversionOutput, Pdf2MdError> {
+    let source = new_pdf.as_ref();
+    let result = run_incremental(old_output, source, config).await;
+    if let Some(ref hook) = config.webhook {
+        let payload = WebhookPayload::for_result(source, &result, hook.include_markdown);
+        webhook::notify(hook, &payload).await;
+    }
+    result
+}
+
+async fn run_incremental(
+    old_output: &ConversionOutput,
+    source: &str,
+    config: &ConversionConfig,
+) -> Result<ConversionOutput, Pdf2MdError> {
+    let start = Instant::now();
+    check_local_input(config, source)?;
+    let resolved = input::resolve_input(source, config.download_timeout_secs).await?;
+    let pdf_path = resolved.path();
+
+    let metadata = render::extract_metadata(pdf_path, config.password.as_deref()).await?;
+    let page_indices = config.pages.to_indices(metadata.page_count);
+    if page_indices.is_empty() {
+        return Err(Pdf2MdError::PageOutOfRange {
+            page: 0,
+            total: metadata.page_count,
+        });
+    }
+    let hashes = page_hashes(pdf_path, config, &page_indices).await?;
+
+    let previous: HashMap<&str, &PageResult> = old_output
+        .pages
+        .iter()
+        .filter(|p| p.error.is_none())
+        .filter_map(|p| p.image_hash.as_deref().map(|h| (h, p)))
+        .collect();
+
+    // New page number → old page number, for every reused page.
+    let mut reused: BTreeMap<usize, usize> = BTreeMap::new();
+    let mut pages = Vec::with_capacity(page_indices.len());
+    let mut changed = Vec::new();
+    for idx in &page_indices {
+        let page_num = idx + 1;
+        let old = hashes.get(&page_num).and_then(|h| previous.get(h.as_str()));
+        match old {
+            Some(old) => {
+                reused.insert(page_num, old.page_num);
+                pages.push(reused_page(old, page_num));
+            }
+            None => changed.push(page_num),
+        }
+    }
+    info!(
+        "Incremental conversion: {} of {} pages unchanged, converting {}",
+        reused.len(),
+        page_indices.len(),
+        changed.len()
+    );
+
+    let fresh = if changed.is_empty() {
+        None
+    } else {
+        let mut partial = config.clone();
+        partial.pages = PageSelection::Set(changed);
+        // Whole-document passes would only see the changed pages, and the
+        // webhook is sent once for the merged result.
+        partial.summary = None;
+        partial.enrichment = None;
+        partial.parse_references = false;
+        partial.webhook = None;
+        Some(convert(pdf_path.to_string_lossy(), &partial).await?)
+    };
+
+    let mut pii_findings: Vec<PiiFinding> = old_output
+        .pii_findings
+        .iter()
+        .flat_map(|f| {
+            reused
+                .iter()
+                .filter(move |(_, old)| **old == f.page)
+                .map(move |(&new, _)| PiiFinding {
+                    page: new,
+                    ..f.clone()
+                })
+        })
+        .collect();
+
+    let (mut stats, fatal_error, reproducibility) = match fresh {
+        Some(fresh) => {
+            pages.extend(fresh.pages);
+            pii_findings.extend(fresh.pii_findings);
+            (fresh.stats, fresh.fatal_error, fresh.reproducibility)
+        }
+        None => (
+            ConversionStats::default(),
+            None,
+            old_output.reproducibility.clone(),
+        ),
+    };
+    pages.sort_by_key(|p| (p.page_num, config.region_rank(p)));
+    pii_findings.sort_by_key(|f| f.page);
+    // Reused pages are already linked; the passes leave them as they are.
+    if config.link_footnotes {
+        footnotes::relabel(&mut pages);
+    }
+    let references = if config.parse_references {
+        references::link_references(&mut pages)
+    } else {
+        Vec::new()
+    };
+
+    let prepended = old_output
+        .summary
+        .as_deref()
+        .filter(|_| config.summary.as_ref().is_some_and(|sc| sc.prepend));
+    let enrichment = old_output.enrichment.clone();
+    let markdown = assemble_document(&pages, config, &metadata, prepended, enrichment.as_ref());
+    let headings = headings::index(&pages, prepended.is_some());
+
+    let blank = pages.iter().filter(|p| p.skipped_blank).count();
+    stats.total_pages = metadata.page_count;
+    stats.processed_pages = pages
+        .iter()
+        .filter(|p| p.error.is_none() && !p.skipped_blank)
+        .count();
+    stats.failed_pages = pages.iter().filter(|p| p.error.is_some()).count();
+    stats.skipped_pages = page_indices.len().saturating_sub(pages.len()) + blank;
+    stats.reused_pages = reused.len();
+    stats.total_input_tokens = pages.iter().map(|p| p.input_tokens as u64).sum();
+    stats.total_image_tokens = pages.iter().map(|p| p.image_tokens as u64).sum();
+    stats.total_output_tokens = pages.iter().map(|p| p.output_tokens as u64).sum();
+    stats.total_cost_usd = pricing::total_cost(pages.iter().map(|p| p.cost_usd));
+    stats.total_duration_ms = start.elapsed().as_millis() as u64;
+
+    Ok(ConversionOutput {
+        markdown,
+        pages,
+        metadata,
+        stats,
+        summary: old_output.summary.clone(),
+        enrichment,
+        pii_findings,
+        references,
+        headings,
+        fatal_error,
+        reproducibility,
+    })
+}
+
+/// Render the selected pages and return each one's image hash by 1-based
+/// page number. Pages that fail to render have no entry, nor do pages with
+/// [`ConversionConfig::page_crops`], which are always converted again.
+async fn page_hashes(
+    pdf_path: &Path,
+    config: &ConversionConfig,
+    page_indices: &[usize],
+) -> Result<HashMap<usize, String>, Pdf2MdError> {
+    let mut rx =
+        render::spawn_lazy_render_encode(pdf_path, config, page_indices, config.concurrency)
+            .await?;
+    let mut hashes = HashMap::with_capacity(page_indices.len());
+    while let Some(page) = rx.recv().await {
+        if let (Some(hash), None) = (page.image_hash, page.region) {
+            hashes.insert(page.page_index + 1, hash);
+        }
+    }
+    debug!("Hashed {} of {} pages", hashes.len(), page_indices.len());
+    Ok(hashes)
+}
+
+/// `old` moved to `page_num`, with the cost of its original conversion
+/// cleared.
+fn reused_page(old: &PageResult, page_num: usize) -> PageResult {
+    PageResult {
+        page_num,
+        input_tokens: 0,
+        image_tokens: 0,
+        output_tokens: 0,
+        duration_ms: 0,
+        retries: 0,
+        cost_usd: None,
+        ..old.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_pages_are_renumbered_and_free() {
+        let old = PageResult {
+            page_num: 2,
+            markdown: "## Terms".into(),
+            input_tokens: 1200,
+            output_tokens: 300,
+            duration_ms: 4000,
+            retries: 1,
+            cost_usd: Some(0.01),
+            image_hash: Some("00ff00ff00ff00ff".into()),
+            ..Default::default()
+        };
+        let page = reused_page(&old, 3);
+        assert_eq!(page.page_num, 3);
+        assert_eq!(page.markdown, "## Terms");
+        assert_eq!(page.image_hash, old.image_hash);
+        assert_eq!((page.input_tokens, page.output_tokens), (0, 0));
+        assert_eq!(page.cost_usd, None);
+    }
+}
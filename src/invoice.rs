@@ -0,0 +1,304 @@
+//! Typed invoice and receipt extraction.
+//!
+//! ## Why a dedicated extractor?
+//!
+//! Invoices are the most common reason to pull data out of a PDF, and the
+//! generic [`crate::extract_structured`] leaves every caller to write the
+//! same schema and the same sanity checks. [`extract_invoice`] fills a fixed
+//! schema ([`Invoice::schema`]) from the document's Markdown and returns a
+//! typed [`Invoice`].
+//!
+//! A schema can say that `total` is a number but not that it is the right
+//! number, and misread digits are the typical failure. [`Invoice::validate`]
+//! adds the arithmetic, within one cent per summed value:
+//!
+//! - each line's `quantity × unit_price` equals its `amount`;
+//! - the line amounts add up to `subtotal` (or, without one, to `total`
+//!   less `tax` and `other_charges`);
+//! - `subtotal + tax + other_charges` equals `total`.
+//!
+//! Checks whose inputs are missing are skipped. Failures go back to the model
+//! with the schema violations for repair (see [`crate::extract`]); an answer
+//! that is still inconsistent after [`crate::ExtractionConfig::repair_attempts`]
+//! is returned as [`Pdf2MdError::SchemaViolation`].
+
+use crate::config::ConversionConfig;
+use crate::error::Pdf2MdError;
+use crate::extract;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Allowed rounding difference per summed amount.
+const CENT: f64 = 0.01;
+
+/// An invoice or receipt. Amounts are in [`Self::currency`], tax included
+/// only where the document says so.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Invoice {
+    /// Seller's name as printed, e.g. `"Acme GmbH"`.
+    pub vendor: Option<String>,
+    /// Buyer's name, when the document names one.
+    pub customer: Option<String>,
+    /// Invoice or receipt number.
+    pub invoice_number: Option<String>,
+    /// Issue date, `YYYY-MM-DD`.
+    pub date: Option<String>,
+    /// Payment due date, `YYYY-MM-DD`.
+    pub due_date: Option<String>,
+    /// ISO 4217 code such as `"EUR"`.
+    pub currency: Option<String>,
+    /// Billed items, in document order.
+    pub line_items: Vec<LineItem>,
+    /// Sum of the line items before tax.
+    pub subtotal: Option<f64>,
+    /// Total tax.
+    pub tax: Option<f64>,
+    /// Shipping, fees, and discounts (negative) outside the line items.
+    pub other_charges: Option<f64>,
+    /// Amount due.
+    pub total: Option<f64>,
+}
+
+/// One billed item of an [`Invoice`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LineItem {
+    /// Item text as printed.
+    pub description: String,
+    /// Units billed.
+    pub quantity: Option<f64>,
+    /// Price per unit.
+    pub unit_price: Option<f64>,
+    /// Line total as printed.
+    pub amount: Option<f64>,
+}
+
+impl Invoice {
+    /// The JSON Schema sent to the model.
+    pub fn schema() -> Value {
+        let money = json!({ "type": ["number", "null"] });
+        let text = json!({ "type": ["string", "null"] });
+        json!({
+            "type": "object",
+            "required": ["vendor", "date", "currency", "line_items", "total"],
+            "additionalProperties": false,
+            "properties": {
+                "vendor": text,
+                "customer": text,
+                "invoice_number": text,
+                "date": { "type": ["string", "null"], "description": "issue date, YYYY-MM-DD" },
+                "due_date": { "type": ["string", "null"], "description": "YYYY-MM-DD" },
+                "currency": {
+                    "type": ["string", "null"],
+                    "minLength": 3,
+                    "maxLength": 3,
+                    "description": "ISO 4217 code, e.g. EUR"
+                },
+                "line_items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["description", "amount"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "description": { "type": "string" },
+                            "quantity": money,
+                            "unit_price": money,
+                            "amount": money
+                        }
+                    }
+                },
+                "subtotal": money,
+                "tax": money,
+                "other_charges": {
+                    "type": ["number", "null"],
+                    "description": "shipping, fees, and discounts (negative) outside the line items"
+                },
+                "total": money
+            }
+        })
+    }
+
+    /// Cross-field checks the schema cannot express; empty when the invoice
+    /// is consistent. See the module docs.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (name, date) in [("date", &self.date), ("due_date", &self.due_date)] {
+            if let Some(date) = date {
+                if !is_iso_date(date) {
+                    errors.push(format!("/{name}: expected YYYY-MM-DD, got \"{date}\""));
+                }
+            }
+        }
+        if let Some(ref code) = self.currency {
+            if !(code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase())) {
+                errors.push(format!(
+                    "/currency: expected an ISO 4217 code such as EUR, got \"{code}\""
+                ));
+            }
+        }
+
+        for (i, item) in self.line_items.iter().enumerate() {
+            if let (Some(q), Some(p), Some(a)) = (item.quantity, item.unit_price, item.amount) {
+                if !close(q * p, a, 1) {
+                    errors.push(format!(
+                        "/line_items/{i}: quantity {q} × unit_price {p} = {:.2}, but amount is {a}",
+                        q * p
+                    ));
+                }
+            }
+        }
+
+        let amounts: Option<Vec<f64>> = self.line_items.iter().map(|l| l.amount).collect();
+        let lines_sum = amounts
+            .filter(|a| !a.is_empty())
+            .map(|a| (a.iter().sum::<f64>(), a.len()));
+        let extras = self.tax.unwrap_or(0.0) + self.other_charges.unwrap_or(0.0);
+        match (lines_sum, self.subtotal, self.total) {
+            (Some((sum, n)), Some(subtotal), _) if !close(sum, subtotal, n) => {
+                errors.push(format!(
+                    "/subtotal: line items add up to {sum:.2}, but subtotal is {subtotal}"
+                ));
+            }
+            (Some((sum, n)), None, Some(total)) if !close(sum + extras, total, n + 2) => {
+                errors.push(format!(
+                    "/total: line items plus tax and other charges add up to {:.2}, but total is {total}",
+                    sum + extras
+                ));
+            }
+            _ => {}
+        }
+        if let (Some(subtotal), Some(total)) = (self.subtotal, self.total) {
+            if !close(subtotal + extras, total, 3) {
+                errors.push(format!(
+                    "/total: subtotal plus tax and other charges is {:.2}, but total is {total}",
+                    subtotal + extras
+                ));
+            }
+        }
+        errors
+    }
+}
+
+/// Extract an [`Invoice`] from the PDF at `input`.
+///
+/// Always reads the whole document ([`crate::ExtractionScope::Document`]);
+/// [`crate::ConversionConfig::extraction`] supplies the repair attempts and
+/// the size limit. [`crate::DocumentPreset::Invoice`] is a good match for
+/// the conversion settings.
+pub async fn extract_invoice(
+    input: impl AsRef<str>,
+    config: &ConversionConfig,
+) -> Result<Invoice, Pdf2MdError> {
+    let value =
+        extract::extract_document(input.as_ref(), &Invoice::schema(), config, &invoice_rules)
+            .await?;
+    serde_json::from_value(value)
+        .map_err(|e| Pdf2MdError::Internal(format!("validated invoice did not deserialize: {e}")))
+}
+
+fn invoice_rules(value: &Value) -> Vec<String> {
+    match Invoice::deserialize(value) {
+        Ok(invoice) => invoice.validate(),
+        Err(e) => vec![format!("/: {e}")],
+    }
+}
+
+/// `a` and `b` differ by at most one cent per summed value.
+fn close(a: f64, b: f64, terms: usize) -> bool {
+    (a - b).abs() <= CENT * terms.max(1) as f64 + 1e-9
+}
+
+fn is_iso_date(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() == 10
+        && b.iter().enumerate().all(|(i, c)| match i {
+            4 | 7 => *c == b'-',
+            _ => c.is_ascii_digit(),
+        })
+        && (1..=12).contains(&s[5..7].parse::<u32>().unwrap_or(0))
+        && (1..=31).contains(&s[8..10].parse::<u32>().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invoice() -> Invoice {
+        Invoice {
+            vendor: Some("Acme GmbH".into()),
+            date: Some("2024-03-01".into()),
+            currency: Some("EUR".into()),
+            line_items: vec![
+                LineItem {
+                    description: "Widget".into(),
+                    quantity: Some(3.0),
+                    unit_price: Some(19.99),
+                    amount: Some(59.97),
+                },
+                LineItem {
+                    description: "Setup".into(),
+                    quantity: None,
+                    unit_price: None,
+                    amount: Some(40.0),
+                },
+            ],
+            subtotal: Some(99.97),
+            tax: Some(19.0),
+            other_charges: Some(-4.97),
+            total: Some(114.0),
+            ..Invoice::default()
+        }
+    }
+
+    #[test]
+    fn consistent_invoice_passes() {
+        assert!(invoice().validate().is_empty());
+        // Without a subtotal the lines are checked against the total.
+        let inv = Invoice {
+            subtotal: None,
+            ..invoice()
+        };
+        assert!(inv.validate().is_empty());
+    }
+
+    #[test]
+    fn arithmetic_errors_are_reported() {
+        let mut inv = invoice();
+        inv.line_items[0].amount = Some(59.79);
+        inv.total = Some(141.0);
+        assert_eq!(
+            inv.validate(),
+            vec![
+                "/line_items/0: quantity 3 × unit_price 19.99 = 59.97, but amount is 59.79",
+                "/subtotal: line items add up to 99.79, but subtotal is 99.97",
+                "/total: subtotal plus tax and other charges is 114.00, but total is 141",
+            ]
+        );
+    }
+
+    #[test]
+    fn dates_and_currency_are_checked() {
+        let inv = Invoice {
+            date: Some("01/03/2024".into()),
+            currency: Some("€".into()),
+            line_items: Vec::new(),
+            ..invoice()
+        };
+        assert_eq!(
+            inv.validate(),
+            vec![
+                "/date: expected YYYY-MM-DD, got \"01/03/2024\"",
+                "/currency: expected an ISO 4217 code such as EUR, got \"€\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_accepts_a_model_answer() {
+        let answer = serde_json::to_value(invoice()).unwrap();
+        assert!(extract::validate(&answer, &Invoice::schema()).is_empty());
+        assert!(invoice_rules(&answer).is_empty());
+    }
+}
@@ -0,0 +1,493 @@
+//! Background conversion jobs with IDs, status, and stored results.
+//!
+//! ## Why a job manager?
+//!
+//! An application server cannot hold an HTTP request open for the minutes a
+//! long document takes. It wants to accept the document, hand back an ID,
+//! and let clients poll (or get a [`crate::webhook`]) later. [`JobManager`]
+//! is that layer: [`submit`](JobManager::submit) spawns the conversion on
+//! the Tokio runtime and returns a [`JobId`]; [`status`](JobManager::status),
+//! [`cancel`](JobManager::cancel), and [`result`](JobManager::result) look
+//! the job up again.
+//!
+//! ```rust,no_run
+//! use edgequake_pdf2md::{ConversionConfig, JobManager, JobStatus};
+//!
+//! # async fn run() -> Result<(), edgequake_pdf2md::Pdf2MdError> {
+//! let jobs = JobManager::in_memory().max_concurrent(4);
+//! let id = jobs.submit("report.pdf", ConversionConfig::default())?;
+//! // … later, from another request handler:
+//! if let Some(JobStatus::Completed) = jobs.status(&id)? {
+//!     let output = jobs.result(&id)?.expect("completed jobs keep their output");
+//!     println!("{}", output.markdown);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Job records and outputs live in a [`JobStore`]. [`MemoryJobStore`] is
+//! the default; with the `sqlite` feature, [`SqliteJobStore`] keeps them in
+//! a database file so finished results survive a restart. Jobs that were
+//! still queued or running when the previous process stopped are marked
+//! failed when a manager opens the store, since their tasks are gone.
+
+use crate::config::ConversionConfig;
+use crate::convert::convert;
+use crate::error::Pdf2MdError;
+use crate::output::ConversionOutput;
+use crate::progress::ConversionProgressCallback;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tokio::task::AbortHandle;
+use tracing::warn;
+
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteJobStore;
+
+/// Opaque, unique identifier of a submitted job.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct JobId(String);
+
+impl JobId {
+    /// A fresh ID: submission time plus a per-process counter, in hex.
+    fn generate() -> Self {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        JobId(format!("{:x}-{:x}", now_ms(), n))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for JobId {
+    fn from(s: String) -> Self {
+        JobId(s)
+    }
+}
+
+impl fmt::Display for JobId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Where a job is in its lifecycle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a free slot (see [`JobManager::max_concurrent`]).
+    Queued,
+    /// Converting; `total_pages` is 0 until the PDF has been opened.
+    Running {
+        pages_done: usize,
+        total_pages: usize,
+    },
+    /// Finished; [`JobManager::result`] returns the output.
+    Completed,
+    /// The conversion returned an error.
+    Failed { error: String },
+    /// Stopped by [`JobManager::cancel`].
+    Cancelled,
+}
+
+impl JobStatus {
+    /// `true` once the job can no longer change.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed { .. } | JobStatus::Cancelled
+        )
+    }
+}
+
+/// What a [`JobStore`] keeps per job, besides the output.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: JobId,
+    /// The path or URL passed to [`JobManager::submit`].
+    pub input: String,
+    pub status: JobStatus,
+    /// Unix time in milliseconds.
+    pub submitted_at_ms: u64,
+    /// Unix time in milliseconds; set once the status is terminal.
+    pub finished_at_ms: Option<u64>,
+}
+
+/// Persistence for job records and outputs.
+///
+/// Calls are short and synchronous; implementations must be `Send + Sync`
+/// because jobs update their records from Tokio tasks.
+pub trait JobStore: Send + Sync {
+    /// Insert or replace a record.
+    fn put(&self, record: &JobRecord) -> Result<(), Pdf2MdError>;
+    fn get(&self, id: &JobId) -> Result<Option<JobRecord>, Pdf2MdError>;
+    /// Every record, in no particular order.
+    fn list(&self) -> Result<Vec<JobRecord>, Pdf2MdError>;
+    fn put_output(&self, id: &JobId, output: &ConversionOutput) -> Result<(), Pdf2MdError>;
+    fn output(&self, id: &JobId) -> Result<Option<ConversionOutput>, Pdf2MdError>;
+}
+
+/// Keeps everything in process memory; lost on restart.
+#[derive(Debug, Default)]
+pub struct MemoryJobStore {
+    records: Mutex<HashMap<JobId, JobRecord>>,
+    outputs: Mutex<HashMap<JobId, ConversionOutput>>,
+}
+
+fn poisoned<T>(_: T) -> Pdf2MdError {
+    Pdf2MdError::Internal("job store lock poisoned".into())
+}
+
+impl JobStore for MemoryJobStore {
+    fn put(&self, record: &JobRecord) -> Result<(), Pdf2MdError> {
+        self.records
+            .lock()
+            .map_err(poisoned)?
+            .insert(record.id.clone(), record.clone());
+        Ok(())
+    }
+
+    fn get(&self, id: &JobId) -> Result<Option<JobRecord>, Pdf2MdError> {
+        Ok(self.records.lock().map_err(poisoned)?.get(id).cloned())
+    }
+
+    fn list(&self) -> Result<Vec<JobRecord>, Pdf2MdError> {
+        Ok(self
+            .records
+            .lock()
+            .map_err(poisoned)?
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    fn put_output(&self, id: &JobId, output: &ConversionOutput) -> Result<(), Pdf2MdError> {
+        self.outputs
+            .lock()
+            .map_err(poisoned)?
+            .insert(id.clone(), output.clone());
+        Ok(())
+    }
+
+    fn output(&self, id: &JobId) -> Result<Option<ConversionOutput>, Pdf2MdError> {
+        Ok(self.outputs.lock().map_err(poisoned)?.get(id).cloned())
+    }
+}
+
+/// Runs conversions in the background and tracks them in a [`JobStore`].
+pub struct JobManager {
+    store: Arc<dyn JobStore>,
+    slots: Option<Arc<Semaphore>>,
+    running: Arc<Mutex<HashMap<JobId, AbortHandle>>>,
+}
+
+impl JobManager {
+    /// A manager over `store`. Jobs left queued or running by a previous
+    /// process are marked failed.
+    pub fn new(store: Arc<dyn JobStore>) -> Result<Self, Pdf2MdError> {
+        for mut record in store.list()? {
+            if !record.status.is_terminal() {
+                record.status = JobStatus::Failed {
+                    error: "interrupted: the process running this job stopped".into(),
+                };
+                record.finished_at_ms = Some(now_ms());
+                store.put(&record)?;
+            }
+        }
+        Ok(Self {
+            store,
+            slots: None,
+            running: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// A manager over a fresh [`MemoryJobStore`].
+    pub fn in_memory() -> Self {
+        Self {
+            store: Arc::new(MemoryJobStore::default()),
+            slots: None,
+            running: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Run at most `n` jobs at once; the rest wait as [`JobStatus::Queued`].
+    /// Default: unlimited. Each job still uses its config's `concurrency`.
+    pub fn max_concurrent(mut self, n: usize) -> Self {
+        self.slots = Some(Arc::new(Semaphore::new(n.max(1))));
+        self
+    }
+
+    /// Start converting `input` in the background. Must be called from
+    /// within a Tokio runtime.
+    pub fn submit(
+        &self,
+        input: impl Into<String>,
+        mut config: ConversionConfig,
+    ) -> Result<JobId, Pdf2MdError> {
+        let record = JobRecord {
+            id: JobId::generate(),
+            input: input.into(),
+            status: JobStatus::Queued,
+            submitted_at_ms: now_ms(),
+            finished_at_ms: None,
+        };
+        self.store.put(&record)?;
+        let id = record.id.clone();
+
+        config.progress_callback = Some(Arc::new(JobProgress {
+            store: Arc::clone(&self.store),
+            record: Mutex::new(record.clone()),
+            pages_done: AtomicUsize::new(0),
+            inner: config.progress_callback.take(),
+        }));
+        let store = Arc::clone(&self.store);
+        let slots = self.slots.clone();
+        let running = Arc::clone(&self.running);
+        let task_id = id.clone();
+
+        // Hold the lock across spawn so the task cannot finish (and remove
+        // itself) before its handle is registered.
+        let mut handles = self.running.lock().map_err(poisoned)?;
+        let task = tokio::spawn(async move {
+            let _permit = match slots {
+                Some(s) => s.acquire_owned().await.ok(),
+                None => None,
+            };
+            let mut record = record;
+            record.status = JobStatus::Running {
+                pages_done: 0,
+                total_pages: 0,
+            };
+            log_store_error(store.put(&record));
+
+            let result = convert(&record.input, &config).await;
+            if let Ok(Some(current)) = store.get(&record.id) {
+                if current.status == JobStatus::Cancelled {
+                    return;
+                }
+            }
+            record.status = match result {
+                Ok(output) => match store.put_output(&record.id, &output) {
+                    Ok(()) => JobStatus::Completed,
+                    Err(e) => JobStatus::Failed {
+                        error: format!("output could not be stored: {e}"),
+                    },
+                },
+                Err(e) => JobStatus::Failed {
+                    error: e.to_string(),
+                },
+            };
+            record.finished_at_ms = Some(now_ms());
+            log_store_error(store.put(&record));
+            if let Ok(mut handles) = running.lock() {
+                handles.remove(&task_id);
+            }
+        });
+        handles.insert(id.clone(), task.abort_handle());
+        Ok(id)
+    }
+
+    /// The job's status, or `None` for an unknown ID.
+    pub fn status(&self, id: &JobId) -> Result<Option<JobStatus>, Pdf2MdError> {
+        Ok(self.store.get(id)?.map(|r| r.status))
+    }
+
+    /// The full record of a job, or `None` for an unknown ID.
+    pub fn record(&self, id: &JobId) -> Result<Option<JobRecord>, Pdf2MdError> {
+        self.store.get(id)
+    }
+
+    /// Stop a queued or running job. Returns `false` if the job is unknown
+    /// or already finished.
+    pub fn cancel(&self, id: &JobId) -> Result<bool, Pdf2MdError> {
+        let Some(handle) = self.running.lock().map_err(poisoned)?.remove(id) else {
+            return Ok(false);
+        };
+        handle.abort();
+        let Some(mut record) = self.store.get(id)? else {
+            return Ok(false);
+        };
+        if record.status.is_terminal() {
+            return Ok(false);
+        }
+        record.status = JobStatus::Cancelled;
+        record.finished_at_ms = Some(now_ms());
+        self.store.put(&record)?;
+        Ok(true)
+    }
+
+    /// The output of a [`JobStatus::Completed`] job, else `None`.
+    pub fn result(&self, id: &JobId) -> Result<Option<ConversionOutput>, Pdf2MdError> {
+        self.store.output(id)
+    }
+}
+
+impl fmt::Debug for JobManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JobManager")
+            .field("max_concurrent", &self.slots.as_ref().map(|_| "<limited>"))
+            .field(
+                "running",
+                &self.running.lock().map(|r| r.len()).unwrap_or_default(),
+            )
+            .finish()
+    }
+}
+
+/// Updates the job's `Running` progress, then forwards to the caller's
+/// own callback, if any.
+struct JobProgress {
+    store: Arc<dyn JobStore>,
+    record: Mutex<JobRecord>,
+    pages_done: AtomicUsize,
+    inner: Option<Arc<dyn ConversionProgressCallback>>,
+}
+
+impl JobProgress {
+    fn update(&self, total_pages: usize) {
+        let pages_done = self.pages_done.load(Ordering::Relaxed);
+        if let Ok(mut record) = self.record.lock() {
+            record.status = JobStatus::Running {
+                pages_done,
+                total_pages,
+            };
+            log_store_error(self.store.put(&record));
+        }
+    }
+}
+
+impl ConversionProgressCallback for JobProgress {
+    fn on_conversion_start(&self, total_pages: usize) {
+        self.update(total_pages);
+        if let Some(ref cb) = self.inner {
+            cb.on_conversion_start(total_pages);
+        }
+    }
+
+    fn on_page_start(&self, page_num: usize, total_pages: usize) {
+        if let Some(ref cb) = self.inner {
+            cb.on_page_start(page_num, total_pages);
+        }
+    }
+
+    fn on_page_complete(&self, page_num: usize, total_pages: usize, markdown_len: usize) {
+        self.pages_done.fetch_add(1, Ordering::Relaxed);
+        self.update(total_pages);
+        if let Some(ref cb) = self.inner {
+            cb.on_page_complete(page_num, total_pages, markdown_len);
+        }
+    }
+
+    fn on_page_error(&self, page_num: usize, total_pages: usize, error: String) {
+        self.pages_done.fetch_add(1, Ordering::Relaxed);
+        self.update(total_pages);
+        if let Some(ref cb) = self.inner {
+            cb.on_page_error(page_num, total_pages, error);
+        }
+    }
+
+    fn on_conversion_complete(&self, total_pages: usize, success_count: usize) {
+        if let Some(ref cb) = self.inner {
+            cb.on_conversion_complete(total_pages, success_count);
+        }
+    }
+}
+
+fn log_store_error(result: Result<(), Pdf2MdError>) {
+    if let Err(e) = result {
+        warn!("Job store update failed: {}", e);
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn wait_for_terminal(jobs: &JobManager, id: &JobId) -> JobStatus {
+        for _ in 0..200 {
+            let status = jobs.status(id).unwrap().expect("known job");
+            if status.is_terminal() {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {id} did not finish");
+    }
+
+    #[tokio::test]
+    async fn failed_job_records_the_error() {
+        let jobs = JobManager::in_memory();
+        let id = jobs
+            .submit("/nonexistent/missing.pdf", ConversionConfig::default())
+            .unwrap();
+        let status = wait_for_terminal(&jobs, &id).await;
+        assert!(matches!(status, JobStatus::Failed { .. }), "{status:?}");
+        assert!(jobs.result(&id).unwrap().is_none());
+        assert!(!jobs.cancel(&id).unwrap());
+        assert_eq!(jobs.status(&JobId::from("nope".to_string())).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn cancel_stops_a_running_job() {
+        // A server that accepts the download and never answers.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _server = tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let jobs = JobManager::in_memory();
+        let id = jobs
+            .submit(
+                format!("http://{addr}/slow.pdf"),
+                ConversionConfig::default(),
+            )
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(jobs.cancel(&id).unwrap());
+        assert_eq!(jobs.status(&id).unwrap(), Some(JobStatus::Cancelled));
+        assert!(jobs.record(&id).unwrap().unwrap().finished_at_ms.is_some());
+    }
+
+    #[test]
+    fn interrupted_jobs_are_failed_on_open() {
+        let store = Arc::new(MemoryJobStore::default());
+        let record = JobRecord {
+            id: JobId::generate(),
+            input: "a.pdf".into(),
+            status: JobStatus::Running {
+                pages_done: 3,
+                total_pages: 9,
+            },
+            submitted_at_ms: 1,
+            finished_at_ms: None,
+        };
+        store.put(&record).unwrap();
+        let jobs = JobManager::new(store).unwrap();
+        assert!(matches!(
+            jobs.status(&record.id).unwrap(),
+            Some(JobStatus::Failed { .. })
+        ));
+    }
+}
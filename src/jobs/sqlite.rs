@@ -0,0 +1,146 @@
+//! [`JobStore`] in a SQLite database file (feature `sqlite`).
+
+use super::{JobId, JobRecord, JobStore};
+use crate::error::Pdf2MdError;
+use crate::output::ConversionOutput;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS pdf2md_jobs (
+    id     TEXT PRIMARY KEY,
+    record TEXT NOT NULL,
+    output TEXT
+)";
+
+/// Keeps job records and outputs as JSON in a `pdf2md_jobs` table.
+///
+/// The table is created on open, so the file may be shared with the host's
+/// own tables.
+#[derive(Debug)]
+pub struct SqliteJobStore {
+    conn: Mutex<Connection>,
+}
+
+fn db_error(e: impl std::fmt::Display) -> Pdf2MdError {
+    Pdf2MdError::Internal(format!("job store: {e}"))
+}
+
+impl SqliteJobStore {
+    /// Open (or create) the database at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Pdf2MdError> {
+        Self::from_connection(Connection::open(path).map_err(db_error)?)
+    }
+
+    /// A throwaway in-memory database, for tests.
+    pub fn open_in_memory() -> Result<Self, Pdf2MdError> {
+        Self::from_connection(Connection::open_in_memory().map_err(db_error)?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self, Pdf2MdError> {
+        conn.execute(SCHEMA, []).map_err(db_error)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn with<T>(
+        &self,
+        f: impl FnOnce(&Connection) -> rusqlite::Result<T>,
+    ) -> Result<T, Pdf2MdError> {
+        let conn = self.conn.lock().map_err(super::poisoned)?;
+        f(&conn).map_err(db_error)
+    }
+}
+
+impl JobStore for SqliteJobStore {
+    fn put(&self, record: &JobRecord) -> Result<(), Pdf2MdError> {
+        let json = serde_json::to_string(record).map_err(db_error)?;
+        self.with(|c| {
+            c.execute(
+                "INSERT INTO pdf2md_jobs (id, record) VALUES (?1, ?2)
+                 ON CONFLICT(id) DO UPDATE SET record = excluded.record",
+                params![record.id.as_str(), json],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn get(&self, id: &JobId) -> Result<Option<JobRecord>, Pdf2MdError> {
+        let json: Option<String> = self.with(|c| {
+            c.query_row(
+                "SELECT record FROM pdf2md_jobs WHERE id = ?1",
+                params![id.as_str()],
+                |row| row.get(0),
+            )
+            .optional()
+        })?;
+        json.map(|j| serde_json::from_str(&j).map_err(db_error))
+            .transpose()
+    }
+
+    fn list(&self) -> Result<Vec<JobRecord>, Pdf2MdError> {
+        let rows: Vec<String> = self.with(|c| {
+            let mut stmt = c.prepare("SELECT record FROM pdf2md_jobs")?;
+            let rows = stmt.query_map([], |row| row.get(0))?;
+            rows.collect()
+        })?;
+        rows.iter()
+            .map(|j| serde_json::from_str(j).map_err(db_error))
+            .collect()
+    }
+
+    fn put_output(&self, id: &JobId, output: &ConversionOutput) -> Result<(), Pdf2MdError> {
+        let json = serde_json::to_string(output).map_err(db_error)?;
+        self.with(|c| {
+            c.execute(
+                "UPDATE pdf2md_jobs SET output = ?2 WHERE id = ?1",
+                params![id.as_str(), json],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn output(&self, id: &JobId) -> Result<Option<ConversionOutput>, Pdf2MdError> {
+        let json: Option<String> = self.with(|c| {
+            c.query_row(
+                "SELECT output FROM pdf2md_jobs WHERE id = ?1",
+                params![id.as_str()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map(Option::flatten)
+        })?;
+        json.map(|j| serde_json::from_str(&j).map_err(db_error))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jobs::JobStatus;
+
+    #[test]
+    fn records_round_trip_and_update() {
+        let store = SqliteJobStore::open_in_memory().unwrap();
+        let mut record = JobRecord {
+            id: JobId::from("job-1".to_string()),
+            input: "a.pdf".into(),
+            status: JobStatus::Queued,
+            submitted_at_ms: 5,
+            finished_at_ms: None,
+        };
+        store.put(&record).unwrap();
+        record.status = JobStatus::Completed;
+        store.put(&record).unwrap();
+
+        assert_eq!(store.get(&record.id).unwrap(), Some(record.clone()));
+        assert_eq!(store.list().unwrap().len(), 1);
+        assert!(store.output(&record.id).unwrap().is_none());
+        assert!(store
+            .get(&JobId::from("other".to_string()))
+            .unwrap()
+            .is_none());
+    }
+}
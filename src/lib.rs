@@ -17,7 +17,7 @@
 //!  │
 //!  ├─ 1. Input   resolve local file or download from URL
 //!  ├─ 2. Render  rasterise pages via pdfium (CPU-bound, spawn_blocking)
-//!  ├─ 3. Encode  PNG → base64 ImageData
+//!  ├─ 3. Encode  PNG → base64 PageImage
 //!  ├─ 4. VLM     concurrent calls to gpt-4.1-nano / claude / gemini / …
 //!  ├─ 5. Polish  10-rule post-processing (fences, tables, whitespace)
 //!  └─ 6. Output  assembled Markdown + per-page stats
@@ -46,6 +46,10 @@
 //! | Feature | Default | Description |
 //! |---------|---------|-------------|
 //! | `cli`   | on      | Enables the `pdf2md` binary (clap + anyhow + tracing-subscriber) |
+//! | `otel`  | off     | Forward a parent OpenTelemetry trace context into VLM requests |
+//! | `testkit` | off   | Mock VLM provider and golden-file fixtures for offline pipeline tests |
+//! | `python` | off    | `pdf2md` Python extension module (PyO3), built with maturin |
+//! | `server` | off    | HTTP API with SSE progress ([`server`], `pdf2md serve`) |
 //!
 //! Disable `cli` when using only the library to avoid pulling in CLI-only deps:
 //! ```toml
@@ -66,22 +70,131 @@
 
 // ── Modules ──────────────────────────────────────────────────────────────
 
+pub mod attachments;
+pub mod auto_dpi;
+pub mod backend;
+pub mod charts;
 pub mod config;
+pub mod config_file;
 pub mod convert;
+pub mod diff;
+pub mod enrich;
 pub mod error;
+pub mod eval;
+pub mod event_log;
+pub mod extract;
+pub mod figures;
+pub mod footnotes;
+pub mod formats;
+pub mod handwriting;
+pub mod headings;
+pub mod images;
+pub mod incremental;
+pub mod invoice;
+pub mod jobs;
+pub mod mermaid;
+pub mod metrics;
+pub mod niche;
+pub mod openrouter;
+#[cfg(feature = "otel")]
+pub mod otel;
 pub mod output;
+pub mod page_kind;
+pub mod pii;
 pub mod pipeline;
+pub mod polish;
+pub mod presets;
+pub mod pricing;
 pub mod progress;
 pub mod prompts;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod references;
+pub mod report;
+pub mod reproducibility;
+pub mod resolver;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod signatures;
+pub mod split;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 pub mod stream;
+pub mod summary;
+pub mod tagged;
+#[cfg(feature = "testkit")]
+pub mod testkit;
+pub mod toc;
+pub mod transcript;
+pub mod vault;
+pub mod webhook;
 
 // ── Re-exports ───────────────────────────────────────────────────────────
 
+pub use attachments::Attachment;
+pub use auto_dpi::{AutoDpiConfig, DpiCalibration, DpiProbe};
+pub use backend::{FewShotExample, LlmProviderBackend, VisionBackend};
+pub use charts::{ChartData, ChartKind};
 pub use config::{
-    ConversionConfig, ConversionConfigBuilder, FidelityTier, PageSelection, PageSeparator,
+    ContentHint, ConversionConfig, ConversionConfigBuilder, CropRect, FidelityTier, FilterDecision,
+    ImageFilter, ImageLinkPolicy, MarkdownFlavor, OutputFilter, PageSelection, PageSeparator,
+    ProviderCredentials, PunctuationStyle, RenderLayers, RenderOptions, Strictness, TocHandling,
+    UnicodePolicy,
 };
-pub use convert::{convert, convert_from_bytes, convert_sync, convert_to_file, inspect};
+pub use config_file::{ConfigFile, ConfigProfile};
+pub use convert::{
+    convert, convert_from_bytes, convert_sync, convert_to_file, convert_to_layout,
+    convert_with_context, convert_with_debug_report, inspect,
+};
+pub use diff::{diff_outputs, DocumentDiff};
+pub use enrich::{Enrichment, EnrichmentConfig, Entity};
 pub use error::{PageError, Pdf2MdError};
-pub use output::{ConversionOutput, ConversionStats, DocumentMetadata, PageResult};
+pub use eval::{evaluate, EvalReport};
+pub use event_log::{EventLog, RunEvent};
+pub use extract::{extract_structured, ExtractionConfig, ExtractionScope};
+pub use figures::Figure;
+pub use formats::{convert_to_format, OutputTarget};
+pub use headings::HeadingEntry;
+pub use image::DynamicImage;
+pub use images::{
+    generate_thumbnails, render_pages_to_images, render_pages_to_png, Thumbnail, ThumbnailConfig,
+    ThumbnailFormat,
+};
+pub use incremental::convert_incremental;
+pub use invoice::{extract_invoice, Invoice, LineItem};
+#[cfg(feature = "sqlite")]
+pub use jobs::SqliteJobStore;
+pub use jobs::{JobId, JobManager, JobRecord, JobStatus, JobStore, MemoryJobStore};
+#[cfg(feature = "prometheus")]
+pub use metrics::prometheus::PrometheusMetrics;
+pub use metrics::{LlmCallMetrics, MetricsSink, NoopMetricsSink, PipelineStage, SharedMetricsSink};
+pub use niche::ContentClass;
+#[cfg(feature = "otel")]
+pub use otel::TraceContext;
+pub use output::{
+    ConversionOutput, ConversionStats, DocumentMetadata, LatencyStats, OutputLayout,
+    PageFileNaming, PageInfo, PageResult,
+};
+pub use page_kind::{PageClassifier, PageKind};
+pub use pii::{PiiAction, PiiConfig, PiiFinding, PiiKind};
+pub use pipeline::margins::MarginMask;
+pub use pipeline::postprocess::{CleanReport, RuleChange};
+pub use polish::PolishConfig;
+pub use presets::DocumentPreset;
+pub use pricing::ModelPrice;
 pub use progress::{ConversionProgressCallback, NoopProgressCallback, ProgressCallback};
-pub use stream::{convert_stream, convert_stream_from_bytes};
+pub use prompts::PromptTemplate;
+pub use references::Citation;
+pub use reproducibility::ReproducibilityReport;
+pub use reqwest::header::HeaderMap;
+pub use resolver::{ConversionContext, ProviderResolver};
+pub use signatures::SignatureInfo;
+pub use split::{merge_pdfs, split_pdf};
+#[cfg(feature = "sqlite")]
+pub use sqlite::convert_to_sqlite;
+pub use stream::{
+    convert_stream, convert_stream_events, convert_stream_from_bytes, ConversionEvent, EventStream,
+};
+pub use summary::SummaryConfig;
+pub use tagged::{Conformance, PageTags, TaggedHeading};
+pub use webhook::{WebhookConfig, WebhookPayload};
@@ -0,0 +1,305 @@
+//! Mermaid diagram blocks of [`crate::FidelityTier::Tier4`].
+//!
+//! ## Why check them?
+//!
+//! Asked to redraw a flowchart as Mermaid, a model gets the boxes right
+//! far more often than the syntax: an unquoted label with brackets in it,
+//! an arrow written `->`, a `subgraph` never closed. A renderer shows such a
+//! block as an error box, which is worse than the italic caption the lower
+//! tiers write. So each ```` ```mermaid ```` block in the output is parsed
+//! with [`is_valid`], a grammar for the `flowchart` / `graph` subset the
+//! Tier4 prompt asks for, and [`check_blocks`] replaces blocks that fail
+//! with a caption:
+//!
+//! 1. the block's `accDescr:` (or `accTitle:`) line, in italics;
+//! 2. nothing, when the model already wrote an italic caption above it;
+//! 3. otherwise `*Diagram: …*` listing the quoted box labels.
+//!
+//! Other diagram types (`sequenceDiagram`, `gantt`, …) are not part of the
+//! grammar and fall back the same way.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tracing::debug;
+
+static ACC_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^\s*acc(Descr|Title)\s*:\s*(\S.*?)\s*$").unwrap());
+static LABEL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#""([^"\n]+)""#).unwrap());
+/// `-- text -->`, `== text ==>`, `-. text .->`.
+static TEXT_LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:--|==|-\.)[ \t]+[^ \t|][^|]*?[ \t]*(?:-{2,}>|={2,}>|\.-+>|-{3,})").unwrap()
+});
+/// `-->`, `---`, `==>`, `-.->`, `<-->`, `--x`, `--o`, with an optional
+/// `|label|`.
+static LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"^[<xo]?(?:-{2,}|={2,}|-\.+-)[>xo]?(?:[ \t]*\|(?:"[^"]*"|[^|"]*)\|)?"#).unwrap()
+});
+
+const DIRECTIONS: [&str; 5] = ["TD", "TB", "BT", "LR", "RL"];
+/// Lines that style or annotate a diagram; their arguments are not checked.
+const DIRECTIVES: [&str; 5] = ["classDef ", "class ", "style ", "linkStyle ", "click "];
+/// Box labels listed in a fallback caption.
+const MAX_CAPTION_LABELS: usize = 8;
+
+/// Whether `code` (the inside of a ```` ```mermaid ```` block) is a
+/// well-formed flowchart: a `flowchart` or `graph` header, then node and
+/// link statements with balanced `subgraph` … `end` and at least one node.
+pub fn is_valid(code: &str) -> bool {
+    let mut lines = code
+        .lines()
+        .map(|l| l.trim().trim_end_matches(';').trim_end())
+        .filter(|l| !l.is_empty() && !l.starts_with("%%"));
+    let Some(header) = lines.next() else {
+        return false;
+    };
+    let mut words = header.split_whitespace();
+    if !matches!(words.next(), Some("flowchart" | "graph")) {
+        return false;
+    }
+    match (words.next(), words.next()) {
+        (None, _) => {}
+        (Some(dir), None) if DIRECTIONS.contains(&dir) => {}
+        _ => return false,
+    }
+
+    let mut depth = 0usize;
+    let mut nodes = false;
+    for line in lines {
+        if line.starts_with("accTitle") || line.starts_with("accDescr") {
+            if !ACC_RE.is_match(line) {
+                return false;
+            }
+        } else if line == "subgraph" || line.starts_with("subgraph ") {
+            depth += 1;
+        } else if line == "end" {
+            let Some(d) = depth.checked_sub(1) else {
+                return false;
+            };
+            depth = d;
+        } else if let Some(dir) = line.strip_prefix("direction ") {
+            if !DIRECTIONS.contains(&dir.trim()) {
+                return false;
+            }
+        } else if DIRECTIVES.iter().any(|d| line.starts_with(d)) {
+            continue;
+        } else if statement(line) {
+            nodes = true;
+        } else {
+            return false;
+        }
+    }
+    depth == 0 && nodes
+}
+
+/// `A["x"] & B --> C -->|"y"| D` and the like.
+fn statement(line: &str) -> bool {
+    let mut rest = match node_group(line) {
+        Some(r) => r.trim_start(),
+        None => return false,
+    };
+    while !rest.is_empty() {
+        let Some(m) = TEXT_LINK_RE.find(rest).or_else(|| LINK_RE.find(rest)) else {
+            return false;
+        };
+        rest = match node_group(rest[m.end()..].trim_start()) {
+            Some(r) => r.trim_start(),
+            None => return false,
+        };
+    }
+    true
+}
+
+/// One or more nodes joined by `&`; returns the text after them.
+fn node_group(s: &str) -> Option<&str> {
+    let mut rest = node(s)?;
+    loop {
+        let trimmed = rest.trim_start();
+        match trimmed.strip_prefix('&') {
+            Some(next) => rest = node(next.trim_start())?,
+            None => return Some(rest),
+        }
+    }
+}
+
+/// An ID with an optional shape (`["label"]`, `{"label"}`, `((label))`, …)
+/// and `:::class`; returns the text after it.
+fn node(s: &str) -> Option<&str> {
+    let id_len = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    if id_len == 0 {
+        return None;
+    }
+    let mut rest = &s[id_len..];
+    if rest.starts_with(['[', '(', '{', '>']) {
+        rest = shape(rest)?;
+    }
+    if let Some(class) = rest.strip_prefix(":::") {
+        let len = class
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(class.len());
+        if len == 0 {
+            return None;
+        }
+        rest = &class[len..];
+    }
+    Some(rest)
+}
+
+/// A node shape: brackets balanced by kind, quoted text skipped. The
+/// asymmetric `>label]` opens with `>`.
+fn shape(s: &str) -> Option<&str> {
+    let mut closers = Vec::new();
+    let mut chars = s.char_indices();
+    if s.starts_with('>') {
+        chars.next();
+        closers.push(']');
+    }
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => loop {
+                match chars.next() {
+                    Some((_, '"')) => break,
+                    Some(_) => {}
+                    None => return None,
+                }
+            },
+            '[' => closers.push(']'),
+            '(' => closers.push(')'),
+            '{' => closers.push('}'),
+            ']' | ')' | '}' => {
+                if closers.pop() != Some(c) {
+                    return None;
+                }
+                if closers.is_empty() {
+                    return Some(&s[i + 1..]);
+                }
+            }
+            '\n' => return None,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Replace each ```` ```mermaid ```` block of `markdown` that fails
+/// [`is_valid`] (or is never closed) with a caption; see the module docs.
+pub fn check_blocks(markdown: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut lines = markdown.lines();
+    let mut dropped = 0;
+    while let Some(line) = lines.next() {
+        if line.trim() != "```mermaid" {
+            out.push(line.to_string());
+            continue;
+        }
+        let mut body = Vec::new();
+        let mut closed = false;
+        for l in lines.by_ref() {
+            if l.trim() == "```" {
+                closed = true;
+                break;
+            }
+            body.push(l);
+        }
+        let code = body.join("\n");
+        if closed && is_valid(&code) {
+            out.push(line.to_string());
+            out.extend(body.into_iter().map(str::to_string));
+            out.push("```".to_string());
+            continue;
+        }
+        dropped += 1;
+        let previous = out.iter().rev().find(|l| !l.is_empty());
+        if let Some(caption) = fallback_caption(&code, previous.map(String::as_str)) {
+            out.push(caption);
+        }
+    }
+    if dropped > 0 {
+        debug!(
+            "{} Mermaid block(s) failed to parse, kept as captions",
+            dropped
+        );
+    }
+    let mut s = out.join("\n");
+    if markdown.ends_with('\n') {
+        s.push('\n');
+    }
+    s
+}
+
+/// The caption an invalid block falls back to, or `None` when `previous`
+/// (the last non-blank line before it) already is one.
+fn fallback_caption(code: &str, previous: Option<&str>) -> Option<String> {
+    let acc = ACC_RE
+        .captures_iter(code)
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .collect::<Vec<_>>();
+    let described = acc
+        .iter()
+        .find(|(kind, _)| kind == "Descr")
+        .or_else(|| acc.first());
+    if let Some((_, text)) = described {
+        return Some(format!("*{}*", text.trim_matches('*')));
+    }
+    let italic = previous.is_some_and(|l| {
+        let l = l.trim();
+        l.len() > 2 && l.starts_with('*') && l.ends_with('*') && !l.starts_with("**")
+    });
+    if italic {
+        return None;
+    }
+    let labels = LABEL_RE
+        .captures_iter(code)
+        .map(|c| c[1].trim().to_string())
+        .filter(|l| !l.is_empty())
+        .take(MAX_CAPTION_LABELS)
+        .collect::<Vec<_>>();
+    Some(if labels.is_empty() {
+        "*Diagram*".to_string()
+    } else {
+        format!("*Diagram: {}*", labels.join(", "))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flowchart_grammar() {
+        assert!(is_valid(
+            "flowchart TD\n  accDescr: How a request is approved\n  \
+             A[\"Submit request\"] --> B{\"Approved?\"}\n  \
+             B -->|\"Yes\"| C([\"Done\"])\n  B -- No --> D[\"Revise (draft)\"] --> A\n  \
+             subgraph Review\n    direction LR\n    E & F --> G:::hot\n  end\n  \
+             classDef hot fill:#f96;"
+        ));
+        assert!(is_valid("graph LR\nCEO --> CTO & CFO\nCTO --- Eng"));
+        // Wrong arrow, unbalanced label, open subgraph, other diagram types.
+        assert!(!is_valid("flowchart TD\nA -> B"));
+        assert!(!is_valid("flowchart TD\nA[Step (1] --> B"));
+        assert!(!is_valid("flowchart TD\nsubgraph S\nA --> B"));
+        assert!(!is_valid("sequenceDiagram\nAlice->>Bob: Hi"));
+        assert!(!is_valid("flowchart TD"));
+        assert!(!is_valid("flowchart sideways\nA --> B"));
+    }
+
+    #[test]
+    fn invalid_blocks_become_captions() {
+        let valid = "```mermaid\nflowchart TD\nA --> B\n```";
+        assert_eq!(check_blocks(valid), valid);
+
+        let described = "Intro\n\n```mermaid\nflowchart TD\naccDescr: Three approval steps\nA -> B\n```\n\nAfter\n";
+        assert_eq!(
+            check_blocks(described),
+            "Intro\n\n*Three approval steps*\n\nAfter\n"
+        );
+
+        let captioned = "*Figure 2: Org chart*\n```mermaid\ngraph TD\nA[\"CEO\"] -> B\n```";
+        assert_eq!(check_blocks(captioned), "*Figure 2: Org chart*");
+
+        let truncated = "```mermaid\nflowchart TD\nA[\"Start\"] --> B[\"Check\"]";
+        assert_eq!(check_blocks(truncated), "*Diagram: Start, Check*");
+    }
+}
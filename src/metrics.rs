@@ -0,0 +1,177 @@
+//! Metrics hooks for per-stage pipeline measurements.
+//!
+//! Inject an [`Arc<dyn MetricsSink>`] via
+//! [`crate::config::ConversionConfigBuilder::metrics_sink`] to receive
+//! durations, token counts, and retry counts as structured values.
+//!
+//! # Why a sink trait alongside `tracing` spans?
+//!
+//! Every stage also runs inside a `tracing` span (`pdf2md.render`,
+//! `pdf2md.encode`, `pdf2md.llm`, `pdf2md.postprocess`) carrying the page
+//! number and, once finished, its duration, tokens, and retries. Spans are
+//! ideal for logs and distributed traces, but turning them into Prometheus
+//! counters or OpenTelemetry histograms means parsing log output or wiring a
+//! custom subscriber layer. The sink hands hosts the numbers directly, with no
+//! dependency on any particular metrics crate.
+//!
+//! With the `prometheus` feature, [`prometheus::PrometheusMetrics`] is a
+//! ready-made sink that renders the Prometheus text format; `pdf2md serve`
+//! exposes it on `GET /metrics`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use edgequake_pdf2md::{ConversionConfig, MetricsSink, PipelineStage};
+//! use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
+//! use std::time::Duration;
+//!
+//! #[derive(Default)]
+//! struct TokenCounter {
+//!     output_tokens: AtomicU64,
+//! }
+//!
+//! impl MetricsSink for TokenCounter {
+//!     fn record_llm_call(&self, call: &edgequake_pdf2md::LlmCallMetrics<'_>) {
+//!         self.output_tokens.fetch_add(call.output_tokens as u64, Ordering::Relaxed);
+//!     }
+//! }
+//!
+//! let config = ConversionConfig::builder()
+//!     .metrics_sink(Arc::new(TokenCounter::default()) as Arc<dyn MetricsSink>)
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use crate::output::PageResult;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+/// A pipeline stage whose per-page duration is reported to a [`MetricsSink`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PipelineStage {
+    /// pdfium rasterisation of one page.
+    Render,
+    /// PNG + base64 encoding of one page (all tiles, when tiled).
+    Encode,
+    /// One VLM call, including retries and fallbacks.
+    Llm,
+    /// Markdown post-processing of one page.
+    Postprocess,
+}
+
+impl PipelineStage {
+    /// Stable lowercase name, suitable as a metric label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineStage::Render => "render",
+            PipelineStage::Encode => "encode",
+            PipelineStage::Llm => "llm",
+            PipelineStage::Postprocess => "postprocess",
+        }
+    }
+}
+
+impl fmt::Display for PipelineStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Outcome of one VLM call, passed to [`MetricsSink::record_llm_call`].
+///
+/// A tiled page produces one call per tile.
+#[derive(Debug, Clone)]
+pub struct LlmCallMetrics<'a> {
+    /// 1-indexed page number.
+    pub page_num: usize,
+    /// Primary model of the call; fallback models are not distinguished.
+    pub model: &'a str,
+    /// Prompt tokens, including any truncation retry.
+    pub input_tokens: usize,
+    /// Completion tokens, including any truncation retry.
+    pub output_tokens: usize,
+    /// Retry attempts beyond the first.
+    pub retries: u8,
+    /// `false` when the call failed after all retries and fallbacks.
+    pub success: bool,
+    /// Wall-clock duration of the call including back-off.
+    pub duration: Duration,
+}
+
+/// Receives structured measurements from the conversion pipeline.
+///
+/// Implementations must be `Send + Sync`: render runs on a blocking thread and
+/// VLM calls run concurrently. All methods default to no-ops.
+pub trait MetricsSink: Send + Sync {
+    /// Time one stage took for one page.
+    fn record_stage(&self, stage: PipelineStage, page_num: usize, duration: Duration) {
+        let _ = (stage, page_num, duration);
+    }
+
+    /// One VLM call finished, successfully or not.
+    fn record_llm_call(&self, call: &LlmCallMetrics<'_>) {
+        let _ = call;
+    }
+
+    /// One page finished: converted, failed, or skipped as blank. Called
+    /// once per page after post-processing, tiles and spread halves merged.
+    fn record_page(&self, result: &PageResult) {
+        let _ = result;
+    }
+}
+
+/// A no-op sink; the default when none is configured.
+pub struct NoopMetricsSink;
+
+impl MetricsSink for NoopMetricsSink {}
+
+/// Convenience alias matching the type stored in [`crate::config::ConversionConfig`].
+pub type SharedMetricsSink = Arc<dyn MetricsSink>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        stages: Mutex<Vec<(PipelineStage, usize)>>,
+    }
+
+    impl MetricsSink for Recorder {
+        fn record_stage(&self, stage: PipelineStage, page_num: usize, _duration: Duration) {
+            self.stages.lock().unwrap().push((stage, page_num));
+        }
+    }
+
+    #[test]
+    fn stage_labels_are_stable() {
+        assert_eq!(PipelineStage::Render.as_str(), "render");
+        assert_eq!(PipelineStage::Postprocess.to_string(), "postprocess");
+    }
+
+    #[test]
+    fn default_methods_are_noops_and_overrides_fire() {
+        let recorder = Arc::new(Recorder::default());
+        let sink: SharedMetricsSink = recorder.clone();
+        sink.record_stage(PipelineStage::Llm, 3, Duration::from_millis(5));
+        sink.record_llm_call(&LlmCallMetrics {
+            page_num: 3,
+            model: "m",
+            input_tokens: 1,
+            output_tokens: 1,
+            retries: 0,
+            success: true,
+            duration: Duration::ZERO,
+        });
+        NoopMetricsSink.record_stage(PipelineStage::Render, 1, Duration::ZERO);
+        assert_eq!(
+            *recorder.stages.lock().unwrap(),
+            vec![(PipelineStage::Llm, 3)]
+        );
+    }
+}
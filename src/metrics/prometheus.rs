@@ -0,0 +1,230 @@
+//! Prometheus exposition of conversion metrics (feature `prometheus`).
+//!
+//! [`PrometheusMetrics`] is a [`MetricsSink`] that aggregates every page it
+//! sees across conversions and renders the totals in the Prometheus text
+//! format. Long-lived hosts share one instance between all conversions and
+//! serve [`PrometheusMetrics::render`] on a scrape endpoint; `pdf2md serve`
+//! does exactly that on `GET /metrics`:
+//!
+//! ```text
+//! pdf2md_pages_total{outcome="converted"} 118
+//! pdf2md_page_failures_total{class="rate_limited"} 2
+//! pdf2md_tokens_total{direction="input"} 141230
+//! pdf2md_cost_usd_total 0.0934
+//! pdf2md_page_duration_seconds_bucket{le="5"} 97
+//! ```
+//!
+//! The text is written by hand rather than through a metrics crate: five
+//! families do not justify a dependency, and the format is stable.
+
+use super::MetricsSink;
+use crate::error::PageError;
+use crate::output::PageResult;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+
+/// Content type of [`PrometheusMetrics::render`]'s output.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds (seconds) of the page latency histogram buckets. A page's
+/// model call usually takes 2–20 s; retries with back-off push the tail out.
+pub const LATENCY_BUCKETS_SECS: &[f64] = &[0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+
+/// Page outcomes, always exported so a scrape shows zeros before any work.
+const OUTCOMES: [&str; 3] = ["converted", "failed", "skipped"];
+
+/// Process-wide conversion counters in Prometheus form.
+#[derive(Debug, Default)]
+pub struct PrometheusMetrics {
+    inner: Mutex<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    pages: BTreeMap<&'static str, u64>,
+    failures: BTreeMap<&'static str, u64>,
+    input_tokens: u64,
+    output_tokens: u64,
+    cost_usd: f64,
+    /// Non-cumulative counts per bucket; one extra slot for `+Inf`.
+    latency_buckets: Vec<u64>,
+    latency_sum_secs: f64,
+    latency_count: u64,
+}
+
+impl PrometheusMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current totals in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let Ok(c) = self.inner.lock() else {
+            return String::new();
+        };
+        let mut out = String::new();
+
+        family(
+            &mut out,
+            "pdf2md_pages_total",
+            "counter",
+            "Pages finished, by outcome.",
+        );
+        for outcome in OUTCOMES {
+            let n = c.pages.get(outcome).copied().unwrap_or(0);
+            let _ = writeln!(out, "pdf2md_pages_total{{outcome=\"{outcome}\"}} {n}");
+        }
+
+        family(
+            &mut out,
+            "pdf2md_page_failures_total",
+            "counter",
+            "Failed pages, by error class.",
+        );
+        for (class, n) in &c.failures {
+            let _ = writeln!(out, "pdf2md_page_failures_total{{class=\"{class}\"}} {n}");
+        }
+
+        family(
+            &mut out,
+            "pdf2md_tokens_total",
+            "counter",
+            "Tokens used by page calls, by direction.",
+        );
+        let _ = writeln!(
+            out,
+            "pdf2md_tokens_total{{direction=\"input\"}} {}",
+            c.input_tokens
+        );
+        let _ = writeln!(
+            out,
+            "pdf2md_tokens_total{{direction=\"output\"}} {}",
+            c.output_tokens
+        );
+
+        family(
+            &mut out,
+            "pdf2md_cost_usd_total",
+            "counter",
+            "Estimated spend of page calls in US dollars (priced models only).",
+        );
+        let _ = writeln!(out, "pdf2md_cost_usd_total {}", c.cost_usd);
+
+        family(
+            &mut out,
+            "pdf2md_page_duration_seconds",
+            "histogram",
+            "Model latency per page, retries included.",
+        );
+        let mut cumulative = 0;
+        for (i, le) in LATENCY_BUCKETS_SECS.iter().enumerate() {
+            cumulative += c.latency_buckets.get(i).copied().unwrap_or(0);
+            let _ = writeln!(
+                out,
+                "pdf2md_page_duration_seconds_bucket{{le=\"{le}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "pdf2md_page_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            c.latency_count
+        );
+        let _ = writeln!(
+            out,
+            "pdf2md_page_duration_seconds_sum {}",
+            c.latency_sum_secs
+        );
+        let _ = writeln!(
+            out,
+            "pdf2md_page_duration_seconds_count {}",
+            c.latency_count
+        );
+        out
+    }
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+impl MetricsSink for PrometheusMetrics {
+    fn record_page(&self, result: &PageResult) {
+        let Ok(mut c) = self.inner.lock() else {
+            return;
+        };
+        let outcome = match (&result.error, result.skipped_blank) {
+            (Some(_), _) => "failed",
+            (None, true) => "skipped",
+            (None, false) => "converted",
+        };
+        *c.pages.entry(outcome).or_default() += 1;
+        if let Some(ref e) = result.error {
+            *c.failures.entry(e.kind()).or_default() += 1;
+        }
+        c.input_tokens += result.input_tokens as u64;
+        c.output_tokens += result.output_tokens as u64;
+        c.cost_usd += result.cost_usd.unwrap_or(0.0);
+
+        // Like the stage timings, only pages that reached the model count.
+        if result.skipped_blank || matches!(result.error, Some(PageError::RenderFailed { .. })) {
+            return;
+        }
+        let secs = result.duration_ms as f64 / 1000.0;
+        let bucket = LATENCY_BUCKETS_SECS
+            .iter()
+            .position(|&le| secs <= le)
+            .unwrap_or(LATENCY_BUCKETS_SECS.len());
+        if c.latency_buckets.is_empty() {
+            c.latency_buckets = vec![0; LATENCY_BUCKETS_SECS.len() + 1];
+        }
+        c.latency_buckets[bucket] += 1;
+        c.latency_sum_secs += secs;
+        c.latency_count += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(duration_ms: u64, error: Option<PageError>) -> PageResult {
+        PageResult {
+            page_num: 1,
+            input_tokens: 1000,
+            output_tokens: 200,
+            duration_ms,
+            cost_usd: Some(0.25),
+            error,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn renders_counters_and_cumulative_histogram() {
+        let metrics = PrometheusMetrics::new();
+        metrics.record_page(&page(800, None));
+        metrics.record_page(&page(4000, None));
+        metrics.record_page(&page(
+            300_000,
+            Some(PageError::RateLimited {
+                page: 3,
+                provider: "openai".into(),
+                retry_after_secs: None,
+            }),
+        ));
+
+        let text = metrics.render();
+        assert!(text.contains("pdf2md_pages_total{outcome=\"converted\"} 2\n"));
+        assert!(text.contains("pdf2md_pages_total{outcome=\"skipped\"} 0\n"));
+        assert!(text.contains("pdf2md_page_failures_total{class=\"rate_limited\"} 1\n"));
+        assert!(text.contains("pdf2md_tokens_total{direction=\"input\"} 3000\n"));
+        assert!(text.contains("pdf2md_cost_usd_total 0.75\n"));
+        assert!(text.contains("pdf2md_page_duration_seconds_bucket{le=\"1\"} 1\n"));
+        assert!(text.contains("pdf2md_page_duration_seconds_bucket{le=\"5\"} 2\n"));
+        assert!(text.contains("pdf2md_page_duration_seconds_bucket{le=\"120\"} 2\n"));
+        assert!(text.contains("pdf2md_page_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(text.contains("pdf2md_page_duration_seconds_count 3\n"));
+    }
+}
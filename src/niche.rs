@@ -0,0 +1,161 @@
+//! Pages the model cannot transcribe: sheet music, technical drawings, maps.
+//!
+//! ## Why placeholders?
+//!
+//! Asked to turn a page of sheet music or a CAD drawing into Markdown, a
+//! model does not refuse: it describes the staves bar by bar, or invents
+//! dimension tables, and the document gains pages of confident prose that
+//! nobody can use. With [`crate::ConversionConfigBuilder::niche_placeholders`]
+//! the page prompt gains [`crate::prompts::NICHE_CONTENT_RULES`], which ask
+//! the model to answer such content with a marker line instead:
+//!
+//! ```text
+//! [[NOT TRANSCRIBED: sheet-music | Sonata in A, second movement]]
+//! ```
+//!
+//! [`replace_markers`] turns each marker into a standard placeholder block,
+//! linked to the page thumbnail when
+//! [`crate::ConversionConfig::placeholder_thumbnails`] names the directory
+//! the thumbnails are in, and the class lands in
+//! [`crate::PageResult::content_class`]:
+//!
+//! ```text
+//! > **Sheet music not transcribed** (page 12): Sonata in A, second movement
+//! >
+//! > ![Page 12](thumbs/page-0012.jpg)
+//! ```
+//!
+//! Printed text around the content (a title, a caption, a drawing's title
+//! block) is still transcribed.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// `[[NOT TRANSCRIBED: class | title]]` on a line of its own.
+static MARKER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^\s*\[\[\s*NOT[ _-]TRANSCRIBED\s*:\s*([a-z][a-z _-]*?)\s*(?:\|\s*(.*?))?\s*\]\]\s*$",
+    )
+    .unwrap()
+});
+
+/// Content a vision model cannot faithfully write as Markdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ContentClass {
+    /// Music notation: staves, tablature.
+    SheetMusic,
+    /// CAD drawings, blueprints, circuit schematics.
+    TechnicalDrawing,
+    /// Maps and site plans.
+    Map,
+    /// Any other class the model named.
+    Other,
+}
+
+impl ContentClass {
+    /// Kebab-case name, as written in the marker.
+    pub fn name(self) -> &'static str {
+        match self {
+            ContentClass::SheetMusic => "sheet-music",
+            ContentClass::TechnicalDrawing => "technical-drawing",
+            ContentClass::Map => "map",
+            ContentClass::Other => "other",
+        }
+    }
+
+    /// Heading of the placeholder block, e.g. `Sheet music`.
+    fn label(self) -> &'static str {
+        match self {
+            ContentClass::SheetMusic => "Sheet music",
+            ContentClass::TechnicalDrawing => "Technical drawing",
+            ContentClass::Map => "Map",
+            ContentClass::Other => "Content",
+        }
+    }
+
+    fn from_marker(name: &str) -> Self {
+        match name.to_ascii_lowercase().replace([' ', '_'], "-").as_str() {
+            "sheet-music" | "music" | "tablature" => ContentClass::SheetMusic,
+            "technical-drawing" | "cad" | "blueprint" | "schematic" => {
+                ContentClass::TechnicalDrawing
+            }
+            "map" => ContentClass::Map,
+            _ => ContentClass::Other,
+        }
+    }
+}
+
+/// Replace each marker line of `markdown` with a placeholder block; see
+/// the module docs. Returns the new text and the first marker's class.
+///
+/// `thumbnails` is the directory holding `page-NNNN.jpg` previews, linked
+/// by its last component.
+pub fn replace_markers(
+    markdown: &str,
+    page_num: usize,
+    thumbnails: Option<&Path>,
+) -> (String, Option<ContentClass>) {
+    let mut class = None;
+    let mut out = Vec::new();
+    for line in markdown.lines() {
+        let Some(caps) = MARKER_RE.captures(line) else {
+            out.push(line.to_string());
+            continue;
+        };
+        let found = ContentClass::from_marker(&caps[1]);
+        class.get_or_insert(found);
+        let mut heading = format!(
+            "> **{} not transcribed** (page {})",
+            found.label(),
+            page_num
+        );
+        if let Some(title) = caps.get(2).map(|m| m.as_str()).filter(|t| !t.is_empty()) {
+            heading.push_str(": ");
+            heading.push_str(title);
+        }
+        out.push(heading);
+        if let Some(dir) = thumbnails.and_then(Path::file_name) {
+            out.push(">".to_string());
+            out.push(format!(
+                "> ![Page {}]({}/page-{:04}.jpg)",
+                page_num,
+                dir.to_string_lossy(),
+                page_num
+            ));
+        }
+    }
+    let mut s = out.join("\n");
+    if markdown.ends_with('\n') {
+        s.push('\n');
+    }
+    (s, class)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markers_become_placeholders() {
+        let md = "# Études\n\n[[NOT TRANSCRIBED: sheet-music | Étude No. 3]]\n";
+        let (out, class) = replace_markers(md, 12, Some(Path::new("out/thumbs")));
+        assert_eq!(class, Some(ContentClass::SheetMusic));
+        assert_eq!(
+            out,
+            "# Études\n\n> **Sheet music not transcribed** (page 12): Étude No. 3\n>\n> ![Page 12](thumbs/page-0012.jpg)\n"
+        );
+
+        let (out, class) = replace_markers("[[not transcribed: CAD]]", 3, None);
+        assert_eq!(class, Some(ContentClass::TechnicalDrawing));
+        assert_eq!(out, "> **Technical drawing not transcribed** (page 3)");
+    }
+
+    #[test]
+    fn ordinary_text_is_untouched() {
+        let md = "See [[wiki links]] and the NOT TRANSCRIBED: note.";
+        assert_eq!(replace_markers(md, 1, None), (md.to_string(), None));
+    }
+}
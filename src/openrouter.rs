@@ -0,0 +1,307 @@
+//! OpenRouter page calls with server-side model fallback.
+//!
+//! ## Why a separate backend?
+//!
+//! OpenRouter takes an ordered `models` array in the request body and tries
+//! each model in turn on its side when the one before it is down, rate
+//! limited, or refuses the request. That beats the client-side fallback loop
+//! in [`crate::pipeline::llm`]: no round trip per failed model, and routing
+//! reflects OpenRouter's own view of provider health. edgequake-llm's
+//! options have no field for it, so with
+//! [`crate::ConversionConfig::fallback_models`] on the `openrouter` provider
+//! page calls go through [`OpenRouterBackend`], which builds the chat
+//! request itself:
+//!
+//! ```text
+//! {"model":"openai/gpt-4.1-nano",
+//!  "models":["openai/gpt-4.1-nano","google/gemini-2.0-flash-001"],
+//!  "messages":[…], "temperature":0.1, "max_tokens":4096, "seed":7}
+//! ```
+//!
+//! Every other provider keeps the client-side loop. The model that answered
+//! is logged when it is not the primary one.
+
+use crate::backend::{DescribeOptions, PageCompletion, PageImage, PagePrompt, VisionBackend};
+use crate::error::Pdf2MdError;
+use async_trait::async_trait;
+use reqwest::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::debug;
+
+/// Page calls to OpenRouter's chat completions endpoint, with the fallback
+/// models sent in the request's `models` array.
+pub struct OpenRouterBackend {
+    client: reqwest::Client,
+    endpoint: String,
+    /// Sent as a bearer token; `None` when `headers` carry the
+    /// `Authorization` header.
+    api_key: Option<String>,
+    headers: HeaderMap,
+    /// The primary model first, then the fallbacks in order.
+    models: Vec<String>,
+}
+
+impl OpenRouterBackend {
+    /// A backend for `models` (primary first) at `base_url`, e.g.
+    /// `https://openrouter.ai/api/v1`. `headers` are sent with every
+    /// request; `timeout` bounds each call.
+    pub fn new(
+        base_url: &str,
+        api_key: Option<String>,
+        models: Vec<String>,
+        headers: HeaderMap,
+        timeout: Duration,
+    ) -> Result<Self, Pdf2MdError> {
+        if models.is_empty() {
+            return Err(Pdf2MdError::InvalidConfig(
+                "OpenRouter backend needs at least one model".into(),
+            ));
+        }
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| Pdf2MdError::Internal(format!("HTTP client: {e}")))?;
+        Ok(Self {
+            client,
+            endpoint: format!("{}/chat/completions", base_url.trim_end_matches('/')),
+            // A caller-set Authorization header wins over the key.
+            api_key: api_key.filter(|_| !headers.contains_key(AUTHORIZATION)),
+            headers,
+            models,
+        })
+    }
+
+    /// The request body for one page.
+    fn request_body(
+        &self,
+        image: &PageImage,
+        prompt: &PagePrompt,
+        opts: &DescribeOptions,
+    ) -> Value {
+        let mut messages = vec![json!({"role": "system", "content": prompt.system})];
+        if let Some(ref context) = prompt.context {
+            messages.push(json!({"role": "system", "content": context}));
+        }
+        for example in &prompt.examples {
+            messages.push(json!({"role": "user", "content": [image_part(&example.image)]}));
+            messages.push(json!({"role": "assistant", "content": example.markdown}));
+        }
+        let mut content = Vec::new();
+        if !prompt.user_text.is_empty() {
+            content.push(json!({"type": "text", "text": prompt.user_text}));
+        }
+        content.push(image_part(image));
+        messages.push(json!({"role": "user", "content": content}));
+
+        let mut body = json!({
+            "model": self.models[0],
+            "models": self.models,
+            "messages": messages,
+            "temperature": opts.temperature,
+            "max_tokens": opts.max_tokens,
+        });
+        if let Some(seed) = opts.seed {
+            body["seed"] = json!(seed);
+        }
+        body
+    }
+}
+
+/// An image content part with the page as a data URL.
+fn image_part(image: &PageImage) -> Value {
+    let mut url = json!({"url": format!("data:{};base64,{}", image.mime_type, image.data)});
+    if let Some(ref detail) = image.detail {
+        url["detail"] = json!(detail);
+    }
+    json!({"type": "image_url", "image_url": url})
+}
+
+/// Read a chat completion, or the error OpenRouter reports in its body.
+fn parse_completion(body: &Value, primary: &str) -> Result<PageCompletion, Pdf2MdError> {
+    if let Some(error) = body.get("error") {
+        let code = match error.get("code") {
+            Some(Value::String(code)) => code.clone(),
+            Some(code) => code.to_string(),
+            None => String::new(),
+        };
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(Pdf2MdError::LlmApiError {
+            message: format!("OpenRouter error {code}: {message}"),
+        });
+    }
+    let choice = body
+        .pointer("/choices/0")
+        .ok_or_else(|| Pdf2MdError::LlmApiError {
+            message: "OpenRouter response: missing field `choices`".into(),
+        })?;
+    if let Some(model) = body.get("model").and_then(Value::as_str) {
+        if model != primary {
+            debug!("OpenRouter answered with fallback model '{}'", model);
+        }
+    }
+    let usage = |field: &str| {
+        body.pointer(&format!("/usage/{field}"))
+            .and_then(Value::as_u64)
+            .unwrap_or(0) as usize
+    };
+    Ok(PageCompletion {
+        markdown: choice
+            .pointer("/message/content")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        input_tokens: usage("prompt_tokens"),
+        output_tokens: usage("completion_tokens"),
+        finish_reason: choice
+            .get("finish_reason")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        image_tokens: None,
+    })
+}
+
+#[async_trait]
+impl VisionBackend for OpenRouterBackend {
+    fn name(&self) -> &str {
+        "openrouter"
+    }
+
+    fn model(&self) -> &str {
+        &self.models[0]
+    }
+
+    async fn describe_page(
+        &self,
+        image: &PageImage,
+        prompt: &PagePrompt,
+        opts: &DescribeOptions,
+    ) -> Result<PageCompletion, Pdf2MdError> {
+        let body = serde_json::to_vec(&self.request_body(image, prompt, opts))
+            .map_err(|e| Pdf2MdError::Internal(format!("OpenRouter request: {e}")))?;
+        let api_error = |message: String| Pdf2MdError::LlmApiError { message };
+        let mut request = self
+            .client
+            .post(&self.endpoint)
+            .headers(self.headers.clone())
+            .header(CONTENT_TYPE, "application/json")
+            .body(body);
+        if let Some(ref key) = self.api_key {
+            request = request.header(AUTHORIZATION, format!("Bearer {key}"));
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| api_error(format!("OpenRouter request failed: {e}")))?;
+
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let text = response
+            .text()
+            .await
+            .map_err(|e| api_error(format!("OpenRouter response: {e}")))?;
+        if !status.is_success() {
+            // Status and Retry-After in the message drive the retry policy.
+            let hint = retry_after.map_or(String::new(), |s| format!(", retry after {s}s"));
+            return Err(api_error(format!("{status}{hint}: {text}")));
+        }
+        let body: Value = serde_json::from_str(&text)
+            .map_err(|e| api_error(format!("OpenRouter response: failed to parse: {e}")))?;
+        parse_completion(&body, &self.models[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::FewShotExample;
+    use crate::pipeline::llm::{classify_error, ErrorClass};
+
+    fn backend() -> OpenRouterBackend {
+        OpenRouterBackend::new(
+            "https://openrouter.ai/api/v1/",
+            Some("sk-or".into()),
+            vec![
+                "openai/gpt-4.1-nano".into(),
+                "google/gemini-2.0-flash-001".into(),
+            ],
+            HeaderMap::new(),
+            Duration::from_secs(5),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn request_carries_the_models_array_and_seed() {
+        let backend = backend();
+        assert_eq!(
+            backend.endpoint,
+            "https://openrouter.ai/api/v1/chat/completions"
+        );
+        let prompt = PagePrompt {
+            system: "Convert.".into(),
+            context: Some("Prior page.".into()),
+            examples: vec![FewShotExample::new(
+                PageImage::new("ex", "image/png"),
+                "# Example",
+            )],
+            user_text: String::new(),
+        };
+        let opts = DescribeOptions {
+            temperature: 0.0,
+            max_tokens: 512,
+            seed: Some(7),
+        };
+        let body = backend.request_body(&PageImage::new("abc", "image/png"), &prompt, &opts);
+        assert_eq!(body["model"], "openai/gpt-4.1-nano");
+        assert_eq!(
+            body["models"],
+            json!(["openai/gpt-4.1-nano", "google/gemini-2.0-flash-001"])
+        );
+        assert_eq!(body["seed"], 7);
+        assert_eq!(body["max_tokens"], 512);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[3]["content"], "# Example");
+        assert_eq!(
+            messages[4]["content"][0]["image_url"]["url"],
+            "data:image/png;base64,abc"
+        );
+
+        let unseeded = DescribeOptions { seed: None, ..opts };
+        let body = backend.request_body(&PageImage::new("abc", "image/png"), &prompt, &unseeded);
+        assert!(body.get("seed").is_none());
+    }
+
+    #[test]
+    fn completion_and_errors_are_read_from_the_body() {
+        let body = json!({
+            "model": "google/gemini-2.0-flash-001",
+            "choices": [{"message": {"content": "# Title"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 900, "completion_tokens": 12}
+        });
+        let completion = parse_completion(&body, "openai/gpt-4.1-nano").unwrap();
+        assert_eq!(completion.markdown, "# Title");
+        assert_eq!(
+            (completion.input_tokens, completion.output_tokens),
+            (900, 12)
+        );
+        assert_eq!(completion.finish_reason.as_deref(), Some("stop"));
+
+        let body = json!({"error": {"code": 429, "message": "Rate limit exceeded"}});
+        let Err(Pdf2MdError::LlmApiError { message }) = parse_completion(&body, "m") else {
+            panic!("expected an API error");
+        };
+        assert!(matches!(
+            classify_error(&message),
+            ErrorClass::RateLimited { .. }
+        ));
+    }
+}
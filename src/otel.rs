@@ -0,0 +1,137 @@
+//! W3C trace-context propagation into VLM requests (feature `otel`).
+//!
+//! ## Why propagate?
+//!
+//! In a host service that is already traced, a conversion shows up as one
+//! opaque span while the minutes it spends are really dozens of VLM calls.
+//! Forwarding the host's `traceparent` (and `tracestate`) headers lets a
+//! tracing-aware gateway or provider attach those calls to the host trace.
+//!
+//! The context is attached as request headers on the OpenAI-compatible client
+//! (see [`crate::ConversionConfigBuilder::trace_context`]), so every page call
+//! of a conversion carries the same parent span.
+
+use crate::error::Pdf2MdError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static TRACEPARENT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[0-9a-f]{2}-([0-9a-f]{32})-([0-9a-f]{16})-[0-9a-f]{2}$").unwrap());
+
+/// A parent trace context in W3C Trace Context form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    traceparent: String,
+    tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Parse a `traceparent` header value, e.g.
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`.
+    ///
+    /// Rejects malformed values and the all-zero trace/span IDs that the
+    /// spec defines as invalid.
+    pub fn parse(traceparent: impl Into<String>) -> Result<Self, Pdf2MdError> {
+        let traceparent = traceparent.into().trim().to_ascii_lowercase();
+        let caps = TRACEPARENT_RE.captures(&traceparent).ok_or_else(|| {
+            Pdf2MdError::InvalidConfig(format!("Invalid traceparent '{traceparent}'"))
+        })?;
+        if caps[1].bytes().all(|b| b == b'0') || caps[2].bytes().all(|b| b == b'0') {
+            return Err(Pdf2MdError::InvalidConfig(format!(
+                "traceparent '{traceparent}' has an all-zero trace or span id"
+            )));
+        }
+        Ok(Self {
+            traceparent,
+            tracestate: None,
+        })
+    }
+
+    fn from_parts(traceparent: String, tracestate: String) -> Self {
+        Self {
+            traceparent,
+            tracestate: None,
+        }
+        .with_tracestate(tracestate)
+    }
+
+    /// Attach a vendor-specific `tracestate` header value.
+    pub fn with_tracestate(mut self, tracestate: impl Into<String>) -> Self {
+        let state = tracestate.into();
+        self.tracestate = (!state.is_empty()).then_some(state);
+        self
+    }
+
+    /// Build from an OpenTelemetry context, or `None` if it has no valid span.
+    pub fn from_context(cx: &opentelemetry::Context) -> Option<Self> {
+        use opentelemetry::trace::TraceContextExt;
+
+        let span = cx.span();
+        let sc = span.span_context();
+        if !sc.is_valid() {
+            return None;
+        }
+        let traceparent = format!(
+            "00-{}-{}-{:02x}",
+            sc.trace_id(),
+            sc.span_id(),
+            sc.trace_flags().to_u8()
+        );
+        Some(Self::from_parts(traceparent, sc.trace_state().header()))
+    }
+
+    /// Build from the OpenTelemetry context active on the current thread.
+    pub fn current() -> Option<Self> {
+        Self::from_context(&opentelemetry::Context::current())
+    }
+
+    /// The `traceparent` header value.
+    pub fn traceparent(&self) -> &str {
+        &self.traceparent
+    }
+
+    /// The `tracestate` header value, if any.
+    pub fn tracestate(&self) -> Option<&str> {
+        self.tracestate.as_deref()
+    }
+
+    /// Header name/value pairs to add to outgoing requests.
+    pub fn headers(&self) -> Vec<(String, String)> {
+        let mut headers = vec![("traceparent".to_string(), self.traceparent.clone())];
+        if let Some(ref state) = self.tracestate {
+            headers.push(("tracestate".to_string(), state.clone()));
+        }
+        headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn parse_valid_traceparent() {
+        let tc = TraceContext::parse(VALID).unwrap();
+        assert_eq!(tc.traceparent(), VALID);
+        assert_eq!(tc.headers(), vec![("traceparent".into(), VALID.into())]);
+    }
+
+    #[test]
+    fn parse_rejects_malformed_and_zero_ids() {
+        assert!(TraceContext::parse("00-abc-def-01").is_err());
+        assert!(
+            TraceContext::parse("00-00000000000000000000000000000000-00f067aa0ba902b7-01").is_err()
+        );
+    }
+
+    #[test]
+    fn tracestate_is_forwarded() {
+        let tc = TraceContext::parse(VALID)
+            .unwrap()
+            .with_tracestate("congo=t61rcWkgMzE");
+        assert_eq!(tc.headers().len(), 2);
+        assert_eq!(tc.tracestate(), Some("congo=t61rcWkgMzE"));
+    }
+}
@@ -5,8 +5,25 @@
 //! be missing (e.g. optional PDF metadata) uses `Option` rather than empty
 //! strings so callers can distinguish "not present" from "present but blank".
 
+use crate::attachments::Attachment;
+use crate::auto_dpi::DpiCalibration;
+use crate::backend::PageImage;
+use crate::charts::ChartData;
+use crate::config::{ContentHint, CropRect};
+use crate::enrich::Enrichment;
 use crate::error::{PageError, Pdf2MdError};
+use crate::figures::Figure;
+use crate::headings::HeadingEntry;
+use crate::niche::ContentClass;
+use crate::page_kind::PageKind;
+use crate::pii::PiiFinding;
+use crate::pipeline::postprocess::CleanReport;
+use crate::references::Citation;
+use crate::reproducibility::ReproducibilityReport;
+use crate::signatures::SignatureInfo;
+use crate::tagged::{Conformance, PageTags};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// The complete result of converting a PDF document to Markdown.
 ///
@@ -42,6 +59,51 @@ pub struct ConversionOutput {
     ///
     /// Useful for cost estimation, benchmark comparisons, and progress bars.
     pub stats: ConversionStats,
+
+    /// Abstract of the document, when [`crate::summary::SummaryConfig`] was set.
+    ///
+    /// `None` when summarisation was off or its LLM pass failed.
+    #[serde(default)]
+    pub summary: Option<String>,
+
+    /// Keywords, entities, and document type, when [`crate::enrich::EnrichmentConfig`] was set.
+    ///
+    /// `None` when enrichment was off or its LLM pass failed.
+    #[serde(default)]
+    pub enrichment: Option<Enrichment>,
+
+    /// Personal data found by the PII pass, per page and kind, when
+    /// [`crate::pii::PiiConfig`] was set. Empty otherwise.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pii_findings: Vec<PiiFinding>,
+
+    /// Bibliography entries parsed by the references pass, in document
+    /// order, when [`crate::ConversionConfig::parse_references`] was set.
+    /// Empty otherwise or when no references section was found.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<Citation>,
+
+    /// Every heading of `markdown` with its GitHub-compatible anchor, in
+    /// document order, for tables of contents and deep links. See
+    /// [`crate::headings`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headings: Vec<HeadingEntry>,
+
+    /// Why the run stopped early, if it did.
+    ///
+    /// Set when the provider failed on
+    /// [`crate::ConversionConfig::failure_abort_threshold`] pages in a row
+    /// after others had converted (auth revoked, network outage). `pages` and
+    /// `markdown` hold what was converted before that; pages never attempted
+    /// count as skipped in `stats`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fatal_error: Option<String>,
+
+    /// Library versions, model, prompt hash, and sampling settings of the
+    /// run, for re-verifying an archived conversion later. Always set by
+    /// [`crate::convert`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reproducibility: Option<ReproducibilityReport>,
 }
 
 impl ConversionOutput {
@@ -54,10 +116,12 @@ impl ConversionOutput {
     }
 
     /// Convert this output into a `Result`, returning
-    /// [`Pdf2MdError::PartialFailure`] if any pages failed.
+    /// [`Pdf2MdError::PartialFailure`] if any pages failed or the run
+    /// stopped early (see [`Self::fatal_error`]).
     ///
     /// Use this when the caller wants to treat *any* page failure as an error
     /// rather than receiving partial output silently.
+    /// [`crate::config::Strictness::AllOrNothing`] applies it for you.
     ///
     /// # Example
     /// ```rust,no_run
@@ -74,7 +138,7 @@ impl ConversionOutput {
     /// ```
     pub fn into_result(self) -> Result<Self, Pdf2MdError> {
         let failed = self.stats.failed_pages;
-        if failed > 0 {
+        if failed > 0 || self.fatal_error.is_some() {
             Err(Pdf2MdError::PartialFailure {
                 success: self.stats.processed_pages,
                 failed,
@@ -91,7 +155,7 @@ impl ConversionOutput {
 /// A page can succeed (`error` is `None`) or fail (`error` is `Some`).
 /// Failed pages emit a non-fatal [`PageError`] and carry an empty `markdown`
 /// string — the overall conversion continues unless every page fails.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PageResult {
     /// 1-indexed page number matching PDF reader conventions.
     pub page_num: usize,
@@ -105,6 +169,16 @@ pub struct PageResult {
     /// depending on the model's vision tokeniser.
     pub input_tokens: usize,
 
+    /// The part of `input_tokens` spent on images (the page or its tiles,
+    /// and any few-shot example images); the rest is the text prompt.
+    ///
+    /// Taken from the backend when it reports image usage
+    /// ([`crate::backend::PageCompletion::image_tokens`]). Otherwise
+    /// estimated as `input_tokens` minus the prompt text at 4 characters per
+    /// token, which is close for Latin-script prompts.
+    #[serde(default)]
+    pub image_tokens: usize,
+
     /// Tokens generated by the model (i.e. the Markdown text).
     pub output_tokens: usize,
 
@@ -118,6 +192,83 @@ pub struct PageResult {
     /// API errors; worth monitoring in production.
     pub retries: u8,
 
+    /// `true` when the model stopped because it hit `max_tokens`, so the
+    /// Markdown is likely cut off mid-sentence.
+    ///
+    /// Still `true` after a doubled-budget retry if that was truncated too
+    /// (see [`crate::ConversionConfig::max_tokens_retry_cap`]).
+    #[serde(default)]
+    pub truncated: bool,
+
+    /// `true` when the page rendered blank and was skipped without a model
+    /// call (see [`crate::ConversionConfig::skip_blank_pages`]). `markdown`
+    /// is empty and the page counts towards
+    /// [`ConversionStats::skipped_pages`].
+    #[serde(default)]
+    pub skipped_blank: bool,
+
+    /// Clockwise degrees (0, 90, 180, or 270) the rendered image was turned
+    /// before it was sent, by [`crate::ConversionConfig::auto_rotate`].
+    #[serde(default)]
+    pub rotation_applied: u16,
+
+    /// The part of the page this result covers, from
+    /// [`crate::ConversionConfig::page_crops`]; `None` for the whole page.
+    /// A cropped page has one result per region, in the configured order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<CropRect>,
+
+    /// Figures found on the page, with their alt text, when
+    /// [`crate::ConversionConfig::figure_captions`] is on. See
+    /// [`crate::figures`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub figures: Vec<Figure>,
+
+    /// Data of the page's bar, line, and pie charts, parsed from the tables
+    /// the model wrote beneath them with
+    /// [`crate::ConversionConfig::chart_tables`]. See [`crate::charts`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub charts: Vec<ChartData>,
+
+    /// What the page holds that the model could not transcribe, when
+    /// [`crate::ConversionConfig::niche_placeholders`] put a placeholder in
+    /// its place. See [`crate::niche`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_class: Option<ContentClass>,
+
+    /// What the page is (cover, contents, body, …), when
+    /// [`crate::ConversionConfig::page_classifier`] placed it. See
+    /// [`crate::page_kind`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_kind: Option<PageKind>,
+
+    /// Estimated cost of this page's model calls in US dollars, from its
+    /// token counts and the answering model's price (see
+    /// [`crate::ConversionConfig::model_prices`]). `None` when the model has
+    /// no known price or the page made no successful call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+
+    /// Fingerprint of the rendered page image (see
+    /// [`crate::pipeline::encode::image_hash`]), used by
+    /// [`crate::convert_incremental`] to spot unchanged pages. `None` when the
+    /// page failed to render.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_hash: Option<String>,
+
+    /// The model's answer before post-processing, when
+    /// [`crate::ConversionConfig::keep_raw_output`] is on.
+    ///
+    /// Compare with `markdown` to see exactly what the cleanup rules in
+    /// [`crate::pipeline::postprocess`] changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_markdown: Option<String>,
+
+    /// The image this page was sent to the model as, when
+    /// [`crate::ConversionConfig::keep_page_images`] is on. Not serialised.
+    #[serde(skip)]
+    pub page_image: Option<PageImage>,
+
     /// Non-`None` when this page failed after all retries.
     ///
     /// The surrounding `ConversionOutput` still contains results for
@@ -125,10 +276,22 @@ pub struct PageResult {
     pub error: Option<PageError>,
 }
 
+impl PageResult {
+    /// A successful result for `page_num` with nothing recorded but its
+    /// Markdown; set other fields with `..PageResult::new(n, md)`.
+    pub fn new(page_num: usize, markdown: impl Into<String>) -> Self {
+        Self {
+            page_num,
+            markdown: markdown.into(),
+            ..Default::default()
+        }
+    }
+}
+
 /// Aggregate statistics for the whole conversion run.
 ///
-/// Used for cost estimation: multiply token counts by the model's per-token
-/// price to get total spend. Timing breakdown (render vs. LLM) helps identify
+/// Used for cost reporting: `total_cost_usd` prices the token counts with
+/// [`crate::pricing`], or multiply them by your own rates. Timing breakdown (render vs. LLM) helps identify
 /// whether bottlenecks are in rasterisation or API latency.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ConversionStats {
@@ -142,27 +305,120 @@ pub struct ConversionStats {
     pub failed_pages: usize,
 
     /// Pages in the selection that were skipped before any processing (e.g.
-    /// page encoding failed before the LLM call was even attempted).
+    /// page encoding failed before the LLM call was even attempted), plus
+    /// blank pages skipped by [`crate::ConversionConfig::skip_blank_pages`].
     pub skipped_pages: usize,
 
     /// Sum of all `PageResult::input_tokens` across processed pages.
     pub total_input_tokens: u64,
 
+    /// Sum of all `PageResult::image_tokens`: the image share of
+    /// `total_input_tokens`. Higher DPI raises this; a richer prompt
+    /// (fidelity tier 3, few-shot examples) raises the rest.
+    #[serde(default)]
+    pub total_image_tokens: u64,
+
     /// Sum of all `PageResult::output_tokens` across processed pages.
     pub total_output_tokens: u64,
 
+    /// Sum of all known `PageResult::cost_usd`, in US dollars. `None` when no
+    /// page could be priced. Summary and enrichment calls are not included.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
+
     /// End-to-end wall time from first byte read to last page assembled (ms).
     pub total_duration_ms: u64,
 
-    /// Time spent in pdfium rasterisation only (ms).
+    /// Time spent rasterising and encoding pages, summed over pages (ms).
     ///
     /// If this dominates, lower DPI or reduce `max_rendered_pixels`.
     pub render_duration_ms: u64,
 
-    /// Time spent waiting for LLM API calls only (ms).
+    /// Time spent in LLM calls (retries included), summed over pages (ms).
     ///
+    /// Concurrent calls overlap, so this can exceed `total_duration_ms`.
     /// If this dominates, increase concurrency or switch to a faster model.
     pub llm_duration_ms: u64,
+
+    /// Per-page render + encode latency.
+    #[serde(default)]
+    pub render_latency: LatencyStats,
+
+    /// Per-page wait between being encoded and reaching a worker. Long waits
+    /// mean pages are rendered faster than the model takes them; raising
+    /// `concurrency` helps, a faster renderer does not.
+    #[serde(default)]
+    pub queue_wait: LatencyStats,
+
+    /// Per-page LLM latency, retries included.
+    #[serde(default)]
+    pub llm_latency: LatencyStats,
+
+    /// Pages by retry count: `retries_histogram[n]` pages needed `n` retries.
+    /// Only pages sent to the model are counted.
+    #[serde(default)]
+    pub retries_histogram: Vec<usize>,
+
+    /// Pages copied unchanged from the previous output by
+    /// [`crate::convert_incremental`]; counted in `processed_pages` too.
+    #[serde(default)]
+    pub reused_pages: usize,
+
+    /// Post-processing rules that changed pages, with the pages and
+    /// characters each changed (see [`crate::pipeline::postprocess`]).
+    #[serde(default, skip_serializing_if = "CleanReport::is_empty")]
+    pub postprocess_report: CleanReport,
+
+    /// Pages changed by [`crate::ConversionConfig::text_polish`].
+    #[serde(default)]
+    pub polished_pages: usize,
+
+    /// Pages whose polished text was discarded as a rewrite.
+    #[serde(default)]
+    pub polish_rejected_pages: usize,
+
+    /// Result of [`crate::ConversionConfig::auto_dpi`] calibration, when it
+    /// ran and chose a DPI. The probes' tokens are not in the totals above.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dpi_calibration: Option<DpiCalibration>,
+
+    /// Answer of the [`crate::ContentHint::Auto`] handwriting check, when it
+    /// ran and the model gave one. See [`crate::handwriting`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detected_content: Option<ContentHint>,
+}
+
+/// Distribution of one pipeline stage's per-page latency, in milliseconds.
+///
+/// Percentiles use the nearest-rank method; all fields are 0 when `count` is.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    /// Number of pages measured.
+    pub count: usize,
+    pub min_ms: u64,
+    pub median_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+impl LatencyStats {
+    /// Summarise a set of per-page samples (in any order).
+    pub fn from_samples(samples: &[u64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let rank =
+            |p: f64| sorted[((p * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1];
+        Self {
+            count: sorted.len(),
+            min_ms: sorted[0],
+            median_ms: rank(0.5),
+            p95_ms: rank(0.95),
+            max_ms: sorted[sorted.len() - 1],
+        }
+    }
 }
 
 /// Document metadata read from the PDF's info dictionary.
@@ -192,6 +448,99 @@ pub struct DocumentMetadata {
     pub pdf_version: String,
     /// Whether the document requires a password to open.
     pub is_encrypted: bool,
+    /// Tagged-PDF structure and PDF/A or PDF/UA claims from the XMP
+    /// metadata.
+    #[serde(default)]
+    pub conformance: Conformance,
+    /// Per-page geometry and content summary, in page order.
+    ///
+    /// Read from the PDF structure without rendering, so callers can plan DPI,
+    /// spot scanned pages (no text layer), and estimate cost up front.
+    pub pages: Vec<PageInfo>,
+    /// Files embedded in the PDF, by name and size. Saved paths and parsed
+    /// e-invoices are filled in by a conversion configured for them; see
+    /// [`crate::attachments`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    /// Digital signatures, in the order pdfium lists them; empty for an
+    /// unsigned document. See [`crate::signatures`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<SignatureInfo>,
+}
+
+/// Structural facts about a single PDF page, gathered without rasterising.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageInfo {
+    /// 1-indexed page number.
+    pub page_num: usize,
+    /// Page width in PDF points (1/72 inch).
+    pub width_pt: f32,
+    /// Page height in PDF points (1/72 inch).
+    pub height_pt: f32,
+    /// Clockwise rotation applied by the viewer: 0, 90, 180, or 270 degrees.
+    pub rotation: u16,
+    /// Whether the page carries extractable text.
+    ///
+    /// `false` usually means a scanned page: the VLM is the only way to read it.
+    pub has_text_layer: bool,
+    /// Number of raster image objects placed on the page.
+    pub image_count: usize,
+    /// Headings, tables, and figure alt text marked by the structure tree
+    /// of a tagged PDF; `None` for an untagged page. See [`crate::tagged`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<PageTags>,
+}
+
+/// Where [`crate::convert_to_layout`] writes the converted document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputLayout {
+    /// One Markdown file, as written by [`crate::convert_to_file`].
+    SingleFile { path: PathBuf },
+    /// A folder of linked notes, one per section, plus an index note; see
+    /// [`crate::vault`].
+    ObsidianVault { dir: PathBuf },
+    /// Each selected page's PNG, as sent to the model, with its Markdown in
+    /// a `.md` sidecar of the same name. Pages that failed get the PNG only.
+    PerPageFiles {
+        dir: PathBuf,
+        naming: PageFileNaming,
+    },
+}
+
+/// File names of [`OutputLayout::PerPageFiles`]. Page numbers are padded to
+/// four digits so the files sort in page order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageFileNaming {
+    /// `page-0001.png`, `page-0001.md`. Default.
+    #[default]
+    PageNumber,
+    /// The input's file stem first: `report-0001.png`, `report-0001.md`.
+    /// Lets several documents share one directory.
+    InputStem,
+}
+
+impl PageFileNaming {
+    /// File name without extension for `page_num` of `source`.
+    pub fn file_stem(&self, source: &str, page_num: usize) -> String {
+        let prefix = match self {
+            PageFileNaming::PageNumber => "page".to_string(),
+            PageFileNaming::InputStem => {
+                let name = source.rsplit(['/', '\\']).next().unwrap_or(source);
+                let stem = name.split('?').next().unwrap_or(name);
+                let stem = stem
+                    .strip_suffix(".pdf")
+                    .or_else(|| stem.strip_suffix(".PDF"))
+                    .unwrap_or(stem);
+                if stem.is_empty() || stem == "-" {
+                    "page".to_string()
+                } else {
+                    stem.to_string()
+                }
+            }
+        };
+        format!("{prefix}-{page_num:04}")
+    }
 }
 
 #[cfg(test)]
@@ -209,9 +558,31 @@ mod tests {
                 failed_pages: failed,
                 ..Default::default()
             },
+            summary: None,
+            enrichment: None,
+            pii_findings: vec![],
+            references: vec![],
+            headings: vec![],
+            fatal_error: None,
+            reproducibility: None,
         }
     }
 
+    #[test]
+    fn page_file_names() {
+        assert_eq!(
+            PageFileNaming::PageNumber.file_stem("a.pdf", 7),
+            "page-0007"
+        );
+        let naming = PageFileNaming::InputStem;
+        assert_eq!(naming.file_stem("scans/report.pdf", 12), "report-0012");
+        assert_eq!(
+            naming.file_stem("https://x.org/files/paper.pdf?dl=1", 1),
+            "paper-0001"
+        );
+        assert_eq!(naming.file_stem("-", 3), "page-0003");
+    }
+
     #[test]
     fn failed_pages_matches_stats() {
         let out = make_output(2, 8, 10);
@@ -239,4 +610,26 @@ mod tests {
             other => panic!("expected PartialFailure, got {other:?}"),
         }
     }
+
+    #[test]
+    fn latency_stats_percentiles() {
+        let samples: Vec<u64> = (1..=100).rev().collect();
+        let stats = LatencyStats::from_samples(&samples);
+        assert_eq!(stats.count, 100);
+        assert_eq!((stats.min_ms, stats.max_ms), (1, 100));
+        assert_eq!(stats.median_ms, 50);
+        assert_eq!(stats.p95_ms, 95);
+        assert_eq!(LatencyStats::from_samples(&[]), LatencyStats::default());
+        assert_eq!(LatencyStats::from_samples(&[7]).p95_ms, 7);
+    }
+
+    #[test]
+    fn into_result_err_when_stopped_early() {
+        let mut out = make_output(0, 4, 10);
+        out.fatal_error = Some("provider lost".into());
+        assert!(matches!(
+            out.into_result(),
+            Err(Pdf2MdError::PartialFailure { success: 4, .. })
+        ));
+    }
 }
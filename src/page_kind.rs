@@ -0,0 +1,368 @@
+//! What each page is: cover, contents, body, references, blank, or advert.
+//!
+//! ## Why classify pages?
+//!
+//! Page numbers are a poor way to skip the parts of a document nobody
+//! wants converted: the adverts of a scanned magazine fall on different
+//! pages in every issue, and the table of contents of a report is rebuilt
+//! from the headings anyway. With
+//! [`crate::ConversionConfigBuilder::page_classifier`] set, [`crate::convert`]
+//! runs a pre-pass over the selected pages and stores the answer in
+//! [`crate::PageResult::page_kind`];
+//! [`crate::PageSelection::ExcludeKinds`] then drops pages of the given
+//! kinds before they are rendered for conversion. Dropped pages count as
+//! skipped.
+//!
+//! Two classifiers:
+//!
+//! | Classifier | Cost | Reads | Finds adverts |
+//! |------------|------|-------|---------------|
+//! | [`PageClassifier::Heuristic`] | free | text layer | no |
+//! | [`PageClassifier::Model`] | one short call per page at 72 DPI | rendered page | yes |
+//!
+//! The heuristic leaves pages without a text layer (scans) unclassified;
+//! they are converted. Excluding kinds without choosing a classifier uses
+//! the heuristic.
+
+use crate::backend::{DescribeOptions, PagePrompt, VisionBackend};
+use crate::config::ConversionConfig;
+use crate::error::Pdf2MdError;
+use crate::pipeline::render;
+use crate::pipeline::session::PdfSession;
+use crate::prompts::PAGE_KIND_CLASSIFIER_PROMPT;
+use crate::transcript;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio_stream::wrappers::ReceiverStream;
+use tracing::{info, warn};
+
+/// Rendering DPI of the pages sent to [`PageClassifier::Model`].
+pub const CLASSIFIER_DPI: u32 = 72;
+
+/// Output token budget for the classifier's one-word answer.
+const CLASSIFIER_MAX_TOKENS: usize = 10;
+
+/// A contents line: an entry, dot leaders or spaces, then a page number
+/// (arabic, or roman after leaders).
+static TOC_LINE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\S.{2,}?(?:(?:\s*[.·…_]{2,}\s*|\s{2,})[ivxlc]{1,5}|\s*[.·…_]*\s+(\d{1,4}))$")
+        .unwrap()
+});
+
+/// A contents heading.
+static TOC_HEADING_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^(?:table of )?contents$|^sommaire$|^inhalt$").unwrap());
+
+/// A bibliography heading.
+static REFERENCES_HEADING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^(?:\d+\.?\s*)?(?:references|bibliography|works cited|literature cited)$")
+        .unwrap()
+});
+
+/// A bibliography entry: `[12] …`, `12. Smith, J.`, or `Smith, J. (2019)`.
+static REFERENCE_ENTRY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?:\[\d{1,3}\]|\d{1,3}\.\s+\p{Lu}[\p{L}'-]+,)|^\p{Lu}[\p{L}'-]+,\s.*\(\d{4}[a-z]?\)",
+    )
+    .unwrap()
+});
+
+/// What a page is, as far as skipping it is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PageKind {
+    /// Title or cover page.
+    Cover,
+    /// Table of contents.
+    Toc,
+    /// Ordinary content.
+    Body,
+    /// Bibliography or reference list.
+    References,
+    /// Nothing on the page.
+    Blank,
+    /// Full-page advertisement.
+    Advertisement,
+}
+
+impl PageKind {
+    /// Kebab-case name, as in `--pages '!toc'`.
+    pub fn name(self) -> &'static str {
+        match self {
+            PageKind::Cover => "cover",
+            PageKind::Toc => "toc",
+            PageKind::Body => "body",
+            PageKind::References => "references",
+            PageKind::Blank => "blank",
+            PageKind::Advertisement => "advertisement",
+        }
+    }
+}
+
+impl fmt::Display for PageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Parses a kind name; `contents`, `bibliography`, `ad`, and `ads` are
+/// accepted too.
+impl FromStr for PageKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "cover" => Ok(PageKind::Cover),
+            "toc" | "contents" => Ok(PageKind::Toc),
+            "body" => Ok(PageKind::Body),
+            "references" | "bibliography" => Ok(PageKind::References),
+            "blank" => Ok(PageKind::Blank),
+            "advertisement" | "ad" | "ads" => Ok(PageKind::Advertisement),
+            other => Err(format!(
+                "unknown page kind '{other}' (expected cover, toc, body, references, blank, or advertisement)"
+            )),
+        }
+    }
+}
+
+/// How pages are classified; see the module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageClassifier {
+    /// Rules over the text layer; no model calls. (default)
+    #[default]
+    Heuristic,
+    /// Ask the conversion model about a low-resolution render of each page.
+    Model,
+}
+
+/// Classify the selected pages when `config` asks for it, and drop those
+/// of the kinds `config.pages` excludes. Returns the remaining indices and
+/// the kinds found, by 0-based page index.
+pub(crate) async fn select(
+    session: &PdfSession,
+    provider: &Arc<dyn VisionBackend>,
+    config: &ConversionConfig,
+    page_indices: Vec<usize>,
+) -> Result<(Vec<usize>, HashMap<usize, PageKind>), Pdf2MdError> {
+    let excluded = config.pages.excluded_kinds();
+    if config.page_classifier.is_none() && excluded.is_empty() {
+        return Ok((page_indices, HashMap::new()));
+    }
+    let kinds = classify(session, provider, config, &page_indices).await?;
+    let selected = page_indices.len();
+    let kept: Vec<usize> = page_indices
+        .into_iter()
+        .filter(|idx| !kinds.get(idx).is_some_and(|k| excluded.contains(k)))
+        .collect();
+    if kept.len() < selected {
+        info!("Skipping {} pages of excluded kinds", selected - kept.len());
+    }
+    Ok((kept, kinds))
+}
+
+/// Classify `page_indices` (0-based) of the session's document. Pages the
+/// classifier could not place are missing from the map.
+async fn classify(
+    session: &PdfSession,
+    provider: &Arc<dyn VisionBackend>,
+    config: &ConversionConfig,
+    page_indices: &[usize],
+) -> Result<HashMap<usize, PageKind>, Pdf2MdError> {
+    let kinds = match config.page_classifier.unwrap_or_default() {
+        PageClassifier::Heuristic => {
+            let layers = session.page_text_layers(page_indices).await?;
+            page_indices
+                .iter()
+                .zip(layers)
+                .filter_map(|(&idx, (text, has_images))| {
+                    Some((idx, classify_text(&text, idx, has_images)?))
+                })
+                .collect()
+        }
+        PageClassifier::Model => {
+            classify_with_model(session, provider, config, page_indices).await?
+        }
+    };
+    info!("Classified {} of {} pages", kinds.len(), page_indices.len());
+    Ok(kinds)
+}
+
+/// One short model call per page, `concurrency` at a time.
+async fn classify_with_model(
+    session: &PdfSession,
+    provider: &Arc<dyn VisionBackend>,
+    config: &ConversionConfig,
+    page_indices: &[usize],
+) -> Result<HashMap<usize, PageKind>, Pdf2MdError> {
+    let small = ConversionConfig {
+        dpi: CLASSIFIER_DPI,
+        tile_dense_pages: false,
+        split_spreads: false,
+        ..config.clone()
+    };
+    let rx = render::spawn_lazy_render_encode_in(session, &small, page_indices, config.concurrency)
+        .await?;
+    let prompt = PagePrompt {
+        system: PAGE_KIND_CLASSIFIER_PROMPT.to_string(),
+        ..Default::default()
+    };
+    let options = DescribeOptions {
+        temperature: 0.0,
+        max_tokens: CLASSIFIER_MAX_TOKENS,
+        seed: config.seed,
+    };
+    let kinds = ReceiverStream::new(rx)
+        .map(|page| {
+            let prompt = &prompt;
+            let options = &options;
+            async move {
+                if page.blank {
+                    return Some((page.page_index, PageKind::Blank));
+                }
+                if page.render_error.is_some() {
+                    return None;
+                }
+                let answer = provider
+                    .describe_page(&page.image_data, prompt, options)
+                    .await;
+                transcript::record(
+                    config,
+                    page.page_index + 1,
+                    provider.as_ref(),
+                    &page.image_data,
+                    prompt,
+                    options,
+                    &answer,
+                );
+                match answer {
+                    Ok(completion) => Some((page.page_index, parse_kind(&completion.markdown)?)),
+                    Err(e) => {
+                        warn!("Page {} classification failed: {}", page.page_index + 1, e);
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(config.concurrency.max(1))
+        .filter_map(futures::future::ready)
+        .collect()
+        .await;
+    Ok(kinds)
+}
+
+/// The classifier's one-word answer.
+fn parse_kind(answer: &str) -> Option<PageKind> {
+    answer
+        .trim()
+        .trim_matches(|c: char| !c.is_alphabetic())
+        .parse()
+        .ok()
+}
+
+/// Classify a page from its text layer. `None` when the page has images
+/// but no text, as scans do.
+///
+/// A page of mostly contents lines (an entry ending in a page number) or
+/// headed "Contents" is [`PageKind::Toc`]; one headed "References" or of
+/// mostly bibliography entries is [`PageKind::References`]; a first page
+/// of under 60 words is [`PageKind::Cover`]. Adverts are not recognised.
+pub fn classify_text(text: &str, page_index: usize, has_images: bool) -> Option<PageKind> {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return if has_images {
+            None
+        } else {
+            Some(PageKind::Blank)
+        };
+    }
+    let share = |re: &Regex| lines.iter().filter(|l| re.is_match(l)).count() * 2 >= lines.len();
+    if TOC_HEADING_RE.is_match(lines[0]) || (lines.len() >= 5 && is_contents(&lines)) {
+        return Some(PageKind::Toc);
+    }
+    if REFERENCES_HEADING_RE.is_match(lines[0]) || (lines.len() >= 3 && share(&REFERENCE_ENTRY_RE))
+    {
+        return Some(PageKind::References);
+    }
+    let words = lines
+        .iter()
+        .map(|l| l.split_whitespace().count())
+        .sum::<usize>();
+    if page_index == 0 && words < 60 {
+        return Some(PageKind::Cover);
+    }
+    Some(PageKind::Body)
+}
+
+/// Most lines are contents lines, and their page numbers never go down
+/// (a table column of amounts or years rarely manages that).
+fn is_contents(lines: &[&str]) -> bool {
+    let mut matched = 0;
+    let mut last = 0;
+    for caps in lines.iter().filter_map(|l| TOC_LINE_RE.captures(l)) {
+        matched += 1;
+        if let Some(n) = caps.get(1).and_then(|m| m.as_str().parse::<u32>().ok()) {
+            if n < last {
+                return false;
+            }
+            last = n;
+        }
+    }
+    matched * 2 >= lines.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_layers_are_classified() {
+        let toc = "Contents\n1 Introduction ........ 1\n2 Methods ........ 7\n3 Results 12";
+        assert_eq!(classify_text(toc, 2, false), Some(PageKind::Toc));
+        let toc = "Preface  ix\nIntroduction  1\nThe Model  14\nData  33\nLimits  51\nIndex  60";
+        assert_eq!(classify_text(toc, 3, false), Some(PageKind::Toc));
+
+        let refs = "[1] Smith, J. Title. 2019.\n[2] Doe, A. Other. 2020.\n[3] Roe, B. More.";
+        assert_eq!(classify_text(refs, 9, false), Some(PageKind::References));
+        assert_eq!(
+            classify_text("References\nSome entry", 9, false),
+            Some(PageKind::References)
+        );
+
+        assert_eq!(
+            classify_text("Annual Report\n2024\nAcme Corp", 0, true),
+            Some(PageKind::Cover)
+        );
+        let body = "We measured the effect of the change on three sites over two years.";
+        assert_eq!(classify_text(body, 4, false), Some(PageKind::Body));
+
+        assert_eq!(classify_text("  \n", 4, false), Some(PageKind::Blank));
+        assert_eq!(classify_text("", 4, true), None);
+    }
+
+    #[test]
+    fn kind_names_round_trip() {
+        for kind in [
+            PageKind::Cover,
+            PageKind::Toc,
+            PageKind::Body,
+            PageKind::References,
+            PageKind::Blank,
+            PageKind::Advertisement,
+        ] {
+            assert_eq!(kind.name().parse::<PageKind>(), Ok(kind));
+        }
+        assert_eq!(parse_kind(" Ads."), Some(PageKind::Advertisement));
+        assert!("chapter".parse::<PageKind>().is_err());
+    }
+}
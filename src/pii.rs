@@ -0,0 +1,379 @@
+//! Optional PII detection and masking pass.
+//!
+//! ## Why mask after conversion?
+//!
+//! Converted Markdown often flows straight into search indexes, RAG stores,
+//! and shared drives, where personal data is a liability. This pass finds
+//! common identifiers in each page and either replaces them with a
+//! placeholder (`[EMAIL]`) or wraps them in a tag
+//! (`<span data-pii="email">…</span>`) for a downstream reviewer.
+//!
+//! Detection is deliberately conservative:
+//!
+//! | Kind | How |
+//! |------|-----|
+//! | Email | regex |
+//! | IBAN | regex + ISO 7064 mod-97 checksum |
+//! | SSN | `NNN-NN-NNNN` |
+//! | Phone | digit groups with separators, 9–15 digits, ISO dates excluded |
+//! | Name | optional text-only LLM pass ([`PiiConfig::llm_names`]) |
+//!
+//! The regex kinds run on every page as it is post-processed, in the eager
+//! and streaming APIs alike. The name pass needs the whole document, so it
+//! runs in [`crate::convert`] only, right after the regex pass and before
+//! text polish, summaries and enrichment, so those model calls never see
+//! what it masks. (With [`PiiAction::Tag`] the data stays in the text; polish
+//! then skips tagged pages.) Counts per page and kind land in
+//! [`crate::ConversionOutput::pii_findings`].
+//!
+//! [`crate::output::PageResult::raw_markdown`] is dropped from masked
+//! pages rather than masked: it predates the Unicode clean-up, and an
+//! address with a soft hyphen or full-width digits in it would slip past
+//! the patterns.
+//!
+//! Enabled with [`crate::ConversionConfigBuilder::pii`]. Regex detection
+//! cannot catch everything; treat it as a safety net, not a guarantee.
+
+use crate::config::ConversionConfig;
+use crate::output::PageResult;
+use crate::pipeline::llm;
+use crate::prompts::{pii_names_request, PII_NAMES_SYSTEM_PROMPT};
+use crate::summary::chunk_pages;
+use edgequake_llm::LLMProvider;
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// Category of personal data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Ssn,
+    Iban,
+    Name,
+}
+
+impl PiiKind {
+    /// Lowercase label used in tags and reports.
+    pub fn label(self) -> &'static str {
+        match self {
+            PiiKind::Email => "email",
+            PiiKind::Phone => "phone",
+            PiiKind::Ssn => "ssn",
+            PiiKind::Iban => "iban",
+            PiiKind::Name => "name",
+        }
+    }
+}
+
+/// What to do with each match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiAction {
+    /// Replace with a placeholder such as `[EMAIL]`. (default)
+    #[default]
+    Mask,
+    /// Keep the text, wrapped in `<span data-pii="email">…</span>`.
+    Tag,
+}
+
+/// Options for the PII pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PiiConfig {
+    /// Mask or tag. Default: mask.
+    pub action: PiiAction,
+    /// Regex kinds to detect. Default: email, phone, SSN, IBAN.
+    /// ([`PiiKind::Name`] here has no effect; use `llm_names`.)
+    pub kinds: Vec<PiiKind>,
+    /// Also ask the text model for person names and mask every occurrence.
+    /// Default: false. One call per `chunk_chars` of Markdown.
+    pub llm_names: bool,
+    /// Largest chunk of Markdown sent per name-detection request. Default: 40 000.
+    pub chunk_chars: usize,
+}
+
+impl Default for PiiConfig {
+    fn default() -> Self {
+        Self {
+            action: PiiAction::Mask,
+            kinds: vec![PiiKind::Email, PiiKind::Iban, PiiKind::Ssn, PiiKind::Phone],
+            llm_names: false,
+            chunk_chars: 40_000,
+        }
+    }
+}
+
+/// How many items of one kind were found on one page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PiiFinding {
+    /// 1-indexed page number.
+    pub page: usize,
+    pub kind: PiiKind,
+    pub count: usize,
+}
+
+static EMAIL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b[A-Z0-9._%+-]+@[A-Z0-9.-]+\.[A-Z]{2,}\b").unwrap());
+static IBAN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,3})?\b").unwrap()
+});
+static SSN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").unwrap());
+static PHONE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?)?\d{2,4}(?:[ .-]\d{2,4}){1,4}\b").unwrap()
+});
+static ISO_DATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap());
+static MARKER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"\[(?:EMAIL|PHONE|SSN|IBAN|NAME)\]|<span data-pii="[a-z]+">"#).unwrap()
+});
+
+/// Mask (or tag) the regex kinds in `text`, returning the new text and a
+/// count per kind.
+pub fn mask_text(text: &str, config: &PiiConfig) -> (String, BTreeMap<PiiKind, usize>) {
+    let mut out = text.to_string();
+    let mut counts = BTreeMap::new();
+    // IBAN and SSN before phone: their digit runs would otherwise read as numbers.
+    for kind in [PiiKind::Email, PiiKind::Iban, PiiKind::Ssn, PiiKind::Phone] {
+        if !config.kinds.contains(&kind) {
+            continue;
+        }
+        let (re, accept): (&Regex, fn(&str) -> bool) = match kind {
+            PiiKind::Email => (&*EMAIL_RE, any_match),
+            PiiKind::Iban => (&*IBAN_RE, iban_checksum_ok),
+            PiiKind::Ssn => (&*SSN_RE, any_match),
+            PiiKind::Phone => (&*PHONE_RE, plausible_phone),
+            PiiKind::Name => continue,
+        };
+        let mut n = 0;
+        out = re
+            .replace_all(&out, |caps: &regex::Captures| {
+                let m = &caps[0];
+                if accept(m) {
+                    n += 1;
+                    replacement(kind, m, config.action)
+                } else {
+                    m.to_string()
+                }
+            })
+            .into_owned();
+        if n > 0 {
+            counts.insert(kind, n);
+        }
+    }
+    (out, counts)
+}
+
+/// Apply the regex kinds to one page's `markdown` and drop its
+/// `raw_markdown`.
+pub fn mask_page(page: &mut PageResult, config: &PiiConfig) -> Vec<PiiFinding> {
+    let (masked, counts) = mask_text(&page.markdown, config);
+    page.markdown = masked;
+    page.raw_markdown = None;
+    findings(page.page_num, counts)
+}
+
+/// The placeholders and tag openings the pass leaves, in order of
+/// appearance, so later text passes can check they kept them.
+pub fn markers(text: &str) -> Vec<&str> {
+    MARKER_RE.find_iter(text).map(|m| m.as_str()).collect()
+}
+
+/// Find person names with the text model and mask them across `pages`.
+///
+/// Chunks that fail are skipped with a warning; the regex pass has already
+/// run, so a failure here only means names stay unmasked.
+pub async fn mask_names(
+    provider: &Arc<dyn LLMProvider>,
+    pages: &mut [PageResult],
+    config: &ConversionConfig,
+    pii: &PiiConfig,
+) -> Vec<PiiFinding> {
+    let chunks = chunk_pages(pages, pii.chunk_chars);
+    if chunks.is_empty() {
+        return Vec::new();
+    }
+    info!("PII: detecting names in {} chunks", chunks.len());
+    let requests: Vec<String> = chunks.iter().map(|c| pii_names_request(c)).collect();
+    let answers: Vec<_> = futures::stream::iter(requests)
+        .map(|request| async move {
+            llm::complete_text(provider, PII_NAMES_SYSTEM_PROMPT, &request, config).await
+        })
+        .buffered(config.concurrency)
+        .collect()
+        .await;
+
+    let mut names = BTreeSet::new();
+    for answer in answers {
+        match answer {
+            Ok((text, _, _)) => names.extend(parse_names(&text)),
+            Err(e) => warn!("PII: name detection failed for a chunk — {}", e),
+        }
+    }
+    if names.is_empty() {
+        return Vec::new();
+    }
+
+    // Longest first so "Mary Ann Smith" wins over "Mary Ann".
+    let mut names: Vec<String> = names.into_iter().collect();
+    names.sort_by_key(|n| std::cmp::Reverse(n.len()));
+    let alternation = names
+        .iter()
+        .map(|n| regex::escape(n))
+        .collect::<Vec<_>>()
+        .join("|");
+    let Ok(re) = Regex::new(&format!(r"\b(?:{alternation})\b")) else {
+        warn!("PII: could not build the name pattern");
+        return Vec::new();
+    };
+
+    let mut out = Vec::new();
+    for page in pages.iter_mut().filter(|p| p.error.is_none()) {
+        let mut n = 0;
+        page.markdown = re
+            .replace_all(&page.markdown, |caps: &regex::Captures| {
+                n += 1;
+                replacement(PiiKind::Name, &caps[0], pii.action)
+            })
+            .into_owned();
+        if n > 0 {
+            out.push(PiiFinding {
+                page: page.page_num,
+                kind: PiiKind::Name,
+                count: n,
+            });
+        }
+    }
+    out
+}
+
+/// Parse a JSON array of names, tolerating fences and surrounding prose.
+pub fn parse_names(text: &str) -> Vec<String> {
+    let (Some(start), Some(end)) = (text.find('['), text.rfind(']')) else {
+        return Vec::new();
+    };
+    if end < start {
+        return Vec::new();
+    }
+    serde_json::from_str::<Vec<String>>(&text[start..=end])
+        .unwrap_or_default()
+        .into_iter()
+        .map(|n| n.trim().to_string())
+        // Single letters or initials would mask far too much.
+        .filter(|n| n.chars().filter(|c| c.is_alphabetic()).count() >= 3)
+        .collect()
+}
+
+fn replacement(kind: PiiKind, original: &str, action: PiiAction) -> String {
+    match action {
+        PiiAction::Mask => format!("[{}]", kind.label().to_uppercase()),
+        PiiAction::Tag => format!(r#"<span data-pii="{}">{}</span>"#, kind.label(), original),
+    }
+}
+
+fn findings(page: usize, counts: BTreeMap<PiiKind, usize>) -> Vec<PiiFinding> {
+    counts
+        .into_iter()
+        .map(|(kind, count)| PiiFinding { page, kind, count })
+        .collect()
+}
+
+fn any_match(_: &str) -> bool {
+    true
+}
+
+/// ISO 7064 mod-97: move the first four characters to the end, map letters
+/// to 10–35, and the number must be ≡ 1 (mod 97).
+fn iban_checksum_ok(candidate: &str) -> bool {
+    let compact: String = candidate.chars().filter(|c| !c.is_whitespace()).collect();
+    if !(15..=34).contains(&compact.len()) {
+        return false;
+    }
+    let rearranged = compact[4..].chars().chain(compact[..4].chars());
+    let mut rem: u32 = 0;
+    for c in rearranged {
+        let Some(v) = c.to_digit(36) else {
+            return false;
+        };
+        rem = if v >= 10 {
+            (rem * 100 + v) % 97
+        } else {
+            (rem * 10 + v) % 97
+        };
+    }
+    rem == 1
+}
+
+fn plausible_phone(candidate: &str) -> bool {
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    (9..=15).contains(&digits) && !ISO_DATE_RE.is_match(candidate.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_regex_kinds() {
+        let text = "Mail jane.doe@example.com or call +1 (415) 555-0134. \
+                    SSN 123-45-6789, IBAN DE89 3704 0044 0532 0130 00.";
+        let (out, counts) = mask_text(text, &PiiConfig::default());
+        assert_eq!(out, "Mail [EMAIL] or call [PHONE]. SSN [SSN], IBAN [IBAN].");
+        assert_eq!(counts[&PiiKind::Email], 1);
+        assert_eq!(counts[&PiiKind::Iban], 1);
+        assert_eq!(counts[&PiiKind::Ssn], 1);
+        assert_eq!(counts[&PiiKind::Phone], 1);
+    }
+
+    #[test]
+    fn leaves_dates_and_bad_ibans_alone() {
+        let text = "Signed 2024-03-15. Ref GB00 0000 0000 0000 0000 00. Total 1,234.56";
+        let (out, counts) = mask_text(text, &PiiConfig::default());
+        assert_eq!(out, text);
+        assert!(counts.is_empty());
+    }
+
+    #[test]
+    fn tag_keeps_original_text() {
+        let config = PiiConfig {
+            action: PiiAction::Tag,
+            kinds: vec![PiiKind::Email],
+            ..Default::default()
+        };
+        let (out, _) = mask_text("a@b.io", &config);
+        assert_eq!(out, r#"<span data-pii="email">a@b.io</span>"#);
+    }
+
+    #[test]
+    fn mask_page_drops_raw_output() {
+        let mut page = PageResult {
+            page_num: 3,
+            markdown: "x@y.org".into(),
+            raw_markdown: Some("```\nx@y.org\n```".into()),
+            ..Default::default()
+        };
+        let found = mask_page(&mut page, &PiiConfig::default());
+        assert_eq!(page.markdown, "[EMAIL]");
+        assert_eq!(page.raw_markdown, None);
+        assert_eq!(markers(&page.markdown), ["[EMAIL]"]);
+        assert_eq!(
+            found,
+            vec![PiiFinding {
+                page: 3,
+                kind: PiiKind::Email,
+                count: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_names_filters_noise() {
+        let names = parse_names("```json\n[\"Ada Lovelace\", \"J\", \" Bob \"]\n```");
+        assert_eq!(names, vec!["Ada Lovelace", "Bob"]);
+        assert!(parse_names("no names").is_empty());
+    }
+}
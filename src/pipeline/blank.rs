@@ -0,0 +1,76 @@
+//! Blank-page detection for rendered pages.
+//!
+//! ## Why skip blank pages?
+//!
+//! Scanned documents are full of empty separator pages and the backs of
+//! single-sided sheets. Each one costs a full VLM call that returns nothing
+//! useful (or, worse, a hallucinated "This page is blank."). A rendered page
+//! whose pixels barely vary is almost certainly empty, and measuring that on
+//! a thumbnail takes well under a millisecond.
+//!
+//! Detection is deliberately conservative: [`crate::pipeline::render`] only
+//! asks when the page has no text layer, and the threshold tolerates scanner
+//! noise but not a single line of text. Borders, punch holes, and shadows
+//! push a page over the threshold, so at worst a blank page still gets sent.
+
+use image::DynamicImage;
+
+/// Longest thumbnail edge used for the measurement. Downscaling averages out
+/// scanner noise while a line of text still leaves a clear trace.
+const THUMBNAIL_EDGE: u32 = 256;
+
+/// Largest luminance standard deviation (0–255 scale) of a blank page.
+///
+/// Clean paper scans land around 1–3; a page holding a single line of body
+/// text measures above 10.
+pub const MAX_BLANK_STDDEV: f64 = 6.0;
+
+/// Luminance standard deviation of `image`, measured on a thumbnail.
+pub fn luminance_stddev(image: &DynamicImage) -> f64 {
+    let thumb = image.thumbnail(THUMBNAIL_EDGE, THUMBNAIL_EDGE).to_luma8();
+    let n = thumb.pixels().len();
+    if n == 0 {
+        return 0.0;
+    }
+    let (sum, sum_sq) = thumb.pixels().fold((0f64, 0f64), |(s, sq), p| {
+        let v = p.0[0] as f64;
+        (s + v, sq + v * v)
+    });
+    let mean = sum / n as f64;
+    (sum_sq / n as f64 - mean * mean).max(0.0).sqrt()
+}
+
+/// `true` when `image` is (nearly) a single flat colour.
+pub fn is_blank(image: &DynamicImage) -> bool {
+    luminance_stddev(image) <= MAX_BLANK_STDDEV
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    fn page(f: impl Fn(u32, u32) -> u8) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(850, 1100, |x, y| Luma([f(x, y)])))
+    }
+
+    #[test]
+    fn white_and_noisy_pages_are_blank() {
+        assert!(is_blank(&page(|_, _| 255)));
+        // ±4 levels of pseudo-random scanner noise
+        assert!(is_blank(&page(|x, y| 247 + ((x * 7 + y * 13) % 9) as u8)));
+    }
+
+    #[test]
+    fn a_line_of_text_is_not_blank() {
+        // A 30 px band of dark strokes across most of the width.
+        let text = page(|x, y| {
+            if (100..750).contains(&x) && (500..530).contains(&y) && x % 6 < 3 {
+                20
+            } else {
+                255
+            }
+        });
+        assert!(!is_blank(&text));
+    }
+}
@@ -1,17 +1,42 @@
-//! Image encoding: `DynamicImage` → base64 PNG wrapped in `ImageData`.
+//! Image encoding: `DynamicImage` → base64 PNG wrapped in `PageImage`.
 //!
 //! VLM APIs (OpenAI, Anthropic, Gemini) accept images as base64 data-URIs
 //! embedded in the JSON request body. PNG is chosen over JPEG because it is
 //! lossless — text crispness matters far more than file size for OCR accuracy.
 //! `detail: "high"` instructs GPT-4-class models to use the full 768-token
 //! image tile budget; without it fine print and small tables are lost.
+//!
+//! Pages are RGBA renders, four bytes per pixel. [`PngOptions`] packs them
+//! into one byte per pixel — grayscale or a 256-colour palette — which
+//! typically halves the payload of a text page without losing any text.
 
+use crate::backend::PageImage;
+use crate::config::ConversionConfig;
 use base64::{engine::general_purpose::STANDARD, Engine as _};
-use edgequake_llm::ImageData;
-use image::DynamicImage;
+use image::{DynamicImage, Rgb, RgbImage};
+use std::collections::HashMap;
 use std::io::Cursor;
 use tracing::debug;
 
+/// Pixel format of encoded pages; see [`ConversionConfig::grayscale`] and
+/// [`ConversionConfig::png_palette`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PngOptions {
+    /// 8-bit grayscale.
+    pub grayscale: bool,
+    /// Indexed colour, at most 256 colours. Ignored with `grayscale`.
+    pub palette: bool,
+}
+
+impl PngOptions {
+    pub fn for_config(config: &ConversionConfig) -> Self {
+        Self {
+            grayscale: config.grayscale,
+            palette: config.png_palette,
+        }
+    }
+}
+
 /// Encode a rasterised page as a base64 PNG ready for the VLM API.
 ///
 /// ## Why PNG?
@@ -23,14 +48,181 @@ use tracing::debug;
 /// enables up to 10 tiles (765 tokens each), allowing fine print, small tables,
 /// and math notation to be seen. `detail: "low"` forces a single 512 px
 /// overview tile and loses all fine structure.
-pub fn encode_page(img: &DynamicImage) -> Result<ImageData, image::ImageError> {
-    let mut buf = Vec::new();
-    img.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Png)?;
+pub fn encode_page(img: &DynamicImage) -> Result<PageImage, image::ImageError> {
+    encode_page_with(img, PngOptions::default())
+}
+
+/// [`encode_page`] with the pixel format set by `options`.
+pub fn encode_page_with(
+    img: &DynamicImage,
+    options: PngOptions,
+) -> Result<PageImage, image::ImageError> {
+    let buf = if options.grayscale {
+        encode_png_bytes(&DynamicImage::ImageLuma8(img.to_luma8()))?
+    } else if options.palette {
+        encode_indexed_png(&img.to_rgb8())?
+    } else {
+        encode_png_bytes(img)?
+    };
 
     let b64 = STANDARD.encode(&buf);
     debug!("Encoded image → {} bytes base64", b64.len());
 
-    Ok(ImageData::new(b64, "image/png").with_detail("high"))
+    Ok(PageImage::new(b64, "image/png").with_detail("high"))
+}
+
+/// Stable fingerprint of a rendered page: 64-bit FNV-1a over the pixel
+/// dimensions, pixel layout, and raw pixel bytes, as 16 hex digits.
+///
+/// Identical renders of the same page hash alike across runs and builds,
+/// which `DefaultHasher` does not promise. Any change to the render
+/// settings (DPI, image filter, rotation) changes the hash too.
+pub fn image_hash(img: &DynamicImage) -> String {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let color = img.color();
+    let header = [
+        img.width().to_le_bytes(),
+        img.height().to_le_bytes(),
+        u32::from(color.bytes_per_pixel()).to_le_bytes(),
+        u32::from(color.channel_count()).to_le_bytes(),
+    ];
+    let hash = header
+        .iter()
+        .flatten()
+        .chain(img.as_bytes())
+        .fold(OFFSET, |h, &b| (h ^ b as u64).wrapping_mul(PRIME));
+    format!("{hash:016x}")
+}
+
+/// Encode an image as raw PNG bytes (no base64).
+pub fn encode_png_bytes(img: &DynamicImage) -> Result<Vec<u8>, image::ImageError> {
+    let mut buf = Vec::new();
+    img.write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Png)?;
+    Ok(buf)
+}
+
+/// Encode an image as an indexed PNG (no base64).
+///
+/// An image with at most 256 colours keeps them exactly; others are reduced
+/// to 256 by median cut. Rendered text pages are ink, paper, and a few
+/// anti-aliasing greys, so they usually take the exact path.
+pub fn encode_indexed_png(img: &RgbImage) -> Result<Vec<u8>, image::ImageError> {
+    let (palette, indices) = exact_palette(img).unwrap_or_else(|| quantise(img));
+    let to_image_error = |e: png::EncodingError| {
+        image::ImageError::Encoding(image::error::EncodingError::new(
+            image::ImageFormat::Png.into(),
+            e,
+        ))
+    };
+    let mut buf = Vec::new();
+    let mut encoder = png::Encoder::new(&mut buf, img.width(), img.height());
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_palette(palette);
+    let mut writer = encoder.write_header().map_err(to_image_error)?;
+    writer.write_image_data(&indices).map_err(to_image_error)?;
+    writer.finish().map_err(to_image_error)?;
+    Ok(buf)
+}
+
+/// The image's own colours as an RGB palette, and each pixel's index;
+/// `None` when it has more than 256.
+fn exact_palette(img: &RgbImage) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut index: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut palette = Vec::new();
+    let mut indices = Vec::with_capacity(img.as_raw().len() / 3);
+    for p in img.pixels() {
+        let i = match index.get(&p.0) {
+            Some(&i) => i,
+            None if index.len() == 256 => return None,
+            None => {
+                let i = index.len() as u8;
+                index.insert(p.0, i);
+                palette.extend_from_slice(&p.0);
+                i
+            }
+        };
+        indices.push(i);
+    }
+    Some((palette, indices))
+}
+
+/// A palette of at most 256 colours for `img` and each pixel's index, by
+/// median cut: pixels are counted in 5-bit-per-channel buckets, and the box
+/// of buckets spanning the widest channel range is split at its median
+/// pixel until there are 256 boxes. Each box is coloured by its mean.
+fn quantise(img: &RgbImage) -> (Vec<u8>, Vec<u8>) {
+    let bucket = |p: &Rgb<u8>| {
+        (usize::from(p[0] >> 3) << 10) | (usize::from(p[1] >> 3) << 5) | usize::from(p[2] >> 3)
+    };
+    let mut counts = vec![0u64; 1 << 15];
+    let mut sums = vec![[0u64; 3]; 1 << 15];
+    for p in img.pixels() {
+        let b = bucket(p);
+        counts[b] += 1;
+        for (sum, &c) in sums[b].iter_mut().zip(&p.0) {
+            *sum += u64::from(c);
+        }
+    }
+    let mean = |b: usize| sums[b].map(|s| (s / counts[b]) as u8);
+    let widest = |bx: &[usize]| {
+        (0..3)
+            .map(|c| {
+                let values = bx.iter().map(|&b| mean(b)[c]);
+                let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+                (c, range)
+            })
+            .max_by_key(|&(_, range)| range)
+            .unwrap_or((0, 0))
+    };
+
+    let mut boxes: Vec<Vec<usize>> = vec![(0..counts.len()).filter(|&b| counts[b] > 0).collect()];
+    while boxes.len() < 256 {
+        let Some((i, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, bx)| bx.len() > 1)
+            .map(|(i, bx)| (i, widest(bx)))
+            .max_by_key(|&(_, (_, range))| range)
+            .map(|(i, (channel, _))| (i, channel))
+        else {
+            break;
+        };
+        let mut bx = boxes.swap_remove(i);
+        bx.sort_by_key(|&b| mean(b)[channel]);
+        let total: u64 = bx.iter().map(|&b| counts[b]).sum();
+        let mut seen = 0;
+        let median = bx
+            .iter()
+            .position(|&b| {
+                seen += counts[b];
+                seen * 2 >= total
+            })
+            .unwrap_or(0);
+        let rest = bx.split_off((median + 1).clamp(1, bx.len() - 1));
+        boxes.push(bx);
+        boxes.push(rest);
+    }
+
+    let mut lut = vec![0u8; 1 << 15];
+    let mut palette = Vec::with_capacity(boxes.len() * 3);
+    for (i, bx) in boxes.iter().enumerate() {
+        let n: u64 = bx.iter().map(|&b| counts[b]).sum();
+        let sum = bx.iter().fold([0u64; 3], |acc, &b| {
+            [
+                acc[0] + sums[b][0],
+                acc[1] + sums[b][1],
+                acc[2] + sums[b][2],
+            ]
+        });
+        palette.extend(sum.map(|s| (s / n.max(1)) as u8));
+        for &b in bx {
+            lut[b] = i as u8;
+        }
+    }
+    let indices = img.pixels().map(|p| lut[bucket(p)]).collect();
+    (palette, indices)
 }
 
 #[cfg(test)]
@@ -38,6 +230,20 @@ mod tests {
     use super::*;
     use image::{Rgba, RgbaImage};
 
+    #[test]
+    fn image_hash_tracks_pixels() {
+        let red = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([255, 0, 0, 255])));
+        let mut changed = red.to_rgba8();
+        changed.put_pixel(3, 3, Rgba([254, 0, 0, 255]));
+        let changed = DynamicImage::ImageRgba8(changed);
+        assert_eq!(image_hash(&red), image_hash(&red.clone()));
+        assert_ne!(image_hash(&red), image_hash(&changed));
+        assert_eq!(image_hash(&red).len(), 16);
+        // Same bytes, different shape.
+        let wide = DynamicImage::ImageRgba8(RgbaImage::from_pixel(8, 2, Rgba([255, 0, 0, 255])));
+        assert_ne!(image_hash(&red), image_hash(&wide));
+    }
+
     #[test]
     fn encode_small_image() {
         let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(10, 10, Rgba([255, 0, 0, 255])));
@@ -48,4 +254,52 @@ mod tests {
         let decoded = STANDARD.decode(&data.data).expect("valid base64");
         assert!(!decoded.is_empty());
     }
+
+    #[test]
+    fn packed_formats_decode_to_the_page() {
+        // Black text on white with grey anti-aliasing: 3 colours.
+        let mut page = RgbaImage::from_pixel(40, 20, Rgba([255, 255, 255, 255]));
+        for x in 5..35 {
+            page.put_pixel(x, 9, Rgba([0, 0, 0, 255]));
+            page.put_pixel(x, 10, Rgba([128, 128, 128, 255]));
+        }
+        let page = DynamicImage::ImageRgba8(page);
+        let decode = |options| {
+            let data = encode_page_with(&page, options).expect("encode should succeed");
+            image::load_from_memory(&STANDARD.decode(&data.data).unwrap()).unwrap()
+        };
+
+        let gray = decode(PngOptions {
+            grayscale: true,
+            palette: false,
+        });
+        assert_eq!(gray.color(), image::ColorType::L8);
+        assert_eq!(gray.to_luma8(), page.to_luma8());
+
+        let indexed = decode(PngOptions {
+            grayscale: false,
+            palette: true,
+        });
+        assert_eq!(indexed.to_rgb8(), page.to_rgb8());
+    }
+
+    #[test]
+    fn many_colours_are_quantised_closely() {
+        let img = RgbImage::from_fn(64, 64, |x, y| Rgb([(x * 4) as u8, (y * 4) as u8, 100]));
+        assert!(exact_palette(&img).is_none());
+        let bytes = encode_indexed_png(&img).expect("encode should succeed");
+        let back = image::load_from_memory(&bytes).unwrap().to_rgb8();
+        for (a, b) in img.pixels().zip(back.pixels()) {
+            for (x, y) in a.0.iter().zip(&b.0) {
+                assert!(x.abs_diff(*y) <= 24, "{a:?} became {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn png_bytes_have_png_signature() {
+        let img = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, Rgba([0, 0, 0, 255])));
+        let bytes = encode_png_bytes(&img).expect("encode should succeed");
+        assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n");
+    }
 }
@@ -0,0 +1,517 @@
+//! Rewrite page Markdown for the configured [`MarkdownFlavor`].
+//!
+//! The model writes GitHub Flavored Markdown by habit even when the prompt
+//! asks for another dialect, so the flavor rules run after the generic
+//! cleanup as a deterministic safety net. GFM output, the default, is left
+//! as the generic cleanup made it; for the other flavors:
+//!
+//! - **Callouts** in any of the known syntaxes — GFM alerts (`> [!NOTE]`),
+//!   colon fences (`:::note`, `::: warning`) — are rewritten to the flavor's
+//!   own; see the table on [`MarkdownFlavor`].
+//! - **CommonMark** has no pipe tables or strikethrough: tables become HTML
+//!   `<table>` blocks and `~~x~~` becomes `<del>x</del>`.
+//! - **MDX** parses `{…}` as JavaScript and `<…>` as JSX, so one stray brace
+//!   in a scanned page fails the whole site build. Outside code and math,
+//!   braces and any `<` that does not open a known HTML tag are
+//!   backslash-escaped, HTML comments become `{/* … */}`, autolinks become
+//!   links, void tags are self-closed, and `class`/`style` attributes are
+//!   fixed or dropped.
+//!
+//! Every rule is idempotent, so reprocessing a page changes nothing.
+
+use crate::config::MarkdownFlavor;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// `> [!NOTE]` opening a GFM alert.
+static ALERT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^>\s*\[!(note|tip|important|warning|caution)\]\s*$").unwrap());
+/// `:::note`, `::: warning Title`, or `::: {.tip}` opening a colon fence.
+static COLON_OPEN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^:::\s*\{?\.?([A-Za-z]+)\}?").unwrap());
+/// `~~struck~~`, which CommonMark lacks.
+static STRIKE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"~~([^~\n]+)~~").unwrap());
+static AUTOLINK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^<((?:https?|mailto):[^<>\s]+)>").unwrap());
+static TAG_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^</?([A-Za-z][A-Za-z0-9]*)(?:\s+[^<>]*?)?\s*/?>"#).unwrap());
+static STYLE_ATTR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\s+style\s*=\s*("[^"]*"|'[^']*')"#).unwrap());
+static CLASS_ATTR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\s)class(\s*=)").unwrap());
+
+/// HTML elements passed through to MDX as JSX.
+const HTML_TAGS: [&str; 41] = [
+    "a",
+    "abbr",
+    "b",
+    "br",
+    "caption",
+    "code",
+    "col",
+    "colgroup",
+    "dd",
+    "del",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "kbd",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "s",
+    "span",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "u",
+];
+const VOID_TAGS: [&str; 4] = ["br", "col", "hr", "img"];
+
+/// The callout kinds, in GFM's names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Callout {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl Callout {
+    /// Any flavor's name for a kind: GFM's, Docusaurus's (`info`,
+    /// `danger`), or Pandoc's.
+    fn parse(name: &str) -> Option<Callout> {
+        match name.to_ascii_lowercase().as_str() {
+            "note" => Some(Callout::Note),
+            "tip" | "hint" => Some(Callout::Tip),
+            "important" | "info" => Some(Callout::Important),
+            "warning" => Some(Callout::Warning),
+            "caution" | "danger" => Some(Callout::Caution),
+            _ => None,
+        }
+    }
+
+    fn gfm(self) -> &'static str {
+        match self {
+            Callout::Note => "NOTE",
+            Callout::Tip => "TIP",
+            Callout::Important => "IMPORTANT",
+            Callout::Warning => "WARNING",
+            Callout::Caution => "CAUTION",
+        }
+    }
+
+    fn docusaurus(self) -> &'static str {
+        match self {
+            Callout::Note => "note",
+            Callout::Tip => "tip",
+            Callout::Important => "info",
+            Callout::Warning => "warning",
+            Callout::Caution => "danger",
+        }
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Callout::Note => "Note",
+            Callout::Tip => "Tip",
+            Callout::Important => "Important",
+            Callout::Warning => "Warning",
+            Callout::Caution => "Caution",
+        }
+    }
+}
+
+/// Rewrite one page (or any Markdown fragment) for `flavor`.
+pub fn apply(markdown: &str, flavor: MarkdownFlavor) -> String {
+    if flavor == MarkdownFlavor::Gfm {
+        return markdown.to_string();
+    }
+    let markdown = convert_callouts(markdown, flavor);
+    let lines = fenced_lines(&markdown);
+    let out: Vec<String> = match flavor {
+        MarkdownFlavor::Gfm | MarkdownFlavor::Pandoc => return markdown,
+        MarkdownFlavor::CommonMark => commonmark(&lines),
+        MarkdownFlavor::Mdx => lines
+            .iter()
+            .map(|&(line, fenced)| {
+                if fenced {
+                    line.to_string()
+                } else {
+                    escape_mdx(line)
+                }
+            })
+            .collect(),
+    };
+    let mut out = out.join("\n");
+    if markdown.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// An HTML-style comment in `flavor`: `{/* text */}` for MDX, where HTML
+/// comments do not parse.
+pub fn comment(text: &str, flavor: MarkdownFlavor) -> String {
+    match flavor {
+        MarkdownFlavor::Mdx => format!("{{/* {text} */}}"),
+        _ => format!("<!-- {text} -->"),
+    }
+}
+
+/// Lines of `markdown` with whether each is inside a fenced code block.
+fn fenced_lines(markdown: &str) -> Vec<(&str, bool)> {
+    let mut in_fence = false;
+    markdown
+        .lines()
+        .map(|line| {
+            let t = line.trim_start();
+            if t.starts_with("```") || t.starts_with("~~~") {
+                in_fence = !in_fence;
+                return (line, true);
+            }
+            (line, in_fence)
+        })
+        .collect()
+}
+
+/// Find callouts in any syntax and write them in `flavor`'s.
+fn convert_callouts(markdown: &str, flavor: MarkdownFlavor) -> String {
+    let lines = fenced_lines(markdown);
+    let mut out: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let (line, fenced) = lines[i];
+        let alert = ALERT_RE.captures(line).and_then(|c| Callout::parse(&c[1]));
+        let colon = COLON_OPEN_RE
+            .captures(line)
+            .and_then(|c| Callout::parse(&c[1]));
+        let (kind, body, next) = match (fenced, alert, colon) {
+            (false, Some(kind), _) => {
+                let mut j = i + 1;
+                let mut body = Vec::new();
+                while j < lines.len() && lines[j].0.trim_start().starts_with('>') {
+                    let t = lines[j].0.trim_start()[1..].to_string();
+                    body.push(t.strip_prefix(' ').map(str::to_string).unwrap_or(t));
+                    j += 1;
+                }
+                (kind, body, j)
+            }
+            (false, None, Some(kind)) => {
+                let close = (i + 1..lines.len()).find(|&j| lines[j].0.trim() == ":::");
+                let Some(close) = close else {
+                    out.push(line.to_string());
+                    i += 1;
+                    continue;
+                };
+                let body = lines[i + 1..close]
+                    .iter()
+                    .map(|(l, _)| l.to_string())
+                    .collect();
+                (kind, body, close + 1)
+            }
+            _ => {
+                out.push(line.to_string());
+                i += 1;
+                continue;
+            }
+        };
+        out.extend(render_callout(kind, &body, flavor));
+        i = next;
+    }
+    let mut text = out.join("\n");
+    if markdown.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+fn render_callout(kind: Callout, body: &[String], flavor: MarkdownFlavor) -> Vec<String> {
+    let quoted = |l: &String| {
+        if l.is_empty() {
+            ">".to_string()
+        } else {
+            format!("> {l}")
+        }
+    };
+    let mut out = Vec::with_capacity(body.len() + 2);
+    match flavor {
+        MarkdownFlavor::Gfm => {
+            out.push(format!("> [!{}]", kind.gfm()));
+            out.extend(body.iter().map(quoted));
+        }
+        MarkdownFlavor::CommonMark => {
+            out.push(format!("> **{}**", kind.title()));
+            out.push(">".to_string());
+            out.extend(body.iter().map(quoted));
+        }
+        MarkdownFlavor::Pandoc => {
+            out.push(format!("::: {}", kind.gfm().to_ascii_lowercase()));
+            out.extend(body.iter().cloned());
+            out.push(":::".to_string());
+        }
+        MarkdownFlavor::Mdx => {
+            out.push(format!(":::{}", kind.docusaurus()));
+            out.extend(body.iter().cloned());
+            out.push(":::".to_string());
+        }
+    }
+    out
+}
+
+/// CommonMark rules: pipe tables to HTML, strikethrough to `<del>`.
+fn commonmark(lines: &[(&str, bool)]) -> Vec<String> {
+    let mut out = Vec::with_capacity(lines.len());
+    let mut i = 0;
+    while i < lines.len() {
+        let (line, fenced) = lines[i];
+        let is_table = !fenced
+            && line.trim_start().starts_with('|')
+            && lines
+                .get(i + 1)
+                .filter(|(_, f)| !f)
+                .and_then(|(l, _)| alignments(l))
+                .is_some();
+        if !is_table {
+            out.push(if fenced {
+                line.to_string()
+            } else {
+                STRIKE_RE.replace_all(line, "<del>$1</del>").into_owned()
+            });
+            i += 1;
+            continue;
+        }
+        let aligns = alignments(lines[i + 1].0).unwrap_or_default();
+        let mut end = i + 2;
+        while end < lines.len() && !lines[end].1 && lines[end].0.trim_start().starts_with('|') {
+            end += 1;
+        }
+        let row = |line: &str, cell: &str| {
+            let cells: String = split_row(line)
+                .iter()
+                .enumerate()
+                .map(|(k, text)| (k, STRIKE_RE.replace_all(text, "<del>$1</del>")))
+                .map(|(k, text)| match aligns.get(k).copied().flatten() {
+                    Some(a) => format!("<{cell} align=\"{a}\">{text}</{cell}>"),
+                    None => format!("<{cell}>{text}</{cell}>"),
+                })
+                .collect();
+            format!("<tr>{cells}</tr>")
+        };
+        out.push("<table>".to_string());
+        out.push("<thead>".to_string());
+        out.push(row(line, "th"));
+        out.push("</thead>".to_string());
+        if end > i + 2 {
+            out.push("<tbody>".to_string());
+            out.extend(lines[i + 2..end].iter().map(|(l, _)| row(l, "td")));
+            out.push("</tbody>".to_string());
+        }
+        out.push("</table>".to_string());
+        i = end;
+    }
+    out
+}
+
+/// Column alignments of a delimiter row such as `|:---|---:|`, or `None`
+/// when `line` is not one.
+pub(crate) fn alignments(line: &str) -> Option<Vec<Option<&'static str>>> {
+    let cells = split_row(line);
+    let valid = !cells.is_empty()
+        && cells.iter().all(|c| {
+            let dashes = c.trim_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|ch| ch == '-')
+        });
+    valid.then(|| {
+        cells
+            .iter()
+            .map(|c| match (c.starts_with(':'), c.ends_with(':')) {
+                (true, true) => Some("center"),
+                (true, false) => Some("left"),
+                (false, true) => Some("right"),
+                (false, false) => None,
+            })
+            .collect()
+    })
+}
+
+/// Trimmed cells of a pipe-table row; `\|` does not split.
+pub(crate) fn split_row(line: &str) -> Vec<String> {
+    let t = line.trim();
+    let t = t.strip_prefix('|').unwrap_or(t);
+    let t = t
+        .strip_suffix('|')
+        .filter(|s| !s.ends_with('\\'))
+        .unwrap_or(t);
+    let mut cells = vec![String::new()];
+    let mut chars = t.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cells.last_mut().unwrap().push('|');
+                chars.next();
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(c),
+        }
+    }
+    cells.into_iter().map(|c| c.trim().to_string()).collect()
+}
+
+/// Make one line outside code fences safe for MDX. See the module docs.
+fn escape_mdx(line: &str) -> String {
+    let mut out = String::with_capacity(line.len() + 8);
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &line[i..];
+        let c = rest.chars().next().expect("i is on a char boundary");
+        let taken = match c {
+            // Escapes, including the ones this pass wrote.
+            '\\' => {
+                let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+                out.push_str(&rest[..len]);
+                len
+            }
+            '`' => {
+                let run = rest.len() - rest.trim_start_matches('`').len();
+                let fence = &rest[..run];
+                let len = rest[run..]
+                    .find(fence)
+                    .map_or(run, |close| run + close + run);
+                out.push_str(&rest[..len]);
+                len
+            }
+            '$' => {
+                let delim = if rest.starts_with("$$") { "$$" } else { "$" };
+                let len = rest[delim.len()..]
+                    .find(delim)
+                    .map_or(delim.len(), |close| 2 * delim.len() + close);
+                out.push_str(&rest[..len]);
+                len
+            }
+            // Comments this pass wrote.
+            '{' if rest.starts_with("{/*") && rest.contains("*/}") => {
+                let len = rest.find("*/}").unwrap() + 3;
+                out.push_str(&rest[..len]);
+                len
+            }
+            '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+                1
+            }
+            '<' => escape_angle(rest, &mut out),
+            _ => {
+                out.push(c);
+                c.len_utf8()
+            }
+        };
+        i += taken;
+    }
+    out
+}
+
+/// Handle the `<` at the start of `rest`; returns the bytes consumed.
+fn escape_angle(rest: &str, out: &mut String) -> usize {
+    if let Some(end) = rest.strip_prefix("<!--").and_then(|r| r.find("-->")) {
+        out.push_str(&comment(rest[4..4 + end].trim(), MarkdownFlavor::Mdx));
+        return 4 + end + 3;
+    }
+    if let Some(caps) = AUTOLINK_RE.captures(rest) {
+        out.push_str(&format!("[{0}]({0})", &caps[1]));
+        return caps[0].len();
+    }
+    if let Some(caps) = TAG_RE.captures(rest) {
+        let name = caps[1].to_ascii_lowercase();
+        if HTML_TAGS.contains(&name.as_str()) {
+            let tag = &caps[0];
+            let tag = STYLE_ATTR_RE.replace_all(tag, "");
+            let mut tag = CLASS_ATTR_RE
+                .replace_all(&tag, "${1}className${2}")
+                .into_owned();
+            if VOID_TAGS.contains(&name.as_str()) && !tag.ends_with("/>") {
+                tag.pop();
+                let trimmed = tag.trim_end().len();
+                tag.truncate(trimmed);
+                tag.push_str(" />");
+            }
+            out.push_str(&tag);
+            return caps[0].len();
+        }
+    }
+    out.push_str("\\<");
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mdx_escapes_braces_and_stray_angles() {
+        let md = "Set {x} if a < b or x<y.\nUse `{raw}` and $f(x) = \\{1\\}$ as is.\n";
+        assert_eq!(
+            apply(md, MarkdownFlavor::Mdx),
+            "Set \\{x\\} if a \\< b or x\\<y.\nUse `{raw}` and $f(x) = \\{1\\}$ as is.\n"
+        );
+    }
+
+    #[test]
+    fn mdx_rewrites_html_for_jsx() {
+        let md = "Line<br>break <!-- page 3 --> <https://example.com>\n<td class=\"x\" style=\"color:red\">1</td>\n```\n<!-- code -->\n```\n";
+        let out = apply(md, MarkdownFlavor::Mdx);
+        assert_eq!(
+            out,
+            "Line<br />break {/* page 3 */} [https://example.com](https://example.com)\n<td className=\"x\">1</td>\n```\n<!-- code -->\n```\n"
+        );
+        assert_eq!(apply(&out, MarkdownFlavor::Mdx), out);
+    }
+
+    #[test]
+    fn callouts_follow_the_flavor() {
+        let gfm = "Intro\n\n> [!WARNING]\n> Hot surface.\n> Do not touch.\n\nAfter\n";
+        assert_eq!(
+            apply(gfm, MarkdownFlavor::Mdx),
+            "Intro\n\n:::warning\nHot surface.\nDo not touch.\n:::\n\nAfter\n"
+        );
+        assert_eq!(
+            apply(gfm, MarkdownFlavor::Pandoc),
+            "Intro\n\n::: warning\nHot surface.\nDo not touch.\n:::\n\nAfter\n"
+        );
+        assert_eq!(
+            apply(":::note\nA.\n:::\n", MarkdownFlavor::Pandoc),
+            "::: note\nA.\n:::\n"
+        );
+        assert_eq!(
+            apply(":::danger\nStop.\n:::\n", MarkdownFlavor::CommonMark),
+            "> **Caution**\n>\n> Stop.\n"
+        );
+    }
+
+    #[test]
+    fn commonmark_turns_tables_into_html() {
+        let md = "| Item | Price |\n|:---|---:|\n| Tea \\| milk | ~~4~~ 3 |\n\nDone ~~now~~.\n";
+        assert_eq!(
+            apply(md, MarkdownFlavor::CommonMark),
+            "<table>\n<thead>\n<tr><th align=\"left\">Item</th><th align=\"right\">Price</th></tr>\n</thead>\n<tbody>\n<tr><td align=\"left\">Tea | milk</td><td align=\"right\"><del>4</del> 3</td></tr>\n</tbody>\n</table>\n\nDone <del>now</del>.\n"
+        );
+    }
+}
@@ -197,4 +197,46 @@ mod tests {
             vec![0, 2] // deduplicated and sorted
         );
     }
+
+    #[test]
+    fn test_page_selection_steps_and_exclusions() {
+        use crate::config::PageSelection;
+
+        assert_eq!(PageSelection::Odd.to_indices(5), vec![0, 2, 4]);
+        assert_eq!(PageSelection::Even.to_indices(5), vec![1, 3]);
+        assert_eq!(PageSelection::EveryNth(3).to_indices(7), vec![0, 3, 6]);
+        assert_eq!(
+            PageSelection::StepRange(2, 100, 2).to_indices(7),
+            vec![1, 3, 5]
+        );
+        assert_eq!(PageSelection::Last(2).to_indices(5), vec![3, 4]);
+        assert_eq!(PageSelection::Last(9).to_indices(3), vec![0, 1, 2]);
+        assert_eq!(
+            PageSelection::Exclude(vec![2, 9]).to_indices(4),
+            vec![0, 2, 3]
+        );
+        let union = PageSelection::Union(vec![
+            PageSelection::Range(1, 3),
+            PageSelection::Last(1),
+            PageSelection::Exclude(vec![2]),
+        ]);
+        assert_eq!(union.to_indices(6), vec![0, 2, 5]);
+        assert!(union.excluded_kinds().is_empty());
+    }
+
+    #[test]
+    fn test_page_selection_kind_exclusions_keep_indices() {
+        use crate::config::PageSelection;
+        use crate::page_kind::PageKind;
+
+        let kinds = PageSelection::ExcludeKinds(vec![PageKind::Toc]);
+        assert_eq!(kinds.to_indices(3), vec![0, 1, 2]);
+        let union = PageSelection::Union(vec![
+            PageSelection::Range(2, 4),
+            PageSelection::ExcludeKinds(vec![PageKind::Advertisement]),
+            PageSelection::Exclude(vec![3]),
+        ]);
+        assert_eq!(union.to_indices(6), vec![1, 3]);
+        assert_eq!(union.excluded_kinds(), vec![PageKind::Advertisement]);
+    }
 }
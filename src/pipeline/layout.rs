@@ -0,0 +1,189 @@
+//! Page layout analysis from the PDF text layer.
+//!
+//! ## Why look at geometry at all?
+//!
+//! Vision models read a two-column academic paper line by line across the
+//! gutter surprisingly often, interleaving sentences from both columns. The
+//! PDF text layer already tells us where the text sits, so a cheap histogram
+//! over segment x-extents is enough to spot a 2- or 3-column layout and tell
+//! the model, up front, to read each column top to bottom before moving on.
+//!
+//! Detection is deliberately conservative: scanned pages (no text layer),
+//! sparse pages, and anything ambiguous report a single column so the prompt
+//! is left untouched.
+//!
+//! The same text layer also reveals sideways content: a landscape table
+//! printed on a portrait page has its characters at 90° or 270°, and the
+//! model reads such an image far worse than an upright one.
+//! [`detect_rotation`] turns the character angles into the clockwise turn
+//! that makes the rendered page upright.
+
+/// Horizontal resolution of the coverage histogram.
+const BINS: usize = 240;
+
+/// Segments wider than this fraction of the page (titles, abstracts,
+/// full-width figures' captions) span the gutter and are ignored.
+const MAX_SEGMENT_FRACTION: f32 = 0.6;
+
+/// Fewer text segments than this is too little evidence to call a layout.
+const MIN_SEGMENTS: usize = 12;
+
+/// A gutter must be at least this wide, as a fraction of the page width.
+const MIN_GUTTER_FRACTION: f32 = 0.015;
+
+/// Estimate the number of text columns (1–3) on a page.
+///
+/// `segments` are the `(left, right)` x-coordinates of text-layer segments in
+/// points; `page_width` is the page width in points.
+pub fn detect_columns(segments: &[(f32, f32)], page_width: f32) -> usize {
+    if page_width <= 0.0 {
+        return 1;
+    }
+    let narrow: Vec<(f32, f32)> = segments
+        .iter()
+        .copied()
+        .filter(|&(l, r)| r > l && (r - l) / page_width <= MAX_SEGMENT_FRACTION)
+        .collect();
+    if narrow.len() < MIN_SEGMENTS {
+        return 1;
+    }
+
+    // ── Coverage histogram ───────────────────────────────────────────────
+    let bin_of = |x: f32| (((x / page_width) * BINS as f32) as isize).clamp(0, BINS as isize - 1);
+    let mut coverage = [0usize; BINS];
+    for &(l, r) in &narrow {
+        for bin in bin_of(l)..=bin_of(r) {
+            coverage[bin as usize] += 1;
+        }
+    }
+
+    // Only look for gutters between the outermost text, so page margins
+    // are not mistaken for them.
+    let Some(first) = coverage.iter().position(|&c| c > 0) else {
+        return 1;
+    };
+    let last = coverage.iter().rposition(|&c| c > 0).unwrap_or(first);
+
+    // A bin is "empty" when almost no segments cross it.
+    let threshold = (narrow.len() / 20).max(1);
+    let min_gutter_bins = ((MIN_GUTTER_FRACTION * BINS as f32).ceil() as usize).max(1);
+
+    let mut gutters = 0;
+    let mut run = 0;
+    for &c in &coverage[first..=last] {
+        if c < threshold {
+            run += 1;
+        } else {
+            if run >= min_gutter_bins {
+                gutters += 1;
+            }
+            run = 0;
+        }
+    }
+
+    (gutters + 1).min(3)
+}
+
+/// Fewer non-whitespace characters than this is too little evidence to
+/// call an orientation.
+const MIN_ROTATION_CHARS: usize = 20;
+
+/// Share of characters that must agree on one orientation.
+const MIN_ROTATION_SHARE: f32 = 0.6;
+
+/// Clockwise degrees (0, 90, 180, or 270) that turn a rendered page upright.
+///
+/// `char_angles` are the counter-clockwise angles (degrees) of the page's
+/// text characters in PDF user space, as reported by pdfium; `page_rotation`
+/// is the page's own `/Rotate`, which rendering already applies. Returns 0
+/// when the text is upright, too sparse, or mixed.
+pub fn detect_rotation(char_angles: &[f32], page_rotation: u16) -> u16 {
+    if char_angles.len() < MIN_ROTATION_CHARS {
+        return 0;
+    }
+    // Quarter turns counter-clockwise as seen in the rendered image.
+    let mut votes = [0usize; 4];
+    for &angle in char_angles {
+        let visual = angle - page_rotation as f32;
+        let quarter = (visual / 90.0).round().rem_euclid(4.0) as usize;
+        votes[quarter % 4] += 1;
+    }
+    let (quarter, &count) = votes
+        .iter()
+        .enumerate()
+        .max_by_key(|&(_, c)| *c)
+        .unwrap_or((0, &0));
+    if (count as f32) < MIN_ROTATION_SHARE * char_angles.len() as f32 {
+        return 0;
+    }
+    // Text turned n quarters counter-clockwise needs n quarters clockwise.
+    (quarter as u16) * 90
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` lines per column, each spanning the full column width.
+    fn columns(bounds: &[(f32, f32)], n: usize) -> Vec<(f32, f32)> {
+        bounds
+            .iter()
+            .flat_map(|&b| std::iter::repeat_n(b, n))
+            .collect()
+    }
+
+    #[test]
+    fn single_column_page() {
+        let segs = columns(&[(72.0, 400.0)], 40);
+        assert_eq!(detect_columns(&segs, 612.0), 1);
+    }
+
+    #[test]
+    fn two_column_paper() {
+        let segs = columns(&[(54.0, 297.0), (315.0, 558.0)], 40);
+        assert_eq!(detect_columns(&segs, 612.0), 2);
+    }
+
+    #[test]
+    fn three_column_newsletter() {
+        let segs = columns(&[(36.0, 200.0), (212.0, 400.0), (412.0, 576.0)], 30);
+        assert_eq!(detect_columns(&segs, 612.0), 3);
+    }
+
+    #[test]
+    fn full_width_title_does_not_hide_gutter() {
+        let mut segs = columns(&[(54.0, 297.0), (315.0, 558.0)], 40);
+        segs.push((54.0, 558.0));
+        assert_eq!(detect_columns(&segs, 612.0), 2);
+    }
+
+    #[test]
+    fn sparse_page_is_single_column() {
+        let segs = columns(&[(54.0, 297.0), (315.0, 558.0)], 3);
+        assert_eq!(detect_columns(&segs, 612.0), 1);
+    }
+
+    #[test]
+    fn upright_and_sparse_text_needs_no_rotation() {
+        assert_eq!(detect_rotation(&[0.0; 100], 0), 0);
+        assert_eq!(detect_rotation(&[90.0; 5], 0), 0);
+    }
+
+    #[test]
+    fn sideways_text_is_turned_upright() {
+        // Reads bottom to top: turn clockwise.
+        assert_eq!(detect_rotation(&[90.0; 100], 0), 90);
+        // Reads top to bottom: turn counter-clockwise (270 clockwise).
+        assert_eq!(detect_rotation(&[270.0; 100], 0), 270);
+        assert_eq!(detect_rotation(&[180.0; 100], 0), 180);
+        // /Rotate 90 already makes this text upright when rendered.
+        assert_eq!(detect_rotation(&[90.0; 100], 90), 0);
+    }
+
+    #[test]
+    fn mixed_orientation_is_left_alone() {
+        let mut angles = vec![0.0; 50];
+        angles.extend([90.0; 50]);
+        assert_eq!(detect_rotation(&angles, 0), 0);
+    }
+}
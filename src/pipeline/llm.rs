@@ -1,9 +1,11 @@
-//! VLM interaction: build vision messages and call the provider.
+//! VLM interaction: build the page prompt and call the vision backend.
 //!
 //! This module converts a rasterised page image into a VLM API call and
 //! returns structured Markdown. It is intentionally thin — all prompt
 //! engineering lives in [`crate::prompts`] so it can be changed without
-//! touching retry or error-handling logic here.
+//! touching retry or error-handling logic here. The call itself goes through
+//! [`crate::backend::VisionBackend`], so retries and fallbacks apply equally
+//! to edgequake-llm providers and custom backends.
 //!
 //! ## Retry Strategy
 //!
@@ -11,25 +13,139 @@
 //! concurrent load. Exponential backoff (`retry_backoff_ms * 2^attempt`)
 //! avoids thundering-herd: with 500 ms base and 3 retries the wait sequence
 //! is 500 ms → 1 s → 2 s, totalling < 4 s of back-off per page.
+//!
+//! Not every failure deserves a retry, so each error is first classified
+//! (see [`classify_error`]):
+//!
+//! | Status           | Retried?                                 | Surfaced as                  |
+//! |------------------|------------------------------------------|------------------------------|
+//! | 429              | yes, waiting `Retry-After` when given    | `PageError::RateLimited`     |
+//! | 401 / 403        | never, not even on fallback models       | `PageError::AuthFailed`      |
+//! | content filter   | not on the same model; fallbacks are     | `PageError::ContentFiltered` |
+//! | refusal in text  | once, with a firmer prompt; then fallbacks | `PageError::ModelRefusal`  |
+//! | 400              | not on the same model; fallbacks are     | `PageError::LlmFailed`       |
+//! | unparseable body | yes, exponential backoff                 | `PageError::InvalidResponse` |
+//! | 5xx / other      | yes, exponential backoff                 | `PageError::LlmFailed`       |
+//!
+//! Providers report errors as text, so classification parses the status code
+//! and `Retry-After` hint out of the error message. A completion that comes
+//! back empty because a safety filter stopped it, or because it used its
+//! whole `max_tokens` budget without producing text, fails the page as
+//! `PageError::ContentFiltered` or `PageError::Truncated` and moves on to
+//! the fallbacks.
+//!
+//! Some models refuse in prose instead: "I'm sorry, I can't help with
+//! that", or "This image appears to contain a table…" in place of the
+//! table. [`detect_refusal`] spots such an opening paragraph; the page is
+//! re-asked once with [`crate::prompts::REFUSAL_RETRY_RULES`] appended, and
+//! a second refusal fails it as `PageError::ModelRefusal` so the text never
+//! lands in the document. A describing sentence followed by a real
+//! transcription is not a refusal: [`description_rest`] drops the sentence
+//! and the page keeps the rest. The re-ask is sent straight away and does not use
+//! up one of `max_retries`: it is a different question, not a retry, so it
+//! happens even with `max_retries = 0`.
+//!
+//! ## Model Fallback
+//!
+//! When [`ConversionConfig::fallback_models`] is set, which side falls back
+//! depends on the provider:
+//!
+//! - **OpenRouter**: the models go to OpenRouter in the request's `models`
+//!   array ([`crate::openrouter`]), which tries them in order server-side.
+//!   The page gets one backend here, and the loop below has no fallbacks.
+//! - **Every other provider**: a page that exhausts its retries on the
+//!   primary provider is re-sent to each fallback provider in order (each
+//!   with its own retry budget).
 
+use crate::backend::{
+    completion_options, DescribeOptions, PageCompletion, PageImage, PagePrompt, VisionBackend,
+};
 use crate::config::ConversionConfig;
+use crate::error::{PageError, Pdf2MdError};
+use crate::figures;
+use crate::metrics::{LlmCallMetrics, PipelineStage};
 use crate::output::PageResult;
-use crate::prompts::{maintain_format_context, DEFAULT_SYSTEM_PROMPT};
-use edgequake_llm::{ChatMessage, CompletionOptions, ImageData, LLMProvider};
+use crate::pipeline::render::EncodedPage;
+use crate::prompts::{
+    column_layout_hint, maintain_format_context, spread_context, structure_hint, tile_context,
+    REFUSAL_RETRY_RULES,
+};
+use crate::tagged;
+use crate::transcript;
+use edgequake_llm::{ChatMessage, LLMProvider};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::VecDeque;
 use std::sync::Arc;
 use std::time::Instant;
 use tokio::time::{sleep, Duration};
-use tracing::{debug, warn};
+use tracing::{debug, field, info_span, warn, Instrument};
+
+/// Sliding window of recently converted pages used as `maintain_format` context.
+///
+/// Holds the Markdown of the last [`ConversionConfig::context_window_pages`]
+/// successful pages; older pages fall out as new ones are pushed, so the
+/// context — and its token cost — stays bounded however long the document.
+#[derive(Debug, Clone)]
+pub struct PriorPages {
+    capacity: usize,
+    pages: VecDeque<String>,
+}
+
+impl PriorPages {
+    /// Create an empty window holding at most `capacity` pages (minimum 1).
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            pages: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Window sized from [`ConversionConfig::context_window_pages`].
+    pub fn for_config(config: &ConversionConfig) -> Self {
+        Self::new(config.context_window_pages)
+    }
+
+    /// Record a successfully converted page, evicting the oldest if full.
+    pub fn push(&mut self, markdown: &str) {
+        if markdown.trim().is_empty() {
+            return;
+        }
+        if self.pages.len() == self.capacity {
+            self.pages.pop_front();
+        }
+        self.pages.push_back(markdown.to_string());
+    }
+
+    /// The windowed pages joined oldest-first, or `None` before the first page.
+    pub fn context(&self) -> Option<String> {
+        if self.pages.is_empty() {
+            return None;
+        }
+        Some(
+            self.pages
+                .iter()
+                .map(|p| p.trim())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        )
+    }
+}
 
 /// Convert a single rasterised page into Markdown via the VLM.
 ///
 /// ## Message Layout
 ///
-/// The request contains (in order):
-/// 1. **System message** — the 7-rule conversion prompt (or user-supplied override)
-/// 2. **Format-continuity message** *(maintain_format only)* — previous page markdown
-///    as context so the VLM keeps numbering, style, and running text consistent
-/// 3. **User message** — the page PNG as a base64 image attachment (empty text)
+/// The [`PagePrompt`] handed to the backend carries, and
+/// [`crate::backend::LlmProviderBackend`] sends (in order):
+/// 1. **System message** — the page prompt template for the fidelity tier (or
+///    user-supplied override), rendered for this page; see [`crate::prompts`]
+/// 2. **Format-continuity message** *(maintain_format only)* — markdown of the
+///    previous page(s), see [`PriorPages`], as context so the VLM keeps numbering, style, and running text consistent
+/// 3. **Example turns** *(few_shot_examples only)* — per example, a user
+///    message with its image and an assistant message with its Markdown
+/// 4. **User message** — the page PNG as a base64 image attachment (empty text)
 ///
 /// The empty user text is intentional: VLM APIs require at least one user
 /// turn to respond to, but the image carries all the actual content.
@@ -39,103 +155,843 @@ use tracing::{debug, warn};
 /// Always returns a `PageResult` — never propagates the error upward so a
 /// single bad page doesn't abort the entire document. Callers check
 /// `result.error` to decide whether to include or skip the page.
+///
+/// `fallbacks` are tried in order only after `provider` has exhausted
+/// `max_retries`; pass an empty slice to disable fallback.
 pub async fn process_page(
-    provider: &Arc<dyn LLMProvider>,
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
+    page_num: usize,
+    image_data: PageImage,
+    prior_page: Option<&str>,
+    config: &ConversionConfig,
+) -> PageResult {
+    process_image(
+        provider, fallbacks, page_num, image_data, "", prior_page, config,
+    )
+    .await
+}
+
+/// Convert a page produced by the render pipeline, tiled or not.
+///
+/// Ordinary pages are sent whole, with a reading-order hint when
+/// [`ConversionConfig::layout_hints`] detected multiple columns; pages split
+/// by [`ConversionConfig::tile_dense_pages`] go through [`process_tiles`].
+/// Two-page spreads convert each half as its own page, left then right.
+/// Blank pages return an empty result flagged `skipped_blank` without a call,
+/// and pages that failed to render a [`PageError::RenderFailed`] result.
+pub async fn process_encoded_page(
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
+    page: EncodedPage,
+    prior_page: Option<&str>,
+    config: &ConversionConfig,
+) -> PageResult {
+    let page_num = page.page_index + 1;
+    if let Some(detail) = page.render_error {
+        return PageResult {
+            page_num,
+            duration_ms: page.render_encode_ms,
+            region: page.region,
+            error: Some(PageError::RenderFailed {
+                page: page_num,
+                detail,
+            }),
+            ..Default::default()
+        };
+    }
+    if page.blank {
+        return PageResult {
+            page_num,
+            skipped_blank: true,
+            rotation_applied: page.rotation,
+            image_hash: page.image_hash,
+            ..Default::default()
+        };
+    }
+    let page_image = config.keep_page_images.then(|| page.image_data.clone());
+    let rotation = page.rotation;
+    let image_hash = page.image_hash;
+    let region = page.region;
+    let headings = page.headings;
+    let figures = page.figures;
+    let mut result = if page.tiles.is_empty() {
+        let mut hints = Vec::new();
+        if config.layout_hints && page.columns > 1 {
+            hints.push(column_layout_hint(page.columns));
+        }
+        if !headings.is_empty() {
+            hints.push(structure_hint(&headings));
+        }
+        let hint = hints.join("\n\n");
+        process_image(
+            provider,
+            fallbacks,
+            page_num,
+            page.image_data,
+            &hint,
+            prior_page,
+            config,
+        )
+        .await
+    } else if page.spread {
+        process_parts(
+            provider,
+            fallbacks,
+            page_num,
+            page.tiles,
+            spread_context,
+            prior_page,
+            config,
+        )
+        .await
+    } else {
+        process_tiles(
+            provider, fallbacks, page_num, page.tiles, prior_page, config,
+        )
+        .await
+    };
+    if result.error.is_none() && !headings.is_empty() {
+        result.markdown = tagged::align_headings(&result.markdown, &headings);
+    }
+    if result.error.is_none() && !figures.is_empty() {
+        figures::describe_figures(provider, &mut result, figures, config).await;
+    }
+    result.rotation_applied = rotation;
+    result.region = region;
+    result.image_hash = image_hash;
+    result.page_image = page_image;
+    result
+}
+
+/// Convert the tiles of one split page in reading order and merge them.
+///
+/// Tiles run sequentially so the merged Markdown keeps tile order and a
+/// tiled page never takes more than one concurrency slot. Token counts and
+/// durations are summed; if any tile fails the whole page fails, because a
+/// page with a missing quadrant is worse than an explicit error.
+pub async fn process_tiles(
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
+    page_num: usize,
+    tiles: Vec<(PageImage, &'static str)>,
+    prior_page: Option<&str>,
+    config: &ConversionConfig,
+) -> PageResult {
+    process_parts(
+        provider,
+        fallbacks,
+        page_num,
+        tiles,
+        tile_context,
+        prior_page,
+        config,
+    )
+    .await
+}
+
+/// Shared loop behind [`process_tiles`] and two-page spreads: `hint` builds
+/// the user text for part `index` of `count` at `position`.
+async fn process_parts(
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
+    page_num: usize,
+    tiles: Vec<(PageImage, &'static str)>,
+    hint: fn(usize, usize, &str) -> String,
+    prior_page: Option<&str>,
+    config: &ConversionConfig,
+) -> PageResult {
+    let count = tiles.len();
+    let mut merged = PageResult {
+        page_num,
+        ..Default::default()
+    };
+    let mut parts = Vec::with_capacity(count);
+
+    for (index, (image_data, position)) in tiles.into_iter().enumerate() {
+        let hint = hint(index, count, position);
+        let part = process_image(
+            provider, fallbacks, page_num, image_data, &hint, prior_page, config,
+        )
+        .await;
+
+        merged.input_tokens += part.input_tokens;
+        merged.image_tokens += part.image_tokens;
+        merged.output_tokens += part.output_tokens;
+        if let Some(cost) = part.cost_usd {
+            *merged.cost_usd.get_or_insert(0.0) += cost;
+        }
+        merged.duration_ms += part.duration_ms;
+        merged.retries = merged.retries.saturating_add(part.retries);
+        merged.truncated |= part.truncated;
+        if part.error.is_some() {
+            merged.error = part.error;
+            return merged;
+        }
+        parts.push(part.markdown.trim().to_string());
+    }
+
+    merged.markdown = parts.join("\n\n");
+    merged
+}
+
+/// Shared single-image VLM call behind [`process_page`] and [`process_tiles`].
+///
+/// `user_text` accompanies the image in the user turn (empty for whole pages).
+/// Runs inside a `pdf2md.llm` span and reports to the configured
+/// [`crate::metrics::MetricsSink`].
+async fn process_image(
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
+    page_num: usize,
+    image_data: PageImage,
+    user_text: &str,
+    prior_page: Option<&str>,
+    config: &ConversionConfig,
+) -> PageResult {
+    let span = info_span!(
+        "pdf2md.llm",
+        page = page_num,
+        model = %provider.model(),
+        input_tokens = field::Empty,
+        output_tokens = field::Empty,
+        retries = field::Empty,
+        duration_ms = field::Empty,
+        success = field::Empty,
+    );
+    let result = call_vlm(
+        provider, fallbacks, page_num, image_data, user_text, prior_page, config,
+    )
+    .instrument(span.clone())
+    .await;
+
+    let success = result.error.is_none();
+    span.record("input_tokens", result.input_tokens);
+    span.record("output_tokens", result.output_tokens);
+    span.record("retries", result.retries);
+    span.record("duration_ms", result.duration_ms);
+    span.record("success", success);
+
+    if let Some(ref m) = config.metrics_sink {
+        let duration = Duration::from_millis(result.duration_ms);
+        m.record_stage(PipelineStage::Llm, page_num, duration);
+        m.record_llm_call(&LlmCallMetrics {
+            page_num,
+            model: provider.model(),
+            input_tokens: result.input_tokens,
+            output_tokens: result.output_tokens,
+            retries: result.retries,
+            success,
+            duration,
+        });
+    }
+    result
+}
+
+/// Retry/fallback loop for one image: the un-instrumented core of
+/// [`process_image`].
+async fn call_vlm(
+    provider: &Arc<dyn VisionBackend>,
+    fallbacks: &[Arc<dyn VisionBackend>],
     page_num: usize,
-    image_data: ImageData,
+    image_data: PageImage,
+    user_text: &str,
     prior_page: Option<&str>,
     config: &ConversionConfig,
 ) -> PageResult {
     let start = Instant::now();
-    let system_prompt = config
-        .system_prompt
-        .as_deref()
-        .unwrap_or(DEFAULT_SYSTEM_PROMPT);
-
-    let mut messages = vec![ChatMessage::system(system_prompt)];
-
-    // Maintain format context from prior page
-    if config.maintain_format {
-        if let Some(prior) = prior_page {
-            if !prior.is_empty() {
-                messages.push(ChatMessage::system(maintain_format_context(prior)));
+    let system_prompt = config.page_prompt_template().render(page_num);
+
+    // Maintain format context from prior page(s)
+    let context = match prior_page {
+        Some(prior) if config.maintain_format && !prior.is_empty() => {
+            Some(maintain_format_context(prior))
+        }
+        _ => None,
+    };
+    let mut prompt = PagePrompt {
+        system: system_prompt,
+        context,
+        examples: config.few_shot_examples.clone(),
+        user_text: user_text.to_string(),
+    };
+    let options = describe_options(config);
+
+    let mut last_err: Option<PageError> = None;
+    let mut total_attempts: u32 = 0;
+
+    'chain: for (chain_idx, prov) in std::iter::once(provider).chain(fallbacks).enumerate() {
+        if chain_idx > 0 {
+            warn!(
+                "Page {}: falling back to model '{}' ({}/{})",
+                page_num,
+                prov.model(),
+                chain_idx,
+                fallbacks.len()
+            );
+        }
+
+        let mut retry_after: Option<u64> = None;
+        let mut reasked = false;
+        // Set for the one-shot refusal re-ask, which skips the backoff and
+        // does not count against `max_retries`.
+        let mut reask_now = false;
+        let mut attempt: u32 = 0;
+        while reask_now || attempt <= config.max_retries {
+            if !std::mem::take(&mut reask_now) {
+                if attempt > 0 {
+                    let backoff = config.retry_backoff_ms * 2u64.pow(attempt - 1);
+                    // A server-specified delay wins over our own schedule, but
+                    // never wait *less* than the exponential backoff.
+                    let delay = retry_after.map_or(backoff, |secs| backoff.max(secs * 1000));
+                    warn!(
+                        "Page {}: retry {}/{} after {}ms",
+                        page_num, attempt, config.max_retries, delay
+                    );
+                    sleep(Duration::from_millis(delay)).await;
+                }
+                attempt += 1;
+            }
+            total_attempts += 1;
+
+            let answer = prov.describe_page(&image_data, &prompt, &options).await;
+            transcript::record(
+                config,
+                page_num,
+                prov.as_ref(),
+                &image_data,
+                &prompt,
+                &options,
+                &answer,
+            );
+            match answer {
+                Ok(mut response) => {
+                    // ── Truncation: re-send with a doubled budget if allowed ──
+                    let mut budget = config.max_tokens;
+                    let mut input_tokens = response.input_tokens;
+                    let mut image_tokens = image_share(&response, &prompt);
+                    let mut output_tokens = response.output_tokens;
+                    let mut truncated = is_truncated(
+                        response.finish_reason.as_deref(),
+                        response.output_tokens,
+                        budget,
+                    );
+                    while truncated {
+                        let Some(next) = next_token_budget(budget, config.max_tokens_retry_cap)
+                        else {
+                            break;
+                        };
+                        warn!(
+                            "Page {}: output truncated at {} tokens, retrying with {}",
+                            page_num, budget, next
+                        );
+                        let bigger = DescribeOptions {
+                            max_tokens: next,
+                            ..options
+                        };
+                        let answer = prov.describe_page(&image_data, &prompt, &bigger).await;
+                        transcript::record(
+                            config,
+                            page_num,
+                            prov.as_ref(),
+                            &image_data,
+                            &prompt,
+                            &bigger,
+                            &answer,
+                        );
+                        match answer {
+                            Ok(r) => {
+                                input_tokens += r.input_tokens;
+                                image_tokens += image_share(&r, &prompt);
+                                output_tokens += r.output_tokens;
+                                budget = next;
+                                truncated = is_truncated(
+                                    r.finish_reason.as_deref(),
+                                    r.output_tokens,
+                                    budget,
+                                );
+                                response = r;
+                            }
+                            Err(e) => {
+                                // Keep the truncated output rather than nothing.
+                                warn!("Page {}: truncation retry failed — {}", page_num, e);
+                                break;
+                            }
+                        }
+                    }
+                    if truncated {
+                        warn!(
+                            "Page {}: output truncated at max_tokens={}",
+                            page_num, budget
+                        );
+                    }
+
+                    // An empty completion is a failure only when the provider
+                    // says why; otherwise the page may simply hold no text.
+                    if response.markdown.trim().is_empty() {
+                        let reason = response.finish_reason.as_deref();
+                        if is_content_filtered(reason) {
+                            warn!("Page {}: completion blocked by content filter", page_num);
+                            last_err = Some(PageError::ContentFiltered {
+                                page: page_num,
+                                provider: prov.name().to_string(),
+                                detail: format!("finish_reason={}", reason.unwrap_or_default()),
+                            });
+                            continue 'chain;
+                        }
+                        if truncated {
+                            last_err = Some(PageError::Truncated {
+                                page: page_num,
+                                max_tokens: budget,
+                            });
+                            continue 'chain;
+                        }
+                    }
+
+                    if let Some(opening) = detect_refusal(&response.markdown) {
+                        warn!(
+                            "Page {}: model refused or described the page — {}",
+                            page_num, opening
+                        );
+                        last_err = Some(PageError::ModelRefusal {
+                            page: page_num,
+                            provider: prov.name().to_string(),
+                            detail: opening.to_string(),
+                        });
+                        if reasked {
+                            continue 'chain;
+                        }
+                        reasked = true;
+                        reask_now = true;
+                        if !prompt.system.ends_with(REFUSAL_RETRY_RULES) {
+                            prompt.system.push_str(REFUSAL_RETRY_RULES);
+                        }
+                        continue;
+                    }
+
+                    // A describing sentence before a real transcription is
+                    // dropped rather than refused.
+                    let markdown = description_rest(&response.markdown)
+                        .map(str::to_string)
+                        .unwrap_or(response.markdown);
+
+                    let duration = start.elapsed();
+                    debug!(
+                        "Page {}: {} input tokens, {} output tokens, {:?}",
+                        page_num, input_tokens, output_tokens, duration
+                    );
+
+                    return PageResult {
+                        page_num,
+                        markdown,
+                        input_tokens,
+                        image_tokens,
+                        output_tokens,
+                        duration_ms: duration.as_millis() as u64,
+                        retries: total_attempts.saturating_sub(1).min(u8::MAX as u32) as u8,
+                        truncated,
+                        cost_usd: config
+                            .price_for(prov.model())
+                            .map(|p| p.cost(input_tokens, output_tokens)),
+                        ..Default::default()
+                    };
+                }
+                Err(e) => {
+                    let err_msg = match e {
+                        Pdf2MdError::LlmApiError { message } => message,
+                        other => other.to_string(),
+                    };
+                    let class = classify_error(&err_msg);
+                    warn!(
+                        "Page {}: attempt {} failed ({:?}) — {}",
+                        page_num, attempt, class, err_msg
+                    );
+                    let retries = total_attempts.saturating_sub(1).min(u8::MAX as u32) as u8;
+                    match class {
+                        ErrorClass::Auth => {
+                            // Fallbacks share the same credentials; stop here.
+                            last_err = Some(PageError::AuthFailed {
+                                page: page_num,
+                                provider: prov.name().to_string(),
+                                detail: err_msg,
+                            });
+                            break 'chain;
+                        }
+                        ErrorClass::ContentFiltered => {
+                            last_err = Some(PageError::ContentFiltered {
+                                page: page_num,
+                                provider: prov.name().to_string(),
+                                detail: err_msg,
+                            });
+                            continue 'chain;
+                        }
+                        ErrorClass::BadRequest => {
+                            last_err = Some(PageError::LlmFailed {
+                                page: page_num,
+                                retries,
+                                detail: err_msg,
+                            });
+                            continue 'chain;
+                        }
+                        ErrorClass::InvalidResponse => {
+                            retry_after = None;
+                            last_err = Some(PageError::InvalidResponse {
+                                page: page_num,
+                                detail: err_msg,
+                            });
+                        }
+                        ErrorClass::RateLimited { retry_after_secs } => {
+                            retry_after = retry_after_secs;
+                            last_err = Some(PageError::RateLimited {
+                                page: page_num,
+                                provider: prov.name().to_string(),
+                                retry_after_secs,
+                            });
+                        }
+                        ErrorClass::Transient => {
+                            retry_after = None;
+                            last_err = Some(PageError::LlmFailed {
+                                page: page_num,
+                                retries,
+                                detail: err_msg,
+                            });
+                        }
+                    }
+                }
             }
         }
     }
 
-    // User message with the page image
-    messages.push(ChatMessage::user_with_images("", vec![image_data]));
+    // All retries (and fallbacks) exhausted
+    let duration = start.elapsed();
+    let retries = total_attempts.saturating_sub(1).min(u8::MAX as u32) as u8;
+    let error = match last_err {
+        Some(PageError::LlmFailed { page, detail, .. }) => PageError::LlmFailed {
+            page,
+            retries,
+            detail,
+        },
+        Some(other) => other,
+        None => PageError::LlmFailed {
+            page: page_num,
+            retries,
+            detail: "Unknown error".to_string(),
+        },
+    };
 
-    let options = build_options(config);
+    PageResult {
+        page_num,
+        duration_ms: duration.as_millis() as u64,
+        retries,
+        error: Some(error),
+        ..Default::default()
+    }
+}
 
-    let mut last_err: Option<String> = None;
+/// Text-only completion with the same retry policy as page calls.
+///
+/// Used by post-conversion passes (e.g. [`crate::summary`]) that send
+/// Markdown back to the model rather than an image. Returns the response
+/// text with its input and output token counts. Auth errors and 400s are not
+/// retried; everything else backs off exactly like [`process_page`].
+///
+/// Sampling options (temperature, token budget, seed) come from the same
+/// [`DescribeOptions`] as page calls. edgequake-llm providers have no seed
+/// field, so text passes rely on the temperature of 0 that a seed implies;
+/// see [`crate::ConversionConfig::seed`].
+pub async fn complete_text(
+    provider: &Arc<dyn LLMProvider>,
+    system_prompt: &str,
+    user_text: &str,
+    config: &ConversionConfig,
+) -> Result<(String, usize, usize), Pdf2MdError> {
+    let messages = vec![
+        ChatMessage::system(system_prompt),
+        ChatMessage::user(user_text),
+    ];
+    let options = completion_options(&describe_options(config));
 
+    let mut last_err = String::from("Unknown error");
+    let mut retry_after: Option<u64> = None;
     for attempt in 0..=config.max_retries {
         if attempt > 0 {
             let backoff = config.retry_backoff_ms * 2u64.pow(attempt - 1);
+            let delay = retry_after.map_or(backoff, |secs| backoff.max(secs * 1000));
             warn!(
-                "Page {}: retry {}/{} after {}ms",
-                page_num, attempt, config.max_retries, backoff
+                "Text completion: retry {}/{} after {}ms",
+                attempt, config.max_retries, delay
             );
-            sleep(Duration::from_millis(backoff)).await;
+            sleep(Duration::from_millis(delay)).await;
         }
-
         match provider.chat(&messages, Some(&options)).await {
             Ok(response) => {
-                let duration = start.elapsed();
-                debug!(
-                    "Page {}: {} input tokens, {} output tokens, {:?}",
-                    page_num, response.prompt_tokens, response.completion_tokens, duration
-                );
-
-                return PageResult {
-                    page_num,
-                    markdown: response.content,
-                    input_tokens: response.prompt_tokens,
-                    output_tokens: response.completion_tokens,
-                    duration_ms: duration.as_millis() as u64,
-                    retries: attempt as u8,
-                    error: None,
-                };
+                return Ok((
+                    response.content,
+                    response.prompt_tokens,
+                    response.completion_tokens,
+                ))
             }
             Err(e) => {
-                let err_msg = format!("{}", e);
-                warn!(
-                    "Page {}: attempt {} failed — {}",
-                    page_num,
-                    attempt + 1,
-                    err_msg
-                );
-                last_err = Some(err_msg);
+                last_err = e.to_string();
+                match classify_error(&last_err) {
+                    ErrorClass::Auth => {
+                        return Err(Pdf2MdError::AuthError {
+                            provider: provider.name().to_string(),
+                            detail: last_err,
+                        })
+                    }
+                    ErrorClass::BadRequest | ErrorClass::ContentFiltered => break,
+                    ErrorClass::RateLimited { retry_after_secs } => retry_after = retry_after_secs,
+                    ErrorClass::Transient | ErrorClass::InvalidResponse => retry_after = None,
+                }
             }
         }
     }
+    Err(Pdf2MdError::LlmApiError { message: last_err })
+}
+
+/// Whether a completion stopped because it ran out of token budget.
+///
+/// Providers spell the stop reason differently (`length` for OpenAI-style
+/// APIs, `max_tokens` for Anthropic, `MAX_TOKENS` for Gemini); when none is
+/// reported, reaching the budget exactly is treated as truncation.
+fn is_truncated(finish_reason: Option<&str>, completion_tokens: usize, budget: usize) -> bool {
+    match finish_reason {
+        Some(reason) => matches!(
+            reason.to_ascii_lowercase().as_str(),
+            "length" | "max_tokens"
+        ),
+        None => budget > 0 && completion_tokens >= budget,
+    }
+}
 
-    // All retries exhausted
-    let duration = start.elapsed();
-    let err_msg = last_err.unwrap_or_else(|| "Unknown error".to_string());
+/// Next token budget for a truncated page: double the current one, or `None`
+/// when no cap is configured or doubling would exceed it.
+fn next_token_budget(current: usize, cap: Option<usize>) -> Option<usize> {
+    let next = current.saturating_mul(2);
+    cap.filter(|&cap| next <= cap).map(|_| next)
+}
 
-    PageResult {
-        page_num,
-        markdown: String::new(),
-        input_tokens: 0,
-        output_tokens: 0,
-        duration_ms: duration.as_millis() as u64,
-        retries: config.max_retries as u8,
-        error: Some(crate::error::PageError::LlmFailed {
-            page: page_num,
-            retries: config.max_retries as u8,
-            detail: err_msg,
-        }),
+// ── Error classification ─────────────────────────────────────────────────
+
+/// How a provider error should influence the retry loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// HTTP 429 — retry, honouring the server's `Retry-After` when present.
+    RateLimited { retry_after_secs: Option<u64> },
+    /// HTTP 401/403 — the key is wrong; retrying cannot help.
+    Auth,
+    /// A safety / content-policy filter refused the request or the output.
+    ContentFiltered,
+    /// HTTP 400 — the request itself was rejected by this model.
+    BadRequest,
+    /// The provider answered, but its response could not be parsed.
+    InvalidResponse,
+    /// 5xx, timeouts, connection resets, and anything unrecognised.
+    Transient,
+}
+
+static STATUS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b([45]\d{2})\b").unwrap());
+
+static RETRY_AFTER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)retry[-_ ]after["':=\s]*(\d+(?:\.\d+)?)\s*(ms|s)?"#).unwrap());
+
+static TRY_AGAIN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)try again in\s*(\d+(?:\.\d+)?)\s*(ms|s)").unwrap());
+
+/// Classify a provider error message into an [`ErrorClass`].
+///
+/// Keyword matches take precedence over bare status codes because some
+/// providers wrap a 429 inside a generic 400/500 envelope.
+pub fn classify_error(message: &str) -> ErrorClass {
+    let lower = message.to_ascii_lowercase();
+    let status = STATUS_RE
+        .captures(message)
+        .and_then(|c| c[1].parse::<u16>().ok());
+
+    if status == Some(429)
+        || lower.contains("rate limit")
+        || lower.contains("rate_limit")
+        || lower.contains("too many requests")
+    {
+        return ErrorClass::RateLimited {
+            retry_after_secs: parse_retry_after(message),
+        };
+    }
+    if matches!(status, Some(401) | Some(403))
+        || lower.contains("unauthorized")
+        || lower.contains("invalid api key")
+        || lower.contains("invalid_api_key")
+        || lower.contains("authentication")
+    {
+        return ErrorClass::Auth;
     }
+    // Content filters usually arrive as a 400, so check them first.
+    if [
+        "content_filter",
+        "content filter",
+        "content management policy",
+        "content policy",
+        "safety settings",
+        "blocked due to safety",
+        "prohibited_content",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+    {
+        return ErrorClass::ContentFiltered;
+    }
+    if status == Some(400) || lower.contains("bad request") {
+        return ErrorClass::BadRequest;
+    }
+    if [
+        "error decoding response",
+        "failed to parse",
+        "invalid json",
+        "expected value at line",
+        "missing field",
+        "eof while parsing",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+    {
+        return ErrorClass::InvalidResponse;
+    }
+    ErrorClass::Transient
 }
 
-/// Build `CompletionOptions` from the conversion config.
-fn build_options(config: &ConversionConfig) -> CompletionOptions {
-    CompletionOptions {
-        temperature: Some(config.temperature),
-        max_tokens: Some(config.max_tokens),
-        ..Default::default()
+/// Apology or refusal opening a page response.
+static REFUSAL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^(?:(?:i['’]?m|i am) sorry|sorry|i apologi[sz]e|unfortunately|as an ai)\b[^\n]{0,60}?\bi(?:['’]m| am)? (?:can(?:['’]|no)?t|cannot|unable|not able|won['’]?t)\b|^i(?:['’]m| am)? (?:can(?:['’]|no)?t|cannot|unable to|not able to) (?:help|assist|transcribe|process|convert|read|extract|provide|comply|do that)\b",
+    )
+    .unwrap()
+});
+
+/// Meta-commentary describing the page instead of transcribing it.
+static DESCRIPTION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^(?:this|the) (?:image|picture|photo|screenshot|scan(?:ned (?:image|page|document))?|provided (?:image|page|document)) (?:appears|seems|shows|contains|depicts|displays|is an? (?:image|photo|scan|page))\b",
+    )
+    .unwrap()
+});
+
+/// End of the first sentence, or of the first paragraph when it has none.
+static SENTENCE_END_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[.:](?:\s|$)|\n\n").unwrap());
+
+/// Characters of transcription that must follow a describing sentence for
+/// the reply to count as a transcription rather than a description.
+const DESCRIBED_CONTENT_MIN_CHARS: usize = 200;
+
+/// The opening paragraph of `markdown` when it is a refusal ("I'm sorry, I
+/// can't help with that") or describes the image instead of transcribing it
+/// ("This image appears to contain…") with little else after it.
+///
+/// Only the first paragraph is examined, so a document that quotes such a
+/// sentence further down is not affected.
+pub(crate) fn detect_refusal(markdown: &str) -> Option<&str> {
+    let text = markdown.trim_start();
+    let opening = text.split("\n\n").next().unwrap_or(text).trim_end();
+    let plain = opening.trim_start_matches(['#', '>', '*', '_', ' ']);
+    let described = DESCRIPTION_RE.is_match(plain) && description_rest(markdown).is_none();
+    (REFUSAL_RE.is_match(plain) || described).then(|| {
+        opening
+            .char_indices()
+            .nth(160)
+            .map_or(opening, |(i, _)| &opening[..i])
+    })
+}
+
+/// What follows an opening sentence that describes the image, when it is
+/// a transcription of at least [`DESCRIBED_CONTENT_MIN_CHARS`] characters
+/// ("This image shows a table of results:" before the table).
+pub(crate) fn description_rest(markdown: &str) -> Option<&str> {
+    let plain = markdown
+        .trim_start()
+        .trim_start_matches(['#', '>', '*', '_', ' ']);
+    if !DESCRIPTION_RE.is_match(plain) {
+        return None;
+    }
+    let end = SENTENCE_END_RE.find(plain).map_or(plain.len(), |m| m.end());
+    let rest = plain[end..].trim_start();
+    (rest.chars().count() >= DESCRIBED_CONTENT_MIN_CHARS).then_some(rest)
+}
+
+/// Whether a completion's stop reason means a safety filter cut it off
+/// (`content_filter` for OpenAI-style APIs, `SAFETY` / `RECITATION` /
+/// `PROHIBITED_CONTENT` for Gemini).
+fn is_content_filtered(finish_reason: Option<&str>) -> bool {
+    finish_reason.is_some_and(|reason| {
+        matches!(
+            reason.to_ascii_lowercase().as_str(),
+            "content_filter" | "safety" | "recitation" | "prohibited_content" | "blocklist"
+        )
+    })
+}
+
+/// Whether a failed page points at the provider being unusable (bad
+/// credentials or unreachable endpoint) rather than at the page itself.
+pub fn is_provider_outage(error: &PageError) -> bool {
+    match error {
+        PageError::AuthFailed { .. } => true,
+        PageError::LlmFailed { detail, .. } => {
+            let lower = detail.to_ascii_lowercase();
+            [
+                "connection refused",
+                "connection reset",
+                "connect error",
+                "error sending request",
+                "dns error",
+                "failed to lookup address",
+                "network is unreachable",
+                "no route to host",
+            ]
+            .iter()
+            .any(|needle| lower.contains(needle))
+        }
+        _ => false,
+    }
+}
+
+/// Extract a retry delay in whole seconds (rounded up) from an error message.
+fn parse_retry_after(message: &str) -> Option<u64> {
+    let caps = RETRY_AFTER_RE
+        .captures(message)
+        .or_else(|| TRY_AGAIN_RE.captures(message))?;
+    let value: f64 = caps[1].parse().ok()?;
+    let secs = match caps.get(2).map(|m| m.as_str().to_ascii_lowercase()) {
+        Some(unit) if unit == "ms" => value / 1000.0,
+        _ => value,
+    };
+    Some(secs.ceil() as u64)
+}
+
+/// Rough characters per token of prompt text, for [`image_share`].
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Prompt tokens of `response` spent on images: the backend's figure if it
+/// reports one, else the input tokens left after the prompt text.
+fn image_share(response: &PageCompletion, prompt: &PagePrompt) -> usize {
+    if let Some(n) = response.image_tokens {
+        return n.min(response.input_tokens);
+    }
+    let text_chars: usize = [prompt.system.as_str(), prompt.user_text.as_str()]
+        .into_iter()
+        .chain(prompt.context.as_deref())
+        .chain(prompt.examples.iter().map(|e| e.markdown.as_str()))
+        .map(|s| s.chars().count())
+        .sum();
+    response
+        .input_tokens
+        .saturating_sub(text_chars.div_ceil(CHARS_PER_TOKEN))
+}
+
+/// Build the per-image [`DescribeOptions`] from the conversion config.
+fn describe_options(config: &ConversionConfig) -> DescribeOptions {
+    DescribeOptions {
+        temperature: config.temperature,
+        max_tokens: config.max_tokens,
+        seed: config.seed,
     }
 }
 
@@ -144,10 +1000,511 @@ mod tests {
     use super::*;
 
     #[test]
-    fn build_options_defaults() {
+    fn text_options_follow_page_options() {
         let config = ConversionConfig::default();
-        let opts = build_options(&config);
+        let opts = completion_options(&describe_options(&config));
         assert_eq!(opts.temperature, Some(0.1));
         assert_eq!(opts.max_tokens, Some(4096));
+
+        let seeded = ConversionConfig::builder().seed(7).build().unwrap();
+        let page = describe_options(&seeded);
+        assert_eq!(page.seed, Some(7));
+        assert_eq!(completion_options(&page).temperature, Some(0.0));
+    }
+
+    #[test]
+    fn truncation_detected_from_finish_reason() {
+        assert!(is_truncated(Some("length"), 100, 4096));
+        assert!(is_truncated(Some("MAX_TOKENS"), 100, 4096));
+        assert!(!is_truncated(Some("stop"), 4096, 4096));
+        assert!(is_truncated(None, 4096, 4096));
+        assert!(!is_truncated(None, 4000, 4096));
+    }
+
+    #[test]
+    fn token_budget_doubles_up_to_cap() {
+        assert_eq!(next_token_budget(4096, None), None);
+        assert_eq!(next_token_budget(4096, Some(16384)), Some(8192));
+        assert_eq!(next_token_budget(8192, Some(16384)), Some(16384));
+        assert_eq!(next_token_budget(16384, Some(16384)), None);
+    }
+
+    #[test]
+    fn classify_rate_limit_with_retry_after() {
+        let class = classify_error("HTTP 429 Too Many Requests (Retry-After: 12)");
+        assert_eq!(
+            class,
+            ErrorClass::RateLimited {
+                retry_after_secs: Some(12)
+            }
+        );
+    }
+
+    #[test]
+    fn classify_rate_limit_openai_style_hint() {
+        let class = classify_error("Rate limit reached for gpt-4.1. Please try again in 1.5s.");
+        assert_eq!(
+            class,
+            ErrorClass::RateLimited {
+                retry_after_secs: Some(2)
+            }
+        );
+    }
+
+    #[test]
+    fn classify_rate_limit_without_hint() {
+        assert_eq!(
+            classify_error("status 429"),
+            ErrorClass::RateLimited {
+                retry_after_secs: None
+            }
+        );
+    }
+
+    #[test]
+    fn classify_auth_errors() {
+        assert_eq!(classify_error("401 Unauthorized"), ErrorClass::Auth);
+        assert_eq!(classify_error("API error 403: forbidden"), ErrorClass::Auth);
+        assert_eq!(classify_error("Invalid API key provided"), ErrorClass::Auth);
+    }
+
+    #[test]
+    fn classify_bad_request_and_transient() {
+        assert_eq!(
+            classify_error("400 Bad Request: image too large"),
+            ErrorClass::BadRequest
+        );
+        assert_eq!(
+            classify_error("503 Service Unavailable"),
+            ErrorClass::Transient
+        );
+        assert_eq!(
+            classify_error("connection reset by peer"),
+            ErrorClass::Transient
+        );
+    }
+
+    #[test]
+    fn provider_outage_detection() {
+        let auth = PageError::AuthFailed {
+            page: 1,
+            provider: "openai".into(),
+            detail: "401".into(),
+        };
+        let refused = PageError::LlmFailed {
+            page: 1,
+            retries: 3,
+            detail: "error sending request: Connection refused (os error 111)".into(),
+        };
+        let content = PageError::LlmFailed {
+            page: 1,
+            retries: 0,
+            detail: "400 Bad Request: image too large".into(),
+        };
+        assert!(is_provider_outage(&auth));
+        assert!(is_provider_outage(&refused));
+        assert!(!is_provider_outage(&content));
+    }
+
+    #[test]
+    fn prior_pages_keeps_last_n() {
+        let mut prior = PriorPages::new(2);
+        assert_eq!(prior.context(), None);
+        prior.push("one");
+        prior.push("  ");
+        prior.push("two\n");
+        prior.push("three");
+        assert_eq!(prior.context().as_deref(), Some("two\n\nthree"));
+    }
+
+    #[test]
+    fn prior_pages_zero_capacity_acts_as_one() {
+        let mut prior = PriorPages::new(0);
+        prior.push("one");
+        prior.push("two");
+        assert_eq!(prior.context().as_deref(), Some("two"));
+    }
+
+    #[test]
+    fn retry_after_milliseconds_rounds_up() {
+        assert_eq!(parse_retry_after("overloaded, retry-after: 250ms"), Some(1));
+    }
+
+    /// Backend that replays a fixed script of answers, one per call.
+    struct Scripted {
+        script: std::sync::Mutex<VecDeque<Result<PageCompletion, Pdf2MdError>>>,
+        budgets: std::sync::Mutex<Vec<usize>>,
+        prompts: std::sync::Mutex<Vec<PagePrompt>>,
+    }
+
+    impl Scripted {
+        fn new(script: Vec<Result<PageCompletion, Pdf2MdError>>) -> Arc<Self> {
+            Arc::new(Self {
+                script: std::sync::Mutex::new(script.into()),
+                budgets: std::sync::Mutex::new(Vec::new()),
+                prompts: std::sync::Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl VisionBackend for Scripted {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        fn model(&self) -> &str {
+            "scripted-1"
+        }
+
+        async fn describe_page(
+            &self,
+            _image: &PageImage,
+            prompt: &PagePrompt,
+            opts: &DescribeOptions,
+        ) -> Result<PageCompletion, Pdf2MdError> {
+            self.budgets.lock().unwrap().push(opts.max_tokens);
+            self.prompts.lock().unwrap().push(prompt.clone());
+            self.script
+                .lock()
+                .unwrap()
+                .pop_front()
+                .expect("script exhausted")
+        }
+    }
+
+    fn api_error(message: &str) -> Result<PageCompletion, Pdf2MdError> {
+        Err(Pdf2MdError::LlmApiError {
+            message: message.to_string(),
+        })
+    }
+
+    #[tokio::test]
+    async fn custom_backend_is_retried_on_transient_errors() {
+        let backend = Scripted::new(vec![
+            api_error("503 Service Unavailable"),
+            Ok(PageCompletion::new("# Title").with_usage(100, 5)),
+        ]);
+        let config = ConversionConfig::builder()
+            .max_retries(2)
+            .retry_backoff_ms(0)
+            .model_price("scripted", crate::pricing::ModelPrice::new(10.0, 20.0))
+            .build()
+            .unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        let result = process_page(&dyn_backend, &[], 1, image, None, &config).await;
+        assert!(result.error.is_none());
+        assert_eq!(result.markdown, "# Title");
+        assert_eq!(result.retries, 1);
+        assert_eq!(result.input_tokens, 100);
+        assert_eq!(result.cost_usd, Some(0.0011));
+    }
+
+    #[test]
+    fn image_share_prefers_reported_usage() {
+        let prompt = PagePrompt {
+            system: "x".repeat(400),
+            user_text: "top-left tile".into(),
+            ..Default::default()
+        };
+        let estimated = PageCompletion::new("# Title").with_usage(1200, 5);
+        assert_eq!(image_share(&estimated, &prompt), 1200 - 104);
+        let reported = estimated.clone().with_image_tokens(1105);
+        assert_eq!(image_share(&reported, &prompt), 1105);
+        let unknown = PageCompletion::new("# Title");
+        assert_eq!(image_share(&unknown, &prompt), 0);
+    }
+
+    #[tokio::test]
+    async fn prompt_carries_rendered_template_and_examples() {
+        use crate::backend::FewShotExample;
+        use crate::prompts::PromptTemplate;
+
+        let backend = Scripted::new(vec![Ok(PageCompletion::new("| Total | 9 |"))]);
+        let example = FewShotExample::new(
+            PageImage::new("ZXhhbXBsZQ==", "image/png"),
+            "| Total | 12 |",
+        );
+        let config = ConversionConfig::builder()
+            .prompt_template(PromptTemplate::new("Invoice page {page_num}."))
+            .few_shot_examples(vec![example.clone()])
+            .build()
+            .unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        let result = process_page(&dyn_backend, &[], 7, image, None, &config).await;
+        assert!(result.error.is_none());
+        let prompts = backend.prompts.lock().unwrap();
+        assert_eq!(prompts[0].system, "Invoice page 7.");
+        assert_eq!(prompts[0].examples, vec![example]);
+    }
+
+    #[tokio::test]
+    async fn custom_backend_auth_error_is_not_retried() {
+        let backend = Scripted::new(vec![api_error("401 Unauthorized")]);
+        let config = ConversionConfig::builder()
+            .max_retries(3)
+            .retry_backoff_ms(0)
+            .build()
+            .unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        let result = process_page(&dyn_backend, &[], 1, image, None, &config).await;
+        assert!(matches!(result.error, Some(PageError::AuthFailed { .. })));
+        assert_eq!(backend.budgets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn content_filter_and_invalid_response_are_classified() {
+        assert_eq!(
+            classify_error("400 Bad Request: finish_reason content_filter triggered"),
+            ErrorClass::ContentFiltered
+        );
+        assert_eq!(
+            classify_error("error decoding response body: expected value at line 1"),
+            ErrorClass::InvalidResponse
+        );
+        assert!(is_content_filtered(Some("SAFETY")));
+        assert!(!is_content_filtered(Some("stop")));
+        assert!(!is_content_filtered(None));
+    }
+
+    #[tokio::test]
+    async fn empty_filtered_completion_is_content_filtered() {
+        let mut blocked = PageCompletion::new("");
+        blocked.finish_reason = Some("content_filter".into());
+        let backend = Scripted::new(vec![Ok(blocked)]);
+        let config = ConversionConfig::builder().max_retries(3).build().unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        let result = process_page(&dyn_backend, &[], 1, image, None, &config).await;
+        assert!(matches!(
+            result.error,
+            Some(PageError::ContentFiltered { page: 1, .. })
+        ));
+        assert_eq!(backend.budgets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn refusals_and_image_descriptions_are_detected() {
+        assert!(detect_refusal("I'm sorry, but I can't help with that.").is_some());
+        assert!(detect_refusal("I\u{2019}m unable to transcribe this image.").is_some());
+        assert!(detect_refusal("I cannot assist with this request.").is_some());
+        assert!(detect_refusal("This image appears to contain a table of results.").is_some());
+        assert!(
+            detect_refusal("This image appears to contain a table of results:\n\n| A |").is_some()
+        );
+        assert!(detect_refusal("The provided page shows a bar chart.").is_some());
+
+        assert_eq!(
+            detect_refusal("# Returns\n\nUnfortunately, we cannot refund."),
+            None
+        );
+        assert_eq!(detect_refusal("Sorry, we can't accept late entries."), None);
+        assert_eq!(detect_refusal("This page intentionally left blank."), None);
+        assert_eq!(detect_refusal("# Intro\n\nI'm sorry, I can't help."), None);
+    }
+
+    #[test]
+    fn description_before_a_transcription_is_stripped() {
+        let rows: String = (1..=12)
+            .map(|i| format!("| Q{i} | {} | {} |\n", i * 100, i * 7))
+            .collect();
+        let table = format!("| Quarter | Revenue | Margin |\n|---|---|---|\n{rows}");
+        let reply = format!("This image appears to contain a table of results:\n\n{table}");
+        assert_eq!(detect_refusal(&reply), None);
+        assert_eq!(description_rest(&reply), Some(table.as_str()));
+
+        let inline = format!("The scanned page shows a ledger. {table}");
+        assert_eq!(description_rest(&inline), Some(table.as_str()));
+        assert_eq!(description_rest(&table), None);
+        assert_eq!(
+            description_rest("The provided page shows a bar chart."),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn refusal_is_reasked_with_a_firmer_prompt() {
+        let backend = Scripted::new(vec![
+            Ok(PageCompletion::new("I'm sorry, I can't help with that.")),
+            Ok(PageCompletion::new("# Title")),
+        ]);
+        let config = ConversionConfig::builder()
+            .max_retries(2)
+            .retry_backoff_ms(0)
+            .build()
+            .unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        let result = process_page(&dyn_backend, &[], 1, image, None, &config).await;
+        assert_eq!(result.markdown, "# Title");
+        let prompts = backend.prompts.lock().unwrap();
+        assert!(!prompts[0].system.contains(REFUSAL_RETRY_RULES));
+        assert!(prompts[1].system.ends_with(REFUSAL_RETRY_RULES));
+    }
+
+    #[tokio::test]
+    async fn refusal_reask_is_not_a_retry() {
+        let backend = Scripted::new(vec![
+            Ok(PageCompletion::new("I'm sorry, I can't help with that.")),
+            Ok(PageCompletion::new("# Title")),
+        ]);
+        let config = ConversionConfig::builder()
+            .max_retries(0)
+            .retry_backoff_ms(60_000)
+            .build()
+            .unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        // A backoff before the re-ask would blow the timeout.
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            process_page(&dyn_backend, &[], 1, image, None, &config),
+        )
+        .await
+        .unwrap();
+        assert!(result.error.is_none());
+        assert_eq!(result.markdown, "# Title");
+        assert_eq!(backend.budgets.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn transcripts_record_every_call_without_keys() {
+        let backend = Scripted::new(vec![
+            api_error("503 upstream unavailable for sk-tenant-0123456789"),
+            Ok(PageCompletion::new("# Title").with_usage(100, 5)),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        let config = ConversionConfig::builder()
+            .max_retries(2)
+            .retry_backoff_ms(0)
+            .api_key("tenant-secret-42")
+            .transcript_dir(dir.path())
+            .build()
+            .unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        let result = process_page(&dyn_backend, &[], 4, image, None, &config).await;
+        assert!(result.error.is_none());
+
+        let text = std::fs::read_to_string(transcript::page_path(dir.path(), 4)).unwrap();
+        let calls: Vec<transcript::TranscriptEntry> = text
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(calls.len(), 2);
+        assert!(calls[0]
+            .error
+            .as_deref()
+            .unwrap()
+            .ends_with(transcript::REDACTED));
+        assert_eq!(calls[1].response.as_deref(), Some("# Title"));
+        assert_eq!(calls[1].model, "scripted-1");
+        assert_eq!(calls[1].image_hash, calls[0].image_hash);
+        assert!(!calls[1].system_prompt.is_empty());
+        assert!(!text.contains("sk-tenant") && !text.contains("tenant-secret-42"));
+    }
+
+    #[tokio::test]
+    async fn repeated_refusal_fails_the_page() {
+        let refusal = || Ok(PageCompletion::new("This image appears to contain a form."));
+        let backend = Scripted::new(vec![refusal(), refusal(), refusal()]);
+        let config = ConversionConfig::builder()
+            .max_retries(3)
+            .retry_backoff_ms(0)
+            .build()
+            .unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        let result = process_page(&dyn_backend, &[], 2, image, None, &config).await;
+        assert!(result.markdown.is_empty());
+        assert!(matches!(
+            result.error,
+            Some(PageError::ModelRefusal { page: 2, .. })
+        ));
+        assert_eq!(backend.budgets.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn render_failure_becomes_page_error_without_a_call() {
+        let backend = Scripted::new(Vec::new());
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let page = EncodedPage {
+            page_index: 3,
+            image_data: PageImage::new(String::new(), "image/png"),
+            tiles: Vec::new(),
+            columns: 1,
+            render_encode_ms: 7,
+            blank: false,
+            rotation: 0,
+            spread: false,
+            render_error: Some("bitmap allocation failed".into()),
+            image_hash: None,
+            region: None,
+            headings: Vec::new(),
+            figures: Vec::new(),
+            encoded_at: Instant::now(),
+        };
+        let config = ConversionConfig::default();
+        let result = process_encoded_page(&dyn_backend, &[], page, None, &config).await;
+        assert!(matches!(
+            result.error,
+            Some(PageError::RenderFailed { page: 4, .. })
+        ));
+        assert!(backend.budgets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn page_image_is_kept_on_request() {
+        let page = || EncodedPage {
+            page_index: 0,
+            image_data: PageImage::new("dGVzdA==", "image/png"),
+            tiles: Vec::new(),
+            columns: 1,
+            render_encode_ms: 7,
+            blank: false,
+            rotation: 0,
+            spread: false,
+            render_error: None,
+            image_hash: None,
+            region: None,
+            headings: Vec::new(),
+            figures: Vec::new(),
+            encoded_at: Instant::now(),
+        };
+        let backend = Scripted::new(vec![
+            Ok(PageCompletion::new("# One")),
+            Ok(PageCompletion::new("# One")),
+        ]);
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let plain = ConversionConfig::default();
+        let result = process_encoded_page(&dyn_backend, &[], page(), None, &plain).await;
+        assert_eq!(result.page_image, None);
+        let keep = ConversionConfig::builder()
+            .keep_page_images(true)
+            .build()
+            .unwrap();
+        let result = process_encoded_page(&dyn_backend, &[], page(), None, &keep).await;
+        assert_eq!(result.page_image, Some(page().image_data));
+    }
+
+    #[tokio::test]
+    async fn custom_backend_truncation_raises_budget() {
+        let mut cut = PageCompletion::new("half").with_usage(10, 4096);
+        cut.finish_reason = Some("length".into());
+        let backend = Scripted::new(vec![Ok(cut), Ok(PageCompletion::new("whole"))]);
+        let config = ConversionConfig::builder()
+            .max_tokens(4096)
+            .max_tokens_retry_cap(8192)
+            .build()
+            .unwrap();
+        let dyn_backend: Arc<dyn VisionBackend> = backend.clone();
+        let image = PageImage::new("dGVzdA==", "image/png");
+        let result = process_page(&dyn_backend, &[], 1, image, None, &config).await;
+        assert_eq!(result.markdown, "whole");
+        assert!(!result.truncated);
+        assert_eq!(*backend.budgets.lock().unwrap(), vec![4096, 8192]);
     }
 }
@@ -0,0 +1,271 @@
+//! Blank out running headers and footers before a page reaches the model.
+//!
+//! ## Why mask pixels?
+//!
+//! The prompts ask the model to leave out page numbers and running
+//! headers, and most of the time it does. When it does not, "Chapter 3 —
+//! Methods 47" turns up between two halves of a sentence that continues
+//! across the page break. Painting the margin bands white before encoding
+//! makes the instruction unnecessary: what the model never sees it cannot
+//! transcribe.
+//!
+//! A [`MarginMask`] blanks fixed bands (fractions of the page height), bands
+//! found by [`detect_bands`], or both. Detection looks at the outer
+//! [`MAX_AUTO_BAND`] of the page for a strip of ink set apart from the body
+//! by a wider gap than any between body lines; a title printed close to the
+//! top edge can look the same, so documents without running headers should
+//! leave detection off.
+
+use crate::error::Pdf2MdError;
+use image::{DynamicImage, Rgba};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Fraction of the page height, at each edge, that detection searches.
+pub const MAX_AUTO_BAND: f32 = 0.1;
+
+/// Longest thumbnail edge used for detection.
+const THUMBNAIL_EDGE: u32 = 800;
+
+/// Luminance below which a pixel counts as ink.
+const INK_LUMA: u8 = 160;
+
+/// Gap, as a fraction of the page height, that separates a header or
+/// footer from the body. Line spacing of body text stays well under it.
+const MIN_GAP: f32 = 0.015;
+
+/// Bands at the top and bottom of each rendered page to paint white.
+/// See [`crate::pipeline::margins`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarginMask {
+    /// Fraction of the page height always masked at the top, 0–0.5.
+    #[serde(default)]
+    pub top: f32,
+    /// Fraction of the page height always masked at the bottom, 0–0.5.
+    #[serde(default)]
+    pub bottom: f32,
+    /// Also mask header and footer bands found by [`detect_bands`].
+    #[serde(default)]
+    pub auto: bool,
+}
+
+impl MarginMask {
+    /// Mask `top` and `bottom` fractions of every page.
+    pub fn fixed(top: f32, bottom: f32) -> Self {
+        Self {
+            top,
+            bottom,
+            auto: false,
+        }
+    }
+
+    /// Mask the header and footer bands found on each page.
+    pub fn auto() -> Self {
+        Self {
+            auto: true,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), Pdf2MdError> {
+        let valid = |f: f32| (0.0..=0.5).contains(&f);
+        if valid(self.top) && valid(self.bottom) {
+            Ok(())
+        } else {
+            Err(Pdf2MdError::InvalidConfig(format!(
+                "margin mask {self} must be 0–0.5 of the page at each edge"
+            )))
+        }
+    }
+
+    /// Rows to mask at the top and bottom of `image`: the larger of the
+    /// fixed and the detected band at each edge.
+    pub fn bands(&self, image: &DynamicImage) -> (u32, u32) {
+        let height = image.height();
+        let px = |f: f32| (f * height as f32).round() as u32;
+        let (mut top, mut bottom) = (px(self.top), px(self.bottom));
+        if self.auto {
+            let (t, b) = detect_bands(image);
+            top = top.max(t);
+            bottom = bottom.max(b);
+        }
+        (top.min(height), bottom.min(height - top.min(height)))
+    }
+}
+
+impl fmt::Display for MarginMask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.auto {
+            f.write_str("auto")?;
+            if self.top == 0.0 && self.bottom == 0.0 {
+                return Ok(());
+            }
+            f.write_str(",")?;
+        }
+        write!(f, "{},{}", self.top * 100.0, self.bottom * 100.0)
+    }
+}
+
+/// Parses the `--mask-margins` syntax: `auto`, `TOP,BOTTOM` in percent of
+/// the page height (`8,5`), or both (`auto,8,5`).
+impl std::str::FromStr for MarginMask {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        let (auto, rest) = match s.strip_prefix("auto") {
+            Some(rest) => (true, rest.trim_start_matches(',').trim()),
+            None => (false, s.as_str()),
+        };
+        if rest.is_empty() {
+            return if auto {
+                Ok(Self::auto())
+            } else {
+                Err("empty margin mask".to_string())
+            };
+        }
+        let parts: Vec<f32> = rest
+            .split(',')
+            .map(|p| p.trim().trim_end_matches('%').parse::<f32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("invalid margin mask '{s}', expected auto or TOP,BOTTOM"))?;
+        match parts[..] {
+            [top, bottom] => Ok(Self {
+                top: top / 100.0,
+                bottom: bottom / 100.0,
+                auto,
+            }),
+            _ => Err(format!(
+                "invalid margin mask '{s}', expected two percentages TOP,BOTTOM"
+            )),
+        }
+    }
+}
+
+/// Paint the bands of `mask` white. Returns `image` unchanged when there
+/// is nothing to mask.
+pub fn apply(image: DynamicImage, mask: &MarginMask) -> DynamicImage {
+    let (top, bottom) = mask.bands(&image);
+    if top == 0 && bottom == 0 {
+        return image;
+    }
+    let mut rgba = image.into_rgba8();
+    let (width, height) = rgba.dimensions();
+    for y in (0..top).chain(height - bottom..height) {
+        for x in 0..width {
+            rgba.put_pixel(x, y, Rgba([255, 255, 255, 255]));
+        }
+    }
+    DynamicImage::ImageRgba8(rgba)
+}
+
+/// Rows (of `image`) holding a running header at the top and a running
+/// footer at the bottom; `0` where there is none.
+pub fn detect_bands(image: &DynamicImage) -> (u32, u32) {
+    let thumb = image.thumbnail(THUMBNAIL_EDGE, THUMBNAIL_EDGE).to_luma8();
+    let (tw, th) = thumb.dimensions();
+    if tw == 0 || th < 20 {
+        return (0, 0);
+    }
+    let min_ink = (tw / 200).max(2) as usize;
+    let mut ink: Vec<bool> = thumb
+        .rows()
+        .map(|row| row.filter(|p| p.0[0] < INK_LUMA).count() >= min_ink)
+        .collect();
+    let limit = (th as f32 * MAX_AUTO_BAND) as usize;
+    let min_gap = ((th as f32 * MIN_GAP).ceil() as usize).max(2);
+    let top = edge_band(&ink, limit, min_gap);
+    ink.reverse();
+    let bottom = edge_band(&ink, limit, min_gap);
+    let scale = |rows: usize| (rows as f32 * image.height() as f32 / th as f32).round() as u32;
+    (scale(top), scale(bottom))
+}
+
+/// Rows from the start of `ink` to mask: up to the middle of the widest
+/// gap of at least `min_gap` blank rows that follows ink and starts within
+/// `limit`. `0` when there is none.
+fn edge_band(ink: &[bool], limit: usize, min_gap: usize) -> usize {
+    let Some(first) = ink.iter().position(|&i| i) else {
+        return 0;
+    };
+    let mut best = (0, 0);
+    let mut y = first;
+    while y < limit.min(ink.len()) {
+        if ink[y] {
+            y += 1;
+            continue;
+        }
+        let gap = ink[y..].iter().take_while(|&&i| !i).count();
+        if y + gap == ink.len() {
+            break;
+        }
+        if gap >= min_gap && gap > best.1 {
+            best = (y, gap);
+        }
+        y += gap;
+    }
+    match best {
+        (_, 0) => 0,
+        (start, gap) => start + gap / 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    /// A 200×1000 page: header at rows 30–40, body lines every 20 rows from
+    /// 150 to 850, footer at 960–970.
+    fn page() -> DynamicImage {
+        let mut img = RgbaImage::from_pixel(200, 1000, Rgba([255, 255, 255, 255]));
+        let mut ink = |from: u32, to: u32| {
+            for y in from..to {
+                for x in 20..180 {
+                    img.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+                }
+            }
+        };
+        ink(30, 40);
+        for y in (150..850).step_by(20) {
+            ink(y, y + 10);
+        }
+        ink(960, 970);
+        DynamicImage::ImageRgba8(img)
+    }
+
+    #[test]
+    fn header_and_footer_bands_are_found() {
+        let (top, bottom) = detect_bands(&page());
+        assert!((40..150).contains(&top), "top band {top}");
+        assert!((30..150).contains(&bottom), "bottom band {bottom}");
+
+        let masked = apply(page(), &MarginMask::auto()).to_luma8();
+        assert_eq!(masked.get_pixel(100, 35).0[0], 255);
+        assert_eq!(masked.get_pixel(100, 965).0[0], 255);
+        assert_eq!(masked.get_pixel(100, 155).0[0], 0);
+    }
+
+    #[test]
+    fn body_text_alone_is_not_masked() {
+        let mut img = RgbaImage::from_pixel(200, 1000, Rgba([255, 255, 255, 255]));
+        for y in (60..940).step_by(20) {
+            for row in y..y + 10 {
+                for x in 20..180 {
+                    img.put_pixel(x, row, Rgba([0, 0, 0, 255]));
+                }
+            }
+        }
+        assert_eq!(detect_bands(&DynamicImage::ImageRgba8(img)), (0, 0));
+    }
+
+    #[test]
+    fn masks_parse_from_cli_syntax() {
+        assert_eq!("auto".parse(), Ok(MarginMask::auto()));
+        assert_eq!("8,5".parse(), Ok(MarginMask::fixed(0.08, 0.05)));
+        let both: MarginMask = "auto,10%,0".parse().unwrap();
+        assert!(both.auto && both.top == 0.1 && both.bottom == 0.0);
+        assert!("8".parse::<MarginMask>().is_err());
+        assert!(MarginMask::fixed(0.6, 0.0).validate().is_err());
+    }
+}
@@ -13,17 +13,41 @@
 //! ```
 //!
 //! 1. [`input`]  — canonicalise the user-supplied path or URL to a local file
-//! 2. [`render`] — rasterise selected pages; runs in `spawn_blocking` because
-//!    pdfium is not async-safe
+//! 2. [`render`] — rasterise selected pages; runs on the thread of a
+//!    [`session`] because pdfium is not async-safe
 //! 3. [`encode`] — PNG-encode and base64-wrap each `DynamicImage` for the
 //!    multimodal API request body
 //! 4. [`llm`]    — drive the VLM call with retry/backoff; the only stage with
 //!    network I/O
 //! 5. [`postprocess`] — deterministic text-cleanup rules to fix VLM quirks
 //!    (markdown fences, hallucinated images, broken tables, etc.)
+//!
+//! [`layout`] is a helper for `render`: it inspects the text layer of each
+//! page to derive prompt hints (e.g. column count) alongside the image.
+//! [`sections`] splits a document at chapter boundaries so `maintain_format`
+//! can run chapters in parallel. `session` keeps one document open for all
+//! the passes of a conversion. [`blank`] spots empty scans after render so
+//! they can skip the VLM call, and [`spread`] finds the gutter of two-page
+//! book scans so each page is converted on its own. [`margins`] paints
+//! running headers and footers white before encoding, and [`watermark`]
+//! suppresses diagonal "DRAFT" stamps. [`timing`] collects the
+//! per-page stage latencies reported in [`crate::ConversionStats`].
+//! [`flavor`] rewrites cleaned pages for the configured Markdown dialect;
+//! [`unicode`] fixes ligatures, soft hyphens, and normalisation before
+//! cleanup.
 
+pub mod blank;
 pub mod encode;
+pub mod flavor;
 pub mod input;
+pub mod layout;
 pub mod llm;
+pub mod margins;
 pub mod postprocess;
 pub mod render;
+pub mod sections;
+pub(crate) mod session;
+pub mod spread;
+pub mod timing;
+pub mod unicode;
+pub mod watermark;
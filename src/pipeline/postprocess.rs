@@ -24,8 +24,233 @@
 /// standardised before the fence-stripping regex runs, strip fences before
 /// heading-spacing so heading detection works on clean input, and remove
 /// image links before the final-newline pass.
+use crate::charts;
+use crate::config::{
+    ContentHint, ConversionConfig, FidelityTier, FilterDecision, ImageLinkPolicy, TocHandling,
+};
+use crate::diff::{self, Op};
+use crate::figures;
+use crate::footnotes;
+use crate::handwriting;
+use crate::mermaid;
+use crate::metrics::PipelineStage;
+use crate::niche;
+use crate::output::PageResult;
+use crate::page_kind::PageKind;
+use crate::pipeline::{flavor, unicode};
+use crate::toc;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tracing::{debug, debug_span};
+
+/// Clean a successful page's Markdown in place, inside a `pdf2md.postprocess`
+/// span, reporting the duration to the configured metrics sink.
+///
+/// [`ConversionConfig::unicode`] runs before the generic rules,
+/// [`ConversionConfig::preset`] adds its own rules after them,
+/// [`ConversionConfig::link_footnotes`] then normalises footnotes, and
+/// [`ConversionConfig::flavor`] rewrites the result for its dialect last.
+/// With [`ConversionConfig::chart_tables`] the chart tables are parsed into
+/// [`PageResult::charts`] before figure links can replace their captions.
+/// Contents pages are handled per [`ConversionConfig::toc_handling`] and
+/// reported as `toc`.
+/// Failed pages (with `error` set) are left untouched. With
+/// [`ConversionConfig::keep_raw_output`] the uncleaned text is first copied
+/// to [`PageResult::raw_markdown`].
+///
+/// Returns what each rule changed; the unicode, preset, footnote, and flavor
+/// passes are reported as `unicode`, `preset`, `footnotes`, and `flavor`.
+pub(crate) fn postprocess_page(result: &mut PageResult, config: &ConversionConfig) -> CleanReport {
+    if result.error.is_some() {
+        return CleanReport::default();
+    }
+    let _span = debug_span!("pdf2md.postprocess", page = result.page_num).entered();
+    let start = Instant::now();
+    if config.keep_raw_output {
+        result.raw_markdown = Some(result.markdown.clone());
+    }
+    let mut report = CleanReport::default();
+    let s = report.apply("unicode", &result.markdown, |s| {
+        unicode::apply(s, &config.unicode)
+    });
+    let (mut s, rules) = clean_markdown_with(&s, &config.image_link_policy);
+    report.merge(&rules);
+    if config.content_hint == ContentHint::Handwritten {
+        s = report.apply("handwriting", &s, handwriting::mark_uncertain);
+    }
+    if config.fidelity == FidelityTier::Tier4 {
+        s = report.apply("mermaid", &s, mermaid::check_blocks);
+    }
+    if config.chart_tables {
+        result.charts = charts::extract(&s);
+    }
+    if config.niche_placeholders {
+        let thumbnails = config.placeholder_thumbnails.as_deref();
+        s = report.apply("placeholders", &s, |s| {
+            let (out, class) = niche::replace_markers(s, result.page_num, thumbnails);
+            result.content_class = class;
+            out
+        });
+    }
+    if config.toc_handling != TocHandling::Keep
+        && (result.page_kind == Some(PageKind::Toc) || toc::is_toc(&s))
+    {
+        result.page_kind = Some(PageKind::Toc);
+        s = report.apply("toc", &s, |s| match config.toc_handling {
+            TocHandling::Normalize => toc::normalize(s),
+            _ => String::new(),
+        });
+    }
+    if !result.figures.is_empty() {
+        s = report.apply("figures", &s, |s| figures::place(s, &result.figures));
+    }
+    if let Some(preset) = config.preset {
+        s = report.apply("preset", &s, |s| preset.postprocess(s, result.page_num));
+    }
+    if config.link_footnotes {
+        s = report.apply("footnotes", &s, footnotes::normalize_page);
+    }
+    result.markdown = report.apply("flavor", &s, |s| flavor::apply(s, config.flavor));
+    if let Some(ref m) = config.metrics_sink {
+        m.record_stage(PipelineStage::Postprocess, result.page_num, start.elapsed());
+    }
+    report
+}
+
+/// Pass a successful, non-blank page through
+/// [`ConversionConfig::output_filter`], if one is set.
+pub(crate) fn filter_page(result: &mut PageResult, config: &ConversionConfig) {
+    let Some(ref filter) = config.output_filter else {
+        return;
+    };
+    if result.error.is_some() || result.skipped_blank {
+        return;
+    }
+    let text = match filter(result.page_num, result.markdown.clone()) {
+        FilterDecision::Keep => return,
+        FilterDecision::Redact(text) => text,
+        FilterDecision::Drop => String::new(),
+    };
+    debug!("Output filter withheld text of page {}", result.page_num);
+    // The withheld text must not survive in the raw copy either.
+    if result.raw_markdown.is_some() {
+        result.raw_markdown = Some(text.clone());
+    }
+    result.markdown = text;
+}
+
+/// How much one rule changed, in a [`CleanReport`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleChange {
+    /// Rule name, e.g. `"remove_hallucinated_images"`.
+    pub rule: String,
+    /// Pages the rule changed.
+    pub pages: usize,
+    /// Characters the rule removed, added, or replaced.
+    pub chars_changed: usize,
+}
+
+/// Which post-processing rules fired, and how much each changed.
+///
+/// [`clean_markdown`] returns one per page; conversions sum them into
+/// [`crate::ConversionStats::postprocess_report`]. Rules that changed
+/// nothing are absent. Characters are counted per changed line, from the
+/// first differing character to the last, so rewriting
+/// `![Figure](fig.png)` as `*Figure*` counts 18 and a dropped blank line 1.
+/// Changes to trailing newlines alone are not reported.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanReport {
+    /// In the order the rules first fired.
+    pub rules: Vec<RuleChange>,
+}
+
+impl CleanReport {
+    /// `true` when no rule changed anything.
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The entry for `rule`, if it fired.
+    pub fn get(&self, rule: &str) -> Option<&RuleChange> {
+        self.rules.iter().find(|r| r.rule == rule)
+    }
+
+    /// Add `other`'s counts to this report.
+    pub fn merge(&mut self, other: &CleanReport) {
+        for change in &other.rules {
+            match self.rules.iter_mut().find(|r| r.rule == change.rule) {
+                Some(r) => {
+                    r.pages += change.pages;
+                    r.chars_changed += change.chars_changed;
+                }
+                None => self.rules.push(change.clone()),
+            }
+        }
+    }
+
+    /// Run `rule` on `text`, recording what it changed.
+    ///
+    /// Trailing newlines are ignored: several rules rebuild the text line by
+    /// line and add or drop the last one, which `ensure_final_newline`
+    /// settles anyway.
+    fn apply(&mut self, rule: &str, text: &str, f: impl FnOnce(&str) -> String) -> String {
+        let out = f(text);
+        if out.trim_end_matches('\n') != text.trim_end_matches('\n') {
+            self.merge(&CleanReport {
+                rules: vec![RuleChange {
+                    rule: rule.to_string(),
+                    pages: 1,
+                    chars_changed: changed_chars(text, &out),
+                }],
+            });
+        }
+        out
+    }
+}
+
+/// Characters changed from `before` to `after`: a line diff, then per
+/// changed line (or block, when lines were added or removed) the span
+/// between the first and last differing character.
+fn changed_chars(before: &str, after: &str) -> usize {
+    let a: Vec<&str> = before.split_inclusive('\n').collect();
+    let b: Vec<&str> = after.split_inclusive('\n').collect();
+    let (mut removed, mut added) = (Vec::new(), Vec::new());
+    let mut total = 0;
+    for (op, line) in diff::diff_lines(&a, &b)
+        .into_iter()
+        .chain([(Op::Equal, "")])
+    {
+        match op {
+            Op::Delete => removed.push(line),
+            Op::Insert => added.push(line),
+            Op::Equal => {
+                total += if removed.len() == added.len() {
+                    removed.iter().zip(&added).map(|(x, y)| span(x, y)).sum()
+                } else {
+                    span(&removed.concat(), &added.concat())
+                };
+                removed.clear();
+                added.clear();
+            }
+        }
+    }
+    total
+}
+
+/// Length of the differing middle of `a` and `b`, in characters.
+fn span(a: &str, b: &str) -> usize {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    (a.len() - prefix - suffix).max(b.len() - prefix - suffix)
+}
 
 /// Apply all post-processing rules to the raw VLM output.
 ///
@@ -45,17 +270,34 @@ use regex::Regex;
 /// 8. Remove hallucinated image links (`![...]()` with fake/placeholder URLs)
 /// 9. Strip invisible Unicode (zero-width spaces, BOM, soft hyphens, etc.)
 /// 10. Ensure the file ends with exactly one newline
-pub fn clean_markdown(input: &str) -> String {
-    let s = normalise_line_endings(input); // must run before strip_markdown_fences
-    let s = strip_markdown_fences(&s);
-    let s = trim_trailing_whitespace(&s);
-    let s = collapse_blank_lines(&s);
-    let s = normalise_heading_spacing(&s);
-    let s = fix_broken_tables(&s);
-    let s = remove_mid_table_separators(&s);
-    let s = remove_hallucinated_images(&s);
-    let s = remove_invisible_chars(&s);
-    ensure_final_newline(&s)
+///
+/// Also returns a [`CleanReport`] of the rules that changed the text, named
+/// after the functions above (`strip_markdown_fences`, …).
+pub fn clean_markdown(input: &str) -> (String, CleanReport) {
+    clean_markdown_with(input, &ImageLinkPolicy::default())
+}
+
+/// [`clean_markdown`] with rule 8 keeping the image links `images` allows.
+pub fn clean_markdown_with(input: &str, images: &ImageLinkPolicy) -> (String, CleanReport) {
+    let mut r = CleanReport::default();
+    // Line endings must be normalised before strip_markdown_fences.
+    let s = r.apply("normalise_line_endings", input, normalise_line_endings);
+    let s = r.apply("strip_markdown_fences", &s, strip_markdown_fences);
+    let s = r.apply("trim_trailing_whitespace", &s, trim_trailing_whitespace);
+    let s = r.apply("collapse_blank_lines", &s, collapse_blank_lines);
+    let s = r.apply("normalise_heading_spacing", &s, normalise_heading_spacing);
+    let s = r.apply("fix_broken_tables", &s, fix_broken_tables);
+    let s = r.apply(
+        "remove_mid_table_separators",
+        &s,
+        remove_mid_table_separators,
+    );
+    let s = r.apply("remove_hallucinated_images", &s, |s| {
+        remove_hallucinated_images(s, images)
+    });
+    let s = r.apply("remove_invisible_chars", &s, remove_invisible_chars);
+    let s = r.apply("ensure_final_newline", &s, ensure_final_newline);
+    (s, r)
 }
 
 // ── Rule 1: Strip outer markdown fences ──────────────────────────────────────
@@ -69,14 +311,18 @@ pub fn clean_markdown(input: &str) -> String {
 /// ```
 /// ````
 ///
-/// The strategy is deliberately lenient:
-///  - The first line is removed if it is a bare fence opener (```` ``` ```` or
-///    ```` ```markdown ````).
-///  - The last non-empty line is removed if it is a bare fence closer (```` ``` ````).
-///  - If only one of the two is present we still strip it (handles models that
-///    open but don't close, or vice versa).
-///  - A second pass handles the degenerate case where the model emits trailing
-///    commentary after the closing fence: we only strip the last ```` ``` ```` line.
+/// But a page can also genuinely *be* a code block — an appendix listing,
+/// a config file — so the strategy is structure-aware:
+///  - A leading ```` ```markdown ```` (or `md`, `gfm`, `mdx`) opener is
+///    always a wrapper and is removed.
+///  - A bare ```` ``` ```` opener is removed only when the block reads as
+///    Markdown rather than code (see [`looks_like_markdown`]); an opener
+///    with any other language tag (```` ```python ````) is real code and kept.
+///  - The last line is removed if it is a bare ```` ``` ```` that closes the
+///    removed wrapper, or that closes nothing (models that close but don't
+///    open). A closer that ends a real code block at the bottom of the page
+///    is kept.
+///  - Openers without a closer are still stripped (truncated output).
 fn strip_markdown_fences(input: &str) -> String {
     let trimmed = input.trim();
     if trimmed.is_empty() {
@@ -88,25 +334,96 @@ fn strip_markdown_fences(input: &str) -> String {
         return trimmed.to_string();
     }
 
-    // Strip leading fence opener: ``` or ```<lang>
+    // Strip leading fence opener: ```markdown, or ``` around Markdown
     let first = lines[0].trim();
     if first.starts_with("```") && !first[3..].contains('`') {
-        lines.remove(0);
+        let lang = first[3..].trim().to_ascii_lowercase();
+        let wrapper = match lang.as_str() {
+            "markdown" | "md" | "gfm" | "mdx" => true,
+            "" => looks_like_markdown(&lines[1..]),
+            _ => false,
+        };
+        if wrapper {
+            lines.remove(0);
+        }
     }
 
     if lines.is_empty() {
         return String::new();
     }
 
-    // Strip trailing fence closer: must be exactly ```  (nothing else)
+    // Strip trailing fence closer: exactly ``` with no fence left open for
+    // it to close. Fences inside the content pair up; an odd count means the
+    // last one belongs to the wrapper (or to nothing).
     let last = lines[lines.len() - 1].trim();
-    if last == "```" {
+    let fences = lines
+        .iter()
+        .filter(|l| l.trim_start().starts_with("```"))
+        .count();
+    if last == "```" && fences % 2 == 1 {
         lines.pop();
     }
 
     lines.join("\n")
 }
 
+/// Whether the lines inside a bare ```` ``` ```` fence read as Markdown (a
+/// wrapped page) rather than source code: Markdown structure — headings,
+/// tables, lists, quotes, links, bold — must outweigh code signals such as
+/// trailing `;`/`{`/`}`, keywords, and indentation. Prose with neither
+/// counts as Markdown. Inner code fences are skipped.
+fn looks_like_markdown(lines: &[&str]) -> bool {
+    const KEYWORDS: [&str; 14] = [
+        "def ",
+        "fn ",
+        "function ",
+        "class ",
+        "import ",
+        "from ",
+        "return ",
+        "let ",
+        "const ",
+        "var ",
+        "pub ",
+        "#include",
+        "#!",
+        "package ",
+    ];
+    let (mut markdown, mut code) = (0usize, 0usize);
+    let mut in_fence = false;
+    for line in lines {
+        let t = line.trim();
+        if t.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || t.is_empty() {
+            continue;
+        }
+        if line.starts_with("    ")
+            || line.starts_with('\t')
+            || t.ends_with(';')
+            || t.ends_with('{')
+            || t == "}"
+            || KEYWORDS.iter().any(|k| t.starts_with(k))
+        {
+            code += 1;
+        } else if RE_HEADING_LINE.is_match(line)
+            || t.starts_with('|')
+            || t.starts_with("> ")
+            || RE_LIST_ITEM.is_match(t)
+            || t.contains("](")
+            || t.contains("**")
+        {
+            markdown += 1;
+        }
+    }
+    markdown >= code
+}
+
+static RE_HEADING_LINE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^#{1,6} \S").unwrap());
+static RE_LIST_ITEM: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?:[-*+]|\d+[.)]) \S").unwrap());
+
 fn normalise_line_endings(input: &str) -> String {
     input.replace("\r\n", "\n").replace('\r', "\n")
 }
@@ -233,19 +550,29 @@ fn remove_invisible_chars(input: &str) -> String {
 //
 // Strategy: keep `![alt](url)` only when the url starts with `http://` or
 // `https://` AND the host is not a known placeholder domain. Otherwise convert
-// to `*alt*` (italic caption) so the text is not lost.
+// to `*alt*` (italic caption) so the text is not lost. `ImageLinkPolicy` can
+// also keep relative file paths or allow-listed prefixes, for workflows that
+// put the extracted figures next to the Markdown.
 
 static RE_IMAGE: Lazy<Regex> = Lazy::new(|| Regex::new(r"!\[([^\]]*)\]\(([^)]*)\)").unwrap());
 
 /// List of URL patterns that indicate a fabricated/placeholder image link.
-fn is_placeholder_url(url: &str) -> bool {
+fn is_placeholder_url(url: &str, policy: &ImageLinkPolicy) -> bool {
     let u = url.trim();
     if u.is_empty() {
         return true;
     }
+    if let ImageLinkPolicy::AllowList(prefixes) = policy {
+        if prefixes
+            .iter()
+            .any(|p| !p.is_empty() && u.starts_with(p.as_str()))
+        {
+            return false;
+        }
+    }
     // Local-looking or obviously fake URLs
     if !u.starts_with("http://") && !u.starts_with("https://") {
-        return true;
+        return !(*policy == ImageLinkPolicy::KeepRelative && is_relative_file(u));
     }
     // Known placeholder/example domains
     let fake_domains = [
@@ -260,12 +587,24 @@ fn is_placeholder_url(url: &str) -> bool {
     fake_domains.iter().any(|d| u.contains(d))
 }
 
-fn remove_hallucinated_images(input: &str) -> String {
+/// `figures/fig1.png`, `./p3.jpg`: no scheme, and a file name with an
+/// extension (which `image-url` lacks).
+fn is_relative_file(u: &str) -> bool {
+    if u.contains(':') || u.starts_with("//") || u.contains(char::is_whitespace) {
+        return false;
+    }
+    let name = u.rsplit('/').next().unwrap_or(u);
+    name.rsplit_once('.').is_some_and(|(stem, ext)| {
+        !stem.is_empty() && !ext.is_empty() && ext.chars().all(|c| c.is_ascii_alphanumeric())
+    })
+}
+
+fn remove_hallucinated_images(input: &str, policy: &ImageLinkPolicy) -> String {
     RE_IMAGE
         .replace_all(input, |caps: &regex::Captures<'_>| {
             let alt = caps[1].trim();
             let url = &caps[2];
-            if is_placeholder_url(url) {
+            if is_placeholder_url(url, policy) {
                 // Replace with just an italic caption (preserve the description text)
                 if alt.is_empty() {
                     String::new()
@@ -325,6 +664,63 @@ fn remove_mid_table_separators(input: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_postprocess_page_keeps_raw_output() {
+        let mut page = PageResult::new(1, "```markdown\n# Title\n```");
+        let mut plain = page.clone();
+        let config = ConversionConfig {
+            keep_raw_output: true,
+            ..Default::default()
+        };
+        postprocess_page(&mut page, &config);
+        assert_eq!(page.markdown, "# Title\n");
+        assert_eq!(
+            page.raw_markdown.as_deref(),
+            Some("```markdown\n# Title\n```")
+        );
+
+        postprocess_page(&mut plain, &ConversionConfig::default());
+        assert_eq!(plain.raw_markdown, None);
+    }
+
+    #[test]
+    fn test_filter_page_applies_decision() {
+        let page = PageResult {
+            page_num: 2,
+            markdown: "Account 4412\n".into(),
+            raw_markdown: Some("Account 4412".into()),
+            ..Default::default()
+        };
+        let config = |decision: FilterDecision| {
+            ConversionConfig::builder()
+                .output_filter(Arc::new(move |page, _| {
+                    assert_eq!(page, 2);
+                    decision.clone()
+                }))
+                .build()
+                .unwrap()
+        };
+
+        let mut kept = page.clone();
+        filter_page(&mut kept, &config(FilterDecision::Keep));
+        assert_eq!(kept.markdown, page.markdown);
+        assert_eq!(kept.raw_markdown, page.raw_markdown);
+
+        let mut redacted = page.clone();
+        filter_page(
+            &mut redacted,
+            &config(FilterDecision::Redact("[withheld]\n".into())),
+        );
+        assert_eq!(redacted.markdown, "[withheld]\n");
+        assert_eq!(redacted.raw_markdown.as_deref(), Some("[withheld]\n"));
+
+        let mut dropped = page.clone();
+        filter_page(&mut dropped, &config(FilterDecision::Drop));
+        assert_eq!(dropped.markdown, "");
+        assert_eq!(dropped.raw_markdown.as_deref(), Some(""));
+    }
 
     #[test]
     fn test_strip_fences() {
@@ -338,6 +734,51 @@ mod tests {
         assert_eq!(strip_markdown_fences(input), "# Hello\nWorld");
     }
 
+    #[test]
+    fn test_strip_fences_keeps_genuine_code_pages() {
+        // An appendix listing tagged with its language is content.
+        let listing = "```python\ndef total(xs):\n    return sum(xs)\n```";
+        assert_eq!(strip_markdown_fences(listing), listing);
+
+        // A bare fence around code is content too, comments and all.
+        let bare = "```\n# compute totals\nfn main() {\n    run();\n}\n```";
+        assert_eq!(strip_markdown_fences(bare), bare);
+
+        // A code block that merely ends the page keeps its closer.
+        let tail = "# Appendix B\n\n```rust\nlet x = 1;\n```";
+        assert_eq!(strip_markdown_fences(tail), tail);
+        let bare_tail = "See below.\n\n```\nmake install\n```";
+        assert_eq!(strip_markdown_fences(bare_tail), bare_tail);
+    }
+
+    #[test]
+    fn test_strip_fences_pathological_wrappers() {
+        // Wrapper around a page that itself ends in a code block.
+        let nested = "```markdown\n# Setup\n\n```bash\nmake\n```\n```";
+        assert_eq!(
+            strip_markdown_fences(nested),
+            "# Setup\n\n```bash\nmake\n```"
+        );
+        // Bare wrapper whose only code is inside an inner fence.
+        let bare = "```\n| A | B |\n| --- | --- |\n\n```js\nlet a = 1;\nlet b = 2;\n```\n```";
+        assert_eq!(
+            strip_markdown_fences(bare),
+            "| A | B |\n| --- | --- |\n\n```js\nlet a = 1;\nlet b = 2;\n```"
+        );
+        // Opened but never closed, closed but never opened.
+        assert_eq!(
+            strip_markdown_fences("```md\n# Title\nText"),
+            "# Title\nText"
+        );
+        assert_eq!(strip_markdown_fences("# Title\nText\n```"), "# Title\nText");
+        // Only fences.
+        assert_eq!(strip_markdown_fences("```markdown\n```"), "");
+        assert_eq!(strip_markdown_fences("```"), "");
+        // Inline triple backticks are not fences.
+        let inline = "```x``` is inline";
+        assert_eq!(strip_markdown_fences(inline), inline);
+    }
+
     #[test]
     fn test_no_fences_passthrough() {
         let input = "# Hello\nWorld";
@@ -351,7 +792,7 @@ mod tests {
     fn test_strip_fences_crlf_local_model_output() {
         // Simulate glm-ocr/llava returning CRLF-terminated fenced output
         let input = "```markdown\r\n# Tax Form 1040\r\nIncome: $50,000\r\n```";
-        let result = clean_markdown(input);
+        let (result, _) = clean_markdown(input);
         assert!(
             !result.starts_with("```"),
             "CRLF fences must be stripped; got: {:?}",
@@ -420,7 +861,7 @@ mod tests {
     #[test]
     fn test_remove_hallucinated_image_placeholder_url() {
         let input = "Some text\n![Chart Title](chart.png)\nMore text";
-        let result = remove_hallucinated_images(input);
+        let result = remove_hallucinated_images(input, &ImageLinkPolicy::StripAll);
         assert!(
             !result.contains("!["),
             "Should remove image with local path"
@@ -434,7 +875,7 @@ mod tests {
     #[test]
     fn test_remove_hallucinated_image_fake_url() {
         let input = "![Diagram](image-url)";
-        let result = remove_hallucinated_images(input);
+        let result = remove_hallucinated_images(input, &ImageLinkPolicy::StripAll);
         assert!(!result.contains("!["));
         assert!(result.contains("*Diagram*"));
     }
@@ -442,10 +883,30 @@ mod tests {
     #[test]
     fn test_keep_real_image_link() {
         let input = "![Figure](https://arxiv.org/figures/fig1.png)";
-        let result = remove_hallucinated_images(input);
+        let result = remove_hallucinated_images(input, &ImageLinkPolicy::StripAll);
         assert!(result.contains("![Figure]"), "Should keep real image link");
     }
 
+    #[test]
+    fn test_image_link_policy_keeps_relative_or_allowed_links() {
+        let input =
+            "![A](figures/fig1.png) ![B](image-url) ![C](assets/c) ![D](https://example.com/d.png)";
+        let keep = remove_hallucinated_images(input, &ImageLinkPolicy::KeepRelative);
+        assert_eq!(keep, "![A](figures/fig1.png) *B* *C* *D*");
+
+        let allow =
+            ImageLinkPolicy::AllowList(vec!["assets/".into(), "https://example.com/".into()]);
+        let kept = remove_hallucinated_images(input, &allow);
+        assert_eq!(
+            kept,
+            "*A* *B* ![C](assets/c) ![D](https://example.com/d.png)"
+        );
+
+        let (page, _) = clean_markdown_with("![Fig](./img/p3.jpg)", &ImageLinkPolicy::KeepRelative);
+        assert_eq!(page, "![Fig](./img/p3.jpg)\n");
+        assert!(!is_relative_file("data:image/png;base64,AAAA"));
+    }
+
     #[test]
     fn test_remove_mid_table_separator() {
         let input = "| A | B |\n| --- | --- |\n| 1 | 2 |\n| --- | --- |\n| 3 | 4 |";
@@ -466,10 +927,42 @@ mod tests {
     #[test]
     fn test_clean_markdown_full_pipeline() {
         let input = "```markdown\n# Title\r\n\r\nSome text   \n\n\n\n\n\n## Section\n\n| A | B |\n| 1 | 2 |\n```";
-        let result = clean_markdown(input);
+        let (result, _) = clean_markdown(input);
         assert!(result.starts_with("# Title"));
         assert!(result.ends_with('\n'));
         // No excessive blank lines
         assert!(!result.contains("\n\n\n\n"));
     }
+
+    #[test]
+    fn test_clean_report_counts_changes_per_rule() {
+        let input = "```markdown\r\n# Results\r\nSee ![Chart](chart.png) below.\r\n```";
+        let (result, report) = clean_markdown(input);
+        assert_eq!(result, "# Results\nSee *Chart* below.\n");
+
+        let fired: Vec<&str> = report.rules.iter().map(|r| r.rule.as_str()).collect();
+        assert_eq!(
+            fired,
+            [
+                "normalise_line_endings",
+                "strip_markdown_fences",
+                "remove_hallucinated_images"
+            ]
+        );
+        let chars = |rule| report.get(rule).unwrap().chars_changed;
+        assert_eq!(chars("normalise_line_endings"), 3);
+        assert_eq!(
+            chars("strip_markdown_fences"),
+            "```markdown\n".len() + "\n```".len()
+        );
+        assert_eq!(
+            chars("remove_hallucinated_images"),
+            "![Chart](chart.png)".len()
+        );
+
+        let mut total = report.clone();
+        total.merge(&report);
+        assert_eq!(total.get("strip_markdown_fences").unwrap().pages, 2);
+        assert!(clean_markdown("# Clean\n").1.is_empty());
+    }
 }
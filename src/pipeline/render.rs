@@ -8,23 +8,35 @@
 //! thread designed for blocking operations, preventing the Tokio worker
 //! threads from stalling during CPU-heavy rendering.
 //!
-//! ## Why cap pixels, not DPI?
+//! ## Why DPI *and* a pixel cap?
 //!
-//! Page sizes vary wildly: an A0 poster at 150 DPI would produce a
-//! 12,000 × 17,000 px image. `max_rendered_pixels` caps the longest edge
-//! regardless of physical size, keeping memory bounded and matching the
-//! image-size sweet spot for GPT-4 vision (around 1,024–2,048 px).
-
-use super::encode;
-use crate::config::ConversionConfig;
+//! Pages render at [`ConversionConfig::dpi`]: a US Letter page at 150 DPI is
+//! 1275 px wide. But page sizes vary wildly: an A0 poster at 150 DPI would
+//! produce a 12,000 × 17,000 px image. `max_rendered_pixels` caps the longest
+//! edge regardless of physical size ([`render_size`]), keeping memory bounded
+//! and matching the image-size sweet spot for GPT-4 vision (around
+//! 1,024–2,048 px).
+
+use super::margins::{self, MarginMask};
+use super::session::PdfSession;
+use super::watermark;
+use super::{blank, encode, layout, sections, spread};
+use crate::attachments::Attachment;
+use crate::backend::PageImage;
+use crate::config::{ConversionConfig, CropRect, ImageFilter, RenderLayers, RenderOptions};
 use crate::error::Pdf2MdError;
-use crate::output::DocumentMetadata;
-use edgequake_llm::ImageData;
+use crate::figures;
+use crate::metrics::{PipelineStage, SharedMetricsSink};
+use crate::output::{DocumentMetadata, PageInfo};
+use crate::signatures;
+use crate::tagged::{self, TaggedHeading};
 use image::DynamicImage;
 use pdfium_render::prelude::*;
+use std::collections::HashMap;
 use std::path::Path;
-use tokio::sync::{mpsc, oneshot};
-use tracing::{debug, info, warn};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::{debug, debug_span, field, info, warn};
 
 /// Obtain a `Pdfium` instance via pdfium-auto.
 ///
@@ -38,7 +50,7 @@ use tracing::{debug, info, warn};
 /// # Errors
 /// Returns `Pdf2MdError::Internal` when the library cannot be loaded.  The
 /// error message includes a `PDFIUM_LIB_PATH` override hint.
-fn get_pdfium() -> Result<Pdfium, Pdf2MdError> {
+pub(crate) fn get_pdfium() -> Result<Pdfium, Pdf2MdError> {
     #[cfg(feature = "bundled")]
     {
         pdfium_auto::bind_bundled().map_err(|e| {
@@ -58,6 +70,24 @@ fn get_pdfium() -> Result<Pdfium, Pdf2MdError> {
     })
 }
 
+/// Make sure the pdfium library is on disk before a blocking task binds it.
+///
+/// Without the `bundled` feature the first use downloads the library; doing
+/// that here, with async HTTP, keeps the download off tokio's worker and
+/// blocking threads. Later [`get_pdfium`] calls find it without I/O.
+pub(crate) async fn ensure_pdfium() -> Result<(), Pdf2MdError> {
+    #[cfg(not(feature = "bundled"))]
+    pdfium_auto::ensure_pdfium_library_async(None)
+        .await
+        .map_err(|e| {
+            Pdf2MdError::Internal(format!(
+                "PDFium library unavailable: {e}\n\
+                 Hint: set PDFIUM_LIB_PATH=/path/to/libpdfium to use an existing copy."
+            ))
+        })?;
+    Ok(())
+}
+
 /// Rasterise selected pages of a PDF into images.
 ///
 /// This runs inside `spawn_blocking` since pdfium operations are CPU-bound.
@@ -69,14 +99,25 @@ pub async fn render_pages(
     config: &ConversionConfig,
     page_indices: &[usize],
 ) -> Result<Vec<(usize, DynamicImage)>, Pdf2MdError> {
+    ensure_pdfium().await?;
     let path = pdf_path.to_path_buf();
     let dpi = config.dpi;
     let max_pixels = config.max_rendered_pixels;
     let password = config.password.clone();
+    let layers = config.render_layers;
+    let render_options = config.render_options;
     let indices = page_indices.to_vec();
 
     let result = tokio::task::spawn_blocking(move || {
-        render_pages_blocking(&path, dpi, max_pixels, password.as_deref(), &indices)
+        render_pages_blocking(
+            &path,
+            dpi,
+            max_pixels,
+            password.as_deref(),
+            &layers,
+            &render_options,
+            &indices,
+        )
     })
     .await
     .map_err(|e| Pdf2MdError::Internal(format!("Render task panicked: {}", e)))?;
@@ -87,9 +128,11 @@ pub async fn render_pages(
 /// Blocking implementation of page rendering.
 fn render_pages_blocking(
     pdf_path: &Path,
-    _dpi: u32,
+    dpi: u32,
     max_pixels: u32,
     password: Option<&str>,
+    layers: &RenderLayers,
+    render_options: &RenderOptions,
     page_indices: &[usize],
 ) -> Result<Vec<(usize, DynamicImage)>, Pdf2MdError> {
     let pdfium = get_pdfium()?;
@@ -102,10 +145,6 @@ fn render_pages_blocking(
     let total_pages = pages.len() as usize;
     info!("PDF loaded: {} pages", total_pages);
 
-    let render_config = PdfRenderConfig::new()
-        .set_target_width(max_pixels as i32)
-        .set_maximum_height(max_pixels as i32);
-
     let mut results = Vec::with_capacity(page_indices.len());
 
     for &idx in page_indices {
@@ -118,19 +157,29 @@ fn render_pages_blocking(
             continue;
         }
 
-        let page = pages
+        let mut page = pages
             .get(idx as u16)
             .map_err(|e| Pdf2MdError::RasterisationFailed {
                 page: idx + 1,
                 detail: format!("{:?}", e),
             })?;
+        if !layers.content {
+            clear_content(&mut page);
+        }
 
-        let bitmap = page.render_with_config(&render_config).map_err(|e| {
-            Pdf2MdError::RasterisationFailed {
-                page: idx + 1,
-                detail: format!("{:?}", e),
-            }
-        })?;
+        let sized = sized_config(
+            render_config_for(layers, render_options),
+            &page,
+            dpi as f32,
+            max_pixels,
+            max_pixels,
+        );
+        let bitmap =
+            page.render_with_config(&sized)
+                .map_err(|e| Pdf2MdError::RasterisationFailed {
+                    page: idx + 1,
+                    detail: format!("{:?}", e),
+                })?;
 
         let image = bitmap.as_image();
         debug!(
@@ -146,6 +195,70 @@ fn render_pages_blocking(
     Ok(results)
 }
 
+/// Render settings that draw the chosen [`RenderLayers`] with the chosen
+/// [`RenderOptions`].
+fn render_config_for(layers: &RenderLayers, options: &RenderOptions) -> PdfRenderConfig {
+    PdfRenderConfig::new()
+        .render_annotations(layers.annotations)
+        .render_form_data(layers.form_fields)
+        .set_text_smoothing(options.text_smoothing)
+        .set_image_smoothing(options.image_smoothing)
+        .set_path_smoothing(options.path_smoothing)
+        .use_lcd_text_rendering(options.lcd_text)
+        .disable_native_text_rendering(!options.native_text)
+        .set_reverse_byte_order(options.reverse_byte_order)
+}
+
+/// Bitmap size of a `width_pt × height_pt` page rendered at `dpi`, scaled
+/// down to fit within `max_width × max_height` pixels.
+pub(crate) fn render_size(
+    width_pt: f32,
+    height_pt: f32,
+    dpi: f32,
+    max_width: u32,
+    max_height: u32,
+) -> (u32, u32) {
+    let scale = dpi / 72.0;
+    let (width, height) = (width_pt * scale, height_pt * scale);
+    let fit = (max_width as f32 / width)
+        .min(max_height as f32 / height)
+        .min(1.0);
+    (
+        (width * fit).round().max(1.0) as u32,
+        (height * fit).round().max(1.0) as u32,
+    )
+}
+
+/// `base` sized for `page` at `dpi` within `max_width × max_height`.
+fn sized_config(
+    base: PdfRenderConfig,
+    page: &PdfPage,
+    dpi: f32,
+    max_width: u32,
+    max_height: u32,
+) -> PdfRenderConfig {
+    let (width, height) = render_size(
+        page.width().value,
+        page.height().value,
+        dpi,
+        max_width,
+        max_height,
+    );
+    base.set_target_width(width as i32)
+        .set_maximum_height(height as i32)
+}
+
+/// Remove every object of the in-memory page, so that only its annotations
+/// and form fields are drawn. The file is never written.
+fn clear_content(page: &mut PdfPage) {
+    let objects = page.objects_mut();
+    for index in (0..objects.len()).rev() {
+        if let Err(e) = objects.remove_object_at_index(index) {
+            debug!("Could not remove page object {}: {:?}", index, e);
+        }
+    }
+}
+
 // ── Lazy render + encode pipeline ────────────────────────────────────────
 
 /// A single page that has been rendered and base64-encoded, ready for VLM.
@@ -157,17 +270,137 @@ pub struct EncodedPage {
     /// 0-based page index.
     pub page_index: usize,
     /// Base64-encoded PNG image data.
-    pub image_data: ImageData,
+    pub image_data: PageImage,
+    /// Tiles of a page split by [`ConversionConfig::tile_dense_pages`] (or the
+    /// two halves of a spread, see `spread`), in reading order, each with a
+    /// human-readable position ("top half").
+    /// Empty for ordinary pages; when non-empty `image_data` holds the first
+    /// tile and should not be sent on its own.
+    pub tiles: Vec<(PageImage, &'static str)>,
+    /// Text columns detected by [`layout::detect_columns`] when
+    /// [`ConversionConfig::layout_hints`] is on; `1` otherwise.
+    pub columns: usize,
     /// Time spent rendering + encoding this single page (ms).
     pub render_encode_ms: u64,
+    /// `true` when [`blank::is_blank`] judged the page empty under
+    /// [`ConversionConfig::skip_blank_pages`]. `image_data` is then empty and
+    /// the page must not be sent to the model.
+    pub blank: bool,
+    /// Clockwise degrees the rendered image was turned by
+    /// [`ConversionConfig::auto_rotate`]; `0` otherwise.
+    pub rotation: u16,
+    /// `true` when `tiles` are the left and right pages of a two-page spread
+    /// split by [`ConversionConfig::split_spreads`], rather than parts of one
+    /// dense page.
+    pub spread: bool,
+    /// Why the page could not be rendered or encoded. When set, the image
+    /// fields are empty and the page converts to a
+    /// [`crate::PageError::RenderFailed`] result without a model call.
+    pub render_error: Option<String>,
+    /// [`encode::image_hash`] of the rendered page; `None` when rendering
+    /// failed.
+    pub image_hash: Option<String>,
+    /// The part of the page this image holds, from
+    /// [`ConversionConfig::page_crops`]; `None` for the whole page.
+    pub region: Option<CropRect>,
+    /// Headings the structure tree marks on the page, when
+    /// [`ConversionConfig::structure_tags`] is on and the PDF is tagged.
+    pub headings: Vec<TaggedHeading>,
+    /// Figures cut out of the page for alt text when
+    /// [`ConversionConfig::figure_captions`] is on; see [`crate::figures`].
+    pub figures: Vec<(CropRect, PageImage)>,
+    /// When the page finished encoding; the queue-wait sample in
+    /// [`super::timing`] is measured from here.
+    pub encoded_at: std::time::Instant,
+}
+
+// ── Tiling ───────────────────────────────────────────────────────────────
+
+/// Longest page edge (points) above which a page counts as oversized: 17 in,
+/// i.e. anything larger than US tabloid / A3.
+pub const TILE_MIN_EDGE_PT: f32 = 1224.0;
+
+/// Text-layer characters above which a page counts as dense. A typical
+/// single-column academic page carries ~3 500.
+pub const TILE_MIN_TEXT_CHARS: usize = 6000;
+
+/// Decide whether (and how) to split a page: returns `(columns, rows)`.
+///
+/// - Oversized *and* dense, or more than twice the size threshold → quadrants.
+/// - Otherwise oversized or dense → halves along the longer axis, so a
+///   landscape spread becomes left/right pages and a tall portrait page
+///   becomes top/bottom.
+/// - Neither → `None`.
+pub fn tile_grid(width_pt: f32, height_pt: f32, text_chars: usize) -> Option<(u32, u32)> {
+    let longest = width_pt.max(height_pt);
+    let large = longest >= TILE_MIN_EDGE_PT;
+    let dense = text_chars >= TILE_MIN_TEXT_CHARS;
+    if !large && !dense {
+        return None;
+    }
+    if (large && dense) || longest >= 2.0 * TILE_MIN_EDGE_PT {
+        Some((2, 2))
+    } else if width_pt > height_pt {
+        Some((2, 1))
+    } else {
+        Some((1, 2))
+    }
+}
+
+/// Largest factor by which a page with crop regions is rendered above
+/// `max_rendered_pixels`, bounding its bitmap at 4× the usual memory.
+pub const MAX_CROP_ZOOM: f32 = 2.0;
+
+/// Human-readable name of tile `(col, row)` in a `cols × rows` grid.
+fn tile_position(cols: u32, rows: u32, col: u32, row: u32) -> &'static str {
+    match (cols, rows, col, row) {
+        (2, 1, 0, _) => "left half",
+        (2, 1, _, _) => "right half",
+        (1, 2, _, 0) => "top half",
+        (1, 2, _, _) => "bottom half",
+        (_, _, 0, 0) => "top-left quadrant",
+        (_, _, _, 0) => "top-right quadrant",
+        (_, _, 0, _) => "bottom-left quadrant",
+        _ => "bottom-right quadrant",
+    }
+}
+
+/// Cut a rendered page into a `cols × rows` grid, row-major (reading order).
+fn split_into_tiles(
+    image: &DynamicImage,
+    cols: u32,
+    rows: u32,
+) -> Vec<(DynamicImage, &'static str)> {
+    let tile_w = image.width().div_ceil(cols);
+    let tile_h = image.height().div_ceil(rows);
+    let mut tiles = Vec::with_capacity((cols * rows) as usize);
+    for row in 0..rows {
+        for col in 0..cols {
+            let x = col * tile_w;
+            let y = row * tile_h;
+            let w = tile_w.min(image.width().saturating_sub(x));
+            let h = tile_h.min(image.height().saturating_sub(y));
+            if w == 0 || h == 0 {
+                continue;
+            }
+            tiles.push((
+                image.crop_imm(x, y, w, h),
+                tile_position(cols, rows, col, row),
+            ));
+        }
+    }
+    tiles
 }
 
 /// Spawn a lazy render+encode pipeline that produces pages one at a time.
 ///
-/// Opens the PDF once in a [`tokio::task::spawn_blocking`] task, then for
-/// each selected page:
-/// 1. Renders the page to a `DynamicImage` via pdfium
-/// 2. Encodes it to base64 PNG ([`ImageData`])
+/// Opens the PDF once in a [`PdfSession`], then for each selected page:
+/// 1. Renders the page to a `DynamicImage` via pdfium, then applies
+///    [`ConversionConfig::image_filter`] if set (after turning it upright
+///    under [`ConversionConfig::auto_rotate`] and suppressing watermarks
+///    under [`ConversionConfig::remove_watermarks`]); blank pages are sent on
+///    unencoded when [`ConversionConfig::skip_blank_pages`] is on
+/// 2. Encodes it to base64 PNG ([`PageImage`])
 /// 3. **Drops** the `DynamicImage` immediately (freeing the bitmap memory)
 /// 4. Sends the [`EncodedPage`] through a bounded channel
 ///
@@ -176,6 +409,17 @@ pub struct EncodedPage {
 /// side, peak memory is `≈ 2 × concurrency × page_size` instead of
 /// `total_pages × page_size`.
 ///
+/// Pages are rasterised on the thread of a [`PdfSession`], one job per
+/// page, and post-processed and encoded on the blocking pool. With
+/// [`ConversionConfig::render_workers`] above 1, that many producers run
+/// side by side, each with an interleaved share of the pages
+/// ([`shard_pages`]), all sending into the same channel: one encodes while
+/// another rasterises. Rasterising itself stays serial; pdfium-render's
+/// `thread_safe` bindings hold a process-wide lock for as long as a pdfium
+/// instance lives, so a second document handle would only wait for the
+/// first. Pages arrive roughly, not strictly, in page order, so
+/// [`ConversionConfig::maintain_format`] always uses a single producer.
+///
 /// # Returns
 /// - `Ok(receiver)` — pages will arrive as [`EncodedPage`] items
 /// - `Err(Pdf2MdError)` — if the PDF cannot be opened (fatal)
@@ -188,139 +432,629 @@ pub async fn spawn_lazy_render_encode(
     page_indices: &[usize],
     channel_capacity: usize,
 ) -> Result<mpsc::Receiver<EncodedPage>, Pdf2MdError> {
-    let path = pdf_path.to_path_buf();
-    let max_pixels = config.max_rendered_pixels;
-    let password = config.password.clone();
-    let indices = page_indices.to_vec();
+    // The producers keep the session open until their last page is sent.
+    let session = PdfSession::open(pdf_path, config.password.as_deref()).await?;
+    spawn_lazy_render_encode_in(&session, config, page_indices, channel_capacity).await
+}
 
-    let (ready_tx, ready_rx) = oneshot::channel::<Result<(), Pdf2MdError>>();
-    let (tx, rx) = mpsc::channel(channel_capacity.max(1));
+/// [`spawn_lazy_render_encode`] rendering from the document `session`
+/// already holds open, instead of opening it again.
+pub(crate) async fn spawn_lazy_render_encode_in(
+    session: &PdfSession,
+    config: &ConversionConfig,
+    page_indices: &[usize],
+    channel_capacity: usize,
+) -> Result<mpsc::Receiver<EncodedPage>, Pdf2MdError> {
+    let options = LazyRenderOptions {
+        dpi: config.dpi,
+        max_pixels: config.max_rendered_pixels,
+        tile_dense_pages: config.tile_dense_pages,
+        layout_hints: config.layout_hints,
+        structure_tags: config.structure_tags,
+        figure_captions: config.figure_captions,
+        metrics_sink: config.metrics_sink.clone(),
+        image_filter: config.image_filter.clone(),
+        skip_blank_pages: config.skip_blank_pages,
+        auto_rotate: config.auto_rotate,
+        split_spreads: config.split_spreads,
+        png: encode::PngOptions::for_config(config),
+        page_crops: config.page_crops.clone(),
+        margin_mask: config.margin_mask,
+        remove_watermarks: config.remove_watermarks,
+        render_layers: config.render_layers,
+        render_options: config.render_options,
+    };
+    let options = Arc::new(options);
+    let workers = if config.maintain_format {
+        1
+    } else {
+        config.render_workers
+    };
 
-    tokio::task::spawn_blocking(move || {
-        lazy_render_encode_blocking(
-            &path,
-            max_pixels,
-            password.as_deref(),
-            &indices,
-            tx,
-            ready_tx,
-        )
-    });
+    let (tx, rx) = mpsc::channel(channel_capacity.max(1));
+    let shards = shard_pages(page_indices, workers);
+    info!(
+        "Lazy render: producing {} selected pages with {} render worker(s)",
+        page_indices.len(),
+        shards.len()
+    );
+    for shard in shards {
+        tokio::spawn(produce(
+            session.clone(),
+            Arc::clone(&options),
+            shard,
+            tx.clone(),
+        ));
+    }
+    Ok(rx)
+}
 
-    // Wait for the producer to confirm the PDF opened successfully.
-    match ready_rx.await {
-        Ok(Ok(())) => Ok(rx),
-        Ok(Err(e)) => Err(e),
-        Err(_) => Err(Pdf2MdError::Internal(
-            "Render producer task failed before opening PDF".into(),
-        )),
+/// Deal `page_indices` round-robin to at most `workers` producers, so each
+/// one works through the whole document and pages arrive close to page
+/// order. Always at least one shard, possibly empty.
+pub(crate) fn shard_pages(page_indices: &[usize], workers: usize) -> Vec<Vec<usize>> {
+    let workers = workers.clamp(1, page_indices.len().max(1));
+    let mut shards = vec![Vec::new(); workers];
+    for (i, &idx) in page_indices.iter().enumerate() {
+        shards[i % workers].push(idx);
     }
+    shards
 }
 
-/// Blocking producer: opens PDF once, renders + encodes pages one at a time.
-fn lazy_render_encode_blocking(
-    pdf_path: &Path,
+/// The subset of [`ConversionConfig`] the producers need, owned so it can
+/// move into session jobs and `spawn_blocking`.
+struct LazyRenderOptions {
+    dpi: u32,
     max_pixels: u32,
-    password: Option<&str>,
-    page_indices: &[usize],
+    tile_dense_pages: bool,
+    layout_hints: bool,
+    structure_tags: bool,
+    figure_captions: bool,
+    metrics_sink: Option<SharedMetricsSink>,
+    image_filter: Option<ImageFilter>,
+    skip_blank_pages: bool,
+    auto_rotate: bool,
+    split_spreads: bool,
+    png: encode::PngOptions,
+    page_crops: HashMap<usize, Vec<CropRect>>,
+    margin_mask: Option<MarginMask>,
+    remove_watermarks: bool,
+    render_layers: RenderLayers,
+    render_options: RenderOptions,
+}
+
+/// One producer: rasterises its pages in order on the session thread and
+/// finishes each on the blocking pool, sending the results to `tx`. Stops
+/// early when the receiver is dropped.
+async fn produce(
+    session: PdfSession,
+    options: Arc<LazyRenderOptions>,
+    page_indices: Vec<usize>,
     tx: mpsc::Sender<EncodedPage>,
-    ready_tx: oneshot::Sender<Result<(), Pdf2MdError>>,
 ) {
-    let pdfium = match get_pdfium() {
+    let render_options = Arc::clone(&options);
+    let render = move |idx: usize| {
+        let session = session.clone();
+        let job_options = Arc::clone(&render_options);
+        async move {
+            session
+                .run(move |document| render_page(document, &job_options, idx))
+                .await
+        }
+    };
+    produce_with(page_indices, tx, render, move |page| {
+        finish_page(page, &options)
+    })
+    .await;
+}
+
+/// The loop of [`produce`], with the session job (`render`) and the
+/// blocking-pool step (`finish`) passed in.
+///
+/// Only `finish` runs off the session thread, so producers overlap one
+/// page's encoding with another's rasterising; they never rasterise two
+/// pages at once.
+async fn produce_with<R, Fut, F>(
+    page_indices: Vec<usize>,
+    tx: mpsc::Sender<EncodedPage>,
+    mut render: R,
+    finish: F,
+) where
+    R: FnMut(usize) -> Fut,
+    Fut: std::future::Future<Output = Result<Rendered, Pdf2MdError>>,
+    F: Fn(RenderedPage) -> Vec<EncodedPage> + Send + Sync + 'static,
+{
+    let finish = Arc::new(finish);
+    for idx in page_indices {
+        if tx.is_closed() {
+            debug!("Lazy render producer: receiver dropped, stopping");
+            return;
+        }
+        let pages = match render(idx).await {
+            Ok(Rendered::Page(page)) => {
+                let start = page.start;
+                let finish = Arc::clone(&finish);
+                match tokio::task::spawn_blocking(move || finish(page)).await {
+                    Ok(pages) => pages,
+                    Err(e) => vec![failed_page(
+                        idx,
+                        format!("encoding task failed: {e}"),
+                        start,
+                    )],
+                }
+            }
+            Ok(Rendered::Failed { detail, start }) => vec![failed_page(idx, detail, start)],
+            Ok(Rendered::OutOfRange) => continue,
+            Err(e) => {
+                warn!("Page {}: {}", idx + 1, e);
+                vec![failed_page(idx, e.to_string(), std::time::Instant::now())]
+            }
+        };
+        // Waits while the channel is full (back-pressure from the consumer).
+        for page in pages {
+            if tx.send(page).await.is_err() {
+                debug!("Lazy render producer: receiver dropped, stopping");
+                return;
+            }
+        }
+    }
+}
+
+/// What [`render_page`] made of one page index.
+enum Rendered {
+    Page(RenderedPage),
+    Failed {
+        detail: String,
+        start: std::time::Instant,
+    },
+    OutOfRange,
+}
+
+/// A rasterised page with what [`finish_page`] still needs from the PDF.
+struct RenderedPage {
+    idx: usize,
+    start: std::time::Instant,
+    /// Upright, before watermark whitening, margin masking, and the image
+    /// filter.
+    image: DynamicImage,
+    rotation: u16,
+    /// Tile grid, already swapped for a quarter turn.
+    grid: Option<(u32, u32)>,
+    columns: usize,
+    headings: Vec<TaggedHeading>,
+    /// Figure regions in the upright image, when figures are cropped.
+    figure_regions: Vec<CropRect>,
+    /// `false` only for pages without a text layer that may be blank.
+    has_text: bool,
+}
+
+/// Rasterise page `idx` of an open `document` and read what the later steps
+/// need from it. Runs on the session thread.
+fn render_page(document: &PdfDocument, options: &LazyRenderOptions, idx: usize) -> Rendered {
+    let max_pixels = options.max_pixels;
+    let pages = document.pages();
+    let total_pages = pages.len() as usize;
+    if idx >= total_pages {
+        warn!(
+            "Skipping page {} (out of range, total={})",
+            idx + 1,
+            total_pages
+        );
+        return Rendered::OutOfRange;
+    }
+
+    let start = std::time::Instant::now();
+    let render_span = debug_span!(
+        "pdf2md.render",
+        page = idx + 1,
+        width = field::Empty,
+        height = field::Empty,
+        duration_ms = field::Empty,
+    )
+    .entered();
+
+    let mut page = match pages.get(idx as u16) {
         Ok(p) => p,
         Err(e) => {
-            let _ = ready_tx.send(Err(e));
-            return;
+            warn!("Page {}: could not load: {:?}", idx + 1, e);
+            let detail = format!("could not load page: {e:?}");
+            return Rendered::Failed { detail, start };
         }
     };
+    // Edits below (watermark removal, cleared content) only shape this
+    // render: without regeneration they are dropped with the page, and later
+    // passes over the session's document see the original content.
+    page.set_content_regeneration_strategy(PdfPageContentRegenerationStrategy::Manual);
+
+    if options.remove_watermarks {
+        let removed = watermark::remove_objects(&mut page);
+        if removed > 0 {
+            debug!("Page {}: removed {} watermark object(s)", idx + 1, removed);
+        }
+    }
 
-    let document = match pdfium.load_pdf_from_file(pdf_path, password) {
-        Ok(doc) => doc,
+    let columns = if options.layout_hints {
+        page_columns(&page)
+    } else {
+        1
+    };
+    let headings = if options.structure_tags {
+        tagged::page_tags(&page)
+            .map(|t| t.headings)
+            .unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    // Cropped pages are rendered larger instead, so small regions keep
+    // their detail.
+    let crops = options.page_crops.get(&(idx + 1)).filter(|r| !r.is_empty());
+    let grid = if options.tile_dense_pages && crops.is_none() {
+        let text_chars = page.text().map(|t| t.all().len()).unwrap_or(0);
+        tile_grid(page.width().value, page.height().value, text_chars)
+    } else {
+        None
+    };
+    // After the text layer has been read for hints and tiling.
+    if !options.render_layers.content {
+        clear_content(&mut page);
+    }
+    let base = render_config_for(&options.render_layers, &options.render_options);
+    let dpi = options.dpi as f32;
+    let render_config = match (grid, crops) {
+        // Tiled pages render at `cols × rows` times the DPI and pixel cap so
+        // each tile gets the resolution of a whole page.
+        (Some((cols, rows)), _) => sized_config(
+            base,
+            &page,
+            dpi * cols.max(rows) as f32,
+            max_pixels * cols,
+            max_pixels * rows,
+        ),
+        (None, Some(regions)) => {
+            let zoom = crop_zoom(regions);
+            let edge = (max_pixels as f32 * zoom) as u32;
+            sized_config(base, &page, dpi * zoom, edge, edge)
+        }
+        (None, None) => sized_config(base, &page, dpi, max_pixels, max_pixels),
+    };
+    let bitmap = page.render_with_config(&render_config);
+    let bitmap = match bitmap {
+        Ok(b) => b,
         Err(e) => {
-            let _ = ready_tx.send(Err(map_pdf_open_error(e, pdf_path, password.is_some())));
-            return;
+            warn!("Page {}: render failed: {:?}", idx + 1, e);
+            let detail = format!("{e:?}");
+            return Rendered::Failed { detail, start };
         }
     };
 
-    // PDF opened successfully — signal the async caller.
-    let _ = ready_tx.send(Ok(()));
+    let rotation = if options.auto_rotate {
+        page_upright_rotation(&page)
+    } else {
+        0
+    };
+    let image = match rotation {
+        90 => bitmap.as_image().rotate90(),
+        180 => bitmap.as_image().rotate180(),
+        270 => bitmap.as_image().rotate270(),
+        _ => bitmap.as_image(),
+    };
+    if rotation != 0 {
+        debug!("Page {}: rotated {}° clockwise", idx + 1, rotation);
+    }
+    // A quarter turn swaps the tile grid's columns and rows.
+    let grid = match grid {
+        Some((cols, rows)) if rotation % 180 == 90 => Some((rows, cols)),
+        other => other,
+    };
+    let figure_regions = if options.figure_captions && crops.is_none() {
+        figures::figure_regions(&page, rotation)
+    } else {
+        Vec::new()
+    };
+    // Only pages without a text layer can be blank: a scanned separator
+    // sheet has none, and any extracted text means there is content.
+    let has_text = !options.skip_blank_pages || page_has_text(&page);
+
+    render_span.record("width", image.width());
+    render_span.record("height", image.height());
+    render_span.record("duration_ms", start.elapsed().as_millis() as u64);
+    drop(render_span);
+    Rendered::Page(RenderedPage {
+        idx,
+        start,
+        image,
+        rotation,
+        grid,
+        columns,
+        headings,
+        figure_regions,
+        has_text,
+    })
+}
 
-    let pages = document.pages();
-    let total_pages = pages.len() as usize;
-    info!(
-        "Lazy render: PDF loaded ({} pages), producing {} selected pages",
-        total_pages,
-        page_indices.len()
+/// Post-process and encode a rendered page, off the session thread. Returns
+/// the pages to send, in order: one, or one per crop region.
+fn finish_page(rendered: RenderedPage, options: &LazyRenderOptions) -> Vec<EncodedPage> {
+    let RenderedPage {
+        idx,
+        start,
+        image,
+        rotation,
+        grid,
+        columns,
+        headings,
+        figure_regions,
+        has_text,
+    } = rendered;
+    let image = if options.remove_watermarks {
+        watermark::whiten_light_pixels(image)
+    } else {
+        image
+    };
+    let image = match options.margin_mask {
+        Some(ref mask) => margins::apply(image, mask),
+        None => image,
+    };
+    let image = match options.image_filter {
+        Some(ref filter) => filter(idx + 1, image),
+        None => image,
+    };
+    debug!(
+        "Rendered page {} → {}x{} px",
+        idx + 1,
+        image.width(),
+        image.height()
     );
+    if let Some(ref m) = options.metrics_sink {
+        m.record_stage(PipelineStage::Render, idx + 1, start.elapsed());
+    }
+    if let Some(regions) = options.page_crops.get(&(idx + 1)).filter(|r| !r.is_empty()) {
+        debug!("Page {}: converting {} regions", idx + 1, regions.len());
+        return region_pages(idx, &image, regions, rotation, options, start);
+    }
+    let image_hash = encode::image_hash(&image);
+    let figures = figures::crop_figures(&image, figure_regions);
 
-    let render_config = PdfRenderConfig::new()
-        .set_target_width(max_pixels as i32)
-        .set_maximum_height(max_pixels as i32);
+    if !has_text && blank::is_blank(&image) {
+        debug!("Page {}: blank, skipping the model call", idx + 1);
+        return vec![EncodedPage {
+            page_index: idx,
+            image_data: PageImage::new(String::new(), "image/png"),
+            tiles: Vec::new(),
+            columns: 1,
+            render_encode_ms: start.elapsed().as_millis() as u64,
+            blank: true,
+            rotation,
+            spread: false,
+            render_error: None,
+            image_hash: Some(image_hash),
+            region: None,
+            headings: Vec::new(),
+            figures: Vec::new(),
+            encoded_at: std::time::Instant::now(),
+        }];
+    }
 
-    for &idx in page_indices {
-        if idx >= total_pages {
-            warn!(
-                "Skipping page {} (out of range, total={})",
+    // Dense-page tiling already cuts landscape pages in half; spreads are
+    // only looked for on pages it leaves whole.
+    let gutter = if options.split_spreads && grid.is_none() {
+        spread::detect_spread(&image)
+    } else {
+        None
+    };
+
+    let encode_start = std::time::Instant::now();
+    let encode_span = debug_span!(
+        "pdf2md.encode",
+        page = idx + 1,
+        tiles = if gutter.is_some() {
+            2
+        } else {
+            grid.map_or(1, |(c, r)| c * r)
+        },
+        duration_ms = field::Empty,
+    )
+    .entered();
+    let encoded = match (grid, gutter) {
+        (_, Some(gutter)) => {
+            debug!(
+                "Page {}: two-page spread, splitting at x={}",
                 idx + 1,
-                total_pages
+                gutter
             );
-            continue;
+            let (left, right) = spread::split_spread(&image, gutter);
+            [(left, "left page"), (right, "right page")]
+                .iter()
+                .map(|(half, pos)| encode::encode_page_with(half, options.png).map(|d| (d, *pos)))
+                .collect::<Result<Vec<_>, _>>()
+        }
+        (Some((cols, rows)), None) => {
+            debug!("Page {}: splitting into {}x{} tiles", idx + 1, cols, rows);
+            split_into_tiles(&image, cols, rows)
+                .into_iter()
+                .map(|(tile, pos)| encode::encode_page_with(&tile, options.png).map(|d| (d, pos)))
+                .collect::<Result<Vec<_>, _>>()
+        }
+        (None, None) => {
+            encode::encode_page_with(&image, options.png).map(|d| vec![(d, "full page")])
+        }
+    };
+    let mut tiles = match encoded {
+        Ok(t) if !t.is_empty() => t,
+        failed => {
+            let detail = match failed {
+                Err(e) => format!("encoding failed: {e}"),
+                Ok(_) => "empty render".to_string(),
+            };
+            warn!("Page {}: {}", idx + 1, detail);
+            return vec![failed_page(idx, detail, start)];
         }
+    };
+    // `image` is dropped here, freeing the DynamicImage bitmap memory.
 
-        let start = std::time::Instant::now();
+    let encode_elapsed = encode_start.elapsed();
+    encode_span.record("duration_ms", encode_elapsed.as_millis() as u64);
+    drop(encode_span);
+    if let Some(ref m) = options.metrics_sink {
+        m.record_stage(PipelineStage::Encode, idx + 1, encode_elapsed);
+    }
 
-        let page = match pages.get(idx as u16) {
-            Ok(p) => p,
-            Err(e) => {
-                warn!("Skipping page {} (render failed: {:?})", idx + 1, e);
-                continue;
-            }
-        };
+    let render_encode_ms = start.elapsed().as_millis() as u64;
 
-        let bitmap = match page.render_with_config(&render_config) {
-            Ok(b) => b,
-            Err(e) => {
-                warn!("Skipping page {} (render failed: {:?})", idx + 1, e);
-                continue;
-            }
-        };
+    let image_data = tiles[0].0.clone();
+    if grid.is_none() && gutter.is_none() {
+        tiles.clear();
+    }
+    vec![EncodedPage {
+        page_index: idx,
+        image_data,
+        tiles,
+        columns,
+        render_encode_ms,
+        blank: false,
+        rotation,
+        spread: gutter.is_some(),
+        render_error: None,
+        image_hash: Some(image_hash),
+        region: None,
+        headings,
+        figures,
+        encoded_at: std::time::Instant::now(),
+    }]
+}
 
-        let image = bitmap.as_image();
-        debug!(
-            "Rendered page {} → {}x{} px",
-            idx + 1,
-            image.width(),
-            image.height()
-        );
+/// An [`EncodedPage`] that carries `detail` as its render error, so the
+/// consumer reports it as [`crate::PageError::RenderFailed`] instead of
+/// losing it.
+fn failed_page(idx: usize, detail: String, start: std::time::Instant) -> EncodedPage {
+    EncodedPage {
+        page_index: idx,
+        image_data: PageImage::new(String::new(), "image/png"),
+        tiles: Vec::new(),
+        columns: 1,
+        render_encode_ms: start.elapsed().as_millis() as u64,
+        blank: false,
+        rotation: 0,
+        spread: false,
+        render_error: Some(detail),
+        image_hash: None,
+        region: None,
+        headings: Vec::new(),
+        figures: Vec::new(),
+        encoded_at: std::time::Instant::now(),
+    }
+}
 
-        let data = match encode::encode_page(&image) {
-            Ok(d) => d,
+/// Cut each of `regions` out of a rendered page and encode it as its own
+/// [`EncodedPage`], in order.
+fn region_pages(
+    idx: usize,
+    image: &DynamicImage,
+    regions: &[CropRect],
+    rotation: u16,
+    options: &LazyRenderOptions,
+    start: std::time::Instant,
+) -> Vec<EncodedPage> {
+    let mut pages = Vec::with_capacity(regions.len());
+    for region in regions {
+        let (x, y, w, h) = region.to_pixels(image.width(), image.height());
+        let cropped = image.crop_imm(x, y, w, h);
+        let encode_start = std::time::Instant::now();
+        let page = match encode::encode_page_with(&cropped, options.png) {
+            Ok(image_data) => EncodedPage {
+                page_index: idx,
+                image_data,
+                tiles: Vec::new(),
+                columns: 1,
+                render_encode_ms: start.elapsed().as_millis() as u64,
+                blank: false,
+                rotation,
+                spread: false,
+                render_error: None,
+                image_hash: Some(encode::image_hash(&cropped)),
+                region: Some(*region),
+                headings: Vec::new(),
+                figures: Vec::new(),
+                encoded_at: std::time::Instant::now(),
+            },
             Err(e) => {
-                warn!("Skipping page {} (encoding failed: {})", idx + 1, e);
-                continue;
+                warn!("Page {}: region encoding failed: {}", idx + 1, e);
+                EncodedPage {
+                    region: Some(*region),
+                    ..failed_page(idx, format!("encoding failed: {e}"), start)
+                }
             }
         };
-        // `image` is dropped here, freeing the DynamicImage bitmap memory.
+        if let Some(ref m) = options.metrics_sink {
+            m.record_stage(PipelineStage::Encode, idx + 1, encode_start.elapsed());
+        }
+        pages.push(page);
+    }
+    pages
+}
 
-        let render_encode_ms = start.elapsed().as_millis() as u64;
+/// Render zoom for a page with crop regions: enough that the smallest region
+/// gets the full pixel cap along its longer side, up to [`MAX_CROP_ZOOM`].
+fn crop_zoom(regions: &[CropRect]) -> f32 {
+    let smallest = regions
+        .iter()
+        .map(|r| r.width.max(r.height))
+        .fold(1.0_f32, f32::min);
+    (1.0 / smallest.max(f32::EPSILON)).clamp(1.0, MAX_CROP_ZOOM)
+}
 
-        let encoded_page = EncodedPage {
-            page_index: idx,
-            image_data: data,
-            render_encode_ms,
-        };
+/// `true` when the page's text layer holds any non-whitespace character.
+fn page_has_text(page: &PdfPage) -> bool {
+    page.text()
+        .map(|t| t.all().chars().any(|c| !c.is_whitespace()))
+        .unwrap_or(false)
+}
 
-        // Blocking send: waits if channel is full (back-pressure from consumer).
-        // Returns Err if receiver is dropped (consumer cancelled).
-        if tx.blocking_send(encoded_page).is_err() {
-            debug!("Lazy render producer: receiver dropped, stopping");
-            break;
-        }
+/// Clockwise turn that makes the rendered page upright, from the angles of
+/// its text-layer characters (see [`layout::detect_rotation`]).
+///
+/// Pages without a text layer, such as scans, report 0; their `/Rotate` flag
+/// is already applied by pdfium when rendering.
+fn page_upright_rotation(page: &PdfPage) -> u16 {
+    let Ok(text) = page.text() else {
+        return 0;
+    };
+    let angles: Vec<f32> = text
+        .chars()
+        .iter()
+        .filter(|c| c.unicode_char().is_some_and(|ch| !ch.is_whitespace()))
+        .filter_map(|c| c.angle_degrees().ok())
+        .collect();
+    let page_rotation = page.rotation().map(rotation_degrees).unwrap_or(0);
+    layout::detect_rotation(&angles, page_rotation)
+}
+
+/// Column count of a page from its text-layer segment geometry.
+fn page_columns(page: &PdfPage) -> usize {
+    let Ok(text) = page.text() else {
+        return 1;
+    };
+    let segments: Vec<(f32, f32)> = text
+        .segments()
+        .iter()
+        .map(|seg| {
+            let b = seg.bounds();
+            (b.left().value, b.right().value)
+        })
+        .collect();
+    let columns = layout::detect_columns(&segments, page.width().value);
+    if columns > 1 {
+        debug!("Detected {}-column layout", columns);
     }
+    columns
 }
 
 /// Map a pdfium document-open error to a [`Pdf2MdError`].
-fn map_pdf_open_error(e: impl std::fmt::Debug, pdf_path: &Path, has_password: bool) -> Pdf2MdError {
+pub(crate) fn map_pdf_open_error(
+    e: impl std::fmt::Debug,
+    pdf_path: &Path,
+    has_password: bool,
+) -> Pdf2MdError {
     let err_str = format!("{:?}", e);
     if err_str.contains("Password") || err_str.contains("password") {
         if has_password {
@@ -345,6 +1079,7 @@ pub async fn extract_metadata(
     pdf_path: &Path,
     password: Option<&str>,
 ) -> Result<DocumentMetadata, Pdf2MdError> {
+    ensure_pdfium().await?;
     let path = pdf_path.to_path_buf();
     let pwd = password.map(|s| s.to_string());
 
@@ -367,7 +1102,11 @@ fn extract_metadata_blocking(
                 path: pdf_path.to_path_buf(),
                 detail: format!("{:?}", e),
             })?;
+    Ok(document_metadata(&document, pdf_path))
+}
 
+/// Metadata of an open `document`, read from `pdf_path`.
+pub(crate) fn document_metadata(document: &PdfDocument, pdf_path: &Path) -> DocumentMetadata {
     let metadata = document.metadata();
     let pages = document.pages();
 
@@ -382,7 +1121,14 @@ fn extract_metadata_blocking(
         })
     };
 
-    Ok(DocumentMetadata {
+    let conformance = tagged::conformance(document, pdf_path);
+    let page_infos = pages
+        .iter()
+        .enumerate()
+        .map(|(idx, page)| page_info(idx, &page, conformance.tagged))
+        .collect();
+
+    DocumentMetadata {
         title: get_meta(PdfDocumentMetadataTagType::Title),
         author: get_meta(PdfDocumentMetadataTagType::Author),
         subject: get_meta(PdfDocumentMetadataTagType::Subject),
@@ -393,7 +1139,223 @@ fn extract_metadata_blocking(
         page_count: pages.len() as usize,
         pdf_version: format!("{:?}", document.version()),
         is_encrypted: false, // pdfium doesn't readily expose this after opening
+        conformance,
+        pages: page_infos,
+        attachments: document
+            .attachments()
+            .iter()
+            .map(|a| Attachment {
+                name: a.name(),
+                size_bytes: a.len(),
+                ..Default::default()
+            })
+            .collect(),
+        signatures: signatures::read(document, pdf_path),
+    }
+}
+
+/// Text-layer text of the first pages, up to about `max_chars` characters.
+///
+/// Empty for scanned documents. Used to detect the document language
+/// without a model call.
+pub async fn sample_text(
+    pdf_path: &Path,
+    password: Option<&str>,
+    max_chars: usize,
+) -> Result<String, Pdf2MdError> {
+    ensure_pdfium().await?;
+    let path = pdf_path.to_path_buf();
+    let pwd = password.map(|s| s.to_string());
+
+    tokio::task::spawn_blocking(move || {
+        let pdfium = get_pdfium()?;
+        let document = pdfium
+            .load_pdf_from_file(&path, pwd.as_deref())
+            .map_err(|e| map_pdf_open_error(e, &path, pwd.is_some()))?;
+        Ok(document_text_sample(&document, max_chars))
+    })
+    .await
+    .map_err(|e| Pdf2MdError::Internal(format!("Text sample task panicked: {}", e)))?
+}
+
+/// [`sample_text`] of an open `document`.
+pub(crate) fn document_text_sample(document: &PdfDocument, max_chars: usize) -> String {
+    let mut text = String::new();
+    for page in document.pages().iter() {
+        if text.len() >= max_chars {
+            break;
+        }
+        if let Ok(t) = page.text() {
+            text.push_str(&t.all());
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// Text-layer text of each of `page_indices` (0-based), and whether the
+/// page holds any image objects. Used by [`crate::page_kind`] to classify
+/// pages without rendering them.
+pub async fn page_text_layers(
+    pdf_path: &Path,
+    password: Option<&str>,
+    page_indices: &[usize],
+) -> Result<Vec<(String, bool)>, Pdf2MdError> {
+    ensure_pdfium().await?;
+    let path = pdf_path.to_path_buf();
+    let pwd = password.map(|s| s.to_string());
+    let indices = page_indices.to_vec();
+
+    tokio::task::spawn_blocking(move || {
+        let pdfium = get_pdfium()?;
+        let document = pdfium
+            .load_pdf_from_file(&path, pwd.as_deref())
+            .map_err(|e| map_pdf_open_error(e, &path, pwd.is_some()))?;
+        Ok(document_text_layers(&document, &indices))
     })
+    .await
+    .map_err(|e| Pdf2MdError::Internal(format!("Text layer task panicked: {}", e)))?
+}
+
+/// [`page_text_layers`] of an open `document`.
+pub(crate) fn document_text_layers(
+    document: &PdfDocument,
+    page_indices: &[usize],
+) -> Vec<(String, bool)> {
+    let pages = document.pages();
+    page_indices
+        .iter()
+        .map(|&idx| match pages.get(idx as u16) {
+            Ok(page) => {
+                let text = page.text().map(|t| t.all()).unwrap_or_default();
+                let has_images = page
+                    .objects()
+                    .iter()
+                    .any(|o| o.object_type() == PdfPageObjectType::Image);
+                (text, has_images)
+            }
+            Err(_) => (String::new(), true),
+        })
+        .collect()
+}
+
+/// Find the 0-based page indices where chapters start, without rendering.
+///
+/// Uses the PDF outline when it has at least two top-level entries (or,
+/// for outlines with a single root such as the book title, that root's
+/// children). Documents without a usable outline fall back to pages whose
+/// text layer opens with a "Chapter N" / "Part N" heading. Returns an empty
+/// list when neither finds anything. See [`sections`].
+pub async fn detect_section_starts(
+    pdf_path: &Path,
+    password: Option<&str>,
+) -> Result<Vec<usize>, Pdf2MdError> {
+    ensure_pdfium().await?;
+    let path = pdf_path.to_path_buf();
+    let pwd = password.map(|s| s.to_string());
+
+    tokio::task::spawn_blocking(move || detect_section_starts_blocking(&path, pwd.as_deref()))
+        .await
+        .map_err(|e| Pdf2MdError::Internal(format!("Section task panicked: {}", e)))?
+}
+
+/// Blocking implementation of section detection.
+fn detect_section_starts_blocking(
+    pdf_path: &Path,
+    password: Option<&str>,
+) -> Result<Vec<usize>, Pdf2MdError> {
+    let pdfium = get_pdfium()?;
+    let document = pdfium
+        .load_pdf_from_file(pdf_path, password)
+        .map_err(|e| map_pdf_open_error(e, pdf_path, password.is_some()))?;
+    Ok(document_section_starts(&document))
+}
+
+/// [`detect_section_starts`] of an open `document`.
+pub(crate) fn document_section_starts(document: &PdfDocument) -> Vec<usize> {
+    // ── Outline: top level, or the children of a lone root ──────────────
+    let mut level = document.bookmarks().root();
+    for _ in 0..2 {
+        let mut starts = Vec::new();
+        let mut entries = 0;
+        let mut first = None;
+        let mut next = level;
+        while let Some(bookmark) = next {
+            entries += 1;
+            if let Some(idx) = bookmark.destination().and_then(|d| d.page_index().ok()) {
+                starts.push(idx as usize);
+            }
+            next = bookmark.next_sibling();
+            first.get_or_insert(bookmark);
+        }
+        starts.sort_unstable();
+        starts.dedup();
+        if starts.len() >= 2 {
+            debug!("Outline gives {} section starts", starts.len());
+            return starts;
+        }
+        level = if entries == 1 {
+            first.and_then(|b| b.first_child())
+        } else {
+            None
+        };
+    }
+
+    // ── Fallback: chapter headings in the text layer ────────────────────
+    let starts: Vec<usize> = document
+        .pages()
+        .iter()
+        .enumerate()
+        .filter(|(_, page)| {
+            page.text()
+                .map(|t| {
+                    let all = t.all();
+                    let first_line = all.lines().find(|l| !l.trim().is_empty());
+                    first_line.is_some_and(sections::is_section_heading)
+                })
+                .unwrap_or(false)
+        })
+        .map(|(idx, _)| idx)
+        .collect();
+    debug!("Text-layer headings give {} section starts", starts.len());
+    starts
+}
+
+/// Summarise one page's geometry and content without rendering it.
+fn page_info(idx: usize, page: &PdfPage, tagged: bool) -> PageInfo {
+    let has_text_layer = page
+        .text()
+        .map(|t| !t.all().trim().is_empty())
+        .unwrap_or(false);
+    let image_count = page
+        .objects()
+        .iter()
+        .filter(|o| o.object_type() == PdfPageObjectType::Image)
+        .count();
+
+    PageInfo {
+        page_num: idx + 1,
+        width_pt: page.width().value,
+        height_pt: page.height().value,
+        rotation: page.rotation().map(rotation_degrees).unwrap_or(0),
+        has_text_layer,
+        image_count,
+        tags: if tagged {
+            tagged::page_tags(page)
+        } else {
+            None
+        },
+    }
+}
+
+/// Convert pdfium's rotation enum into clockwise degrees.
+fn rotation_degrees(rotation: PdfPageRenderRotation) -> u16 {
+    match rotation {
+        PdfPageRenderRotation::None => 0,
+        PdfPageRenderRotation::Degrees90 => 90,
+        PdfPageRenderRotation::Degrees180 => 180,
+        PdfPageRenderRotation::Degrees270 => 270,
+    }
 }
 
 #[cfg(test)]
@@ -401,6 +1363,71 @@ mod tests {
     use super::*;
     use std::path::PathBuf;
 
+    #[test]
+    fn pages_are_dealt_round_robin() {
+        assert_eq!(
+            shard_pages(&[0, 1, 2, 3, 4], 2),
+            vec![vec![0, 2, 4], vec![1, 3]]
+        );
+        assert_eq!(shard_pages(&[7, 8], 4), vec![vec![7], vec![8]]);
+        assert_eq!(shard_pages(&[], 3), vec![Vec::<usize>::new()]);
+    }
+
+    /// Workers overlap encoding with the serial rasterising step, so more
+    /// of them get through the same pages sooner.
+    #[tokio::test]
+    async fn render_workers_raise_throughput() {
+        use std::time::{Duration, Instant};
+
+        async fn run(workers: usize) -> (usize, Duration) {
+            // One lock stands in for the session thread.
+            let session = Arc::new(tokio::sync::Mutex::new(()));
+            let pages: Vec<usize> = (0..8).collect();
+            let (tx, mut rx) = mpsc::channel(8);
+            let start = Instant::now();
+            for shard in shard_pages(&pages, workers) {
+                let session = Arc::clone(&session);
+                let render = move |idx: usize| {
+                    let session = Arc::clone(&session);
+                    async move {
+                        let _thread = session.lock().await;
+                        tokio::time::sleep(Duration::from_millis(20)).await;
+                        Ok::<_, Pdf2MdError>(Rendered::Page(RenderedPage {
+                            idx,
+                            start: Instant::now(),
+                            image: DynamicImage::new_rgba8(1, 1),
+                            rotation: 0,
+                            grid: None,
+                            columns: 1,
+                            headings: Vec::new(),
+                            figure_regions: Vec::new(),
+                            has_text: true,
+                        }))
+                    }
+                };
+                let finish = |page: RenderedPage| {
+                    std::thread::sleep(Duration::from_millis(60));
+                    vec![failed_page(page.idx, String::new(), page.start)]
+                };
+                tokio::spawn(produce_with(shard, tx.clone(), render, finish));
+            }
+            drop(tx);
+            let mut produced = 0;
+            while rx.recv().await.is_some() {
+                produced += 1;
+            }
+            (produced, start.elapsed())
+        }
+
+        let (serial_pages, serial) = run(1).await;
+        let (pooled_pages, pooled) = run(4).await;
+        assert_eq!((serial_pages, pooled_pages), (8, 8));
+        // 8 × (20 + 60) ms in one producer; about 220 ms across four, whose
+        // rasterising still takes turns.
+        assert!(serial >= Duration::from_millis(640), "{serial:?}");
+        assert!(pooled * 2 < serial, "{pooled:?} vs {serial:?}");
+    }
+
     #[test]
     fn map_pdf_open_error_password_required() {
         let e = "PdfiumError::PasswordRequired";
@@ -438,13 +1465,66 @@ mod tests {
         }
     }
 
+    #[test]
+    fn rotation_degrees_maps_all_variants() {
+        assert_eq!(rotation_degrees(PdfPageRenderRotation::None), 0);
+        assert_eq!(rotation_degrees(PdfPageRenderRotation::Degrees90), 90);
+        assert_eq!(rotation_degrees(PdfPageRenderRotation::Degrees180), 180);
+        assert_eq!(rotation_degrees(PdfPageRenderRotation::Degrees270), 270);
+    }
+
+    #[test]
+    fn tile_grid_thresholds() {
+        // US Letter, sparse: untouched.
+        assert_eq!(tile_grid(612.0, 792.0, 3000), None);
+        // US Letter, very dense: top/bottom halves.
+        assert_eq!(tile_grid(612.0, 792.0, 9000), Some((1, 2)));
+        // Landscape tabloid spread: left/right halves.
+        assert_eq!(tile_grid(1584.0, 1224.0, 1000), Some((2, 1)));
+        // Broadsheet: quadrants.
+        assert_eq!(tile_grid(1620.0, 2592.0, 1000), Some((2, 2)));
+        assert_eq!(tile_grid(1300.0, 1300.0, 9000), Some((2, 2)));
+    }
+
+    #[test]
+    fn render_size_follows_dpi_until_capped() {
+        // US Letter is 8.5 × 11 in.
+        assert_eq!(render_size(612.0, 792.0, 96.0, 2000, 2000), (816, 1056));
+        assert_eq!(render_size(612.0, 792.0, 150.0, 2000, 2000), (1275, 1650));
+        // 300 DPI would be 2550 × 3300; the cap scales both edges down.
+        assert_eq!(render_size(612.0, 792.0, 300.0, 2000, 2000), (1545, 2000));
+        assert_eq!(render_size(1.0, 1.0, 72.0, 100, 100), (1, 1));
+    }
+
+    #[test]
+    fn split_into_tiles_covers_image() {
+        let img = DynamicImage::new_rgba8(101, 50);
+        let tiles = split_into_tiles(&img, 2, 2);
+        assert_eq!(tiles.len(), 4);
+        assert_eq!(tiles[0].1, "top-left quadrant");
+        assert_eq!(tiles[3].1, "bottom-right quadrant");
+        let total: u32 = tiles.iter().map(|(t, _)| t.width() * t.height()).sum();
+        assert_eq!(total, 101 * 50);
+    }
+
     #[test]
     fn encoded_page_fields() {
-        let data = ImageData::new("dGVzdA==".to_string(), "image/png");
+        let data = PageImage::new("dGVzdA==".to_string(), "image/png");
         let page = EncodedPage {
             page_index: 5,
             image_data: data,
+            tiles: Vec::new(),
+            columns: 1,
             render_encode_ms: 42,
+            blank: false,
+            rotation: 0,
+            spread: false,
+            render_error: None,
+            image_hash: None,
+            region: None,
+            headings: Vec::new(),
+            figures: Vec::new(),
+            encoded_at: std::time::Instant::now(),
         };
         assert_eq!(page.page_index, 5);
         assert_eq!(page.image_data.mime_type, "image/png");
@@ -500,6 +1580,42 @@ mod tests {
         assert!(pages[0].render_encode_ms > 0 || pages[1].render_encode_ms > 0);
     }
 
+    /// Verify the rendered width tracks `dpi` rather than the pixel cap.
+    #[tokio::test]
+    async fn spawn_lazy_renders_at_configured_dpi() {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+
+        let pdf_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test_cases")
+            .join("irs_form_1040.pdf");
+        if !pdf_path.exists() {
+            println!("SKIP — test_cases/irs_form_1040.pdf not found");
+            return;
+        }
+
+        let mut widths = Vec::new();
+        for dpi in [96, 200] {
+            let config = ConversionConfig {
+                dpi,
+                max_rendered_pixels: 4000,
+                ..Default::default()
+            };
+            let mut rx = match spawn_lazy_render_encode(&pdf_path, &config, &[0], 1).await {
+                Ok(rx) => rx,
+                Err(e) => {
+                    println!("SKIP — pdfium not available: {e}");
+                    return;
+                }
+            };
+            let page = rx.recv().await.expect("page 0 rendered");
+            let bytes = STANDARD.decode(&page.image_data.data).unwrap();
+            widths.push(image::load_from_memory(&bytes).unwrap().width());
+        }
+
+        // US Letter is 8.5 in wide.
+        assert_eq!(widths, vec![816, 1700]);
+    }
+
     /// Verify out-of-range page indices are silently skipped.
     #[tokio::test]
     async fn spawn_lazy_skips_out_of_range() {
@@ -0,0 +1,108 @@
+//! Document segmentation into independent sections (chapters).
+//!
+//! ## Why segment?
+//!
+//! `maintain_format` mode passes prior pages to the VLM as context, which
+//! forces pages through one at a time. But continuity only matters *within*
+//! a chapter: the first page of chapter 5 gains nothing from the last page of
+//! chapter 4. Splitting the document at chapter boundaries lets each section
+//! run sequentially while the sections themselves run in parallel.
+//!
+//! Boundaries come from the PDF outline (bookmarks) when the document has
+//! one; otherwise from pages whose text layer opens with a "Chapter N" or
+//! "Part N" heading. Both are read in [`crate::pipeline::render`]; this
+//! module holds the pure logic so it can be unit-tested without pdfium.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// Matches a chapter/part heading at the start of a page's first line,
+/// e.g. "Chapter 3", "CHAPTER IV", "Part 2: Methods".
+static SECTION_HEADING: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)^\s*(chapter|part)\s+([0-9]+|[ivxlc]+)\b").unwrap());
+
+/// `true` when `first_line` looks like the heading that opens a chapter.
+pub fn is_section_heading(first_line: &str) -> bool {
+    SECTION_HEADING.is_match(first_line)
+}
+
+/// Group selected pages into contiguous sections.
+///
+/// `page_indices` must be sorted (as produced by
+/// [`crate::config::PageSelection::to_indices`]); `starts` are 0-based page
+/// indices where a new section begins, in any order. Pages before the first
+/// start belong to a leading section (front matter). Empty sections are
+/// dropped, so the result always covers every selected page exactly once.
+pub fn split_sections(page_indices: &[usize], starts: &[usize]) -> Vec<Vec<usize>> {
+    let mut starts = starts.to_vec();
+    starts.sort_unstable();
+    starts.dedup();
+
+    let mut sections: Vec<Vec<usize>> = Vec::new();
+    let mut current: Vec<usize> = Vec::new();
+    let mut next_start = 0;
+
+    for &idx in page_indices {
+        // Advance past every boundary at or before this page; crossing at
+        // least one closes the current section.
+        let mut crossed = false;
+        while next_start < starts.len() && starts[next_start] <= idx {
+            next_start += 1;
+            crossed = true;
+        }
+        if crossed && !current.is_empty() {
+            sections.push(std::mem::take(&mut current));
+        }
+        current.push(idx);
+    }
+    if !current.is_empty() {
+        sections.push(current);
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heading_detection() {
+        assert!(is_section_heading("Chapter 3"));
+        assert!(is_section_heading("  CHAPTER IV — The Return"));
+        assert!(is_section_heading("Part 2: Methods"));
+        assert!(!is_section_heading("In this chapter we show"));
+        assert!(!is_section_heading("Chapterhouse"));
+        assert!(!is_section_heading(""));
+    }
+
+    #[test]
+    fn split_with_front_matter() {
+        let pages: Vec<usize> = (0..10).collect();
+        let sections = split_sections(&pages, &[6, 2]);
+        assert_eq!(
+            sections,
+            vec![vec![0, 1], vec![2, 3, 4, 5], vec![6, 7, 8, 9]]
+        );
+    }
+
+    #[test]
+    fn split_respects_page_selection() {
+        // Pages 3–7 selected; boundary at 5 and one outside the selection.
+        let pages = vec![3, 4, 5, 6, 7];
+        let sections = split_sections(&pages, &[0, 5, 20]);
+        assert_eq!(sections, vec![vec![3, 4], vec![5, 6, 7]]);
+    }
+
+    #[test]
+    fn split_without_boundaries_is_one_section() {
+        let pages = vec![0, 1, 2];
+        assert_eq!(split_sections(&pages, &[]), vec![vec![0, 1, 2]]);
+        assert!(split_sections(&[], &[1]).is_empty());
+    }
+
+    #[test]
+    fn boundary_on_skipped_page_starts_next_selected_page() {
+        let pages = vec![0, 1, 4, 5];
+        assert_eq!(split_sections(&pages, &[3]), vec![vec![0, 1], vec![4, 5]]);
+    }
+}
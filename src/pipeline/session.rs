@@ -0,0 +1,142 @@
+//! One open PDF shared by the passes of a single conversion.
+//!
+//! ## Why?
+//!
+//! Metadata extraction, language sampling, page classification, section
+//! detection, and rendering each used to bind pdfium and open the file
+//! again. For a large encrypted PDF every open decrypts and parses the
+//! cross-reference table anew, which adds up to seconds before the first
+//! page is sent.
+//!
+//! A [`PdfSession`] opens the document once on a thread of its own and runs
+//! each pass there as a job; the document handle never leaves that thread,
+//! which keeps pdfium's threading rules. Clones share the thread, which
+//! exits once every clone is dropped and the queued jobs have run.
+//!
+//! Rendering edits pages in memory (watermark removal,
+//! [`crate::RenderLayers::content`]) without regenerating their content, so
+//! the edits are gone once the page is closed and passes can run in any
+//! order.
+
+use super::render;
+use crate::error::Pdf2MdError;
+use crate::output::DocumentMetadata;
+use pdfium_render::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc as std_mpsc;
+use tokio::sync::oneshot;
+use tracing::debug;
+
+/// Work run against the open document on the session thread.
+type Job = Box<dyn FnOnce(&PdfDocument) + Send>;
+
+/// A PDF opened once and kept open; see [`crate::pipeline::session`].
+#[derive(Clone)]
+pub(crate) struct PdfSession {
+    path: PathBuf,
+    jobs: std_mpsc::Sender<Job>,
+}
+
+impl PdfSession {
+    /// Bind pdfium and open `pdf_path` on a new session thread.
+    pub(crate) async fn open(pdf_path: &Path, password: Option<&str>) -> Result<Self, Pdf2MdError> {
+        render::ensure_pdfium().await?;
+        let path = pdf_path.to_path_buf();
+        let password = password.map(str::to_string);
+        let (jobs, rx) = std_mpsc::channel::<Job>();
+        let (ready_tx, ready_rx) = oneshot::channel::<Result<(), Pdf2MdError>>();
+
+        let thread_path = path.clone();
+        std::thread::Builder::new()
+            .name("pdf2md-session".into())
+            .spawn(move || {
+                let pdfium = match render::get_pdfium() {
+                    Ok(p) => p,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let password = password.as_deref();
+                let document = match pdfium.load_pdf_from_file(&thread_path, password) {
+                    Ok(doc) => doc,
+                    Err(e) => {
+                        let err = render::map_pdf_open_error(e, &thread_path, password.is_some());
+                        let _ = ready_tx.send(Err(err));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(()));
+                while let Ok(job) = rx.recv() {
+                    job(&document);
+                }
+                debug!("PDF session closed: {}", thread_path.display());
+            })
+            .map_err(|e| Pdf2MdError::Internal(format!("Failed to start PDF session: {e}")))?;
+
+        match ready_rx.await {
+            Ok(Ok(())) => Ok(Self { path, jobs }),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(Pdf2MdError::Internal(
+                "PDF session thread failed before opening PDF".into(),
+            )),
+        }
+    }
+
+    /// Path of the open document.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Queue `job` without waiting for it. Jobs run one at a time, in the
+    /// order they were sent.
+    pub(crate) fn spawn(
+        &self,
+        job: impl FnOnce(&PdfDocument) + Send + 'static,
+    ) -> Result<(), Pdf2MdError> {
+        self.jobs
+            .send(Box::new(job))
+            .map_err(|_| Pdf2MdError::Internal("PDF session thread has stopped".into()))
+    }
+
+    /// Run `job` against the document and wait for its result.
+    pub(crate) async fn run<T: Send + 'static>(
+        &self,
+        job: impl FnOnce(&PdfDocument) -> T + Send + 'static,
+    ) -> Result<T, Pdf2MdError> {
+        let (tx, rx) = oneshot::channel();
+        self.spawn(move |document| {
+            let _ = tx.send(job(document));
+        })?;
+        rx.await
+            .map_err(|_| Pdf2MdError::Internal("PDF session job panicked".into()))
+    }
+
+    /// Same as [`render::extract_metadata`].
+    pub(crate) async fn metadata(&self) -> Result<DocumentMetadata, Pdf2MdError> {
+        let path = self.path.clone();
+        self.run(move |document| render::document_metadata(document, &path))
+            .await
+    }
+
+    /// Same as [`render::sample_text`].
+    pub(crate) async fn sample_text(&self, max_chars: usize) -> Result<String, Pdf2MdError> {
+        self.run(move |document| render::document_text_sample(document, max_chars))
+            .await
+    }
+
+    /// Same as [`render::page_text_layers`].
+    pub(crate) async fn page_text_layers(
+        &self,
+        page_indices: &[usize],
+    ) -> Result<Vec<(String, bool)>, Pdf2MdError> {
+        let indices = page_indices.to_vec();
+        self.run(move |document| render::document_text_layers(document, &indices))
+            .await
+    }
+
+    /// Same as [`render::detect_section_starts`].
+    pub(crate) async fn section_starts(&self) -> Result<Vec<usize>, Pdf2MdError> {
+        self.run(render::document_section_starts).await
+    }
+}
@@ -0,0 +1,137 @@
+//! Double-page spread detection for scanned books.
+//!
+//! ## Why split spreads?
+//!
+//! Books are often digitised two facing pages at a time, so one PDF page
+//! holds a left and a right page side by side. Sent whole, the model tends
+//! to read straight across the gutter, interleaving lines from both pages.
+//! Cutting the image at the gutter and converting each half as its own page
+//! restores the reading order.
+//!
+//! A rendered page counts as a spread when it is clearly landscape and a
+//! narrow vertical band near the centre is nearly uniform (a white gap or a
+//! binding shadow) while both halves carry content. Measured on a thumbnail,
+//! the check costs well under a millisecond. A landscape page with two text
+//! columns passes the same test; splitting it still yields the right reading
+//! order, which is why the option is opt-in rather than heuristic-proofed.
+
+use image::DynamicImage;
+
+/// Longest thumbnail edge used for the measurement.
+const THUMBNAIL_EDGE: u32 = 400;
+
+/// Width / height above which a page may be a spread. Two facing A4 or US
+/// Letter pages come to ~1.3–1.5.
+pub const MIN_SPREAD_ASPECT: f32 = 1.2;
+
+/// The gutter is searched for within this fraction of the width either side
+/// of the centre.
+const GUTTER_BAND: f32 = 0.1;
+
+/// A gutter column varies at most this fraction as much as the text areas.
+const MAX_GUTTER_ACTIVITY: f64 = 0.35;
+
+/// Mean column activity (luminance std dev) each half needs to count as
+/// holding content.
+const MIN_HALF_ACTIVITY: f64 = 5.0;
+
+/// X coordinate (pixels) of the gutter if `image` looks like a two-page
+/// spread, else `None`.
+pub fn detect_spread(image: &DynamicImage) -> Option<u32> {
+    let (width, height) = (image.width(), image.height());
+    if height == 0 || (width as f32 / height as f32) < MIN_SPREAD_ASPECT {
+        return None;
+    }
+    let thumb = image.thumbnail(THUMBNAIL_EDGE, THUMBNAIL_EDGE).to_luma8();
+    let (tw, th) = thumb.dimensions();
+    if tw < 10 || th == 0 {
+        return None;
+    }
+
+    // Per-column luminance standard deviation: text makes a column busy,
+    // a gap or a uniform shadow keeps it flat.
+    let activity: Vec<f64> = (0..tw)
+        .map(|x| {
+            let (sum, sum_sq) = (0..th).fold((0f64, 0f64), |(s, sq), y| {
+                let v = thumb.get_pixel(x, y).0[0] as f64;
+                (s + v, sq + v * v)
+            });
+            let mean = sum / th as f64;
+            (sum_sq / th as f64 - mean * mean).max(0.0).sqrt()
+        })
+        .collect();
+
+    let at = |f: f32| ((tw as f32 * f) as usize).min(tw as usize - 1);
+    let mean_of = |from: usize, to: usize| {
+        let slice = &activity[from..to.max(from + 1)];
+        slice.iter().sum::<f64>() / slice.len() as f64
+    };
+    let left = mean_of(at(0.1), at(0.5 - GUTTER_BAND));
+    let right = mean_of(at(0.5 + GUTTER_BAND), at(0.9));
+    if left < MIN_HALF_ACTIVITY || right < MIN_HALF_ACTIVITY {
+        return None;
+    }
+
+    let (gutter, &quietest) = activity[at(0.5 - GUTTER_BAND)..=at(0.5 + GUTTER_BAND)]
+        .iter()
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(b.1))?;
+    if quietest > MAX_GUTTER_ACTIVITY * left.min(right) {
+        return None;
+    }
+    let gutter = at(0.5 - GUTTER_BAND) + gutter;
+    Some(((gutter as f64 + 0.5) * width as f64 / tw as f64) as u32)
+}
+
+/// Cut `image` at `gutter` into its left and right pages.
+pub fn split_spread(image: &DynamicImage, gutter: u32) -> (DynamicImage, DynamicImage) {
+    let gutter = gutter.clamp(1, image.width().saturating_sub(1).max(1));
+    let left = image.crop_imm(0, 0, gutter, image.height());
+    let right = image.crop_imm(gutter, 0, image.width() - gutter, image.height());
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    /// Lines of "text" (dark stripes) wherever `inked(x)` is true.
+    fn page(width: u32, height: u32, inked: impl Fn(u32) -> bool) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, y| {
+            if inked(x) && y % 20 < 6 && (60..height - 60).contains(&y) {
+                Luma([30])
+            } else {
+                Luma([250])
+            }
+        }))
+    }
+
+    #[test]
+    fn finds_the_gutter_of_a_spread() {
+        let spread = page(1600, 1100, |x| {
+            (100..740).contains(&x) || (860..1500).contains(&x)
+        });
+        let gutter = detect_spread(&spread).expect("spread");
+        assert!((740..=860).contains(&gutter), "gutter at {gutter}");
+
+        let (left, right) = split_spread(&spread, gutter);
+        assert_eq!(left.width() + right.width(), 1600);
+        assert_eq!(left.height(), 1100);
+    }
+
+    #[test]
+    fn portrait_and_full_width_pages_are_not_spreads() {
+        let portrait = page(850, 1100, |x| (100..750).contains(&x));
+        assert_eq!(detect_spread(&portrait), None);
+
+        let landscape = page(1600, 1100, |x| (100..1500).contains(&x));
+        assert_eq!(detect_spread(&landscape), None);
+    }
+
+    #[test]
+    fn one_sided_spread_is_not_split() {
+        let half_empty = page(1600, 1100, |x| (100..740).contains(&x));
+        assert_eq!(detect_spread(&half_empty), None);
+    }
+}
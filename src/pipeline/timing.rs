@@ -0,0 +1,136 @@
+//! Per-page stage timings collected while a conversion runs.
+//!
+//! ## Why per-page samples?
+//!
+//! Pages overlap: with `concurrency = 10` the wall time of the pipeline says
+//! little about how long one render or one model call takes. Capacity
+//! planning needs the distribution instead, so every page contributes one
+//! sample per stage:
+//!
+//! * **render** — rasterise + encode, measured by the render producer
+//!   ([`EncodedPage::render_encode_ms`]);
+//! * **queue wait** — from the page being encoded until a worker picks it up,
+//!   which grows when the model, not pdfium, is the bottleneck;
+//! * **LLM** — the page's [`PageResult::duration_ms`], retries included.
+//!
+//! [`StageSamples::fill`] folds them into [`ConversionStats`] as totals,
+//! min / median / p95 / max latencies, and a retries histogram.
+
+use crate::error::PageError;
+use crate::output::{ConversionStats, LatencyStats, PageResult};
+use crate::pipeline::render::EncodedPage;
+use std::sync::Mutex;
+
+/// Thread-safe accumulator shared by the page workers of one conversion.
+#[derive(Debug, Default)]
+pub struct StageSamples {
+    inner: Mutex<Samples>,
+}
+
+#[derive(Debug, Default)]
+struct Samples {
+    render_ms: Vec<u64>,
+    queue_ms: Vec<u64>,
+    llm_ms: Vec<u64>,
+    retries: Vec<usize>,
+}
+
+impl StageSamples {
+    /// Record a page as a worker picks it up. Blank and unrenderable pages
+    /// add a render sample but never wait for a model call.
+    pub fn record_encoded(&self, page: &EncodedPage) {
+        let Ok(mut s) = self.inner.lock() else {
+            return;
+        };
+        s.render_ms.push(page.render_encode_ms);
+        if !page.blank && page.render_error.is_none() {
+            s.queue_ms
+                .push(page.encoded_at.elapsed().as_millis() as u64);
+        }
+    }
+
+    /// Record a finished page. Pages that never reached the model are ignored.
+    pub fn record_result(&self, result: &PageResult) {
+        if result.skipped_blank || matches!(result.error, Some(PageError::RenderFailed { .. })) {
+            return;
+        }
+        let Ok(mut s) = self.inner.lock() else {
+            return;
+        };
+        s.llm_ms.push(result.duration_ms);
+        let retries = result.retries as usize;
+        if s.retries.len() <= retries {
+            s.retries.resize(retries + 1, 0);
+        }
+        s.retries[retries] += 1;
+    }
+
+    /// Write stage totals, latency distributions, and the retries histogram
+    /// into `stats`.
+    pub fn fill(&self, stats: &mut ConversionStats) {
+        let Ok(s) = self.inner.lock() else {
+            return;
+        };
+        stats.render_duration_ms = s.render_ms.iter().sum();
+        stats.llm_duration_ms = s.llm_ms.iter().sum();
+        stats.render_latency = LatencyStats::from_samples(&s.render_ms);
+        stats.queue_wait = LatencyStats::from_samples(&s.queue_ms);
+        stats.llm_latency = LatencyStats::from_samples(&s.llm_ms);
+        stats.retries_histogram = s.retries.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::PageImage;
+
+    fn encoded(render_encode_ms: u64, blank: bool) -> EncodedPage {
+        EncodedPage {
+            page_index: 0,
+            image_data: PageImage::new(String::new(), "image/png"),
+            tiles: Vec::new(),
+            columns: 1,
+            render_encode_ms,
+            blank,
+            rotation: 0,
+            spread: false,
+            render_error: None,
+            image_hash: None,
+            region: None,
+            headings: Vec::new(),
+            figures: Vec::new(),
+            encoded_at: std::time::Instant::now(),
+        }
+    }
+
+    fn result(duration_ms: u64, retries: u8, skipped_blank: bool) -> PageResult {
+        PageResult {
+            page_num: 1,
+            duration_ms,
+            retries,
+            skipped_blank,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fills_totals_latencies_and_retries() {
+        let samples = StageSamples::default();
+        for (ms, blank) in [(10, false), (30, false), (5, true)] {
+            samples.record_encoded(&encoded(ms, blank));
+        }
+        samples.record_result(&result(1000, 0, false));
+        samples.record_result(&result(3000, 2, false));
+        samples.record_result(&result(0, 0, true));
+
+        let mut stats = ConversionStats::default();
+        samples.fill(&mut stats);
+        assert_eq!(stats.render_duration_ms, 45);
+        assert_eq!(stats.llm_duration_ms, 4000);
+        assert_eq!(stats.render_latency.count, 3);
+        assert_eq!(stats.queue_wait.count, 2);
+        assert_eq!(stats.llm_latency.max_ms, 3000);
+        assert_eq!(stats.retries_histogram, vec![1, 0, 1]);
+    }
+}
@@ -0,0 +1,148 @@
+//! Unicode clean-up of page Markdown, configured by [`UnicodePolicy`].
+//!
+//! PDF text layers store what was *drawn*, not what was *meant*: ligature
+//! glyphs (`ﬁ`), accents as separate combining marks, and soft hyphens
+//! where a word wrapped. Vision models copy these faithfully, and the
+//! result looks right but defeats search, spell-checking, and diffs —
+//! `ﬁnance` does not match `finance`.
+//!
+//! This pass runs before the generic cleanup rules in
+//! [`crate::pipeline::postprocess`], in this order:
+//!
+//! 1. Join words split by a soft hyphen (U+00AD) at a line break: `infor`,
+//!    soft hyphen, newline, `mation` → `information`. The
+//!    invisible-character rule removes the soft hyphen later but would leave
+//!    the line break.
+//! 2. Expand ligatures U+FB00–FB06. They have compatibility decompositions
+//!    only, so NFC alone keeps them.
+//! 3. NFC normalisation.
+//! 4. Quote and dash mapping, per [`PunctuationStyle`].
+//!
+//! Every step is idempotent, so reprocessing a page changes nothing.
+
+use crate::config::{PunctuationStyle, UnicodePolicy};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+
+/// A soft hyphen ending a line, with any spaces around the break.
+static SOFT_HYPHEN_BREAK_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\x{AD}[ \t]*\r?\n[ \t]*").unwrap());
+
+const LIGATURES: [(char, &str); 7] = [
+    ('\u{FB00}', "ff"),
+    ('\u{FB01}', "fi"),
+    ('\u{FB02}', "fl"),
+    ('\u{FB03}', "ffi"),
+    ('\u{FB04}', "ffl"),
+    ('\u{FB05}', "st"),
+    ('\u{FB06}', "st"),
+];
+
+/// Rare quote variants and their common typographic form.
+const QUOTES_STANDARD: [(char, &str); 6] = [
+    ('\u{201E}', "\u{201C}"), // „ low double
+    ('\u{201F}', "\u{201C}"), // ‟ reversed double
+    ('\u{201A}', "\u{2018}"), // ‚ low single
+    ('\u{201B}', "\u{2018}"), // ‛ reversed single
+    ('\u{FF02}', "\""),       // ＂ fullwidth
+    ('\u{FF07}', "'"),        // ＇ fullwidth
+];
+const QUOTES_ASCII: [(char, &str); 4] = [
+    ('\u{201C}', "\""),
+    ('\u{201D}', "\""),
+    ('\u{2018}', "'"),
+    ('\u{2019}', "'"),
+];
+
+/// Rare dash variants and their common form.
+const DASHES_STANDARD: [(char, &str); 7] = [
+    ('\u{2010}', "-"),        // ‐ hyphen
+    ('\u{2011}', "-"),        // ‑ non-breaking hyphen
+    ('\u{2012}', "\u{2013}"), // ‒ figure dash
+    ('\u{2015}', "\u{2014}"), // ― horizontal bar
+    ('\u{FE58}', "\u{2014}"), // ﹘ small em dash
+    ('\u{FE63}', "-"),        // ﹣ small hyphen-minus
+    ('\u{FF0D}', "-"),        // － fullwidth hyphen-minus
+];
+const DASHES_ASCII: [(char, &str); 2] = [('\u{2013}', "-"), ('\u{2014}', "--")];
+
+/// Apply `policy` to one page of Markdown.
+pub fn apply(markdown: &str, policy: &UnicodePolicy) -> String {
+    let mut s = markdown.to_string();
+    if policy.join_soft_hyphens && s.contains('\u{AD}') {
+        s = SOFT_HYPHEN_BREAK_RE.replace_all(&s, "").into_owned();
+    }
+    if policy.expand_ligatures {
+        s = map_chars(&s, &LIGATURES);
+    }
+    if policy.nfc && !is_nfc(&s) {
+        s = s.nfc().collect();
+    }
+    s = match policy.quotes {
+        PunctuationStyle::Keep => s,
+        PunctuationStyle::Standard => map_chars(&s, &QUOTES_STANDARD),
+        PunctuationStyle::Ascii => map_chars(&map_chars(&s, &QUOTES_STANDARD), &QUOTES_ASCII),
+    };
+    match policy.dashes {
+        PunctuationStyle::Keep => s,
+        PunctuationStyle::Standard => map_chars(&s, &DASHES_STANDARD),
+        PunctuationStyle::Ascii => map_chars(&map_chars(&s, &DASHES_STANDARD), &DASHES_ASCII),
+    }
+}
+
+/// Replace every character found in `table`.
+fn map_chars(s: &str, table: &[(char, &str)]) -> String {
+    if !s.chars().any(|c| table.iter().any(|(from, _)| *from == c)) {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match table.iter().find(|(from, _)| *from == c) {
+            Some((_, to)) => out.push_str(to),
+            None => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_fixes_pdf_artifacts_only() {
+        let page = "The \u{FB01}nal e\u{301}tude covers infor\u{AD}\nmation \u{201E}flow\u{201C} \u{2012} 2020\u{2011}21.";
+        let out = apply(page, &UnicodePolicy::default());
+        assert_eq!(
+            out,
+            "The final \u{E9}tude covers information \u{201E}flow\u{201C} \u{2012} 2020\u{2011}21."
+        );
+        assert_eq!(apply(&out, &UnicodePolicy::default()), out);
+        assert_eq!(apply(page, &UnicodePolicy::off()), page);
+    }
+
+    #[test]
+    fn punctuation_styles_map_quotes_and_dashes() {
+        let page =
+            "\u{201E}Ja\u{201C}, \u{2018}no\u{2019} \u{2015} pp. 3\u{2012}5, \u{AB}oui\u{BB}";
+        let standard = UnicodePolicy {
+            quotes: PunctuationStyle::Standard,
+            dashes: PunctuationStyle::Standard,
+            ..UnicodePolicy::default()
+        };
+        assert_eq!(
+            apply(page, &standard),
+            "\u{201C}Ja\u{201C}, \u{2018}no\u{2019} \u{2014} pp. 3\u{2013}5, \u{AB}oui\u{BB}"
+        );
+        let ascii = UnicodePolicy {
+            quotes: PunctuationStyle::Ascii,
+            dashes: PunctuationStyle::Ascii,
+            ..UnicodePolicy::default()
+        };
+        assert_eq!(
+            apply(page, &ascii),
+            "\"Ja\", 'no' -- pp. 3-5, \u{AB}oui\u{BB}"
+        );
+    }
+}
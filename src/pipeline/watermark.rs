@@ -0,0 +1,112 @@
+//! Watermark suppression: "DRAFT" and "CONFIDENTIAL" stamped across pages.
+//!
+//! ## Why?
+//!
+//! A large diagonal watermark crosses every line of the page. The model
+//! reads it as text and the word turns up mid-paragraph, in table cells,
+//! or as a heading of its own. With
+//! [`crate::ConversionConfig::remove_watermarks`] two passes run on each
+//! page:
+//!
+//! 1. Before rendering, text objects that look like a watermark are removed
+//!    from the page: set at an angle ([`MIN_ANGLE`]–[`MAX_ANGLE`] from the
+//!    horizontal) and either translucent or at least [`MIN_FONT_SIZE`] points.
+//!    Only the in-memory page changes; the file is never written.
+//! 2. After rendering, light pixels (luminance at or above
+//!    [`LIGHT_LUMA`]) are painted white. This catches watermarks the first
+//!    pass cannot see, such as those drawn as paths or baked into a scan,
+//!    which are printed in pale grey so the text stays legible through them.
+//!
+//! The second pass also whitens pale table shading and highlighter colour,
+//! which costs nothing for transcription.
+
+use image::{DynamicImage, Rgba};
+use pdfium_render::prelude::*;
+
+/// Smallest angle from the horizontal, in degrees, of a watermark.
+pub const MIN_ANGLE: f32 = 20.0;
+
+/// Largest angle from the horizontal, in degrees, of a watermark.
+pub const MAX_ANGLE: f32 = 70.0;
+
+/// Opaque angled text at least this large (points) counts as a watermark.
+pub const MIN_FONT_SIZE: f32 = 36.0;
+
+/// Pixels at least this light are painted white.
+pub const LIGHT_LUMA: u8 = 200;
+
+/// `true` for text drawn at `angle` degrees (clockwise or counter-clockwise)
+/// that is `translucent` or at least [`MIN_FONT_SIZE`] points.
+pub fn is_watermark(angle: f32, font_size: f32, translucent: bool) -> bool {
+    let angle = angle.rem_euclid(180.0);
+    let from_horizontal = angle.min(180.0 - angle);
+    (MIN_ANGLE..=MAX_ANGLE).contains(&from_horizontal)
+        && (translucent || font_size >= MIN_FONT_SIZE)
+}
+
+/// Remove the watermark text objects of `page`; see [`is_watermark`].
+/// Returns how many were removed.
+pub(crate) fn remove_objects(page: &mut PdfPage) -> usize {
+    let marked: Vec<usize> = page
+        .objects()
+        .iter()
+        .enumerate()
+        .filter(|(_, object)| {
+            object.as_text_object().is_some_and(|text| {
+                let angle = object.get_rotation_clockwise_degrees();
+                is_watermark(
+                    angle,
+                    text.scaled_font_size().value,
+                    object.has_transparency(),
+                )
+            })
+        })
+        .map(|(index, _)| index)
+        .collect();
+    let objects = page.objects_mut();
+    marked
+        .iter()
+        .rev()
+        .filter(|&&index| objects.remove_object_at_index(index).is_ok())
+        .count()
+}
+
+/// Paint pixels at least [`LIGHT_LUMA`] light white.
+pub fn whiten_light_pixels(image: DynamicImage) -> DynamicImage {
+    let mut rgba = image.into_rgba8();
+    for pixel in rgba.pixels_mut() {
+        let [r, g, b, _] = pixel.0;
+        let luma = (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000;
+        if luma >= LIGHT_LUMA as u32 {
+            *pixel = Rgba([255, 255, 255, 255]);
+        }
+    }
+    DynamicImage::ImageRgba8(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    #[test]
+    fn angled_large_or_translucent_text_is_a_watermark() {
+        assert!(is_watermark(45.0, 72.0, false));
+        assert!(is_watermark(-45.0, 12.0, true));
+        assert!(is_watermark(315.0, 48.0, false));
+        assert!(!is_watermark(45.0, 12.0, false));
+        assert!(!is_watermark(0.0, 72.0, true));
+        assert!(!is_watermark(90.0, 72.0, true));
+    }
+
+    #[test]
+    fn light_pixels_turn_white_and_text_stays() {
+        let mut img = RgbaImage::from_pixel(3, 1, Rgba([220, 220, 220, 255]));
+        img.put_pixel(1, 0, Rgba([30, 30, 30, 255]));
+        img.put_pixel(2, 0, Rgba([150, 150, 150, 255]));
+        let out = whiten_light_pixels(DynamicImage::ImageRgba8(img)).to_rgba8();
+        assert_eq!(out.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(out.get_pixel(1, 0).0, [30, 30, 30, 255]);
+        assert_eq!(out.get_pixel(2, 0).0, [150, 150, 150, 255]);
+    }
+}
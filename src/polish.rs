@@ -0,0 +1,254 @@
+//! Optional text-polish pass: a cheap text model fixes OCR-style typos.
+//!
+//! ## Why guard the edits?
+//!
+//! Vision models misread the odd glyph — `tbe`, `rnodern`, `l0ss` — and a
+//! text-only model fixes those for a fraction of the page call's cost. But a
+//! text model asked to "fix typos" will happily rephrase, reorder, or
+//! "correct" a figure it does not understand. Each polished page is
+//! therefore only accepted when it keeps the page's structure and stays
+//! within an edit budget:
+//!
+//! - the same number of lines, each with the same Markdown prefix (heading
+//!   marks, list markers, quotes, indentation) and the same number of `|`;
+//! - code blocks and `$$` math blocks unchanged;
+//! - the placeholders and tags left by [`crate::pii`] unchanged;
+//! - at most [`PolishConfig::max_edit_ratio`] of the page's characters
+//!   edited (Levenshtein distance, summed over lines), and no line edited by
+//!   more than a quarter of its length (at least [`MIN_LINE_EDITS`]).
+//!
+//! Anything else is treated as a rewrite and the page keeps its original
+//! text. Enabled with [`crate::ConversionConfigBuilder::text_polish`]; runs
+//! in [`crate::convert`] after post-processing and PII masking, so the text
+//! model never sees what was masked. Pages holding
+//! [`PiiAction::Tag`](crate::pii::PiiAction::Tag) spans still carry the data
+//! and are not sent at all. A failed call never fails the conversion.
+
+use crate::config::ConversionConfig;
+use crate::output::PageResult;
+use crate::pii::{self, PiiAction};
+use crate::pipeline::llm;
+use crate::prompts::{polish_request, POLISH_SYSTEM_PROMPT};
+use edgequake_llm::LLMProvider;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Every line may change by this many characters, however short.
+pub const MIN_LINE_EDITS: usize = 3;
+
+/// Options for the text-polish pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolishConfig {
+    /// Model for the pass, from the conversion's provider. Default: `None`,
+    /// the conversion's model. A small text model such as `gpt-4.1-nano` is
+    /// enough and much cheaper than the vision model.
+    pub model: Option<String>,
+    /// Largest share of a page's characters the model may change. Default:
+    /// 0.05. Pages over the budget keep their original text.
+    pub max_edit_ratio: f64,
+}
+
+impl Default for PolishConfig {
+    fn default() -> Self {
+        Self {
+            model: None,
+            max_edit_ratio: 0.05,
+        }
+    }
+}
+
+/// What the pass did, for [`crate::ConversionStats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PolishOutcome {
+    /// Pages whose polished text was accepted and differed from the original.
+    pub polished: usize,
+    /// Pages whose polished text failed a guard and was discarded.
+    pub rejected: usize,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// Polish the successful, non-blank pages in place, except pages with
+/// tagged (unmasked) PII.
+pub async fn polish_pages(
+    provider: &Arc<dyn LLMProvider>,
+    pages: &mut [PageResult],
+    config: &ConversionConfig,
+    polish: &PolishConfig,
+) -> PolishOutcome {
+    let tagging = config
+        .pii
+        .as_ref()
+        .is_some_and(|pc| pc.action == PiiAction::Tag);
+    let targets: Vec<usize> = pages
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.error.is_none() && !p.markdown.trim().is_empty())
+        .filter(|(_, p)| !(tagging && !pii::markers(&p.markdown).is_empty()))
+        .map(|(i, _)| i)
+        .collect();
+    info!("Polishing {} pages", targets.len());
+    let requests: Vec<String> = targets
+        .iter()
+        .map(|&i| polish_request(&pages[i].markdown))
+        .collect();
+    let answers: Vec<_> = futures::stream::iter(requests)
+        .map(|request| async move {
+            llm::complete_text(provider, POLISH_SYSTEM_PROMPT, &request, config).await
+        })
+        .buffered(config.concurrency)
+        .collect()
+        .await;
+
+    let mut outcome = PolishOutcome::default();
+    for (i, answer) in targets.into_iter().zip(answers) {
+        let page = &mut pages[i];
+        match answer {
+            Ok((text, input, output)) => {
+                outcome.input_tokens += input as u64;
+                outcome.output_tokens += output as u64;
+                match accept(&page.markdown, &text, polish.max_edit_ratio) {
+                    Some(polished) if polished != page.markdown => {
+                        page.markdown = polished;
+                        outcome.polished += 1;
+                    }
+                    Some(_) => {}
+                    None => {
+                        debug!("Polish of page {} rejected as a rewrite", page.page_num);
+                        outcome.rejected += 1;
+                    }
+                }
+            }
+            Err(e) => warn!("Polish failed for page {} — {}", page.page_num, e),
+        }
+    }
+    outcome
+}
+
+/// The model's answer for `original`, if it passes every guard.
+///
+/// Line endings and a trailing newline are matched to the original, so the
+/// answer is compared line by line.
+pub fn accept(original: &str, answer: &str, max_edit_ratio: f64) -> Option<String> {
+    let answer = answer.trim_matches('\n');
+    let before: Vec<&str> = original.trim_matches('\n').lines().collect();
+    let after: Vec<&str> = answer.lines().collect();
+    if before.len() != after.len() {
+        return None;
+    }
+
+    let mut in_block = false;
+    let mut edits = 0;
+    for (a, b) in before.iter().zip(&after) {
+        let fence = a.trim_start().starts_with("```") || a.trim() == "$$";
+        if in_block || fence {
+            if a != b {
+                return None;
+            }
+            in_block ^= fence;
+            continue;
+        }
+        if prefix(a) != prefix(b) || a.matches('|').count() != b.matches('|').count() {
+            return None;
+        }
+        let d = levenshtein(a, b);
+        if d > MIN_LINE_EDITS.max(a.chars().count() / 4) {
+            return None;
+        }
+        edits += d;
+    }
+    let chars = original.chars().count().max(1);
+    if edits as f64 > max_edit_ratio * chars as f64 {
+        return None;
+    }
+    if pii::markers(original) != pii::markers(answer) {
+        return None;
+    }
+
+    let start = &original[..original.len() - original.trim_start_matches('\n').len()];
+    let end = &original[original.trim_end_matches('\n').len()..];
+    Some(format!("{start}{answer}{end}"))
+}
+
+/// Leading Markdown markup of a line: indentation, `#`, `>`, list markers,
+/// and the spaces after them.
+fn prefix(line: &str) -> &str {
+    let rest = line.trim_start_matches(|c: char| {
+        c.is_whitespace() || matches!(c, '#' | '>' | '-' | '*' | '+')
+    });
+    let rest = match rest.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) if i > 0 && rest[i..].starts_with(['.', ')']) => rest[i + 1..].trim_start(),
+        _ => rest,
+    };
+    &line[..line.len() - rest.len()]
+}
+
+/// Character-level edit distance.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (diag + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            diag = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "# Resu1ts\n\n- Tbe model improves recall.\n\n| Year | Loss |\n| --- | --- |\n| 2020 | 0.4 |\n\n```python\nprnt(x)\n```\n";
+
+    #[test]
+    fn typo_fixes_are_accepted() {
+        let fixed = "# Results\n\n- The model improves recall.\n\n| Year | Loss |\n| --- | --- |\n| 2020 | 0.4 |\n\n```python\nprnt(x)\n```";
+        assert_eq!(
+            accept(PAGE, fixed, 0.05).as_deref(),
+            Some(format!("{fixed}\n").as_str())
+        );
+        assert_eq!(levenshtein("Tbe", "The"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn rewrites_and_structure_changes_are_rejected() {
+        let rephrased = PAGE.replace("Tbe model improves recall.", "Recall goes up a lot.");
+        assert_eq!(accept(PAGE, &rephrased, 0.05), None);
+        let code = PAGE.replace("prnt(x)", "print(x)");
+        assert_eq!(accept(PAGE, &code, 0.05), None);
+        let relevelled = PAGE.replace("# Resu1ts", "## Results");
+        assert_eq!(accept(PAGE, &relevelled, 0.05), None);
+        let merged = PAGE.replace("| 2020 | 0.4 |\n", "");
+        assert_eq!(accept(PAGE, &merged, 0.05), None);
+        let numbered = accept("1. Frist\n2) Secnod", "1. First\n2) Second", 0.5);
+        assert_eq!(numbered.as_deref(), Some("1. First\n2) Second"));
+        assert_eq!(accept("1. Frist", "- First", 0.5), None);
+    }
+
+    #[test]
+    fn pii_placeholders_must_survive() {
+        let page = "Contcat [EMAIL] or [PHONE].";
+        assert!(accept(page, "Contact [EMAIL] or [PHONE].", 0.2).is_some());
+        assert_eq!(accept(page, "Contact [EMAIL] or [PHONES].", 0.2), None);
+        assert_eq!(accept(page, "Contact [MAIL] or [PHONE].", 0.2), None);
+    }
+
+    #[test]
+    fn page_budget_limits_total_edits() {
+        let page = "Tbe cat. Tbe dog.";
+        let fixed = "The cat. The dog.";
+        assert!(accept(page, fixed, 0.2).is_some());
+        assert_eq!(accept(page, fixed, 0.05), None);
+    }
+}
@@ -0,0 +1,295 @@
+//! Curated settings for common document classes.
+//!
+//! ## Why presets?
+//!
+//! Getting good output from an invoice or a slide deck takes more than a
+//! model choice: the prompt has to ask for the right structure, a few
+//! pipeline knobs have to match the layout, and the model's habitual
+//! mistakes for that class need a cleanup pass. A [`DocumentPreset`] bundles
+//! all three:
+//!
+//! | Preset | Prompt asks for | Settings | Post-processing |
+//! |--------|-----------------|----------|-----------------|
+//! | `invoice` | header fields, one line-item table, a totals table | tier 2, temperature 0 | drops all-empty table rows |
+//! | `scientific-paper` | two-column reading order, LaTeX, captions, citations | tier 3, layout hints | joins words hyphenated at line ends |
+//! | `legal-contract` | verbatim wording and clause numbering | tier 2, temperature 0, maintain format | — |
+//! | `slide-deck` | one `##` title per slide, bullets, chart descriptions | tier 2, skip blank pages | exactly one `##` heading per slide |
+//!
+//! A preset is a starting point. With [`crate::ConversionConfigBuilder::preset`]
+//! later builder calls override its settings, and an explicit
+//! `system_prompt` or `prompt_template` replaces its prompt; the
+//! post-processing stays. In `pdf2md.toml` and on the CLI, every other key or
+//! flag likewise wins over the preset.
+
+use crate::config::FidelityTier;
+use crate::config_file::ConfigProfile;
+use crate::prompts;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A document class with a tuned prompt, settings, and cleanup. See the
+/// module docs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DocumentPreset {
+    /// Invoices, receipts, purchase orders.
+    #[serde(alias = "invoice")]
+    Invoice,
+    /// Journal articles and preprints.
+    #[serde(alias = "scientific_paper")]
+    ScientificPaper,
+    /// Contracts, agreements, terms.
+    #[serde(alias = "legal_contract")]
+    LegalContract,
+    /// Presentation slides exported to PDF, one slide per page.
+    #[serde(alias = "slide_deck")]
+    SlideDeck,
+}
+
+impl DocumentPreset {
+    /// Every preset, in documentation order.
+    pub const ALL: [DocumentPreset; 4] = [
+        DocumentPreset::Invoice,
+        DocumentPreset::ScientificPaper,
+        DocumentPreset::LegalContract,
+        DocumentPreset::SlideDeck,
+    ];
+
+    /// Kebab-case name, as used by `--preset` and `pdf2md.toml`.
+    pub fn name(self) -> &'static str {
+        match self {
+            DocumentPreset::Invoice => "invoice",
+            DocumentPreset::ScientificPaper => "scientific-paper",
+            DocumentPreset::LegalContract => "legal-contract",
+            DocumentPreset::SlideDeck => "slide-deck",
+        }
+    }
+
+    /// The page prompt, used unless `system_prompt` or `prompt_template` is set.
+    pub fn prompt(self) -> &'static str {
+        match self {
+            DocumentPreset::Invoice => prompts::INVOICE_SYSTEM_PROMPT,
+            DocumentPreset::ScientificPaper => prompts::SCIENTIFIC_PAPER_SYSTEM_PROMPT,
+            DocumentPreset::LegalContract => prompts::LEGAL_CONTRACT_SYSTEM_PROMPT,
+            DocumentPreset::SlideDeck => prompts::SLIDE_DECK_SYSTEM_PROMPT,
+        }
+    }
+
+    /// The settings the preset applies, as a config layer.
+    pub fn profile(self) -> ConfigProfile {
+        let mut p = ConfigProfile::default();
+        match self {
+            DocumentPreset::Invoice => {
+                p.fidelity = Some(FidelityTier::Tier2);
+                p.temperature = Some(0.0);
+            }
+            DocumentPreset::ScientificPaper => {
+                p.fidelity = Some(FidelityTier::Tier3);
+                p.layout_hints = Some(true);
+            }
+            DocumentPreset::LegalContract => {
+                p.fidelity = Some(FidelityTier::Tier2);
+                p.temperature = Some(0.0);
+                p.maintain_format = Some(true);
+            }
+            DocumentPreset::SlideDeck => {
+                p.fidelity = Some(FidelityTier::Tier2);
+                p.skip_blank_pages = Some(true);
+            }
+        }
+        p
+    }
+
+    /// Preset-specific cleanup of one page's Markdown, run after the generic
+    /// rules of [`crate::pipeline::postprocess::clean_markdown`].
+    pub fn postprocess(self, markdown: &str, page_num: usize) -> String {
+        match self {
+            DocumentPreset::Invoice => drop_empty_table_rows(markdown),
+            DocumentPreset::ScientificPaper => join_hyphenated_words(markdown),
+            DocumentPreset::LegalContract => markdown.to_string(),
+            DocumentPreset::SlideDeck => one_title_per_slide(markdown, page_num),
+        }
+    }
+}
+
+impl fmt::Display for DocumentPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DocumentPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = s.trim().to_ascii_lowercase().replace('_', "-");
+        Self::ALL
+            .into_iter()
+            .find(|p| p.name() == wanted)
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|p| p.name()).collect();
+                format!(
+                    "unknown preset '{s}', expected one of: {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Lines of `markdown` with whether each is inside a fenced code block.
+fn fenced_lines(markdown: &str) -> Vec<(&str, bool)> {
+    let mut in_fence = false;
+    markdown
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_fence = !in_fence;
+                return (line, true);
+            }
+            (line, in_fence)
+        })
+        .collect()
+}
+
+/// `lines` joined with `\n`, ending in a newline when `original` did.
+fn rejoin(lines: Vec<String>, original: &str) -> String {
+    let mut out = lines.join("\n");
+    if original.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// Remove pipe-table rows whose cells are all empty, which models emit to
+/// pad line-item tables to a "full" page.
+fn drop_empty_table_rows(markdown: &str) -> String {
+    let lines = fenced_lines(markdown)
+        .into_iter()
+        .filter(|(line, fenced)| {
+            let t = line.trim();
+            *fenced
+                || !(t.starts_with('|')
+                    && t.len() > 1
+                    && t.trim_matches('|').split('|').all(|c| c.trim().is_empty()))
+        })
+        .map(|(line, _)| line.to_string())
+        .collect();
+    rejoin(lines, markdown)
+}
+
+/// Join a word split as `exam-` / `ple` across consecutive text lines.
+///
+/// Only lowercase-to-lowercase breaks are joined, so compounds that end a
+/// line before a capitalised word, list items, and table rows are kept.
+fn join_hyphenated_words(markdown: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    let mut joinable = false;
+    for (line, fenced) in fenced_lines(markdown) {
+        let starts_lower = line.chars().next().is_some_and(|c| c.is_lowercase());
+        if joinable && !fenced && starts_lower {
+            let prev = out.last_mut().expect("joinable implies a previous line");
+            prev.pop(); // the hyphen
+            let (word_end, rest) = line.split_once(' ').unwrap_or((line, ""));
+            prev.push_str(word_end);
+            if !rest.is_empty() {
+                out.push(rest.to_string());
+            }
+        } else {
+            out.push(line.to_string());
+        }
+        let last = out.last().map(String::as_str).unwrap_or("");
+        let mut tail = last.chars().rev();
+        joinable = !fenced
+            && !last.trim_start().starts_with(['|', '#'])
+            && tail.next() == Some('-')
+            && tail.next().is_some_and(|c| c.is_lowercase());
+    }
+    rejoin(out, markdown)
+}
+
+/// ATX heading level of `line`, if it is one.
+fn heading_level(line: &str) -> Option<usize> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then_some(level)
+}
+
+/// Make the slide's title its only `##` heading.
+///
+/// A heading on the first non-empty line becomes `##`; any other `#` or `##`
+/// heading is demoted to `###`. A slide without a leading heading gets
+/// `## Slide N`.
+fn one_title_per_slide(markdown: &str, page_num: usize) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut seen_content = false;
+    let mut has_title = false;
+    for (line, fenced) in fenced_lines(markdown) {
+        let level = if fenced { None } else { heading_level(line) };
+        let text = level.map(|l| line[l..].trim());
+        match (level, text) {
+            (Some(_), Some(text)) if !seen_content => {
+                lines.push(format!("## {text}"));
+                has_title = true;
+            }
+            (Some(l), Some(text)) if l <= 2 => lines.push(format!("### {text}")),
+            _ => lines.push(line.to_string()),
+        }
+        if !line.trim().is_empty() {
+            seen_content = true;
+        }
+    }
+    if !has_title && seen_content {
+        lines.insert(0, String::new());
+        lines.insert(0, format!("## Slide {page_num}"));
+    }
+    rejoin(lines, markdown)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for preset in DocumentPreset::ALL {
+            assert_eq!(preset.name().parse::<DocumentPreset>(), Ok(preset));
+        }
+        assert_eq!(
+            "Scientific_Paper".parse::<DocumentPreset>(),
+            Ok(DocumentPreset::ScientificPaper)
+        );
+        assert!("memo".parse::<DocumentPreset>().is_err());
+    }
+
+    #[test]
+    fn invoice_drops_padding_rows() {
+        let md = "| Item | Amount |\n|---|---:|\n| Widget | 5.00 |\n|  |  |\n| | |\n";
+        assert_eq!(
+            DocumentPreset::Invoice.postprocess(md, 1),
+            "| Item | Amount |\n|---|---:|\n| Widget | 5.00 |\n"
+        );
+    }
+
+    #[test]
+    fn paper_joins_words_broken_at_line_end() {
+        let md = "the experi-\nments show that self-\nSupervised and well-\n| a- |\n";
+        assert_eq!(
+            join_hyphenated_words(md),
+            "the experiments\nshow that self-\nSupervised and well-\n| a- |\n"
+        );
+    }
+
+    #[test]
+    fn slides_get_exactly_one_h2() {
+        assert_eq!(
+            one_title_per_slide("# Roadmap\n\n## Q1\n- ship\n", 3),
+            "## Roadmap\n\n### Q1\n- ship\n"
+        );
+        assert_eq!(
+            one_title_per_slide("- just bullets\n", 4),
+            "## Slide 4\n\n- just bullets\n"
+        );
+        assert_eq!(one_title_per_slide("", 5), "");
+    }
+}
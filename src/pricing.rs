@@ -0,0 +1,140 @@
+//! Per-model token prices for cost attribution.
+//!
+//! ## Why in the crate?
+//!
+//! Token counts alone leave every caller to keep their own price sheet in
+//! step with the model they happen to run. Pricing each page where its
+//! tokens are counted gives [`crate::PageResult::cost_usd`] and
+//! [`crate::ConversionStats::total_cost_usd`] for free, so per-document cost
+//! reports need no pricing code outside the crate.
+//!
+//! The built-in table covers common hosted vision models at their list
+//! prices. Prices change and negotiated rates differ, so
+//! [`crate::ConversionConfig::model_prices`] overrides or extends it. A model
+//! matches the entry with the longest name that is a prefix of its own, after
+//! any `vendor/` routing prefix is dropped: `gpt-4.1-mini-2025-04-14` and
+//! `openai/gpt-4.1-mini` both price as `gpt-4.1-mini`. An override wins over
+//! a built-in entry of the same name only, so overriding `gpt-4.1` leaves
+//! `gpt-4.1-mini` at its own price. Models with no entry
+//! (local Ollama or LM Studio models, for instance) have no cost.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// List price of one model, in US dollars per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+impl ModelPrice {
+    pub const fn new(input_per_mtok: f64, output_per_mtok: f64) -> Self {
+        Self {
+            input_per_mtok,
+            output_per_mtok,
+        }
+    }
+
+    /// Cost in US dollars of a call with these token counts.
+    pub fn cost(&self, input_tokens: usize, output_tokens: usize) -> f64 {
+        (input_tokens as f64 * self.input_per_mtok + output_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// Built-in list prices, matched by longest prefix.
+const BUILTIN_PRICES: &[(&str, ModelPrice)] = &[
+    ("gpt-5", ModelPrice::new(1.25, 10.0)),
+    ("gpt-5-mini", ModelPrice::new(0.25, 2.0)),
+    ("gpt-5-nano", ModelPrice::new(0.05, 0.40)),
+    ("gpt-4.1", ModelPrice::new(2.0, 8.0)),
+    ("gpt-4.1-mini", ModelPrice::new(0.40, 1.60)),
+    ("gpt-4.1-nano", ModelPrice::new(0.10, 0.40)),
+    ("gpt-4o", ModelPrice::new(2.50, 10.0)),
+    ("gpt-4o-mini", ModelPrice::new(0.15, 0.60)),
+    ("claude-opus-4", ModelPrice::new(15.0, 75.0)),
+    ("claude-sonnet-4", ModelPrice::new(3.0, 15.0)),
+    ("claude-3-7-sonnet", ModelPrice::new(3.0, 15.0)),
+    ("claude-3-5-sonnet", ModelPrice::new(3.0, 15.0)),
+    ("claude-haiku-4-5", ModelPrice::new(1.0, 5.0)),
+    ("claude-3-5-haiku", ModelPrice::new(0.80, 4.0)),
+    ("gemini-2.5-pro", ModelPrice::new(1.25, 10.0)),
+    ("gemini-2.5-flash", ModelPrice::new(0.30, 2.50)),
+    ("gemini-2.5-flash-lite", ModelPrice::new(0.10, 0.40)),
+    ("gemini-2.0-flash", ModelPrice::new(0.10, 0.40)),
+    ("pixtral-large", ModelPrice::new(2.0, 6.0)),
+    ("pixtral-12b", ModelPrice::new(0.15, 0.15)),
+];
+
+/// Price of `model`: the longest matching entry of `overrides` and the
+/// built-in table, with `overrides` winning a tie, or `None`.
+pub fn price_for(model: &str, overrides: &BTreeMap<String, ModelPrice>) -> Option<ModelPrice> {
+    let model = model.rsplit('/').next().unwrap_or(model);
+    let builtin = BUILTIN_PRICES
+        .iter()
+        .map(|&(name, price)| (name, false, price));
+    let custom = overrides
+        .iter()
+        .map(|(name, price)| (name.as_str(), true, *price));
+    builtin
+        .chain(custom)
+        .filter(|(name, _, _)| model.starts_with(name))
+        .max_by_key(|&(name, is_override, _)| (name.len(), is_override))
+        .map(|(_, _, price)| price)
+}
+
+/// Sum of the known costs, or `None` when none is known.
+pub fn total_cost(costs: impl IntoIterator<Item = Option<f64>>) -> Option<f64> {
+    costs
+        .into_iter()
+        .flatten()
+        .fold(None, |sum, c| Some(sum.unwrap_or(0.0) + c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_prefix_wins_and_vendor_prefix_is_ignored() {
+        let none = BTreeMap::new();
+        assert_eq!(
+            price_for("gpt-4.1-mini-2025-04-14", &none),
+            Some(ModelPrice::new(0.40, 1.60))
+        );
+        assert_eq!(
+            price_for("openai/gpt-4.1", &none),
+            Some(ModelPrice::new(2.0, 8.0))
+        );
+        assert_eq!(price_for("llava:13b", &none), None);
+    }
+
+    #[test]
+    fn overrides_beat_the_builtin_table() {
+        let mut overrides = BTreeMap::new();
+        overrides.insert("gpt-4.1".to_string(), ModelPrice::new(1.0, 4.0));
+        overrides.insert("llava".to_string(), ModelPrice::new(0.0, 0.0));
+        assert_eq!(
+            price_for("gpt-4.1-2025-04-14", &overrides),
+            Some(ModelPrice::new(1.0, 4.0))
+        );
+        // The built-in `gpt-4.1-mini` entry is a longer, closer match.
+        assert_eq!(
+            price_for("gpt-4.1-mini", &overrides),
+            Some(ModelPrice::new(0.40, 1.60))
+        );
+        assert_eq!(
+            price_for("llava:13b", &overrides),
+            Some(ModelPrice::new(0.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn cost_and_total() {
+        let price = ModelPrice::new(2.0, 8.0);
+        assert!((price.cost(1_000, 500) - 0.006).abs() < 1e-12);
+        assert_eq!(total_cost([None, None]), None);
+        assert_eq!(total_cost([Some(0.5), None, Some(0.25)]), Some(0.75));
+    }
+}
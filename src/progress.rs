@@ -49,8 +49,9 @@ use std::sync::Arc;
 ///
 /// # Thread safety
 ///
-/// When `maintain_format = false`, `on_page_start`, `on_page_complete`, and
-/// `on_page_error` may be called concurrently from different threads.
+/// When `maintain_format = false` (or `parallel_sections = true`),
+/// `on_page_start`, `on_page_complete`, and `on_page_error` may be called
+/// concurrently from different threads.
 /// Implementations must protect shared mutable state with appropriate
 /// synchronisation primitives (e.g. `Mutex`, `AtomicUsize`).
 pub trait ConversionProgressCallback: Send + Sync {
@@ -8,12 +8,36 @@
 //! 2. **Testability** — unit tests can import and inspect prompts directly
 //!    without spinning up a real VLM, making prompt regressions easy to catch.
 //!
-//! Callers can override the default via [`crate::config::ConversionConfig::system_prompt`];
-//! the constants here are used only when no override is provided.
+//! Callers can override the default via [`crate::config::ConversionConfig::system_prompt`]
+//! or [`crate::config::ConversionConfig::prompt_template`]; the constants here
+//! are used only when no override is provided.
+//!
+//! ## Page prompt templates
+//!
+//! The page prompt is a [`PromptTemplate`]: text with `{name}` placeholders
+//! filled in for every page. Built-in variables are
+//!
+//! | Variable | Value |
+//! |----------|-------|
+//! | `{page_num}` | 1-based number of the page being converted |
+//! | `{total_pages}` | page count of the document |
+//! | `{document_title}` | PDF `/Title`, else the input file name without extension |
+//! | `{detected_language}` | language of the PDF text layer, e.g. `French`, or `unknown` |
+//!
+//! Callers may add their own with [`PromptTemplate::var`]. Placeholders with
+//! no value are left as written, so prompts containing literal braces (JSON
+//! examples, LaTeX) need no escaping. Without an override, the built-in
+//! template for the configured [`FidelityTier`] is used.
+
+use crate::config::FidelityTier;
+use crate::tagged::TaggedHeading;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Default system prompt for converting a PDF page image to Markdown.
 ///
-/// This prompt is used when `ConversionConfig::system_prompt` is `None`.
+/// This is the built-in template of [`FidelityTier::Tier2`], used when
+/// neither `ConversionConfig::prompt_template` nor `system_prompt` is set.
 pub const DEFAULT_SYSTEM_PROMPT: &str = r#"You are an expert document converter. Your task is to convert a PDF page image to clean, well-structured Markdown.
 
 Follow these rules precisely:
@@ -54,6 +78,508 @@ Follow these rules precisely:
    - Do NOT add "Page X of Y" markers
    - Start directly with the page content"#;
 
+/// Built-in page prompt of [`FidelityTier::Tier1`]: text, headings, and lists
+/// only, in as few prompt tokens as possible.
+pub const TIER1_SYSTEM_PROMPT: &str = r#"You are an expert document converter. Your task is to transcribe a PDF page image to simple Markdown.
+
+Follow these rules precisely:
+
+1. TEXT PRESERVATION
+   - Preserve ALL text content completely and accurately
+   - Maintain the reading order as a human would read the page
+
+2. STRUCTURE
+   - Use #, ## and ### for headings
+   - Use - for unordered lists and 1. 2. 3. for ordered lists
+   - Write table rows as plain lines of text, one row per line
+   - Do not use any other Markdown formatting
+
+3. WHAT TO IGNORE
+   - Page numbers, repeated headers and footers
+   - Images, decorative borders, and lines
+
+4. OUTPUT FORMAT
+   - Output ONLY the Markdown content, without ```markdown fences or commentary"#;
+
+/// Built-in page prompt of [`FidelityTier::Tier3`]: the default rules plus
+/// figure captions and stricter math and table handling, for scientific and
+/// technical documents.
+pub const TIER3_SYSTEM_PROMPT: &str = r#"You are an expert document converter. Your task is to convert a PDF page image to clean, well-structured Markdown with the highest possible fidelity.
+
+Follow these rules precisely:
+
+1. TEXT PRESERVATION
+   - Preserve ALL text content completely and accurately, including footnotes and their markers
+   - Maintain the reading order as a human would read the page
+   - Correct obvious OCR-like errors only if you are completely certain
+
+2. STRUCTURE
+   - Use # for the main page title (at most one per page)
+   - Use ## for major sections, ### for subsections, #### for minor headings
+   - Use - for unordered lists and 1. 2. 3. for ordered lists
+   - Preserve list nesting with indentation
+   - Use **bold** and *italic* to match the visual emphasis
+   - Write footnotes as [^n] references with their text at the end of the page
+
+3. TABLES
+   - Convert tables to GFM pipe format
+   - Add alignment markers (:---, :---:, ---:) matching visual alignment
+   - Use HTML table markup for merged cells, multi-line cells, or nested headers
+   - Keep table captions directly above the table
+
+4. CODE
+   - Wrap code blocks in triple backticks with language identifier
+   - Wrap inline code in single backticks
+
+5. FORMULAS
+   - Render ALL mathematical expressions using LaTeX: $inline$ and $$display$$
+   - Transcribe symbols, subscripts, and superscripts exactly; keep equation numbers as \tag{n}
+
+6. FIGURES
+   - For each figure, chart, or photo, write its caption in italics, e.g. *Figure 3: Results by year*
+   - If a figure has no caption, write a one-line italic description of what it shows
+
+7. WHAT TO IGNORE
+   - Page numbers (bottom/top of page)
+   - Repeated headers/footers that appear on every page
+   - Decorative borders and lines that carry no content meaning
+
+8. OUTPUT FORMAT
+   - Output ONLY the Markdown content
+   - Do NOT wrap in ```markdown fences
+   - Do NOT add commentary or explanations
+   - Do NOT add "Page X of Y" markers
+   - Start directly with the page content"#;
+
+/// Built-in page prompt of [`FidelityTier::Tier4`]: the Tier3 rules, with
+/// flowcharts and org charts redrawn as Mermaid code blocks. Blocks that do
+/// not parse are replaced by their description; see [`crate::mermaid`].
+pub const TIER4_SYSTEM_PROMPT: &str = r#"You are an expert document converter. Your task is to convert a PDF page image to clean, well-structured Markdown with the highest possible fidelity.
+
+Follow these rules precisely:
+
+1. TEXT PRESERVATION
+   - Preserve ALL text content completely and accurately, including footnotes and their markers
+   - Maintain the reading order as a human would read the page
+   - Correct obvious OCR-like errors only if you are completely certain
+
+2. STRUCTURE
+   - Use # for the main page title (at most one per page)
+   - Use ## for major sections, ### for subsections, #### for minor headings
+   - Use - for unordered lists and 1. 2. 3. for ordered lists
+   - Preserve list nesting with indentation
+   - Use **bold** and *italic* to match the visual emphasis
+   - Write footnotes as [^n] references with their text at the end of the page
+
+3. TABLES
+   - Convert tables to GFM pipe format
+   - Add alignment markers (:---, :---:, ---:) matching visual alignment
+   - Use HTML table markup for merged cells, multi-line cells, or nested headers
+   - Keep table captions directly above the table
+
+4. CODE
+   - Wrap code blocks in triple backticks with language identifier
+   - Wrap inline code in single backticks
+
+5. FORMULAS
+   - Render ALL mathematical expressions using LaTeX: $inline$ and $$display$$
+   - Transcribe symbols, subscripts, and superscripts exactly; keep equation numbers as \tag{n}
+
+6. FIGURES AND DIAGRAMS
+   - For each chart or photo, write its caption in italics, e.g. *Figure 3: Results by year*
+   - If a figure has no caption, write a one-line italic description of what it shows
+   - Redraw flowcharts, process diagrams, decision trees, and org charts as a ```mermaid code block using `flowchart TD` (or `flowchart LR` for left-to-right diagrams)
+   - Inside the block, start with `accDescr: ` and a one-sentence description of the diagram
+   - Give every box a short ID and its exact text as a quoted label, e.g. A["Submit request"] --> B{"Approved?"}
+   - Write edge labels as -->|"Yes"| and group boxes drawn inside a frame with subgraph … end
+   - Keep the diagram's caption as an italic line directly above the block
+
+7. WHAT TO IGNORE
+   - Page numbers (bottom/top of page)
+   - Repeated headers/footers that appear on every page
+   - Decorative borders and lines that carry no content meaning
+
+8. OUTPUT FORMAT
+   - Output ONLY the Markdown content
+   - Do NOT wrap in ```markdown fences
+   - Do NOT add commentary or explanations
+   - Do NOT add "Page X of Y" markers
+   - Start directly with the page content"#;
+
+/// Page prompt of [`crate::ContentHint::Handwritten`] (see
+/// [`crate::handwriting`]): a literal transcription that marks what it cannot
+/// read instead of guessing.
+pub const HANDWRITING_SYSTEM_PROMPT: &str = r#"You are an expert transcriber of handwritten documents: notes, letters, and filled-in forms. Transcribe this page image to Markdown.
+
+Follow these rules precisely:
+
+1. TRANSCRIBE, DO NOT GUESS
+   - Write exactly what is written, word for word, including spelling mistakes and crossed-out text as ~~strikethrough~~
+   - If you are not sure of a word, write your best reading followed by [?], e.g. Tuesday[?]
+   - If a word is illegible, write [?] in its place; never invent words to complete a sentence
+   - Do not correct grammar, expand abbreviations, or rewrite in your own words
+
+2. STRUCTURE
+   - Keep the writer's line breaks for lists, addresses, and verse; join lines of running prose into paragraphs
+   - Use # headings only for text that is clearly a title
+   - Write printed form labels in **bold** followed by the handwritten value, e.g. **Name:** Jane Doe
+   - Write tick boxes as [x] when ticked and [ ] when empty
+   - Write marginal notes and insertions as a separate paragraph starting with *Margin:*
+
+3. WHAT TO IGNORE
+   - Page numbers, ruled lines, and punch holes
+   - Doodles and stray marks that are not writing
+
+4. OUTPUT FORMAT
+   - Output ONLY the Markdown content
+   - Do NOT wrap in ```markdown fences
+   - Do NOT add commentary or explanations
+   - Start directly with the page content"#;
+
+/// Page prompt of [`crate::presets::DocumentPreset::Invoice`].
+pub const INVOICE_SYSTEM_PROMPT: &str = r#"You are an expert at transcribing invoices, receipts, and purchase orders. Convert this page image to Markdown.
+
+Follow these rules precisely:
+
+1. HEADER FIELDS
+   - Write seller, buyer, invoice number, dates, currency, and payment terms as a list of **Field:** value lines, in the order they appear
+   - Copy identifiers (invoice, order, VAT, IBAN numbers) character for character
+
+2. LINE ITEMS
+   - Put every line item in ONE GFM pipe table with the columns printed on the page (e.g. Description | Quantity | Unit price | Amount)
+   - One row per item; never merge or split items, never drop a row
+   - Right-align numeric columns with ---:
+   - Keep amounts exactly as printed, including currency symbols, thousands separators, and decimal commas
+
+3. TOTALS
+   - Write subtotal, discounts, taxes, shipping, and total as a second two-column table (Item | Amount) after the line items
+
+4. OTHER TEXT
+   - Transcribe notes, bank details, and terms as plain paragraphs
+   - Ignore logos, stamps, and decorative lines
+
+5. OUTPUT FORMAT
+   - Output ONLY the Markdown content, without ```markdown fences or commentary
+   - Never compute, correct, or invent amounts"#;
+
+/// Page prompt of [`crate::presets::DocumentPreset::ScientificPaper`].
+pub const SCIENTIFIC_PAPER_SYSTEM_PROMPT: &str = r#"You are an expert at transcribing scientific papers. Convert this page image to clean, well-structured Markdown.
+
+Follow these rules precisely:
+
+1. READING ORDER
+   - Papers are often set in two columns: read the left column fully, then the right column
+   - Join words hyphenated across line breaks
+   - Keep figures and tables where they appear in the text flow
+
+2. STRUCTURE
+   - Use # for the paper title (first page only), ## for numbered sections, ### for subsections
+   - Keep section numbers as written (e.g. ## 3.2 Results)
+   - Write the abstract as a ## Abstract section
+
+3. MATH
+   - Render ALL mathematical expressions using LaTeX: $inline$ and $$display$$
+   - Keep equation numbers as 	ag{n}
+
+4. TABLES AND FIGURES
+   - Convert tables to GFM pipe format, or HTML for merged cells; keep the caption above the table
+   - For each figure, write its caption in italics, e.g. *Figure 2: Accuracy by model size*
+
+5. REFERENCES AND FOOTNOTES
+   - Keep citation markers exactly as printed ([12], (Smith et al., 2020))
+   - Write each bibliography entry as one list item
+   - Write footnotes as [^n] references with their text at the end of the page
+
+6. WHAT TO IGNORE
+   - Page numbers, running heads, journal banners, and line numbers in the margin
+
+7. OUTPUT FORMAT
+   - Output ONLY the Markdown content, without ```markdown fences or commentary"#;
+
+/// Page prompt of [`crate::presets::DocumentPreset::LegalContract`].
+pub const LEGAL_CONTRACT_SYSTEM_PROMPT: &str = r#"You are an expert legal transcriber. Convert this contract page image to Markdown without changing a single word.
+
+Follow these rules precisely:
+
+1. WORDING
+   - Transcribe every word exactly, including capitalisation, defined terms, and punctuation
+   - Never paraphrase, summarise, correct, or modernise the language
+
+2. NUMBERING
+   - Keep clause and sub-clause numbers exactly as printed (1., 1.1, (a), (i), Article IV)
+   - Use ## for articles or top-level clauses that have a title, ### for titled sub-clauses
+   - Write untitled clauses as paragraphs starting with their number; do not turn them into Markdown lists
+
+3. DEFINITIONS AND REFERENCES
+   - Keep defined terms in the emphasis used on the page (usually **bold** or "quoted")
+   - Keep cross-references ("see Clause 7.2") as written
+
+4. SIGNATURES AND BLANKS
+   - Write signature blocks as plain lines: name, title, date
+   - Write blank fields as ________ and checked boxes as [x], unchecked as [ ]
+
+5. WHAT TO IGNORE
+   - Page numbers, initials boxes in the margin, and repeated headers and footers
+
+6. OUTPUT FORMAT
+   - Output ONLY the Markdown content, without ```markdown fences or commentary"#;
+
+/// Page prompt of [`crate::presets::DocumentPreset::SlideDeck`].
+pub const SLIDE_DECK_SYSTEM_PROMPT: &str = r#"You are an expert at transcribing presentation slides. Convert this slide image to Markdown.
+
+Follow these rules precisely:
+
+1. TITLE
+   - Start with the slide title as a single ## heading
+   - Use ### for any sub-headings on the slide; never use #
+
+2. BODY
+   - Write bullet points as - lists, preserving their nesting
+   - Transcribe text boxes and callouts as short paragraphs in reading order
+   - Convert tables to GFM pipe format
+
+3. VISUALS
+   - For each chart, diagram, or image that carries information, write one italic line describing what it shows, e.g. *Bar chart: revenue by quarter, Q4 highest*
+   - Ignore logos, slide numbers, footers, and decorative shapes
+
+4. SPEAKER NOTES
+   - If speaker notes are visible, put them last under ### Notes
+
+5. OUTPUT FORMAT
+   - Output ONLY the Markdown content, without ```markdown fences or commentary"#;
+
+/// Appended to the built-in and preset prompts for
+/// [`crate::MarkdownFlavor::CommonMark`].
+pub const COMMONMARK_RULES: &str = r#"
+
+MARKDOWN FLAVOR: strict CommonMark
+   - Write every table as HTML <table> markup; pipe tables are not supported
+   - Do not use ~~strikethrough~~, task lists, or [!NOTE] alerts
+   - Write notes and warnings as a blockquote starting with **Note** or **Warning**"#;
+
+/// Appended to the built-in and preset prompts for
+/// [`crate::MarkdownFlavor::Pandoc`].
+pub const PANDOC_RULES: &str = r#"
+
+MARKDOWN FLAVOR: Pandoc Markdown
+   - Write callout boxes (notes, tips, warnings) as fenced divs: a line "::: note", the text, then a line ":::"
+   - Write math as $inline$ and $$display$$ TeX"#;
+
+/// Appended to the built-in and preset prompts for
+/// [`crate::MarkdownFlavor::Mdx`].
+pub const MDX_RULES: &str = r#"
+
+MARKDOWN FLAVOR: MDX (Docusaurus)
+   - Write callout boxes as admonitions: a line ":::note" (or :::tip, :::info, :::warning, :::danger), the text, then a line ":::"
+   - Any HTML must be valid JSX: close every tag, self-close void tags (<br />), no HTML comments
+   - Never write a literal { or } or < in running text; keep them inside `code` or math"#;
+
+/// Appended to the page prompt when the model declined the page or
+/// described it instead of transcribing it (see
+/// [`crate::PageError::ModelRefusal`]).
+pub const REFUSAL_RETRY_RULES: &str = r#"
+
+IMPORTANT: This is a document digitisation task. The image is a page of a document the user is entitled to convert.
+   - Transcribe the text exactly as it appears; do not describe, summarise, or comment on the image
+   - Do not apologise or explain; if part of the page is unreadable, transcribe the rest
+   - Start your answer directly with the page content"#;
+
+/// Appended to the built-in and preset prompts with
+/// [`crate::ConversionConfig::chart_tables`] (see [`crate::charts`]).
+pub const CHART_DATA_RULES: &str = r#"
+
+CHARTS
+   - For each bar, line, or pie chart, write an italic caption naming the chart type, e.g. *Bar chart: Revenue by region, 2023*
+   - Directly beneath the caption, write the chart's data as a GFM pipe table: one row per category or x value, one column per series
+   - Use the axis or legend names as column headers and copy data labels exactly; estimate unlabelled values from the axis and prefix them with ~
+   - Do not describe the chart in prose in addition to the table"#;
+
+/// Appended to the built-in and preset prompts with
+/// [`crate::ConversionConfig::niche_placeholders`] (see [`crate::niche`]).
+pub const NICHE_CONTENT_RULES: &str = r#"
+
+CONTENT YOU CANNOT TRANSCRIBE
+   - Do not describe sheet music, technical or CAD drawings, schematics, or maps in prose, and do not invent tables for them
+   - Instead write one line in their place: [[NOT TRANSCRIBED: CLASS | TITLE]], where CLASS is sheet-music, technical-drawing, or map and TITLE is the piece's or drawing's printed title, if any
+   - Still transcribe printed text around them: page titles, captions, and paragraphs"#;
+
+/// Variable holding the 1-based page number.
+pub const VAR_PAGE_NUM: &str = "page_num";
+/// Variable holding the document's page count.
+pub const VAR_TOTAL_PAGES: &str = "total_pages";
+/// Variable holding the document title.
+pub const VAR_DOCUMENT_TITLE: &str = "document_title";
+/// Variable holding the language detected in the PDF text layer.
+pub const VAR_DETECTED_LANGUAGE: &str = "detected_language";
+
+/// A page prompt with `{name}` placeholders. See the module docs.
+///
+/// ```rust
+/// use edgequake_pdf2md::prompts::PromptTemplate;
+///
+/// let t = PromptTemplate::new("Page {page_num} of {audience} notes; keep {braces}.")
+///     .var("audience", "legal");
+/// assert_eq!(t.render(4), "Page 4 of legal notes; keep {braces}.");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    text: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    vars: BTreeMap<String, String>,
+}
+
+impl PromptTemplate {
+    /// A template from raw text.
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            vars: BTreeMap::new(),
+        }
+    }
+
+    /// The built-in page prompt for `tier`.
+    pub fn builtin(tier: FidelityTier) -> Self {
+        Self::new(match tier {
+            FidelityTier::Tier1 => TIER1_SYSTEM_PROMPT,
+            FidelityTier::Tier2 => DEFAULT_SYSTEM_PROMPT,
+            FidelityTier::Tier3 => TIER3_SYSTEM_PROMPT,
+            FidelityTier::Tier4 => TIER4_SYSTEM_PROMPT,
+        })
+    }
+
+    /// Set variable `name` to `value`. Values set here take precedence over
+    /// the document variables filled in at conversion time; `{page_num}`
+    /// cannot be overridden.
+    pub fn var(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set `name` unless the caller already did.
+    pub(crate) fn var_or_keep(mut self, name: &str, value: impl FnOnce() -> String) -> Self {
+        if !self.vars.contains_key(name) {
+            self.vars.insert(name.to_string(), value());
+        }
+        self
+    }
+
+    /// The template text, placeholders unexpanded.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Whether the text contains the `{name}` placeholder.
+    pub fn uses(&self, name: &str) -> bool {
+        self.text.contains(&format!("{{{name}}}"))
+    }
+
+    /// The prompt for page `page_num`, with every placeholder that has a
+    /// value replaced. Substituted values are not expanded again.
+    pub fn render(&self, page_num: usize) -> String {
+        let mut out = String::with_capacity(self.text.len());
+        let mut rest = self.text.as_str();
+        while let Some(open) = rest.find('{') {
+            out.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            let name_len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            let name = &after[..name_len];
+            let value = if name.is_empty() || !after[name_len..].starts_with('}') {
+                None
+            } else if name == VAR_PAGE_NUM {
+                Some(page_num.to_string())
+            } else {
+                self.vars.get(name).cloned()
+            };
+            match value {
+                Some(value) => {
+                    out.push_str(&value);
+                    rest = &after[name_len + 1..];
+                }
+                None => {
+                    out.push('{');
+                    rest = after;
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Common function words of the languages [`detect_language`] recognises.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "English",
+        &[
+            "the", "and", "of", "to", "is", "in", "that", "with", "for", "this",
+        ],
+    ),
+    (
+        "French",
+        &[
+            "le", "la", "les", "et", "des", "est", "une", "dans", "pour", "qui",
+        ],
+    ),
+    (
+        "German",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "mit", "den", "ein", "sich",
+        ],
+    ),
+    (
+        "Spanish",
+        &[
+            "el", "los", "las", "y", "es", "del", "una", "por", "con", "que",
+        ],
+    ),
+    (
+        "Italian",
+        &[
+            "il", "di", "che", "e", "della", "per", "non", "sono", "gli", "una",
+        ],
+    ),
+    (
+        "Portuguese",
+        &[
+            "o", "os", "da", "do", "em", "uma", "para", "não", "com", "que",
+        ],
+    ),
+    (
+        "Dutch",
+        &[
+            "de", "het", "een", "en", "van", "is", "niet", "dat", "op", "voor",
+        ],
+    ),
+];
+
+/// Language of `text` by function-word frequency, or `None` when fewer than
+/// ten function words are found (too little text, or another language).
+///
+/// Crude but dependency-free; good enough to tell the model which language
+/// a scanned page is in.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut scores = [0usize; STOPWORDS.len()];
+    for word in text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+    {
+        let word = word.to_lowercase();
+        for (score, (_, words)) in scores.iter_mut().zip(STOPWORDS) {
+            if words.contains(&word.as_str()) {
+                *score += 1;
+            }
+        }
+    }
+    let (best, score) = scores
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, s)| **s)
+        .expect("STOPWORDS is not empty");
+    (*score >= 10).then_some(STOPWORDS[best].0)
+}
+
 /// Additional instruction appended when `maintain_format` is enabled.
 ///
 /// The placeholder `{prior_page}` must be replaced with the previous page's
@@ -74,3 +600,292 @@ pub fn maintain_format_context(prior_page: &str) -> String {
         prior_page
     )
 }
+
+/// Build the user-turn hint sent with one tile of a split page.
+///
+/// Without it the model treats each tile as a whole page: it invents a
+/// `#` title for the bottom half and "completes" sentences cut at the edge.
+pub fn tile_context(index: usize, count: usize, position: &str) -> String {
+    format!(
+        "This image is tile {} of {} ({}) of a single page that was split for legibility. \
+Transcribe only what is visible in this tile, in reading order. Do not add a page title \
+unless one is visible, and do not complete text that is cut off at the tile edge.",
+        index + 1,
+        count,
+        position
+    )
+}
+
+/// Build the user-turn hint sent with one half of a two-page spread.
+///
+/// Each half is a complete book page, so unlike [`tile_context`] the model
+/// may keep its headings; it only needs to know not to look for the
+/// facing page.
+pub fn spread_context(index: usize, count: usize, position: &str) -> String {
+    format!(
+        "This image is the {} ({} of {}) of a two-page book spread that was split at the gutter. \
+Transcribe this page on its own, in reading order. Text cut off at the inner edge continues on \
+the facing page; do not complete it.",
+        position,
+        index + 1,
+        count
+    )
+}
+
+/// Build the reading-order hint for a page detected as multi-column.
+pub fn column_layout_hint(columns: usize) -> String {
+    let order = match columns {
+        2 => "read the left column fully, top to bottom, before the right column",
+        _ => "read each column fully, top to bottom, from left to right",
+    };
+    format!(
+        "This page is laid out in {} text columns: {}. Do not interleave lines \
+across columns. Headings, figures, or tables spanning all columns stay where they appear.",
+        columns, order
+    )
+}
+
+/// Build the heading hint for a page whose structure tree marks headings
+/// (see [`crate::tagged`]).
+pub fn structure_hint(headings: &[TaggedHeading]) -> String {
+    let list: Vec<String> = headings
+        .iter()
+        .take(MAX_HINTED_HEADINGS)
+        .map(|h| format!("- {} {}", "#".repeat(h.level as usize), h.text))
+        .collect();
+    format!(
+        "The PDF's accessibility tags mark these headings on this page, with their Markdown \
+levels:\n{}\nUse exactly these levels for them, and do not make other text a heading of the same \
+or higher level unless it clearly is one.",
+        list.join("\n")
+    )
+}
+
+/// Headings listed by [`structure_hint`]; the rest still get their levels
+/// corrected afterwards.
+const MAX_HINTED_HEADINGS: usize = 30;
+
+/// System prompt for the document-summary pass (see [`crate::summary`]).
+pub const SUMMARY_SYSTEM_PROMPT: &str = r#"You are an expert technical editor. You write concise, faithful abstracts of documents.
+
+Rules:
+- Summarise only what the text says; never add facts, opinions, or speculation
+- Cover the purpose, main points, and conclusions in document order
+- Write plain prose paragraphs; no headings, lists, or Markdown fences
+- Do NOT add commentary such as "This summary..." or "Here is the abstract""#;
+
+/// Build the user turn asking for an abstract of `markdown`.
+///
+/// `partial` marks one chunk of a longer document (the "map" step); the
+/// final abstract is then written from the partial summaries.
+pub fn summary_request(markdown: &str, max_words: usize, partial: bool) -> String {
+    let what = if partial {
+        "this excerpt of a longer document"
+    } else {
+        "the following document"
+    };
+    format!(
+        "Write an abstract of {} in at most {} words.\n\n\"\"\"{}\"\"\"",
+        what, max_words, markdown
+    )
+}
+
+/// Build the "reduce" user turn combining per-chunk summaries into one abstract.
+pub fn summary_reduce_request(partials: &[String], max_words: usize) -> String {
+    let sections: Vec<String> = partials
+        .iter()
+        .enumerate()
+        .map(|(i, p)| format!("Part {}:\n{}", i + 1, p.trim()))
+        .collect();
+    format!(
+        "The following are summaries of consecutive parts of one document. \
+Combine them into a single abstract of the whole document in at most {} words.\n\n\"\"\"{}\"\"\"",
+        max_words,
+        sections.join("\n\n")
+    )
+}
+
+/// System prompt for the keyword/entity extraction pass (see [`crate::enrich`]).
+pub const ENRICHMENT_SYSTEM_PROMPT: &str = r#"You are an expert indexer. You extract search metadata from documents.
+
+Reply with a single JSON object and nothing else:
+{"document_type": "...", "keywords": ["..."], "entities": [{"name": "...", "type": "..."}]}
+
+Rules:
+- document_type: one short lowercase label, e.g. "research paper", "invoice", "contract", "manual", "book", "report", "slide deck"
+- keywords: the most specific topical terms, most important first; no generic words such as "document" or "introduction"
+- entities: people, organizations, locations, products, and dates that are actually named in the text; type is one of "person", "organization", "location", "product", "date", "other"
+- Use only what the text says; never invent entities
+- Do NOT wrap the JSON in Markdown fences or add commentary"#;
+
+/// Build the user turn asking for enrichment metadata of `markdown`.
+pub fn enrichment_request(markdown: &str, max_keywords: usize) -> String {
+    format!(
+        "Extract the document type, at most {} keywords, and the named entities of the following text.\n\n\"\"\"{}\"\"\"",
+        max_keywords, markdown
+    )
+}
+
+/// System prompt for the person-name detection pass (see [`crate::pii`]).
+pub const PII_NAMES_SYSTEM_PROMPT: &str = r#"You are a privacy reviewer. You find the names of real people in documents.
+
+Reply with a single JSON array of strings and nothing else, e.g. ["Jane Doe", "Dr. Rahul Mehta"]
+
+Rules:
+- Include every person's name exactly as it is written in the text, once
+- Do not include organizations, products, places, or fictional characters
+- Do not include authors of cited works in a bibliography unless they also appear in the body
+- Reply [] when there are no names
+- Do NOT wrap the JSON in Markdown fences or add commentary"#;
+
+/// Build the user turn asking for the person names in `markdown`.
+pub fn pii_names_request(markdown: &str) -> String {
+    format!(
+        "List the names of people in the following text.\n\n\"\"\"{}\"\"\"",
+        markdown
+    )
+}
+
+/// System prompt for the text-polish pass (see [`crate::polish`]).
+pub const POLISH_SYSTEM_PROMPT: &str = r#"You are a careful proofreader. You fix character-recognition errors in Markdown transcribed from scanned pages.
+
+Reply with the corrected Markdown and nothing else.
+
+Rules:
+- Fix only obvious misrecognitions: wrong letters (tbe → the, rnodern → modern), digits for letters (l0ss → loss), stray spaces inside words
+- Keep every line: same line breaks, headings, list markers, table cells, and links
+- Do not rephrase, reorder, shorten, translate, or modernise spelling
+- Do not change numbers, names, code, math, or anything you are unsure about
+- Reply with the text unchanged when there is nothing to fix
+- Do NOT wrap the Markdown in fences or add commentary"#;
+
+/// Build the user turn asking for typos in `markdown` to be fixed.
+pub fn polish_request(markdown: &str) -> String {
+    format!(
+        "Fix the recognition errors in the following Markdown.\n\n\"\"\"{}\"\"\"",
+        markdown
+    )
+}
+
+/// System prompt for figure alt text (see [`crate::figures`]).
+pub const FIGURE_ALT_PROMPT: &str = r#"You are an accessibility specialist. You write alt text for figures cut out of documents.
+
+Reply with the alt text and nothing else.
+
+Rules:
+- One or two plain sentences, at most 40 words
+- Say what kind of figure it is (bar chart, photo, diagram, map) and what it shows
+- For charts and diagrams, give the main trend, comparison, or flow, with key numbers if legible
+- Do not start with "Image of" or "Picture of", and do not guess at anything illegible
+- No Markdown, quotes, or labels"#;
+
+/// System prompt of the [`crate::ContentHint::Auto`] check (see
+/// [`crate::handwriting`]).
+pub const HANDWRITING_CLASSIFIER_PROMPT: &str = r#"You classify scanned document pages by how their text was written.
+
+Reply with exactly one word and nothing else:
+- printed: the text is typeset, typed, or computer-generated; a few handwritten signatures or initials do not count
+- handwritten: most of the text was written by hand, including forms whose filled-in values are handwritten
+- mixed: substantial amounts of both"#;
+
+/// System prompt of [`crate::page_kind::PageClassifier::Model`].
+pub const PAGE_KIND_CLASSIFIER_PROMPT: &str = r#"You classify document pages so that unwanted ones can be skipped.
+
+Reply with exactly one word and nothing else:
+- cover: a title or cover page
+- toc: a table of contents
+- references: a bibliography or reference list
+- advertisement: a page or near-full page of advertising
+- blank: nothing, or only a page number or running header
+- body: anything else"#;
+
+/// System prompt for document-scope structured extraction (see [`crate::extract`]).
+pub const EXTRACTION_SYSTEM_PROMPT: &str = r#"You are a meticulous data-entry specialist. You fill a JSON Schema with facts taken from documents.
+
+Reply with a single JSON value that validates against the schema and nothing else.
+
+Rules:
+- Use only what the document says; never invent values
+- Copy names, numbers, dates, and identifiers exactly as written, converted to the schema's types
+- Use null for a value the document does not contain, unless the schema forbids it
+- Include every required property; add no properties the schema does not define
+- Do NOT wrap the JSON in Markdown fences or add commentary"#;
+
+/// Build the user turn asking for `schema` to be filled from `markdown`.
+pub fn extraction_request(markdown: &str, schema: &str) -> String {
+    format!(
+        "Fill this JSON Schema from the document below.\n\nSchema:\n{}\n\nDocument:\n\"\"\"{}\"\"\"",
+        schema, markdown
+    )
+}
+
+/// Tell the model its `previous` answer failed validation with `errors`,
+/// appended to the original request for a repair attempt.
+pub fn extraction_feedback(previous: &str, errors: &[String]) -> String {
+    format!(
+        "Your previous answer was:\n{}\n\nIt does not validate against the schema:\n- {}\n\nReply with the corrected JSON only.",
+        previous,
+        errors.join("\n- ")
+    )
+}
+
+/// Page prompt for page-scope structured extraction: the page image is
+/// read directly into `schema`. `feedback` carries validation errors of the
+/// previous attempt, if any.
+pub fn page_extraction_prompt(schema: &str, feedback: Option<&str>) -> String {
+    let mut prompt = format!(
+        "{}\n\nFill this JSON Schema from the page image.\n\nSchema:\n{}",
+        EXTRACTION_SYSTEM_PROMPT, schema
+    );
+    if let Some(feedback) = feedback {
+        prompt.push_str("\n\n");
+        prompt.push_str(feedback);
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_fills_known_variables_only() {
+        let t = PromptTemplate::new(
+            "{document_title}: page {page_num}/{total_pages} {x} {} {{page_num}",
+        )
+        .var(VAR_DOCUMENT_TITLE, "Report {page_num}")
+        .var(VAR_TOTAL_PAGES, "9");
+        assert_eq!(t.render(3), "Report {page_num}: page 3/9 {x} {} {3");
+        assert!(t.uses(VAR_TOTAL_PAGES));
+        assert!(!t.uses(VAR_DETECTED_LANGUAGE));
+    }
+
+    #[test]
+    fn caller_variables_win_over_document_ones() {
+        let t = PromptTemplate::new("{document_title}")
+            .var(VAR_DOCUMENT_TITLE, "Mine")
+            .var_or_keep(VAR_DOCUMENT_TITLE, || "From PDF".into());
+        assert_eq!(t.render(1), "Mine");
+    }
+
+    #[test]
+    fn tier2_builtin_is_the_default_prompt() {
+        assert_eq!(
+            PromptTemplate::builtin(FidelityTier::Tier2).render(1),
+            DEFAULT_SYSTEM_PROMPT
+        );
+        assert_ne!(
+            PromptTemplate::builtin(FidelityTier::Tier1).text(),
+            DEFAULT_SYSTEM_PROMPT
+        );
+    }
+
+    #[test]
+    fn detects_language_from_function_words() {
+        let en = "The results of the study show that the method is robust and that it works for this data in the field.";
+        let fr = "Les résultats de la étude montrent que la méthode est robuste et qu'elle fonctionne pour les données dans le domaine et pour la suite des travaux qui sont une base.";
+        assert_eq!(detect_language(en), Some("English"));
+        assert_eq!(detect_language(fr), Some("French"));
+        assert_eq!(detect_language("12 34 56"), None);
+    }
+}
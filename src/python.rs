@@ -0,0 +1,164 @@
+//! Python bindings (feature `python`).
+//!
+//! ## Why in-process bindings?
+//!
+//! ML teams live in notebooks. Shelling out to the `pdf2md` binary loses
+//! per-page results and statistics, and parsing its output is brittle. This
+//! module builds a `pdf2md` extension module with [PyO3] that calls the
+//! pipeline directly:
+//!
+//! ```python
+//! import pdf2md
+//!
+//! result = pdf2md.convert("paper.pdf", model="gpt-4.1-mini", pages=(1, 5))
+//! print(result["markdown"])
+//! print(result["stats"]["total_input_tokens"])
+//!
+//! # From asyncio code (e.g. Jupyter, which already runs an event loop):
+//! result = await pdf2md.convert_async("paper.pdf", maintain_format=True)
+//! ```
+//!
+//! The result is a `dict` with the same shape as the JSON output of
+//! [`crate::ConversionOutput`]: `markdown`, `pages`, `metadata`, and `stats`.
+//! Failures raise `pdf2md.ConversionError`.
+//!
+//! Build a wheel with `maturin build --release` (see `pyproject.toml`). The
+//! blocking [`convert`] releases the GIL while it runs, and both functions
+//! share one Tokio runtime.
+//!
+//! [PyO3]: https://pyo3.rs
+
+use crate::config::{ConversionConfig, FidelityTier, PageSelection};
+use crate::output::ConversionOutput;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+create_exception!(
+    pdf2md,
+    ConversionError,
+    PyException,
+    "Raised when a PDF cannot be converted."
+);
+
+fn to_py_err(e: crate::Pdf2MdError) -> PyErr {
+    ConversionError::new_err(e.to_string())
+}
+
+/// Convert a PDF path or URL to Markdown, blocking until every page is done.
+///
+/// Keyword arguments: `model`, `provider`, `base_url`, `api_key`, `dpi`, `concurrency`,
+/// `pages` (an int, a `(first, last)` tuple, or a list), `password`,
+/// `system_prompt`, `fidelity` (1–3), `temperature`, `seed`, `max_tokens`,
+/// `max_retries`, `maintain_format`, `include_metadata`, `local_only`,
+/// `max_pages`, `max_file_size_bytes`, `api_timeout_secs`.
+#[pyfunction]
+#[pyo3(signature = (path, **config))]
+fn convert(py: Python<'_>, path: String, config: Option<&PyDict>) -> PyResult<PyObject> {
+    let config = config_from_kwargs(config)?;
+    let runtime = pyo3_asyncio::tokio::get_runtime();
+    let output = py
+        .allow_threads(|| runtime.block_on(crate::convert::convert(path, &config)))
+        .map_err(to_py_err)?;
+    output_to_py(py, &output)
+}
+
+/// Awaitable version of [`convert`] for asyncio code.
+#[pyfunction]
+#[pyo3(signature = (path, **config))]
+fn convert_async<'py>(
+    py: Python<'py>,
+    path: String,
+    config: Option<&PyDict>,
+) -> PyResult<&'py PyAny> {
+    let config = config_from_kwargs(config)?;
+    pyo3_asyncio::tokio::future_into_py(py, async move {
+        let output = crate::convert::convert(path, &config)
+            .await
+            .map_err(to_py_err)?;
+        Python::with_gil(|py| output_to_py(py, &output))
+    })
+}
+
+/// Python module definition: `import pdf2md`.
+#[pymodule]
+fn pdf2md(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(convert, m)?)?;
+    m.add_function(wrap_pyfunction!(convert_async, m)?)?;
+    m.add("ConversionError", py.get_type::<ConversionError>())?;
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    Ok(())
+}
+
+/// Map `**config` keyword arguments onto [`ConversionConfig::builder`].
+fn config_from_kwargs(kwargs: Option<&PyDict>) -> PyResult<ConversionConfig> {
+    let mut b = ConversionConfig::builder();
+    for (key, value) in kwargs.into_iter().flat_map(|kw| kw.iter()) {
+        let key: &str = key.extract()?;
+        b = match key {
+            "model" => b.model(value.extract::<String>()?),
+            "provider" => b.provider_name(value.extract::<String>()?),
+            "base_url" => b.base_url(value.extract::<String>()?),
+            "api_key" => b.api_key(value.extract::<String>()?),
+            "dpi" => b.dpi(value.extract()?),
+            "concurrency" => b.concurrency(value.extract()?),
+            "pages" => b.pages(pages_from_py(value)?),
+            "password" => b.password(value.extract::<String>()?),
+            "system_prompt" => b.system_prompt(value.extract::<String>()?),
+            "fidelity" => b.fidelity(fidelity_from_py(value.extract()?)?),
+            "temperature" => b.temperature(value.extract()?),
+            "seed" => b.seed(value.extract()?),
+            "max_tokens" => b.max_tokens(value.extract()?),
+            "max_retries" => b.max_retries(value.extract()?),
+            "maintain_format" => b.maintain_format(value.extract()?),
+            "include_metadata" => b.include_metadata(value.extract()?),
+            "local_only" => b.local_only(value.extract()?),
+            "max_pages" => b.max_pages(value.extract()?),
+            "max_file_size_bytes" => b.max_file_size_bytes(value.extract()?),
+            "api_timeout_secs" => b.api_timeout_secs(value.extract()?),
+            other => {
+                return Err(PyTypeError::new_err(format!(
+                    "convert() got an unexpected keyword argument '{other}'"
+                )))
+            }
+        };
+    }
+    b.build().map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// `3` → one page, `(1, 5)` → a range, `[1, 4, 9]` → a set (all 1-based).
+fn pages_from_py(value: &PyAny) -> PyResult<PageSelection> {
+    if let Ok(page) = value.extract::<usize>() {
+        return Ok(PageSelection::Single(page));
+    }
+    if let Ok((first, last)) = value.extract::<(usize, usize)>() {
+        return Ok(PageSelection::Range(first, last));
+    }
+    if let Ok(pages) = value.extract::<Vec<usize>>() {
+        return Ok(PageSelection::Set(pages));
+    }
+    Err(PyTypeError::new_err(
+        "pages must be an int, a (first, last) tuple, or a list of ints",
+    ))
+}
+
+fn fidelity_from_py(tier: u8) -> PyResult<FidelityTier> {
+    match tier {
+        1 => Ok(FidelityTier::Tier1),
+        2 => Ok(FidelityTier::Tier2),
+        3 => Ok(FidelityTier::Tier3),
+        4 => Ok(FidelityTier::Tier4),
+        _ => Err(PyValueError::new_err("fidelity must be 1, 2, 3, or 4")),
+    }
+}
+
+/// Round-trip through JSON so the dict matches the documented JSON output.
+fn output_to_py(py: Python<'_>, output: &ConversionOutput) -> PyResult<PyObject> {
+    let json = serde_json::to_string(output)
+        .map_err(|e| ConversionError::new_err(format!("failed to serialise output: {e}")))?;
+    Ok(py
+        .import("json")?
+        .call_method1("loads", (json,))?
+        .into_py(py))
+}
@@ -0,0 +1,671 @@
+//! Optional bibliography parsing and citation linking.
+//!
+//! ## Why?
+//!
+//! In converted papers the bibliography is a block of text and `[12]` in the
+//! body is just brackets: readers cannot jump to the source, and indexes
+//! cannot tell which works a paper cites. This pass finds the references
+//! section, parses each entry into a [`Citation`], and links the body to it
+//! with Markdown footnotes:
+//!
+//! ```text
+//! as shown in [2, 4-5].          →  as shown in [^2][^4][^5].
+//! (Smith et al., 2020)           →  (Smith et al., 2020)[^smith2020]
+//! ## References                     ## References
+//! [2] A. Lee, "Title," 2019.     →  [^2]: A. Lee, "Title," 2019.
+//! ```
+//!
+//! The section is the last heading titled References, Bibliography, Works
+//! Cited, or Literature Cited, up to the next heading of the same or a higher
+//! level (or the end of the document). Entries start with `[n]`, `n.`, a list
+//! bullet, or, in unmarked lists, a line after a blank line. Numbered entries
+//! are linked from numeric markers; unnumbered ones from author–year markers,
+//! keyed by the first author's surname and the year.
+//!
+//! Parsing is local and heuristic — no model calls. It reads the common
+//! APA, IEEE, and Vancouver layouts; odd entries keep their
+//! [`Citation::raw`] text with fewer fields filled. Markers whose numbers
+//! have no entry are left alone. The pass is idempotent, so
+//! [`crate::convert_incremental`] can rerun it over reused pages.
+//!
+//! Enabled with [`crate::ConversionConfigBuilder::parse_references`]; results
+//! land in [`crate::ConversionOutput::references`].
+
+use crate::output::PageResult;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tracing::info;
+
+/// One parsed bibliography entry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Citation {
+    /// Footnote label: the entry number (`"12"`, or `"ref12"` when a page
+    /// footnote already uses `12`) or, for unnumbered lists, surname and year
+    /// (`"smith2020"`).
+    pub label: String,
+    /// Author names as written, e.g. `["Smith, J.", "Doe, A."]`.
+    pub authors: Vec<String>,
+    /// Title of the cited work.
+    pub title: Option<String>,
+    /// Publication year.
+    pub year: Option<u16>,
+    /// DOI without a resolver prefix, e.g. `"10.1000/xyz123"`.
+    pub doi: Option<String>,
+    /// 1-based page the entry starts on.
+    pub page: usize,
+    /// The entry text without its marker, on one line.
+    pub raw: String,
+}
+
+const SECTION_TITLES: [&str; 6] = [
+    "references",
+    "bibliography",
+    "works cited",
+    "literature cited",
+    "references and notes",
+    "cited works",
+];
+
+/// Longest numeric range expanded in a marker such as `[3-7]`.
+const MAX_RANGE: u32 = 50;
+
+static FOOTNOTE_DEF_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[\^([\w-]+)\]:\s*(.*)$").unwrap());
+static BRACKET_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\[(\d{1,4})\]\s*(.*)$").unwrap());
+static NUMBER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d{1,4})[.)]\s+(.*)$").unwrap());
+static BULLET_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[-*+]\s+(.*)$").unwrap());
+static DOI_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(10\.\d{4,9}/[^\s\x22<>]+)").unwrap());
+static YEAR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(1[6-9]\d{2}|20\d{2})([a-z])?\b").unwrap());
+static PAREN_YEAR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\((?:1[6-9]\d{2}|20\d{2})[a-z]?\)").unwrap());
+static QUOTED_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"["“]([^"”]{3,})["”]"#).unwrap());
+static NUMERIC_MARKER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\[(\d{1,4}(?:\s*[,–-]\s*\d{1,4})*)\]").unwrap());
+static PAREN_MARKER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\(([^()]*\b\d{4}[a-z]?)\)").unwrap());
+static AUTHOR_YEAR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*(?:e\.g\.,?\s*|see\s+)?(\p{Lu}[\p{L}'’-]+)(?:\s+et al\.?|\s+(?:and|&)\s+\p{Lu}[\p{L}'’-]+)?,?\s+(\d{4}[a-z]?)\s*$",
+    )
+    .unwrap()
+});
+static NARRATIVE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(\p{Lu}[\p{L}'’-]+)(?:\s+et al\.?|\s+(?:and|&)\s+\p{Lu}[\p{L}'’-]+)?\s+\((\d{4}[a-z]?)\)",
+    )
+    .unwrap()
+});
+
+/// An entry as found: page index into the pages slice, first line, and
+/// continuation lines.
+struct Entry {
+    page: usize,
+    line: usize,
+    continuations: Vec<(usize, usize)>,
+    numbered: bool,
+    label: Option<String>,
+    /// First line without its marker.
+    first: String,
+    /// Whole entry, continuations joined with spaces.
+    text: String,
+}
+
+/// Parse the bibliography of `pages` and link citation markers to it.
+///
+/// Rewrites each page's `markdown` in place and returns the parsed entries
+/// in document order; empty when no references section was found.
+pub fn link_references(pages: &mut [PageResult]) -> Vec<Citation> {
+    let mut lines: Vec<Vec<String>> = pages
+        .iter()
+        .map(|p| {
+            if p.error.is_none() {
+                p.markdown.split('\n').map(str::to_string).collect()
+            } else {
+                Vec::new()
+            }
+        })
+        .collect();
+    let fenced = fence_mask(&lines);
+
+    let Some((start, level)) = find_section(&lines, &fenced) else {
+        return Vec::new();
+    };
+    let end = section_end(&lines, &fenced, start, level);
+    let entries = collect_entries(&lines, &fenced, start, end);
+    if entries.is_empty() {
+        return Vec::new();
+    }
+
+    let numbered = entries.iter().all(|e| e.numbered);
+    // Footnotes defined outside the section keep their numbers.
+    let footnotes: HashSet<&str> = lines
+        .iter()
+        .enumerate()
+        .flat_map(|(p, page)| page.iter().enumerate().map(move |(l, line)| (p, l, line)))
+        .filter(|&(p, l, _)| !fenced[p][l] && ((p, l) < start || (p, l) >= end))
+        .filter_map(|(_, _, line)| FOOTNOTE_DEF_RE.captures(line))
+        .map(|c| c.get(1).unwrap().as_str())
+        .collect();
+    let mut citations = Vec::with_capacity(entries.len());
+    let mut taken: HashMap<String, usize> = HashMap::new();
+    for entry in &entries {
+        let mut citation = parse_entry(&entry.text);
+        citation.page = pages[entry.page].page_num;
+        citation.label = match &entry.label {
+            Some(label) if footnotes.contains(label.as_str()) => format!("ref{label}"),
+            Some(label) => label.clone(),
+            None => unique_label(author_year_key(&citation), &mut taken),
+        };
+        citations.push(citation);
+    }
+
+    // Entries become footnote definitions.
+    for (entry, citation) in entries.iter().zip(&citations) {
+        lines[entry.page][entry.line] = format!("[^{}]: {}", citation.label, entry.first);
+        for &(p, l) in &entry.continuations {
+            lines[p][l] = format!("    {}", lines[p][l].trim_start());
+        }
+    }
+
+    // Markers outside the section become footnote references.
+    let labels: HashMap<String, String> = citations
+        .iter()
+        .map(|c| {
+            let key = if numbered {
+                number(&c.label).to_string()
+            } else {
+                author_year_key(c)
+            };
+            (key, c.label.clone())
+        })
+        .collect();
+    for (p, page_lines) in lines.iter_mut().enumerate() {
+        for (l, line) in page_lines.iter_mut().enumerate() {
+            let in_section = (p, l) >= start && (p, l) < end;
+            if fenced[p][l] || in_section {
+                continue;
+            }
+            *line = if numbered {
+                link_numeric(line, &labels)
+            } else {
+                link_author_year(line, &labels)
+            };
+        }
+    }
+
+    for (page, page_lines) in pages.iter_mut().zip(lines) {
+        if page.error.is_none() {
+            page.markdown = page_lines.join("\n");
+        }
+    }
+    info!("References: parsed {} entries", citations.len());
+    citations
+}
+
+/// Per line, whether it is inside (or delimits) a fenced code block.
+fn fence_mask(lines: &[Vec<String>]) -> Vec<Vec<bool>> {
+    lines
+        .iter()
+        .map(|page| {
+            let mut in_fence = false;
+            page.iter()
+                .map(|line| {
+                    let t = line.trim_start();
+                    if t.starts_with("```") || t.starts_with("~~~") {
+                        in_fence = !in_fence;
+                        return true;
+                    }
+                    in_fence
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// ATX heading level and text of `line`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    ((1..=6).contains(&level) && rest.starts_with(' '))
+        .then(|| (level, rest.trim().trim_end_matches('#').trim()))
+}
+
+/// Position and level of the last references heading.
+fn find_section(lines: &[Vec<String>], fenced: &[Vec<bool>]) -> Option<((usize, usize), usize)> {
+    let mut found = None;
+    for (p, page) in lines.iter().enumerate() {
+        for (l, line) in page.iter().enumerate() {
+            if fenced[p][l] {
+                continue;
+            }
+            if let Some((level, text)) = heading(line) {
+                let title = text
+                    .trim_start_matches(|c: char| {
+                        c.is_ascii_digit() || matches!(c, '.' | ' ' | 'I' | 'V' | 'X')
+                    })
+                    .trim_matches('*')
+                    .to_lowercase();
+                if SECTION_TITLES.contains(&title.as_str()) {
+                    found = Some(((p, l), level));
+                }
+            }
+        }
+    }
+    found
+}
+
+/// First position after the section: the next heading of level `<= level`.
+fn section_end(
+    lines: &[Vec<String>],
+    fenced: &[Vec<bool>],
+    start: (usize, usize),
+    level: usize,
+) -> (usize, usize) {
+    for (p, page) in lines.iter().enumerate().skip(start.0) {
+        for (l, line) in page.iter().enumerate() {
+            if (p, l) <= start || fenced[p][l] {
+                continue;
+            }
+            if heading(line).is_some_and(|(lv, _)| lv <= level) {
+                return (p, l);
+            }
+        }
+    }
+    (lines.len(), 0)
+}
+
+fn collect_entries(
+    lines: &[Vec<String>],
+    fenced: &[Vec<bool>],
+    start: (usize, usize),
+    end: (usize, usize),
+) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut after_blank = true;
+    for (p, page) in lines.iter().enumerate().skip(start.0) {
+        for (l, line) in page.iter().enumerate() {
+            if (p, l) <= start || (p, l) >= end {
+                continue;
+            }
+            let t = line.trim();
+            if t.is_empty() || fenced[p][l] || heading(t).is_some() {
+                after_blank = true;
+                continue;
+            }
+            let marked = FOOTNOTE_DEF_RE
+                .captures(t)
+                .map(|c| (true, Some(c[1].to_string()), c[2].to_string()))
+                .or_else(|| {
+                    BRACKET_RE
+                        .captures(t)
+                        .or_else(|| NUMBER_RE.captures(t))
+                        .map(|c| (true, Some(c[1].to_string()), c[2].to_string()))
+                })
+                .or_else(|| {
+                    BULLET_RE
+                        .captures(t)
+                        .map(|c| (false, None, c[1].to_string()))
+                });
+            // An indented line continues the entry above even after a blank.
+            let indented = line.starts_with("    ") || line.starts_with('\t');
+            match (marked, entries.last_mut()) {
+                (Some((numbered, label, text)), _) => {
+                    // `[^smith2020]:` is a label this pass made, not a number.
+                    let numbered = numbered
+                        && label.as_deref().map(number).is_some_and(|n| {
+                            !n.is_empty() && n.chars().all(|c| c.is_ascii_digit())
+                        });
+                    let label = label.filter(|_| numbered);
+                    entries.push(Entry {
+                        page: p,
+                        line: l,
+                        continuations: Vec::new(),
+                        numbered,
+                        label,
+                        first: text.clone(),
+                        text,
+                    });
+                }
+                (None, Some(last)) if !after_blank || indented => {
+                    last.continuations.push((p, l));
+                    last.text.push(' ');
+                    last.text.push_str(t);
+                }
+                (None, _) => entries.push(Entry {
+                    page: p,
+                    line: l,
+                    continuations: Vec::new(),
+                    numbered: false,
+                    label: None,
+                    first: t.to_string(),
+                    text: t.to_string(),
+                }),
+            }
+            after_blank = false;
+        }
+    }
+    entries
+}
+
+/// The entry number in a numbered label: `"ref12"` → `"12"`.
+fn number(label: &str) -> &str {
+    label.strip_prefix("ref").unwrap_or(label)
+}
+
+/// Split one entry into its fields. See the module docs for the layouts read.
+fn parse_entry(text: &str) -> Citation {
+    let raw = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let doi = DOI_RE
+        .captures(&raw)
+        .map(|c| c[1].trim_end_matches(['.', ',', ';', ')', ']']).to_string());
+    let year = YEAR_RE.captures(&raw).and_then(|c| c[1].parse().ok());
+    let quoted = QUOTED_RE.captures(&raw);
+    let paren_year = PAREN_YEAR_RE.find(&raw);
+
+    // IEEE: names before the quoted title. APA: names before "(2020)".
+    // Otherwise (Vancouver): the longest run of names ending in ". ".
+    let authors_end = match (&quoted, paren_year) {
+        (Some(q), _) => q.get(0).unwrap().start(),
+        (None, Some(y)) => y.start(),
+        (None, None) => author_list_end(&raw)
+            .or_else(|| raw.find(". "))
+            .unwrap_or(raw.len()),
+    };
+    let authors_text = raw[..authors_end].trim_end_matches(['(', ' ', ',', ':']);
+    let authors = split_authors(authors_text);
+
+    let title = match quoted {
+        Some(q) => Some(q[1].trim_end_matches([',', '.']).trim().to_string()),
+        None => {
+            let rest = match paren_year {
+                Some(y) if y.start() == authors_end => &raw[y.end()..],
+                _ => &raw[authors_end..],
+            };
+            let rest = rest.trim_start_matches(['.', ',', ':', ' ']);
+            let end = rest.find(". ").or_else(|| rest.find("? ").map(|i| i + 1));
+            let title = rest[..end.unwrap_or(rest.len())]
+                .trim_end_matches('.')
+                .trim();
+            (!title.is_empty()).then(|| title.to_string())
+        }
+    };
+
+    Citation {
+        authors,
+        title,
+        year,
+        doi,
+        raw,
+        ..Citation::default()
+    }
+}
+
+/// End of the longest prefix followed by `". "` that reads as a list of
+/// names, before the period.
+fn author_list_end(raw: &str) -> Option<usize> {
+    let mut best = None;
+    for (i, _) in raw.match_indices(". ") {
+        if !looks_like_names(&raw[..i]) {
+            break;
+        }
+        best = Some(i);
+    }
+    best
+}
+
+fn looks_like_names(text: &str) -> bool {
+    let text = text.replace(" and ", ",").replace(" & ", ",");
+    text.split([',', ';']).all(|part| {
+        let tokens: Vec<&str> = part
+            .split_whitespace()
+            .filter(|t| !matches!(*t, "et" | "al" | "al."))
+            .collect();
+        tokens.len() <= 4
+            && tokens
+                .iter()
+                .all(|t| t.chars().next().is_some_and(|c| c.is_uppercase()))
+    })
+}
+
+fn is_initials(part: &str) -> bool {
+    let part = part.trim();
+    !part.is_empty()
+        && part.split_whitespace().all(|t| {
+            let letters: String = t.chars().filter(|c| c.is_alphabetic()).collect();
+            !letters.is_empty()
+                && letters.chars().all(char::is_uppercase)
+                && (t.ends_with('.') || letters.chars().count() <= 3)
+        })
+}
+
+fn split_authors(text: &str) -> Vec<String> {
+    let text = text.replace(" and ", ", ").replace(" & ", ", ");
+    let parts: Vec<&str> = if text.contains(';') {
+        text.split(';').collect()
+    } else {
+        text.split(',').collect()
+    };
+    let mut authors: Vec<String> = Vec::new();
+    for part in parts.iter().map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        if matches!(part, "et al" | "et al." | "others") {
+            continue;
+        }
+        // "Smith, J." — initials after a comma belong to the surname before.
+        match authors.last_mut() {
+            Some(last) if is_initials(part) && !last.contains(", ") && !is_initials(last) => {
+                last.push_str(", ");
+                last.push_str(part);
+            }
+            _ => authors.push(part.to_string()),
+        }
+    }
+    authors
+}
+
+/// First author's surname, lowercased, plus the year as written in the
+/// entry (`"smith2020a"`).
+fn author_year_key(citation: &Citation) -> String {
+    let surname = citation
+        .authors
+        .first()
+        .map(|a| {
+            let name = match a.split_once(',') {
+                Some((surname, _)) => surname,
+                None => a.split_whitespace().rfind(|t| !is_initials(t)).unwrap_or(a),
+            };
+            name.split_whitespace().last().unwrap_or("").to_string()
+        })
+        .unwrap_or_default();
+    let surname: String = surname
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect();
+    let year = YEAR_RE
+        .captures(&citation.raw)
+        .map(|c| format!("{}{}", &c[1], c.get(2).map_or("", |m| m.as_str())))
+        .unwrap_or_default();
+    format!("{surname}{year}")
+}
+
+/// `key`, or `key-2`, `key-3`… when already used.
+fn unique_label(key: String, taken: &mut HashMap<String, usize>) -> String {
+    let key = if key.is_empty() {
+        "ref".to_string()
+    } else {
+        key
+    };
+    let n = taken.entry(key.clone()).or_insert(0);
+    *n += 1;
+    if *n == 1 {
+        key
+    } else {
+        format!("{key}-{n}")
+    }
+}
+
+/// `[2, 4-5]` → `[^2][^4][^5]`, when every number has an entry.
+fn link_numeric(line: &str, labels: &HashMap<String, String>) -> String {
+    replace_unlinked(line, &NUMERIC_MARKER_RE, |caps| {
+        let mut numbers = Vec::new();
+        for part in caps[1].split(',') {
+            let bounds: Vec<u32> = part
+                .split(['-', '–'])
+                .filter_map(|n| n.trim().parse().ok())
+                .collect();
+            match bounds[..] {
+                [n] => numbers.push(n),
+                [a, b] if a <= b && b - a <= MAX_RANGE => numbers.extend(a..=b),
+                _ => return None,
+            }
+        }
+        numbers
+            .iter()
+            .map(|n| labels.get(&n.to_string()).map(|l| format!("[^{l}]")))
+            .collect::<Option<String>>()
+    })
+}
+
+/// `(Smith et al., 2020; Doe 2019)` and `Smith (2020)` get footnote
+/// references after the parenthesis, when every work has an entry.
+fn link_author_year(line: &str, labels: &HashMap<String, String>) -> String {
+    let lookup = |name: &str, year: &str| {
+        let key: String = name
+            .chars()
+            .filter(|c| c.is_alphabetic())
+            .flat_map(char::to_lowercase)
+            .collect();
+        labels.get(&format!("{key}{year}"))
+    };
+    let line = replace_unlinked(line, &PAREN_MARKER_RE, |caps| {
+        let refs = caps[1]
+            .split(';')
+            .map(|part| {
+                let c = AUTHOR_YEAR_RE.captures(part)?;
+                lookup(&c[1], &c[2]).map(|l| format!("[^{l}]"))
+            })
+            .collect::<Option<String>>()?;
+        Some(format!("{}{refs}", &caps[0]))
+    });
+    replace_unlinked(&line, &NARRATIVE_RE, |caps| {
+        let label = lookup(&caps[1], &caps[2])?;
+        Some(format!("{}[^{label}]", &caps[0]))
+    })
+}
+
+/// Replace matches of `re` that are not already followed by a link or a
+/// footnote reference; `f` returning `None` keeps the match as is.
+fn replace_unlinked(
+    line: &str,
+    re: &Regex,
+    mut f: impl FnMut(&Captures) -> Option<String>,
+) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut last = 0;
+    for caps in re.captures_iter(line) {
+        let m = caps.get(0).unwrap();
+        let after = &line[m.end()..];
+        if after.starts_with('(') || after.starts_with("[^") || after.starts_with(':') {
+            continue;
+        }
+        if let Some(replacement) = f(&caps) {
+            out.push_str(&line[last..m.start()]);
+            out.push_str(&replacement);
+            last = m.end();
+        }
+    }
+    out.push_str(&line[last..]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_bibliography_is_linked() {
+        let mut pages = vec![
+            PageResult::new(1, "# Intro\n\nAs shown in [1] and [2-3], see also [9].\n"),
+            PageResult::new(
+                2,
+                "## References\n\n[1] A. Lee and B. Kim, \"Fast parsing of PDFs,\" in Proc. ICDAR, 2019. doi:10.1109/icdar.2019.42.\n[2] Smith, J., Doe, A. (2020). Deep tables. Nature, 12.\n[3] C. Wu, \"Layouts,\"\n    arXiv, 2021.\n",
+            ),
+        ];
+        let refs = link_references(&mut pages);
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[0].label, "1");
+        assert_eq!(refs[0].authors, vec!["A. Lee", "B. Kim"]);
+        assert_eq!(refs[0].title.as_deref(), Some("Fast parsing of PDFs"));
+        assert_eq!(refs[0].year, Some(2019));
+        assert_eq!(refs[0].doi.as_deref(), Some("10.1109/icdar.2019.42"));
+        assert_eq!(refs[0].page, 2);
+        assert_eq!(refs[1].authors, vec!["Smith, J.", "Doe, A."]);
+        assert_eq!(refs[1].title.as_deref(), Some("Deep tables"));
+        assert_eq!(refs[2].year, Some(2021));
+
+        assert_eq!(
+            pages[0].markdown,
+            "# Intro\n\nAs shown in [^1] and [^2][^3], see also [9].\n"
+        );
+        assert!(pages[1]
+            .markdown
+            .contains("\n[^2]: Smith, J., Doe, A. (2020)."));
+        assert!(pages[1]
+            .markdown
+            .contains("\n[^3]: C. Wu, \"Layouts,\"\n    arXiv, 2021.\n"));
+
+        // Running again changes nothing.
+        let before: Vec<String> = pages.iter().map(|p| p.markdown.clone()).collect();
+        assert_eq!(link_references(&mut pages).len(), 3);
+        let after: Vec<String> = pages.iter().map(|p| p.markdown.clone()).collect();
+        assert_eq!(before, after);
+    }
+
+    #[test]
+    fn author_year_bibliography_is_linked() {
+        let mut pages = vec![PageResult::new(
+            1,
+            "Prior work (Smith et al., 2020; Doe 2019) and Doe (2019) disagree (Roe, 2001).\n\n## Bibliography\n\n- Smith, J., Lee, K. (2020). Parsing. Journal.\n- Doe JA. Another title. Science. 2019;3:1-2.\n\n## Appendix\n\nSee (Smith et al., 2020).\n",
+        )];
+        let refs = link_references(&mut pages);
+        let labels: Vec<&str> = refs.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(labels, vec!["smith2020", "doe2019"]);
+        assert_eq!(refs[1].authors, vec!["Doe JA"]);
+        assert_eq!(refs[1].title.as_deref(), Some("Another title"));
+        assert_eq!(
+            pages[0].markdown,
+            "Prior work (Smith et al., 2020; Doe 2019)[^smith2020][^doe2019] and Doe (2019)[^doe2019] disagree (Roe, 2001).\n\n## Bibliography\n\n[^smith2020]: Smith, J., Lee, K. (2020). Parsing. Journal.\n[^doe2019]: Doe JA. Another title. Science. 2019;3:1-2.\n\n## Appendix\n\nSee (Smith et al., 2020)[^smith2020].\n"
+        );
+    }
+
+    #[test]
+    fn no_section_leaves_pages_alone() {
+        let mut pages = vec![PageResult::new(1, "Text with [1] in it.\n")];
+        assert!(link_references(&mut pages).is_empty());
+        assert_eq!(pages[0].markdown, "Text with [1] in it.\n");
+    }
+
+    #[test]
+    fn numbers_taken_by_footnotes_are_prefixed() {
+        let mut pages = vec![
+            PageResult::new(1, "Prior work[^1] [1].\n\n[^1]: A footnote.\n"),
+            PageResult::new(2, "# References\n\n1. Lee, A. (2019). Parsing.\n"),
+        ];
+        let refs = link_references(&mut pages);
+        assert_eq!(refs[0].label, "ref1");
+        assert_eq!(
+            pages[0].markdown,
+            "Prior work[^1] [^ref1].\n\n[^1]: A footnote.\n"
+        );
+        let again = link_references(&mut pages);
+        assert_eq!(again[0].label, "ref1");
+        assert_eq!(
+            pages[0].markdown,
+            "Prior work[^1] [^ref1].\n\n[^1]: A footnote.\n"
+        );
+    }
+}
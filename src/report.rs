@@ -0,0 +1,373 @@
+//! Side-by-side HTML debug report.
+//!
+//! ## Why a report?
+//!
+//! When a page converts badly the question is always the same: what did the
+//! page look like, what did the model answer, and what did post-processing
+//! change? Answering it means re-rendering the page, digging the raw output
+//! out of `--json --keep-raw`, and diffing by hand. [`render_report`] puts it
+//! all in one self-contained HTML file (images inlined, no external assets):
+//!
+//! * a summary of the run — pages, tokens, cost, and stage timings;
+//! * an index of pages, flagging failed, truncated, retried, and blank ones;
+//! * per page, the rendered image next to the final Markdown, with its
+//!   duration, token counts, retries, rotation, and cost;
+//! * per page, a unified diff from the model's raw answer to the final
+//!   Markdown, covering every post-processing and document pass.
+//!
+//! [`crate::convert_with_debug_report`] converts a PDF and writes the report
+//! in one call.
+
+use crate::diff;
+use crate::error::Pdf2MdError;
+use crate::images::{Thumbnail, ThumbnailFormat};
+use crate::output::{ConversionOutput, LatencyStats, PageResult};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+const STYLE: &str = "\
+body{font-family:system-ui,sans-serif;margin:0 auto;max-width:1600px;padding:1rem 2rem;color:#222}
+table.stats td,table.stats th{padding:.15rem .8rem;text-align:left}
+table.stats td{font-variant-numeric:tabular-nums}
+nav a{display:inline-block;margin:.1rem;padding:.1rem .45rem;border-radius:3px;background:#eef;text-decoration:none;color:#225}
+nav a.failed{background:#fcc}nav a.warn{background:#fe9}nav a.blank{background:#eee;color:#888}
+section{border-top:2px solid #ccc;margin-top:2rem;padding-top:.5rem}
+.meta span{margin-right:1.2rem}.flag{font-weight:bold;color:#a40}.error{color:#b00;font-weight:bold}
+.pair{display:grid;grid-template-columns:minmax(0,1fr) minmax(0,1fr);gap:1rem;align-items:start}
+.pair img{max-width:100%;border:1px solid #bbb}
+pre{background:#f6f6f6;padding:.6rem;overflow:auto;white-space:pre-wrap;word-break:break-word;font-size:.85rem;margin:0}
+.diff .add{background:#dfd;display:block}.diff .del{background:#fdd;display:block}.diff .hunk{color:#77a;display:block}
+";
+
+/// Build the report for `output`. `images` are the rendered pages, matched
+/// to pages by number; pages without one show a placeholder.
+pub fn render_report(output: &ConversionOutput, source: &str, images: &[Thumbnail]) -> String {
+    let images: HashMap<usize, &Thumbnail> = images.iter().map(|t| (t.page_num, t)).collect();
+    let title = format!("pdf2md debug report: {source}");
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(&title),
+        escape(&title)
+    );
+    if let Some(ref reason) = output.fatal_error {
+        let _ = writeln!(
+            html,
+            "<p class=\"error\">Stopped early, output is partial: {}</p>",
+            escape(reason)
+        );
+    }
+    html.push_str(&summary_table(output));
+
+    html.push_str("<nav>\n");
+    for page in &output.pages {
+        let _ = writeln!(
+            html,
+            "<a href=\"#page-{n}\" class=\"{}\" title=\"{}\">{n}</a>",
+            page_class(page),
+            escape(&nav_title(page)),
+            n = page.page_num,
+        );
+    }
+    html.push_str("</nav>\n");
+
+    for page in &output.pages {
+        html.push_str(&page_section(page, images.get(&page.page_num).copied()));
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// A page's PNG as an image for [`render_report`], or `None` when it has no
+/// readable PNG header.
+pub(crate) fn png_image(page_num: usize, bytes: Vec<u8>) -> Option<Thumbnail> {
+    let (width, height) =
+        image::ImageReader::with_format(std::io::Cursor::new(&bytes), image::ImageFormat::Png)
+            .into_dimensions()
+            .ok()?;
+    Some(Thumbnail {
+        page_num,
+        width,
+        height,
+        format: ThumbnailFormat::Png,
+        bytes,
+    })
+}
+
+/// Write [`render_report`]'s HTML to `path`, creating parent directories.
+pub async fn write_report(
+    output: &ConversionOutput,
+    source: &str,
+    images: &[Thumbnail],
+    path: &Path,
+) -> Result<(), Pdf2MdError> {
+    let write_err = |e| Pdf2MdError::OutputWriteFailed {
+        path: path.to_path_buf(),
+        source: e,
+    };
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        tokio::fs::create_dir_all(parent).await.map_err(write_err)?;
+    }
+    tokio::fs::write(path, render_report(output, source, images))
+        .await
+        .map_err(write_err)
+}
+
+fn summary_table(output: &ConversionOutput) -> String {
+    let s = &output.stats;
+    let latency = |l: &LatencyStats| {
+        format!(
+            "min {} / median {} / p95 {} / max {} ms",
+            l.min_ms, l.median_ms, l.p95_ms, l.max_ms
+        )
+    };
+    let rows = [
+        (
+            "Pages",
+            format!(
+                "{} processed, {} failed, {} skipped, of {} in the PDF",
+                s.processed_pages, s.failed_pages, s.skipped_pages, s.total_pages
+            ),
+        ),
+        (
+            "Tokens",
+            format!(
+                "{} in ({} image) / {} out",
+                s.total_input_tokens, s.total_image_tokens, s.total_output_tokens
+            ),
+        ),
+        (
+            "Cost",
+            s.total_cost_usd
+                .map_or_else(|| "unknown".to_string(), |c| format!("${c:.4}")),
+        ),
+        (
+            "Wall time",
+            format!(
+                "{} ms (render {} ms, LLM {} ms, summed over pages)",
+                s.total_duration_ms, s.render_duration_ms, s.llm_duration_ms
+            ),
+        ),
+        ("Render latency", latency(&s.render_latency)),
+        ("Queue wait", latency(&s.queue_wait)),
+        ("LLM latency", latency(&s.llm_latency)),
+    ];
+    let mut html = String::from("<table class=\"stats\">\n");
+    for (label, value) in rows {
+        let _ = writeln!(html, "<tr><th>{label}</th><td>{}</td></tr>", escape(&value));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn page_section(page: &PageResult, image: Option<&Thumbnail>) -> String {
+    let n = page.page_num;
+    let mut html = format!("<section id=\"page-{n}\">\n<h2>Page {n}</h2>\n<p class=\"meta\">");
+    let _ = write!(
+        html,
+        "<span>{} ms</span><span>{} tokens in ({} image) / {} out</span><span>{} retries</span>",
+        page.duration_ms, page.input_tokens, page.image_tokens, page.output_tokens, page.retries
+    );
+    if let Some(cost) = page.cost_usd {
+        let _ = write!(html, "<span>${cost:.4}</span>");
+    }
+    if page.rotation_applied != 0 {
+        let _ = write!(html, "<span>rotated {}°</span>", page.rotation_applied);
+    }
+    for flag in page_flags(page) {
+        let _ = write!(html, "<span class=\"flag\">{}</span>", escape(flag));
+    }
+    html.push_str("</p>\n");
+    if let Some(ref error) = page.error {
+        let _ = writeln!(
+            html,
+            "<p class=\"error\">{}</p>",
+            escape(&error.to_string())
+        );
+    }
+
+    html.push_str("<div class=\"pair\">\n");
+    match image {
+        Some(t) => {
+            let mime = match t.format {
+                ThumbnailFormat::Jpeg { .. } => "image/jpeg",
+                ThumbnailFormat::Png => "image/png",
+            };
+            let _ = writeln!(
+                html,
+                "<img alt=\"Page {n}\" width=\"{}\" height=\"{}\" src=\"data:{mime};base64,{}\">",
+                t.width,
+                t.height,
+                STANDARD.encode(&t.bytes)
+            );
+        }
+        None => html.push_str("<p>(no page image)</p>\n"),
+    }
+    let _ = writeln!(html, "<pre>{}</pre>", escape(&page.markdown));
+    html.push_str("</div>\n");
+
+    if let Some(ref raw) = page.raw_markdown {
+        let unified = diff::unified_text(&format!("page {n}"), raw, &page.markdown);
+        let changed = unified.lines().filter(|l| is_change(l)).count();
+        let _ = write!(
+            html,
+            "<details>\n<summary>Post-processing changed {changed} line(s)</summary>\n"
+        );
+        if unified.is_empty() {
+            html.push_str("<p>The model's answer was kept as is.</p>\n");
+        } else {
+            html.push_str("<pre class=\"diff\">");
+            for line in unified.lines() {
+                let class = if line.starts_with("@@") {
+                    "hunk"
+                } else if !is_change(line) {
+                    ""
+                } else if line.starts_with('+') {
+                    "add"
+                } else {
+                    "del"
+                };
+                if class.is_empty() {
+                    let _ = writeln!(html, "{}", escape(line));
+                } else {
+                    let _ = write!(html, "<span class=\"{class}\">{}</span>", escape(line));
+                }
+            }
+            html.push_str("</pre>\n");
+        }
+        html.push_str("</details>\n");
+    }
+    html.push_str("</section>\n");
+    html
+}
+
+/// An added or removed line of a unified diff (not its file header).
+fn is_change(line: &str) -> bool {
+    (line.starts_with('+') && !line.starts_with("+++"))
+        || (line.starts_with('-') && !line.starts_with("---"))
+}
+
+/// Conditions worth a look: failure, blank skip, truncation.
+fn page_flags(page: &PageResult) -> Vec<&'static str> {
+    let mut flags = Vec::new();
+    if page.error.is_some() {
+        flags.push("failed");
+    }
+    if page.skipped_blank {
+        flags.push("blank, skipped");
+    }
+    if page.truncated {
+        flags.push("truncated");
+    }
+    flags
+}
+
+/// Tooltip of a page's index link: its flags and retries.
+fn nav_title(page: &PageResult) -> String {
+    let mut parts: Vec<String> = page_flags(page).iter().map(|f| f.to_string()).collect();
+    if page.retries > 0 {
+        parts.push(format!("{} retries", page.retries));
+    }
+    parts.join(", ")
+}
+
+fn page_class(page: &PageResult) -> &'static str {
+    if page.error.is_some() {
+        "failed"
+    } else if page.skipped_blank {
+        "blank"
+    } else if page.truncated || page.retries > 0 {
+        "warn"
+    } else {
+        ""
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PageError;
+
+    fn page(n: usize, markdown: &str) -> PageResult {
+        PageResult {
+            input_tokens: 1200,
+            output_tokens: 300,
+            duration_ms: 2500,
+            ..PageResult::new(n, markdown)
+        }
+    }
+
+    fn output(pages: Vec<PageResult>) -> ConversionOutput {
+        ConversionOutput {
+            markdown: String::new(),
+            pages,
+            metadata: Default::default(),
+            stats: Default::default(),
+            summary: None,
+            enrichment: None,
+            pii_findings: Vec::new(),
+            references: Vec::new(),
+            headings: Vec::new(),
+            fatal_error: None,
+            reproducibility: None,
+        }
+    }
+
+    #[test]
+    fn pages_show_image_markdown_and_postprocess_diff() {
+        let mut first = page(1, "# Q3 <Results>\n\nRevenue up.\n");
+        first.raw_markdown = Some("```markdown\n# Q3 <Results>\n\nRevenue up.\n```\n".into());
+        first.retries = 2;
+        let mut second = page(2, "");
+        second.error = Some(PageError::Timeout { page: 2, secs: 60 });
+        let image = Thumbnail {
+            page_num: 1,
+            width: 10,
+            height: 14,
+            format: ThumbnailFormat::Png,
+            bytes: vec![1, 2, 3],
+        };
+
+        let html = render_report(&output(vec![first, second]), "q3 & q4.pdf", &[image]);
+        assert!(html.contains("<title>pdf2md debug report: q3 &amp; q4.pdf</title>"));
+        assert!(html.contains("src=\"data:image/png;base64,AQID\""));
+        assert!(html.contains("<pre># Q3 &lt;Results&gt;\n\nRevenue up.\n</pre>"));
+        assert!(html.contains("<span>2 retries</span>"));
+        assert!(html.contains("<a href=\"#page-1\" class=\"warn\" title=\"2 retries\">1</a>"));
+        assert!(html.contains("Post-processing changed 2 line(s)"));
+        assert!(html.contains("<span class=\"del\">-```markdown</span>"));
+
+        assert!(html.contains("<a href=\"#page-2\" class=\"failed\" title=\"failed\">2</a>"));
+        assert!(html.contains("<p class=\"error\">Page 2: LLM call timed out after 60s</p>"));
+        assert!(html.contains("<p>(no page image)</p>"));
+    }
+
+    #[test]
+    fn pipeline_pngs_keep_their_size() {
+        let png = crate::pipeline::encode::encode_png_bytes(&image::DynamicImage::new_rgb8(3, 2))
+            .unwrap();
+        let image = png_image(4, png.clone()).unwrap();
+        assert_eq!((image.page_num, image.width, image.height), (4, 3, 2));
+        assert_eq!(image.bytes, png);
+        assert!(png_image(4, vec![1, 2, 3]).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+//! A record of the settings that determine a conversion's output.
+//!
+//! Archival pipelines store [`ReproducibilityReport`] next to the Markdown
+//! (it is part of [`crate::ConversionOutput`] and the CLI's JSON output) so
+//! a later run can be checked against it: same library and pdfium builds,
+//! same provider and model, same prompt, same render DPI, same sampling.
+//!
+//! [`crate::ConversionConfigBuilder::seed`] pins sampling as far as the
+//! backend allows: temperature 0, plus the seed for backends that take one.
+//! Hosted models can still change behind a fixed name, so matching reports
+//! mean "same inputs", not "same text" — compare the Markdown too.
+//!
+//! ```rust
+//! use edgequake_pdf2md::{ConversionConfig, ReproducibilityReport};
+//!
+//! let config = ConversionConfig::builder().seed(7).build().unwrap();
+//! let archived = ReproducibilityReport::new(&config, "openai", "gpt-4.1-nano");
+//! let rerun = ReproducibilityReport::new(&config, "openai", "gpt-4.1-mini");
+//! assert_eq!(archived.differences(&rerun), ["model"]);
+//! ```
+
+use crate::config::ConversionConfig;
+use serde::{Deserialize, Serialize};
+
+/// Settings of one conversion run; see the [module docs](self).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReproducibilityReport {
+    /// Version of this crate.
+    pub pdf2md_version: String,
+    /// pdfium build fetched by `pdfium-auto`. A library supplied through
+    /// `PDFIUM_LIB_PATH` may be a different build.
+    pub pdfium_version: String,
+    /// Vision backend that converted the pages.
+    pub provider: String,
+    /// Model that converted the pages. Pages answered by a fallback model
+    /// are not reproducible from this report alone.
+    pub model: String,
+    /// Hash of the page prompt as sent, after template variables are
+    /// filled in: 64-bit FNV-1a, as 16 hex digits.
+    pub prompt_hash: String,
+    /// Render resolution.
+    pub dpi: u32,
+    /// Sampling temperature.
+    pub temperature: f32,
+    /// Output token budget per page.
+    pub max_tokens: usize,
+    /// Sampling seed, if one was set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl ReproducibilityReport {
+    /// The report for a run of `config` on `provider`'s `model`.
+    pub fn new(config: &ConversionConfig, provider: &str, model: &str) -> Self {
+        Self {
+            pdf2md_version: env!("CARGO_PKG_VERSION").to_string(),
+            pdfium_version: pdfium_auto::PDFIUM_VERSION.to_string(),
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt_hash: fnv1a(config.page_prompt_template().text()),
+            dpi: config.dpi,
+            temperature: config.temperature,
+            max_tokens: config.max_tokens,
+            seed: config.seed,
+        }
+    }
+
+    /// Names of the fields that differ from `other`; empty when both runs
+    /// used the same settings.
+    pub fn differences(&self, other: &Self) -> Vec<&'static str> {
+        [
+            (
+                "pdf2md_version",
+                self.pdf2md_version == other.pdf2md_version,
+            ),
+            (
+                "pdfium_version",
+                self.pdfium_version == other.pdfium_version,
+            ),
+            ("provider", self.provider == other.provider),
+            ("model", self.model == other.model),
+            ("prompt_hash", self.prompt_hash == other.prompt_hash),
+            ("dpi", self.dpi == other.dpi),
+            ("temperature", self.temperature == other.temperature),
+            ("max_tokens", self.max_tokens == other.max_tokens),
+            ("seed", self.seed == other.seed),
+        ]
+        .into_iter()
+        .filter(|(_, same)| !same)
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+/// 64-bit FNV-1a of `text`, as 16 hex digits. Stable across runs and builds.
+pub(crate) fn fnv1a(text: &str) -> String {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash = text
+        .bytes()
+        .fold(OFFSET, |h, b| (h ^ b as u64).wrapping_mul(PRIME));
+    format!("{hash:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_tracks_settings_that_change_output() {
+        let config = ConversionConfig::builder().seed(42).build().unwrap();
+        let report = ReproducibilityReport::new(&config, "openai", "gpt-4.1-nano");
+        assert_eq!(report.temperature, 0.0);
+        assert_eq!(report.seed, Some(42));
+        assert_eq!(report.prompt_hash.len(), 16);
+        assert!(report.differences(&report).is_empty());
+
+        let other = ConversionConfig::builder()
+            .dpi(200)
+            .system_prompt("Transcribe the page.")
+            .build()
+            .unwrap();
+        let rerun = ReproducibilityReport::new(&other, "openai", "gpt-4.1-nano");
+        assert_eq!(
+            report.differences(&rerun),
+            ["prompt_hash", "dpi", "temperature", "seed"]
+        );
+        assert_eq!(fnv1a(""), "cbf29ce484222325");
+    }
+}
@@ -0,0 +1,83 @@
+//! Per-conversion provider resolution for multi-tenant hosts.
+//!
+//! ## Why a resolver?
+//!
+//! A server converting documents for many workspaces usually keeps one
+//! provider (and one rate limiter) per tenant. Building a fresh
+//! [`ConversionConfig`] for every call just to swap the provider is
+//! wasteful and easy to get wrong. Instead, share one config that carries a
+//! [`ProviderResolver`] and pass the tenant with each call through
+//! [`crate::convert_with_context`]:
+//!
+//! ```rust,no_run
+//! use async_trait::async_trait;
+//! use edgequake_llm::LLMProvider;
+//! use edgequake_pdf2md::{
+//!     convert_with_context, ConversionConfig, ConversionContext, Pdf2MdError, ProviderResolver,
+//! };
+//! use std::collections::HashMap;
+//! use std::sync::Arc;
+//!
+//! struct TenantPool(HashMap<String, Arc<dyn LLMProvider>>);
+//!
+//! #[async_trait]
+//! impl ProviderResolver for TenantPool {
+//!     async fn resolve(&self, ctx: &ConversionContext) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
+//!         let tenant = ctx.tenant.as_deref().unwrap_or("default");
+//!         self.0.get(tenant).cloned().ok_or_else(|| {
+//!             Pdf2MdError::InvalidConfig(format!("no provider for tenant '{tenant}'"))
+//!         })
+//!     }
+//! }
+//!
+//! # async fn run(pool: TenantPool) -> Result<(), Pdf2MdError> {
+//! let config = ConversionConfig::builder()
+//!     .provider_resolver(Arc::new(pool))
+//!     .build()?;
+//! let ctx = ConversionContext::default().with_tenant("acme");
+//! let output = convert_with_context("report.pdf", &config, ctx).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The resolver runs once per conversion, before any page is rendered. It
+//! ranks just below a pre-built `config.provider` and above every other
+//! provider setting. The returned provider also serves the summary and
+//! enrichment passes.
+
+use crate::error::Pdf2MdError;
+use async_trait::async_trait;
+use edgequake_llm::LLMProvider;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// What a [`ProviderResolver`] knows about the conversion it is serving.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConversionContext {
+    /// The path or URL being converted. Filled in by the library.
+    pub input: String,
+    /// Tenant / workspace identifier chosen by the caller.
+    pub tenant: Option<String>,
+    /// Free-form routing hints (plan tier, region, request id, …).
+    pub labels: BTreeMap<String, String>,
+}
+
+impl ConversionContext {
+    pub fn with_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    pub fn with_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+}
+
+/// Chooses the LLM provider for each conversion.
+///
+/// Errors abort the conversion before any page is processed.
+#[async_trait]
+pub trait ProviderResolver: Send + Sync {
+    async fn resolve(&self, ctx: &ConversionContext) -> Result<Arc<dyn LLMProvider>, Pdf2MdError>;
+}
@@ -0,0 +1,695 @@
+//! Built-in HTTP API (feature `server`, CLI `pdf2md serve`).
+//!
+//! ## Why a built-in server?
+//!
+//! Teams that want the converter as a microservice otherwise write the same
+//! wrapper every time: accept an upload, run [`crate::convert`], report
+//! progress, return JSON. This module is that wrapper, built on axum.
+//!
+//! ## Endpoints
+//!
+//! | Method | Path       | Description |
+//! |--------|------------|-------------|
+//! | GET    | `/health`  | Liveness probe; returns `ok` |
+//! | POST   | `/convert` | Convert an uploaded PDF or a URL |
+//! | GET    | `/metrics` | Prometheus metrics (feature `prometheus`) |
+//!
+//! `POST /convert` takes `multipart/form-data` with either a `file` part (the
+//! PDF bytes) or a `url` field (an `http(s)://` URL), plus an optional `model`
+//! field overriding the server's default. Local paths are never accepted.
+//!
+//! ## Limits
+//!
+//! - **URL input is off by default.** A `url` field makes the server fetch
+//!   whatever the client names, so it is rejected unless
+//!   [`ServerOptions::allow_url_input`] is set (CLI `--allow-url-input`).
+//!   Even then the server fetches the PDF itself and refuses hosts that
+//!   resolve to loopback, private, link-local or other non-public addresses,
+//!   on the first request and on every redirect.
+//! - **At most [`ServerOptions::max_concurrent`] conversions run at once.**
+//!   Requests beyond that get `503 Service Unavailable` straight away rather
+//!   than queueing behind the running ones.
+//! - **A client that goes away cancels its conversion.** For a JSON request
+//!   axum drops the handler; for an event stream the conversion stops as soon
+//!   as the stream is dropped, so no further pages are sent to the model.
+//!
+//! The response is the [`ConversionOutput`] as JSON. If the request sends
+//! `Accept: text/event-stream`, progress streams as Server-Sent Events
+//! instead:
+//!
+//! ```text
+//! event: start        data: {"total_pages": 12}
+//! event: page         data: {"page": 1, "total_pages": 12, "markdown_len": 2048}
+//! event: page_error   data: {"page": 2, "total_pages": 12, "error": "…"}
+//! event: result       data: <ConversionOutput JSON>
+//! event: error        data: {"error": "…"}          (instead of result)
+//! ```
+//!
+//! Every request converts with a clone of the server's base
+//! [`ConversionConfig`] (provider, model, concurrency, …).
+//!
+//! With the `prometheus` feature, one
+//! [`crate::metrics::prometheus::PrometheusMetrics`] counts the pages of every
+//! request since the server started; a `metrics_sink` already on the base
+//! config keeps receiving measurements alongside it.
+
+use crate::config::ConversionConfig;
+use crate::convert::convert_from_bytes;
+use crate::error::Pdf2MdError;
+#[cfg(feature = "prometheus")]
+use crate::metrics::prometheus::{PrometheusMetrics, CONTENT_TYPE as PROMETHEUS_CONTENT_TYPE};
+#[cfg(feature = "prometheus")]
+use crate::metrics::{LlmCallMetrics, MetricsSink, PipelineStage, SharedMetricsSink};
+use crate::output::ConversionOutput;
+#[cfg(feature = "prometheus")]
+use crate::output::PageResult;
+use crate::progress::ConversionProgressCallback;
+use axum::extract::{DefaultBodyLimit, Multipart, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::redirect;
+use serde_json::json;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::StreamExt;
+use tracing::{info, warn};
+
+/// Default upload limit: 100 MiB.
+pub const DEFAULT_MAX_UPLOAD_BYTES: usize = 100 * 1024 * 1024;
+
+/// Default number of conversions that may run at once.
+pub const DEFAULT_MAX_CONCURRENT: usize = 4;
+
+/// Redirects followed when fetching a `url` input.
+const MAX_REDIRECTS: usize = 5;
+
+/// Limits and switches of the HTTP API.
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// Largest accepted upload, and largest PDF fetched for a `url` input.
+    pub max_upload_bytes: usize,
+    /// Conversions that may run at once; further requests get 503.
+    /// Values below 1 are treated as 1.
+    pub max_concurrent: usize,
+    /// Accept the `url` form field. Off by default: it lets any client make
+    /// the server issue requests (to public hosts only, see the module docs).
+    pub allow_url_input: bool,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+            max_concurrent: DEFAULT_MAX_CONCURRENT,
+            allow_url_input: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct AppState {
+    config: Arc<ConversionConfig>,
+    options: Arc<ServerOptions>,
+    /// One permit per running conversion.
+    slots: Arc<Semaphore>,
+}
+
+/// Build the router with `base` as every request's configuration.
+pub fn router(base: ConversionConfig, options: ServerOptions) -> Router {
+    #[cfg(feature = "prometheus")]
+    let (base, routes) = {
+        let mut base = base;
+        let metrics = Arc::new(PrometheusMetrics::new());
+        let sink: SharedMetricsSink = match base.metrics_sink.take() {
+            Some(host) => Arc::new(Tee(metrics.clone(), host)),
+            None => metrics.clone(),
+        };
+        base.metrics_sink = Some(sink);
+        let routes = Router::new().route(
+            "/metrics",
+            get(move || {
+                let body = metrics.render();
+                async move { ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body) }
+            }),
+        );
+        (base, routes)
+    };
+    #[cfg(not(feature = "prometheus"))]
+    let routes = Router::new();
+
+    routes
+        .route("/health", get(|| async { "ok" }))
+        .route("/convert", post(convert_handler))
+        .layer(DefaultBodyLimit::max(options.max_upload_bytes))
+        .with_state(AppState {
+            config: Arc::new(base),
+            slots: Arc::new(Semaphore::new(options.max_concurrent.max(1))),
+            options: Arc::new(options),
+        })
+}
+
+/// Feeds the server's metrics and the host's own sink.
+#[cfg(feature = "prometheus")]
+struct Tee(Arc<PrometheusMetrics>, SharedMetricsSink);
+
+#[cfg(feature = "prometheus")]
+impl MetricsSink for Tee {
+    fn record_stage(&self, stage: PipelineStage, page_num: usize, duration: Duration) {
+        self.0.record_stage(stage, page_num, duration);
+        self.1.record_stage(stage, page_num, duration);
+    }
+
+    fn record_llm_call(&self, call: &LlmCallMetrics<'_>) {
+        self.0.record_llm_call(call);
+        self.1.record_llm_call(call);
+    }
+
+    fn record_page(&self, result: &PageResult) {
+        self.0.record_page(result);
+        self.1.record_page(result);
+    }
+}
+
+/// Serve the API on `addr` until the process is stopped.
+pub async fn serve(
+    addr: SocketAddr,
+    base: ConversionConfig,
+    options: ServerOptions,
+) -> Result<(), Pdf2MdError> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| Pdf2MdError::Internal(format!("cannot bind {addr}: {e}")))?;
+    info!("pdf2md server listening on http://{}", addr);
+    axum::serve(listener, router(base, options))
+        .await
+        .map_err(|e| Pdf2MdError::Internal(format!("server error: {e}")))
+}
+
+// ── Request parsing ──────────────────────────────────────────────────────
+
+enum Source {
+    Bytes(Vec<u8>),
+    Url(String),
+}
+
+struct ConvertRequest {
+    source: Source,
+    model: Option<String>,
+}
+
+async fn read_request(
+    mut multipart: Multipart,
+    allow_url_input: bool,
+) -> Result<ConvertRequest, String> {
+    let mut source = None;
+    let mut model = None;
+    while let Some(field) = multipart.next_field().await.map_err(|e| e.to_string())? {
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "file" => {
+                let bytes = field.bytes().await.map_err(|e| e.to_string())?;
+                source = Some(Source::Bytes(bytes.to_vec()));
+            }
+            "url" => {
+                if !allow_url_input {
+                    return Err(
+                        "url input is disabled on this server; upload the PDF as 'file'".into(),
+                    );
+                }
+                let url = field.text().await.map_err(|e| e.to_string())?;
+                let url = url.trim().to_string();
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    return Err("url must start with http:// or https://".into());
+                }
+                let parsed = reqwest::Url::parse(&url).map_err(|e| format!("invalid url: {e}"))?;
+                check_host(&parsed)?;
+                source = Some(Source::Url(url));
+            }
+            "model" => {
+                let m = field.text().await.map_err(|e| e.to_string())?;
+                model = Some(m.trim().to_string()).filter(|m| !m.is_empty());
+            }
+            other => return Err(format!("unexpected form field '{other}'")),
+        }
+    }
+    let source = source.ok_or("expected a 'file' part or a 'url' field")?;
+    Ok(ConvertRequest { source, model })
+}
+
+// ── Handler ──────────────────────────────────────────────────────────────
+
+async fn convert_handler(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    multipart: Multipart,
+) -> Response {
+    // Held until the conversion ends, including in the event-stream task.
+    let Ok(permit) = state.slots.clone().try_acquire_owned() else {
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "too many conversions in progress; retry later",
+        );
+    };
+    let request = match read_request(multipart, state.options.allow_url_input).await {
+        Ok(r) => r,
+        Err(msg) => return error_response(StatusCode::BAD_REQUEST, &msg),
+    };
+    let max_bytes = state.options.max_upload_bytes;
+    let mut config = (*state.config).clone();
+    if let Some(model) = request.model {
+        config.model = Some(model);
+    }
+
+    let wants_sse = headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/event-stream"));
+    if !wants_sse {
+        // A disconnect drops this future, and the conversion with it.
+        let result = run(request.source, config, max_bytes).await;
+        drop(permit);
+        return match result {
+            Ok(output) => Json(output).into_response(),
+            Err(e) => error_response(status_for(&e), &e.to_string()),
+        };
+    }
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    config.progress_callback = Some(Arc::new(SseProgress(tx.clone())));
+    tokio::spawn(async move {
+        let _permit: OwnedSemaphorePermit = permit;
+        let result = tokio::select! {
+            result = run(request.source, config, max_bytes) => result,
+            // The stream was dropped: stop converting for nobody.
+            _ = tx.closed() => {
+                info!("event-stream client disconnected; conversion cancelled");
+                return;
+            }
+        };
+        let event = match result {
+            Ok(output) => sse_event("result", &output),
+            Err(e) => sse_event("error", &json!({ "error": e.to_string() })),
+        };
+        let _ = tx.send(event);
+    });
+    let stream = UnboundedReceiverStream::new(rx).map(Ok::<_, Infallible>);
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+async fn run(
+    source: Source,
+    config: ConversionConfig,
+    max_bytes: usize,
+) -> Result<ConversionOutput, Pdf2MdError> {
+    let bytes = match source {
+        Source::Bytes(bytes) => bytes,
+        Source::Url(url) => fetch_public(&url, config.download_timeout_secs, max_bytes).await?,
+    };
+    convert_from_bytes(&bytes, &config).await
+}
+
+// ── URL input ────────────────────────────────────────────────────────────
+
+/// Download `url`, refusing non-public hosts on every hop.
+///
+/// The check sits in the DNS resolver, so the address that passed it is the
+/// one connected to; a name that resolves to a private address a second time
+/// cannot slip through. Hosts written as IP literals skip the resolver and
+/// are checked by [`check_host`] instead.
+async fn fetch_public(
+    url: &str,
+    timeout_secs: u64,
+    max_bytes: usize,
+) -> Result<Vec<u8>, Pdf2MdError> {
+    let failed = |reason: String| Pdf2MdError::DownloadFailed {
+        url: url.to_string(),
+        reason,
+    };
+    let parsed = reqwest::Url::parse(url).map_err(|e| failed(e.to_string()))?;
+    check_host(&parsed).map_err(failed)?;
+
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(timeout_secs))
+        .no_proxy()
+        .dns_resolver(Arc::new(PublicOnly))
+        .redirect(redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match check_host(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(reason) => attempt.error(reason),
+            }
+        }))
+        .build()
+        .map_err(|e| failed(e.to_string()))?;
+    let mut response = client.get(parsed).send().await.map_err(|e| {
+        if e.is_timeout() {
+            Pdf2MdError::DownloadTimeout {
+                url: url.to_string(),
+                secs: timeout_secs,
+            }
+        } else {
+            // Show the policy's reason, not just "error following redirect".
+            let mut reason = e.to_string();
+            let mut source = std::error::Error::source(&e);
+            while let Some(inner) = source {
+                reason = format!("{reason}: {inner}");
+                source = inner.source();
+            }
+            failed(reason)
+        }
+    })?;
+    if !response.status().is_success() {
+        return Err(failed(format!("HTTP {}", response.status())));
+    }
+
+    let too_large = || Pdf2MdError::DocumentTooLarge {
+        detail: format!("{url} is larger than the {max_bytes}-byte upload limit"),
+    };
+    if response
+        .content_length()
+        .is_some_and(|n| n > max_bytes as u64)
+    {
+        return Err(too_large());
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(|e| failed(e.to_string()))? {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// Reject URLs whose host is an IP literal outside the public internet.
+fn check_host(url: &reqwest::Url) -> Result<(), String> {
+    let host = url.host_str().ok_or("url has no host")?;
+    // IPv6 literals keep their brackets in `host_str`.
+    match host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        Ok(ip) if !is_public(ip) => Err(format!("{ip} is not a public address")),
+        _ => Ok(()),
+    }
+}
+
+/// Resolves names with the system resolver, keeping public addresses only.
+struct PublicOnly;
+
+impl Resolve for PublicOnly {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{host} does not resolve to a public address").into());
+            }
+            Ok::<Addrs, Box<dyn std::error::Error + Send + Sync>>(Box::new(addrs.into_iter()))
+        })
+    }
+}
+
+/// Whether `ip` is routable on the public internet: not loopback, private,
+/// link-local, carrier-grade NAT, unique-local, multicast or unspecified.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // 100.64.0.0/10, carrier-grade NAT
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // fc00::/7, unique local
+                || (first & 0xfe00) == 0xfc00
+                // fe80::/10, link local
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// HTTP status for a failed conversion.
+fn status_for(e: &Pdf2MdError) -> StatusCode {
+    match e {
+        Pdf2MdError::NotAPdf { .. }
+        | Pdf2MdError::CorruptPdf { .. }
+        | Pdf2MdError::PasswordRequired { .. }
+        | Pdf2MdError::WrongPassword { .. }
+        | Pdf2MdError::PageOutOfRange { .. }
+        | Pdf2MdError::InvalidInput { .. }
+        | Pdf2MdError::InvalidConfig(_)
+        | Pdf2MdError::LocalOnlyViolation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        Pdf2MdError::DocumentTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+        Pdf2MdError::DownloadFailed { .. }
+        | Pdf2MdError::DownloadTimeout { .. }
+        | Pdf2MdError::LlmApiError { .. }
+        | Pdf2MdError::SchemaViolation { .. }
+        | Pdf2MdError::AuthError { .. }
+        | Pdf2MdError::AllPagesFailed { .. }
+        | Pdf2MdError::PartialFailure { .. }
+        | Pdf2MdError::PageFailed(_)
+        | Pdf2MdError::ProviderUnavailable { .. }
+        | Pdf2MdError::ProviderLost { .. } => StatusCode::BAD_GATEWAY,
+        Pdf2MdError::RateLimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+        Pdf2MdError::ApiTimeout { .. } => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    if status.is_server_error() {
+        warn!("convert request failed: {}", message);
+    }
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+fn sse_event(name: &str, data: &impl serde::Serialize) -> Event {
+    Event::default()
+        .event(name)
+        .json_data(data)
+        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+}
+
+// ── Progress → SSE ───────────────────────────────────────────────────────
+
+/// Forwards pipeline progress into the response's event stream.
+struct SseProgress(mpsc::UnboundedSender<Event>);
+
+impl ConversionProgressCallback for SseProgress {
+    fn on_conversion_start(&self, total_pages: usize) {
+        let _ = self
+            .0
+            .send(sse_event("start", &json!({ "total_pages": total_pages })));
+    }
+
+    fn on_page_complete(&self, page_num: usize, total_pages: usize, markdown_len: usize) {
+        let _ = self.0.send(sse_event(
+            "page",
+            &json!({ "page": page_num, "total_pages": total_pages, "markdown_len": markdown_len }),
+        ));
+    }
+
+    fn on_page_error(&self, page_num: usize, total_pages: usize, error: String) {
+        let _ = self.0.send(sse_event(
+            "page_error",
+            &json!({ "page": page_num, "total_pages": total_pages, "error": error }),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_errors_are_client_errors() {
+        let e = Pdf2MdError::NotAPdf {
+            path: "x".into(),
+            magic: *b"GIF8",
+        };
+        assert_eq!(status_for(&e), StatusCode::UNPROCESSABLE_ENTITY);
+        let e = Pdf2MdError::RateLimitExceeded {
+            provider: "openai".into(),
+            retry_after_secs: Some(5),
+        };
+        assert_eq!(status_for(&e), StatusCode::TOO_MANY_REQUESTS);
+        let e = Pdf2MdError::Internal("boom".into());
+        assert_eq!(status_for(&e), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn only_public_addresses_pass() {
+        for ip in ["93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    // ── Router ───────────────────────────────────────────────────────────
+
+    use axum::body::Body;
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    const BOUNDARY: &str = "pdf2md-test-boundary";
+
+    fn convert_request(fields: &[(&str, &[u8])], accept: Option<&str>) -> Request<Body> {
+        let mut body = Vec::new();
+        for (name, value) in fields {
+            body.extend_from_slice(format!("--{BOUNDARY}\r\n").as_bytes());
+            let filename = if *name == "file" {
+                "; filename=\"doc.pdf\""
+            } else {
+                ""
+            };
+            body.extend_from_slice(
+                format!("Content-Disposition: form-data; name=\"{name}\"{filename}\r\n\r\n")
+                    .as_bytes(),
+            );
+            body.extend_from_slice(value);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{BOUNDARY}--\r\n").as_bytes());
+        let mut request = Request::post("/convert").header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={BOUNDARY}"),
+        );
+        if let Some(accept) = accept {
+            request = request.header(header::ACCEPT, accept);
+        }
+        request.body(Body::from(body)).unwrap()
+    }
+
+    async fn send(options: ServerOptions, request: Request<Body>) -> (StatusCode, String) {
+        let response = router(ConversionConfig::default(), options)
+            .oneshot(request)
+            .await
+            .unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8_lossy(&body).into_owned())
+    }
+
+    #[tokio::test]
+    async fn health_answers_ok() {
+        let request = Request::get("/health").body(Body::empty()).unwrap();
+        assert_eq!(
+            send(ServerOptions::default(), request).await,
+            (StatusCode::OK, "ok".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn upload_that_is_not_a_pdf_is_unprocessable() {
+        let request = convert_request(&[("file", b"GIF89a not a pdf")], None);
+        let (status, body) = send(ServerOptions::default(), request).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(body.contains("not a valid PDF"), "{body}");
+    }
+
+    #[tokio::test]
+    async fn malformed_forms_are_bad_requests() {
+        for fields in [
+            &[("model", &b"gpt-4.1"[..])][..],
+            &[("path", &b"/etc/passwd"[..])][..],
+            &[("url", &b"file:///etc/passwd"[..])][..],
+        ] {
+            let options = ServerOptions {
+                allow_url_input: true,
+                ..Default::default()
+            };
+            let (status, _) = send(options, convert_request(fields, None)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn url_input_is_opt_in_and_public_only() {
+        let request = convert_request(&[("url", b"https://example.com/a.pdf")], None);
+        let (status, body) = send(ServerOptions::default(), request).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("url input is disabled"), "{body}");
+
+        let options = ServerOptions {
+            allow_url_input: true,
+            ..Default::default()
+        };
+        for url in [
+            "http://127.0.0.1:8080/a.pdf",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/a.pdf",
+        ] {
+            let request = convert_request(&[("url", url.as_bytes())], None);
+            let (status, body) = send(options.clone(), request).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{url}");
+            assert!(body.contains("not a public address"), "{body}");
+        }
+    }
+
+    #[tokio::test]
+    async fn event_stream_reports_the_failure_as_an_event() {
+        let request = convert_request(&[("file", b"GIF89a not a pdf")], Some("text/event-stream"));
+        let response = router(ConversionConfig::default(), ServerOptions::default())
+            .oneshot(request)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/event-stream"
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("event: error"), "{body}");
+        assert!(!body.contains("event: result"), "{body}");
+    }
+}
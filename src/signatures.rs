@@ -0,0 +1,338 @@
+//! Digital signatures: who signed, when, and how much of the file.
+//!
+//! ## Why report signatures?
+//!
+//! A signed contract converted to Markdown loses the one property that made
+//! it trustworthy. [`crate::DocumentMetadata::signatures`] keeps the facts a
+//! reader needs before relying on the text: whether the PDF is signed, by
+//! whom, and whether each signature still covers the whole file.
+//!
+//! A signature covers the bytes listed in its `/ByteRange`. When that range
+//! ends at the end of the file, nothing was appended after signing; when it
+//! does not, the file carries an incremental update — a later signature, a
+//! form fill, or an edit — that this signature does not vouch for.
+//!
+//! The signer is the common name (`CN`) of the signing certificate in the
+//! embedded CMS blob. Nothing here verifies the cryptography or the
+//! certificate chain; use a PDF validator for that.
+
+use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::warn;
+
+/// One digital signature of the PDF, as found by [`crate::inspect`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SignatureInfo {
+    /// Common name of the signing certificate, when it can be read.
+    pub signer: Option<String>,
+    /// Reason given by the signer (`/Reason`).
+    pub reason: Option<String>,
+    /// Signing time as stored in the PDF (`/M`), e.g. `D:20240315093000+01'00'`.
+    pub signing_time: Option<String>,
+    /// Whether the signed byte range runs to the end of the file, i.e.
+    /// nothing was changed or appended after this signature.
+    pub covers_whole_document: bool,
+    /// DocMDP permission level (1–3) of a certification signature: 1 allows
+    /// no changes, 2 form filling and signing, 3 also annotations. None for
+    /// an ordinary approval signature.
+    pub certification_level: Option<u8>,
+}
+
+/// The signatures of `document`, read from `pdf_path`.
+pub(crate) fn read(document: &PdfDocument, pdf_path: &Path) -> Vec<SignatureInfo> {
+    let signatures = document.signatures();
+    if signatures.is_empty() {
+        return Vec::new();
+    }
+    let file = std::fs::read(pdf_path).unwrap_or_else(|e| {
+        warn!(
+            "Could not read {} for signature ranges: {}",
+            pdf_path.display(),
+            e
+        );
+        Vec::new()
+    });
+    let ranges = byte_ranges(&file);
+
+    signatures
+        .iter()
+        .map(|signature| {
+            let contents = signature.bytes();
+            let covers_whole_document = ranges
+                .iter()
+                .find(|r| signed_contents(&file, r).is_some_and(|c| same_contents(&c, &contents)))
+                .is_some_and(|r| covers_file(r, &file));
+            SignatureInfo {
+                signer: signer_name(&contents),
+                reason: signature.reason().filter(|r| !r.is_empty()),
+                signing_time: signature.signing_date().filter(|t| !t.is_empty()),
+                covers_whole_document,
+                certification_level: signature.modification_detection_permission().ok().map(|p| {
+                    match p {
+                        PdfSignatureModificationDetectionPermission::Mdp1 => 1,
+                        PdfSignatureModificationDetectionPermission::Mdp2 => 2,
+                        PdfSignatureModificationDetectionPermission::Mdp3 => 3,
+                    }
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Every `/ByteRange [a b c d]` array in the raw file.
+fn byte_ranges(file: &[u8]) -> Vec<[usize; 4]> {
+    const KEY: &[u8] = b"/ByteRange";
+    let mut ranges = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = file[pos..].windows(KEY.len()).position(|w| w == KEY) {
+        pos += found + KEY.len();
+        let rest = &file[pos..file.len().min(pos + 128)];
+        let Some(open) = rest.iter().position(|&b| b == b'[') else {
+            continue;
+        };
+        let Some(close) = rest.iter().position(|&b| b == b']') else {
+            continue;
+        };
+        if close < open || !rest[..open].iter().all(u8::is_ascii_whitespace) {
+            continue;
+        }
+        let numbers: Vec<usize> = String::from_utf8_lossy(&rest[open + 1..close])
+            .split_ascii_whitespace()
+            .filter_map(|n| n.parse().ok())
+            .collect();
+        if let Ok(range) = <[usize; 4]>::try_from(numbers) {
+            ranges.push(range);
+        }
+    }
+    ranges
+}
+
+/// The `/Contents` bytes in the gap a `/ByteRange` leaves out: the hex
+/// string `<…>` between its two ranges, decoded.
+fn signed_contents(file: &[u8], range: &[usize; 4]) -> Option<Vec<u8>> {
+    let gap = file.get(range[0] + range[1]..range[2])?;
+    let hex: Vec<u8> = gap
+        .iter()
+        .copied()
+        .filter(|b| b.is_ascii_hexdigit())
+        .collect();
+    hex.chunks_exact(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+/// Equal up to the zero padding reserved for the signature.
+fn same_contents(a: &[u8], b: &[u8]) -> bool {
+    let trim = |s: &[u8]| s.len() - s.iter().rev().take_while(|&&b| b == 0).count();
+    trim(a) > 0 && a[..trim(a)] == b[..trim(b)]
+}
+
+/// Whether `range` starts at the first byte and ends at the last, allowing
+/// for trailing whitespace.
+fn covers_file(range: &[usize; 4], file: &[u8]) -> bool {
+    let end = range[2] + range[3];
+    range[0] == 0 && end <= file.len() && file[end..].iter().all(u8::is_ascii_whitespace)
+}
+
+/// A DER element: tag, contents, and the bytes after it.
+fn der(buf: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = buf.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let n = (first & 0x7f) as usize;
+        if n == 0 || n > 4 || rest.len() < n {
+            return None; // indefinite or oversized length
+        }
+        let len = rest[..n]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, &rest[n..])
+    };
+    (rest.len() >= len).then(|| (tag, &rest[..len], &rest[len..]))
+}
+
+/// The elements inside a constructed DER value.
+fn der_children(mut buf: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut children = Vec::new();
+    while let Some((tag, contents, rest)) = der(buf) {
+        children.push((tag, contents));
+        buf = rest;
+    }
+    children
+}
+
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const INTEGER: u8 = 0x02;
+/// 1.2.840.113549.1.7.2, PKCS #7 signedData.
+const SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+/// 2.5.4.3, commonName.
+const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+
+/// Common name of the certificate that made a CMS signature.
+///
+/// The signer is matched to its certificate by serial number; a signer
+/// identified by key identifier falls back to the first certificate, which
+/// signing tools put first.
+fn signer_name(cms: &[u8]) -> Option<String> {
+    let (SEQUENCE, content_info, _) = der(cms)? else {
+        return None;
+    };
+    let (0x06, oid, rest) = der(content_info)? else {
+        return None;
+    };
+    if oid != SIGNED_DATA {
+        return None;
+    }
+    let (0xa0, explicit, _) = der(rest)? else {
+        return None;
+    };
+    let (SEQUENCE, signed_data, _) = der(explicit)? else {
+        return None;
+    };
+    let parts = der_children(signed_data);
+    let certificates: Vec<&[u8]> = parts
+        .iter()
+        .find(|(tag, _)| *tag == 0xa0)
+        .map(|(_, certs)| {
+            der_children(certs)
+                .into_iter()
+                .filter(|(tag, _)| *tag == SEQUENCE)
+                .map(|(_, cert)| cert)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let signer_serial = parts
+        .iter()
+        .rev()
+        .find(|(tag, _)| *tag == SET)
+        .and_then(|(_, infos)| der_children(infos).into_iter().next())
+        .and_then(|(_, info)| der_children(info).get(1).copied())
+        .filter(|(tag, _)| *tag == SEQUENCE)
+        .and_then(|(_, sid)| der_children(sid).get(1).copied())
+        .map(|(_, serial)| serial);
+
+    let signer = certificates
+        .iter()
+        .filter_map(|cert| tbs_fields(cert))
+        .find(|fields| {
+            signer_serial.is_some_and(|serial| fields.first() == Some(&(INTEGER, serial)))
+        })
+        .or_else(|| certificates.first().and_then(|cert| tbs_fields(cert)))?;
+    // serialNumber, signature, issuer, validity, subject
+    let (_, subject) = *signer.get(4)?;
+    common_name(subject)
+}
+
+/// The fields of a certificate's `TBSCertificate`, without the version.
+fn tbs_fields(cert: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let (_, tbs, _) = der(cert)?;
+    let mut fields = der_children(tbs);
+    if fields.first().is_some_and(|(tag, _)| *tag == 0xa0) {
+        fields.remove(0); // explicit version
+    }
+    Some(fields)
+}
+
+/// The `CN` of an X.501 name.
+fn common_name(name: &[u8]) -> Option<String> {
+    der_children(name)
+        .into_iter()
+        .flat_map(|(_, rdn)| der_children(rdn))
+        .find_map(|(_, attribute)| {
+            let (0x06, oid, rest) = der(attribute)? else {
+                return None;
+            };
+            if oid != COMMON_NAME {
+                return None;
+            }
+            let (tag, value, _) = der(rest)?;
+            Some(match tag {
+                // BMPString
+                0x1e => String::from_utf16_lossy(
+                    &value
+                        .chunks_exact(2)
+                        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                        .collect::<Vec<_>>(),
+                ),
+                _ => String::from_utf8_lossy(value).into_owned(),
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut out = match contents.len() {
+            n @ 0..=0x7f => vec![tag, n as u8],
+            n => vec![tag, 0x82, (n >> 8) as u8, n as u8],
+        };
+        out.extend_from_slice(contents);
+        out
+    }
+
+    fn name(cn: &str) -> Vec<u8> {
+        let attribute = [tlv(0x06, COMMON_NAME), tlv(0x0c, cn.as_bytes())].concat();
+        tlv(SEQUENCE, &tlv(SET, &tlv(SEQUENCE, &attribute)))
+    }
+
+    fn certificate(serial: u8, cn: &str) -> Vec<u8> {
+        let tbs = [
+            tlv(0xa0, &tlv(INTEGER, &[2])),
+            tlv(INTEGER, &[serial]),
+            tlv(SEQUENCE, &[]),
+            name("Test CA"),
+            tlv(SEQUENCE, &[]),
+            name(cn),
+        ]
+        .concat();
+        tlv(SEQUENCE, &tlv(SEQUENCE, &tbs))
+    }
+
+    #[test]
+    fn signer_and_coverage_are_read_from_the_file() {
+        // The signer (serial 7) comes second in the certificate list.
+        let sid = tlv(SEQUENCE, &[name("Test CA"), tlv(INTEGER, &[7])].concat());
+        let signer_info = tlv(SEQUENCE, &[tlv(INTEGER, &[1]), sid].concat());
+        let certs = [certificate(1, "Test CA"), certificate(7, "Jane Notary")].concat();
+        let signed_data = tlv(
+            SEQUENCE,
+            &[
+                tlv(INTEGER, &[1]),
+                tlv(SET, &[]),
+                tlv(SEQUENCE, &[]),
+                tlv(0xa0, &certs),
+                tlv(SET, &signer_info),
+            ]
+            .concat(),
+        );
+        let cms = tlv(
+            SEQUENCE,
+            &[tlv(0x06, SIGNED_DATA), tlv(0xa0, &signed_data)].concat(),
+        );
+        assert_eq!(signer_name(&cms).as_deref(), Some("Jane Notary"));
+        assert_eq!(signer_name(b"not a signature"), None);
+
+        let hex: String = cms.iter().map(|b| format!("{b:02X}")).collect();
+        let gap = format!("<{hex}0000>");
+        let tail = ">>\n%%EOF\n";
+        let head = |b: usize, c: usize, d: usize| {
+            format!("%PDF-1.7\n<</Type/Sig/ByteRange [0 {b:06} {c:06} {d:06}]/Contents ")
+        };
+        let b = head(0, 0, 0).len();
+        let file = head(b, b + gap.len(), tail.len()) + &gap + tail;
+        let ranges = byte_ranges(file.as_bytes());
+        assert_eq!(ranges.len(), 1);
+        let contents = signed_contents(file.as_bytes(), &ranges[0]).unwrap();
+        assert!(same_contents(&contents, &cms));
+        assert!(covers_file(&ranges[0], file.as_bytes()));
+        let appended = format!("{file}1 0 obj\n<<>>\nendobj\n");
+        assert!(!covers_file(&ranges[0], appended.as_bytes()));
+    }
+}
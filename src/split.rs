@@ -0,0 +1,219 @@
+//! PDF splitting and merging, before conversion.
+//!
+//! A 900-page manual converts more reliably, and resumes more cheaply, as a
+//! dozen chapter-sized files; a scan delivered as `part1.pdf`, `part2.pdf`
+//! reads best as one document, so `maintain_format` carries context across
+//! the joins. [`split_pdf`] and [`merge_pdfs`] do both with pdfium, without
+//! rendering anything or calling a model.
+//!
+//! ```rust,no_run
+//! use edgequake_pdf2md::split::{chunk_ranges, split_pdf};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let ranges = chunk_ranges(950, 100); // (1, 100), (101, 200), …, (901, 950)
+//! let parts = split_pdf("manual.pdf", &ranges, "parts", None).await?;
+//! assert_eq!(parts.len(), 10);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Pdf2MdError;
+use crate::pipeline::render;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Write each of `ranges` (1-based, inclusive) of `input` to its own PDF in
+/// `output_dir`, created if missing, and return the paths in order.
+///
+/// Files are named `<stem>-<start>-<end>.pdf`, zero-padded to the document's
+/// page count so they sort in page order. Ranges may overlap.
+///
+/// # Errors
+/// [`Pdf2MdError::InvalidConfig`] for an empty list or a range that starts
+/// at 0 or ends before it starts, [`Pdf2MdError::PageOutOfRange`] for one
+/// past the last page, open errors as for [`crate::inspect`], and
+/// [`Pdf2MdError::OutputWriteFailed`] when a part cannot be written.
+pub async fn split_pdf(
+    input: impl AsRef<Path>,
+    ranges: &[(usize, usize)],
+    output_dir: impl AsRef<Path>,
+    password: Option<&str>,
+) -> Result<Vec<PathBuf>, Pdf2MdError> {
+    render::ensure_pdfium().await?;
+    let input = input.as_ref().to_path_buf();
+    let output_dir = output_dir.as_ref().to_path_buf();
+    let ranges = ranges.to_vec();
+    let pwd = password.map(|s| s.to_string());
+
+    tokio::task::spawn_blocking(move || {
+        split_blocking(&input, &ranges, &output_dir, pwd.as_deref())
+    })
+    .await
+    .map_err(|e| Pdf2MdError::Internal(format!("Split task panicked: {}", e)))?
+}
+
+/// Concatenate `inputs`, in order, into one PDF at `output` and return its
+/// path. Encrypted inputs must be decrypted first.
+pub async fn merge_pdfs(
+    inputs: &[PathBuf],
+    output: impl AsRef<Path>,
+) -> Result<PathBuf, Pdf2MdError> {
+    if inputs.is_empty() {
+        return Err(Pdf2MdError::InvalidConfig("Nothing to merge".into()));
+    }
+    render::ensure_pdfium().await?;
+    let inputs = inputs.to_vec();
+    let output = output.as_ref().to_path_buf();
+
+    tokio::task::spawn_blocking(move || merge_blocking(&inputs, &output))
+        .await
+        .map_err(|e| Pdf2MdError::Internal(format!("Merge task panicked: {}", e)))?
+}
+
+/// `page_count` pages in consecutive ranges of `pages_per_part` (the last
+/// may be shorter).
+pub fn chunk_ranges(page_count: usize, pages_per_part: usize) -> Vec<(usize, usize)> {
+    let step = pages_per_part.max(1);
+    (1..=page_count)
+        .step_by(step)
+        .map(|start| (start, (start + step - 1).min(page_count)))
+        .collect()
+}
+
+/// Ranges running from each section start (0-based, as returned by
+/// [`render::detect_section_starts`]) to the page before the next. Pages
+/// before the first start, such as a title page and contents, are a range
+/// of their own.
+pub fn section_ranges(starts: &[usize], page_count: usize) -> Vec<(usize, usize)> {
+    let mut bounds: Vec<usize> = starts
+        .iter()
+        .map(|s| s + 1)
+        .filter(|&p| p > 1 && p <= page_count)
+        .collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+    bounds.insert(0, 1);
+    bounds
+        .iter()
+        .zip(bounds.iter().skip(1).map(|b| b - 1).chain([page_count]))
+        .map(|(&start, end)| (start, end))
+        .filter(|&(start, end)| start <= end)
+        .collect()
+}
+
+/// Check `ranges` against a document of `total` pages.
+fn check_ranges(ranges: &[(usize, usize)], total: usize) -> Result<(), Pdf2MdError> {
+    if ranges.is_empty() {
+        return Err(Pdf2MdError::InvalidConfig("No page ranges to split".into()));
+    }
+    for &(start, end) in ranges {
+        if start == 0 || end < start {
+            return Err(Pdf2MdError::InvalidConfig(format!(
+                "Invalid page range {start}-{end}"
+            )));
+        }
+        if end > total {
+            return Err(Pdf2MdError::PageOutOfRange { page: end, total });
+        }
+    }
+    Ok(())
+}
+
+/// A pdfium save failure as an output write error.
+fn save_error(path: &Path, e: impl std::fmt::Debug) -> Pdf2MdError {
+    Pdf2MdError::OutputWriteFailed {
+        path: path.to_path_buf(),
+        source: std::io::Error::other(format!("{e:?}")),
+    }
+}
+
+/// Blocking implementation of [`split_pdf`].
+fn split_blocking(
+    input: &Path,
+    ranges: &[(usize, usize)],
+    output_dir: &Path,
+    password: Option<&str>,
+) -> Result<Vec<PathBuf>, Pdf2MdError> {
+    let pdfium = render::get_pdfium()?;
+    let source = pdfium
+        .load_pdf_from_file(input, password)
+        .map_err(|e| render::map_pdf_open_error(e, input, password.is_some()))?;
+    let total = source.pages().len() as usize;
+    check_ranges(ranges, total)?;
+    std::fs::create_dir_all(output_dir).map_err(|source| Pdf2MdError::OutputWriteFailed {
+        path: output_dir.to_path_buf(),
+        source,
+    })?;
+
+    let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("part");
+    let width = total.to_string().len();
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            let path = output_dir.join(format!("{stem}-{start:0width$}-{end:0width$}.pdf"));
+            let mut part = pdfium
+                .create_new_pdf()
+                .map_err(|e| Pdf2MdError::Internal(format!("Could not create a PDF: {e:?}")))?;
+            part.pages_mut()
+                .copy_page_range_from_document(&source, (start - 1) as u16..=(end - 1) as u16, 0)
+                .map_err(|e| save_error(&path, e))?;
+            part.save_to_file(&path).map_err(|e| save_error(&path, e))?;
+            debug!("Wrote pages {}-{} to {}", start, end, path.display());
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Blocking implementation of [`merge_pdfs`].
+fn merge_blocking(inputs: &[PathBuf], output: &Path) -> Result<PathBuf, Pdf2MdError> {
+    let pdfium = render::get_pdfium()?;
+    let mut merged = pdfium
+        .create_new_pdf()
+        .map_err(|e| Pdf2MdError::Internal(format!("Could not create a PDF: {e:?}")))?;
+    for input in inputs {
+        let source = pdfium
+            .load_pdf_from_file(input, None)
+            .map_err(|e| render::map_pdf_open_error(e, input, false))?;
+        merged
+            .pages_mut()
+            .append(&source)
+            .map_err(|e| save_error(output, e))?;
+        debug!(
+            "Appended {} pages from {}",
+            source.pages().len(),
+            input.display()
+        );
+    }
+    merged
+        .save_to_file(output)
+        .map_err(|e| save_error(output, e))?;
+    Ok(output.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_cover_the_document() {
+        assert_eq!(chunk_ranges(250, 100), [(1, 100), (101, 200), (201, 250)]);
+        assert_eq!(chunk_ranges(0, 100), []);
+        // Sections at pages 3 and 10 (0-based 2 and 9); front matter first.
+        assert_eq!(section_ranges(&[2, 9], 12), [(1, 2), (3, 9), (10, 12)]);
+        assert_eq!(section_ranges(&[0, 5], 8), [(1, 5), (6, 8)]);
+        assert_eq!(section_ranges(&[], 4), [(1, 4)]);
+
+        assert!(check_ranges(&[(1, 4), (3, 12)], 12).is_ok());
+        assert!(matches!(
+            check_ranges(&[(5, 13)], 12),
+            Err(Pdf2MdError::PageOutOfRange {
+                page: 13,
+                total: 12
+            })
+        ));
+        assert!(check_ranges(&[(0, 3)], 12).is_err());
+        assert!(check_ranges(&[(4, 3)], 12).is_err());
+        assert!(check_ranges(&[], 12).is_err());
+    }
+}
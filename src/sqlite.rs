@@ -0,0 +1,302 @@
+//! Conversion straight into a SQLite database (feature `sqlite`).
+//!
+//! ## Why a database sink?
+//!
+//! Local search over a corpus of converted PDFs needs the pages, their
+//! metadata and some form of index side by side. Writing `.md` files leaves
+//! every caller to invent that layout. [`convert_to_sqlite`] converts one
+//! document and appends it to a database with a fixed schema:
+//!
+//! ```text
+//! documents(id, source, converted_at_ms, metadata JSON, stats JSON, markdown)
+//! pages(document_id, page_num, markdown, input_tokens, output_tokens, error)
+//! chunks(id, document_id, page_num, chunk_index, text, embedding, embedding_model)
+//! ```
+//!
+//! Chunks are page Markdown split at paragraph breaks into pieces of at most
+//! [`CHUNK_CHARS`] characters, so a search hit always points at one page.
+//! With [`crate::ConversionConfig::embedding_provider`] set, every chunk gets
+//! an `embedding` BLOB of little-endian `f32` values; otherwise the column is
+//! NULL and the table serves plain full-text use. Each call writes one
+//! document in a single transaction; calling it again for the same source
+//! adds a new document row rather than replacing the old one.
+
+use crate::config::ConversionConfig;
+use crate::convert::convert;
+use crate::error::Pdf2MdError;
+use crate::output::{ConversionOutput, ConversionStats};
+use rusqlite::{params, Connection};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, warn};
+
+/// Upper bound on the length of one chunk, in characters. Paragraphs longer
+/// than this are split on character boundaries.
+pub const CHUNK_CHARS: usize = 1500;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS documents (
+    id              INTEGER PRIMARY KEY,
+    source          TEXT NOT NULL,
+    converted_at_ms INTEGER NOT NULL,
+    metadata        TEXT NOT NULL,
+    stats           TEXT NOT NULL,
+    markdown        TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS pages (
+    document_id   INTEGER NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_num      INTEGER NOT NULL,
+    markdown      TEXT NOT NULL,
+    input_tokens  INTEGER NOT NULL,
+    output_tokens INTEGER NOT NULL,
+    error         TEXT,
+    PRIMARY KEY (document_id, page_num)
+);
+CREATE TABLE IF NOT EXISTS chunks (
+    id              INTEGER PRIMARY KEY,
+    document_id     INTEGER NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+    page_num        INTEGER NOT NULL,
+    chunk_index     INTEGER NOT NULL,
+    text            TEXT NOT NULL,
+    embedding       BLOB,
+    embedding_model TEXT
+);
+CREATE INDEX IF NOT EXISTS chunks_document ON chunks(document_id, page_num);
+";
+
+/// One piece of a page, ready to store.
+struct Chunk {
+    page_num: usize,
+    index: usize,
+    text: String,
+    embedding: Option<Vec<f32>>,
+}
+
+fn db_error(e: impl std::fmt::Display) -> Pdf2MdError {
+    Pdf2MdError::Internal(format!("sqlite sink: {e}"))
+}
+
+/// Convert `input` and store the result in the database at `db_path`.
+///
+/// The database and its tables are created if missing. Pages that failed
+/// are stored with their error and no chunks. Returns the run's stats; the
+/// new document's row id is the largest `documents.id`.
+pub async fn convert_to_sqlite(
+    input: impl AsRef<str>,
+    db_path: impl AsRef<Path>,
+    config: &ConversionConfig,
+) -> Result<ConversionStats, Pdf2MdError> {
+    let source = input.as_ref().to_string();
+    let output = convert(&source, config).await?;
+
+    let mut chunks: Vec<Chunk> = output
+        .pages
+        .iter()
+        .filter(|p| p.error.is_none())
+        .flat_map(|p| {
+            chunk_markdown(&p.markdown, CHUNK_CHARS)
+                .into_iter()
+                .enumerate()
+                .map(move |(index, text)| Chunk {
+                    page_num: p.page_num,
+                    index,
+                    text,
+                    embedding: None,
+                })
+        })
+        .collect();
+
+    let mut embedding_model = None;
+    if let Some(ref provider) = config.embedding_provider {
+        if !chunks.is_empty() {
+            let texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+            match provider.embed(&texts).await {
+                Ok(vectors) if vectors.len() == chunks.len() => {
+                    for (chunk, vector) in chunks.iter_mut().zip(vectors) {
+                        chunk.embedding = Some(vector);
+                    }
+                    embedding_model = Some(provider.model().to_string());
+                }
+                Ok(vectors) => warn!(
+                    "Embedding provider returned {} vectors for {} chunks; storing chunks without embeddings",
+                    vectors.len(),
+                    chunks.len()
+                ),
+                Err(e) => warn!(
+                    "Embedding failed, storing chunks without embeddings: {}",
+                    e
+                ),
+            }
+        }
+    }
+
+    let stats = output.stats.clone();
+    let db_path: PathBuf = db_path.as_ref().to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        write_document(
+            &db_path,
+            &source,
+            &output,
+            &chunks,
+            embedding_model.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| Pdf2MdError::Internal(format!("sqlite sink task failed: {e}")))??;
+    Ok(stats)
+}
+
+fn write_document(
+    db_path: &Path,
+    source: &str,
+    output: &ConversionOutput,
+    chunks: &[Chunk],
+    embedding_model: Option<&str>,
+) -> Result<(), Pdf2MdError> {
+    let mut conn = Connection::open(db_path).map_err(db_error)?;
+    conn.execute_batch(SCHEMA).map_err(db_error)?;
+
+    let metadata = serde_json::to_string(&output.metadata).map_err(db_error)?;
+    let stats = serde_json::to_string(&output.stats).map_err(db_error)?;
+    let converted_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+
+    let tx = conn.transaction().map_err(db_error)?;
+    tx.execute(
+        "INSERT INTO documents (source, converted_at_ms, metadata, stats, markdown)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![source, converted_at_ms, metadata, stats, output.markdown],
+    )
+    .map_err(db_error)?;
+    let document_id = tx.last_insert_rowid();
+
+    {
+        let mut insert_page = tx
+            .prepare(
+                "INSERT INTO pages (document_id, page_num, markdown, input_tokens, output_tokens, error)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(db_error)?;
+        for page in &output.pages {
+            insert_page
+                .execute(params![
+                    document_id,
+                    page.page_num as i64,
+                    page.markdown,
+                    page.input_tokens as i64,
+                    page.output_tokens as i64,
+                    page.error.as_ref().map(|e| e.to_string()),
+                ])
+                .map_err(db_error)?;
+        }
+
+        let mut insert_chunk = tx
+            .prepare(
+                "INSERT INTO chunks (document_id, page_num, chunk_index, text, embedding, embedding_model)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )
+            .map_err(db_error)?;
+        for chunk in chunks {
+            let blob = chunk.embedding.as_deref().map(embedding_to_blob);
+            insert_chunk
+                .execute(params![
+                    document_id,
+                    chunk.page_num as i64,
+                    chunk.index as i64,
+                    chunk.text,
+                    blob,
+                    chunk.embedding.as_ref().and(embedding_model),
+                ])
+                .map_err(db_error)?;
+        }
+    }
+    tx.commit().map_err(db_error)?;
+
+    debug!(
+        "Stored '{}' as document {} ({} pages, {} chunks) in {}",
+        source,
+        document_id,
+        output.pages.len(),
+        chunks.len(),
+        db_path.display()
+    );
+    Ok(())
+}
+
+/// Split `markdown` at blank lines into chunks of at most `max_chars`
+/// characters, packing consecutive paragraphs together while they fit.
+pub fn chunk_markdown(markdown: &str, max_chars: usize) -> Vec<String> {
+    let max_chars = max_chars.max(1);
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_chars = 0;
+
+    for paragraph in markdown
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        let chars = paragraph.chars().count();
+        if current_chars > 0 && current_chars + 2 + chars > max_chars {
+            chunks.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        if chars > max_chars {
+            let all: Vec<char> = paragraph.chars().collect();
+            for piece in all.chunks(max_chars) {
+                chunks.push(piece.iter().collect());
+            }
+            continue;
+        }
+        if current_chars > 0 {
+            current.push_str("\n\n");
+            current_chars += 2;
+        }
+        current.push_str(paragraph);
+        current_chars += chars;
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Little-endian `f32` bytes, the layout used by common SQLite vector
+/// extensions.
+fn embedding_to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paragraphs_are_packed_up_to_the_limit() {
+        let md = "aaaa\n\nbbbb\n\n\n\ncccc";
+        assert_eq!(chunk_markdown(md, 10), vec!["aaaa\n\nbbbb", "cccc"]);
+        assert_eq!(chunk_markdown(md, 100), vec!["aaaa\n\nbbbb\n\ncccc"]);
+        assert!(chunk_markdown("  \n\n ", 10).is_empty());
+    }
+
+    #[test]
+    fn long_paragraphs_are_split() {
+        let md = format!("intro\n\n{}", "x".repeat(25));
+        let chunks = chunk_markdown(&md, 10);
+        assert_eq!(chunks[0], "intro");
+        assert_eq!(
+            chunks[1..].iter().map(|c| c.len()).collect::<Vec<_>>(),
+            [10, 10, 5]
+        );
+    }
+
+    #[test]
+    fn embeddings_are_little_endian_f32() {
+        assert_eq!(
+            embedding_to_blob(&[1.0, -2.0]),
+            [0, 0, 128, 63, 0, 0, 0, 192]
+        );
+    }
+}
@@ -9,7 +9,8 @@
 //! Unlike the eager [`crate::convert::convert`] which returns only after
 //! all pages finish, [`convert_stream`] yields `PageResult` items via a
 //! `Stream` as each page completes. In concurrent mode pages may arrive out
-//! of order (sort by `page_num` if order matters).
+//! of order (sort by `page_num` if order matters), unless
+//! [`ConversionConfig::ordered_stream`] is set.
 //!
 //! ## Lazy pipeline (v0.5.0)
 //!
@@ -18,15 +19,27 @@
 //! freed immediately after encoding, so memory is bounded to at most
 //! `concurrency` pages regardless of document size. See issue #16.
 
+use crate::backend::VisionBackend;
 use crate::config::ConversionConfig;
+use crate::convert;
 use crate::error::{PageError, Pdf2MdError};
-use crate::output::PageResult;
+use crate::handwriting;
+use crate::output::{ConversionStats, PageResult};
+use crate::page_kind::{self, PageKind};
+use crate::pii;
+use crate::pipeline::postprocess::CleanReport;
+use crate::pipeline::render::EncodedPage;
+use crate::pipeline::session::PdfSession;
+use crate::pipeline::timing::StageSamples;
 use crate::pipeline::{input, llm, postprocess, render};
-use edgequake_llm::{LLMProvider, ProviderFactory};
+use crate::resolver::ConversionContext;
 use futures::StreamExt;
+use std::collections::HashMap;
 use std::io::Write;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::Stream;
 use tracing::info;
@@ -34,100 +47,405 @@ use tracing::info;
 /// A boxed stream of page results.
 pub type PageStream = Pin<Box<dyn Stream<Item = Result<PageResult, PageError>> + Send>>;
 
-/// Convert a PDF to Markdown, streaming pages as they are ready.
-///
-/// Pages are emitted in completion order (not necessarily page order)
-/// when `maintain_format = false`. Sort by `page_num` if order matters.
+/// A lifecycle event from [`convert_stream_events`].
 ///
-/// # Returns
-/// - `Ok(PageStream)` — a stream of `Result<PageResult, PageError>`
-/// - `Err(Pdf2MdError)` — fatal error (file not found, not a PDF, etc.)
-pub async fn convert_stream(
-    input_str: impl AsRef<str>,
-    config: &ConversionConfig,
-) -> Result<PageStream, Pdf2MdError> {
-    let input_str = input_str.as_ref();
-    info!("Starting streaming conversion: {}", input_str);
+/// Carries the same information as [`crate::ConversionProgressCallback`]
+/// plus the page results themselves, in one ordered channel.
+#[derive(Debug, Clone)]
+pub enum ConversionEvent {
+    /// The PDF was opened; `total_pages` pages are selected for conversion.
+    Started { total_pages: usize },
+    /// A page is about to be sent to the VLM.
+    PageStarted { page_num: usize },
+    /// A page converted successfully.
+    Page(PageResult),
+    /// A page failed after all retries.
+    PageFailed(PageError),
+    /// Every selected page has been attempted. Always the last event.
+    Completed(ConversionStats),
+}
 
+/// A boxed stream of [`ConversionEvent`]s.
+pub type EventStream = Pin<Box<dyn Stream<Item = ConversionEvent> + Send>>;
+
+/// Everything a streaming conversion needs once setup has succeeded.
+struct PreparedStream {
+    /// Keeps a downloaded temp file alive while the producer reads it.
+    _resolved: input::ResolvedInput,
+    rx: mpsc::Receiver<EncodedPage>,
+    provider: Arc<dyn VisionBackend>,
+    fallbacks: Arc<[Arc<dyn VisionBackend>]>,
+    total_pages: usize,
+    selected_pages: usize,
+    /// Selected pages dropped by [`crate::PageSelection::ExcludeKinds`].
+    excluded_by_kind: usize,
+    /// Kinds found by [`ConversionConfig::page_classifier`], by 0-based
+    /// page index.
+    page_kinds: Arc<HashMap<usize, PageKind>>,
+    /// The caller's config with the page prompt bound to this document.
+    config: ConversionConfig,
+}
+
+/// Resolve input and provider, inspect the PDF, and start the lazy renderer.
+async fn prepare_stream(
+    input_str: &str,
+    config: &ConversionConfig,
+) -> Result<PreparedStream, Pdf2MdError> {
     // ── Resolve input ────────────────────────────────────────────────────
+    convert::check_local_input(config, input_str)?;
     let resolved = input::resolve_input(input_str, config.download_timeout_secs).await?;
     let pdf_path = resolved.path().to_path_buf();
+    convert::check_file_size(config, &pdf_path)?;
 
-    // ── Get provider ─────────────────────────────────────────────────────
-    let provider = resolve_provider(config)?;
+    // ── Get backend ──────────────────────────────────────────────────────
+    // Shared with the eager path so both honour the same resolution chain
+    // and provider-aware default models.
+    let ctx = ConversionContext {
+        input: input_str.to_string(),
+        ..Default::default()
+    };
+    let (provider, _) = convert::resolve_backend(config, &ctx).await?;
+    let fallbacks: Arc<[Arc<dyn VisionBackend>]> =
+        convert::resolve_fallback_backends(config)?.into();
 
     // ── Extract metadata for page count ──────────────────────────────────
-    let metadata = render::extract_metadata(&pdf_path, config.password.as_deref()).await?;
+    let session = PdfSession::open(&pdf_path, config.password.as_deref()).await?;
+    let metadata = session.metadata().await?;
     let total_pages = metadata.page_count;
+    let settled = handwriting::settle(config, None);
+    let config = settled.as_ref().unwrap_or(config);
+    let bound = convert::bind_page_prompt(config, &session, input_str, &metadata).await;
 
     // ── Compute page indices ─────────────────────────────────────────────
-    let page_indices = config.pages.to_indices(total_pages);
-    if page_indices.is_empty() {
+    let selected = config.pages.to_indices(total_pages);
+    if selected.is_empty() {
         return Err(Pdf2MdError::PageOutOfRange {
             page: 0,
             total: total_pages,
         });
     }
+    let selected_before_kinds = selected.len();
+    let (page_indices, page_kinds) =
+        page_kind::select(&session, &provider, config, selected).await?;
+    convert::check_page_limit(config, page_indices.len())?;
 
     // ── Lazy render+encode pipeline ─────────────────────────────────────
-    let rx = render::spawn_lazy_render_encode(&pdf_path, config, &page_indices, config.concurrency)
-        .await?;
+    let rx =
+        render::spawn_lazy_render_encode_in(&session, config, &page_indices, config.concurrency)
+            .await?;
+
+    Ok(PreparedStream {
+        _resolved: resolved,
+        rx,
+        provider,
+        fallbacks,
+        total_pages,
+        selected_pages: page_indices.len(),
+        excluded_by_kind: selected_before_kinds - page_indices.len(),
+        page_kinds: Arc::new(page_kinds),
+        config: bound,
+    })
+}
+
+/// Convert a PDF to Markdown, streaming pages as they are ready.
+///
+/// Pages are emitted in completion order (not necessarily page order)
+/// when `maintain_format = false`. Sort by `page_num` if order matters, or
+/// set [`ConversionConfig::ordered_stream`] to receive them in page order.
+///
+/// # Returns
+/// - `Ok(PageStream)` — a stream of `Result<PageResult, PageError>`
+/// - `Err(Pdf2MdError)` — fatal error (file not found, not a PDF, etc.)
+pub async fn convert_stream(
+    input_str: impl AsRef<str>,
+    config: &ConversionConfig,
+) -> Result<PageStream, Pdf2MdError> {
+    let input_str = input_str.as_ref();
+    info!("Starting streaming conversion: {}", input_str);
+
+    let PreparedStream {
+        rx,
+        provider,
+        fallbacks,
+        page_kinds,
+        config: config_clone,
+        ..
+    } = prepare_stream(input_str, config).await?;
 
     // ── Build the stream ─────────────────────────────────────────────────
     let concurrency = config.concurrency;
-    let config_clone = config.clone();
 
     if config.maintain_format {
         // Sequential mode: process in page order, passing prior markdown as
         // context to each VLM call via `unfold`.
+        let prior = llm::PriorPages::for_config(config);
         let s = futures::stream::unfold(
-            (rx, provider, config_clone, None::<String>),
-            |(mut rx, provider, cfg, prior_markdown)| async move {
+            (rx, provider, fallbacks, config_clone, prior, page_kinds),
+            |(mut rx, provider, fallbacks, cfg, mut prior, kinds)| async move {
                 let page = rx.recv().await?;
-                let page_num = page.page_index + 1;
-                let mut result = llm::process_page(
+                let context = prior.context();
+                let mut result = llm::process_encoded_page(
                     &provider,
-                    page_num,
-                    page.image_data,
-                    prior_markdown.as_deref(),
+                    &fallbacks,
+                    page,
+                    context.as_deref(),
                     &cfg,
                 )
                 .await;
+                result.page_kind = kinds.get(&(result.page_num - 1)).copied();
+                postprocess::postprocess_page(&mut result, &cfg);
+                postprocess::filter_page(&mut result, &cfg);
+                if let Some(ref pc) = cfg.pii {
+                    pii::mask_page(&mut result, pc);
+                }
+                if let Some(ref m) = cfg.metrics_sink {
+                    m.record_page(&result);
+                }
                 if result.error.is_none() {
-                    result.markdown = postprocess::clean_markdown(&result.markdown);
-                    let new_prior = Some(result.markdown.clone());
-                    Some((Ok(result), (rx, provider, cfg, new_prior)))
+                    if !result.skipped_blank {
+                        prior.push(&result.markdown);
+                    }
+                    Some((Ok(result), (rx, provider, fallbacks, cfg, prior, kinds)))
                 } else {
                     let err = result.error.take().unwrap();
-                    Some((Err(err), (rx, provider, cfg, prior_markdown)))
+                    Some((Err(err), (rx, provider, fallbacks, cfg, prior, kinds)))
                 }
             },
         );
 
         Ok(Box::pin(s))
     } else {
-        // Concurrent mode: process in parallel, emit as ready
-        let s = ReceiverStream::new(rx)
-            .map(move |page| {
+        // Concurrent mode: process in parallel. The render producer sends pages
+        // in ascending order, so `buffered` (which yields in submission order)
+        // gives strict page order while keeping `concurrency` calls in flight.
+        let s = ReceiverStream::new(rx).map(move |page| {
+            let provider = Arc::clone(&provider);
+            let fallbacks = Arc::clone(&fallbacks);
+            let kinds = Arc::clone(&page_kinds);
+            let cfg = config_clone.clone();
+            async move {
+                let mut result =
+                    llm::process_encoded_page(&provider, &fallbacks, page, None, &cfg).await;
+                result.page_kind = kinds.get(&(result.page_num - 1)).copied();
+                postprocess::postprocess_page(&mut result, &cfg);
+                postprocess::filter_page(&mut result, &cfg);
+                if let Some(ref pc) = cfg.pii {
+                    pii::mask_page(&mut result, pc);
+                }
+                if let Some(ref m) = cfg.metrics_sink {
+                    m.record_page(&result);
+                }
+                if result.error.is_none() {
+                    Ok(result)
+                } else {
+                    let err = result.error.take().unwrap();
+                    Err(err)
+                }
+            }
+        });
+
+        if config.ordered_stream {
+            Ok(Box::pin(s.buffered(concurrency)))
+        } else {
+            Ok(Box::pin(s.buffer_unordered(concurrency)))
+        }
+    }
+}
+
+/// Convert a PDF to Markdown, streaming lifecycle events as well as pages.
+///
+/// Where [`convert_stream`] yields only page results, this yields a
+/// [`ConversionEvent`] for every step — `Started`, then `PageStarted` /
+/// `Page` / `PageFailed` per page, then a final `Completed` with the same
+/// [`ConversionStats`] that [`crate::convert`] returns. Streaming consumers
+/// get progress-bar and summary information without also wiring a callback.
+///
+/// Honours `maintain_format`, `ordered_stream`, and `concurrency` exactly as
+/// [`convert_stream`] does. Dropping the stream cancels the conversion.
+///
+/// # Example
+/// ```rust,no_run
+/// use edgequake_pdf2md::{convert_stream_events, ConversionConfig, ConversionEvent};
+/// use futures::StreamExt;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut events = convert_stream_events("document.pdf", &ConversionConfig::default()).await?;
+/// while let Some(event) = events.next().await {
+///     match event {
+///         ConversionEvent::Started { total_pages } => eprintln!("{total_pages} pages"),
+///         ConversionEvent::Page(p) => print!("{}", p.markdown),
+///         ConversionEvent::PageFailed(e) => eprintln!("{e}"),
+///         ConversionEvent::Completed(stats) => eprintln!("{}ms", stats.total_duration_ms),
+///         ConversionEvent::PageStarted { .. } => {}
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn convert_stream_events(
+    input_str: impl AsRef<str>,
+    config: &ConversionConfig,
+) -> Result<EventStream, Pdf2MdError> {
+    let input_str = input_str.as_ref();
+    info!("Starting event-streaming conversion: {}", input_str);
+
+    let start = Instant::now();
+    let prepared = prepare_stream(input_str, config).await?;
+    let (tx, rx) = mpsc::channel(config.concurrency.max(1) * 2);
+    tokio::spawn(drive_events(prepared, start, tx));
+
+    Ok(Box::pin(ReceiverStream::new(rx)))
+}
+
+/// Run the page pipeline, translating each step into a [`ConversionEvent`].
+///
+/// Returns early (cancelling in-flight pages) as soon as the receiver is gone.
+async fn drive_events(prepared: PreparedStream, start: Instant, tx: mpsc::Sender<ConversionEvent>) {
+    let PreparedStream {
+        _resolved,
+        rx,
+        provider,
+        fallbacks,
+        total_pages,
+        selected_pages,
+        excluded_by_kind,
+        page_kinds,
+        config: cfg,
+    } = prepared;
+
+    if tx
+        .send(ConversionEvent::Started {
+            total_pages: selected_pages,
+        })
+        .await
+        .is_err()
+    {
+        return;
+    }
+
+    let samples = Arc::new(StageSamples::default());
+    let mut stats = ConversionStats {
+        total_pages,
+        ..Default::default()
+    };
+
+    if cfg.maintain_format {
+        // Sequential mode: each page gets the previous pages' Markdown.
+        let mut rx = rx;
+        let mut prior = llm::PriorPages::for_config(&cfg);
+        while let Some(page) = rx.recv().await {
+            samples.record_encoded(&page);
+            let page_num = page.page_index + 1;
+            if tx
+                .send(ConversionEvent::PageStarted { page_num })
+                .await
+                .is_err()
+            {
+                return;
+            }
+            let context = prior.context();
+            let mut result =
+                llm::process_encoded_page(&provider, &fallbacks, page, context.as_deref(), &cfg)
+                    .await;
+            result.page_kind = page_kinds.get(&(result.page_num - 1)).copied();
+            let report = postprocess::postprocess_page(&mut result, &cfg);
+            postprocess::filter_page(&mut result, &cfg);
+            if let Some(ref pc) = cfg.pii {
+                pii::mask_page(&mut result, pc);
+            }
+            if result.error.is_none() && !result.skipped_blank {
+                prior.push(&result.markdown);
+            }
+            samples.record_result(&result);
+            if let Some(ref m) = cfg.metrics_sink {
+                m.record_page(&result);
+            }
+            if !emit_page_event(&tx, &mut stats, result, &report).await {
+                return;
+            }
+        }
+    } else {
+        let pages = {
+            let tx = tx.clone();
+            let samples = Arc::clone(&samples);
+            let cfg = cfg.clone();
+            ReceiverStream::new(rx).map(move |page| {
+                samples.record_encoded(&page);
+                let tx = tx.clone();
                 let provider = Arc::clone(&provider);
-                let cfg = config_clone.clone();
+                let fallbacks = Arc::clone(&fallbacks);
+                let kinds = Arc::clone(&page_kinds);
+                let cfg = cfg.clone();
                 async move {
                     let page_num = page.page_index + 1;
+                    let _ = tx.send(ConversionEvent::PageStarted { page_num }).await;
                     let mut result =
-                        llm::process_page(&provider, page_num, page.image_data, None, &cfg).await;
-                    if result.error.is_none() {
-                        result.markdown = postprocess::clean_markdown(&result.markdown);
-                        Ok(result)
-                    } else {
-                        let err = result.error.take().unwrap();
-                        Err(err)
+                        llm::process_encoded_page(&provider, &fallbacks, page, None, &cfg).await;
+                    result.page_kind = kinds.get(&(page_num - 1)).copied();
+                    let report = postprocess::postprocess_page(&mut result, &cfg);
+                    postprocess::filter_page(&mut result, &cfg);
+                    if let Some(ref pc) = cfg.pii {
+                        pii::mask_page(&mut result, pc);
                     }
+                    (result, report)
                 }
             })
-            .buffer_unordered(concurrency);
+        };
+        type Processed = (PageResult, CleanReport);
+        let mut results: Pin<Box<dyn Stream<Item = Processed> + Send>> = if cfg.ordered_stream {
+            Box::pin(pages.buffered(cfg.concurrency))
+        } else {
+            Box::pin(pages.buffer_unordered(cfg.concurrency))
+        };
+        while let Some((result, report)) = results.next().await {
+            samples.record_result(&result);
+            if let Some(ref m) = cfg.metrics_sink {
+                m.record_page(&result);
+            }
+            if !emit_page_event(&tx, &mut stats, result, &report).await {
+                return;
+            }
+        }
+    }
 
-        Ok(Box::pin(s))
+    stats.skipped_pages = selected_pages.saturating_sub(stats.processed_pages + stats.failed_pages)
+        + excluded_by_kind;
+    stats.total_duration_ms = start.elapsed().as_millis() as u64;
+    samples.fill(&mut stats);
+    let _ = tx.send(ConversionEvent::Completed(stats)).await;
+}
+
+/// Fold one page and its post-processing report into `stats` and send it
+/// as `Page` or `PageFailed`.
+///
+/// Returns `false` when the receiver has been dropped.
+async fn emit_page_event(
+    tx: &mpsc::Sender<ConversionEvent>,
+    stats: &mut ConversionStats,
+    mut result: PageResult,
+    report: &CleanReport,
+) -> bool {
+    stats.postprocess_report.merge(report);
+    stats.total_input_tokens += result.input_tokens as u64;
+    stats.total_image_tokens += result.image_tokens as u64;
+    stats.total_output_tokens += result.output_tokens as u64;
+    if let Some(cost) = result.cost_usd {
+        *stats.total_cost_usd.get_or_insert(0.0) += cost;
     }
+    let event = match result.error.take() {
+        // Blank pages are still emitted; the final stats count them as skipped.
+        None if result.skipped_blank => ConversionEvent::Page(result),
+        None => {
+            stats.processed_pages += 1;
+            ConversionEvent::Page(result)
+        }
+        Some(e) => {
+            stats.failed_pages += 1;
+            ConversionEvent::PageFailed(e)
+        }
+    };
+    tx.send(event).await.is_ok()
 }
 
 /// Convert PDF bytes in memory to Markdown, streaming pages as they complete.
@@ -174,8 +492,8 @@ pub async fn convert_stream_from_bytes(
     let path = tmp.path().to_string_lossy().to_string();
     let inner = convert_stream(&path, config).await?;
 
-    // With lazy rendering, the producer's spawn_blocking task still accesses
-    // the tempfile as pages are rendered on-demand.  Keep `tmp` alive for the
+    // With lazy rendering, the PDF session still reads the tempfile as
+    // pages are rendered on-demand.  Keep `tmp` alive for the
     // lifetime of the returned stream using `unfold` — it is dropped only
     // when the stream is fully consumed or dropped by the caller.
     let held = futures::stream::unfold((inner, tmp), |(mut stream, tmp)| async move {
@@ -184,61 +502,3 @@ pub async fn convert_stream_from_bytes(
     });
     Ok(Box::pin(held))
 }
-
-/// Resolve LLM provider from config.
-fn resolve_provider(config: &ConversionConfig) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
-    if let Some(ref provider) = config.provider {
-        return Ok(Arc::clone(provider));
-    }
-
-    if let Some(ref name) = config.provider_name {
-        let model = config.model.as_deref().unwrap_or("gpt-4.1-nano");
-        return create_vision_provider(name, model);
-    }
-
-    // Honour EDGEQUAKE_LLM_PROVIDER + EDGEQUAKE_MODEL when both set
-    if let (Ok(prov), Ok(model)) = (
-        std::env::var("EDGEQUAKE_LLM_PROVIDER"),
-        std::env::var("EDGEQUAKE_MODEL"),
-    ) {
-        if !prov.is_empty() && !model.is_empty() {
-            return create_vision_provider(&prov, &model);
-        }
-    }
-
-    // Prefer OpenAI explicitly when an OpenAI API key is present.
-    // This ensures users with multiple provider keys (e.g. Gemini + OpenAI)
-    // will default to OpenAI unless they explicitly request another provider.
-    if let Ok(openai_key) = std::env::var("OPENAI_API_KEY") {
-        if !openai_key.is_empty() {
-            let model = config.model.as_deref().unwrap_or("gpt-4.1-nano");
-            return create_vision_provider("openai", model);
-        }
-    }
-
-    let (llm_provider, _) =
-        ProviderFactory::from_env().map_err(|e| Pdf2MdError::ProviderNotConfigured {
-            provider: "auto".to_string(),
-            hint: format!("No LLM provider auto-detected: {}", e),
-        })?;
-
-    Ok(llm_provider)
-}
-
-/// Instantiate a named provider with the given model.
-///
-/// Uses [`ProviderFactory::create_llm_provider`] uniformly for all providers.
-/// Previously OpenAI was routed through `OpenAICompatibleProvider` to work around
-/// a bug in `OpenAIProvider::convert_messages()` that silently dropped image data.
-/// That bug was fixed upstream in edgequake-llm v0.2.2.
-fn create_vision_provider(
-    provider_name: &str,
-    model: &str,
-) -> Result<Arc<dyn LLMProvider>, Pdf2MdError> {
-    ProviderFactory::create_llm_provider(provider_name, model).map_err(|e| {
-        Pdf2MdError::ProviderNotConfigured {
-            provider: provider_name.to_string(),
-            hint: format!("{e}"),
-        }
-    })
-}
@@ -0,0 +1,169 @@
+//! Optional document-summary pass run after page conversion.
+//!
+//! ## Why map-reduce?
+//!
+//! A short report fits in one request, so its Markdown is summarised in a
+//! single call. A 400-page book does not: its Markdown is split into chunks
+//! of whole pages no larger than [`SummaryConfig::chunk_chars`], each chunk
+//! is summarised on its own ("map", run with the conversion's
+//! `concurrency`), and the partial summaries are combined into one abstract
+//! ("reduce"). Every request stays within the model's context window while
+//! the final abstract still covers the whole document.
+//!
+//! The pass is enabled with [`crate::ConversionConfigBuilder::generate_summary`]
+//! and its result lands in [`crate::ConversionOutput::summary`]. A failed
+//! summary never fails the conversion; it is logged and left as `None`.
+
+use crate::config::ConversionConfig;
+use crate::error::Pdf2MdError;
+use crate::output::PageResult;
+use crate::pipeline::llm;
+use crate::prompts::{summary_reduce_request, summary_request, SUMMARY_SYSTEM_PROMPT};
+use edgequake_llm::LLMProvider;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+/// Options for the document-summary pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryConfig {
+    /// Target length of the abstract in words. Default: 200.
+    pub max_words: usize,
+    /// Largest chunk of Markdown sent in one request, in characters. Default: 40 000.
+    ///
+    /// Roughly 10 000 tokens: comfortably inside every supported model's
+    /// context window. Documents under this size are summarised in one call.
+    pub chunk_chars: usize,
+    /// Insert the abstract at the top of the assembled Markdown. Default: false.
+    ///
+    /// It is placed after any YAML front-matter, under a `## Summary` heading.
+    pub prepend: bool,
+}
+
+impl Default for SummaryConfig {
+    fn default() -> Self {
+        Self {
+            max_words: 200,
+            chunk_chars: 40_000,
+            prepend: false,
+        }
+    }
+}
+
+/// Summarise the successful pages of a conversion.
+///
+/// Returns the abstract together with the input and output tokens spent.
+pub async fn summarize(
+    provider: &Arc<dyn LLMProvider>,
+    pages: &[PageResult],
+    config: &ConversionConfig,
+    summary: &SummaryConfig,
+) -> Result<(String, u64, u64), Pdf2MdError> {
+    let chunks = chunk_pages(pages, summary.chunk_chars);
+    if chunks.is_empty() {
+        return Err(Pdf2MdError::Internal("No page content to summarise".into()));
+    }
+
+    if chunks.len() == 1 {
+        let request = summary_request(&chunks[0], summary.max_words, false);
+        let (text, input, output) =
+            llm::complete_text(provider, SUMMARY_SYSTEM_PROMPT, &request, config).await?;
+        return Ok((text.trim().to_string(), input as u64, output as u64));
+    }
+
+    info!("Summarising document in {} chunks", chunks.len());
+    let requests: Vec<String> = chunks
+        .iter()
+        .map(|chunk| summary_request(chunk, summary.max_words, true))
+        .collect();
+    let partials: Vec<Result<(String, usize, usize), Pdf2MdError>> =
+        futures::stream::iter(requests)
+            .map(|request| async move {
+                llm::complete_text(provider, SUMMARY_SYSTEM_PROMPT, &request, config).await
+            })
+            .buffered(config.concurrency)
+            .collect()
+            .await;
+
+    let mut input_tokens = 0u64;
+    let mut output_tokens = 0u64;
+    let mut texts = Vec::with_capacity(partials.len());
+    for partial in partials {
+        let (text, input, output) = partial?;
+        input_tokens += input as u64;
+        output_tokens += output as u64;
+        texts.push(text);
+    }
+    debug!("Reducing {} partial summaries", texts.len());
+
+    let request = summary_reduce_request(&texts, summary.max_words);
+    let (text, input, output) =
+        llm::complete_text(provider, SUMMARY_SYSTEM_PROMPT, &request, config).await?;
+    Ok((
+        text.trim().to_string(),
+        input_tokens + input as u64,
+        output_tokens + output as u64,
+    ))
+}
+
+/// Group successful pages, in order, into chunks of at most `chunk_chars`.
+///
+/// Pages are never split, so a single page larger than the limit forms a
+/// chunk of its own. Failed and empty pages are skipped.
+pub fn chunk_pages(pages: &[PageResult], chunk_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for page in pages.iter().filter(|p| p.error.is_none()) {
+        let text = page.markdown.trim();
+        if text.is_empty() {
+            continue;
+        }
+        if !current.is_empty() && current.len() + text.len() + 2 > chunk_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(text);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PageError;
+
+    #[test]
+    fn small_document_is_one_chunk() {
+        let pages = vec![PageResult::new(1, "alpha"), PageResult::new(2, "beta")];
+        assert_eq!(chunk_pages(&pages, 1000), vec!["alpha\n\nbeta"]);
+    }
+
+    #[test]
+    fn chunks_break_between_pages() {
+        let pages = vec![
+            PageResult::new(1, "aaaa"),
+            PageResult::new(2, "bbbb"),
+            PageResult::new(3, "cccccccccc"),
+        ];
+        assert_eq!(chunk_pages(&pages, 10), vec!["aaaa\n\nbbbb", "cccccccccc"]);
+    }
+
+    #[test]
+    fn failed_and_empty_pages_are_skipped() {
+        let mut failed = PageResult::new(2, "");
+        failed.error = Some(PageError::Timeout { page: 2, secs: 60 });
+        let pages = vec![
+            PageResult::new(1, "text"),
+            failed,
+            PageResult::new(3, "   "),
+        ];
+        assert_eq!(chunk_pages(&pages, 1000), vec!["text"]);
+        assert!(chunk_pages(&[PageResult::new(1, "")], 1000).is_empty());
+    }
+}
@@ -0,0 +1,373 @@
+//! Tagged-PDF structure and PDF/A, PDF/UA conformance.
+//!
+//! ## Why read the tags?
+//!
+//! An accessible PDF already says what its headings are: the structure
+//! tree marks `H1`–`H6` elements, tables, and figures with their alt text.
+//! The vision model has to guess heading levels from font sizes, and gets
+//! them wrong often enough to break an outline. Reading the tags costs no
+//! API call.
+//!
+//! [`crate::inspect`] reports the document's [`Conformance`] and, for a
+//! tagged document, each page's [`PageTags`] in [`crate::PageInfo::tags`].
+//! With [`crate::ConversionConfigBuilder::structure_tags`] a conversion also
+//! lists the tagged headings in the page prompt and then, with
+//! [`align_headings`], gives every Markdown heading whose text matches a
+//! tagged one the tagged level — promoting a matching plain or bold line to
+//! a heading.
+//!
+//! PDF/A and PDF/UA claims are read from the document's XMP metadata
+//! (`pdfaid:part`, `pdfaid:conformance`, `pdfuaid:part`), which those
+//! standards require to be stored unfiltered. They are claims, not the
+//! result of validation.
+
+use once_cell::sync::Lazy;
+use pdfium_render::prelude::*;
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::c_void;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use tracing::warn;
+
+/// Standards the document claims to follow.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Conformance {
+    /// Whether the document has a logical structure tree (a tagged PDF).
+    pub tagged: bool,
+    /// PDF/A part and conformance level, e.g. `"2B"` or `"3A"`.
+    pub pdfa: Option<String>,
+    /// PDF/UA part, e.g. `1`.
+    pub pdfua: Option<u32>,
+}
+
+/// A heading marked in the structure tree.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaggedHeading {
+    /// 1–6; a generic `H` takes its level from the enclosing `Sect` nesting.
+    pub level: u8,
+    pub text: String,
+}
+
+/// What the structure tree marks on one page.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageTags {
+    /// Headings in structure order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headings: Vec<TaggedHeading>,
+    /// Number of `Table` elements.
+    pub tables: usize,
+    /// Alt text of `Figure` elements that have one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub figure_alt_text: Vec<String>,
+}
+
+static PDFA_PART_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"pdfaid:part\s*(?:=\s*["']|>)\s*(\d)"#).unwrap());
+static PDFA_LEVEL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"pdfaid:conformance\s*(?:=\s*["']|>)\s*([A-Za-z])"#).unwrap());
+static PDFUA_PART_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"pdfuaid:part\s*(?:=\s*["']|>)\s*(\d)"#).unwrap());
+
+/// The document's conformance claims, read from `document` and its file.
+pub(crate) fn conformance(document: &PdfDocument, pdf_path: &Path) -> Conformance {
+    let bindings = document.bindings();
+    let tagged = bindings.FPDFCatalog_IsTagged(bindings.get_handle_from_document(document)) != 0;
+    let (pdfa, pdfua) = match File::open(pdf_path) {
+        Ok(file) => xmp_conformance(file),
+        Err(e) => {
+            warn!(
+                "Could not read {} for XMP metadata: {}",
+                pdf_path.display(),
+                e
+            );
+            (None, None)
+        }
+    };
+    Conformance {
+        tagged,
+        pdfa,
+        pdfua,
+    }
+}
+
+/// PDF/A and PDF/UA identification from the last XMP packet that has one,
+/// since an incremental update appends a new packet after the old. Reads
+/// the file in chunks, so a large scan is never held in memory.
+fn xmp_conformance(mut file: impl Read) -> (Option<String>, Option<u32>) {
+    const CHUNK: usize = 1 << 20;
+    const OVERLAP: usize = 256;
+    let (mut part, mut level, mut ua) = (None, None, None);
+    let mut buf = Vec::with_capacity(CHUNK + OVERLAP);
+    let mut chunk = vec![0u8; CHUNK];
+    loop {
+        let n = match file.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        buf.extend_from_slice(&chunk[..n]);
+        let last = |re: &Regex| {
+            re.captures_iter(&buf)
+                .last()
+                .map(|c| String::from_utf8_lossy(&c[1]).into_owned())
+        };
+        part = last(&PDFA_PART_RE).or(part);
+        level = last(&PDFA_LEVEL_RE).or(level);
+        ua = last(&PDFUA_PART_RE).or(ua);
+        // Keep a tail so a key split across two reads is still found.
+        let keep = buf.len().saturating_sub(OVERLAP);
+        buf.drain(..keep);
+    }
+    let pdfa = part.map(|p| format!("{p}{}", level.unwrap_or_default().to_ascii_uppercase()));
+    (pdfa, ua.and_then(|p| p.parse().ok()))
+}
+
+/// The tags on `page`, or `None` when it has no structure tree.
+pub(crate) fn page_tags(page: &PdfPage) -> Option<PageTags> {
+    let bindings = page.bindings();
+    let tree = bindings.FPDF_StructTree_GetForPage(bindings.get_handle_from_page(page));
+    if tree.is_null() {
+        return None;
+    }
+    let mut walk = Walk {
+        bindings,
+        page,
+        tags: PageTags::default(),
+        marked_text: None,
+    };
+    for i in 0..bindings.FPDF_StructTree_CountChildren(tree) {
+        let element = bindings.FPDF_StructTree_GetChildAtIndex(tree, i);
+        walk.visit(element, 0);
+    }
+    bindings.FPDF_StructTree_Close(tree);
+    Some(walk.tags)
+}
+
+/// Depth-first walk of one page's structure tree.
+struct Walk<'a, 'b> {
+    bindings: &'a dyn PdfiumLibraryBindings,
+    page: &'b PdfPage<'a>,
+    tags: PageTags,
+    /// Text of the page's objects by marked-content ID, built on first use.
+    marked_text: Option<HashMap<i32, String>>,
+}
+
+impl Walk<'_, '_> {
+    fn visit(&mut self, element: FPDF_STRUCTELEMENT, sections: u8) {
+        if element.is_null() {
+            return;
+        }
+        let b = self.bindings;
+        let kind =
+            utf16(|buf, len| b.FPDF_StructElement_GetType(element, buf, len)).unwrap_or_default();
+        let level = match kind.as_str() {
+            "Title" => Some(1),
+            "H" => Some(sections.clamp(1, 6)),
+            _ => kind
+                .strip_prefix('H')
+                .and_then(|n| n.parse::<u8>().ok())
+                .filter(|n| (1..=6).contains(n)),
+        };
+        match (kind.as_str(), level) {
+            (_, Some(level)) => {
+                let text = self.text_of(element);
+                if !text.is_empty() {
+                    self.tags.headings.push(TaggedHeading { level, text });
+                }
+                return;
+            }
+            ("Table", _) => self.tags.tables += 1,
+            ("Figure", _) => {
+                if let Some(alt) =
+                    utf16(|buf, len| b.FPDF_StructElement_GetAltText(element, buf, len))
+                {
+                    self.tags.figure_alt_text.push(alt);
+                }
+            }
+            _ => {}
+        }
+        let sections = sections.saturating_add(u8::from(kind == "Sect"));
+        for i in 0..b.FPDF_StructElement_CountChildren(element) {
+            self.visit(b.FPDF_StructElement_GetChildAtIndex(element, i), sections);
+        }
+    }
+
+    /// `/ActualText` of `element`, else the text of the content it and its
+    /// descendants mark.
+    fn text_of(&mut self, element: FPDF_STRUCTELEMENT) -> String {
+        let b = self.bindings;
+        if let Some(text) = utf16(|buf, len| b.FPDF_StructElement_GetActualText(element, buf, len))
+        {
+            return collapse(&text);
+        }
+        let mut ids = Vec::new();
+        marked_ids(b, element, &mut ids);
+        let page = self.page;
+        let marked = self.marked_text.get_or_insert_with(|| {
+            let mut map: HashMap<i32, String> = HashMap::new();
+            for object in page.objects().iter() {
+                let id = b.FPDFPageObj_GetMarkedContentID(b.get_handle_from_object(&object));
+                if let (true, Some(text)) = (id >= 0, object.as_text_object().map(|t| t.text())) {
+                    let entry = map.entry(id).or_default();
+                    entry.push_str(&text);
+                    entry.push(' ');
+                }
+            }
+            map
+        });
+        let text: Vec<&str> = ids
+            .iter()
+            .filter_map(|id| marked.get(id).map(String::as_str))
+            .collect();
+        collapse(&text.join(" "))
+    }
+}
+
+/// Marked-content IDs of `element` and its descendants, in order.
+fn marked_ids(b: &dyn PdfiumLibraryBindings, element: FPDF_STRUCTELEMENT, ids: &mut Vec<i32>) {
+    for i in 0..b.FPDF_StructElement_GetMarkedContentIdCount(element).max(0) {
+        let id = b.FPDF_StructElement_GetMarkedContentIdAtIndex(element, i);
+        if id >= 0 {
+            ids.push(id);
+        }
+    }
+    for i in 0..b.FPDF_StructElement_CountChildren(element) {
+        let child = b.FPDF_StructElement_GetChildAtIndex(element, i);
+        if !child.is_null() {
+            marked_ids(b, child, ids);
+        }
+    }
+}
+
+/// A UTF-16LE string from a pdfium getter called first for its length.
+fn utf16(
+    get: impl Fn(*mut c_void, std::os::raw::c_ulong) -> std::os::raw::c_ulong,
+) -> Option<String> {
+    let len = get(std::ptr::null_mut(), 0) as usize;
+    if len <= 2 {
+        return None;
+    }
+    let mut buf = vec![0u8; len];
+    get(buf.as_mut_ptr() as *mut c_void, len as _);
+    let units: Vec<u16> = buf
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    let text = String::from_utf16_lossy(&units).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Heading text reduced for matching: no emphasis, numbering kept, case and
+/// spacing folded, trailing `:` or `.` dropped.
+fn heading_key(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .filter(|c| !matches!(c, '*' | '_' | '`'))
+        .collect();
+    collapse(text.trim_end_matches('#'))
+        .trim_end_matches([':', '.'])
+        .to_lowercase()
+}
+
+/// Give headings of `markdown` the levels of the matching `headings`.
+///
+/// An ATX heading whose text matches a tagged heading gets its level; a
+/// plain or fully bold line that matches becomes a heading. Each tagged
+/// heading is used once, in order, so repeated titles map one to one.
+/// Code blocks, tables, lists, and quotes are left alone.
+pub fn align_headings(markdown: &str, headings: &[TaggedHeading]) -> String {
+    if headings.is_empty() {
+        return markdown.to_string();
+    }
+    let mut pending: Vec<(String, u8)> = headings
+        .iter()
+        .map(|h| (heading_key(&h.text), h.level))
+        .collect();
+    let mut in_fence = false;
+    let mut out: Vec<String> = Vec::new();
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+        }
+        let hashes = trimmed.chars().take_while(|&c| c == '#').count();
+        let text = if (1..=6).contains(&hashes) && trimmed[hashes..].starts_with(' ') {
+            &trimmed[hashes..]
+        } else if hashes == 0
+            && !trimmed.is_empty()
+            && !trimmed.starts_with(['|', '>', '-', '+', '!', '['])
+            && !trimmed.starts_with("* ")
+            && !trimmed.starts_with(char::is_numeric)
+        {
+            trimmed
+        } else {
+            ""
+        };
+        let key = heading_key(text);
+        let found = (!in_fence && !key.is_empty())
+            .then(|| pending.iter().position(|(k, _)| *k == key))
+            .flatten();
+        match found {
+            Some(i) => {
+                let (_, level) = pending.remove(i);
+                let text = text.trim().trim_end_matches('#').trim();
+                let text = text
+                    .strip_prefix("**")
+                    .and_then(|t| t.strip_suffix("**"))
+                    .unwrap_or(text);
+                out.push(format!("{} {}", "#".repeat(level as usize), text));
+            }
+            None => out.push(line.to_string()),
+        }
+    }
+    let mut result = out.join("\n");
+    if markdown.ends_with('\n') {
+        result.push('\n');
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h(level: u8, text: &str) -> TaggedHeading {
+        TaggedHeading {
+            level,
+            text: text.into(),
+        }
+    }
+
+    #[test]
+    fn headings_take_the_tagged_levels() {
+        let tags = [h(1, "Annual Report"), h(2, "1. Scope"), h(2, "Notes")];
+        let md = "## Annual Report\n\n#### 1. Scope:\n\nWe cover notes.\n\n**Notes**\n\n```\n# Notes\n```\n";
+        assert_eq!(
+            align_headings(md, &tags),
+            "# Annual Report\n\n## 1. Scope:\n\nWe cover notes.\n\n## Notes\n\n```\n# Notes\n```\n"
+        );
+        // Each tag is used once; lists and tables are not promoted.
+        let md = "# Notes\n\n# Notes\n\n- Notes\n| Notes |";
+        assert_eq!(
+            align_headings(md, &[h(3, "Notes")]),
+            "### Notes\n\n# Notes\n\n- Notes\n| Notes |"
+        );
+    }
+
+    #[test]
+    fn conformance_is_read_from_xmp() {
+        let attrs = br#"<rdf:Description pdfaid:part="2" pdfaid:conformance="b"/>"#;
+        assert_eq!(xmp_conformance(&attrs[..]), (Some("2B".into()), None));
+        let elements = b"<pdfaid:part>3</pdfaid:part><pdfaid:conformance>A</pdfaid:conformance>\
+<pdfuaid:part>1</pdfuaid:part>";
+        assert_eq!(xmp_conformance(&elements[..]), (Some("3A".into()), Some(1)));
+        assert_eq!(xmp_conformance(&b"%PDF-1.7"[..]), (None, None));
+    }
+}
@@ -0,0 +1,376 @@
+//! Deterministic pipeline tests without live API keys (feature `testkit`).
+//!
+//! ## Why a testkit?
+//!
+//! A prompt or post-processing change can silently alter the Markdown of
+//! every document, and live VLM output is neither free nor reproducible.
+//! This module replaces the model with [`MockVisionProvider`], which replays
+//! canned answers, so the real render → encode → LLM → postprocess →
+//! assemble pipeline runs offline and byte-for-byte repeatably. Comparing the
+//! result against a checked-in "golden" file turns any behaviour change into
+//! a visible diff.
+//!
+//! ## Fixture layout
+//!
+//! A [`GoldenFixture`] is a directory:
+//!
+//! ```text
+//! tests/golden/two-page-report/
+//! ├── input.pdf            the document to convert
+//! ├── responses/           one canned VLM answer per page, replayed in
+//! │   ├── page-0001.md     file-name order
+//! │   └── page-0002.md
+//! └── expected.md          the golden assembled Markdown
+//! ```
+//!
+//! Set `PDF2MD_UPDATE_GOLDEN=1` to (re)write `expected.md` from the actual
+//! output instead of comparing, then review the diff in version control.
+//!
+//! # Example
+//! ```rust,no_run
+//! use edgequake_pdf2md::testkit::GoldenFixture;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let fixture = GoldenFixture::load("tests/golden/two-page-report")?;
+//! let output = fixture.run().await?;
+//! fixture.assert_matches(&output.markdown);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::config::{ConversionConfig, ConversionConfigBuilder};
+use crate::convert::convert;
+use crate::error::Pdf2MdError;
+use crate::output::ConversionOutput;
+use async_trait::async_trait;
+use edgequake_llm::{ChatMessage, CompletionOptions, LLMProvider, LLMResponse, LlmError};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Environment variable that switches golden checks into update mode.
+pub const UPDATE_GOLDEN_ENV: &str = "PDF2MD_UPDATE_GOLDEN";
+
+/// Nominal prompt tokens reported per call: about one 1 024 px page image.
+const MOCK_PROMPT_TOKENS: usize = 1000;
+
+// ── Mock provider ────────────────────────────────────────────────────────
+
+/// One canned answer replayed by [`MockVisionProvider`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockResponse {
+    /// A normal completion (`finish_reason = "stop"`).
+    Markdown(String),
+    /// A completion cut off at the token budget (`finish_reason = "length"`).
+    Truncated(String),
+    /// A provider error with this message, e.g. `"503 Service Unavailable"`
+    /// or `"401 Unauthorized"`, classified like a real one.
+    Error(String),
+}
+
+/// An [`LLMProvider`] that replays canned responses instead of calling a model.
+///
+/// Responses are returned in call order and cycle when exhausted. Pages are
+/// only guaranteed to reach the provider in page order with `concurrency(1)`
+/// (which [`GoldenFixture::config`] sets); [`MockVisionProvider::always`]
+/// is order-independent.
+#[derive(Debug)]
+pub struct MockVisionProvider {
+    responses: Vec<MockResponse>,
+    next: AtomicUsize,
+    model: String,
+}
+
+impl MockVisionProvider {
+    /// Replay `responses` in order, cycling when exhausted.
+    pub fn new(responses: impl IntoIterator<Item = MockResponse>) -> Self {
+        Self {
+            responses: responses.into_iter().collect(),
+            next: AtomicUsize::new(0),
+            model: "mock-vision".to_string(),
+        }
+    }
+
+    /// Replay one Markdown answer per call, in order.
+    pub fn from_markdown<S: Into<String>>(pages: impl IntoIterator<Item = S>) -> Self {
+        Self::new(pages.into_iter().map(|p| MockResponse::Markdown(p.into())))
+    }
+
+    /// Answer every call with the same Markdown.
+    pub fn always(markdown: impl Into<String>) -> Self {
+        Self::new([MockResponse::Markdown(markdown.into())])
+    }
+
+    /// Report `model` as the model name (appears in spans and metrics).
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    /// Number of completion calls made so far, including failed ones.
+    pub fn calls(&self) -> usize {
+        self.next.load(Ordering::Acquire)
+    }
+
+    fn respond(&self) -> Result<LLMResponse, LlmError> {
+        let n = self.next.fetch_add(1, Ordering::AcqRel);
+        if self.responses.is_empty() {
+            return Err(LlmError::ApiError(
+                "MockVisionProvider has no canned responses".to_string(),
+            ));
+        }
+        let (content, finish_reason) = match &self.responses[n % self.responses.len()] {
+            MockResponse::Markdown(s) => (s.clone(), "stop"),
+            MockResponse::Truncated(s) => (s.clone(), "length"),
+            MockResponse::Error(msg) => return Err(LlmError::ApiError(msg.clone())),
+        };
+        let completion_tokens = content.len().div_ceil(4);
+        Ok(LLMResponse::new(content, self.model.clone())
+            .with_usage(MOCK_PROMPT_TOKENS, completion_tokens)
+            .with_finish_reason(finish_reason))
+    }
+}
+
+#[async_trait]
+impl LLMProvider for MockVisionProvider {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    fn max_context_length(&self) -> usize {
+        128_000
+    }
+
+    async fn complete(&self, _prompt: &str) -> Result<LLMResponse, LlmError> {
+        self.respond()
+    }
+
+    async fn complete_with_options(
+        &self,
+        _prompt: &str,
+        _options: &CompletionOptions,
+    ) -> Result<LLMResponse, LlmError> {
+        self.respond()
+    }
+
+    async fn chat(
+        &self,
+        _messages: &[ChatMessage],
+        _options: Option<&CompletionOptions>,
+    ) -> Result<LLMResponse, LlmError> {
+        self.respond()
+    }
+}
+
+// ── Golden fixtures ──────────────────────────────────────────────────────
+
+/// A golden-output test case loaded from a fixture directory.
+///
+/// See the [module documentation](self) for the directory layout.
+#[derive(Debug, Clone)]
+pub struct GoldenFixture {
+    /// The fixture directory.
+    pub dir: PathBuf,
+    /// `input.pdf` inside the fixture.
+    pub pdf: PathBuf,
+    /// Canned VLM answers from `responses/`, in file-name order.
+    pub responses: Vec<String>,
+    /// `expected.md` inside the fixture (may not exist yet).
+    pub expected: PathBuf,
+}
+
+impl GoldenFixture {
+    /// Load the fixture at `dir`.
+    ///
+    /// # Errors
+    /// [`Pdf2MdError::FileNotFound`] when `input.pdf` or `responses/` is
+    /// missing, [`Pdf2MdError::Internal`] when a response cannot be read.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, Pdf2MdError> {
+        let dir = dir.as_ref().to_path_buf();
+        let pdf = dir.join("input.pdf");
+        if !pdf.is_file() {
+            return Err(Pdf2MdError::FileNotFound { path: pdf });
+        }
+        let responses_dir = dir.join("responses");
+        let entries = std::fs::read_dir(&responses_dir).map_err(|_| Pdf2MdError::FileNotFound {
+            path: responses_dir.clone(),
+        })?;
+
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        files.sort();
+        let responses = files
+            .iter()
+            .map(|f| {
+                std::fs::read_to_string(f)
+                    .map_err(|e| Pdf2MdError::Internal(format!("reading {}: {e}", f.display())))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            expected: dir.join("expected.md"),
+            dir,
+            pdf,
+            responses,
+        })
+    }
+
+    /// A fresh provider replaying this fixture's responses.
+    pub fn provider(&self) -> Arc<MockVisionProvider> {
+        Arc::new(MockVisionProvider::from_markdown(
+            self.responses.iter().cloned(),
+        ))
+    }
+
+    /// A builder wired to [`GoldenFixture::provider`], with sequential page
+    /// order and no retry back-off. Add options before building to test them.
+    pub fn config(&self) -> ConversionConfigBuilder {
+        ConversionConfig::builder()
+            .provider(self.provider() as Arc<dyn LLMProvider>)
+            .concurrency(1)
+            .retry_backoff_ms(0)
+    }
+
+    /// Convert `input.pdf` with [`GoldenFixture::config`] defaults.
+    pub async fn run(&self) -> Result<ConversionOutput, Pdf2MdError> {
+        self.run_with(&self.config().build()?).await
+    }
+
+    /// Convert `input.pdf` with a caller-built configuration.
+    pub async fn run_with(
+        &self,
+        config: &ConversionConfig,
+    ) -> Result<ConversionOutput, Pdf2MdError> {
+        convert(self.pdf.to_string_lossy(), config).await
+    }
+
+    /// Compare `actual` against `expected.md`; see [`assert_golden`].
+    pub fn assert_matches(&self, actual: &str) {
+        assert_golden(&self.expected, actual);
+    }
+}
+
+/// Panic with a readable diff unless `actual` equals the golden file at `path`.
+///
+/// With `PDF2MD_UPDATE_GOLDEN` set, writes `actual` to `path` instead.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("create golden directory");
+        }
+        std::fs::write(path, actual).expect("write golden file");
+        return;
+    }
+    let expected = std::fs::read_to_string(path).unwrap_or_else(|e| {
+        panic!(
+            "golden file {} unreadable ({e}); run with {UPDATE_GOLDEN_ENV}=1 to create it",
+            path.display()
+        )
+    });
+    if let Some(diff) = golden_diff(&expected, actual) {
+        panic!(
+            "output differs from golden file {}\n{diff}\nRun with {UPDATE_GOLDEN_ENV}=1 to accept the new output.",
+            path.display()
+        );
+    }
+}
+
+/// Describe the first differing line between `expected` and `actual`, or
+/// `None` when they are identical.
+pub fn golden_diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let mut exp = expected.lines();
+    let mut act = actual.lines();
+    let mut line = 1;
+    loop {
+        match (exp.next(), act.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            // Same lines, different line endings or trailing newline.
+            (None, None) => {
+                return Some("files differ only in line endings or a trailing newline".into())
+            }
+            (e, a) => {
+                return Some(format!(
+                    "first difference at line {line}:\n  expected: {}\n  actual:   {}",
+                    e.map_or("<end of file>".to_string(), |s| format!("{s:?}")),
+                    a.map_or("<end of file>".to_string(), |s| format!("{s:?}")),
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn mock_replays_in_order_and_cycles() {
+        let mock = MockVisionProvider::new([
+            MockResponse::Markdown("one".into()),
+            MockResponse::Truncated("two".into()),
+            MockResponse::Error("503 Service Unavailable".into()),
+        ]);
+        let first = mock.chat(&[], None).await.unwrap();
+        assert_eq!(first.content, "one");
+        assert_eq!(first.finish_reason.as_deref(), Some("stop"));
+        let second = mock.chat(&[], None).await.unwrap();
+        assert_eq!(second.finish_reason.as_deref(), Some("length"));
+        assert!(mock.chat(&[], None).await.is_err());
+        assert_eq!(mock.chat(&[], None).await.unwrap().content, "one");
+        assert_eq!(mock.calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn empty_mock_errors() {
+        let mock = MockVisionProvider::new(Vec::new());
+        assert!(mock.chat(&[], None).await.is_err());
+    }
+
+    #[test]
+    fn golden_diff_reports_first_mismatch() {
+        assert_eq!(golden_diff("a\nb\n", "a\nb\n"), None);
+        let diff = golden_diff("a\nb\n", "a\nc\n").unwrap();
+        assert!(diff.contains("line 2"), "{diff}");
+        assert!(diff.contains("\"c\""), "{diff}");
+        let diff = golden_diff("a\n", "a\nextra\n").unwrap();
+        assert!(diff.contains("<end of file>"), "{diff}");
+        assert!(golden_diff("a\n", "a")
+            .unwrap()
+            .contains("trailing newline"));
+    }
+
+    #[test]
+    fn fixture_load_sorts_responses() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("input.pdf"), b"%PDF-1.4").unwrap();
+        let responses = dir.path().join("responses");
+        std::fs::create_dir(&responses).unwrap();
+        std::fs::write(responses.join("page-0002.md"), "second").unwrap();
+        std::fs::write(responses.join("page-0001.md"), "first").unwrap();
+        std::fs::write(responses.join("notes.txt"), "ignored").unwrap();
+
+        let fixture = GoldenFixture::load(dir.path()).unwrap();
+        assert_eq!(fixture.responses, vec!["first", "second"]);
+        assert_eq!(fixture.expected, dir.path().join("expected.md"));
+    }
+
+    #[test]
+    fn fixture_without_pdf_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            GoldenFixture::load(dir.path()),
+            Err(Pdf2MdError::FileNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+//! Table-of-contents pages: detect, then keep, tidy, skip, or regenerate.
+//!
+//! ## Why?
+//!
+//! A printed contents page converts into lines like
+//! `3.2 Results . . . . . . . . . . 41`: dot leaders that render as noise,
+//! and page numbers that mean nothing in a Markdown file. With
+//! [`crate::ConversionConfigBuilder::toc_handling`], a page whose
+//! [`crate::PageResult::page_kind`] is already
+//! [`crate::PageKind::Toc`] or whose Markdown [`is_toc`] judges a contents
+//! page is marked as such and handled per [`crate::TocHandling`]:
+//!
+//! | Handling | Result |
+//! |----------|--------|
+//! | `Keep` | unchanged (default) |
+//! | `Normalize` | [`normalize`]: one list item per entry, `- Results — 41`, nested by section number |
+//! | `Skip` | the page's text is dropped |
+//! | `Replace` | the page's text is dropped and the first contents page gets a list of links to the document's headings, built by [`crate::convert`] from the final document |
+//!
+//! Streaming conversions never see the final document, so there `Replace`
+//! acts like `Skip`.
+
+use crate::headings::{self, HeadingEntry};
+use crate::output::PageResult;
+use crate::page_kind::PageKind;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// A contents entry: optional bullet, the entry, leaders, a page number.
+static ENTRY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"(?i)^(\s*)(?:[-*+]\s+)?(.*?\S)\s*(?:(?:\\?\.\s?){3,}|…+|_{3,}|·{3,})\s*(\d{1,4}|[ivxlcdm]{1,6})\s*$",
+    )
+    .unwrap()
+});
+
+/// A contents heading, as a Markdown heading or a bold line.
+static HEADING_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)^(?:#{1,6}\s+|\*\*)\s*(?:table of )?contents\s*(?:\*\*)?\s*$").unwrap()
+});
+
+/// Section number at the start of an entry: `3`, `3.2`, `A.1`.
+static SECTION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:\d+|[A-Z])((?:\.\d+)*)\.?\s").unwrap());
+
+/// Heading of a generated table of contents.
+const GENERATED_HEADING: &str = "Contents";
+
+/// `true` when `markdown` is a contents page: it starts with a
+/// "Contents" heading, or at least three lines and half of its text lines
+/// are entries with leaders and a page number.
+pub fn is_toc(markdown: &str) -> bool {
+    let lines: Vec<&str> = markdown
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    if lines.first().is_some_and(|l| HEADING_RE.is_match(l)) {
+        return true;
+    }
+    let text_lines = lines.iter().filter(|l| !l.starts_with('#')).count();
+    let entries = lines.iter().filter(|l| ENTRY_RE.is_match(l)).count();
+    entries >= 3 && entries * 2 >= text_lines
+}
+
+/// Rewrite each leader line of `markdown` as a list item, `- Entry — 41`,
+/// indented one level per dot of its section number. Other lines are kept.
+pub fn normalize(markdown: &str) -> String {
+    let mut out: Vec<String> = markdown
+        .lines()
+        .map(|line| {
+            let Some(caps) = ENTRY_RE.captures(line) else {
+                return line.to_string();
+            };
+            let entry = caps[2].trim();
+            let depth = SECTION_RE
+                .captures(entry)
+                .map_or(0, |c| c[1].matches('.').count());
+            format!("{}- {} — {}", "  ".repeat(depth), entry, &caps[3])
+        })
+        .collect();
+    if markdown.ends_with('\n') {
+        out.push(String::new());
+    }
+    out.join("\n")
+}
+
+/// Write a list of links to the headings of `pages` into the first page of
+/// kind [`PageKind::Toc`], under a `## Contents` heading. `summary`
+/// reserves the slug of a prepended summary, as in [`headings::index`].
+///
+/// Contents pages are expected to be empty already.
+pub(crate) fn insert_generated(pages: &mut [PageResult], summary: bool) {
+    let Some(at) = pages
+        .iter()
+        .position(|p| p.page_kind == Some(PageKind::Toc) && p.error.is_none())
+    else {
+        return;
+    };
+    let page_num = pages[at].page_num;
+    pages[at].markdown = format!("## {GENERATED_HEADING}\n");
+    // Index with the heading in place so the slugs after it are numbered
+    // as in the final document.
+    let entries: Vec<HeadingEntry> = headings::index(pages, summary)
+        .into_iter()
+        .filter(|h| !(h.page == page_num && h.text == GENERATED_HEADING))
+        .collect();
+    let top = entries.iter().map(|h| h.level).min().unwrap_or(1);
+    let list: Vec<String> = entries
+        .iter()
+        .map(|h| {
+            format!(
+                "{}- [{}](#{})",
+                "  ".repeat((h.level - top) as usize),
+                h.text,
+                h.slug
+            )
+        })
+        .collect();
+    if !list.is_empty() {
+        pages[at].markdown = format!("## {GENERATED_HEADING}\n\n{}\n", list.join("\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "## Contents\n\n1 Introduction . . . . . . . . 1\n1.1 Scope ........ 3\n2 Methods …… 7\nAppendix ____ xii\n";
+
+    #[test]
+    fn contents_pages_are_detected() {
+        assert!(is_toc(PAGE));
+        assert!(is_toc(
+            "Preface .... v\nIntroduction .... 1\nMethods .... 9\nResults .... 20"
+        ));
+        assert!(!is_toc(
+            "# Results\n\nThe rate rose to 3.\nWe saw ... 12 cases.\nDone."
+        ));
+    }
+
+    #[test]
+    fn leaders_become_list_items() {
+        assert_eq!(
+            normalize(PAGE),
+            "## Contents\n\n- 1 Introduction — 1\n  - 1.1 Scope — 3\n- 2 Methods — 7\n- Appendix — xii\n"
+        );
+    }
+}
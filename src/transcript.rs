@@ -0,0 +1,218 @@
+//! Per-page transcripts of every model call, for audit.
+//!
+//! ## Why transcripts?
+//!
+//! Regulated users must be able to show exactly what left the machine: the
+//! instructions, the page image, and what came back. The event log
+//! ([`crate::event_log`]) records outcomes and token counts; a transcript
+//! records the request and response themselves.
+//!
+//! Enable with [`crate::ConversionConfigBuilder::transcript_dir`]. Each page
+//! gets `page-NNNN.jsonl` in the directory, with one [`TranscriptEntry`] per
+//! call: retries, truncation re-sends, refusal re-asks, fallback models, and
+//! every tile of a tiled page. Images are recorded by hash, not content; the
+//! hash covers the exact base64 payload sent.
+//!
+//! API keys never reach the files. The configured key, per-provider
+//! credentials, values of `extra_headers` whose name mentions auth, key,
+//! token, or secret, `*_API_KEY` environment variables,
+//! and anything shaped like a key (`sk-…`, `AIza…`, `Bearer …`) are
+//! replaced by `[REDACTED]` in every field — providers echo keys in error
+//! messages. Write failures are logged and otherwise ignored, as for the
+//! event log.
+
+use crate::backend::{DescribeOptions, PageCompletion, PageImage, PagePrompt, VisionBackend};
+use crate::config::ConversionConfig;
+use crate::error::Pdf2MdError;
+use crate::reproducibility::fnv1a;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+/// Replacement for every secret found in a transcript.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Common API key and bearer token shapes.
+static KEY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\b(?:sk-[A-Za-z0-9_-]{8,}|AIza[0-9A-Za-z_-]{20,}|Bearer\s+[A-Za-z0-9._~+/=-]{8,})")
+        .unwrap()
+});
+
+/// Secrets shorter than this are not searched for; they would redact
+/// ordinary words.
+const MIN_SECRET_LEN: usize = 8;
+
+/// One model call, as a line of `page-NNNN.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// Unix time of the answer, in milliseconds.
+    pub ts_ms: u64,
+    /// 1-based page number.
+    pub page: usize,
+    pub provider: String,
+    pub model: String,
+    pub temperature: f32,
+    pub max_tokens: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+    /// The page prompt as sent.
+    pub system_prompt: String,
+    /// Prior-page context, when `maintain_format` is on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// Text sent with the image (tile position, layout hints).
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub user_text: String,
+    /// 64-bit FNV-1a of the base64 image payload, as 16 hex digits.
+    pub image_hash: String,
+    pub image_mime_type: String,
+    /// Hashes of the few-shot example images sent before the page, in order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub example_image_hashes: Vec<String>,
+    /// The model's answer, before post-processing. `None` when the call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    /// Why the call failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Path of the transcript for `page` in `dir`.
+pub fn page_path(dir: &Path, page: usize) -> PathBuf {
+    dir.join(format!("page-{page:04}.jsonl"))
+}
+
+/// Append the transcript of one call to its page's file, if
+/// [`ConversionConfig::transcript_dir`] is set.
+pub(crate) fn record(
+    config: &ConversionConfig,
+    page: usize,
+    backend: &dyn VisionBackend,
+    image: &PageImage,
+    prompt: &PagePrompt,
+    options: &DescribeOptions,
+    answer: &Result<PageCompletion, Pdf2MdError>,
+) {
+    let Some(ref dir) = config.transcript_dir else {
+        return;
+    };
+    let (response, error) = match answer {
+        Ok(c) => (Some(c), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+    let entry = TranscriptEntry {
+        ts_ms: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0),
+        page,
+        provider: backend.name().to_string(),
+        model: backend.model().to_string(),
+        temperature: options.temperature,
+        max_tokens: options.max_tokens,
+        seed: options.seed,
+        system_prompt: prompt.system.clone(),
+        context: prompt.context.clone(),
+        user_text: prompt.user_text.clone(),
+        image_hash: fnv1a(&image.data),
+        image_mime_type: image.mime_type.clone(),
+        example_image_hashes: prompt
+            .examples
+            .iter()
+            .map(|e| fnv1a(&e.image.data))
+            .collect(),
+        response: response.map(|c| c.markdown.clone()),
+        finish_reason: response.and_then(|c| c.finish_reason.clone()),
+        input_tokens: response.map_or(0, |c| c.input_tokens),
+        output_tokens: response.map_or(0, |c| c.output_tokens),
+        error,
+    };
+    let line = match serde_json::to_string(&entry) {
+        Ok(l) => redact(&l, &secrets(config)) + "\n",
+        Err(e) => {
+            warn!("Transcript: failed to serialise page {}: {}", page, e);
+            return;
+        }
+    };
+    let path = page_path(dir, page);
+    let written = std::fs::create_dir_all(dir).and_then(|_| {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)?
+            .write_all(line.as_bytes())
+    });
+    if let Err(e) = written {
+        warn!("Transcript: write to {} failed: {}", path.display(), e);
+    }
+}
+
+/// Every secret value the run knows of, longest first so a key is never
+/// half-replaced by a shorter one it contains.
+fn secrets(config: &ConversionConfig) -> Vec<String> {
+    let mut secrets: Vec<String> = config
+        .api_key
+        .iter()
+        .cloned()
+        .chain(config.credentials.values().map(str::to_string))
+        .chain(
+            config
+                .extra_headers
+                .iter()
+                .filter(|(name, _)| {
+                    let name = name.as_str();
+                    ["auth", "key", "token", "secret"]
+                        .iter()
+                        .any(|s| name.contains(s))
+                })
+                .filter_map(|(_, v)| v.to_str().ok())
+                .map(str::to_string),
+        )
+        .chain(
+            std::env::vars()
+                .filter(|(name, _)| name.ends_with("_API_KEY"))
+                .map(|(_, value)| value),
+        )
+        .filter(|s| s.len() >= MIN_SECRET_LEN)
+        .collect();
+    secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    secrets
+}
+
+/// `text` with every secret and key-shaped token replaced by [`REDACTED`].
+fn redact(text: &str, secrets: &[String]) -> String {
+    let mut out = text.to_string();
+    for secret in secrets {
+        if out.contains(secret.as_str()) {
+            out = out.replace(secret.as_str(), REDACTED);
+        }
+    }
+    KEY_RE.replace_all(&out, REDACTED).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_redacted() {
+        let secrets = vec!["hunter2-tenant-key".to_string()];
+        let text = "401: Incorrect API key provided: sk-proj-abc123XYZ789. \
+                    Header was Bearer eyJhbGciOi.payload; tenant hunter2-tenant-key";
+        assert_eq!(
+            redact(text, &secrets),
+            "401: Incorrect API key provided: [REDACTED]. \
+             Header was [REDACTED]; tenant [REDACTED]"
+        );
+        assert_eq!(redact("ask-me-anything", &[]), "ask-me-anything");
+    }
+}
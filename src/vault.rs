@@ -0,0 +1,358 @@
+//! Obsidian / Zettelkasten export: one note per section.
+//!
+//! ## Why a vault layout?
+//!
+//! Personal-knowledge-management tools work on many small linked notes, not
+//! one long file. [`crate::OutputLayout::ObsidianVault`] splits the converted
+//! Markdown at its section headings and writes a folder of notes:
+//!
+//! ```text
+//! vault/
+//!   Annual Report 2024.md     index note: metadata, summary, list of [[links]]
+//!   Front Matter.md           text before the first section heading, if any
+//!   Introduction.md
+//!   Results.md
+//! ```
+//!
+//! The split level is the shallowest heading level used at least twice, so a
+//! lone `# Title` above a run of `##` sections does not swallow the whole
+//! document; headings inside code fences are ignored. Every note carries
+//! YAML front-matter (`title`, `tags`, `source`, `order`) and a footer
+//! linking the previous note, the index, and the next note as `[[wiki links]]`.
+//! Tags are `pdf2md`, a tag for the document, and the enrichment keywords
+//! when [`crate::EnrichmentConfig`] was set.
+
+use crate::convert::yaml_quote;
+use crate::error::Pdf2MdError;
+use crate::output::ConversionOutput;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// ATX heading: `## Title` with optional closing hashes.
+static HEADING: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(#{1,6})[ \t]+(.+?)[ \t#]*$").unwrap());
+
+/// Longest note name kept, in characters.
+const MAX_NAME_CHARS: usize = 100;
+
+/// One file of the vault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultNote {
+    /// Note name, also the target of `[[wiki links]]`; the file is `{name}.md`.
+    pub name: String,
+    /// Full file content, front-matter included.
+    pub content: String,
+}
+
+/// Split `output` into an index note followed by one note per section.
+///
+/// `source` is the converted input as given by the caller; it names the
+/// index note when the PDF has no title.
+pub fn vault_notes(output: &ConversionOutput, source: &str) -> Vec<VaultNote> {
+    let body = output
+        .pages
+        .iter()
+        .filter(|p| p.error.is_none() && !p.markdown.trim().is_empty())
+        .map(|p| p.markdown.trim())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let title = output
+        .metadata
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            Path::new(source)
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_else(|| source.to_string())
+        });
+
+    let mut taken = HashSet::new();
+    let index_name = unique_name(&note_name(&title, "Index"), &mut taken);
+
+    let mut tags = vec!["pdf2md".to_string()];
+    let keywords = output.enrichment.iter().flat_map(|e| e.keywords.iter());
+    for t in std::iter::once(&title)
+        .chain(keywords)
+        .filter_map(|t| tag(t))
+    {
+        if !tags.contains(&t) {
+            tags.push(t);
+        }
+    }
+    let tags = format!("[{}]", tags.join(", "));
+
+    let sections: Vec<(String, String)> = split_sections(&body)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (heading, text))| {
+            let fallback = format!("Section {}", i + 1);
+            let name = match heading {
+                Some(h) => unique_name(&note_name(&h, &fallback), &mut taken),
+                None => unique_name("Front Matter", &mut taken),
+            };
+            (name, text)
+        })
+        .collect();
+
+    let mut notes = Vec::with_capacity(sections.len() + 1);
+
+    let mut index = front_matter(&title, &tags, source, 0);
+    index.push_str(&format!("# {title}\n\n"));
+    if let Some(ref author) = output.metadata.author {
+        index.push_str(&format!("Author: {author}\n\n"));
+    }
+    if let Some(ref summary) = output.summary {
+        index.push_str(summary.trim());
+        index.push_str("\n\n");
+    }
+    index.push_str("## Contents\n\n");
+    for (name, _) in &sections {
+        index.push_str(&format!("- [[{name}]]\n"));
+    }
+    notes.push(VaultNote {
+        name: index_name.clone(),
+        content: index,
+    });
+
+    for (i, (name, text)) in sections.iter().enumerate() {
+        let mut content = front_matter(name, &tags, source, i + 1);
+        content.push_str(text.trim());
+        content.push_str("\n\n---\n\n");
+        let mut nav = Vec::new();
+        if i > 0 {
+            nav.push(format!("← [[{}]]", sections[i - 1].0));
+        }
+        nav.push(format!("[[{index_name}]]"));
+        if let Some((next, _)) = sections.get(i + 1) {
+            nav.push(format!("[[{next}]] →"));
+        }
+        content.push_str(&nav.join(" | "));
+        content.push('\n');
+        notes.push(VaultNote {
+            name: name.clone(),
+            content,
+        });
+    }
+    notes
+}
+
+/// Write `output` as a vault into `dir`, creating it if needed. Existing
+/// notes of the same names are overwritten. Returns the number of notes.
+pub async fn write_vault(
+    output: &ConversionOutput,
+    source: &str,
+    dir: &Path,
+) -> Result<usize, Pdf2MdError> {
+    let write_err = |path: &Path, e| Pdf2MdError::OutputWriteFailed {
+        path: path.to_path_buf(),
+        source: e,
+    };
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| write_err(dir, e))?;
+    let notes = vault_notes(output, source);
+    for note in &notes {
+        let path = dir.join(format!("{}.md", note.name));
+        tokio::fs::write(&path, &note.content)
+            .await
+            .map_err(|e| write_err(&path, e))?;
+    }
+    Ok(notes.len())
+}
+
+fn front_matter(title: &str, tags: &str, source: &str, order: usize) -> String {
+    format!(
+        "---\ntitle: {}\ntags: {}\nsource: {}\norder: {}\n---\n\n",
+        yaml_quote(title),
+        tags,
+        yaml_quote(source),
+        order
+    )
+}
+
+/// Split `markdown` at headings of the split level (see the module docs).
+/// Returns `(heading text, section markdown)`; a leading section without a
+/// heading has `None`.
+fn split_sections(markdown: &str) -> Vec<(Option<String>, String)> {
+    // (line index, level, text) of every heading outside code fences.
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut headings = Vec::new();
+    let mut fence: Option<&str> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        for marker in ["```", "~~~"] {
+            if trimmed.starts_with(marker) {
+                fence = match fence {
+                    None => Some(marker),
+                    Some(open) if open == marker => None,
+                    other => other,
+                };
+            }
+        }
+        if fence.is_some() {
+            continue;
+        }
+        if let Some(c) = HEADING.captures(line) {
+            headings.push((i, c[1].len(), c[2].to_string()));
+        }
+    }
+
+    let count = |level: usize| headings.iter().filter(|h| h.1 == level).count();
+    let split_level = (1..=6)
+        .find(|&l| count(l) >= 2)
+        .or_else(|| (1..=6).find(|&l| count(l) == 1));
+    let Some(split_level) = split_level else {
+        return if markdown.trim().is_empty() {
+            Vec::new()
+        } else {
+            vec![(None, markdown.to_string())]
+        };
+    };
+
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut heading: Option<String> = None;
+    for (line, _, text) in headings.into_iter().filter(|h| h.1 == split_level) {
+        let chunk = lines[start..line].join("\n");
+        if heading.is_some() || !chunk.trim().is_empty() {
+            sections.push((heading.take(), chunk));
+        }
+        start = line;
+        heading = Some(text);
+    }
+    sections.push((heading, lines[start..].join("\n")));
+    sections
+}
+
+/// A file-safe note name from heading text: Markdown emphasis and the
+/// characters Obsidian forbids in links are dropped.
+fn note_name(text: &str, fallback: &str) -> String {
+    let cleaned: String = text
+        .chars()
+        .filter(|c| !matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .filter(|c| !matches!(c, '#' | '^' | '[' | ']' | '`' | '_'))
+        .collect();
+    let name = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name: String = name
+        .trim_matches('.')
+        .chars()
+        .take(MAX_NAME_CHARS)
+        .collect();
+    let name = name.trim();
+    if name.is_empty() {
+        fallback.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// `name`, or `name 2`, `name 3`, … when already taken (case-insensitively,
+/// as on macOS and Windows file systems).
+fn unique_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let mut candidate = name.to_string();
+    let mut n = 2;
+    while !taken.insert(candidate.to_lowercase()) {
+        candidate = format!("{name} {n}");
+        n += 1;
+    }
+    candidate
+}
+
+/// An Obsidian tag from free text: lowercase words joined by `-`. Purely
+/// numeric results are not valid tags and give `None`.
+fn tag(text: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let tag = words.join("-");
+    (!tag.is_empty() && !tag.chars().all(|c| c.is_ascii_digit() || c == '-')).then_some(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::{DocumentMetadata, PageResult};
+
+    fn output(pages: &[&str]) -> ConversionOutput {
+        ConversionOutput {
+            markdown: String::new(),
+            pages: pages
+                .iter()
+                .enumerate()
+                .map(|(i, md)| PageResult::new(i + 1, *md))
+                .collect(),
+            metadata: DocumentMetadata {
+                title: Some("Annual Report 2024".into()),
+                ..Default::default()
+            },
+            stats: Default::default(),
+            summary: None,
+            enrichment: None,
+            pii_findings: Vec::new(),
+            references: Vec::new(),
+            headings: Vec::new(),
+            fatal_error: None,
+            reproducibility: None,
+        }
+    }
+
+    #[test]
+    fn splits_at_repeated_level_and_links_notes() {
+        let out = output(&[
+            "# Annual Report\n\nIntro text.\n\n## Results\n\nUp 5%.",
+            "```\n## not a heading\n```\n\n## Outlook: 2025\n\nFlat.",
+        ]);
+        let notes = vault_notes(&out, "reports/ar.pdf");
+        let names: Vec<&str> = notes.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Annual Report 2024",
+                "Front Matter",
+                "Results",
+                "Outlook 2025"
+            ]
+        );
+
+        let index = &notes[0].content;
+        assert!(index.contains("tags: [pdf2md, annual-report-2024]\n"));
+        assert!(index.contains("- [[Results]]\n- [[Outlook 2025]]\n"));
+
+        let results = &notes[2].content;
+        assert!(results.starts_with("---\ntitle: \"Results\"\n"));
+        assert!(results.contains("order: 2\n"));
+        assert!(results.contains("## Results\n\nUp 5%."));
+        // The fenced heading stays inside the section it appears in.
+        assert!(results.contains("## not a heading"));
+        assert!(
+            results.ends_with("← [[Front Matter]] | [[Annual Report 2024]] | [[Outlook 2025]] →\n")
+        );
+    }
+
+    #[test]
+    fn names_are_sanitised_and_unique() {
+        let mut taken = HashSet::new();
+        assert_eq!(note_name("**Intro** / Scope?", "x"), "Intro Scope");
+        assert_eq!(note_name("[#]", "Section 3"), "Section 3");
+        assert_eq!(unique_name("Intro", &mut taken), "Intro");
+        assert_eq!(unique_name("intro", &mut taken), "intro 2");
+        assert_eq!(tag("Machine Learning!"), Some("machine-learning".into()));
+        assert_eq!(tag("2024"), None);
+    }
+
+    #[test]
+    fn document_without_headings_is_one_note() {
+        let notes = vault_notes(&output(&["Just text."]), "scan.pdf");
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[1].name, "Front Matter");
+        assert!(notes[1].content.ends_with("[[Annual Report 2024]]\n"));
+    }
+}
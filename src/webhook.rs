@@ -0,0 +1,228 @@
+//! JSON notification POSTed when a conversion finishes or fails.
+//!
+//! ## Why a webhook?
+//!
+//! Hosts that run conversions from a job queue otherwise poll for results or
+//! wrap every call in their own notification code. With
+//! [`crate::ConversionConfigBuilder::webhook`] set, [`crate::convert`] (and
+//! [`crate::convert_from_bytes`], [`crate::convert_to_file`]) POSTs one JSON
+//! object to the configured URL as it returns:
+//!
+//! ```text
+//! {"event":"conversion_completed","input":"report.pdf","stats":{…},"metadata":{…},"markdown":"…"}
+//! {"event":"conversion_failed","input":"report.pdf","error":"Not a PDF: …"}
+//! ```
+//!
+//! `markdown` is only sent with [`WebhookConfig::include_markdown`].
+//! Delivery is best-effort: connection errors and 5xx answers are retried
+//! with back-off, a 4xx answer is not, and a notification that cannot be
+//! delivered is logged without changing the conversion's result. The
+//! streaming APIs do not send webhooks.
+
+use crate::error::Pdf2MdError;
+use crate::output::{ConversionOutput, ConversionStats, DocumentMetadata};
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Delivery attempts per notification.
+const ATTEMPTS: u32 = 3;
+/// Wait before the first retry; doubled for each further one.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+/// Per-attempt request timeout.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where and how to send the completion notification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// `http://` or `https://` endpoint receiving the POST.
+    pub url: String,
+    /// Extra request headers, e.g. `Authorization` for the receiver.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Send the assembled Markdown in the payload. Default: false.
+    #[serde(default)]
+    pub include_markdown: bool,
+}
+
+impl WebhookConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            headers: BTreeMap::new(),
+            include_markdown: false,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), Pdf2MdError> {
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            return Err(Pdf2MdError::InvalidConfig(format!(
+                "webhook url must start with http:// or https://, got '{}'",
+                self.url
+            )));
+        }
+        for (name, value) in &self.headers {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Pdf2MdError::InvalidConfig(format!("webhook header '{name}': {e}")))?;
+            reqwest::header::HeaderValue::from_str(value)
+                .map_err(|e| Pdf2MdError::InvalidConfig(format!("webhook header '{name}': {e}")))?;
+        }
+        Ok(())
+    }
+}
+
+/// The JSON body of a notification, tagged by `event`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum WebhookPayload {
+    /// The conversion returned output (possibly with failed pages).
+    ConversionCompleted {
+        input: String,
+        stats: ConversionStats,
+        metadata: DocumentMetadata,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        markdown: Option<String>,
+        /// Set when the run stopped early; see
+        /// [`ConversionOutput::fatal_error`].
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fatal_error: Option<String>,
+    },
+    /// The conversion returned an error.
+    ConversionFailed { input: String, error: String },
+}
+
+impl WebhookPayload {
+    /// The payload describing `result`.
+    pub fn for_result(
+        input: &str,
+        result: &Result<ConversionOutput, Pdf2MdError>,
+        include_markdown: bool,
+    ) -> Self {
+        match result {
+            Ok(output) => WebhookPayload::ConversionCompleted {
+                input: input.to_string(),
+                stats: output.stats.clone(),
+                metadata: output.metadata.clone(),
+                markdown: include_markdown.then(|| output.markdown.clone()),
+                fatal_error: output.fatal_error.clone(),
+            },
+            Err(e) => WebhookPayload::ConversionFailed {
+                input: input.to_string(),
+                error: e.to_string(),
+            },
+        }
+    }
+}
+
+/// POST `payload` to `config.url`. Never fails; problems are logged.
+pub async fn notify(config: &WebhookConfig, payload: &WebhookPayload) {
+    let body = match serde_json::to_vec(payload) {
+        Ok(b) => b,
+        Err(e) => {
+            warn!("Webhook payload could not be serialised: {}", e);
+            return;
+        }
+    };
+    let client = match reqwest::Client::builder().timeout(TIMEOUT).build() {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("Webhook client could not be built: {}", e);
+            return;
+        }
+    };
+
+    let mut last_error = String::new();
+    for attempt in 1..=ATTEMPTS {
+        let mut request = client
+            .post(&config.url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.clone());
+        for (name, value) in &config.headers {
+            request = request.header(name.as_str(), value.as_str());
+        }
+        match request.send().await {
+            Ok(r) if r.status().is_success() => {
+                debug!("Webhook delivered to {} ({})", config.url, r.status());
+                return;
+            }
+            Ok(r) if r.status().is_client_error() => {
+                warn!("Webhook rejected by {}: {}", config.url, r.status());
+                return;
+            }
+            Ok(r) => last_error = format!("HTTP {}", r.status()),
+            Err(e) => last_error = e.to_string(),
+        }
+        if attempt < ATTEMPTS {
+            tokio::time::sleep(RETRY_BACKOFF * 2u32.pow(attempt - 1)).await;
+        }
+    }
+    warn!(
+        "Webhook to {} failed after {} attempts: {}",
+        config.url, ATTEMPTS, last_error
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn failed_payload_is_tagged() {
+        let result = Err(Pdf2MdError::Internal("boom".into()));
+        let payload = WebhookPayload::for_result("a.pdf", &result, true);
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["event"], "conversion_failed");
+        assert_eq!(json["input"], "a.pdf");
+        assert!(json["error"].as_str().unwrap().contains("boom"));
+    }
+
+    #[test]
+    fn validate_rejects_bad_urls_and_headers() {
+        assert!(WebhookConfig::new("ftp://host/hook").validate().is_err());
+        let mut hook = WebhookConfig::new("https://jobs.example/hook");
+        assert!(hook.validate().is_ok());
+        hook.headers.insert("bad header".into(), "x".into());
+        assert!(hook.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn notify_posts_json_with_headers() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            // Read until the JSON body has arrived.
+            while !request.ends_with(b"}") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            socket
+                .write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let mut hook = WebhookConfig::new(format!("http://{addr}/hook"));
+        hook.headers.insert("X-Job-Id".into(), "42".into());
+        let payload = WebhookPayload::ConversionFailed {
+            input: "a.pdf".into(),
+            error: "boom".into(),
+        };
+        notify(&hook, &payload).await;
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook "));
+        assert!(request.to_ascii_lowercase().contains("x-job-id: 42"));
+        assert!(request.contains(r#""event":"conversion_failed""#));
+    }
+}
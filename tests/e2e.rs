@@ -1235,6 +1235,49 @@ async fn test_lazy_stream_api() {
     println!("[lazy-stream] {} pages received via stream", pages.len());
 }
 
+/// Verify the event stream brackets pages with Started / Completed.
+#[tokio::test]
+async fn test_stream_events_api() {
+    use edgequake_pdf2md::{convert_stream_events, ConversionEvent};
+    use futures::StreamExt;
+
+    let path = e2e_skip_unless_ready!(test_cases_dir().join("irs_form_1040.pdf"));
+
+    let config = ConversionConfig::builder()
+        .pages(PageSelection::All)
+        .max_retries(2)
+        .build()
+        .expect("valid config");
+
+    let events: Vec<ConversionEvent> = convert_stream_events(path.to_str().unwrap(), &config)
+        .await
+        .expect("stream creation should succeed")
+        .collect()
+        .await;
+
+    assert!(matches!(
+        events.first(),
+        Some(ConversionEvent::Started { total_pages: 2 })
+    ));
+    let started = events
+        .iter()
+        .filter(|e| matches!(e, ConversionEvent::PageStarted { .. }))
+        .count();
+    let pages = events
+        .iter()
+        .filter(|e| matches!(e, ConversionEvent::Page(_)))
+        .count();
+    assert_eq!(started, 2);
+    assert_eq!(pages, 2);
+    match events.last() {
+        Some(ConversionEvent::Completed(stats)) => {
+            assert_eq!(stats.processed_pages, 2);
+            assert!(stats.total_output_tokens > 0);
+        }
+        other => panic!("last event should be Completed, got {other:?}"),
+    }
+}
+
 /// Verify progress callbacks fire correctly with lazy pipeline.
 #[tokio::test]
 async fn test_lazy_pipeline_progress_callbacks() {